@@ -0,0 +1,311 @@
+//! System context bundle for AI agents.
+//!
+//! `get_agent_context` gathers what an agent usually needs before acting on
+//! this machine — host identity, a CPU/memory reading, the open terminal
+//! sessions with their working directory and foreground process, and the
+//! latest warnings and errors from the log — into one compact JSON document.
+//! The document is trimmed to fit a token budget so it can be pasted into a
+//! prompt as is: long log messages are shortened first, then the oldest log
+//! lines and finally the last sessions are dropped, with counts of what was
+//! left out.
+
+use serde::Serialize;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::State;
+
+use crate::logging;
+use crate::metrics::MetricsState;
+use crate::pty::{self, PtyState, SessionProcess};
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Token budget when `max_tokens` is omitted, and the accepted range
+const DEFAULT_MAX_TOKENS: usize = 2000;
+const MIN_MAX_TOKENS: usize = 200;
+const MAX_MAX_TOKENS: usize = 100_000;
+
+/// Rough size of a token in JSON text (English prose averages ~4 chars)
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Log messages longer than this are cut (with an ellipsis)
+const MAX_MESSAGE_CHARS: usize = 240;
+
+/// Log lines considered before trimming to the budget
+const MAX_LOG_LINES: usize = 50;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Context document returned by `get_agent_context`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AgentContext {
+    pub host: AgentHost,
+    pub system: AgentSystem,
+    pub sessions: Vec<AgentSession>,
+    /// Latest warnings and errors, newest first
+    pub recent_errors: Vec<AgentLogLine>,
+    /// Sessions and log lines left out to fit the budget
+    pub omitted_sessions: usize,
+    pub omitted_errors: usize,
+    /// Estimated size of this document in tokens
+    pub approx_tokens: usize,
+    /// RFC 3339 time the context was assembled
+    pub generated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AgentHost {
+    pub hostname: String,
+    /// Human-readable OS name and version (e.g. "macOS 14.4 Sonoma")
+    pub os: String,
+    pub arch: String,
+    pub uptime_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AgentSystem {
+    pub cpu_percent: f32,
+    pub mem_percent: f32,
+    pub mem_used_gb: f32,
+    pub mem_total_gb: f32,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AgentSession {
+    pub id: String,
+    /// Working directory of the foreground process (the shell's at a prompt)
+    pub cwd: Option<String>,
+    /// Command line of the foreground process
+    pub foreground: Option<String>,
+    /// Whether the shell itself is in the foreground (no command running)
+    pub at_prompt: bool,
+    /// Seconds since the last input
+    pub idle_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AgentLogLine {
+    pub ts: String,
+    pub level: String,
+    pub source: String,
+    pub message: String,
+    /// Identical consecutive lines this one stands for
+    pub count: u64,
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Host, system load, terminal sessions and recent errors as one compact
+/// document for an LLM prompt.
+///
+/// # Arguments
+/// * `max_tokens` - Approximate size limit of the document (default 2000,
+///   200-100000); estimated at 4 characters per token of JSON
+#[tauri::command]
+pub async fn get_agent_context(
+    metrics: State<'_, MetricsState>,
+    pty: State<'_, PtyState>,
+    max_tokens: Option<usize>,
+) -> Result<AgentContext, AppError> {
+    let max_tokens = max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    if !(MIN_MAX_TOKENS..=MAX_MAX_TOKENS).contains(&max_tokens) {
+        return Err(AppError::Validation(format!(
+            "max_tokens must be {}-{}, got: {}",
+            MIN_MAX_TOKENS, MAX_MAX_TOKENS, max_tokens
+        )));
+    }
+
+    let latest = metrics.latest_stats();
+    let processes = pty::session_processes(pty.inner());
+    let mut context = tokio::task::spawn_blocking(move || {
+        let mut system = System::new();
+        let stats = latest.unwrap_or_else(|| crate::collect_system_stats(&mut system));
+        let unknown = || "unknown".to_string();
+        AgentContext {
+            host: AgentHost {
+                hostname: System::host_name().unwrap_or_else(unknown),
+                os: System::long_os_version().unwrap_or_else(unknown),
+                arch: System::cpu_arch().unwrap_or_else(|| std::env::consts::ARCH.to_string()),
+                uptime_secs: System::uptime(),
+            },
+            system: AgentSystem {
+                cpu_percent: stats.cpu,
+                mem_percent: stats.mem,
+                mem_used_gb: stats.mem_used_gb,
+                mem_total_gb: stats.mem_total_gb,
+            },
+            sessions: processes
+                .iter()
+                .map(|p| describe_session(&mut system, p))
+                .collect(),
+            recent_errors: Vec::new(),
+            omitted_sessions: 0,
+            omitted_errors: 0,
+            approx_tokens: 0,
+            generated_at: chrono::Local::now().to_rfc3339(),
+        }
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Agent context task failed: {}", e)))?;
+
+    context.recent_errors = logging::recent_alerts()
+        .into_iter()
+        .take(MAX_LOG_LINES)
+        .map(|e| AgentLogLine {
+            ts: e.ts,
+            level: e.level,
+            source: e.source,
+            message: shorten(&e.message, MAX_MESSAGE_CHARS),
+            count: e.count,
+        })
+        .collect();
+    fit_to_budget(&mut context, max_tokens);
+    Ok(context)
+}
+
+// =============================================================================
+// Assembly
+// =============================================================================
+
+/// Session entry with the working directory of its foreground process.
+fn describe_session(system: &mut System, process: &SessionProcess) -> AgentSession {
+    let at_prompt = process
+        .foreground
+        .as_ref()
+        .is_some_and(|f| Some(f.pid) == process.shell_pid);
+    let cwd_pid = process.foreground.as_ref().map(|f| f.pid).or(process.shell_pid);
+    AgentSession {
+        id: process.session_id.clone(),
+        cwd: cwd_pid.and_then(|pid| process_cwd(system, pid)),
+        foreground: process.foreground.as_ref().map(|f| f.command.clone()),
+        at_prompt,
+        idle_secs: process.input_idle.as_secs(),
+    }
+}
+
+/// Current working directory of a process, if the OS lets us read it.
+pub(crate) fn process_cwd(system: &mut System, pid: u32) -> Option<String> {
+    let pid = Pid::from_u32(pid);
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::new().with_cwd(UpdateKind::Always),
+    );
+    system
+        .process(pid)?
+        .cwd()
+        .map(|cwd| cwd.to_string_lossy().to_string())
+}
+
+/// `text` cut to `max_chars` characters, ending in an ellipsis if cut.
+fn shorten(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Estimated tokens of the context as JSON.
+fn estimate_tokens(context: &AgentContext) -> usize {
+    serde_json::to_string(context).map_or(0, |json| json.chars().count().div_ceil(CHARS_PER_TOKEN))
+}
+
+/// Drop the oldest log lines, then the last sessions, until the context fits
+/// `max_tokens`, and record its final size. Host and system are always kept.
+fn fit_to_budget(context: &mut AgentContext, max_tokens: usize) {
+    loop {
+        context.approx_tokens = estimate_tokens(context);
+        if context.approx_tokens <= max_tokens {
+            return;
+        }
+        if context.recent_errors.pop().is_some() {
+            context.omitted_errors += 1;
+        } else if context.sessions.pop().is_some() {
+            context.omitted_sessions += 1;
+        } else {
+            return;
+        }
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(sessions: usize, errors: usize) -> AgentContext {
+        AgentContext {
+            host: AgentHost {
+                hostname: "devbox".into(),
+                os: "macOS 14.4 Sonoma".into(),
+                arch: "arm64".into(),
+                uptime_secs: 3600,
+            },
+            system: AgentSystem {
+                cpu_percent: 12.5,
+                mem_percent: 40.0,
+                mem_used_gb: 6.4,
+                mem_total_gb: 16.0,
+            },
+            sessions: (0..sessions)
+                .map(|i| AgentSession {
+                    id: format!("term-{}", i),
+                    cwd: Some("/Users/dev/project".into()),
+                    foreground: Some("zsh".into()),
+                    at_prompt: true,
+                    idle_secs: 5,
+                })
+                .collect(),
+            recent_errors: (0..errors)
+                .map(|i| AgentLogLine {
+                    ts: "2026-03-01T12:00:00+00:00".into(),
+                    level: "ERROR".into(),
+                    source: "synthia_lib::streaming".into(),
+                    message: format!("Frame capture error number {}", i),
+                    count: 1,
+                })
+                .collect(),
+            omitted_sessions: 0,
+            omitted_errors: 0,
+            approx_tokens: 0,
+            generated_at: "2026-03-01T12:00:00+00:00".into(),
+        }
+    }
+
+    #[test]
+    fn test_fit_to_budget_drops_errors_before_sessions() {
+        let mut ctx = context(3, 20);
+        fit_to_budget(&mut ctx, 100_000);
+        assert_eq!((ctx.omitted_errors, ctx.omitted_sessions), (0, 0));
+        assert_eq!(ctx.approx_tokens, estimate_tokens(&ctx));
+
+        let mut ctx = context(3, 20);
+        let budget = estimate_tokens(&context(3, 2)) + 5;
+        fit_to_budget(&mut ctx, budget);
+        assert!(ctx.approx_tokens <= budget);
+        assert_eq!(ctx.sessions.len(), 3);
+        assert_eq!(ctx.omitted_errors, 18);
+        // Newest lines are kept
+        assert_eq!(ctx.recent_errors[1].message, "Frame capture error number 1");
+
+        let mut ctx = context(3, 20);
+        fit_to_budget(&mut ctx, 1);
+        assert_eq!((ctx.omitted_errors, ctx.omitted_sessions), (20, 3));
+        assert_eq!(ctx.host.hostname, "devbox");
+    }
+
+    #[test]
+    fn test_shorten() {
+        assert_eq!(shorten("short", 10), "short");
+        assert_eq!(shorten("ééééé", 3), "ééé…");
+    }
+}
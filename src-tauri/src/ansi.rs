@@ -0,0 +1,377 @@
+//! ANSI escape sequence handling for terminal output.
+//!
+//! PTY output is full of color codes, cursor movement and OSC title
+//! updates. Agents and pattern matching want the visible text only, so this
+//! module strips escape sequences with a `vte` parser. The parser is
+//! stateful, so sequences split across PTY reads are handled correctly.
+//!
+//! It also renders SGR styling (colors, bold, underline, ...) to HTML so
+//! the Logs UI can show colored output without a second parser in JS.
+
+use vte::{Params, Parser, Perform};
+
+// =============================================================================
+// Plain Text
+// =============================================================================
+
+/// Collects printable text, dropping all escape sequences.
+#[derive(Default)]
+struct TextCollector {
+    out: String,
+}
+
+impl Perform for TextCollector {
+    fn print(&mut self, c: char) {
+        self.out.push(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' => self.out.push('\n'),
+            b'\t' => self.out.push('\t'),
+            // Backspace: shells echo it when erasing typed characters
+            0x08 => {
+                self.out.pop();
+            }
+            // \r and other C0 controls carry no visible text
+            _ => {}
+        }
+    }
+}
+
+/// Incremental ANSI stripper for a stream of output chunks.
+pub struct AnsiStripper {
+    parser: Parser,
+    collector: TextCollector,
+}
+
+impl Default for AnsiStripper {
+    fn default() -> Self {
+        Self {
+            parser: Parser::new(),
+            collector: TextCollector::default(),
+        }
+    }
+}
+
+impl AnsiStripper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed raw output and return the plain text it produced.
+    pub fn feed(&mut self, bytes: &[u8]) -> String {
+        self.parser.advance(&mut self.collector, bytes);
+        std::mem::take(&mut self.collector.out)
+    }
+}
+
+/// Strip all escape sequences from a complete piece of text.
+pub fn strip(text: &str) -> String {
+    AnsiStripper::new().feed(text.as_bytes())
+}
+
+// =============================================================================
+// HTML Rendering
+// =============================================================================
+
+/// An RGB color.
+pub(crate) type Rgb = (u8, u8, u8);
+
+/// The 16 standard colors (xterm defaults).
+const BASE_COLORS: [Rgb; 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Map a 256-color palette index to RGB.
+pub(crate) fn palette_color(index: u16) -> Rgb {
+    match index {
+        0..=15 => BASE_COLORS[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            let level = |v: u16| if v == 0 { 0 } else { (55 + v * 40) as u8 };
+            (level(i / 36), level((i / 6) % 6), level(i % 6))
+        }
+        _ => {
+            let gray = (8 + (index.min(255) - 232) * 10) as u8;
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// Current SGR text attributes.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+    fg: Option<Rgb>,
+    bg: Option<Rgb>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    inverse: bool,
+    strikethrough: bool,
+}
+
+impl Style {
+    /// Inline CSS for this style (empty for the default style).
+    fn css(&self) -> String {
+        let (fg, bg) = if self.inverse {
+            (self.bg, self.fg)
+        } else {
+            (self.fg, self.bg)
+        };
+
+        let mut css = Vec::new();
+        if let Some((r, g, b)) = fg {
+            css.push(format!("color:#{:02x}{:02x}{:02x}", r, g, b));
+        }
+        if let Some((r, g, b)) = bg {
+            css.push(format!("background-color:#{:02x}{:02x}{:02x}", r, g, b));
+        }
+        if self.bold {
+            css.push("font-weight:bold".into());
+        }
+        if self.dim {
+            css.push("opacity:0.7".into());
+        }
+        if self.italic {
+            css.push("font-style:italic".into());
+        }
+        match (self.underline, self.strikethrough) {
+            (true, true) => css.push("text-decoration:underline line-through".into()),
+            (true, false) => css.push("text-decoration:underline".into()),
+            (false, true) => css.push("text-decoration:line-through".into()),
+            (false, false) => {}
+        }
+        css.join(";")
+    }
+
+    /// Apply an SGR parameter list (`ESC [ ... m`).
+    fn apply_sgr(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            *self = Style::default();
+            return;
+        }
+
+        let mut i = 0;
+        while i < params.len() {
+            match params[i] {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.inverse = true,
+                9 => self.strikethrough = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.inverse = false,
+                29 => self.strikethrough = false,
+                n @ 30..=37 => self.fg = Some(BASE_COLORS[(n - 30) as usize]),
+                39 => self.fg = None,
+                n @ 40..=47 => self.bg = Some(BASE_COLORS[(n - 40) as usize]),
+                49 => self.bg = None,
+                n @ 90..=97 => self.fg = Some(BASE_COLORS[(n - 90 + 8) as usize]),
+                n @ 100..=107 => self.bg = Some(BASE_COLORS[(n - 100 + 8) as usize]),
+                n @ (38 | 48) => {
+                    // Extended color: 5;index or 2;r;g;b
+                    let (color, used) = match params.get(i + 1) {
+                        Some(5) => (params.get(i + 2).map(|&c| palette_color(c)), 2),
+                        Some(2) => match (params.get(i + 2), params.get(i + 3), params.get(i + 4)) {
+                            (Some(&r), Some(&g), Some(&b)) => {
+                                (Some((r.min(255) as u8, g.min(255) as u8, b.min(255) as u8)), 4)
+                            }
+                            _ => (None, 4),
+                        },
+                        _ => (None, 0),
+                    };
+                    if let Some(c) = color {
+                        if n == 38 {
+                            self.fg = Some(c);
+                        } else {
+                            self.bg = Some(c);
+                        }
+                    }
+                    i += used;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+}
+
+/// Builds HTML from printable text and SGR sequences.
+#[derive(Default)]
+struct HtmlRenderer {
+    out: String,
+    style: Style,
+    /// CSS of the currently open `<span>`, if any
+    open_css: Option<String>,
+}
+
+impl HtmlRenderer {
+    /// Make sure the open span matches the current style before writing text.
+    fn sync_span(&mut self) {
+        let css = self.style.css();
+        if self.open_css.as_deref() == Some(css.as_str())
+            || (self.open_css.is_none() && css.is_empty())
+        {
+            return;
+        }
+        if self.open_css.take().is_some() {
+            self.out.push_str("</span>");
+        }
+        if !css.is_empty() {
+            self.out.push_str(&format!("<span style=\"{}\">", css));
+            self.open_css = Some(css);
+        }
+    }
+
+    fn finish(mut self) -> String {
+        if self.open_css.is_some() {
+            self.out.push_str("</span>");
+        }
+        self.out
+    }
+}
+
+impl Perform for HtmlRenderer {
+    fn print(&mut self, c: char) {
+        self.sync_span();
+        match c {
+            '&' => self.out.push_str("&amp;"),
+            '<' => self.out.push_str("&lt;"),
+            '>' => self.out.push_str("&gt;"),
+            '"' => self.out.push_str("&quot;"),
+            '\'' => self.out.push_str("&#39;"),
+            _ => self.out.push(c),
+        }
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' => self.out.push('\n'),
+            b'\t' => self.out.push('\t'),
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, _intermediates: &[u8], _ignore: bool, action: char) {
+        if action == 'm' {
+            // Flatten so both `38;5;n` and colon-separated `38:5:n` forms work
+            let flat: Vec<u16> = params.iter().flatten().copied().collect();
+            self.style.apply_sgr(&flat);
+        }
+    }
+}
+
+/// Render ANSI-colored text to HTML `<span>`s with inline styles.
+/// All other escape sequences are dropped and text is HTML-escaped.
+pub fn to_html(text: &str) -> String {
+    let mut renderer = HtmlRenderer::default();
+    Parser::new().advance(&mut renderer, text.as_bytes());
+    renderer.finish()
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Remove ANSI escape sequences, returning the visible text.
+#[tauri::command]
+pub fn strip_ansi(text: String) -> String {
+    strip(&text)
+}
+
+/// Convert ANSI-colored text to HTML with inline styles (HTML-escaped).
+#[tauri::command]
+pub fn render_ansi_to_html(text: String) -> String {
+    to_html(&text)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_colors_and_cursor_movement() {
+        assert_eq!(strip("\x1b[1;32mok\x1b[0m done\r\n"), "ok done\n");
+        assert_eq!(strip("\x1b[2K\x1b[1Gprogress"), "progress");
+    }
+
+    #[test]
+    fn test_strip_osc_title() {
+        assert_eq!(strip("\x1b]0;user@host: ~\x07$ "), "$ ");
+    }
+
+    #[test]
+    fn test_backspace_erases() {
+        assert_eq!(strip("lss\x08 \x08"), "ls");
+    }
+
+    #[test]
+    fn test_sequence_split_across_chunks() {
+        let mut stripper = AnsiStripper::new();
+        assert_eq!(stripper.feed(b"red: \x1b[3"), "red: ");
+        assert_eq!(stripper.feed(b"1mtext"), "text");
+    }
+
+    #[test]
+    fn test_html_basic_colors() {
+        assert_eq!(
+            to_html("\x1b[31merror\x1b[0m ok"),
+            "<span style=\"color:#cd0000\">error</span> ok"
+        );
+    }
+
+    #[test]
+    fn test_html_escapes_text() {
+        assert_eq!(to_html("<b>&\"'"), "&lt;b&gt;&amp;&quot;&#39;");
+    }
+
+    #[test]
+    fn test_html_extended_colors_and_attributes() {
+        assert_eq!(
+            to_html("\x1b[1;38;5;196mA\x1b[48;2;0;0;255mB"),
+            "<span style=\"color:#ff0000;font-weight:bold\">A</span>\
+             <span style=\"color:#ff0000;background-color:#0000ff;font-weight:bold\">B</span>"
+        );
+    }
+
+    #[test]
+    fn test_html_unclosed_style_is_closed() {
+        assert_eq!(to_html("\x1b[4mu"), "<span style=\"text-decoration:underline\">u</span>");
+    }
+
+    #[test]
+    fn test_palette_color() {
+        assert_eq!(palette_color(1), (205, 0, 0));
+        assert_eq!(palette_color(16), (0, 0, 0));
+        assert_eq!(palette_color(231), (255, 255, 255));
+        assert_eq!(palette_color(232), (8, 8, 8));
+    }
+}
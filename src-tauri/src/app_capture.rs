@@ -0,0 +1,167 @@
+//! Application capture: which on-screen windows belong to one application.
+//!
+//! scap does not expose ScreenCaptureKit's "including applications" content
+//! filter, so an app stream captures a display while excluding every window
+//! owned by any other application (including the desktop wallpaper and
+//! menu bar). Windows opened after the stream starts are picked up by
+//! recomputing the exclusion list periodically (see `streaming.rs`).
+//!
+//! Only macOS can exclude windows from a capture; elsewhere starting an app
+//! stream fails with a capture error.
+
+use serde::Serialize;
+
+use crate::AppError;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// The application an app stream shows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum AppSelector {
+    /// Exact bundle identifier (e.g. `com.apple.Safari`)
+    BundleId(String),
+    /// Application name, case-insensitive (e.g. `safari`)
+    ProcessName(String),
+}
+
+impl AppSelector {
+    /// Build a selector from the command arguments; exactly one must be set.
+    pub fn from_args(
+        bundle_id: Option<String>,
+        process_name: Option<String>,
+    ) -> Result<Self, AppError> {
+        let non_empty = |s: Option<String>| {
+            s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+        };
+        match (non_empty(bundle_id), non_empty(process_name)) {
+            (Some(id), None) => Ok(Self::BundleId(id)),
+            (None, Some(name)) => Ok(Self::ProcessName(name)),
+            _ => Err(AppError::Validation(
+                "Exactly one of bundle_id or process_name is required".into(),
+            )),
+        }
+    }
+
+    /// Whether a window owner with this bundle id and name is the selected app.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    fn matches(&self, bundle_id: &str, app_name: &str) -> bool {
+        match self {
+            Self::BundleId(id) => bundle_id == id,
+            Self::ProcessName(name) => app_name.eq_ignore_ascii_case(name),
+        }
+    }
+}
+
+impl std::fmt::Display for AppSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BundleId(id) => write!(f, "{}", id),
+            Self::ProcessName(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// On-screen window ids split by owner.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct AppWindows {
+    /// Windows of the selected app
+    pub own: usize,
+    /// Every other window, sorted; excluded from the capture
+    pub excluded: Vec<u32>,
+}
+
+/// Split windows, given as `(id, Some((bundle_id, app_name)))` (None for
+/// windows without an owning app), into the app's and everyone else's.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn split_windows<I>(windows: I, selector: &AppSelector) -> AppWindows
+where
+    I: IntoIterator<Item = (u32, Option<(String, String)>)>,
+{
+    let mut split = AppWindows::default();
+    for (id, owner) in windows {
+        match owner {
+            Some((bundle_id, name)) if selector.matches(&bundle_id, &name) => split.own += 1,
+            _ => split.excluded.push(id),
+        }
+    }
+    split.excluded.sort_unstable();
+    split
+}
+
+// =============================================================================
+// Window Listing
+// =============================================================================
+
+/// Current windows split for `selector`. Blocking; call off the async runtime.
+#[cfg(target_os = "macos")]
+pub(crate) fn app_windows(selector: &AppSelector) -> Result<AppWindows, AppError> {
+    use cidre::sc;
+
+    let content = tauri::async_runtime::block_on(sc::ShareableContent::current())
+        .map_err(|e| AppError::Capture(format!("Failed to list windows: {:?}", e)))?;
+    let windows = content.windows().iter().map(|w| {
+        let owner = w
+            .owning_app()
+            .map(|app| (app.bundle_id().to_string(), app.app_name().to_string()));
+        (w.id(), owner)
+    });
+    Ok(split_windows(windows.collect::<Vec<_>>(), selector))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn app_windows(_selector: &AppSelector) -> Result<AppWindows, AppError> {
+    Err(AppError::Capture("Application capture is only supported on macOS".into()))
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owner(bundle_id: &str, name: &str) -> Option<(String, String)> {
+        Some((bundle_id.to_string(), name.to_string()))
+    }
+
+    #[test]
+    fn test_selector_requires_exactly_one() {
+        assert_eq!(
+            AppSelector::from_args(Some("com.apple.Safari".into()), None).unwrap(),
+            AppSelector::BundleId("com.apple.Safari".into())
+        );
+        assert_eq!(
+            AppSelector::from_args(Some("  ".into()), Some("Safari".into())).unwrap(),
+            AppSelector::ProcessName("Safari".into())
+        );
+        assert!(AppSelector::from_args(None, None).is_err());
+        assert!(AppSelector::from_args(Some("a".into()), Some("b".into())).is_err());
+    }
+
+    #[test]
+    fn test_split_windows() {
+        let windows = vec![
+            (30, owner("com.apple.Safari", "Safari")),
+            (10, owner("com.apple.finder", "Finder")),
+            (20, owner("com.apple.Safari", "Safari")),
+            (5, None),
+        ];
+        let by_name = AppSelector::ProcessName("safari".into());
+        assert_eq!(
+            split_windows(windows.clone(), &by_name),
+            AppWindows {
+                own: 2,
+                excluded: vec![5, 10],
+            }
+        );
+        let by_id = AppSelector::BundleId("com.apple.Safari".into());
+        assert_eq!(split_windows(windows.clone(), &by_id).own, 2);
+        let other = AppSelector::BundleId("com.apple.safari".into());
+        assert_eq!(split_windows(windows, &other).excluded, vec![5, 10, 20, 30]);
+    }
+}
@@ -0,0 +1,283 @@
+//! System audio capture for the screen stream.
+//!
+//! Captures audio with cpal, encodes it as 20 ms Opus packets and publishes
+//! them on a broadcast channel that the WebSocket server multiplexes with
+//! video frames (see the message type bytes in `streaming.rs`).
+//!
+//! Device selection:
+//! - Windows: WASAPI loopback on the default output device (what you hear).
+//! - macOS/Linux: the default input device. System audio requires routing
+//!   output to an input (a PulseAudio/PipeWire "Monitor of ..." source, or a
+//!   loopback driver such as BlackHole on macOS).
+
+use bytes::Bytes;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Sample, SampleFormat, SampleRate};
+use std::sync::mpsc;
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+
+use crate::streaming::MSG_TYPE_AUDIO;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Opus only accepts a few sample rates; 48 kHz is native for most devices
+const AUDIO_SAMPLE_RATE: u32 = 48_000;
+
+/// Samples per channel in one 20 ms Opus frame at 48 kHz
+const OPUS_FRAME_SAMPLES: usize = 960;
+
+/// Upper bound for one encoded packet (recommended by libopus)
+const OPUS_MAX_PACKET_BYTES: usize = 4000;
+
+/// Target bitrate — transparent for desktop audio at stereo 48 kHz
+const OPUS_BITRATE: i32 = 96_000;
+
+/// PCM chunks buffered between the device callback and the encoder thread
+const PCM_QUEUE_CAPACITY: usize = 64;
+
+/// How often the encoder thread checks for shutdown while idle
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// =============================================================================
+// Capture
+// =============================================================================
+
+/// Start capturing and encoding audio on a dedicated thread.
+///
+/// Blocks until the device stream is running (or failed to start) so the
+/// caller gets a synchronous error. Packets are published on `audio_tx` until
+/// `shutdown_rx` flips to true.
+pub fn spawn_audio_capture(
+    audio_tx: broadcast::Sender<Bytes>,
+    shutdown_rx: watch::Receiver<bool>,
+) -> Result<std::thread::JoinHandle<()>, AppError> {
+    let (ready_tx, ready_rx) = mpsc::channel::<Result<(), AppError>>();
+
+    let handle = std::thread::Builder::new()
+        .name("audio-capture".into())
+        .spawn(move || {
+            // The cpal stream is !Send on some platforms, so it is created and
+            // dropped on this thread.
+            let (stream, pcm_rx, channels) = match start_stream() {
+                Ok(s) => s,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+
+            run_encoder(pcm_rx, channels, audio_tx, shutdown_rx);
+
+            drop(stream);
+            log::info!("Audio capture stopped");
+        })
+        .map_err(|e| AppError::Internal(format!("Failed to start audio thread: {}", e)))?;
+
+    ready_rx
+        .recv()
+        .map_err(|_| AppError::Internal("Audio thread exited during startup".into()))??;
+
+    Ok(handle)
+}
+
+/// Open the capture device and start streaming PCM (as f32) into a queue.
+fn start_stream() -> Result<(cpal::Stream, mpsc::Receiver<Vec<f32>>, u16), AppError> {
+    let host = cpal::default_host();
+
+    #[cfg(windows)]
+    let device = host.default_output_device();
+    #[cfg(not(windows))]
+    let device = host.default_input_device();
+
+    let device =
+        device.ok_or_else(|| AppError::Capture("No audio capture device available".into()))?;
+    let device_name = device.name().unwrap_or_else(|_| "unknown".into());
+
+    #[cfg(windows)]
+    let configs = device.supported_output_configs();
+    #[cfg(not(windows))]
+    let configs = device.supported_input_configs();
+
+    let supported = configs
+        .map_err(|e| AppError::Capture(format!("Failed to query audio device: {}", e)))?
+        .filter(|c| matches!(c.channels(), 1 | 2))
+        .find(|c| {
+            c.min_sample_rate().0 <= AUDIO_SAMPLE_RATE && c.max_sample_rate().0 >= AUDIO_SAMPLE_RATE
+        })
+        .ok_or_else(|| {
+            AppError::Capture(format!(
+                "Audio device '{}' does not support mono/stereo at {} Hz",
+                device_name, AUDIO_SAMPLE_RATE
+            ))
+        })?
+        .with_sample_rate(SampleRate(AUDIO_SAMPLE_RATE));
+
+    let channels = supported.channels();
+    let sample_format = supported.sample_format();
+    let config = supported.config();
+    let (pcm_tx, pcm_rx) = mpsc::sync_channel::<Vec<f32>>(PCM_QUEUE_CAPACITY);
+
+    let err_fn = |e: cpal::StreamError| log::error!("Audio stream error: {}", e);
+    let stream = match sample_format {
+        SampleFormat::F32 => device.build_input_stream(
+            &config,
+            move |data: &[f32], _: &_| queue_pcm(&pcm_tx, data.to_vec()),
+            err_fn,
+            None,
+        ),
+        SampleFormat::I16 => device.build_input_stream(
+            &config,
+            move |data: &[i16], _: &_| {
+                queue_pcm(&pcm_tx, data.iter().map(|s| s.to_sample::<f32>()).collect())
+            },
+            err_fn,
+            None,
+        ),
+        SampleFormat::U16 => device.build_input_stream(
+            &config,
+            move |data: &[u16], _: &_| {
+                queue_pcm(&pcm_tx, data.iter().map(|s| s.to_sample::<f32>()).collect())
+            },
+            err_fn,
+            None,
+        ),
+        other => {
+            return Err(AppError::Capture(format!(
+                "Unsupported audio sample format: {:?}",
+                other
+            )))
+        }
+    }
+    .map_err(|e| AppError::Capture(format!("Failed to open audio stream: {}", e)))?;
+
+    stream
+        .play()
+        .map_err(|e| AppError::Capture(format!("Failed to start audio stream: {}", e)))?;
+
+    log::info!(
+        "Audio capture started on '{}' ({} ch, {} Hz, {:?})",
+        device_name,
+        channels,
+        AUDIO_SAMPLE_RATE,
+        sample_format
+    );
+
+    Ok((stream, pcm_rx, channels))
+}
+
+/// Hand PCM from the device callback to the encoder without blocking.
+/// Drops the chunk if the encoder has fallen behind.
+fn queue_pcm(tx: &mpsc::SyncSender<Vec<f32>>, samples: Vec<f32>) {
+    if let Err(mpsc::TrySendError::Full(_)) = tx.try_send(samples) {
+        log::debug!("Audio encoder behind, dropping PCM chunk");
+    }
+}
+
+/// Encoder loop: slice PCM into 20 ms frames, Opus-encode and publish.
+fn run_encoder(
+    pcm_rx: mpsc::Receiver<Vec<f32>>,
+    channels: u16,
+    audio_tx: broadcast::Sender<Bytes>,
+    shutdown_rx: watch::Receiver<bool>,
+) {
+    let opus_channels = if channels == 1 {
+        audiopus::Channels::Mono
+    } else {
+        audiopus::Channels::Stereo
+    };
+
+    let mut encoder = match audiopus::coder::Encoder::new(
+        audiopus::SampleRate::Hz48000,
+        opus_channels,
+        audiopus::Application::LowDelay,
+    ) {
+        Ok(e) => e,
+        Err(e) => {
+            log::error!("Failed to create Opus encoder: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = encoder.set_bitrate(audiopus::Bitrate::BitsPerSecond(OPUS_BITRATE)) {
+        log::warn!("Failed to set Opus bitrate: {}", e);
+    }
+
+    let frame_len = OPUS_FRAME_SAMPLES * channels as usize;
+    let mut pending: Vec<f32> = Vec::with_capacity(frame_len * 4);
+    let mut packet = [0u8; OPUS_MAX_PACKET_BYTES];
+
+    loop {
+        if *shutdown_rx.borrow() {
+            break;
+        }
+
+        match pcm_rx.recv_timeout(SHUTDOWN_POLL_INTERVAL) {
+            Ok(samples) => pending.extend_from_slice(&samples),
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        for frame in drain_frames(&mut pending, frame_len) {
+            match encoder.encode_float(&frame, &mut packet) {
+                Ok(n) => {
+                    // No receivers just means no client is connected right now
+                    let _ = audio_tx.send(frame_audio_packet(channels as u8, &packet[..n]));
+                }
+                Err(e) => log::warn!("Opus encode failed: {}", e),
+            }
+        }
+    }
+}
+
+// =============================================================================
+// Helpers
+// =============================================================================
+
+/// Remove all complete frames of `frame_len` samples from the front of
+/// `pending`, leaving any partial frame for the next round.
+fn drain_frames(pending: &mut Vec<f32>, frame_len: usize) -> Vec<Vec<f32>> {
+    let complete = pending.len() / frame_len * frame_len;
+    pending
+        .drain(..complete)
+        .collect::<Vec<f32>>()
+        .chunks_exact(frame_len)
+        .map(|c| c.to_vec())
+        .collect()
+}
+
+/// Build an audio stream message: type byte, channel count, Opus packet.
+fn frame_audio_packet(channels: u8, opus: &[u8]) -> Bytes {
+    let mut buf = Vec::with_capacity(2 + opus.len());
+    buf.push(MSG_TYPE_AUDIO);
+    buf.push(channels);
+    buf.extend_from_slice(opus);
+    Bytes::from(buf)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_frames_keeps_partial_remainder() {
+        let mut pending: Vec<f32> = (0..10).map(|i| i as f32).collect();
+        let frames = drain_frames(&mut pending, 4);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1], vec![4.0, 5.0, 6.0, 7.0]);
+        assert_eq!(pending, vec![8.0, 9.0]);
+    }
+
+    #[test]
+    fn test_frame_audio_packet_layout() {
+        let packet = frame_audio_packet(2, &[0xAA, 0xBB]);
+        assert_eq!(packet.as_ref(), &[MSG_TYPE_AUDIO, 2, 0xAA, 0xBB]);
+    }
+}
@@ -0,0 +1,225 @@
+//! Burst capture: a few downscaled frames of a display in quick succession.
+//!
+//! Lets agents answer "is anything moving or progressing on screen?" without
+//! starting a stream. Each frame is compared with the one before it, and
+//! `change` reports the fraction of pixels that differ, so most callers can
+//! decide from `max_change` alone and only look at the images when needed.
+//!
+//! Every frame is a fresh single-frame capture: a running capturer may not
+//! deliver frames at all while the screen is static, which is exactly the
+//! case a burst has to detect. Captures take time, so frames can be further
+//! apart than `interval_ms`; `offset_ms` has the actual timing.
+
+use base64::Engine;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use crate::streaming;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+const MIN_FRAMES: u32 = 2;
+const MAX_FRAMES: u32 = 30;
+
+const MIN_INTERVAL_MS: u64 = 50;
+
+/// Longest a burst may span (`count` x `interval_ms`)
+const MAX_BURST_MS: u64 = 60_000;
+
+/// Frame width when the caller sets no `max_width`
+const DEFAULT_MAX_WIDTH: u32 = 640;
+const MIN_WIDTH: u32 = 64;
+const MAX_WIDTH: u32 = 1280;
+
+const JPEG_QUALITY: u8 = 70;
+
+/// A pixel counts as changed when a channel differs by more than this, so
+/// capture noise and color dithering are ignored
+const PIXEL_THRESHOLD: u8 = 16;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Result of `capture_burst`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Burst {
+    /// Size of the downscaled frames
+    pub width: u32,
+    pub height: u32,
+    pub frames: Vec<BurstFrame>,
+    /// Largest `change` between consecutive frames (0 for a static screen)
+    pub max_change: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BurstFrame {
+    /// Time since the first frame was captured
+    pub offset_ms: u64,
+    /// Fraction of pixels (0-1) that differ from the previous frame; None for
+    /// the first
+    pub change: Option<f32>,
+    /// JPEG image encoded as a `data:image/jpeg;base64,...` URL
+    pub data_url: String,
+}
+
+// =============================================================================
+// Capture
+// =============================================================================
+
+/// Fraction of pixels that differ between two packed RGB images. Images of
+/// different sizes (the display changed resolution) count as fully changed.
+fn change_ratio(previous: &[u8], current: &[u8]) -> f32 {
+    if previous.len() != current.len() || current.is_empty() {
+        return 1.0;
+    }
+    let changed = previous
+        .chunks_exact(3)
+        .zip(current.chunks_exact(3))
+        .filter(|(a, b)| a.iter().zip(b.iter()).any(|(x, y)| x.abs_diff(*y) > PIXEL_THRESHOLD))
+        .count();
+    changed as f32 / (current.len() / 3) as f32
+}
+
+/// Capture `count` frames `interval` apart. Blocking.
+fn run_burst(
+    display_id: Option<u32>,
+    count: u32,
+    interval: Duration,
+    max_width: u32,
+) -> Result<Burst, AppError> {
+    let mut frames = Vec::with_capacity(count as usize);
+    let mut previous: Option<Vec<u8>> = None;
+    let mut size = (0, 0);
+    let mut started: Option<Instant> = None;
+
+    for i in 0..count {
+        if let Some(started) = started {
+            // Scheduled from the start so slow captures don't add up
+            let due = started + interval * i;
+            std::thread::sleep(due.saturating_duration_since(Instant::now()));
+        }
+        let target = streaming::find_display_target(display_id)
+            .ok_or_else(|| AppError::NotFound(format!("Display not found: {:?}", display_id)))?;
+        let frame = streaming::capture_single_frame(target)?;
+        let captured_at = Instant::now();
+        let started = *started.get_or_insert(captured_at);
+
+        let (rgb, width, height) = streaming::downscale_bgra_to_rgb(
+            &frame.data,
+            frame.width as u32,
+            frame.height as u32,
+            max_width,
+        );
+        let jpeg = streaming::encode_jpeg(&rgb, width, height, JPEG_QUALITY)?;
+        frames.push(BurstFrame {
+            offset_ms: captured_at.duration_since(started).as_millis() as u64,
+            change: previous.as_deref().map(|prev| change_ratio(prev, &rgb)),
+            data_url: format!(
+                "data:image/jpeg;base64,{}",
+                base64::engine::general_purpose::STANDARD.encode(&jpeg)
+            ),
+        });
+        size = (width, height);
+        previous = Some(rgb);
+    }
+
+    let max_change = frames.iter().filter_map(|f| f.change).fold(0.0, f32::max);
+    Ok(Burst {
+        width: size.0,
+        height: size.1,
+        frames,
+        max_change,
+    })
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Capture `count` downscaled frames of a display, `interval_ms` apart, and
+/// how much each differs from the one before.
+///
+/// # Arguments
+/// * `display_id` - Display to capture (default: main display)
+/// * `count` - Frames to capture (2-30)
+/// * `interval_ms` - Time between frames (at least 50; the whole burst at
+///   most 60 seconds)
+/// * `max_width` - Frame width in pixels (64-1280, default 640; aspect ratio
+///   is preserved)
+#[tauri::command]
+pub async fn capture_burst(
+    display_id: Option<u32>,
+    count: u32,
+    interval_ms: u64,
+    max_width: Option<u32>,
+) -> Result<Burst, AppError> {
+    if !(MIN_FRAMES..=MAX_FRAMES).contains(&count) {
+        return Err(AppError::Validation(format!(
+            "count must be {}-{}, got: {}",
+            MIN_FRAMES, MAX_FRAMES, count
+        )));
+    }
+    if interval_ms < MIN_INTERVAL_MS || interval_ms.saturating_mul(count as u64) > MAX_BURST_MS {
+        return Err(AppError::Validation(format!(
+            "interval_ms must be at least {} and count x interval_ms at most {}, got: {} x {}",
+            MIN_INTERVAL_MS, MAX_BURST_MS, count, interval_ms
+        )));
+    }
+    let max_width = max_width.unwrap_or(DEFAULT_MAX_WIDTH);
+    if !(MIN_WIDTH..=MAX_WIDTH).contains(&max_width) {
+        return Err(AppError::Validation(format!(
+            "max_width must be {}-{}, got: {}",
+            MIN_WIDTH, MAX_WIDTH, max_width
+        )));
+    }
+
+    if !scap::is_supported() {
+        return Err(AppError::Capture("Screen capture not supported on this platform".into()));
+    }
+    if !scap::has_permission() {
+        return Err(AppError::PermissionDenied("Screen capture permission not granted".into()));
+    }
+
+    let interval = Duration::from_millis(interval_ms);
+    let burst =
+        tokio::task::spawn_blocking(move || run_burst(display_id, count, interval, max_width))
+            .await
+            .map_err(|e| AppError::Internal(format!("Burst capture task failed: {}", e)))??;
+    log::debug!(
+        "Captured a burst of {} frame(s) of display {:?}, max change {:.3}",
+        burst.frames.len(),
+        display_id,
+        burst.max_change
+    );
+    Ok(burst)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_ratio() {
+        let still = vec![100u8; 4 * 3];
+        assert_eq!(change_ratio(&still, &still), 0.0);
+
+        // Noise below the threshold is ignored
+        let noisy: Vec<u8> = still.iter().map(|v| v + PIXEL_THRESHOLD).collect();
+        assert_eq!(change_ratio(&still, &noisy), 0.0);
+
+        // One of four pixels changed in one channel
+        let mut moved = still.clone();
+        moved[4] = 200;
+        assert_eq!(change_ratio(&still, &moved), 0.25);
+
+        assert_eq!(change_ratio(&still, &still[..6]), 1.0);
+    }
+}
@@ -0,0 +1,607 @@
+//! Webcam streaming (`start_camera_stream`).
+//!
+//! A camera is captured with nokhwa (AVFoundation on macOS, Media Foundation
+//! on Windows, V4L2 on Linux) and served through the screen stream's
+//! WebSocket pipeline on a port of its own, so a camera and a display can
+//! stream side by side and the `ScreenStream` viewer renders either one
+//! unchanged. MJPEG is requested where the camera offers it and decoded with
+//! turbojpeg; other formats are converted by nokhwa.
+//!
+//! The stream follows the `stream.*` settings (allowed peers, client limit,
+//! TLS) like the screen stream. It carries no audio and no remote control.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use nokhwa::pixel_format::RgbAFormat;
+use nokhwa::utils::{
+    ApiBackend, CameraFormat, CameraIndex, FrameFormat, RequestedFormat, RequestedFormatType,
+};
+use nokhwa::{Buffer, Camera};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::{broadcast, oneshot, watch, Mutex};
+
+use crate::events;
+use crate::settings::SettingsState;
+use crate::streaming::{
+    self, ClientRegistry, FramePublisher, FrameServer, PeerPolicy, ScalingQuality,
+    SharedMaxWidth, StreamClientInfo, VideoUpdate,
+};
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Capture size requested when the caller gives none
+const DEFAULT_WIDTH: u32 = 1280;
+const DEFAULT_HEIGHT: u32 = 720;
+
+/// Accepted capture sizes; frames are sent as raw pixels, so 4K is the cap
+const MIN_DIMENSION: u32 = 160;
+const MAX_WIDTH: u32 = 3840;
+const MAX_HEIGHT: u32 = 2160;
+
+/// Consecutive frame errors (about 100 ms apart) after which capture stops
+const MAX_FRAME_ERRORS: u32 = 30;
+
+/// Pause after a failed frame read before the next attempt
+const FRAME_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// How long the macOS camera permission prompt may stay unanswered
+const PERMISSION_WAIT_TIMEOUT: Duration = Duration::from_secs(300);
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// A camera returned by `list_cameras`
+#[derive(Debug, Clone, Serialize)]
+pub struct CameraDevice {
+    /// Index passed to `start_camera_stream`
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+}
+
+/// Camera stream status returned to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct CameraStreamStatus {
+    pub active: bool,
+    pub port: u16,
+    pub fps: u32,
+    pub camera_index: u32,
+    /// Size the camera delivers, which may differ from the one requested
+    pub width: u32,
+    pub height: u32,
+    /// Format the camera delivers (e.g. "MJPEG", "YUYV")
+    pub frame_format: String,
+    pub clients: usize,
+    /// Served over TLS: viewers connect with `wss://`
+    pub tls: bool,
+    /// SHA-256 fingerprint of the certificate served, when `tls`
+    pub cert_fingerprint: Option<String>,
+    pub client_stats: Vec<StreamClientInfo>,
+}
+
+/// Active camera stream with handles to shut it down
+struct CameraSession {
+    shutdown_tx: watch::Sender<bool>,
+    capture: std::thread::JoinHandle<()>,
+    ws_handle: tokio::task::JoinHandle<()>,
+    port: u16,
+    fps: u32,
+    camera_index: u32,
+    format: CameraFormat,
+    cert_fingerprint: Option<String>,
+    clients: Arc<ClientRegistry>,
+}
+
+/// Shared state managed by Tauri
+#[derive(Default)]
+pub struct CameraState {
+    session: Mutex<Option<CameraSession>>,
+}
+
+/// Everything the capture thread needs
+struct CameraJob {
+    app: AppHandle,
+    camera_index: u32,
+    width: u32,
+    height: u32,
+    fps: u32,
+    max_width: Arc<SharedMaxWidth>,
+    frame_tx: Arc<watch::Sender<VideoUpdate>>,
+    clients: Arc<ClientRegistry>,
+    /// Lets the thread stop the server when it gives up on the camera
+    shutdown_tx: watch::Sender<bool>,
+    shutdown_rx: watch::Receiver<bool>,
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// List the cameras the stream can capture.
+///
+/// On macOS the first call asks for camera permission.
+#[tauri::command]
+pub async fn list_cameras() -> Result<Vec<CameraDevice>, AppError> {
+    ensure_camera_permission().await?;
+    let cameras = tokio::task::spawn_blocking(|| nokhwa::query(ApiBackend::Auto))
+        .await
+        .map_err(|e| AppError::Internal(format!("Camera query task failed: {}", e)))?
+        .map_err(|e| AppError::Capture(format!("Failed to list cameras: {}", e)))?;
+    Ok(cameras
+        .into_iter()
+        .filter_map(|info| {
+            Some(CameraDevice {
+                index: info.index().as_index().ok()?,
+                name: info.human_name(),
+                description: info.description().to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Start streaming a camera over WebSocket, in the screen stream's format.
+///
+/// # Arguments
+/// * `camera_index` - Index from `list_cameras`
+/// * `port` - Port to serve on (9100-9199, or 0 for the first free one)
+/// * `fps` - Target frames per second (1-30)
+/// * `width`, `height` - Capture size to request (default 1280x720); the
+///   camera's closest supported size is used
+#[tauri::command]
+pub async fn start_camera_stream(
+    app: AppHandle,
+    state: State<'_, CameraState>,
+    camera_index: u32,
+    port: u16,
+    fps: u32,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> Result<CameraStreamStatus, AppError> {
+    let width = width.unwrap_or(DEFAULT_WIDTH);
+    let height = height.unwrap_or(DEFAULT_HEIGHT);
+    validate_params(port, fps, width, height)?;
+
+    // Before taking the lock: the macOS prompt can stay open for minutes
+    ensure_camera_permission().await?;
+
+    let mut session = state.session.lock().await;
+    if let Some(failed) = take_failed(&mut session) {
+        shut_down(failed).await;
+    }
+    if session.is_some() {
+        return Err(AppError::Validation(
+            "Camera stream already running. Stop it first.".into(),
+        ));
+    }
+
+    let stream_settings = app.state::<SettingsState>().get().stream;
+    let peers = Arc::new(PeerPolicy::from_settings(
+        &stream_settings.allowed_ips,
+        stream_settings.max_clients,
+    ));
+    let tls = streaming::load_stream_tls(&app, &stream_settings).await?;
+    let cert_fingerprint = tls.as_ref().map(|(_, fp)| fp.clone());
+    let tls_acceptor = tls.map(|(acceptor, _)| acceptor);
+
+    let listener = streaming::bind_stream_listener(port).await?;
+    let actual_port = listener.local_addr().map(|a| a.port()).unwrap_or(port);
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let (frame_tx, _) = watch::channel(VideoUpdate::default());
+    let frame_tx = Arc::new(frame_tx);
+    let clients = Arc::new(ClientRegistry::default());
+    let max_width = Arc::new(SharedMaxWidth::new(None));
+
+    // The camera is opened on the capture thread (nokhwa cameras are not
+    // Send), which reports the format it settled on
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let capture = spawn_camera_capture(
+        CameraJob {
+            app: app.clone(),
+            camera_index,
+            width,
+            height,
+            fps,
+            max_width: max_width.clone(),
+            frame_tx: frame_tx.clone(),
+            clients: clients.clone(),
+            shutdown_tx: shutdown_tx.clone(),
+            shutdown_rx: shutdown_rx.clone(),
+        },
+        ready_tx,
+    );
+    let format = match ready_rx.await {
+        Ok(Ok(format)) => format,
+        Ok(Err(e)) => return Err(e),
+        Err(_) => {
+            return Err(AppError::Capture(
+                "Camera thread exited before the camera opened".into(),
+            ))
+        }
+    };
+
+    // Camera streams carry no audio; the sender only keeps viewers' audio
+    // receivers open
+    let (audio_tx, _) = broadcast::channel(1);
+    let ws_handle = streaming::spawn_frame_server(FrameServer {
+        listener,
+        peers,
+        tls_acceptor,
+        frame_tx,
+        audio_tx,
+        clients: clients.clone(),
+        max_width,
+        remote_input: None,
+        shutdown_rx,
+    });
+
+    log::info!(
+        "Camera {} streaming on {}://127.0.0.1:{} ({}x{} {}, {}fps)",
+        camera_index,
+        if cert_fingerprint.is_some() { "wss" } else { "ws" },
+        actual_port,
+        format.width(),
+        format.height(),
+        format.format(),
+        fps
+    );
+
+    let new_session = CameraSession {
+        shutdown_tx,
+        capture,
+        ws_handle,
+        port: actual_port,
+        fps,
+        camera_index,
+        format,
+        cert_fingerprint,
+        clients,
+    };
+    let status = session_status(&new_session);
+    *session = Some(new_session);
+    Ok(status)
+}
+
+/// Stop the camera stream
+#[tauri::command]
+pub async fn stop_camera_stream(state: State<'_, CameraState>) -> Result<(), AppError> {
+    stop_camera(&state).await
+}
+
+/// Get the current camera stream status
+#[tauri::command]
+pub async fn get_camera_stream_status(
+    state: State<'_, CameraState>,
+) -> Result<CameraStreamStatus, AppError> {
+    let mut session = state.session.lock().await;
+    if let Some(failed) = take_failed(&mut session) {
+        shut_down(failed).await;
+    }
+    Ok(match session.as_ref() {
+        Some(s) => session_status(s),
+        None => CameraStreamStatus {
+            active: false,
+            port: 0,
+            fps: 0,
+            camera_index: 0,
+            width: 0,
+            height: 0,
+            frame_format: String::new(),
+            clients: 0,
+            tls: false,
+            cert_fingerprint: None,
+            client_stats: Vec::new(),
+        },
+    })
+}
+
+// =============================================================================
+// Internal Functions
+// =============================================================================
+
+/// Stop the camera stream, if any. Shared by the stop command and shutdown.
+pub(crate) async fn stop_camera(state: &CameraState) -> Result<(), AppError> {
+    let Some(s) = state.session.lock().await.take() else {
+        return Err(AppError::NotFound("No camera stream is running".into()));
+    };
+    shut_down(s).await;
+    log::info!("Camera stream stopped");
+    Ok(())
+}
+
+/// Signal `s` to stop and wait for its server and capture thread
+async fn shut_down(s: CameraSession) {
+    let _ = s.shutdown_tx.send(true);
+    let _ = s.ws_handle.await;
+    // The thread notices shutdown after its next frame, at most 1/fps away
+    let _ = tokio::task::spawn_blocking(move || s.capture.join()).await;
+}
+
+/// Take the session out of `session` if its capture thread has exited on
+/// its own, which it only does after `MAX_FRAME_ERRORS`.
+fn take_failed(session: &mut Option<CameraSession>) -> Option<CameraSession> {
+    session.take_if(|s| s.capture.is_finished())
+}
+
+fn session_status(s: &CameraSession) -> CameraStreamStatus {
+    CameraStreamStatus {
+        active: true,
+        port: s.port,
+        fps: s.fps,
+        camera_index: s.camera_index,
+        width: s.format.width(),
+        height: s.format.height(),
+        frame_format: s.format.format().to_string(),
+        clients: s.clients.len(),
+        tls: s.cert_fingerprint.is_some(),
+        cert_fingerprint: s.cert_fingerprint.clone(),
+        client_stats: s.clients.snapshot(),
+    }
+}
+
+/// Validate parameters with explicit errors instead of silent clamping
+fn validate_params(port: u16, fps: u32, width: u32, height: u32) -> Result<(), AppError> {
+    if !(1..=streaming::MAX_FPS).contains(&fps) {
+        return Err(AppError::Validation(format!(
+            "FPS must be 1-{}, got: {}",
+            streaming::MAX_FPS,
+            fps
+        )));
+    }
+    if !streaming::is_valid_stream_port(port) {
+        return Err(AppError::Validation(format!(
+            "Streaming port must be {} (auto) or {}-{}, got: {}",
+            streaming::STREAM_PORT_AUTO,
+            streaming::STREAM_PORT_MIN,
+            streaming::STREAM_PORT_MAX,
+            port
+        )));
+    }
+    if !(MIN_DIMENSION..=MAX_WIDTH).contains(&width)
+        || !(MIN_DIMENSION..=MAX_HEIGHT).contains(&height)
+    {
+        return Err(AppError::Validation(format!(
+            "Camera size must be {}x{} to {}x{}, got: {}x{}",
+            MIN_DIMENSION, MIN_DIMENSION, MAX_WIDTH, MAX_HEIGHT, width, height
+        )));
+    }
+    Ok(())
+}
+
+/// Ask for camera access on macOS (a no-op elsewhere), waiting for the
+/// user to answer the prompt.
+async fn ensure_camera_permission() -> Result<(), AppError> {
+    if nokhwa::nokhwa_check() {
+        return Ok(());
+    }
+    let (tx, rx) = oneshot::channel();
+    let tx = std::sync::Mutex::new(Some(tx));
+    nokhwa::nokhwa_initialize(move |granted| {
+        if let Some(tx) = tx.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = tx.send(granted);
+        }
+    });
+    match tokio::time::timeout(PERMISSION_WAIT_TIMEOUT, rx).await {
+        Ok(Ok(true)) => Ok(()),
+        Ok(_) => Err(AppError::PermissionDenied(
+            "Camera access denied. Allow Synthia in System Settings > Privacy & Security > Camera."
+                .into(),
+        )),
+        Err(_) => Err(AppError::Timeout(
+            "The camera permission prompt was not answered".into(),
+        )),
+    }
+}
+
+/// Open camera `index` near `width`x`height` at `fps`, preferring MJPEG.
+fn open_camera(index: u32, width: u32, height: u32, fps: u32) -> Result<Camera, AppError> {
+    let camera_index = CameraIndex::Index(index);
+    let mjpeg = CameraFormat::new_from(width, height, FrameFormat::MJPEG, fps);
+    // Closest only matches the same frame format, so cameras without MJPEG
+    // get the largest size at the requested frame rate, then anything
+    let requests = [
+        RequestedFormatType::Closest(mjpeg),
+        RequestedFormatType::HighestFrameRate(fps),
+        RequestedFormatType::None,
+    ];
+    let mut last_error = None;
+    for request in requests {
+        match Camera::new(camera_index.clone(), RequestedFormat::new::<RgbAFormat>(request)) {
+            Ok(mut camera) => {
+                camera.open_stream().map_err(|e| {
+                    AppError::Capture(format!("Failed to start camera {}: {}", index, e))
+                })?;
+                return Ok(camera);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(AppError::Capture(format!(
+        "Failed to open camera {}: {}",
+        index,
+        last_error.map(|e| e.to_string()).unwrap_or_default()
+    )))
+}
+
+/// Decode a camera frame to tightly packed BGRA, returning it with its size.
+fn frame_to_bgra(frame: &Buffer) -> Result<(Vec<u8>, usize, usize), AppError> {
+    if frame.source_frame_format() == FrameFormat::MJPEG {
+        let image = turbojpeg::decompress(frame.buffer(), turbojpeg::PixelFormat::BGRA)
+            .map_err(|e| AppError::Capture(format!("MJPEG decode failed: {}", e)))?;
+        return Ok((image.pixels, image.width, image.height));
+    }
+    let image = frame
+        .decode_image::<RgbAFormat>()
+        .map_err(|e| AppError::Capture(format!("Frame decode failed: {}", e)))?;
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mut pixels = image.into_raw();
+    rgba_to_bgra(&mut pixels);
+    Ok((pixels, width, height))
+}
+
+/// Swap the red and blue channels of RGBA pixels in place.
+fn rgba_to_bgra(pixels: &mut [u8]) {
+    for px in pixels.chunks_exact_mut(4) {
+        px.swap(0, 2);
+    }
+}
+
+/// Spawn the capture thread. It opens the camera, reports the format it
+/// settled on (or the error) on `ready_tx`, then publishes frames into
+/// `frame_tx` until shutdown.
+fn spawn_camera_capture(
+    job: CameraJob,
+    ready_tx: oneshot::Sender<Result<CameraFormat, AppError>>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let CameraJob {
+            app,
+            camera_index,
+            width,
+            height,
+            fps,
+            max_width,
+            frame_tx,
+            clients,
+            shutdown_tx,
+            shutdown_rx,
+        } = job;
+
+        let mut camera = match open_camera(camera_index, width, height, fps) {
+            Ok(camera) => camera,
+            Err(e) => {
+                log::error!("{}", e);
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        if ready_tx.send(Ok(camera.camera_format())).is_err() {
+            return;
+        }
+
+        let mut publisher = FramePublisher::new(fps, 0);
+        // Reusable buffer for downscaled BGRA frames
+        let mut scaled: Vec<u8> = Vec::new();
+        let frame_interval = Duration::from_secs(1) / fps;
+        let mut last_published: Option<Instant> = None;
+        let mut errors: u32 = 0;
+
+        while !*shutdown_rx.borrow() {
+            let frame = match camera.frame() {
+                Ok(frame) => {
+                    errors = 0;
+                    frame
+                }
+                Err(e) => {
+                    errors += 1;
+                    if errors >= MAX_FRAME_ERRORS {
+                        let error = AppError::Capture(format!(
+                            "Camera {} failed {} times in a row: {}",
+                            camera_index, errors, e
+                        ));
+                        log::error!("{}", error);
+                        let _ = events::emit(&app, "camera-stream-failed", &error);
+                        // Disconnect viewers; the session is dropped on the
+                        // next status or start call
+                        let _ = shutdown_tx.send(true);
+                        break;
+                    }
+                    log::warn!("Camera frame error: {}", e);
+                    std::thread::sleep(FRAME_ERROR_BACKOFF);
+                    continue;
+                }
+            };
+
+            // Cameras often deliver faster than the requested rate
+            let arrived = Instant::now();
+            if last_published
+                .is_some_and(|t| arrived.saturating_duration_since(t) < frame_interval)
+            {
+                continue;
+            }
+
+            let (pixels, mut src_w, mut src_h) = match frame_to_bgra(&frame) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    log::debug!("Skipping camera frame: {}", e);
+                    continue;
+                }
+            };
+            if src_w == 0 || src_h == 0 || pixels.len() < src_w * src_h * 4 {
+                continue;
+            }
+            let mut pixels = &pixels[..src_w * src_h * 4];
+
+            // Viewers can ask for a smaller width over the WebSocket
+            if let Some(max_w) = max_width.get() {
+                let (dst_w, dst_h) = streaming::fit_width(src_w as u32, src_h as u32, max_w);
+                if dst_w as usize != src_w {
+                    streaming::scale_bgra(
+                        pixels,
+                        src_w,
+                        src_h,
+                        dst_w as usize,
+                        dst_h as usize,
+                        ScalingQuality::default(),
+                        &mut scaled,
+                    );
+                    (src_w, src_h) = (dst_w as usize, dst_h as usize);
+                    pixels = &scaled;
+                }
+            }
+
+            publisher.publish(pixels, src_w, src_h, &clients, &frame_tx);
+            last_published = Some(arrived);
+        }
+
+        // Dropping the camera stops its stream
+        drop(camera);
+        log::info!("Camera capture stopped");
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_params() {
+        assert!(validate_params(0, 30, DEFAULT_WIDTH, DEFAULT_HEIGHT).is_ok());
+        assert!(validate_params(streaming::STREAM_PORT_MIN, 1, 160, 160).is_ok());
+        assert!(validate_params(0, 0, DEFAULT_WIDTH, DEFAULT_HEIGHT).is_err());
+        assert!(validate_params(0, streaming::MAX_FPS + 1, DEFAULT_WIDTH, DEFAULT_HEIGHT).is_err());
+        assert!(validate_params(8080, 30, DEFAULT_WIDTH, DEFAULT_HEIGHT).is_err());
+        assert!(validate_params(0, 30, MAX_WIDTH + 1, DEFAULT_HEIGHT).is_err());
+        assert!(validate_params(0, 30, DEFAULT_WIDTH, MIN_DIMENSION - 1).is_err());
+    }
+
+    #[test]
+    fn test_rgba_to_bgra() {
+        let mut pixels = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        rgba_to_bgra(&mut pixels);
+        assert_eq!(pixels, vec![3, 2, 1, 4, 7, 6, 5, 8]);
+    }
+
+    #[test]
+    fn test_mjpeg_frame_to_bgra() {
+        // A red 16x8 JPEG, as an MJPEG camera would deliver it
+        let rgb: Vec<u8> = [255, 0, 0].repeat(16 * 8);
+        let jpeg = streaming::encode_jpeg(&rgb, 16, 8, 90).unwrap();
+        let frame = Buffer::new(nokhwa::utils::Resolution::new(16, 8), &jpeg, FrameFormat::MJPEG);
+        let (pixels, width, height) = frame_to_bgra(&frame).unwrap();
+        assert_eq!((width, height), (16, 8));
+        assert_eq!(pixels.len(), 16 * 8 * 4);
+        // BGRA: blue low, red high
+        assert!(pixels[0] < 16 && pixels[2] > 240, "{:?}", &pixels[..4]);
+    }
+}
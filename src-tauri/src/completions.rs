@@ -0,0 +1,423 @@
+//! Shell completions for the terminal panel's autocomplete overlay.
+//!
+//! `get_completions` asks the session's own shell what could follow a
+//! partially typed command line. A hidden helper of the same shell is
+//! started in the session's working directory:
+//!
+//! - bash loads bash-completion when it is installed, then calls the
+//!   command's completion function (`complete -F`), word list or action,
+//!   falling back to `compgen` for commands and files
+//! - zsh runs an interactive `zsh -f` inside `zpty`, loads `compinit` and
+//!   presses Tab; a `compadd` wrapper prints every match between marker
+//!   lines, which are cut out of the captured pty output
+//!
+//! The helpers do not read the user's rc files, so completions defined only
+//! there (and aliases or functions of the live session) are not offered.
+//! Other shells are not supported.
+
+use serde::Serialize;
+use std::process::Stdio;
+use std::time::Duration;
+use sysinfo::System;
+use tauri::State;
+
+use crate::agent_context;
+use crate::ansi;
+use crate::pty::{self, PtyState};
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Longest `partial` accepted
+const MAX_PARTIAL_CHARS: usize = 4096;
+
+/// Candidates returned at most
+const MAX_CANDIDATES: usize = 500;
+
+/// Time the helper shell gets before it is killed
+const COMPLETION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Separates the matches from the rest of the zsh pty output
+const ZSH_MARKER: char = '\x1e';
+
+/// bash helper: `$1` is the index of the word being completed, followed by
+/// the words of the command
+const BASH_HELPER: &str = r#"
+for f in /usr/share/bash-completion/bash_completion /etc/bash_completion \
+    /opt/homebrew/etc/profile.d/bash_completion.sh /usr/local/etc/profile.d/bash_completion.sh; do
+    if [[ -r $f ]]; then
+        . "$f" >/dev/null 2>&1
+        break
+    fi
+done
+COMP_CWORD=$1
+shift
+COMP_WORDS=("$@")
+COMP_LINE="$*"
+COMP_POINT=${#COMP_LINE}
+cur=${COMP_WORDS[COMP_CWORD]}
+
+files() {
+    compgen -f -- "$cur" | while IFS= read -r c; do
+        [[ -d $c ]] && c+=/
+        printf '%s\n' "$c"
+    done
+}
+
+if ((COMP_CWORD == 0)); then
+    [[ $cur == */* ]] && files || compgen -A function -abck -- "$cur"
+    exit 0
+fi
+cmd=${COMP_WORDS[0]}
+spec=$(complete -p -- "$cmd" 2>/dev/null)
+if [[ -z $spec ]] && declare -F _completion_loader >/dev/null; then
+    _completion_loader "$cmd" >/dev/null 2>&1
+    spec=$(complete -p -- "$cmd" 2>/dev/null)
+fi
+COMPREPLY=()
+if [[ $spec =~ -F\ ([^ ]+) ]]; then
+    "${BASH_REMATCH[1]}" "$cmd" "$cur" "${COMP_WORDS[COMP_CWORD - 1]}" >/dev/null 2>&1
+elif [[ -n $spec ]]; then
+    opts=${spec#complete }
+    eval "COMPREPLY=(\$(compgen ${opts% *} -- \"\$cur\"))" 2>/dev/null
+fi
+if ((${#COMPREPLY[@]})); then
+    printf '%s\n' "${COMPREPLY[@]}"
+elif [[ -z $spec || $spec == *"-o default"* || $spec == *"-o bashdefault"* ]]; then
+    files
+fi
+"#;
+
+/// zsh helper: `$1` is the command line. Everything the inner shell writes
+/// to its terminal is copied to stdout.
+const ZSH_HELPER: &str = r#"
+zmodload zsh/zpty || exit 3
+zpty synthia_complete zsh -f -i
+zpty -w synthia_complete 'eval "$SYNTHIA_ZSH_SETUP"'
+zpty -w -n synthia_complete "$1"$'\t'
+while zpty -r synthia_complete; do :; done
+"#;
+
+/// Evaluated by the inner zsh of `ZSH_HELPER` (passed in an environment
+/// variable, so it is never echoed)
+const ZSH_SETUP: &str = r#"
+PROMPT= RPROMPT=
+autoload -Uz compinit && compinit -D
+zmodload zsh/zutil
+bindkey '^M' undefined
+bindkey '^J' undefined
+bindkey '^I' complete-word
+zstyle ':completion:*' insert-tab false
+zstyle ':completion:*' list-grouped false
+_synthia_mark() { print -r -- $'\x1e' }
+compprefuncs=(_synthia_mark)
+comppostfuncs=(_synthia_mark exit)
+compadd() {
+    # Calls that only collect matches are not ours to print
+    if (( ${@[(I)-[OAD]]} )); then
+        builtin compadd "$@"
+        return
+    fi
+    local -a _synthia_hits
+    local -A _synthia_opts
+    builtin compadd -A _synthia_hits "$@"
+    zparseopts -E -A _synthia_opts P: p: S: s:
+    local hit
+    for hit in $_synthia_hits; do
+        print -r -- "$IPREFIX${_synthia_opts[-P]}${_synthia_opts[-p]}$hit${_synthia_opts[-s]}${_synthia_opts[-S]}"
+    done
+}
+"#;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Shells `get_completions` can drive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+}
+
+impl CompletionShell {
+    /// Shell of a program path, e.g. `/bin/zsh` or `C:\...\bash.exe`.
+    fn from_program(program: &str) -> Option<Self> {
+        let file = program.rsplit(['/', '\\']).next().unwrap_or_default();
+        let file = file.to_lowercase();
+        match file.strip_suffix(".exe").unwrap_or(&file) {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            _ => None,
+        }
+    }
+}
+
+/// Result of `get_completions`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Completions {
+    pub session_id: String,
+    pub shell: CompletionShell,
+    /// The (unquoted) word being completed; candidates replace it
+    pub word: String,
+    /// Candidates in the order the shell produced them, without duplicates
+    pub candidates: Vec<String>,
+    /// More than `MAX_CANDIDATES` candidates were found
+    pub truncated: bool,
+}
+
+// =============================================================================
+// Command Line Parsing
+// =============================================================================
+
+/// Words of the last command of `line` (after any `|`, `;` or `&`), with
+/// quotes and escapes removed. The last word is the one being completed;
+/// it is empty when `line` ends in whitespace.
+fn command_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => {
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                }
+            }
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                }
+                in_word = true;
+            }
+            (None, '|' | ';' | '&') => {
+                words.clear();
+                word.clear();
+                in_word = false;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    words.push(word);
+    words
+}
+
+/// Lines between the first two markers of the zsh pty output, without
+/// carriage returns and escape sequences.
+fn parse_zsh_output(output: &str) -> Vec<String> {
+    let mut sections = output.split(ZSH_MARKER);
+    sections.next();
+    let Some(matches) = sections.next() else {
+        return Vec::new();
+    };
+    ansi::strip(matches)
+        .lines()
+        .map(|line| line.trim_end_matches('\r').trim_end().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Drop duplicates (keeping the first) and cap the list.
+fn dedup_candidates(candidates: Vec<String>) -> (Vec<String>, bool) {
+    let mut seen = std::collections::HashSet::new();
+    let mut unique: Vec<String> = candidates.into_iter().filter(|c| seen.insert(c.clone())).collect();
+    let truncated = unique.len() > MAX_CANDIDATES;
+    unique.truncate(MAX_CANDIDATES);
+    (unique, truncated)
+}
+
+// =============================================================================
+// Helper Shell
+// =============================================================================
+
+/// Run the completion helper of `shell` in `cwd` and return its candidates.
+async fn run_helper(
+    shell: CompletionShell,
+    program: &str,
+    cwd: Option<&str>,
+    partial: &str,
+    words: &[String],
+) -> Result<Vec<String>, AppError> {
+    let mut cmd = tokio::process::Command::new(program);
+    match shell {
+        CompletionShell::Bash => {
+            cmd.args(["--norc", "--noprofile", "-c", BASH_HELPER, "bash"])
+                .arg((words.len() - 1).to_string())
+                .args(words);
+        }
+        CompletionShell::Zsh => {
+            cmd.args(["-f", "-c", ZSH_HELPER, "zsh", partial])
+                .env("SYNTHIA_ZSH_SETUP", ZSH_SETUP);
+        }
+    }
+    if let Some(dir) = cwd {
+        cmd.current_dir(dir);
+    }
+    let output = cmd
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(COMPLETION_TIMEOUT, output)
+        .await
+        .map_err(|_| AppError::Timeout("Shell did not finish completing in time".into()))?
+        .map_err(|e| AppError::Io(format!("Failed to run completion helper: {}", e)))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(match shell {
+        CompletionShell::Bash => stdout
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect(),
+        CompletionShell::Zsh => parse_zsh_output(&stdout),
+    })
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Completion candidates for `partial`, the command line typed so far (up to
+/// the cursor), from the session's own shell.
+///
+/// Runs a hidden helper of the session's shell in its current directory, so
+/// file and command completions match what Tab would offer there. Only bash
+/// and zsh sessions are supported (`validation` error otherwise); a helper
+/// taking longer than 5 seconds fails with `timeout`.
+#[tauri::command]
+pub async fn get_completions(
+    state: State<'_, PtyState>,
+    session_id: String,
+    partial: String,
+) -> Result<Completions, AppError> {
+    if partial.chars().count() > MAX_PARTIAL_CHARS || partial.contains(['\n', '\r']) {
+        return Err(AppError::Validation(format!(
+            "partial must be a single line of at most {} characters",
+            MAX_PARTIAL_CHARS
+        )));
+    }
+    let (program, shell_pid) = pty::session_shell(&state, &session_id)?;
+    let shell = CompletionShell::from_program(&program).ok_or_else(|| {
+        AppError::Validation(format!(
+            "Completions are only available in bash and zsh sessions, not {}",
+            program
+        ))
+    })?;
+    let cwd = match shell_pid {
+        Some(pid) => tokio::task::spawn_blocking(move || {
+            agent_context::process_cwd(&mut System::new(), pid)
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Working directory lookup failed: {}", e)))?,
+        None => None,
+    };
+
+    let words = command_words(&partial);
+    let candidates = run_helper(shell, &program, cwd.as_deref(), &partial, &words).await?;
+    let (candidates, truncated) = dedup_candidates(candidates);
+    log::debug!(
+        "{} completion(s) for session {} ({:?})",
+        candidates.len(),
+        session_id,
+        shell
+    );
+    Ok(Completions {
+        session_id,
+        shell,
+        word: words.last().cloned().unwrap_or_default(),
+        candidates,
+        truncated,
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_words() {
+        assert_eq!(command_words("git ch"), vec!["git", "ch"]);
+        assert_eq!(command_words("git "), vec!["git", ""]);
+        assert_eq!(command_words(""), vec![""]);
+        assert_eq!(command_words("cd 'My Fi"), vec!["cd", "My Fi"]);
+        assert_eq!(command_words(r#"ls My\ Dir "a\"b" x"#), vec!["ls", "My Dir", "a\"b", "x"]);
+        assert_eq!(command_words("make && npm ru"), vec!["npm", "ru"]);
+        assert_eq!(command_words("cat log | gr"), vec!["gr"]);
+    }
+
+    #[test]
+    fn test_shell_from_program() {
+        assert_eq!(CompletionShell::from_program("/bin/zsh"), Some(CompletionShell::Zsh));
+        assert_eq!(
+            CompletionShell::from_program(r"C:\Program Files\Git\bin\bash.exe"),
+            Some(CompletionShell::Bash)
+        );
+        assert_eq!(CompletionShell::from_program("/usr/bin/fish"), None);
+        assert_eq!(CompletionShell::from_program("pwsh.exe"), None);
+    }
+
+    #[test]
+    fn test_parse_zsh_output() {
+        let output = "eval \"$SYNTHIA_ZSH_SETUP\"\r\n\x1b[?2004hgit ch\x1e\r\ncheckout\r\ncherry-pick\r\n\x1e\r\n";
+        assert_eq!(parse_zsh_output(output), vec!["checkout", "cherry-pick"]);
+        assert!(parse_zsh_output("no markers\r\n").is_empty());
+    }
+
+    #[test]
+    fn test_dedup_candidates() {
+        let (unique, truncated) = dedup_candidates(vec!["echo".into(), "ed".into(), "echo".into()]);
+        assert_eq!(unique, vec!["echo", "ed"]);
+        assert!(!truncated);
+
+        let many = (0..MAX_CANDIDATES + 1).map(|i| i.to_string()).collect();
+        let (unique, truncated) = dedup_candidates(many);
+        assert_eq!(unique.len(), MAX_CANDIDATES);
+        assert!(truncated);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bash_helper_completes_files() {
+        if std::process::Command::new("bash").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!("synthia-complete-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("alpine")).unwrap();
+        std::fs::write(dir.join("alpha.txt"), "").unwrap();
+
+        let words = command_words("cat al");
+        let found = run_helper(CompletionShell::Bash, "bash", dir.to_str(), "cat al", &words)
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        // bash-completion, when installed, leaves the directory slash to readline
+        let mut names: Vec<&str> = found.iter().map(|c| c.trim_end_matches('/')).collect();
+        names.sort();
+        assert_eq!(names, vec!["alpha.txt", "alpine"]);
+    }
+}
@@ -0,0 +1,244 @@
+//! Configuration export and import, for moving Synthia to another machine.
+//!
+//! `export_config` writes one versioned JSON bundle holding the settings
+//! (including environment profiles and redaction rules), the managed service
+//! definitions and the schedules. `import_config` reads it back: the
+//! settings replace the current ones, while services and schedules are added
+//! next to the existing ones, skipping names or ids already present and
+//! entries that do not fit this machine (e.g. a missing working directory).
+//!
+//! Keychain secrets (`secrets`) are never exported.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+use crate::scheduler::{self, Schedule, SchedulerState};
+use crate::services::{self, ServiceDef, ServicesState};
+use crate::settings::{self, Settings, SettingsState};
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Marks a file as a Synthia configuration bundle
+const BUNDLE_FORMAT: &str = "synthia-config";
+
+/// Bundle layout version written by `export_config`; older ones still import
+const BUNDLE_VERSION: u32 = 1;
+
+/// Largest bundle `import_config` reads
+const MAX_BUNDLE_BYTES: u64 = 16 * 1024 * 1024;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// The exported file. Sections missing from a (hand-edited) bundle are left
+/// untouched on import.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub format: String,
+    pub version: u32,
+    /// RFC 3339 time of the export
+    pub exported_at: String,
+    /// Synthia version that wrote the bundle
+    pub app_version: String,
+    pub settings: Option<Settings>,
+    #[serde(default)]
+    pub services: Vec<ServiceDef>,
+    #[serde(default)]
+    pub schedules: Vec<Schedule>,
+}
+
+/// Result of `export_config`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigExport {
+    pub path: String,
+    pub profiles: usize,
+    pub services: usize,
+    pub schedules: usize,
+}
+
+/// Result of `import_config`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigImport {
+    /// The bundle's settings replaced the current ones
+    pub settings: bool,
+    pub services_added: usize,
+    pub schedules_added: usize,
+    /// Entries left out, with the reason
+    pub skipped: Vec<String>,
+}
+
+// =============================================================================
+// Bundle File
+// =============================================================================
+
+/// Parse and check a bundle before anything is applied.
+fn parse_bundle(json: &str) -> Result<ConfigBundle, AppError> {
+    let bundle: ConfigBundle = serde_json::from_str(json)
+        .map_err(|e| AppError::Validation(format!("Invalid configuration bundle: {}", e)))?;
+    if bundle.format != BUNDLE_FORMAT {
+        return Err(AppError::Validation(format!(
+            "Not a Synthia configuration bundle (format: {:?})",
+            bundle.format
+        )));
+    }
+    if bundle.version == 0 || bundle.version > BUNDLE_VERSION {
+        return Err(AppError::Validation(format!(
+            "Unsupported configuration bundle version {} (this Synthia reads up to {})",
+            bundle.version, BUNDLE_VERSION
+        )));
+    }
+    if let Some(settings) = &bundle.settings {
+        settings.validate()?;
+    }
+    Ok(bundle)
+}
+
+async fn read_bundle(path: &Path) -> Result<ConfigBundle, AppError> {
+    let metadata = tokio::fs::metadata(path).await.map_err(|e| {
+        AppError::NotFound(format!("Configuration bundle not found: {}: {}", path.display(), e))
+    })?;
+    if metadata.len() > MAX_BUNDLE_BYTES {
+        return Err(AppError::Validation(format!(
+            "Configuration bundle is larger than {} MiB",
+            MAX_BUNDLE_BYTES / (1024 * 1024)
+        )));
+    }
+    let json = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to read configuration bundle: {}", e)))?;
+    parse_bundle(&json)
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Write settings, environment profiles, services and schedules to `path`
+/// as one JSON bundle, replacing the file if it exists.
+#[tauri::command]
+pub async fn export_config(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    services: State<'_, ServicesState>,
+    scheduler: State<'_, SchedulerState>,
+    path: String,
+) -> Result<ConfigExport, AppError> {
+    let bundle = ConfigBundle {
+        format: BUNDLE_FORMAT.into(),
+        version: BUNDLE_VERSION,
+        exported_at: chrono::Local::now().to_rfc3339(),
+        app_version: app.package_info().version.to_string(),
+        settings: Some(settings.get()),
+        services: services::export_defs(&services).await,
+        schedules: scheduler::export_schedules(&scheduler).await,
+    };
+    let json = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize configuration: {}", e)))?;
+    tokio::fs::write(&path, json)
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to write configuration bundle: {}", e)))?;
+
+    let export = ConfigExport {
+        path,
+        profiles: bundle.settings.as_ref().map_or(0, |s| s.terminal.profiles.len()),
+        services: bundle.services.len(),
+        schedules: bundle.schedules.len(),
+    };
+    log::info!(
+        "Exported configuration to {} ({} profile(s), {} service(s), {} schedule(s))",
+        export.path,
+        export.profiles,
+        export.services,
+        export.schedules
+    );
+    Ok(export)
+}
+
+/// Apply a bundle written by `export_config`: its settings replace the
+/// current ones, and its services and schedules are added.
+///
+/// Fails without changing anything if the file is not a valid bundle.
+#[tauri::command]
+pub async fn import_config(
+    app: AppHandle,
+    services: State<'_, ServicesState>,
+    scheduler: State<'_, SchedulerState>,
+    path: String,
+) -> Result<ConfigImport, AppError> {
+    let bundle = read_bundle(Path::new(&path)).await?;
+    let mut import = ConfigImport::default();
+
+    if let Some(new_settings) = &bundle.settings {
+        settings::replace(&app, new_settings)?;
+        import.settings = true;
+    }
+    import.services_added =
+        services::import_defs(&app, &services, bundle.services, &mut import.skipped).await?;
+    import.schedules_added =
+        scheduler::import_schedules(&app, &scheduler, bundle.schedules, &mut import.skipped)
+            .await?;
+
+    log::info!(
+        "Imported configuration from {} (settings: {}, {} service(s), {} schedule(s), {} skipped)",
+        path,
+        import.settings,
+        import.services_added,
+        import.schedules_added,
+        import.skipped.len()
+    );
+    Ok(import)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle_json(format: &str, version: u32) -> String {
+        serde_json::json!({
+            "format": format,
+            "version": version,
+            "exported_at": "2026-01-01T00:00:00+00:00",
+            "app_version": "0.1.0",
+            "settings": Settings::default(),
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_bundle() {
+        let bundle = parse_bundle(&bundle_json(BUNDLE_FORMAT, BUNDLE_VERSION)).unwrap();
+        assert_eq!(bundle.settings, Some(Settings::default()));
+        assert!(bundle.services.is_empty() && bundle.schedules.is_empty());
+    }
+
+    #[test]
+    fn test_parse_bundle_rejects_foreign_and_newer_files() {
+        assert!(parse_bundle(&bundle_json("other", 1)).is_err());
+        assert!(parse_bundle(&bundle_json(BUNDLE_FORMAT, BUNDLE_VERSION + 1)).is_err());
+        assert!(parse_bundle("{}").is_err());
+    }
+
+    #[test]
+    fn test_parse_bundle_validates_settings() {
+        let mut settings = Settings::default();
+        settings.terminal.max_sessions = 0;
+        let json = serde_json::json!({
+            "format": BUNDLE_FORMAT,
+            "version": BUNDLE_VERSION,
+            "exported_at": "",
+            "app_version": "",
+            "settings": settings,
+        })
+        .to_string();
+        assert!(parse_bundle(&json).is_err());
+    }
+}
@@ -0,0 +1,468 @@
+//! Local control interface for scripts.
+//!
+//! Listens on a unix domain socket at `~/.synthia.sock` (a named pipe,
+//! `\\.\pipe\synthia`, on Windows) for newline-delimited JSON requests and
+//! runs them with the same handlers as the Tauri commands of the same name:
+//!
+//! ```text
+//! $ echo '{"cmd":"spawn_terminal","cwd":"/tmp"}' | nc -U ~/.synthia.sock
+//! {"ok":true,"result":"0b6f6a8e-5c1d-4f43-9d53-2e7f0a4c9b11"}
+//! ```
+//!
+//! Arguments sit next to `cmd`, named as in the commands' snake_case
+//! parameters. Each request gets one response line, `{"ok":true,"result":..}`
+//! or `{"ok":false,"error":{"code":..,"message":..}}`, echoing the request's
+//! `id` if it had one. A connection can send any number of requests.
+//!
+//! The socket is only accessible to the user running the app (mode 0600,
+//! and connections from other users are refused). It is removed on exit.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::limits::ResourceLimits;
+use crate::pty::{self, TerminalSize};
+use crate::{dashboard, screen, streaming, AppError};
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Socket file name in the user's home directory
+#[cfg(unix)]
+const SOCKET_FILE_NAME: &str = ".synthia.sock";
+
+#[cfg(windows)]
+const PIPE_NAME: &str = r"\\.\pipe\synthia";
+
+/// Wait before retrying when the next pipe instance cannot be created
+#[cfg(windows)]
+const PIPE_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Longest request line accepted; longer lines are answered with an error
+const MAX_REQUEST_BYTES: u64 = 1024 * 1024;
+
+/// Path of the socket this instance listens on, for `remove_socket`
+#[cfg(unix)]
+static SOCKET_PATH: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// A request line: the command plus an optional `id` echoed in the response.
+#[derive(Debug, Deserialize)]
+struct ControlRequest {
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+    #[serde(flatten)]
+    command: ControlCommand,
+}
+
+/// The commands available over the socket, with the arguments of the Tauri
+/// command of the same name.
+#[derive(Debug, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum ControlCommand {
+    SpawnTerminal {
+        session_id: Option<String>,
+        cwd: Option<String>,
+        shell: Option<String>,
+        size: Option<TerminalSize>,
+        args: Option<Vec<String>>,
+        initial_command: Option<String>,
+        initial_commands: Option<Vec<String>>,
+        profile: Option<String>,
+        limits: Option<ResourceLimits>,
+    },
+    WriteTerminal {
+        session_id: String,
+        data: String,
+        owner: Option<String>,
+    },
+    InjectCommand {
+        session_id: String,
+        command: String,
+        owner: Option<String>,
+    },
+    KillTerminal {
+        session_id: String,
+    },
+    ListTerminals,
+    GetTranscript {
+        session_id: String,
+        max_chars: Option<usize>,
+        from_end: Option<bool>,
+    },
+    GetVisibleScreen {
+        session_id: String,
+    },
+    GetSystemStats,
+    GetStatusbarData,
+    GetStreamStatus,
+    StopLocalStream,
+}
+
+/// A response line.
+#[derive(Debug, Serialize)]
+struct ControlResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<AppError>,
+}
+
+impl ControlResponse {
+    fn new(id: Option<serde_json::Value>, result: Result<serde_json::Value, AppError>) -> Self {
+        match result {
+            Ok(result) => Self {
+                id,
+                ok: true,
+                result: Some(result),
+                error: None,
+            },
+            Err(error) => Self {
+                id,
+                ok: false,
+                result: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+// =============================================================================
+// Dispatch
+// =============================================================================
+
+fn parse_request(line: &str) -> Result<ControlRequest, AppError> {
+    serde_json::from_str(line).map_err(|e| AppError::Validation(format!("Invalid request: {}", e)))
+}
+
+fn to_value<T: Serialize>(result: Result<T, AppError>) -> Result<serde_json::Value, AppError> {
+    serde_json::to_value(result?)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize result: {}", e)))
+}
+
+/// Run `command` with the handler of the Tauri command of the same name.
+async fn dispatch(app: &AppHandle, command: ControlCommand) -> Result<serde_json::Value, AppError> {
+    match command {
+        ControlCommand::SpawnTerminal {
+            session_id,
+            cwd,
+            shell,
+            size,
+            args,
+            initial_command,
+            initial_commands,
+            profile,
+            limits,
+        } => to_value(
+            pty::spawn_terminal(
+                app.clone(),
+                app.state(),
+                app.state(),
+                session_id,
+                cwd,
+                shell,
+                size,
+                args,
+                initial_command,
+                initial_commands,
+                profile,
+                limits,
+            )
+            .await,
+        ),
+        ControlCommand::WriteTerminal {
+            session_id,
+            data,
+            owner,
+        } => to_value(
+            pty::write_terminal(app.state(), app.state(), session_id, data, owner).await,
+        ),
+        ControlCommand::InjectCommand {
+            session_id,
+            command,
+            owner,
+        } => to_value(pty::inject_command(
+            app.state(),
+            app.state(),
+            session_id,
+            command,
+            owner,
+        )),
+        ControlCommand::KillTerminal { session_id } => {
+            to_value(pty::kill_terminal(app.clone(), app.state(), session_id))
+        }
+        ControlCommand::ListTerminals => to_value(pty::list_terminals(app.state())),
+        ControlCommand::GetTranscript {
+            session_id,
+            max_chars,
+            from_end,
+        } => to_value(pty::get_transcript(app.state(), session_id, max_chars, from_end)),
+        ControlCommand::GetVisibleScreen { session_id } => {
+            to_value(screen::get_visible_screen(app.state(), session_id))
+        }
+        ControlCommand::GetSystemStats => to_value(crate::get_system_stats().await),
+        ControlCommand::GetStatusbarData => to_value(Ok(dashboard::get_statusbar_data(
+            app.state(),
+            app.state(),
+            app.state(),
+        ))),
+        ControlCommand::GetStreamStatus => {
+            to_value(streaming::get_stream_status(app.state()).await)
+        }
+        ControlCommand::StopLocalStream => {
+            to_value(streaming::stop_local_stream(app.state()).await)
+        }
+    }
+}
+
+/// Answer one request line.
+async fn handle_line(app: &AppHandle, line: &str) -> ControlResponse {
+    match parse_request(line) {
+        Ok(request) => ControlResponse::new(request.id, dispatch(app, request.command).await),
+        Err(e) => ControlResponse::new(None, Err(e)),
+    }
+}
+
+/// Answer requests on one connection until the client closes it.
+async fn serve_connection<S>(app: AppHandle, stream: S) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let read = (&mut reader).take(MAX_REQUEST_BYTES + 1).read_until(b'\n', &mut buf).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        let response = if read as u64 > MAX_REQUEST_BYTES && !buf.ends_with(b"\n") {
+            // Skip the rest of the oversized line
+            while !buf.ends_with(b"\n") {
+                buf.clear();
+                if (&mut reader).take(MAX_REQUEST_BYTES).read_until(b'\n', &mut buf).await? == 0 {
+                    break;
+                }
+            }
+            ControlResponse::new(
+                None,
+                Err(AppError::Validation(format!(
+                    "Request exceeds {} bytes",
+                    MAX_REQUEST_BYTES
+                ))),
+            )
+        } else {
+            let line = String::from_utf8_lossy(&buf);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            handle_line(&app, line).await
+        };
+        let mut out = serde_json::to_vec(&response).map_err(std::io::Error::from)?;
+        out.push(b'\n');
+        writer.write_all(&out).await?;
+        writer.flush().await?;
+    }
+}
+
+// =============================================================================
+// Listener
+// =============================================================================
+
+/// Start listening for control connections. Failures are logged; the app
+/// runs without the control interface.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = listen(app).await {
+            log::error!("Control interface unavailable: {}", e);
+        }
+    });
+}
+
+#[cfg(unix)]
+async fn listen(app: AppHandle) -> Result<(), AppError> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    use tokio::net::{UnixListener, UnixStream};
+
+    let path = app
+        .path()
+        .home_dir()
+        .map_err(|e| AppError::NotFound(format!("Home directory not found: {}", e)))?
+        .join(SOCKET_FILE_NAME);
+
+    if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+        // Only ever replace a socket, never a file the user put there
+        if !metadata.file_type().is_socket() {
+            return Err(AppError::Validation(format!("{} is not a socket", path.display())));
+        }
+        // A socket someone still listens on belongs to another instance
+        if UnixStream::connect(&path).await.is_ok() {
+            return Err(AppError::Validation(format!(
+                "{} is in use by another instance",
+                path.display()
+            )));
+        }
+        std::fs::remove_file(&path)
+            .map_err(|e| AppError::Io(format!("Failed to remove {}: {}", path.display(), e)))?;
+    }
+    let listener = UnixListener::bind(&path)
+        .map_err(|e| AppError::Io(format!("Failed to bind {}: {}", path.display(), e)))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| AppError::Io(format!("Failed to restrict {}: {}", path.display(), e)))?;
+    log::info!("Control interface listening on {}", path.display());
+    let _ = SOCKET_PATH.set(path);
+
+    let uid = nix::unistd::geteuid().as_raw();
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::warn!("Control connection failed: {}", e);
+                continue;
+            }
+        };
+        match stream.peer_cred() {
+            Ok(cred) if cred.uid() == uid => {}
+            Ok(cred) => {
+                log::warn!("Refused control connection from uid {}", cred.uid());
+                continue;
+            }
+            Err(e) => {
+                log::warn!("Refused control connection without credentials: {}", e);
+                continue;
+            }
+        }
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = serve_connection(app, stream).await {
+                log::debug!("Control connection closed: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(windows)]
+async fn listen(app: AppHandle) -> Result<(), AppError> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let create = |first: bool| {
+        ServerOptions::new()
+            .first_pipe_instance(first)
+            .create(PIPE_NAME)
+            .map_err(|e| AppError::Io(format!("Failed to create {}: {}", PIPE_NAME, e)))
+    };
+    let mut server = create(true)?;
+    log::info!("Control interface listening on {}", PIPE_NAME);
+    loop {
+        if let Err(e) = server.connect().await {
+            log::warn!("Control connection failed: {}", e);
+            continue;
+        }
+        // Each connection gets its own instance; the next client waits on a new one
+        let next = loop {
+            match create(false) {
+                Ok(next) => break next,
+                Err(e) => {
+                    log::warn!("{}; retrying", e);
+                    tokio::time::sleep(PIPE_RETRY_DELAY).await;
+                }
+            }
+        };
+        let connected = std::mem::replace(&mut server, next);
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = serve_connection(app, connected).await {
+                log::debug!("Control connection closed: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+async fn listen(_app: AppHandle) -> Result<(), AppError> {
+    Err(AppError::Unsupported(
+        "The control interface is not supported on this platform".into(),
+    ))
+}
+
+/// Remove the socket file. Called on app exit; returns whether there was one.
+#[cfg(unix)]
+pub fn remove_socket() -> bool {
+    let Some(path) = SOCKET_PATH.get() else {
+        return false;
+    };
+    match std::fs::remove_file(path) {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("Failed to remove {}: {}", path.display(), e);
+            false
+        }
+    }
+}
+
+/// Named pipes disappear with the process.
+#[cfg(not(unix))]
+pub fn remove_socket() -> bool {
+    false
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let request = parse_request(r#"{"cmd":"spawn_terminal"}"#).unwrap();
+        assert!(request.id.is_none());
+        assert!(matches!(
+            request.command,
+            ControlCommand::SpawnTerminal { session_id: None, cwd: None, .. }
+        ));
+
+        let request =
+            parse_request(r#"{"id":7,"cmd":"write_terminal","session_id":"t1","data":"ls\n"}"#)
+                .unwrap();
+        assert_eq!(request.id, Some(serde_json::json!(7)));
+        match request.command {
+            ControlCommand::WriteTerminal {
+                session_id, data, ..
+            } => assert_eq!((session_id.as_str(), data.as_str()), ("t1", "ls\n")),
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        for bad in [r#"{"cmd":"format_disk"}"#, r#"{"cmd":"kill_terminal"}"#, "ls", "{}"] {
+            assert!(matches!(parse_request(bad), Err(AppError::Validation(_))), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_response_lines() {
+        let ok = ControlResponse::new(Some(serde_json::json!("a")), Ok(serde_json::json!("t1")));
+        assert_eq!(
+            serde_json::to_string(&ok).unwrap(),
+            r#"{"id":"a","ok":true,"result":"t1"}"#
+        );
+
+        let err = ControlResponse::new(None, Err(AppError::SessionNotFound("t9".into())));
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["ok"], false);
+        assert_eq!(value["error"]["code"], "session_not_found");
+        assert!(value.get("result").is_none());
+    }
+}
@@ -0,0 +1,317 @@
+//! Crash diagnostics.
+//!
+//! A panic hook records every panic (message, location, thread and a
+//! backtrace) in synthia.log under target `panic` and in `last_panic.json`
+//! next to it. A panic in the capture thread or a PTY reader task otherwise
+//! only reaches a stderr nobody is watching; the JSON copy also survives a
+//! panic that takes the whole app down, for `get_last_panic` on the next
+//! launch. On unix the process's stderr (native libraries, webview warnings)
+//! is bridged into the log as `stderr` lines as well.
+
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// File name of the last panic report inside the app log directory
+const LAST_PANIC_FILE_NAME: &str = "last_panic.json";
+
+/// Log target of panic records
+const PANIC_TARGET: &str = "panic";
+
+/// Log target of bridged stderr lines
+#[cfg_attr(not(unix), allow(dead_code))]
+const STDERR_TARGET: &str = "stderr";
+
+/// Written by fern when a log target fails; never fed back into the log
+#[cfg_attr(not(unix), allow(dead_code))]
+const LOGGING_ERROR_PREFIX: &str = "Error performing logging";
+
+/// Most recent panic, from this launch or loaded from `last_panic.json`
+static LAST_PANIC: Mutex<Option<PanicReport>> = Mutex::new(None);
+
+/// Where the panic hook writes reports (set by `init`)
+static REPORT_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// The real stderr while it is bridged into the log
+static ORIGINAL_STDERR: OnceLock<Mutex<File>> = OnceLock::new();
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// A panic recorded by the panic hook.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PanicReport {
+    pub message: String,
+    /// `file:line:column` of the panic, if known
+    pub location: Option<String>,
+    /// Name of the panicking thread (`<unnamed>` for anonymous threads)
+    pub thread: String,
+    pub backtrace: String,
+    /// RFC 3339 timestamp of the panic
+    pub timestamp: String,
+    pub pid: u32,
+    /// True when the panic happened in an earlier launch of the app
+    #[serde(default)]
+    pub previous_launch: bool,
+}
+
+// =============================================================================
+// Panic Hook
+// =============================================================================
+
+/// Install the panic hook. Called first thing in `run()`, before the logger
+/// exists; panics before `init` are still printed to stderr.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = PanicReport {
+            message: panic_message(info.payload()),
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            thread: std::thread::current()
+                .name()
+                .unwrap_or("<unnamed>")
+                .to_string(),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            timestamp: chrono::Local::now().to_rfc3339(),
+            pid: std::process::id(),
+            previous_launch: false,
+        };
+
+        log::error!(
+            target: PANIC_TARGET,
+            "thread '{}' panicked at {}: {}\n{}",
+            report.thread,
+            report.location.as_deref().unwrap_or("<unknown>"),
+            report.message,
+            report.backtrace
+        );
+        if let Some(path) = REPORT_PATH.get() {
+            if let Err(e) = write_report(path, &report) {
+                log::error!(target: PANIC_TARGET, "Failed to write panic report: {}", e);
+            }
+        }
+
+        // Print to the real terminal; through the bridge it would be logged twice
+        match ORIGINAL_STDERR.get().and_then(|f| f.lock().ok()) {
+            Some(mut stderr) => {
+                let _ = writeln!(
+                    stderr,
+                    "thread '{}' panicked at {}:\n{}",
+                    report.thread,
+                    report.location.as_deref().unwrap_or("<unknown>"),
+                    report.message
+                );
+            }
+            None => default_hook(info),
+        }
+
+        if let Ok(mut last) = LAST_PANIC.lock() {
+            *last = Some(report);
+        }
+    }));
+}
+
+/// Load the report left by an earlier launch, start writing new reports
+/// next to synthia.log, and bridge stderr into the log.
+pub fn init(app: &AppHandle) {
+    let Some(path) = app
+        .path()
+        .app_log_dir()
+        .ok()
+        .map(|dir| dir.join(LAST_PANIC_FILE_NAME))
+    else {
+        log::error!("Could not determine panic report path");
+        return;
+    };
+
+    if let Some(mut report) = read_report(&path) {
+        log::debug!("Last recorded panic: {} at {}", report.message, report.timestamp);
+        report.previous_launch = true;
+        if let Ok(mut last) = LAST_PANIC.lock() {
+            last.get_or_insert(report);
+        }
+    }
+    let _ = REPORT_PATH.set(path);
+
+    #[cfg(unix)]
+    if let Err(e) = bridge_stderr() {
+        log::warn!("Failed to bridge stderr into the log: {}", e);
+    }
+}
+
+/// Text of a panic payload (`panic!` with a literal or a formatted message).
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string())
+}
+
+fn write_report(path: &Path, report: &PanicReport) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    std::fs::write(path, json)
+}
+
+fn read_report(path: &Path) -> Option<PanicReport> {
+    let content = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(report) => Some(report),
+        Err(e) => {
+            log::warn!("Ignoring unreadable panic report {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+// =============================================================================
+// Stderr Bridge
+// =============================================================================
+
+/// Point fd 2 at a pipe and forward each line to both the real stderr and
+/// the log. If the bridge thread stops, the real stderr is put back so
+/// writers never block on a full pipe.
+#[cfg(unix)]
+fn bridge_stderr() -> Result<(), AppError> {
+    use std::io::{BufRead, BufReader};
+    use std::os::fd::{AsRawFd, FromRawFd};
+
+    let errno = |e: nix::errno::Errno| AppError::Io(format!("stderr redirect failed: {}", e));
+
+    let original_fd = nix::unistd::dup(2).map_err(errno)?;
+    let (read, write) = nix::unistd::pipe().map_err(errno)?;
+    // SAFETY: `dup` returned a fresh descriptor that nothing else owns
+    let original = unsafe { File::from_raw_fd(original_fd) };
+    let passthrough = original
+        .try_clone()
+        .map_err(|e| AppError::Io(format!("stderr redirect failed: {}", e)))?;
+
+    std::thread::Builder::new()
+        .name("stderr-bridge".into())
+        .spawn(move || {
+            let mut filter = StderrFilter::default();
+            let mut passthrough = passthrough;
+            for line in BufReader::new(File::from(read)).split(b'\n') {
+                let Ok(line) = line else { break };
+                let _ = passthrough.write_all(&line).and_then(|_| passthrough.write_all(b"\n"));
+                let text = String::from_utf8_lossy(&line);
+                if filter.accept(&text) {
+                    log::warn!(target: STDERR_TARGET, "{}", text.trim_end());
+                }
+            }
+            let _ = nix::unistd::dup2(passthrough.as_raw_fd(), 2);
+        })
+        .map_err(|e| AppError::Io(format!("Failed to start stderr bridge: {}", e)))?;
+
+    nix::unistd::dup2(write.as_raw_fd(), 2).map_err(errno)?;
+    let _ = ORIGINAL_STDERR.set(Mutex::new(original));
+    Ok(())
+}
+
+/// Drops blank lines and fern's own logging-failure reports, which are
+/// written to stderr and would otherwise be logged, fail and loop.
+#[derive(Default)]
+#[cfg_attr(not(unix), allow(dead_code))]
+struct StderrFilter {
+    /// Inside the tab-indented continuation of a logging-failure report
+    in_logging_error: bool,
+}
+
+#[cfg_attr(not(unix), allow(dead_code))]
+impl StderrFilter {
+    fn accept(&mut self, line: &str) -> bool {
+        if line.contains(LOGGING_ERROR_PREFIX) {
+            self.in_logging_error = true;
+            return false;
+        }
+        if self.in_logging_error && line.starts_with('\t') {
+            return false;
+        }
+        self.in_logging_error = false;
+        !line.trim().is_empty()
+    }
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// The most recent panic: from this launch, or else the one recorded by an
+/// earlier launch (`previous_launch` true). Null if none was ever recorded.
+#[tauri::command]
+pub fn get_last_panic() -> Result<Option<PanicReport>, AppError> {
+    let last = LAST_PANIC
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock panic report: {}", e)))?;
+    Ok(last.clone())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_message() {
+        let literal: Box<dyn Any + Send> = Box::new("boom");
+        let formatted: Box<dyn Any + Send> = Box::new(format!("bad frame {}", 7));
+        let other: Box<dyn Any + Send> = Box::new(42u8);
+        assert_eq!(panic_message(literal.as_ref()), "boom");
+        assert_eq!(panic_message(formatted.as_ref()), "bad frame 7");
+        assert_eq!(panic_message(other.as_ref()), "Box<dyn Any>");
+    }
+
+    #[test]
+    fn test_report_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("synthia-crash-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(LAST_PANIC_FILE_NAME);
+        let report = PanicReport {
+            message: "index out of bounds".into(),
+            location: Some("src/streaming.rs:10:5".into()),
+            thread: "capture".into(),
+            backtrace: "0: main".into(),
+            timestamp: "2026-10-16T10:00:00+00:00".into(),
+            pid: 1234,
+            previous_launch: false,
+        };
+
+        assert_eq!(read_report(&path), None);
+        write_report(&path, &report).unwrap();
+        assert_eq!(read_report(&path), Some(report));
+
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(read_report(&path), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stderr_filter() {
+        let mut filter = StderrFilter::default();
+        assert!(filter.accept("Gtk-WARNING: theme parsing error"));
+        assert!(!filter.accept("   "));
+        assert!(!filter.accept("Error performing logging."));
+        assert!(!filter.accept("\tattempted to log: hello"));
+        assert!(!filter.accept("\tlogging error: disk full"));
+        assert!(filter.accept("libEGL warning: DRI2: failed to authenticate"));
+    }
+}
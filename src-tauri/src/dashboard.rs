@@ -0,0 +1,103 @@
+//! Dashboard snapshot.
+//!
+//! The Command Center refreshes system stats, stream status, terminals and
+//! recent warnings every couple of seconds. `get_dashboard_snapshot` returns
+//! all of them in one IPC call, reading state the backend already keeps:
+//! the metrics sampler's latest reading, the stream session, the PTY table
+//! and the in-memory alert ring in `logging`.
+//!
+//! `get_statusbar_data` is the cut-down version for the always-visible
+//! status bar, polled more often: a handful of numbers read from cached
+//! state only, without measuring or waiting on any lock held for long.
+
+use serde::Serialize;
+use sysinfo::System;
+use tauri::State;
+
+use crate::logging::{self, LogEntry};
+use crate::metrics::MetricsState;
+use crate::pty::{self, PtyState, TerminalInfo};
+use crate::streaming::{self, StreamStatus, StreamingState};
+use crate::{AppError, SystemStats};
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Everything the dashboard shows, returned by `get_dashboard_snapshot`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardSnapshot {
+    pub stats: SystemStats,
+    pub stream: StreamStatus,
+    pub terminals: Vec<TerminalInfo>,
+    /// Latest warnings and errors logged since startup, newest first
+    pub alerts: Vec<LogEntry>,
+    /// RFC 3339 timestamp of the snapshot
+    pub taken_at: String,
+}
+
+/// The numbers in the status bar, returned by `get_statusbar_data`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatusBarData {
+    /// CPU and memory usage percent from the sampler's latest reading; None
+    /// before its first sample
+    pub cpu: Option<f32>,
+    pub mem: Option<f32>,
+    /// A stream is running; None while one is starting or stopping
+    pub streaming: Option<bool>,
+    pub sessions: usize,
+    /// Errors logged since they were last marked read (`mark_errors_read`)
+    pub unread_errors: u64,
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// System stats, stream status, terminal list and recent alerts in one call.
+///
+/// Stats come from the metrics sampler (CPU measured over the last second);
+/// before its first sample they are measured on the spot like
+/// `get_system_stats`.
+#[tauri::command]
+pub async fn get_dashboard_snapshot(
+    metrics: State<'_, MetricsState>,
+    streaming: State<'_, StreamingState>,
+    pty: State<'_, PtyState>,
+) -> Result<DashboardSnapshot, AppError> {
+    let stats = match metrics.latest_stats() {
+        Some(stats) => stats,
+        None => tokio::task::spawn_blocking(|| crate::collect_system_stats(&mut System::new()))
+            .await
+            .map_err(|e| AppError::Internal(format!("System stats task failed: {}", e)))?,
+    };
+
+    Ok(DashboardSnapshot {
+        stats,
+        stream: streaming::stream_status(streaming.inner()).await,
+        terminals: pty::terminal_infos(pty.inner()),
+        alerts: logging::recent_alerts(),
+        taken_at: chrono::Local::now().to_rfc3339(),
+    })
+}
+
+/// CPU, memory, stream state, session count and unread errors for the
+/// status bar.
+///
+/// Unlike `get_dashboard_snapshot` this never measures on the spot: before
+/// the sampler's first reading `cpu` and `mem` are None.
+#[tauri::command]
+pub fn get_statusbar_data(
+    metrics: State<'_, MetricsState>,
+    streaming: State<'_, StreamingState>,
+    pty: State<'_, PtyState>,
+) -> StatusBarData {
+    let stats = metrics.latest_stats();
+    StatusBarData {
+        cpu: stats.as_ref().map(|s| s.cpu),
+        mem: stats.as_ref().map(|s| s.mem),
+        streaming: streaming::stream_active(streaming.inner()),
+        sessions: pty.sessions().len(),
+        unread_errors: logging::unread_error_count(),
+    }
+}
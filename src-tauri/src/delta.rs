@@ -0,0 +1,299 @@
+//! Dirty-rectangle (tile) encoding for the screen stream.
+//!
+//! Desktop content is mostly static, so instead of sending every BGRA frame
+//! in full, the capture thread compares each frame to the previous one in
+//! fixed-size tiles and sends only the tiles that changed. Full keyframes are
+//! still sent periodically, on resolution changes, and whenever most of the
+//! screen changed (video, window drags) since a delta would not be smaller.
+//!
+//! Delta message layout (little-endian):
+//! `[MSG_TYPE_VIDEO_DELTA][u32 seq][u64 capture µs][u16 width][u16 height][u16 tile count]`
+//! followed by each tile as `[u16 x][u16 y][u16 w][u16 h][pixels]`, with the
+//! pixels in the client's `PixelFormat`.
+
+use crate::pixel_format::{self, PixelFormat};
+use crate::streaming::{FrameStamp, MSG_TYPE_VIDEO, MSG_TYPE_VIDEO_DELTA, VIDEO_STAMP_LEN};
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Tile edge in pixels. Small enough to isolate a blinking cursor, large
+/// enough that 4K stays around 2k tiles.
+const TILE_SIZE: usize = 64;
+
+/// Send a keyframe instead of a delta when more than this fraction of tiles changed
+const MAX_DELTA_CHANGED_RATIO: f64 = 0.5;
+
+// =============================================================================
+// Tile Diffing
+// =============================================================================
+
+/// A rectangular region of the frame, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+}
+
+/// Split a frame into tiles (edge tiles are clipped to the frame).
+fn tiles(width: usize, height: usize, tile: usize) -> impl Iterator<Item = Tile> {
+    (0..height).step_by(tile).flat_map(move |y| {
+        (0..width).step_by(tile).map(move |x| Tile {
+            x,
+            y,
+            w: tile.min(width - x),
+            h: tile.min(height - y),
+        })
+    })
+}
+
+/// Tiles whose pixels differ between two BGRA frames of the same size.
+fn changed_tiles(prev: &[u8], cur: &[u8], width: usize, height: usize, tile: usize) -> Vec<Tile> {
+    tiles(width, height, tile)
+        .filter(|t| {
+            (t.y..t.y + t.h).any(|row| {
+                let start = (row * width + t.x) * 4;
+                let end = start + t.w * 4;
+                prev[start..end] != cur[start..end]
+            })
+        })
+        .collect()
+}
+
+/// Build a delta message carrying the given tiles of a BGRA `frame`,
+/// converted to `format`. Tiles are clipped to the format's frame size.
+pub fn encode_delta(
+    frame: &[u8],
+    width: usize,
+    height: usize,
+    changed: &[Tile],
+    stamp: FrameStamp,
+    format: PixelFormat,
+) -> Vec<u8> {
+    let (out_w, out_h) = format.frame_size(width, height);
+    let clipped: Vec<Tile> = changed
+        .iter()
+        .filter(|t| t.x < out_w && t.y < out_h)
+        .map(|t| Tile {
+            w: t.w.min(out_w - t.x),
+            h: t.h.min(out_h - t.y),
+            ..*t
+        })
+        .collect();
+    let pixel_bytes: usize = clipped.iter().map(|t| format.region_len(t.w, t.h)).sum();
+    let mut buf = Vec::with_capacity(VIDEO_STAMP_LEN + 6 + clipped.len() * 8 + pixel_bytes);
+
+    stamp.write_header(MSG_TYPE_VIDEO_DELTA, &mut buf);
+    buf.extend_from_slice(&(out_w as u16).to_le_bytes());
+    buf.extend_from_slice(&(out_h as u16).to_le_bytes());
+    buf.extend_from_slice(&(clipped.len() as u16).to_le_bytes());
+
+    for t in &clipped {
+        for v in [t.x, t.y, t.w, t.h] {
+            buf.extend_from_slice(&(v as u16).to_le_bytes());
+        }
+        pixel_format::append_region(frame, width, (t.x, t.y, t.w, t.h), format, &mut buf);
+    }
+
+    buf
+}
+
+/// Build a full-frame video message from a BGRA `frame`, converted to `format`.
+pub fn encode_keyframe(
+    frame: &[u8],
+    width: usize,
+    height: usize,
+    stamp: FrameStamp,
+    format: PixelFormat,
+) -> Vec<u8> {
+    let (out_w, out_h) = format.frame_size(width, height);
+    // Built directly into the buffer handed to `Bytes` so the frame is
+    // copied once (a 4K BGRA frame is ~33 MB)
+    let mut buf = Vec::with_capacity(VIDEO_STAMP_LEN + 4 + format.region_len(out_w, out_h));
+    stamp.write_header(MSG_TYPE_VIDEO, &mut buf);
+    buf.extend_from_slice(&(out_w as u16).to_le_bytes());
+    buf.extend_from_slice(&(out_h as u16).to_le_bytes());
+    pixel_format::append_region(frame, width, (0, 0, out_w, out_h), format, &mut buf);
+    buf
+}
+
+// =============================================================================
+// Encoder
+// =============================================================================
+
+/// What the capture thread should publish for a frame.
+#[derive(Debug, PartialEq)]
+pub enum Encoded {
+    /// Nothing changed since the previous frame; publish nothing
+    Unchanged,
+    /// Publish the full frame only
+    Keyframe,
+    /// Publish the full frame plus a delta of these tiles for up-to-date clients
+    Delta(Vec<Tile>),
+}
+
+/// Per-stream diffing state, owned by the capture thread.
+pub struct DeltaEncoder {
+    prev: Vec<u8>,
+    width: usize,
+    height: usize,
+    frames_since_keyframe: u32,
+    keyframe_interval: u32,
+}
+
+impl DeltaEncoder {
+    /// `keyframe_interval` is the maximum number of frames between keyframes.
+    pub fn new(keyframe_interval: u32) -> Self {
+        Self {
+            prev: Vec::new(),
+            width: 0,
+            height: 0,
+            frames_since_keyframe: 0,
+            keyframe_interval: keyframe_interval.max(1),
+        }
+    }
+
+    /// Diff a BGRA frame (exactly `width * height * 4` bytes) against the
+    /// previous one and remember it for the next call.
+    pub fn encode(&mut self, frame: &[u8], width: usize, height: usize) -> Encoded {
+        let same_size = width == self.width && height == self.height;
+        let due = self.frames_since_keyframe + 1 >= self.keyframe_interval;
+
+        let result = if !same_size || due {
+            Encoded::Keyframe
+        } else {
+            let changed = changed_tiles(&self.prev, frame, width, height, TILE_SIZE);
+            let total = tiles(width, height, TILE_SIZE).count();
+            if changed.is_empty() {
+                return Encoded::Unchanged;
+            } else if changed.len() as f64 > total as f64 * MAX_DELTA_CHANGED_RATIO {
+                Encoded::Keyframe
+            } else {
+                Encoded::Delta(changed)
+            }
+        };
+
+        match result {
+            Encoded::Keyframe => self.frames_since_keyframe = 0,
+            _ => self.frames_since_keyframe += 1,
+        }
+        self.prev.clear();
+        self.prev.extend_from_slice(frame);
+        self.width = width;
+        self.height = height;
+        result
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAMP: FrameStamp = FrameStamp { seq: 0, capture_us: 0 };
+
+    fn frame(width: usize, height: usize) -> Vec<u8> {
+        vec![0u8; width * height * 4]
+    }
+
+    fn set_pixel(frame: &mut [u8], width: usize, x: usize, y: usize) {
+        frame[(y * width + x) * 4] = 0xFF;
+    }
+
+    #[test]
+    fn test_tiles_clip_at_edges() {
+        let all: Vec<Tile> = tiles(100, 70, 64).collect();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[3], Tile { x: 64, y: 64, w: 36, h: 6 });
+    }
+
+    #[test]
+    fn test_changed_tiles_finds_only_dirty_tile() {
+        let prev = frame(128, 128);
+        let mut cur = prev.clone();
+        set_pixel(&mut cur, 128, 100, 10);
+        assert_eq!(
+            changed_tiles(&prev, &cur, 128, 128, 64),
+            vec![Tile { x: 64, y: 0, w: 64, h: 64 }]
+        );
+    }
+
+    #[test]
+    fn test_encode_delta_layout() {
+        let mut cur = frame(4, 2);
+        set_pixel(&mut cur, 4, 3, 1);
+        let stamp = FrameStamp { seq: 2, capture_us: 0x0102 };
+        let tile = Tile { x: 3, y: 1, w: 1, h: 1 };
+        let msg = encode_delta(&cur, 4, 2, &[tile], stamp, PixelFormat::Bgra);
+        assert_eq!(
+            msg,
+            vec![
+                MSG_TYPE_VIDEO_DELTA, 2, 0, 0, 0, 0x02, 0x01, 0, 0, 0, 0, 0, 0,
+                4, 0, 2, 0, 1, 0, 3, 0, 1, 0, 1, 0, 1, 0, 0xFF, 0, 0, 0
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encoder_sequence() {
+        let mut enc = DeltaEncoder::new(100);
+        let base = frame(256, 256);
+        assert_eq!(enc.encode(&base, 256, 256), Encoded::Keyframe);
+        assert_eq!(enc.encode(&base, 256, 256), Encoded::Unchanged);
+
+        let mut small_change = base.clone();
+        set_pixel(&mut small_change, 256, 5, 5);
+        assert!(matches!(enc.encode(&small_change, 256, 256), Encoded::Delta(_)));
+
+        // Most of the screen changed: a keyframe is cheaper
+        let full_change = vec![1u8; base.len()];
+        assert_eq!(enc.encode(&full_change, 256, 256), Encoded::Keyframe);
+
+        // Resolution change always forces a keyframe
+        assert_eq!(enc.encode(&frame(128, 128), 128, 128), Encoded::Keyframe);
+    }
+
+    #[test]
+    fn test_encoder_periodic_keyframe() {
+        let mut enc = DeltaEncoder::new(3);
+        let mut f = frame(128, 128);
+        assert_eq!(enc.encode(&f, 128, 128), Encoded::Keyframe);
+        set_pixel(&mut f, 128, 0, 0);
+        assert!(matches!(enc.encode(&f, 128, 128), Encoded::Delta(_)));
+        set_pixel(&mut f, 128, 1, 0);
+        assert!(matches!(enc.encode(&f, 128, 128), Encoded::Delta(_)));
+        set_pixel(&mut f, 128, 2, 0);
+        assert_eq!(enc.encode(&f, 128, 128), Encoded::Keyframe);
+    }
+
+    #[test]
+    fn test_encode_keyframe_formats() {
+        let f = frame(3, 3);
+        let bgra = encode_keyframe(&f, 3, 3, STAMP, PixelFormat::Bgra);
+        assert_eq!(bgra[0], MSG_TYPE_VIDEO);
+        assert_eq!(bgra.len(), VIDEO_STAMP_LEN + 4 + 36);
+
+        // YUV420 drops the odd row and column: 2x2 luma + 2 chroma samples
+        let yuv = encode_keyframe(&f, 3, 3, STAMP, PixelFormat::Yuv420);
+        assert_eq!(&yuv[VIDEO_STAMP_LEN..VIDEO_STAMP_LEN + 4], &[2, 0, 2, 0]);
+        assert_eq!(yuv.len(), VIDEO_STAMP_LEN + 4 + 6);
+    }
+
+    #[test]
+    fn test_encode_delta_clips_tiles_for_yuv420() {
+        let f = frame(129, 64);
+        let tiles = [Tile { x: 0, y: 0, w: 64, h: 64 }, Tile { x: 128, y: 0, w: 1, h: 64 }];
+        let msg = encode_delta(&f, 129, 64, &tiles, STAMP, PixelFormat::Yuv420);
+
+        let header = &msg[VIDEO_STAMP_LEN..VIDEO_STAMP_LEN + 6];
+        // 128x64 frame, and the 1px column past the even width is gone
+        assert_eq!(header, &[128, 0, 64, 0, 1, 0]);
+        assert_eq!(msg.len(), VIDEO_STAMP_LEN + 6 + 8 + 64 * 64 * 3 / 2);
+    }
+}
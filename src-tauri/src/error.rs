@@ -0,0 +1,135 @@
+//! Shared error type for all Tauri commands.
+//!
+//! Errors are serialized as `{ code, message }` so the frontend can branch on
+//! `code` (e.g. `"session_not_found"`, `"port_in_use"`) instead of matching
+//! on message strings.
+
+use serde::ser::SerializeStruct;
+use thiserror::Error;
+
+/// Application-level errors that can be returned from commands
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("Validation error: {0}")]
+    Validation(String),
+
+    #[error("Session not found: {0}")]
+    SessionNotFound(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("Port {0} is already in use")]
+    PortInUse(u16),
+
+    #[error("Session limit reached: at most {0} terminal sessions may be open")]
+    SessionLimitReached(usize),
+
+    #[error("Session is locked by {0}")]
+    SessionLocked(String),
+
+    /// Carries the milliseconds until the request would be accepted
+    #[error("Rate limit exceeded, retry in {0} ms")]
+    RateLimited(u64),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    /// The feature is not available on this platform
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+
+    #[error("I/O error: {0}")]
+    Io(String),
+
+    #[error("Capture error: {0}")]
+    Capture(String),
+
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl AppError {
+    /// Stable, machine-readable error code for the frontend.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Validation(_) => "validation",
+            AppError::SessionNotFound(_) => "session_not_found",
+            AppError::NotFound(_) => "not_found",
+            AppError::PermissionDenied(_) => "permission_denied",
+            AppError::PortInUse(_) => "port_in_use",
+            AppError::SessionLimitReached(_) => "session_limit_reached",
+            AppError::SessionLocked(_) => "session_locked",
+            AppError::RateLimited(_) => "rate_limited",
+            AppError::Timeout(_) => "timeout",
+            AppError::Unsupported(_) => "unsupported",
+            AppError::Io(_) => "io",
+            AppError::Capture(_) => "capture",
+            AppError::Internal(_) => "internal",
+        }
+    }
+}
+
+// Implement serialization for frontend consumption
+impl serde::Serialize for AppError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let retry_after_ms = match self {
+            AppError::RateLimited(ms) => Some(*ms),
+            _ => None,
+        };
+        let fields = if retry_after_ms.is_some() { 3 } else { 2 };
+        let mut state = serializer.serialize_struct("AppError", fields)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(ms) = retry_after_ms {
+            state.serialize_field("retry_after_ms", &ms)?;
+        }
+        state.end()
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_code_and_message() {
+        let err = AppError::SessionNotFound("abc".into());
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["code"], "session_not_found");
+        assert_eq!(json["message"], "Session not found: abc");
+    }
+
+    #[test]
+    fn test_port_in_use_message() {
+        let json = serde_json::to_value(AppError::PortInUse(9100)).unwrap();
+        assert_eq!(json["code"], "port_in_use");
+        assert_eq!(json["message"], "Port 9100 is already in use");
+    }
+
+    #[test]
+    fn test_rate_limited_carries_retry_after() {
+        let json = serde_json::to_value(AppError::RateLimited(250)).unwrap();
+        assert_eq!(json["code"], "rate_limited");
+        assert_eq!(json["retry_after_ms"], 250);
+        let json = serde_json::to_value(AppError::Timeout("x".into())).unwrap();
+        assert!(json.get("retry_after_ms").is_none());
+    }
+
+    #[test]
+    fn test_unsupported_code() {
+        let json = serde_json::to_value(AppError::Unsupported("x".into())).unwrap();
+        assert_eq!(json["code"], "unsupported");
+        assert_eq!(json["message"], "Unsupported: x");
+    }
+}
@@ -0,0 +1,322 @@
+//! Versioned envelope and schema for events emitted to the frontend.
+//!
+//! Every event sent through `emit` is wrapped as
+//! `{ v, type, payload }`: `v` is `EVENT_SCHEMA_VERSION`, `type` the event's
+//! schema name (per-session events like `pty-output-{session_id}` keep the
+//! placeholder) and `payload` the event data. `get_event_schema` lists every
+//! event with the name of its payload type in `src/types/tauri.ts`, so the
+//! frontend and external consumers can detect a schema they don't know
+//! instead of misreading payloads.
+//!
+//! Bump `EVENT_SCHEMA_VERSION` when an existing payload changes shape;
+//! adding an event or an optional field does not need a bump.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::metrics;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Version carried by every event envelope
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// Placeholder for the session id in per-session event names
+const SESSION_PLACEHOLDER: &str = "{session_id}";
+
+/// Every event the backend emits. `payload` names the TypeScript type in
+/// `src/types/tauri.ts`; `null` means the event carries no data.
+const EVENTS: &[EventSpec] = &[
+    EventSpec {
+        name: "pty-output-{session_id}",
+        payload: "PtyOutput",
+        description: "Output chunk of a terminal session or replay",
+    },
+    EventSpec {
+        name: "pty-close-{session_id}",
+        payload: "null",
+        description: "A terminal session or replay ended",
+    },
+    EventSpec {
+        name: "terminal-exited",
+        payload: "TerminalExit",
+        description: "A session's shell exited",
+    },
+    EventSpec {
+        name: "terminal-idle-warning",
+        payload: "TerminalIdle",
+        description: "An idle session will be killed soon",
+    },
+    EventSpec {
+        name: "terminal-idle-killed",
+        payload: "TerminalIdle",
+        description: "An idle session was killed",
+    },
+    EventSpec {
+        name: "terminal-foreground-changed",
+        payload: "ForegroundChanged",
+        description: "The foreground process of a session changed",
+    },
+    EventSpec {
+        name: "terminal-bell",
+        payload: "TerminalBell",
+        description: "A session rang the bell",
+    },
+    EventSpec {
+        name: "terminal-activity",
+        payload: "TerminalActivity",
+        description: "A session printed output",
+    },
+    EventSpec {
+        name: "terminal-output-captured",
+        payload: "TerminalOutput",
+        description: "Redacted output of a session, emitted only while it has a \
+                      subscribe_terminal subscription",
+    },
+    EventSpec {
+        name: "command-finished",
+        payload: "CommandFinished",
+        description: "A shell command finished (shell integration)",
+    },
+    EventSpec {
+        name: "secure-input-requested",
+        payload: "SecureInputRequested",
+        description: "A session is waiting for a password",
+    },
+    EventSpec {
+        name: "tray-new-terminal",
+        payload: "null",
+        description: "New Terminal was chosen in the tray menu",
+    },
+    EventSpec {
+        name: "permission-pending",
+        payload: "StreamStatus",
+        description: "A stream start waits for screen capture permission",
+    },
+    EventSpec {
+        name: "stream-started",
+        payload: "StreamStatus",
+        description: "A stream deferred for permission started",
+    },
+    EventSpec {
+        name: "stream-start-failed",
+        payload: "AppError",
+        description: "A stream deferred for permission failed to start",
+    },
+    EventSpec {
+        name: "stream-stopped",
+        payload: "null",
+        description: "The stream was stopped from the tray menu",
+    },
+    EventSpec {
+        name: "stream-auto-stopped",
+        payload: "StreamAutoStopped",
+        description: "The stream stopped itself after having no viewers",
+    },
+    EventSpec {
+        name: "stream-degraded",
+        payload: "StreamDegraded",
+        description: "Frame capture failed and the capturer is being rebuilt",
+    },
+    EventSpec {
+        name: "stream-recovered",
+        payload: "StreamRecovered",
+        description: "Frames flow again after stream-degraded",
+    },
+    EventSpec {
+        name: "camera-stream-failed",
+        payload: "AppError",
+        description: "Camera frames kept failing and camera capture stopped",
+    },
+    EventSpec {
+        name: "stream-control-requested",
+        payload: "StreamControlRequest",
+        description: "A viewer asked for remote control",
+    },
+    EventSpec {
+        name: "service-log",
+        payload: "ServiceLogEvent",
+        description: "Output line of a managed service",
+    },
+    EventSpec {
+        name: "service-status-changed",
+        payload: "ServiceStatus",
+        description: "A managed service changed state",
+    },
+    EventSpec {
+        name: "settings-changed",
+        payload: "Settings",
+        description: "Settings were saved, imported or edited on disk",
+    },
+    EventSpec {
+        name: "terminal-appearance-changed",
+        payload: "TerminalAppearance",
+        description: "The terminal theme, font or cursor changed",
+    },
+    EventSpec {
+        name: "power-mode-changed",
+        payload: "PowerStatus",
+        description: "Low-power mode was entered or left",
+    },
+    EventSpec {
+        name: "timelapse-frame-captured",
+        payload: "TimelapseFrame",
+        description: "A timelapse frame was written",
+    },
+    EventSpec {
+        name: "fs-changed",
+        payload: "FsChangedEvent",
+        description: "Debounced changes under a path watched with watch_path",
+    },
+    EventSpec {
+        name: "tunnel-closed",
+        payload: "TunnelClosed",
+        description: "A port forward was closed or its ssh exited",
+    },
+];
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Wire format of every emitted event.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventEnvelope<'a, S> {
+    pub v: u32,
+    #[serde(rename = "type")]
+    pub event_type: &'a str,
+    pub payload: S,
+}
+
+/// One event in `get_event_schema`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EventSpec {
+    /// Event name; `{session_id}` stands for the session's id
+    pub name: &'static str,
+    /// Payload type name in `src/types/tauri.ts`, or `null`
+    pub payload: &'static str,
+    pub description: &'static str,
+}
+
+/// Result of `get_event_schema`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventSchema {
+    pub version: u32,
+    pub events: Vec<EventSpec>,
+}
+
+// =============================================================================
+// Envelope
+// =============================================================================
+
+/// Schema name of an emitted event: the name itself, or its
+/// `{session_id}` pattern for per-session events.
+fn schema_name(event: &str) -> Option<&'static str> {
+    EVENTS.iter().map(|spec| spec.name).find(|name| {
+        match name.strip_suffix(SESSION_PLACEHOLDER) {
+            Some(prefix) => event.len() > prefix.len() && event.starts_with(prefix),
+            None => *name == event,
+        }
+    })
+}
+
+/// Wrap `payload` for emitting as `event`.
+pub(crate) fn envelope<S>(event: &str, payload: S) -> EventEnvelope<'_, S> {
+    let event_type = schema_name(event).unwrap_or_else(|| {
+        log::warn!("Event {:?} is missing from the event schema", event);
+        event
+    });
+    EventEnvelope {
+        v: EVENT_SCHEMA_VERSION,
+        event_type,
+        payload,
+    }
+}
+
+/// Emit an event to the frontend in the versioned envelope, counting it for
+/// `get_app_resource_usage`. All backend events go through here.
+pub(crate) fn emit<S: Serialize + Clone>(
+    app: &AppHandle,
+    event: &str,
+    payload: S,
+) -> tauri::Result<()> {
+    metrics::count_event();
+    app.emit(event, envelope(event, payload))
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// List every event the backend emits with its payload type, and the
+/// envelope version.
+#[tauri::command]
+pub fn get_event_schema() -> EventSchema {
+    EventSchema {
+        version: EVENT_SCHEMA_VERSION,
+        events: EVENTS.to_vec(),
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_schema_names() {
+        assert_eq!(schema_name("settings-changed"), Some("settings-changed"));
+        assert_eq!(schema_name("pty-output-abc"), Some("pty-output-{session_id}"));
+        assert_eq!(schema_name("pty-close-1"), Some("pty-close-{session_id}"));
+        assert_eq!(schema_name("pty-output-"), None);
+        assert_eq!(schema_name("settings-changed-x"), None);
+
+        let names: HashSet<_> = EVENTS.iter().map(|e| e.name).collect();
+        assert_eq!(names.len(), EVENTS.len());
+    }
+
+    #[test]
+    fn test_envelope_shape() {
+        let json = serde_json::to_value(envelope("pty-close-s1", ())).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "v": EVENT_SCHEMA_VERSION,
+                "type": "pty-close-{session_id}",
+                "payload": null,
+            })
+        );
+    }
+
+    /// Every `events::emit` call in the crate names an event in the schema.
+    #[test]
+    fn test_schema_lists_emitted_events() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let source = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            for call in source.split("events::emit(").skip(1) {
+                // The name is the second argument: a literal, format!("...-{}", id)
+                // or a variable holding one of those (skipped)
+                let Some(arg) = call.split(',').nth(1) else {
+                    continue;
+                };
+                let arg = arg.trim().trim_start_matches("&format!(");
+                let Some(literal) = arg.strip_prefix('"') else {
+                    continue;
+                };
+                let name = literal.split('"').next().unwrap().replace("{}", "x");
+                assert!(
+                    schema_name(&name).is_some(),
+                    "event {:?} is emitted but missing from the event schema",
+                    name
+                );
+            }
+        }
+    }
+}
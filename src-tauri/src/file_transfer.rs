@@ -0,0 +1,232 @@
+//! Moving files to and from the directory a terminal session is working in.
+//!
+//! `upload_to_session_cwd` copies a local file into the session shell's
+//! current directory; `download_from_session` copies a file from the
+//! session (relative paths resolve against that directory) into the user's
+//! Downloads folder. Neither overwrites anything: if the name is taken, the
+//! copy gets a numbered name like `notes (1).txt`.
+//!
+//! How the file moves depends on where the session's shell runs
+//! (`SessionHost`). Every session is local today, so files are copied
+//! directly; a remote (SSH) session would move them with scp instead.
+
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use sysinfo::System;
+use tauri::{AppHandle, Manager, State};
+
+use crate::agent_context;
+use crate::pty::{self, PtyState};
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Numbered names tried before giving up on finding a free one
+const MAX_NAME_ATTEMPTS: u32 = 1000;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Result of `upload_to_session_cwd` and `download_from_session`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileTransfer {
+    pub session_id: String,
+    /// File that was copied
+    pub source: String,
+    /// Where the copy was written (may have a numbered name)
+    pub destination: String,
+    pub bytes: u64,
+}
+
+/// Where a session's shell runs, which decides how files are moved.
+enum SessionHost {
+    /// On this machine, in `cwd`: files are copied directly
+    Local { cwd: PathBuf },
+}
+
+// =============================================================================
+// Transfer
+// =============================================================================
+
+async fn session_host(state: &PtyState, session_id: &str) -> Result<SessionHost, AppError> {
+    let (_, shell_pid) = pty::session_shell(state, session_id)?;
+    let cwd = match shell_pid {
+        Some(pid) => tokio::task::spawn_blocking(move || {
+            agent_context::process_cwd(&mut System::new(), pid)
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Working directory lookup failed: {}", e)))?,
+        None => None,
+    };
+    let cwd = cwd.ok_or_else(|| {
+        AppError::Io(format!("Working directory of session {} is unknown", session_id))
+    })?;
+    Ok(SessionHost::Local {
+        cwd: PathBuf::from(cwd),
+    })
+}
+
+/// `name` with ` (n)` before its extension: `notes.txt` -> `notes (2).txt`.
+fn numbered_name(name: &str, n: u32) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{} ({}).{}", stem, n, ext),
+        _ => format!("{} ({})", name, n),
+    }
+}
+
+/// Create a new file in `dir` named `name`, or the first free numbered
+/// variant of it. `create_new` so a file appearing meanwhile is never
+/// overwritten.
+fn create_free_file(dir: &Path, name: &str) -> Result<(File, PathBuf), AppError> {
+    let numbered = (1..MAX_NAME_ATTEMPTS).map(|n| numbered_name(name, n));
+    let candidates = std::iter::once(name.to_string()).chain(numbered);
+    for candidate in candidates {
+        let path = dir.join(&candidate);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((file, path)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(AppError::Io(format!("Failed to create {}: {}", path.display(), e)))
+            }
+        }
+    }
+    Err(AppError::Io(format!("No free file name for {} in {}", name, dir.display())))
+}
+
+/// Copy the regular file `source` into `dir`, keeping its name and
+/// permissions. Returns the destination and the bytes copied.
+fn copy_into(source: &Path, dir: &Path) -> Result<(PathBuf, u64), AppError> {
+    let metadata = std::fs::metadata(source)
+        .map_err(|e| AppError::NotFound(format!("File not found: {}: {}", source.display(), e)))?;
+    if !metadata.is_file() {
+        return Err(AppError::Validation(format!(
+            "Only regular files can be transferred: {}",
+            source.display()
+        )));
+    }
+    let name = source
+        .file_name()
+        .ok_or_else(|| AppError::Validation(format!("Not a file path: {}", source.display())))?
+        .to_string_lossy()
+        .into_owned();
+    let mut input = File::open(source)
+        .map_err(|e| AppError::Io(format!("Failed to open {}: {}", source.display(), e)))?;
+    let (mut output, destination) = create_free_file(dir, &name)?;
+    let copied = std::io::copy(&mut input, &mut output).and_then(|bytes| {
+        output.set_permissions(metadata.permissions())?;
+        Ok(bytes)
+    });
+    match copied {
+        Ok(bytes) => Ok((destination, bytes)),
+        Err(e) => {
+            // Don't leave a partial copy behind
+            let _ = std::fs::remove_file(&destination);
+            Err(AppError::Io(format!("Failed to copy {}: {}", source.display(), e)))
+        }
+    }
+}
+
+async fn transfer(
+    session_id: String,
+    source: PathBuf,
+    dir: PathBuf,
+) -> Result<FileTransfer, AppError> {
+    let from = source.clone();
+    let (destination, bytes) = tokio::task::spawn_blocking(move || copy_into(&from, &dir))
+        .await
+        .map_err(|e| AppError::Internal(format!("File transfer task failed: {}", e)))??;
+    log::info!(
+        "Transferred {} ({} bytes) to {} for session {}",
+        source.display(),
+        bytes,
+        destination.display(),
+        session_id
+    );
+    Ok(FileTransfer {
+        session_id,
+        source: source.to_string_lossy().into_owned(),
+        destination: destination.to_string_lossy().into_owned(),
+        bytes,
+    })
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Copy the local file `local_path` into the current directory of the
+/// session's shell. An existing file of the same name is kept and the copy
+/// gets a numbered name.
+#[tauri::command]
+pub async fn upload_to_session_cwd(
+    state: State<'_, PtyState>,
+    session_id: String,
+    local_path: String,
+) -> Result<FileTransfer, AppError> {
+    let host = session_host(&state, &session_id).await?;
+    match host {
+        SessionHost::Local { cwd } => transfer(session_id, PathBuf::from(local_path), cwd).await,
+    }
+}
+
+/// Copy `remote_path` from the session (relative to its shell's current
+/// directory unless absolute) into the user's Downloads folder. An existing
+/// file of the same name is kept and the copy gets a numbered name.
+#[tauri::command]
+pub async fn download_from_session(
+    app: AppHandle,
+    state: State<'_, PtyState>,
+    session_id: String,
+    remote_path: String,
+) -> Result<FileTransfer, AppError> {
+    let downloads = app
+        .path()
+        .download_dir()
+        .map_err(|e| AppError::NotFound(format!("Downloads folder not found: {}", e)))?;
+    let host = session_host(&state, &session_id).await?;
+    match host {
+        SessionHost::Local { cwd } => transfer(session_id, cwd.join(remote_path), downloads).await,
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbered_name() {
+        assert_eq!(numbered_name("notes.txt", 1), "notes (1).txt");
+        assert_eq!(numbered_name("archive.tar.gz", 2), "archive.tar (2).gz");
+        assert_eq!(numbered_name("Makefile", 3), "Makefile (3)");
+        assert_eq!(numbered_name(".env", 1), ".env (1)");
+    }
+
+    #[test]
+    fn test_copy_into_keeps_existing_files() {
+        let root = std::env::temp_dir().join(format!("synthia-transfer-{}", uuid::Uuid::new_v4()));
+        let dest = root.join("dest");
+        std::fs::create_dir_all(&dest).unwrap();
+        let source = root.join("notes.txt");
+        std::fs::write(&source, "new").unwrap();
+        std::fs::write(dest.join("notes.txt"), "old").unwrap();
+
+        let (copied, bytes) = copy_into(&source, &dest).unwrap();
+        assert_eq!(copied, dest.join("notes (1).txt"));
+        assert_eq!(bytes, 3);
+        assert_eq!(std::fs::read_to_string(dest.join("notes.txt")).unwrap(), "old");
+        assert_eq!(std::fs::read_to_string(&copied).unwrap(), "new");
+
+        assert!(matches!(copy_into(&dest, &root), Err(AppError::Validation(_))));
+        assert!(matches!(copy_into(&root.join("missing"), &dest), Err(AppError::NotFound(_))));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,438 @@
+//! File system watches for the frontend and agents.
+//!
+//! `watch_path` starts a notify watcher on a file or directory and returns a
+//! watch id. Raw events are collected until `DEBOUNCE` passes without a new
+//! one (or `MAX_BATCH_DELAY` since the first), merged per path, and emitted
+//! as one `fs-changed` event, so a build writing hundreds of files produces
+//! a handful of events rather than a flood. `unwatch_path` stops a watch.
+//!
+//! Renames are reported as the old path deleted and the new one created.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+
+use notify_debouncer_mini::notify::event::{ModifyKind, RenameMode};
+use notify_debouncer_mini::notify::{self, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::events;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Quiet time after the last event before a batch is emitted
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Longest a batch is held back while events keep arriving
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(2);
+
+/// Most changes carried by one `fs-changed` event; the rest follow in the next
+const MAX_BATCH_CHANGES: usize = 1000;
+
+/// Most watches open at once
+const MAX_WATCHES: usize = 64;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// What happened to a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FsChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FsChange {
+    pub path: String,
+    pub kind: FsChangeKind,
+}
+
+/// Payload of the `fs-changed` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct FsChangedEvent {
+    pub watch_id: String,
+    /// Changes in the order their paths were first touched
+    pub changes: Vec<FsChange>,
+}
+
+/// Result of `watch_path`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FsWatch {
+    pub watch_id: String,
+    /// Canonical path being watched
+    pub path: String,
+    pub recursive: bool,
+}
+
+/// Shared state managed by Tauri: the open watchers. Dropping a watcher
+/// closes its event channel, which ends its debounce thread.
+#[derive(Default)]
+pub struct FsWatchState {
+    watches: Mutex<HashMap<String, RecommendedWatcher>>,
+}
+
+impl FsWatchState {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, RecommendedWatcher>> {
+        // Holds only watcher handles, so a poisoned lock is still usable
+        self.watches.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// =============================================================================
+// Event Merging
+// =============================================================================
+
+/// Changes of one batch, merged per path.
+#[derive(Debug, Default)]
+struct Batch {
+    order: Vec<PathBuf>,
+    kinds: HashMap<PathBuf, FsChangeKind>,
+}
+
+impl Batch {
+    fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Record a change, folding it into an earlier one for the same path:
+    /// a file created and modified is created, one created and deleted
+    /// within the batch is dropped, and one deleted and re-created (as
+    /// editors do when saving) is modified.
+    fn add(&mut self, path: PathBuf, kind: FsChangeKind) {
+        use FsChangeKind::*;
+        let merged = match (self.kinds.get(&path), kind) {
+            (None, kind) => Some(kind),
+            (Some(Created), Deleted) => None,
+            (Some(Created), _) => Some(Created),
+            (Some(Deleted), Created) | (Some(Deleted), Modified) => Some(Modified),
+            (Some(_), kind) => Some(kind),
+        };
+        match merged {
+            Some(kind) => {
+                if self.kinds.insert(path.clone(), kind).is_none() {
+                    self.order.push(path);
+                }
+            }
+            None => {
+                self.kinds.remove(&path);
+                self.order.retain(|p| p != &path);
+            }
+        }
+    }
+
+    /// Take the changes in first-touched order.
+    fn drain(&mut self) -> Vec<FsChange> {
+        let kinds = std::mem::take(&mut self.kinds);
+        std::mem::take(&mut self.order)
+            .into_iter()
+            .filter_map(|path| {
+                let kind = *kinds.get(&path)?;
+                Some(FsChange {
+                    path: path.to_string_lossy().into_owned(),
+                    kind,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Map a raw notify event to per-path changes. Access events are ignored;
+/// events of unknown kind are resolved by whether the path still exists.
+fn classify(event: notify::Event) -> Vec<(PathBuf, FsChangeKind)> {
+    let by_existence = |path: PathBuf| {
+        let kind = if path.exists() {
+            FsChangeKind::Modified
+        } else {
+            FsChangeKind::Deleted
+        };
+        (path, kind)
+    };
+    match event.kind {
+        EventKind::Access(_) => Vec::new(),
+        EventKind::Create(_) => event
+            .paths
+            .into_iter()
+            .map(|p| (p, FsChangeKind::Created))
+            .collect(),
+        EventKind::Remove(_) => event
+            .paths
+            .into_iter()
+            .map(|p| (p, FsChangeKind::Deleted))
+            .collect(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => event
+            .paths
+            .into_iter()
+            .map(|p| (p, FsChangeKind::Deleted))
+            .collect(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => event
+            .paths
+            .into_iter()
+            .map(|p| (p, FsChangeKind::Created))
+            .collect(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            let mut paths = event.paths.into_iter();
+            let mut changes = Vec::new();
+            if let Some(from) = paths.next() {
+                changes.push((from, FsChangeKind::Deleted));
+            }
+            changes.extend(paths.map(|p| (p, FsChangeKind::Created)));
+            changes
+        }
+        // A rename whose side is unknown: the path is new if it exists
+        EventKind::Modify(ModifyKind::Name(_)) => event
+            .paths
+            .into_iter()
+            .map(|p| match by_existence(p) {
+                (p, FsChangeKind::Modified) => (p, FsChangeKind::Created),
+                change => change,
+            })
+            .collect(),
+        EventKind::Modify(_) => event
+            .paths
+            .into_iter()
+            .map(|p| (p, FsChangeKind::Modified))
+            .collect(),
+        EventKind::Any | EventKind::Other => event.paths.into_iter().map(by_existence).collect(),
+    }
+}
+
+// =============================================================================
+// Debouncing
+// =============================================================================
+
+/// Collect events from `rx` into batches and emit them until the watcher
+/// (the channel's sender) is dropped.
+fn debounce_loop(
+    app: AppHandle,
+    watch_id: String,
+    rx: mpsc::Receiver<notify::Result<notify::Event>>,
+) {
+    let mut batch = Batch::default();
+    let mut first_at: Option<Instant> = None;
+
+    loop {
+        let received = match first_at {
+            None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+            Some(first) => {
+                let deadline = (first + MAX_BATCH_DELAY).saturating_duration_since(Instant::now());
+                rx.recv_timeout(DEBOUNCE.min(deadline))
+            }
+        };
+        match received {
+            Ok(Ok(event)) => {
+                for (path, kind) in classify(event) {
+                    batch.add(path, kind);
+                }
+                if !batch.is_empty() && first_at.is_none() {
+                    first_at = Some(Instant::now());
+                }
+                if batch.order.len() < MAX_BATCH_CHANGES {
+                    continue;
+                }
+            }
+            Ok(Err(e)) => {
+                log::warn!("File watch {} error: {}", watch_id, e);
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                flush(&app, &watch_id, &mut batch);
+                break;
+            }
+        }
+        flush(&app, &watch_id, &mut batch);
+        first_at = None;
+    }
+    log::debug!("File watch {} ended", watch_id);
+}
+
+fn flush(app: &AppHandle, watch_id: &str, batch: &mut Batch) {
+    let changes = batch.drain();
+    if changes.is_empty() {
+        return;
+    }
+    let event = FsChangedEvent {
+        watch_id: watch_id.to_string(),
+        changes,
+    };
+    if let Err(e) = events::emit(app, "fs-changed", event) {
+        log::warn!("Failed to emit fs-changed for watch {}: {}", watch_id, e);
+    }
+}
+
+fn canonical_path(path: &str) -> Result<PathBuf, AppError> {
+    let path = Path::new(path);
+    if path.as_os_str().is_empty() {
+        return Err(AppError::Validation("Path must not be empty".into()));
+    }
+    path.canonicalize()
+        .map_err(|e| AppError::NotFound(format!("Cannot watch {}: {}", path.display(), e)))
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Watch a file or directory (with its subdirectories when `recursive`),
+/// emitting debounced `fs-changed` events until `unwatch_path`.
+#[tauri::command]
+pub fn watch_path(
+    app: AppHandle,
+    state: State<'_, FsWatchState>,
+    path: String,
+    recursive: Option<bool>,
+) -> Result<FsWatch, AppError> {
+    let recursive = recursive.unwrap_or(false);
+    let path = canonical_path(&path)?;
+    if state.lock().len() >= MAX_WATCHES {
+        return Err(AppError::Validation(format!(
+            "Too many file watches (max {})",
+            MAX_WATCHES
+        )));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    })
+    .map_err(|e| AppError::Io(format!("Failed to create file watcher: {}", e)))?;
+    let mode = if recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher
+        .watch(&path, mode)
+        .map_err(|e| AppError::Io(format!("Failed to watch {}: {}", path.display(), e)))?;
+
+    let watch_id = uuid::Uuid::new_v4().to_string();
+    let thread_id = watch_id.clone();
+    std::thread::Builder::new()
+        .name(format!("fs-watch-{}", &watch_id[..8]))
+        .spawn(move || debounce_loop(app, thread_id, rx))
+        .map_err(|e| AppError::Internal(format!("Failed to start file watch thread: {}", e)))?;
+
+    state.lock().insert(watch_id.clone(), watcher);
+    log::info!(
+        "Watching {} ({}) as {}",
+        path.display(),
+        if recursive { "recursive" } else { "non-recursive" },
+        watch_id
+    );
+    Ok(FsWatch {
+        watch_id,
+        path: path.to_string_lossy().into_owned(),
+        recursive,
+    })
+}
+
+/// Stop a watch started by `watch_path`. Changes already batched are still
+/// emitted.
+#[tauri::command]
+pub fn unwatch_path(state: State<'_, FsWatchState>, watch_id: String) -> Result<(), AppError> {
+    state
+        .lock()
+        .remove(&watch_id)
+        .ok_or_else(|| AppError::NotFound(format!("File watch not found: {}", watch_id)))?;
+    log::info!("Stopped file watch {}", watch_id);
+    Ok(())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify_debouncer_mini::notify::event::{AccessKind, CreateKind, DataChange, RemoveKind};
+
+    fn event(kind: EventKind, paths: &[&str]) -> notify::Event {
+        paths
+            .iter()
+            .fold(notify::Event::new(kind), |e, p| e.add_path(PathBuf::from(p)))
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(event(EventKind::Create(CreateKind::File), &["/a"])),
+            vec![(PathBuf::from("/a"), FsChangeKind::Created)]
+        );
+        assert_eq!(
+            classify(event(
+                EventKind::Modify(ModifyKind::Data(DataChange::Content)),
+                &["/a"]
+            )),
+            vec![(PathBuf::from("/a"), FsChangeKind::Modified)]
+        );
+        assert_eq!(
+            classify(event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                &["/a", "/b"]
+            )),
+            vec![
+                (PathBuf::from("/a"), FsChangeKind::Deleted),
+                (PathBuf::from("/b"), FsChangeKind::Created)
+            ]
+        );
+        assert_eq!(
+            classify(event(EventKind::Remove(RemoveKind::File), &["/a", "/b"])),
+            vec![
+                (PathBuf::from("/a"), FsChangeKind::Deleted),
+                (PathBuf::from("/b"), FsChangeKind::Deleted)
+            ]
+        );
+        assert!(classify(event(EventKind::Access(AccessKind::Any), &["/a"])).is_empty());
+        // Unknown kinds fall back to whether the path exists
+        assert_eq!(
+            classify(event(EventKind::Any, &["/nonexistent/synthia-fs-watch"])),
+            vec![(PathBuf::from("/nonexistent/synthia-fs-watch"), FsChangeKind::Deleted)]
+        );
+    }
+
+    #[test]
+    fn test_batch_merges_per_path() {
+        let mut batch = Batch::default();
+        batch.add("/new".into(), FsChangeKind::Created);
+        batch.add("/saved".into(), FsChangeKind::Deleted);
+        batch.add("/new".into(), FsChangeKind::Modified);
+        batch.add("/tmp".into(), FsChangeKind::Created);
+        batch.add("/saved".into(), FsChangeKind::Created);
+        batch.add("/tmp".into(), FsChangeKind::Deleted);
+        batch.add("/gone".into(), FsChangeKind::Modified);
+        batch.add("/gone".into(), FsChangeKind::Deleted);
+
+        let changes = batch.drain();
+        let summary: Vec<_> = changes.iter().map(|c| (c.path.as_str(), c.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("/new", FsChangeKind::Created),
+                ("/saved", FsChangeKind::Modified),
+                ("/gone", FsChangeKind::Deleted),
+            ]
+        );
+        assert!(batch.is_empty());
+    }
+
+    #[test]
+    fn test_canonical_path() {
+        assert_eq!(canonical_path("").unwrap_err().code(), "validation");
+        assert_eq!(
+            canonical_path("/nonexistent/synthia-fs-watch").unwrap_err().code(),
+            "not_found"
+        );
+        assert!(canonical_path(".").unwrap().is_absolute());
+    }
+}
@@ -0,0 +1,432 @@
+//! Git repository status for the dashboard.
+//!
+//! Read-only queries (via libgit2) that let the UI annotate a terminal
+//! session with the state of the repository it is sitting in: branch,
+//! ahead/behind, dirty files, recent commits and a diff summary. Every
+//! command takes a path anywhere inside the working tree; the repository
+//! is discovered by walking up from it.
+
+use git2::{BranchType, DiffOptions, Repository, Status, StatusOptions};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Commits returned by `get_recent_commits` when `limit` is omitted
+const DEFAULT_COMMIT_LIMIT: usize = 10;
+
+/// Upper bound for `get_recent_commits` `limit`
+const MAX_COMMIT_LIMIT: usize = 100;
+
+/// Per-file entries included in a diff summary (totals always cover all files)
+const MAX_DIFF_FILES: usize = 200;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Working tree state of a repository.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoStatus {
+    /// Working tree root
+    pub root: String,
+    /// Current branch, None when HEAD is detached
+    pub branch: Option<String>,
+    /// Short id of the HEAD commit, None in a repository without commits
+    pub head: Option<String>,
+    pub detached: bool,
+    /// Upstream branch, e.g. `origin/main`
+    pub upstream: Option<String>,
+    /// Commits on the branch not on its upstream
+    pub ahead: usize,
+    /// Commits on the upstream not on the branch
+    pub behind: usize,
+    /// Files with staged changes
+    pub staged: usize,
+    /// Files with unstaged changes to tracked content
+    pub modified: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+    /// No staged, modified, untracked or conflicted files
+    pub clean: bool,
+}
+
+/// A commit in the history of HEAD.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitInfo {
+    pub id: String,
+    pub short_id: String,
+    /// First line of the message
+    pub summary: String,
+    pub author: String,
+    /// Commit time as RFC 3339
+    pub time: String,
+}
+
+/// Changes of one file in a diff summary.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// `added`, `deleted`, `modified`, `renamed`, `typechange`, ...
+    pub status: String,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Uncommitted changes (staged and unstaged) relative to HEAD.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffSummary {
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Per-file breakdown, capped at 200 entries
+    pub files: Vec<FileDiff>,
+}
+
+// =============================================================================
+// Repository Access
+// =============================================================================
+
+/// Find the repository containing `path`.
+fn open_repo(path: &str) -> Result<Repository, AppError> {
+    if !Path::new(path).exists() {
+        return Err(AppError::Validation(format!("Path does not exist: {}", path)));
+    }
+    Repository::discover(path)
+        .map_err(|_| AppError::NotFound(format!("Not inside a git repository: {}", path)))
+}
+
+fn git_err(e: git2::Error) -> AppError {
+    AppError::Internal(format!("Git error: {}", e.message()))
+}
+
+/// Run a blocking libgit2 query off the async runtime.
+async fn run_blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Internal(format!("Git task failed: {}", e)))?
+}
+
+/// Current branch name, None when detached or unreadable.
+fn current_branch(repo: &Repository) -> Option<String> {
+    match repo.head() {
+        Ok(head) if head.is_branch() => head.shorthand().map(str::to_string),
+        Ok(_) => None,
+        // Unborn branch (no commits yet): HEAD still names the branch
+        Err(_) => repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|r| r.symbolic_target().map(str::to_string))
+            .map(|t| t.trim_start_matches("refs/heads/").to_string()),
+    }
+}
+
+fn repo_status(repo: &Repository) -> Result<RepoStatus, AppError> {
+    let root = repo
+        .workdir()
+        .unwrap_or_else(|| repo.path())
+        .to_string_lossy()
+        .trim_end_matches(['/', '\\'])
+        .to_string();
+
+    let head_commit = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let head = head_commit.as_ref().and_then(|c| {
+        c.as_object()
+            .short_id()
+            .ok()
+            .and_then(|s| s.as_str().map(str::to_string))
+    });
+    let detached = repo.head_detached().unwrap_or(false);
+    let branch = current_branch(repo).filter(|_| !detached);
+
+    let (mut upstream, mut ahead, mut behind) = (None, 0, 0);
+    if let Some(name) = &branch {
+        if let Ok(up) = repo
+            .find_branch(name, BranchType::Local)
+            .and_then(|b| b.upstream())
+        {
+            upstream = up.name().ok().flatten().map(str::to_string);
+            if let (Some(local), Some(remote)) = (
+                head_commit.as_ref().map(|c| c.id()),
+                up.get().target(),
+            ) {
+                (ahead, behind) = repo.graph_ahead_behind(local, remote).map_err(git_err)?;
+            }
+        }
+    }
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false);
+    let statuses = repo.statuses(Some(&mut opts)).map_err(git_err)?;
+
+    let (mut staged, mut modified, mut untracked, mut conflicted) = (0, 0, 0, 0);
+    for entry in statuses.iter() {
+        let s = entry.status();
+        if s.contains(Status::CONFLICTED) {
+            conflicted += 1;
+            continue;
+        }
+        if s.intersects(
+            Status::INDEX_NEW
+                | Status::INDEX_MODIFIED
+                | Status::INDEX_DELETED
+                | Status::INDEX_RENAMED
+                | Status::INDEX_TYPECHANGE,
+        ) {
+            staged += 1;
+        }
+        if s.intersects(
+            Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE,
+        ) {
+            modified += 1;
+        }
+        if s.contains(Status::WT_NEW) {
+            untracked += 1;
+        }
+    }
+
+    Ok(RepoStatus {
+        root,
+        branch,
+        head,
+        detached,
+        upstream,
+        ahead,
+        behind,
+        staged,
+        modified,
+        untracked,
+        conflicted,
+        clean: staged + modified + untracked + conflicted == 0,
+    })
+}
+
+fn recent_commits(repo: &Repository, limit: usize) -> Result<Vec<CommitInfo>, AppError> {
+    // A repository without commits has no history
+    if repo.head().is_err() {
+        return Ok(Vec::new());
+    }
+
+    let mut walk = repo.revwalk().map_err(git_err)?;
+    walk.push_head().map_err(git_err)?;
+
+    let mut commits = Vec::new();
+    for oid in walk.take(limit) {
+        let commit = repo.find_commit(oid.map_err(git_err)?).map_err(git_err)?;
+        let id = commit.id().to_string();
+        let time = chrono::DateTime::from_timestamp(commit.time().seconds(), 0)
+            .map(|t| t.with_timezone(&chrono::Local).to_rfc3339())
+            .unwrap_or_default();
+        commits.push(CommitInfo {
+            short_id: id.chars().take(7).collect(),
+            id,
+            summary: commit.summary().unwrap_or("").to_string(),
+            author: commit.author().name().unwrap_or("").to_string(),
+            time,
+        });
+    }
+    Ok(commits)
+}
+
+fn diff_summary(repo: &Repository) -> Result<DiffSummary, AppError> {
+    let head_tree = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+    let mut opts = DiffOptions::new();
+    let diff = repo
+        .diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut opts))
+        .map_err(git_err)?;
+
+    let stats = diff.stats().map_err(git_err)?;
+    let mut files = Vec::new();
+    for (idx, delta) in diff.deltas().enumerate().take(MAX_DIFF_FILES) {
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (insertions, deletions) = git2::Patch::from_diff(&diff, idx)
+            .ok()
+            .flatten()
+            .and_then(|p| p.line_stats().ok())
+            .map(|(_, ins, del)| (ins, del))
+            .unwrap_or((0, 0));
+        files.push(FileDiff {
+            path,
+            status: format!("{:?}", delta.status()).to_lowercase(),
+            insertions,
+            deletions,
+        });
+    }
+
+    Ok(DiffSummary {
+        files_changed: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+        files,
+    })
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Branch, upstream tracking and dirty-file counts for the repository
+/// containing `path`. Fails with `not_found` outside a repository.
+#[tauri::command]
+pub async fn get_repo_status(path: String) -> Result<RepoStatus, AppError> {
+    run_blocking(move || repo_status(&open_repo(&path)?)).await
+}
+
+/// Current branch of the repository containing `path` (null when HEAD is detached).
+#[tauri::command]
+pub async fn get_current_branch(path: String) -> Result<Option<String>, AppError> {
+    run_blocking(move || {
+        let repo = open_repo(&path)?;
+        if repo.head_detached().unwrap_or(false) {
+            return Ok(None);
+        }
+        Ok(current_branch(&repo))
+    })
+    .await
+}
+
+/// Most recent commits reachable from HEAD, newest first.
+///
+/// # Arguments
+/// * `limit` - Maximum commits to return (default 10, max 100)
+#[tauri::command]
+pub async fn get_recent_commits(
+    path: String,
+    limit: Option<usize>,
+) -> Result<Vec<CommitInfo>, AppError> {
+    let limit = limit.unwrap_or(DEFAULT_COMMIT_LIMIT);
+    if !(1..=MAX_COMMIT_LIMIT).contains(&limit) {
+        return Err(AppError::Validation(format!(
+            "Limit must be 1-{}, got: {}",
+            MAX_COMMIT_LIMIT, limit
+        )));
+    }
+    run_blocking(move || recent_commits(&open_repo(&path)?, limit)).await
+}
+
+/// Line counts of uncommitted (staged + unstaged) changes relative to HEAD.
+/// Untracked files are not included; see `get_repo_status` for their count.
+#[tauri::command]
+pub async fn get_diff_summary(path: String) -> Result<DiffSummary, AppError> {
+    run_blocking(move || diff_summary(&open_repo(&path)?)).await
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A fresh repository in a unique temp directory, removed on drop.
+    struct TempRepo {
+        dir: PathBuf,
+        repo: Repository,
+    }
+
+    impl TempRepo {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("synthia-git-{}", uuid::Uuid::new_v4()));
+            let repo = Repository::init(&dir).unwrap();
+            let mut config = repo.config().unwrap();
+            config.set_str("user.name", "Test").unwrap();
+            config.set_str("user.email", "test@example.com").unwrap();
+            Self { dir, repo }
+        }
+
+        fn write(&self, name: &str, contents: &str) {
+            std::fs::write(self.dir.join(name), contents).unwrap();
+        }
+
+        fn commit_all(&self, message: &str) {
+            let mut index = self.repo.index().unwrap();
+            index
+                .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+                .unwrap();
+            index.write().unwrap();
+            let tree = self.repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let sig = self.repo.signature().unwrap();
+            let parent = self.repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+            let parents: Vec<&git2::Commit> = parent.iter().collect();
+            self.repo
+                .commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+                .unwrap();
+        }
+    }
+
+    impl Drop for TempRepo {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn test_status_of_empty_repo() {
+        let t = TempRepo::new();
+        let status = repo_status(&t.repo).unwrap();
+        assert!(status.head.is_none());
+        assert!(status.clean);
+        assert!(recent_commits(&t.repo, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_status_counts_and_commits() {
+        let t = TempRepo::new();
+        t.write("a.txt", "one\n");
+        t.commit_all("first commit");
+        t.write("a.txt", "one\ntwo\nthree\n");
+        t.write("new.txt", "x\n");
+
+        let status = repo_status(&t.repo).unwrap();
+        assert!(status.branch.is_some());
+        assert!(!status.detached);
+        assert_eq!(status.modified, 1);
+        assert_eq!(status.untracked, 1);
+        assert_eq!(status.staged, 0);
+        assert!(!status.clean);
+
+        let commits = recent_commits(&t.repo, 10).unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "first commit");
+        assert_eq!(commits[0].short_id.len(), 7);
+
+        let diff = diff_summary(&t.repo).unwrap();
+        assert_eq!(diff.files_changed, 1);
+        assert_eq!(diff.insertions, 2);
+        assert_eq!(diff.files[0].path, "a.txt");
+        assert_eq!(diff.files[0].status, "modified");
+    }
+
+    #[test]
+    fn test_open_repo_outside_repository() {
+        let dir = std::env::temp_dir();
+        assert!(matches!(
+            open_repo("/definitely/not/a/path"),
+            Err(AppError::Validation(_))
+        ));
+        // The temp dir itself is normally not inside a repository
+        if Repository::discover(&dir).is_err() {
+            assert!(matches!(
+                open_repo(&dir.to_string_lossy()),
+                Err(AppError::NotFound(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,559 @@
+//! Terminal session and command history.
+//!
+//! Every spawned session and every command reported finished by shell
+//! integration (OSC 133, see `pty::CommandFinished`) is recorded in a SQLite
+//! database (`history.db` in the app data directory), so it survives app
+//! restarts. Commands are stored with their exit code, duration and the
+//! shell's working directory when they finished, which is what a "what did
+//! I run yesterday in this project" view filters on.
+//!
+//! Command lines pass through `terminal.redaction` before they are stored.
+//! Rows older than 180 days are pruned on startup.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use sysinfo::System;
+use tauri::{AppHandle, Manager, State};
+
+use crate::agent_context;
+use crate::redact;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// File name of the history database inside the app data directory
+const HISTORY_DB_FILE_NAME: &str = "history.db";
+
+/// How long sessions and commands are kept
+const HISTORY_RETENTION_SECS: i64 = 180 * 24 * 3600;
+
+/// Rows returned by the query commands when `limit` is omitted, and the cap
+const DEFAULT_QUERY_LIMIT: usize = 100;
+const MAX_QUERY_LIMIT: usize = 1000;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Filter for `query_history`. Every field is optional; set fields are
+/// combined with AND.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryFilter {
+    /// Only commands of sessions with this id (ids are reused across runs)
+    pub session_id: Option<String>,
+    /// Only commands run in this directory or below it
+    pub cwd: Option<String>,
+    /// Only command lines containing this text (case-insensitive)
+    pub text: Option<String>,
+    /// Unix timestamp (seconds) range of when the command finished
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    /// Only commands that exited with a non-zero status
+    pub failed_only: bool,
+    /// Most rows returned, newest first (default 100, at most 1000)
+    pub limit: Option<usize>,
+}
+
+/// A recorded command returned by `query_history`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryCommand {
+    pub session_id: String,
+    /// The command line (redacted), if the shell reported or echoed it
+    pub command: Option<String>,
+    /// Working directory of the shell when the command finished
+    pub cwd: Option<String>,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// Unix timestamp (seconds)
+    pub finished_at: i64,
+}
+
+/// A recorded session returned by `query_history_sessions`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistorySession {
+    pub session_id: String,
+    /// Shell program the session ran
+    pub shell: String,
+    /// Directory the shell started in
+    pub cwd: Option<String>,
+    pub profile: Option<String>,
+    /// Unix timestamps (seconds); `ended_at` is None while the session is open
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    /// Exit code of the shell, if it exited on its own
+    pub exit_code: Option<u32>,
+    pub commands: u64,
+}
+
+/// Shared state managed by Tauri. The connection is opened in `init`.
+#[derive(Default)]
+pub struct HistoryState {
+    db: Arc<Mutex<Option<Connection>>>,
+    /// Row id of each open session in the `sessions` table
+    open: Mutex<HashMap<String, i64>>,
+}
+
+// =============================================================================
+// Database
+// =============================================================================
+
+/// Get the path to the history database.
+fn get_history_db_path(app: &AppHandle) -> Option<PathBuf> {
+    let data_dir = app.path().app_data_dir().ok()?;
+    Some(data_dir.join(HISTORY_DB_FILE_NAME))
+}
+
+/// Create the tables if they don't exist yet.
+fn create_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sessions (id INTEGER PRIMARY KEY, session_id TEXT NOT NULL, \
+         shell TEXT NOT NULL, cwd TEXT, profile TEXT, started_at INTEGER NOT NULL, \
+         ended_at INTEGER, exit_code INTEGER)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS commands (id INTEGER PRIMARY KEY, \
+         session INTEGER NOT NULL REFERENCES sessions (id), command TEXT, cwd TEXT, \
+         exit_code INTEGER, duration_ms INTEGER NOT NULL, finished_at INTEGER NOT NULL)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS commands_finished_at ON commands (finished_at)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS sessions_started_at ON sessions (started_at)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Delete commands and sessions older than the retention, and close sessions
+/// left open by a previous run that did not exit cleanly.
+fn prune(conn: &Connection, now: i64) -> rusqlite::Result<()> {
+    let cutoff = now - HISTORY_RETENTION_SECS;
+    conn.execute("DELETE FROM commands WHERE finished_at < ?1", params![cutoff])?;
+    conn.execute(
+        "DELETE FROM sessions WHERE started_at < ?1 \
+         AND id NOT IN (SELECT DISTINCT session FROM commands)",
+        params![cutoff],
+    )?;
+    conn.execute(
+        "UPDATE sessions SET ended_at = MAX(started_at, \
+         IFNULL((SELECT MAX(finished_at) FROM commands WHERE session = sessions.id), 0)) \
+         WHERE ended_at IS NULL",
+        [],
+    )?;
+    Ok(())
+}
+
+fn insert_session(
+    conn: &Connection,
+    session_id: &str,
+    shell: &str,
+    cwd: Option<&str>,
+    profile: Option<&str>,
+    started_at: i64,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO sessions (session_id, shell, cwd, profile, started_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![session_id, shell, cwd, profile, started_at],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn end_session(
+    conn: &Connection,
+    row: i64,
+    ended_at: i64,
+    exit_code: Option<u32>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE sessions SET ended_at = ?2, exit_code = ?3 WHERE id = ?1",
+        params![row, ended_at, exit_code],
+    )?;
+    Ok(())
+}
+
+fn insert_command(conn: &Connection, session: i64, command: &HistoryCommand) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO commands (session, command, cwd, exit_code, duration_ms, finished_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            session,
+            command.command,
+            command.cwd,
+            command.exit_code,
+            command.duration_ms as i64,
+            command.finished_at
+        ],
+    )?;
+    Ok(())
+}
+
+fn query_commands(conn: &Connection, filter: &HistoryFilter) -> rusqlite::Result<Vec<HistoryCommand>> {
+    // LIKE patterns with the wildcards in user text escaped
+    let like = |text: &str| text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let cwd_prefix = filter
+        .cwd
+        .as_deref()
+        .map(|dir| format!("{}/%", like(dir.trim_end_matches(['/', '\\']))));
+    let text = filter.text.as_deref().map(|t| format!("%{}%", like(t)));
+    let limit = filter.limit.unwrap_or(DEFAULT_QUERY_LIMIT) as i64;
+
+    let mut stmt = conn.prepare(
+        "SELECT s.session_id, c.command, c.cwd, c.exit_code, c.duration_ms, c.finished_at
+         FROM commands c JOIN sessions s ON s.id = c.session
+         WHERE (?1 IS NULL OR s.session_id = ?1)
+           AND (?2 IS NULL OR c.cwd = ?3 OR c.cwd LIKE ?2 ESCAPE '\\')
+           AND (?4 IS NULL OR c.command LIKE ?4 ESCAPE '\\')
+           AND (?5 IS NULL OR c.finished_at >= ?5)
+           AND (?6 IS NULL OR c.finished_at <= ?6)
+           AND (?7 = 0 OR IFNULL(c.exit_code, 0) != 0)
+         ORDER BY c.finished_at DESC, c.id DESC LIMIT ?8",
+    )?;
+    let rows = stmt.query_map(
+        params![
+            filter.session_id,
+            cwd_prefix,
+            filter.cwd.as_deref().map(|dir| dir.trim_end_matches(['/', '\\'])),
+            text,
+            filter.since,
+            filter.until,
+            filter.failed_only,
+            limit
+        ],
+        |row| {
+            Ok(HistoryCommand {
+                session_id: row.get(0)?,
+                command: row.get(1)?,
+                cwd: row.get(2)?,
+                exit_code: row.get(3)?,
+                duration_ms: row.get::<_, i64>(4)? as u64,
+                finished_at: row.get(5)?,
+            })
+        },
+    )?;
+    rows.collect()
+}
+
+fn query_sessions(conn: &Connection, since: i64, limit: usize) -> rusqlite::Result<Vec<HistorySession>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.session_id, s.shell, s.cwd, s.profile, s.started_at, s.ended_at, s.exit_code,
+                (SELECT COUNT(*) FROM commands c WHERE c.session = s.id)
+         FROM sessions s WHERE s.started_at >= ?1 OR s.ended_at IS NULL OR s.ended_at >= ?1
+         ORDER BY s.started_at DESC, s.id DESC LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![since, limit as i64], |row| {
+        Ok(HistorySession {
+            session_id: row.get(0)?,
+            shell: row.get(1)?,
+            cwd: row.get(2)?,
+            profile: row.get(3)?,
+            started_at: row.get(4)?,
+            ended_at: row.get(5)?,
+            exit_code: row.get(6)?,
+            commands: row.get::<_, i64>(7)? as u64,
+        })
+    })?;
+    rows.collect()
+}
+
+// =============================================================================
+// Recording
+// =============================================================================
+
+/// Open the history database and tidy it up after the previous run.
+pub fn init(app: &AppHandle) {
+    let path = match get_history_db_path(app) {
+        Some(p) => p,
+        None => {
+            log::error!("Could not determine history database path; command history disabled");
+            return;
+        }
+    };
+
+    if let Some(dir) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            log::error!("Failed to create data directory for history: {}", e);
+            return;
+        }
+    }
+
+    let opened = Connection::open(&path).and_then(|c| {
+        create_schema(&c)?;
+        prune(&c, chrono::Utc::now().timestamp())?;
+        Ok(c)
+    });
+    let conn = match opened {
+        Ok(c) => c,
+        Err(e) => {
+            log::error!("Failed to open history database {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    log::info!("Command history stored at {:?}", path);
+    match app.state::<HistoryState>().db.lock() {
+        Ok(mut guard) => *guard = Some(conn),
+        Err(e) => log::error!("Failed to lock history database: {}", e),
+    }
+}
+
+/// Run `f` on the database if history is available. Failures are only logged.
+fn with_db<T>(
+    state: &HistoryState,
+    what: &str,
+    f: impl FnOnce(&Connection) -> rusqlite::Result<T>,
+) -> Option<T> {
+    let guard = state.db.lock().ok()?;
+    let conn = guard.as_ref()?;
+    match f(conn) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            log::warn!("Failed to record {} in history: {}", what, e);
+            None
+        }
+    }
+}
+
+/// Record a newly spawned session.
+pub(crate) fn session_started(
+    app: &AppHandle,
+    session_id: &str,
+    shell: &str,
+    cwd: Option<&str>,
+    profile: Option<&str>,
+) {
+    let state = app.state::<HistoryState>();
+    let now = chrono::Utc::now().timestamp();
+    let row = with_db(&state, "session", |conn| {
+        insert_session(conn, session_id, shell, cwd, profile, now)
+    });
+    if let Some(row) = row {
+        state.open.lock().unwrap_or_else(|e| e.into_inner()).insert(session_id.to_string(), row);
+    }
+}
+
+/// Record the end of a session; `exit_code` is None when it was killed.
+pub(crate) fn session_ended(app: &AppHandle, session_id: &str, exit_code: Option<u32>) {
+    let state = app.state::<HistoryState>();
+    let Some(row) = state.open.lock().ok().and_then(|mut open| open.remove(session_id)) else {
+        return;
+    };
+    let now = chrono::Utc::now().timestamp();
+    with_db(&state, "session end", |conn| end_session(conn, row, now, exit_code));
+}
+
+/// Close every open session, on app exit.
+pub fn end_all(app: &AppHandle) {
+    let ids: Vec<String> = match app.state::<HistoryState>().open.lock() {
+        Ok(open) => open.keys().cloned().collect(),
+        Err(_) => return,
+    };
+    for id in ids {
+        session_ended(app, &id, None);
+    }
+}
+
+/// Record a finished command of a session whose shell has pid `shell_pid`.
+/// Called from the PTY reader thread, so reading the shell's working
+/// directory and the insert run on a blocking task instead.
+pub(crate) fn command_finished(
+    app: &AppHandle,
+    session_id: &str,
+    shell_pid: Option<u32>,
+    command: Option<&str>,
+    exit_code: Option<i32>,
+    duration_ms: u64,
+) {
+    let state = app.state::<HistoryState>();
+    let Some(row) = state.open.lock().ok().and_then(|open| open.get(session_id).copied()) else {
+        return;
+    };
+    let mut entry = HistoryCommand {
+        session_id: session_id.to_string(),
+        command: command.map(str::to_string),
+        cwd: None,
+        exit_code,
+        duration_ms,
+        finished_at: chrono::Utc::now().timestamp(),
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        entry.command = entry.command.map(|c| redact::redact(&c).into_owned());
+        entry.cwd = shell_pid.and_then(|pid| agent_context::process_cwd(&mut System::new(), pid));
+        with_db(&app.state::<HistoryState>(), "command", |conn| insert_command(conn, row, &entry));
+    });
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Query recorded commands, newest first.
+///
+/// # Arguments
+/// * `filter` - Optional session id, directory (matches subdirectories too),
+///   text, time range, failed-only flag and row limit (default 100, max 1000)
+#[tauri::command]
+pub async fn query_history(
+    state: State<'_, HistoryState>,
+    filter: Option<HistoryFilter>,
+) -> Result<Vec<HistoryCommand>, AppError> {
+    let filter = filter.unwrap_or_default();
+    check_limit(filter.limit)?;
+
+    let db = state.db.clone();
+    tokio::task::spawn_blocking(move || {
+        let guard = db
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock history database: {}", e)))?;
+        let conn = guard
+            .as_ref()
+            .ok_or_else(|| AppError::Internal("Command history is not available".into()))?;
+        query_commands(conn, &filter)
+            .map_err(|e| AppError::Io(format!("Failed to query history: {}", e)))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("History query task failed: {}", e)))?
+}
+
+/// Query recorded sessions active since `since` (Unix seconds, default the
+/// last 24 hours), newest first, with their command counts.
+#[tauri::command]
+pub async fn query_history_sessions(
+    state: State<'_, HistoryState>,
+    since: Option<i64>,
+    limit: Option<usize>,
+) -> Result<Vec<HistorySession>, AppError> {
+    check_limit(limit)?;
+    let since = since.unwrap_or_else(|| chrono::Utc::now().timestamp() - 24 * 3600);
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+
+    let db = state.db.clone();
+    tokio::task::spawn_blocking(move || {
+        let guard = db
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock history database: {}", e)))?;
+        let conn = guard
+            .as_ref()
+            .ok_or_else(|| AppError::Internal("Command history is not available".into()))?;
+        query_sessions(conn, since, limit)
+            .map_err(|e| AppError::Io(format!("Failed to query history sessions: {}", e)))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("History query task failed: {}", e)))?
+}
+
+/// Reject a row limit outside 1-1000.
+fn check_limit(limit: Option<usize>) -> Result<(), AppError> {
+    match limit {
+        Some(limit) if !(1..=MAX_QUERY_LIMIT).contains(&limit) => Err(AppError::Validation(
+            format!("Limit must be 1-{}, got: {}", MAX_QUERY_LIMIT, limit),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+        conn
+    }
+
+    fn command(cwd: &str, text: &str, exit_code: i32, finished_at: i64) -> HistoryCommand {
+        HistoryCommand {
+            session_id: String::new(),
+            command: Some(text.into()),
+            cwd: Some(cwd.into()),
+            exit_code: Some(exit_code),
+            duration_ms: 10,
+            finished_at,
+        }
+    }
+
+    #[test]
+    fn test_query_commands_filters() {
+        let conn = test_db();
+        let a = insert_session(&conn, "term-a", "/bin/zsh", Some("/p"), None, 100).unwrap();
+        let b = insert_session(&conn, "term-b", "/bin/bash", None, Some("work"), 100).unwrap();
+        insert_command(&conn, a, &command("/p", "npm run migrate", 0, 110)).unwrap();
+        insert_command(&conn, a, &command("/p/web", "npm run seed", 1, 120)).unwrap();
+        insert_command(&conn, b, &command("/project", "100%_done", 0, 130)).unwrap();
+
+        let all = query_commands(&conn, &HistoryFilter::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].finished_at, 130);
+        assert_eq!(all[2].session_id, "term-a");
+
+        let query = |filter: HistoryFilter| query_commands(&conn, &filter).unwrap();
+        // Directory matches itself and subdirectories, not siblings with the prefix
+        let in_p = query(HistoryFilter {
+            cwd: Some("/p/".into()),
+            ..Default::default()
+        });
+        assert_eq!(in_p.len(), 2);
+        let failed = query(HistoryFilter {
+            failed_only: true,
+            ..Default::default()
+        });
+        assert_eq!(failed[0].command.as_deref(), Some("npm run seed"));
+        assert_eq!(failed.len(), 1);
+        // Wildcards in the search text are literal
+        let text = query(HistoryFilter {
+            text: Some("0%_".into()),
+            ..Default::default()
+        });
+        assert_eq!(text.len(), 1);
+        let ranged = query(HistoryFilter {
+            session_id: Some("term-a".into()),
+            since: Some(115),
+            limit: Some(5),
+            ..Default::default()
+        });
+        assert_eq!(ranged.len(), 1);
+    }
+
+    #[test]
+    fn test_sessions_and_prune() {
+        let conn = test_db();
+        let old = insert_session(&conn, "old", "/bin/zsh", None, None, 0).unwrap();
+        let open = insert_session(&conn, "open", "/bin/zsh", None, None, 1_000_000).unwrap();
+        let done = insert_session(&conn, "done", "/bin/zsh", None, None, 1_000_000).unwrap();
+        end_session(&conn, old, 5, Some(0)).unwrap();
+        end_session(&conn, done, 1_000_050, Some(2)).unwrap();
+        insert_command(&conn, open, &command("/p", "make", 0, 1_000_020)).unwrap();
+
+        prune(&conn, HISTORY_RETENTION_SECS + 10).unwrap();
+        let sessions = query_sessions(&conn, 0, 10).unwrap();
+        let ids: Vec<_> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, ["done", "open"]);
+        // Left open by a crashed run: closed at its last command
+        assert_eq!(sessions[1].ended_at, Some(1_000_020));
+        assert_eq!(sessions[1].commands, 1);
+        assert_eq!(sessions[0].exit_code, Some(2));
+    }
+
+    #[test]
+    fn test_check_limit() {
+        assert!(check_limit(None).is_ok());
+        assert!(check_limit(Some(MAX_QUERY_LIMIT)).is_ok());
+        assert!(check_limit(Some(0)).is_err());
+    }
+}
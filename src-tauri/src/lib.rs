@@ -1,15 +1,63 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
+mod agent_context;
+mod ansi;
+mod app_capture;
+mod audio;
+mod burst;
+mod camera;
+mod completions;
+mod config_transfer;
+mod control;
+mod crash;
+mod dashboard;
+mod delta;
+mod error;
+mod events;
+mod file_transfer;
+mod fs_watch;
+mod git;
+mod history;
+mod limits;
 mod logging;
+mod metrics;
+mod network_probe;
+mod ocr;
+mod palette;
+mod pixel_format;
+mod ports;
+mod power;
+mod processes;
 mod pty;
+mod rate_limit;
+mod recording;
+mod redact;
+mod remote_input;
+mod remote_log;
+mod replay;
+mod sandbox;
+mod scheduler;
+mod screen;
+mod scripting;
+mod secrets;
+mod services;
+mod settings;
+mod shells;
+mod shutdown;
+mod stream_access;
+mod stream_tls;
 mod streaming;
+mod terminal_image;
+mod timelapse;
+mod tray;
+mod tunnels;
 
 use log::LevelFilter;
 use serde::Serialize;
 use sysinfo::System;
-use tauri::Manager;
 use tauri_plugin_log::{RotationStrategy, Target, TargetKind, TimezoneStrategy};
-use thiserror::Error;
+
+pub use error::AppError;
 
 #[cfg(target_os = "macos")]
 use std::process::Command;
@@ -30,7 +78,8 @@ const MAX_LOG_FILE_SIZE: u128 = 5 * 1024 * 1024;
 // =============================================================================
 
 /// Returns log targets for development builds.
-/// Logs to stdout (terminal), webview (browser DevTools), and file (for Logs UI).
+/// Logs to stdout (terminal), webview (browser DevTools), file (for Logs UI),
+/// and the remote shipper (idle unless `logging.remote` is enabled).
 #[cfg(debug_assertions)]
 fn get_log_targets() -> Vec<Target> {
     vec![
@@ -39,11 +88,13 @@ fn get_log_targets() -> Vec<Target> {
         Target::new(TargetKind::LogDir {
             file_name: Some("synthia".into()),
         }),
+        Target::new(TargetKind::Dispatch(remote_log::dispatch())),
     ]
 }
 
 /// Returns log targets for production/release builds.
-/// Logs to stdout and a file in the OS-appropriate log directory.
+/// Logs to stdout, a file in the OS-appropriate log directory, and the
+/// remote shipper (idle unless `logging.remote` is enabled).
 #[cfg(not(debug_assertions))]
 fn get_log_targets() -> Vec<Target> {
     vec![
@@ -51,15 +102,22 @@ fn get_log_targets() -> Vec<Target> {
         Target::new(TargetKind::LogDir {
             file_name: Some("synthia".into()),
         }),
+        Target::new(TargetKind::Dispatch(remote_log::dispatch())),
     ]
 }
 
 /// Parsed vm_stat output containing page statistics
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Default, PartialEq)]
 pub struct VmStatData {
     pub page_size: f64,
+    pub pages_free: f64,
     pub pages_active: f64,
+    pub pages_inactive: f64,
+    pub pages_speculative: f64,
     pub pages_wired: f64,
+    pub pages_purgeable: f64,
+    /// Pages held by the compressor (the compressed memory itself)
+    pub pages_compressed: f64,
 }
 
 /// Parse vm_stat output into structured data.
@@ -91,8 +149,13 @@ pub fn parse_vm_stat_output(output: &str) -> Option<VmStatData> {
 
     Some(VmStatData {
         page_size,
+        pages_free: get_pages("Pages free"),
         pages_active: get_pages("Pages active"),
+        pages_inactive: get_pages("Pages inactive"),
+        pages_speculative: get_pages("Pages speculative"),
         pages_wired: get_pages("Pages wired down"),
+        pages_purgeable: get_pages("Pages purgeable"),
+        pages_compressed: get_pages("Pages occupied by compressor"),
     })
 }
 
@@ -102,15 +165,54 @@ pub fn calculate_used_memory(data: &VmStatData) -> f64 {
     (data.pages_active + data.pages_wired) * data.page_size
 }
 
-/// Get actual memory usage on macOS using vm_stat (matches htop)
+/// Memory split the way Activity Monitor shows it, in bytes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemoryBreakdown {
+    pub total: u64,
+    /// Active + wired, as reported in `SystemStats`
+    pub used: u64,
+    pub free: u64,
+    pub active: u64,
+    pub inactive: u64,
+    pub wired: u64,
+    /// Memory occupied by the compressor
+    pub compressed: u64,
+    /// Cached memory the system may discard under pressure
+    pub purgeable: u64,
+    pub speculative: u64,
+    pub page_size: u64,
+}
+
+/// Convert vm_stat page counts to a byte breakdown against `total` bytes.
+pub fn memory_breakdown(data: &VmStatData, total: f64) -> MemoryBreakdown {
+    let bytes = |pages: f64| (pages * data.page_size) as u64;
+    MemoryBreakdown {
+        total: total as u64,
+        used: calculate_used_memory(data) as u64,
+        free: bytes(data.pages_free),
+        active: bytes(data.pages_active),
+        inactive: bytes(data.pages_inactive),
+        wired: bytes(data.pages_wired),
+        compressed: bytes(data.pages_compressed),
+        purgeable: bytes(data.pages_purgeable),
+        speculative: bytes(data.pages_speculative),
+        page_size: data.page_size as u64,
+    }
+}
+
+/// Run vm_stat and parse its output
 #[cfg(target_os = "macos")]
-fn get_macos_memory_usage() -> Option<(f64, f64)> {
-    // Run vm_stat to get memory page statistics
+fn read_vm_stat() -> Option<VmStatData> {
     let output = Command::new("vm_stat").output().ok()?;
     let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_vm_stat_output(&stdout)
+}
 
-    // Parse the output
-    let data = parse_vm_stat_output(&stdout)?;
+/// Get actual memory usage on macOS using vm_stat (matches htop)
+#[cfg(target_os = "macos")]
+fn get_macos_memory_usage() -> Option<(f64, f64)> {
+    // Run vm_stat to get memory page statistics
+    let data = read_vm_stat()?;
     let used_bytes = calculate_used_memory(&data);
 
     // Get total from sysinfo (more reliable than vm_stat)
@@ -122,7 +224,7 @@ fn get_macos_memory_usage() -> Option<(f64, f64)> {
 }
 
 /// System statistics for the Infrastructure widget
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SystemStats {
     pub cpu: f32,
     pub mem: f32,
@@ -130,32 +232,108 @@ pub struct SystemStats {
     pub mem_total_gb: f32,
 }
 
-/// Application-level errors that can be returned from commands
-#[derive(Debug, Error)]
-pub enum AppError {
-    #[error("Validation error: {0}")]
-    Validation(String),
+/// Returns real-time system CPU and memory statistics
+#[tauri::command]
+async fn get_system_stats() -> Result<SystemStats, AppError> {
+    log::trace!("get_system_stats() called");
+    let mut sys = System::new();
+    let stats = collect_system_stats(&mut sys);
 
-    #[error("Internal error: {0}")]
-    Internal(String),
+    log::debug!(
+        "System stats: cpu={:.1}%, mem={:.1}% ({:.2}/{:.2} GiB)",
+        stats.cpu,
+        stats.mem,
+        stats.mem_used_gb,
+        stats.mem_total_gb
+    );
+
+    Ok(stats)
 }
 
-// Implement serialization for frontend consumption
-impl serde::Serialize for AppError {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        serializer.serialize_str(&self.to_string())
-    }
+/// Read the memory breakdown from vm_stat, with the total from sysinfo
+#[cfg(target_os = "macos")]
+fn get_macos_memory_details() -> Result<MemoryBreakdown, AppError> {
+    let data =
+        read_vm_stat().ok_or_else(|| AppError::Io("Failed to read vm_stat output".into()))?;
+    let mut sys = System::new();
+    sys.refresh_memory();
+    Ok(memory_breakdown(&data, sys.total_memory() as f64))
 }
 
-/// Returns real-time system CPU and memory statistics
+#[cfg(not(target_os = "macos"))]
+fn get_macos_memory_details() -> Result<MemoryBreakdown, AppError> {
+    Err(AppError::Unsupported("Memory details are only available on macOS".into()))
+}
+
+/// Returns the macOS memory breakdown (active, inactive, wired, compressed,
+/// purgeable, speculative, free) for a stacked memory chart
 #[tauri::command]
-async fn get_system_stats() -> Result<SystemStats, AppError> {
-    log::trace!("get_system_stats() called");
-    let mut sys = System::new();
+async fn get_memory_details() -> Result<MemoryBreakdown, AppError> {
+    log::trace!("get_memory_details() called");
+    let details = get_macos_memory_details()?;
+
+    log::debug!(
+        "Memory details: {} of {} bytes used, {} compressed",
+        details.used,
+        details.total,
+        details.compressed
+    );
+
+    Ok(details)
+}
+
+/// Identity of the machine the dashboard is running on
+#[derive(Debug, Clone, Serialize)]
+pub struct HostInfo {
+    pub hostname: String,
+    /// OS name (e.g. "Darwin", "Ubuntu", "Windows")
+    pub os_name: String,
+    pub os_version: String,
+    /// Human-readable name and version (e.g. "macOS 14.4 Sonoma")
+    pub os_long_version: String,
+    pub kernel_version: String,
+    /// CPU architecture (e.g. "arm64", "x86_64")
+    pub arch: String,
+    /// Boot time as Unix timestamp (seconds)
+    pub boot_time: u64,
+    pub uptime_secs: u64,
+}
+
+/// Returns hostname, OS, kernel, architecture, boot time and uptime
+#[tauri::command]
+async fn get_host_info() -> Result<HostInfo, AppError> {
+    log::trace!("get_host_info() called");
+    let unknown = || "unknown".to_string();
+
+    let info = HostInfo {
+        hostname: System::host_name().unwrap_or_else(unknown),
+        os_name: System::name().unwrap_or_else(unknown),
+        os_version: System::os_version().unwrap_or_else(unknown),
+        os_long_version: System::long_os_version().unwrap_or_else(unknown),
+        kernel_version: System::kernel_version().unwrap_or_else(unknown),
+        arch: System::cpu_arch().unwrap_or_else(|| std::env::consts::ARCH.to_string()),
+        boot_time: System::boot_time(),
+        uptime_secs: System::uptime(),
+    };
+
+    log::debug!(
+        "Host info: {} ({} {}, {}), up {}s",
+        info.hostname,
+        info.os_name,
+        info.os_version,
+        info.arch,
+        info.uptime_secs
+    );
 
+    Ok(info)
+}
+
+/// Refresh `sys` and compute CPU and memory statistics.
+///
+/// CPU usage is measured since the previous refresh of the same `System`,
+/// so long-lived callers (the metrics sampler) get accurate values while a
+/// fresh `System` has no baseline and only yields a rough reading.
+pub(crate) fn collect_system_stats(sys: &mut System) -> SystemStats {
     // Refresh CPU and memory info
     sys.refresh_cpu_usage();
     sys.refresh_memory();
@@ -200,65 +378,203 @@ async fn get_system_stats() -> Result<SystemStats, AppError> {
     let mem_total_gb = (mem_total / BYTES_PER_GIB) as f32;
     let mem_used_gb = (mem_used / BYTES_PER_GIB) as f32;
 
-    log::debug!(
-        "System stats: cpu={:.1}%, mem={:.1}% ({:.2}/{:.2} GiB)",
-        cpu_usage,
-        mem_percent,
-        mem_used_gb,
-        mem_total_gb
-    );
-
-    Ok(SystemStats {
+    SystemStats {
         cpu: cpu_usage,
         mem: mem_percent,
         mem_used_gb,
         mem_total_gb,
-    })
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    crash::install_panic_hook();
+
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .plugin(
-            tauri_plugin_log::Builder::new()
+        .manage(settings::SettingsState::default())
+        .manage(pty::PtyState::default())
+        .manage(streaming::StreamingState::default())
+        .manage(camera::CameraState::default())
+        .manage(scheduler::SchedulerState::default())
+        .manage(scripting::ScriptingState::default())
+        .manage(metrics::MetricsState::default())
+        .manage(palette::PaletteState::default())
+        .manage(services::ServicesState::default())
+        .manage(processes::ProcessesState::default())
+        .manage(timelapse::TimelapseState::default())
+        .manage(history::HistoryState::default())
+        .manage(replay::ReplayState::default())
+        .manage(fs_watch::FsWatchState::default())
+        .manage(tray::TrayState::default())
+        .manage(tunnels::TunnelsState::default())
+        .setup(|app| {
+            // The plugin's logger is wrapped to collapse runs of identical records
+            let (log_plugin, max_level, logger) = tauri_plugin_log::Builder::new()
                 .targets(get_log_targets())
-                .level(LevelFilter::Info)
+                // Effective level comes from settings (log::set_max_level) so it can change live
+                .level(LevelFilter::Trace)
+                // Rotated file names use local dates; line timestamps follow `logging.timezone`
                 .timezone_strategy(TimezoneStrategy::UseLocal)
+                .format(logging::format_record)
                 .max_file_size(MAX_LOG_FILE_SIZE)
                 .rotation_strategy(RotationStrategy::KeepAll)
-                .build(),
-        )
-        .manage(pty::PtyState::default())
-        .manage(streaming::StreamingState::default())
+                .split(app.handle())?;
+            app.handle().plugin(log_plugin)?;
+            tauri_plugin_log::attach_logger(
+                max_level,
+                Box::new(logging::CollapsingLogger::new(logger)),
+            )?;
+
+            crash::init(app.handle());
+            settings::init(app.handle());
+            scheduler::init(app.handle());
+            metrics::init(app.handle());
+            power::init(app.handle());
+            history::init(app.handle());
+            services::init(app.handle());
+            stream_access::init(app.handle());
+            pty::init(app.handle());
+            tray::init(app.handle());
+            control::init(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_system_stats,
+            events::get_event_schema,
+            get_memory_details,
+            get_host_info,
+            dashboard::get_dashboard_snapshot,
+            dashboard::get_statusbar_data,
+            agent_context::get_agent_context,
+            ansi::strip_ansi,
+            ansi::render_ansi_to_html,
+            metrics::query_metrics,
+            metrics::get_app_resource_usage,
+            metrics::query_network_probes,
+            history::query_history,
+            history::query_history_sessions,
+            power::get_power_status,
+            power::set_power_mode,
+            network_probe::run_network_probe,
+            processes::get_top_processes,
+            processes::get_process_priority,
+            processes::set_process_priority,
+            processes::set_process_affinity,
             logging::get_logs,
             logging::clear_logs,
             logging::get_log_path,
+            crash::get_last_panic,
+            logging::export_logs,
+            logging::get_log_stats,
+            logging::mark_errors_read,
+            ports::get_listening_ports,
+            settings::get_settings,
+            settings::update_settings,
+            settings::get_terminal_appearance,
+            settings::set_terminal_appearance,
+            config_transfer::export_config,
+            config_transfer::import_config,
+            settings::get_session_limits,
+            secrets::set_secret,
+            secrets::get_secret,
+            secrets::delete_secret,
             pty::spawn_terminal,
+            pty::spawn_sandboxed_terminal,
             pty::write_terminal,
+            pty::write_terminal_secure,
+            pty::inject_paths,
+            pty::paste_terminal,
             pty::resize_terminal,
             pty::kill_terminal,
             pty::list_terminals,
+            pty::get_session_stats,
+            pty::subscribe_terminal,
+            pty::unsubscribe_terminal,
+            pty::acquire_session_lock,
+            pty::release_session_lock,
+            pty::set_idle_exempt,
             pty::inject_command,
             pty::inject_commands,
+            pty::wait_for_output,
+            pty::get_transcript,
+            pty::replay_output,
+            terminal_image::render_terminal_image,
+            screen::get_visible_screen,
+            completions::get_completions,
+            file_transfer::upload_to_session_cwd,
+            file_transfer::download_from_session,
+            fs_watch::watch_path,
+            fs_watch::unwatch_path,
+            replay::replay_recording,
+            replay::stop_replay,
+            recording::start_session_recording,
+            recording::stop_session_recording,
+            pty::search_terminal,
+            shells::list_available_shells,
             streaming::list_displays,
+            streaming::get_display_thumbnails,
             streaming::start_local_stream,
+            streaming::restart_local_stream,
+            streaming::suggest_stream_port,
+            stream_tls::generate_stream_cert,
+            stream_tls::get_stream_cert_fingerprint,
+            streaming::start_app_stream,
+            streaming::get_capture_permission_status,
+            streaming::open_capture_permission_settings,
             streaming::stop_local_stream,
             streaming::get_stream_status,
+            streaming::set_stream_max_width,
+            streaming::disconnect_stream_client,
+            streaming::set_stream_client_control,
+            streaming::get_stream_latency,
+            streaming::get_capture_stats,
+            streaming::subscribe_local_frames,
+            streaming::unsubscribe_local_frames,
+            streaming::local_frame_ack,
+            stream_access::get_stream_access_log,
+            camera::list_cameras,
+            camera::start_camera_stream,
+            camera::stop_camera_stream,
+            camera::get_camera_stream_status,
+            ocr::ocr_screen_region,
+            burst::capture_burst,
+            timelapse::start_timelapse,
+            timelapse::stop_timelapse,
+            timelapse::get_timelapse_status,
+            timelapse::list_timelapse_frames,
+            palette::search_actions,
+            git::get_repo_status,
+            git::get_current_branch,
+            git::get_recent_commits,
+            git::get_diff_summary,
+            scheduler::add_schedule,
+            scheduler::list_schedules,
+            scheduler::remove_schedule,
+            scheduler::get_schedule_history,
+            scripting::run_script,
+            scripting::cancel_script,
+            scripting::list_script_runs,
+            services::add_service,
+            services::list_services,
+            services::remove_service,
+            services::start_service,
+            services::stop_service,
+            services::get_service_status,
+            services::get_service_logs,
+            tunnels::create_port_forward,
+            tunnels::list_tunnels,
+            tunnels::close_tunnel,
         ])
         .build(tauri::generate_context!())
         .expect("Failed to build Tauri application");
 
     // Use App::run() (not Builder::run()) to hook into RunEvent::Exit.
     // Tauri calls std::process::exit() which skips Drop — so we must
-    // explicitly kill all PTY sessions here to prevent leaked processes.
+    // explicitly stop everything here to prevent leaked processes.
     app.run(|app_handle, event| {
         if let tauri::RunEvent::Exit = event {
-            let pty_state = app_handle.state::<pty::PtyState>();
-            pty::kill_all_sessions(pty_state.inner());
-            // Stream cleanup happens via Drop on the shutdown_tx channel
+            shutdown::run(app_handle);
         }
     });
 }
@@ -282,6 +598,22 @@ Pages wired down:                        139137.
 Pages purgeable:                              0.
 "Translation faults":                2831385771.
 Pages copy-on-write:                   51228142.
+"#;
+
+    // Full vm_stat output including the compressor lines
+    const SAMPLE_VM_STAT_FULL: &str = r#"Mach Virtual Memory Statistics: (page size of 16384 bytes)
+Pages free:                                4238.
+Pages active:                            220207.
+Pages inactive:                          217715.
+Pages speculative:                         1205.
+Pages throttled:                              0.
+Pages wired down:                        139137.
+Pages purgeable:                           3521.
+"Translation faults":                2831385771.
+Pages copy-on-write:                   51228142.
+Pages stored in compressor:              312904.
+Pages occupied by compressor:             95412.
+Decompressions:                        48829711.
 "#;
 
     // Sample vm_stat output for Intel Mac (4096 byte pages)
@@ -326,6 +658,7 @@ Pages wired down:                        200000.
             page_size: 16384.0,
             pages_active: 220207.0,
             pages_wired: 139137.0,
+            ..Default::default()
         };
 
         let used = calculate_used_memory(&data);
@@ -339,6 +672,7 @@ Pages wired down:                        200000.
             page_size: 4096.0,
             pages_active: 500000.0,
             pages_wired: 200000.0,
+            ..Default::default()
         };
 
         let used = calculate_used_memory(&data);
@@ -346,6 +680,29 @@ Pages wired down:                        200000.
         assert_eq!(used, 2_867_200_000.0);
     }
 
+    #[test]
+    fn test_parse_vm_stat_breakdown_pages() {
+        let data = parse_vm_stat_output(SAMPLE_VM_STAT_FULL).unwrap();
+        assert_eq!(data.pages_free, 4238.0);
+        assert_eq!(data.pages_inactive, 217715.0);
+        assert_eq!(data.pages_speculative, 1205.0);
+        assert_eq!(data.pages_purgeable, 3521.0);
+        assert_eq!(data.pages_compressed, 95412.0);
+    }
+
+    #[test]
+    fn test_memory_breakdown() {
+        let data = parse_vm_stat_output(SAMPLE_VM_STAT_FULL).unwrap();
+        let details = memory_breakdown(&data, 17_179_869_184.0);
+        assert_eq!(details.total, 17_179_869_184);
+        assert_eq!(details.page_size, 16384);
+        assert_eq!(details.used, 5_887_492_096);
+        assert_eq!(details.active + details.wired, details.used);
+        assert_eq!(details.inactive, 217715 * 16384);
+        assert_eq!(details.compressed, 95412 * 16384);
+        assert_eq!(details.purgeable, 3521 * 16384);
+    }
+
     // ==========================================================================
     // Edge Case Tests
     // ==========================================================================
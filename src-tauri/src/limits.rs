@@ -0,0 +1,200 @@
+//! Per-session resource limits (`limits` argument of `spawn_terminal`).
+//!
+//! The shell is started through `/bin/sh`, which sets the rlimits with
+//! `ulimit` and lowers the priority with `nice` before exec'ing it, so the
+//! limits are in place before the shell runs any code and are inherited by
+//! everything it starts:
+//!
+//! - `max_memory_mb`: address space per process (`RLIMIT_AS`). Linux only;
+//!   macOS does not enforce it.
+//! - `max_cpu_seconds`: CPU time per process (`RLIMIT_CPU`); the kernel
+//!   sends SIGXCPU, then SIGKILL
+//! - `nice`: scheduling priority, 0 (normal) to 19 (lowest)
+//!
+//! Rlimits apply to each process separately, so a shell running many
+//! commands is bounded per command, not in total.
+
+use serde::{Deserialize, Serialize};
+
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Shell that applies the limits and execs the session's program
+const LIMIT_SHELL: &str = "/bin/sh";
+
+/// `$0` of the limit script, shown if `ulimit` fails
+const LIMIT_SCRIPT_NAME: &str = "synthia-limits";
+
+/// Below this the shell itself fails to start
+const MIN_MEMORY_MB: u64 = 64;
+
+/// Lowest priority; raising priority (negative nice) needs root
+const MAX_NICE: i32 = 19;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Limits for a session's shell and everything it runs. Unset fields are
+/// not limited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    /// Address space per process, in MiB (Linux only)
+    pub max_memory_mb: Option<u64>,
+    /// CPU time per process, in seconds
+    pub max_cpu_seconds: Option<u64>,
+    /// Scheduling priority, 0-19
+    pub nice: Option<i32>,
+}
+
+impl ResourceLimits {
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), AppError> {
+        if !cfg!(unix) && !self.is_empty() {
+            return Err(AppError::Unsupported(
+                "Resource limits are not supported on this platform".into(),
+            ));
+        }
+        if let Some(mb) = self.max_memory_mb {
+            if cfg!(target_os = "macos") {
+                return Err(AppError::Unsupported(
+                    "max_memory_mb is not enforced on macOS".into(),
+                ));
+            }
+            // Checked in KiB too, which is what ulimit takes
+            if mb < MIN_MEMORY_MB || mb.checked_mul(1024).is_none() {
+                return Err(AppError::Validation(format!(
+                    "max_memory_mb must be at least {}, got: {}",
+                    MIN_MEMORY_MB, mb
+                )));
+            }
+        }
+        if self.max_cpu_seconds == Some(0) {
+            return Err(AppError::Validation("max_cpu_seconds must be at least 1".into()));
+        }
+        if let Some(nice) = self.nice {
+            if !(0..=MAX_NICE).contains(&nice) {
+                return Err(AppError::Validation(format!(
+                    "nice must be 0-{}, got: {}",
+                    MAX_NICE, nice
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// =============================================================================
+// Wrapping
+// =============================================================================
+
+/// `sh -c` script applying `limits` and exec'ing its arguments. `&&` so a
+/// failing `ulimit` ends the session instead of running it unlimited.
+fn limit_script(limits: &ResourceLimits) -> String {
+    let mut steps = Vec::new();
+    if let Some(mb) = limits.max_memory_mb {
+        steps.push(format!("ulimit -v {}", mb * 1024));
+    }
+    if let Some(seconds) = limits.max_cpu_seconds {
+        steps.push(format!("ulimit -t {}", seconds));
+    }
+    steps.push(match limits.nice {
+        Some(nice) => format!("exec nice -n {} \"$@\"", nice),
+        None => "exec \"$@\"".into(),
+    });
+    steps.join(" && ")
+}
+
+/// Program and arguments running `program args` under `limits`. Arguments
+/// are passed positionally, so they are never parsed by `sh`.
+pub(crate) fn wrap(
+    program: &str,
+    args: &[String],
+    limits: &ResourceLimits,
+) -> (String, Vec<String>) {
+    let mut wrapped = vec![
+        "-c".to_string(),
+        limit_script(limits),
+        LIMIT_SCRIPT_NAME.into(),
+        program.to_string(),
+    ];
+    wrapped.extend(args.iter().cloned());
+    (LIMIT_SHELL.to_string(), wrapped)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        let limits = ResourceLimits {
+            max_memory_mb: Some(512),
+            max_cpu_seconds: Some(60),
+            nice: Some(10),
+        };
+        let (program, args) = wrap("/bin/zsh", &["-l".into(), "it's".into()], &limits);
+        assert_eq!(program, "/bin/sh");
+        assert_eq!(
+            args,
+            [
+                "-c",
+                "ulimit -v 524288 && ulimit -t 60 && exec nice -n 10 \"$@\"",
+                "synthia-limits",
+                "/bin/zsh",
+                "-l",
+                "it's",
+            ]
+        );
+
+        let only_cpu = ResourceLimits {
+            max_cpu_seconds: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(limit_script(&only_cpu), "ulimit -t 5 && exec \"$@\"");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ResourceLimits::default().is_empty());
+        assert!(ResourceLimits::default().validate().is_ok());
+
+        let nice = |nice| ResourceLimits {
+            nice: Some(nice),
+            ..Default::default()
+        };
+        if cfg!(unix) {
+            assert!(nice(0).validate().is_ok());
+            assert!(nice(19).validate().is_ok());
+        }
+        assert!(nice(-5).validate().is_err());
+        assert!(nice(20).validate().is_err());
+
+        let cpu = ResourceLimits {
+            max_cpu_seconds: Some(0),
+            ..Default::default()
+        };
+        assert!(cpu.validate().is_err());
+
+        let memory = |mb| ResourceLimits {
+            max_memory_mb: Some(mb),
+            ..Default::default()
+        };
+        assert!(memory(16).validate().is_err());
+        assert!(memory(u64::MAX).validate().is_err());
+        if cfg!(target_os = "linux") {
+            assert!(memory(1024).validate().is_ok());
+        }
+    }
+}
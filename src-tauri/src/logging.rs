@@ -1,15 +1,65 @@
 //! Log streaming commands for the Logs UI.
 //!
-//! This module provides Tauri commands to read, parse, and clear application logs
-//! that are written by tauri-plugin-log.
+//! This module provides Tauri commands to read, parse, clear, and export
+//! application logs that are written by tauri-plugin-log.
+//!
+//! Lines are written with RFC 3339 timestamps (`format_record`) in the zone
+//! selected by `logging.timezone`, and every timestamp read back, including
+//! offset-less ones from older files, is normalized to RFC 3339 in that zone.
+//!
+//! With `logging.capture_terminal` on, terminal output is mirrored in as
+//! well (source `pty:<session>`), so the Logs UI shows one timeline.
+//!
+//! Runs of identical records (a failing poll loop can log the same error
+//! thousands of times) are collapsed twice: `CollapsingLogger` writes one
+//! line plus a `last message repeated N times` summary, and `get_logs`
+//! folds identical consecutive entries, summaries included, into one entry
+//! with a `count`.
+//!
+//! The last warnings and errors are also kept in memory (`recent_alerts`)
+//! for the dashboard, so it does not have to read the log file.
 
+use chrono::{DateTime, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use log::{Level, Log, Metadata, Record};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Arguments;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tauri::Manager;
+use tauri_plugin_log::fern::FormatCallback;
 use tokio::fs::OpenOptions;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 
+use crate::ansi::AnsiStripper;
+use crate::AppError;
+
+/// Mirrored terminal lines longer than this are split
+const MIRROR_MAX_LINE_CHARS: usize = 2000;
+
+/// A run of repeats is summarized at least this often while it lasts
+const REPEAT_SUMMARY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Message of the record written in place of suppressed repeats
+const REPEAT_SUMMARY_PREFIX: &str = "last message repeated ";
+
+/// Warnings and errors kept for `recent_alerts`
+const MAX_RECENT_ALERTS: usize = 50;
+
+/// Time buckets in `get_log_stats`
+const LOG_STATS_BUCKETS: u64 = 60;
+
+/// Sources listed by name in `get_log_stats`; the rest are summed
+const LOG_STATS_MAX_SOURCES: usize = 20;
+
+/// Longest range `get_log_stats` accepts (30 days)
+const LOG_STATS_MAX_RANGE_SECS: u64 = 30 * 24 * 3600;
+
+/// Latest warnings and errors, oldest first
+static RECENT_ALERTS: Mutex<AlertLog> = Mutex::new(AlertLog::new());
+
 // =============================================================================
 // Types
 // =============================================================================
@@ -24,6 +74,27 @@ pub struct LogEntry {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub meta: Option<HashMap<String, String>>,
+    /// Identical consecutive lines this entry stands for (1 unless collapsed)
+    #[serde(default = "one")]
+    pub count: u64,
+    /// Timestamp of the last line collapsed into this entry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_ts: Option<String>,
+}
+
+fn one() -> u64 {
+    1
+}
+
+/// Time zone log timestamps are written and displayed in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogTimezone {
+    /// The machine's local zone, with its UTC offset
+    #[default]
+    Local,
+    /// UTC (`Z` suffix), for correlating with server logs
+    Utc,
 }
 
 /// Result type for log operations.
@@ -34,6 +105,116 @@ pub struct LogResult {
     pub logs: Vec<LogEntry>,
 }
 
+/// File format for `export_logs`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+/// Criteria an entry must meet to be exported. All fields are optional and
+/// combined with AND.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LogFilter {
+    /// Levels to include (case-insensitive), e.g. `["WARN", "ERROR"]`
+    pub levels: Option<Vec<String>>,
+    /// Exact source/target, e.g. `synthia_lib::pty`
+    pub source: Option<String>,
+    /// Case-insensitive substring of the message or source
+    pub query: Option<String>,
+    /// Inclusive lower bound on `ts` (`YYYY-MM-DDTHH:MM:SS` or a prefix of it)
+    pub since: Option<String>,
+    /// Inclusive upper bound on `ts` (`YYYY-MM-DDTHH:MM:SS` or a prefix of it)
+    pub until: Option<String>,
+}
+
+impl LogFilter {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        if let Some(levels) = &self.levels {
+            if !levels.iter().any(|l| l.eq_ignore_ascii_case(&entry.level)) {
+                return false;
+            }
+        }
+        if let Some(source) = &self.source {
+            if entry.source != *source {
+                return false;
+            }
+        }
+        if let Some(query) = &self.query {
+            let query = query.to_lowercase();
+            if !entry.message.to_lowercase().contains(&query)
+                && !entry.source.to_lowercase().contains(&query)
+            {
+                return false;
+            }
+        }
+        // Timestamps are zero-padded, so string order is chronological
+        if let Some(since) = &self.since {
+            if entry.ts.as_str() < since.as_str() {
+                return false;
+            }
+        }
+        if let Some(until) = &self.until {
+            // Compare only the given precision so `until: "2024-02-04"` includes that day
+            let ts = entry.ts.get(..until.len()).unwrap_or(&entry.ts);
+            if ts > until.as_str() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Summary returned by `export_logs`.
+#[derive(Debug, Serialize)]
+pub struct LogExportSummary {
+    pub path: String,
+    pub format: ExportFormat,
+    /// Entries written to the file
+    pub exported: usize,
+    /// Entries in the log file before filtering
+    pub total: usize,
+    pub bytes: usize,
+}
+
+/// Result of `get_log_stats`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogStats {
+    pub range_secs: u64,
+    /// Width of each bucket
+    pub bucket_secs: u64,
+    /// Records in the range, repeats included
+    pub total: u64,
+    /// Records per level (uppercase)
+    pub levels: BTreeMap<String, u64>,
+    /// Busiest sources, most records first
+    pub sources: Vec<LogSourceCount>,
+    /// Records from sources not in `sources`
+    pub other_sources: u64,
+    /// Oldest first, covering the whole range
+    pub buckets: Vec<LogStatsBucket>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogSourceCount {
+    pub source: String,
+    pub count: u64,
+}
+
+/// Records in one time bucket of `get_log_stats`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogStatsBucket {
+    /// Start of the bucket (RFC 3339, zone from `logging.timezone`)
+    pub start: String,
+    pub total: u64,
+    /// ERROR and FATAL records
+    pub errors: u64,
+    /// `errors / total`, 0 for an empty bucket
+    pub error_rate: f64,
+}
+
 // =============================================================================
 // Log File Location
 // =============================================================================
@@ -43,90 +224,542 @@ pub struct LogResult {
 /// On macOS: ~/Library/Logs/{identifier}/{filename}.log
 /// On Linux: ~/.local/share/{identifier}/logs/{filename}.log
 /// On Windows: %APPDATA%/{identifier}/logs/{filename}.log
-fn get_log_file_path(app: &tauri::AppHandle) -> Option<PathBuf> {
+pub(crate) fn get_log_file_path(app: &tauri::AppHandle) -> Option<PathBuf> {
     let log_dir = app.path().app_log_dir().ok()?;
     Some(log_dir.join("synthia.log"))
 }
 
 // =============================================================================
-// Log Parsing
+// Timestamps
 // =============================================================================
 
-/// Parse a single log line into a LogEntry.
-///
-/// Expected format from tauri-plugin-log:
-/// `[2024-02-04][12:34:56][INFO][source] message`
-/// or
-/// `2024-02-04 12:34:56 INFO [source] message`
-fn parse_log_line(line: &str, index: usize) -> Option<LogEntry> {
-    // Skip empty lines
-    let line = line.trim();
-    if line.is_empty() {
-        return None;
+/// Mirrors `logging.timezone`; read by the log formatter on every record
+static LOG_IN_UTC: AtomicBool = AtomicBool::new(false);
+
+/// Apply the `logging.timezone` setting to new log lines and parsed entries.
+pub(crate) fn set_timezone(tz: LogTimezone) {
+    LOG_IN_UTC.store(tz == LogTimezone::Utc, Ordering::Relaxed);
+}
+
+fn current_timezone() -> LogTimezone {
+    if LOG_IN_UTC.load(Ordering::Relaxed) {
+        LogTimezone::Utc
+    } else {
+        LogTimezone::Local
     }
+}
 
-    // Try to parse tauri-plugin-log format
-    // Format: [date][time][LEVEL][target] message
-    if line.starts_with('[') {
-        return parse_bracketed_format(line, index);
+/// Format an instant as RFC 3339 with millisecond precision in `tz`.
+fn format_ts<Tz: TimeZone>(t: &DateTime<Tz>, tz: LogTimezone) -> String {
+    match tz {
+        LogTimezone::Utc => t.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Millis, true),
+        LogTimezone::Local => t.with_timezone(&Local).to_rfc3339_opts(SecondsFormat::Millis, false),
     }
+}
 
-    // Try space-separated format: date time LEVEL [target] message
-    parse_space_format(line, index)
+/// Normalize a parsed timestamp to RFC 3339 in `tz`.
+///
+/// Accepts RFC 3339 and the offset-less `YYYY-MM-DDTHH:MM:SS` written by
+/// older versions, which is interpreted as local time. Anything else is
+/// returned unchanged.
+fn normalize_ts(raw: &str, tz: LogTimezone) -> String {
+    let parsed = DateTime::parse_from_rfc3339(raw).ok().or_else(|| {
+        let naive = NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+        Local.from_local_datetime(&naive).earliest().map(|t| t.fixed_offset())
+    });
+    match parsed {
+        Some(t) => format_ts(&t, tz),
+        None => raw.to_string(),
+    }
 }
 
-/// Parse bracketed log format: [date][time][LEVEL][target] message
-fn parse_bracketed_format(line: &str, index: usize) -> Option<LogEntry> {
-    let mut parts = Vec::new();
-    let mut current = String::new();
-    let mut in_bracket = false;
-    let mut bracket_count = 0;
+/// tauri-plugin-log formatter: `[RFC 3339 timestamp][LEVEL][target] message`.
+pub(crate) fn format_record(out: FormatCallback, message: &Arguments, record: &log::Record) {
+    out.finish(format_args!(
+        "[{}][{}][{}] {}",
+        format_ts(&Utc::now(), current_timezone()),
+        record.level(),
+        record.target(),
+        message
+    ))
+}
 
-    for ch in line.chars() {
-        match ch {
-            '[' if !in_bracket => {
-                in_bracket = true;
-                bracket_count += 1;
-            }
-            ']' if in_bracket => {
-                in_bracket = false;
-                parts.push(current.clone());
-                current.clear();
-                if bracket_count >= 4 {
-                    // After 4 brackets, rest is message
-                    break;
+/// The message part of a line written by `format_record` (the
+/// `[ts][LEVEL][target] ` prefix removed), or the whole line.
+pub(crate) fn strip_record_prefix(line: &str) -> &str {
+    let message = (|| {
+        let (_, rest) = take_bracket(line)?;
+        let (_, rest) = take_bracket(rest)?;
+        let (_, rest) = take_bracket(rest)?;
+        Some(rest.strip_prefix(' ').unwrap_or(rest))
+    })();
+    message.unwrap_or(line)
+}
+
+// =============================================================================
+// Terminal Mirroring
+// =============================================================================
+
+/// Mirrors `logging.capture_terminal`; read by PTY readers on every chunk
+static CAPTURE_TERMINAL: AtomicBool = AtomicBool::new(false);
+
+/// Apply the `logging.capture_terminal` setting.
+pub(crate) fn set_capture_terminal(enabled: bool) {
+    CAPTURE_TERMINAL.store(enabled, Ordering::Relaxed);
+}
+
+pub(crate) fn capture_terminal() -> bool {
+    CAPTURE_TERMINAL.load(Ordering::Relaxed)
+}
+
+/// Line-buffers one session's ANSI-stripped output into log records with
+/// target `pty:<session>`.
+pub(crate) struct TerminalLogMirror {
+    target: String,
+    stripper: AnsiStripper,
+    partial: String,
+    /// Characters in `partial`
+    partial_chars: usize,
+}
+
+impl TerminalLogMirror {
+    pub(crate) fn new(session_id: &str) -> Self {
+        Self {
+            target: format!("pty:{}", session_id),
+            stripper: AnsiStripper::new(),
+            partial: String::new(),
+            partial_chars: 0,
+        }
+    }
+
+    /// Log every line completed by this output chunk.
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for line in self.feed(bytes) {
+            log::info!(target: &self.target, "{}", line);
+        }
+    }
+
+    /// Log a trailing line without a newline (session ended).
+    pub(crate) fn flush(&mut self) {
+        self.partial_chars = 0;
+        let line = std::mem::take(&mut self.partial);
+        if !line.trim().is_empty() {
+            log::info!(target: &self.target, "{}", line.trim_end());
+        }
+    }
+
+    /// Strip a chunk and return the non-blank lines it completes.
+    fn feed(&mut self, bytes: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for c in self.stripper.feed(bytes).chars() {
+            if c == '\n' {
+                self.partial_chars = 0;
+                let line = std::mem::take(&mut self.partial);
+                if !line.trim().is_empty() {
+                    lines.push(line.trim_end().to_string());
+                }
+            } else {
+                self.partial.push(c);
+                self.partial_chars += 1;
+                // Output without newlines (progress bars, minified dumps) is split
+                if self.partial_chars >= MIRROR_MAX_LINE_CHARS {
+                    self.partial_chars = 0;
+                    lines.push(std::mem::take(&mut self.partial));
                 }
             }
-            _ if in_bracket => {
-                current.push(ch);
+        }
+        lines
+    }
+}
+
+// =============================================================================
+// Burst Collapsing
+// =============================================================================
+
+/// Wraps the plugin's logger so a record identical to the previous one
+/// (level, target and message) is not written again. The repeats are
+/// reported as one summary record when a different record arrives, every
+/// `REPEAT_SUMMARY_INTERVAL` while the run lasts, and on flush.
+pub(crate) struct CollapsingLogger {
+    inner: Box<dyn Log>,
+    repeats: Mutex<RepeatTracker>,
+}
+
+impl CollapsingLogger {
+    pub(crate) fn new(inner: Box<dyn Log>) -> Self {
+        Self {
+            inner,
+            repeats: Mutex::new(RepeatTracker::default()),
+        }
+    }
+
+    fn tracker(&self) -> std::sync::MutexGuard<'_, RepeatTracker> {
+        // The tracker holds no invariants worth losing the log over
+        self.repeats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_summary(&self, summary: Option<RepeatSummary>) {
+        let Some(summary) = summary else { return };
+        self.inner.log(
+            &Record::builder()
+                .level(summary.level)
+                .target(&summary.target)
+                .args(format_args!("{}{} times", REPEAT_SUMMARY_PREFIX, summary.repeats))
+                .build(),
+        );
+    }
+}
+
+impl Log for CollapsingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.inner.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        // Decide under the lock, write outside it: a failing target reports
+        // on stderr, which is bridged back into the log
+        let (summary, write) =
+            self.tracker()
+                .observe(record.level(), record.target(), &message, Instant::now());
+        self.write_summary(summary);
+        if write {
+            self.inner.log(record);
+        }
+
+        if record.level() <= Level::Warn {
+            let ts = format_ts(&Utc::now(), current_timezone());
+            let mut alerts = RECENT_ALERTS.lock().unwrap_or_else(|e| e.into_inner());
+            if write {
+                alerts.push(record.level(), record.target(), message, ts);
+            } else {
+                alerts.repeat(ts);
             }
-            _ => {}
         }
     }
 
-    // Get remaining message after the last bracket
-    let msg_start = line.rfind(']').map(|i| i + 1).unwrap_or(0);
-    let message = line[msg_start..].trim().to_string();
+    fn flush(&self) {
+        let summary = self.tracker().take_summary(Instant::now());
+        self.write_summary(summary);
+        self.inner.flush();
+    }
+}
 
-    if parts.len() >= 4 {
-        Some(LogEntry {
-            id: format!("L-{:04}", index + 1),
-            ts: format!("{}T{}", parts.get(0).unwrap_or(&String::new()), parts.get(1).unwrap_or(&String::new())),
-            level: parts.get(2).unwrap_or(&"INFO".to_string()).to_uppercase(),
-            source: parts.get(3).unwrap_or(&"app".to_string()).to_string(),
+/// Repeats suppressed since the last line written for a run.
+#[derive(Debug, PartialEq)]
+struct RepeatSummary {
+    level: Level,
+    target: String,
+    repeats: u64,
+}
+
+/// The last record written and how often it has repeated since.
+#[derive(Default)]
+struct RepeatTracker {
+    last: Option<(Level, String, String)>,
+    repeats: u64,
+    /// When the current run was last written or summarized
+    since: Option<Instant>,
+}
+
+impl RepeatTracker {
+    /// Record a log call. Returns a summary to write first, and whether the
+    /// record itself should be written.
+    fn observe(
+        &mut self,
+        level: Level,
+        target: &str,
+        message: &str,
+        now: Instant,
+    ) -> (Option<RepeatSummary>, bool) {
+        let same = self
+            .last
+            .as_ref()
+            .is_some_and(|(l, t, m)| *l == level && t == target && m == message);
+        if same {
+            self.repeats += 1;
+            let due = self
+                .since
+                .is_some_and(|t| now.duration_since(t) >= REPEAT_SUMMARY_INTERVAL);
+            return (due.then(|| self.take_summary(now)).flatten(), false);
+        }
+
+        let summary = self.take_summary(now);
+        self.last = Some((level, target.to_string(), message.to_string()));
+        self.since = Some(now);
+        (summary, true)
+    }
+
+    /// Summary of the repeats not yet reported, if any.
+    fn take_summary(&mut self, now: Instant) -> Option<RepeatSummary> {
+        let repeats = std::mem::take(&mut self.repeats);
+        let (level, target, _) = self.last.as_ref().filter(|_| repeats > 0)?;
+        self.since = Some(now);
+        Some(RepeatSummary {
+            level: *level,
+            target: target.clone(),
+            repeats,
+        })
+    }
+}
+
+/// Ring of the latest warning and error records as log entries.
+struct AlertLog {
+    entries: VecDeque<LogEntry>,
+    /// Alerts recorded since startup, for entry ids
+    total: u64,
+    /// Error records since startup, repeats included
+    errors: u64,
+    /// `errors` when they were last marked read
+    errors_read: u64,
+}
+
+impl AlertLog {
+    const fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            total: 0,
+            errors: 0,
+            errors_read: 0,
+        }
+    }
+
+    fn push(&mut self, level: Level, target: &str, message: String, ts: String) {
+        self.total += 1;
+        if level == Level::Error {
+            self.errors += 1;
+        }
+        self.entries.push_back(LogEntry {
+            id: format!("A-{:04}", self.total),
+            ts,
+            level: level.to_string(),
+            source: target.to_string(),
             message,
             meta: None,
+            count: 1,
+            last_ts: None,
+        });
+        if self.entries.len() > MAX_RECENT_ALERTS {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Count a repeat of the latest alert (suppressed by `CollapsingLogger`).
+    fn repeat(&mut self, ts: String) {
+        if let Some(last) = self.entries.back_mut() {
+            last.count += 1;
+            last.last_ts = Some(ts);
+            if last.level == Level::Error.as_str() {
+                self.errors += 1;
+            }
+        }
+    }
+
+    fn unread_errors(&self) -> u64 {
+        self.errors - self.errors_read
+    }
+}
+
+/// The latest warnings and errors logged since startup, newest first, with
+/// runs of identical records collapsed.
+pub(crate) fn recent_alerts() -> Vec<LogEntry> {
+    let alerts = RECENT_ALERTS.lock().unwrap_or_else(|e| e.into_inner());
+    alerts.entries.iter().rev().cloned().collect()
+}
+
+/// Errors logged since they were last marked read with `mark_errors_read`.
+pub(crate) fn unread_error_count() -> u64 {
+    RECENT_ALERTS.lock().unwrap_or_else(|e| e.into_inner()).unread_errors()
+}
+
+/// The count in a `last message repeated N times` summary message.
+fn parse_repeat_summary(message: &str) -> Option<u64> {
+    message
+        .strip_prefix(REPEAT_SUMMARY_PREFIX)?
+        .strip_suffix(" times")?
+        .parse()
+        .ok()
+}
+
+/// Fold identical consecutive entries (same level, source and message), and
+/// the summaries written for their repeats, into the first of them.
+fn collapse_repeats(entries: Vec<LogEntry>) -> Vec<LogEntry> {
+    let mut out: Vec<LogEntry> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if let Some(prev) = out
+            .last_mut()
+            .filter(|p| p.level == entry.level && p.source == entry.source)
+        {
+            let repeats = if prev.message == entry.message {
+                Some(entry.count)
+            } else {
+                parse_repeat_summary(&entry.message)
+            };
+            if let Some(repeats) = repeats {
+                prev.count += repeats;
+                prev.last_ts = Some(entry.last_ts.unwrap_or(entry.ts));
+                continue;
+            }
+        }
+        out.push(entry);
+    }
+    out
+}
+
+// =============================================================================
+// Statistics
+// =============================================================================
+
+/// Counts of the records in the `range_secs` before `now`. A repeat summary
+/// counts as the records it stands for, at its own time; entries whose
+/// timestamp can't be parsed are skipped.
+fn compute_log_stats(
+    entries: &[LogEntry],
+    now: DateTime<Utc>,
+    range_secs: u64,
+    tz: LogTimezone,
+) -> LogStats {
+    let bucket_secs = range_secs.div_ceil(LOG_STATS_BUCKETS).max(1);
+    let start = now - chrono::Duration::seconds(range_secs as i64);
+    let mut buckets: Vec<LogStatsBucket> = (0..range_secs.div_ceil(bucket_secs))
+        .map(|i| LogStatsBucket {
+            start: format_ts(&(start + chrono::Duration::seconds((i * bucket_secs) as i64)), tz),
+            total: 0,
+            errors: 0,
+            error_rate: 0.0,
         })
-    } else {
-        // Fallback: treat whole line as message
-        Some(LogEntry {
+        .collect();
+    let mut levels: BTreeMap<String, u64> = BTreeMap::new();
+    let mut sources: HashMap<&str, u64> = HashMap::new();
+    let mut total = 0;
+
+    for entry in entries {
+        let Ok(ts) = DateTime::parse_from_rfc3339(&entry.ts) else {
+            continue;
+        };
+        let offset = (ts.with_timezone(&Utc) - start).num_seconds();
+        if offset < 0 || offset > range_secs as i64 {
+            continue;
+        }
+        let count = parse_repeat_summary(&entry.message).unwrap_or(entry.count);
+        let level = entry.level.to_uppercase();
+        // A record logged exactly at `now` belongs to the last bucket
+        let index = (offset as u64 / bucket_secs).min(buckets.len() as u64 - 1) as usize;
+        let bucket = &mut buckets[index];
+        bucket.total += count;
+        if level == "ERROR" || level == "FATAL" {
+            bucket.errors += count;
+        }
+        *levels.entry(level).or_default() += count;
+        *sources.entry(entry.source.as_str()).or_default() += count;
+        total += count;
+    }
+
+    for bucket in &mut buckets {
+        if bucket.total > 0 {
+            bucket.error_rate = bucket.errors as f64 / bucket.total as f64;
+        }
+    }
+    let mut sources: Vec<LogSourceCount> = sources
+        .into_iter()
+        .map(|(source, count)| LogSourceCount {
+            source: source.to_string(),
+            count,
+        })
+        .collect();
+    sources.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.source.cmp(&b.source)));
+    let other_sources = sources.iter().skip(LOG_STATS_MAX_SOURCES).map(|s| s.count).sum();
+    sources.truncate(LOG_STATS_MAX_SOURCES);
+
+    LogStats {
+        range_secs,
+        bucket_secs,
+        total,
+        levels,
+        sources,
+        other_sources,
+        buckets,
+    }
+}
+
+// =============================================================================
+// Log Parsing
+// =============================================================================
+
+/// Parse a single log line into a LogEntry, with `ts` normalized to `tz`.
+///
+/// Expected formats:
+/// `[2024-02-04T12:34:56.789+02:00][INFO][source] message` (current),
+/// `[2024-02-04][12:34:56][INFO][source] message` (plugin default),
+/// or `2024-02-04 12:34:56 INFO [source] message`
+fn parse_log_line(line: &str, index: usize, tz: LogTimezone) -> Option<LogEntry> {
+    // Skip empty lines
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+
+    if line.starts_with('[') {
+        return Some(parse_bracketed_format(line, index, tz));
+    }
+
+    // Try space-separated format: date time LEVEL [target] message
+    Some(parse_space_format(line, index, tz))
+}
+
+/// Split a leading `[...]` group off `s`, returning its contents and the rest.
+fn take_bracket(s: &str) -> Option<(&str, &str)> {
+    let rest = s.strip_prefix('[')?;
+    let end = rest.find(']')?;
+    Some((&rest[..end], &rest[end + 1..]))
+}
+
+/// Parse bracketed log format: `[ts][LEVEL][target] message`, where `ts` is
+/// either one RFC 3339 group or separate `[date][time]` groups.
+fn parse_bracketed_format(line: &str, index: usize, tz: LogTimezone) -> LogEntry {
+    let header = (|| {
+        let (first, rest) = take_bracket(line)?;
+        let (ts, rest) = if DateTime::parse_from_rfc3339(first).is_ok() {
+            (first.to_string(), rest)
+        } else {
+            let (time, rest) = take_bracket(rest)?;
+            (format!("{}T{}", first, time), rest)
+        };
+        let (level, rest) = take_bracket(rest)?;
+        let (source, message) = take_bracket(rest)?;
+        Some((ts, level, source, message))
+    })();
+
+    match header {
+        Some((ts, level, source, message)) => LogEntry {
             id: format!("L-{:04}", index + 1),
-            ts: chrono::Local::now().format("%H:%M:%S").to_string(),
-            level: "INFO".to_string(),
-            source: "app".to_string(),
-            message: line.to_string(),
+            ts: normalize_ts(&ts, tz),
+            level: level.to_uppercase(),
+            source: source.to_string(),
+            message: message.trim().to_string(),
             meta: None,
-        })
+            count: 1,
+            last_ts: None,
+        },
+        // Fallback: treat whole line as message
+        None => fallback_entry(line, index, tz),
+    }
+}
+
+/// Entry for a line in no recognized format, stamped with the current time.
+fn fallback_entry(line: &str, index: usize, tz: LogTimezone) -> LogEntry {
+    LogEntry {
+        id: format!("L-{:04}", index + 1),
+        ts: format_ts(&Utc::now(), tz),
+        level: "INFO".to_string(),
+        source: "app".to_string(),
+        message: line.to_string(),
+        meta: None,
+        count: 1,
+        last_ts: None,
     }
 }
 
@@ -139,7 +772,7 @@ fn is_valid_log_level(s: &str) -> bool {
 }
 
 /// Parse space-separated format: date time LEVEL [target] message
-fn parse_space_format(line: &str, index: usize) -> Option<LogEntry> {
+fn parse_space_format(line: &str, index: usize, tz: LogTimezone) -> LogEntry {
     let parts: Vec<&str> = line.splitn(5, ' ').collect();
 
     // Only parse as structured if we have enough parts AND the third part is a valid log level
@@ -156,33 +789,89 @@ fn parse_space_format(line: &str, index: usize) -> Option<LogEntry> {
             ("app".to_string(), parts[3].to_string())
         };
 
-        Some(LogEntry {
+        LogEntry {
             id: format!("L-{:04}", index + 1),
-            ts: format!("{}T{}", date, time),
+            ts: normalize_ts(&format!("{}T{}", date, time), tz),
             level,
             source,
             message,
             meta: None,
-        })
+            count: 1,
+            last_ts: None,
+        }
     } else {
         // Fallback for unrecognized format: treat whole line as message
-        Some(LogEntry {
-            id: format!("L-{:04}", index + 1),
-            ts: chrono::Local::now().format("%H:%M:%S").to_string(),
-            level: "INFO".to_string(),
-            source: "app".to_string(),
-            message: line.to_string(),
-            meta: None,
-        })
+        fallback_entry(line, index, tz)
     }
 }
 
+/// Read and parse every entry in the log file, oldest first, with
+/// timestamps in the configured zone.
+pub(crate) async fn read_log_entries(log_path: &Path) -> Result<Vec<LogEntry>, AppError> {
+    let tz = current_timezone();
+    let file = tokio::fs::File::open(log_path)
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to open log file: {}", e)))?;
+
+    let mut lines = BufReader::new(file).lines();
+    let mut entries: Vec<LogEntry> = Vec::new();
+    let mut idx = 0;
+
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to read log file: {}", e)))?
+    {
+        if let Some(entry) = parse_log_line(&line, idx, tz) {
+            entries.push(entry);
+        }
+        idx += 1;
+    }
+
+    Ok(entries)
+}
+
+// =============================================================================
+// Export Formatting
+// =============================================================================
+
+/// CSV column order for exported entries
+const CSV_HEADER: &str = "id,ts,level,source,message";
+
+/// Quote a CSV field when it contains a delimiter, quote, or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Render entries as CSV with a header row.
+fn entries_to_csv(entries: &[LogEntry]) -> String {
+    let mut out = String::from(CSV_HEADER);
+    out.push('\n');
+    for e in entries {
+        let row = [&e.id, &e.ts, &e.level, &e.source, &e.message]
+            .iter()
+            .map(|v| csv_field(v))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push_str(&row);
+        out.push('\n');
+    }
+    out
+}
+
 // =============================================================================
 // Tauri Commands
 // =============================================================================
 
 /// Fetch logs from the application log file.
 ///
+/// Identical consecutive lines are returned as one entry whose `count` is
+/// the number of lines and `last_ts` the time of the last one.
+///
 /// # Arguments
 /// * `limit` - Maximum number of log entries to return (default: 1000)
 /// * `offset` - Number of entries to skip from the end (for pagination)
@@ -194,11 +883,11 @@ pub async fn get_logs(
     app: tauri::AppHandle,
     limit: Option<usize>,
     offset: Option<usize>,
-) -> Result<LogResult, String> {
+) -> Result<LogResult, AppError> {
     log::debug!("get_logs called with limit={:?}, offset={:?}", limit, offset);
 
     let log_path = get_log_file_path(&app)
-        .ok_or_else(|| "Could not determine log file path".to_string())?;
+        .ok_or_else(|| AppError::Internal("Could not determine log file path".into()))?;
 
     log::debug!("Reading logs from: {:?}", log_path);
 
@@ -212,27 +901,10 @@ pub async fn get_logs(
         });
     }
 
-    // Read and parse log file using async I/O
-    let file = tokio::fs::File::open(&log_path)
-        .await
-        .map_err(|e| format!("Failed to open log file: {}", e))?;
-
-    let reader = BufReader::new(file);
     let limit = limit.unwrap_or(1000);
     let offset = offset.unwrap_or(0);
 
-    // Parse all lines using async reader
-    let mut lines = reader.lines();
-    let mut entries: Vec<LogEntry> = Vec::new();
-    let mut idx = 0;
-
-    while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
-        if let Some(entry) = parse_log_line(&line, idx) {
-            entries.push(entry);
-        }
-        idx += 1;
-    }
-
+    let mut entries = collapse_repeats(read_log_entries(&log_path).await?);
     let total = entries.len();
 
     // Apply offset and limit (from the end, most recent first)
@@ -257,11 +929,11 @@ pub async fn get_logs(
 /// This truncates the log file rather than deleting it,
 /// so the logging system continues to work without interruption.
 #[tauri::command]
-pub async fn clear_logs(app: tauri::AppHandle) -> Result<bool, String> {
+pub async fn clear_logs(app: tauri::AppHandle) -> Result<bool, AppError> {
     log::info!("clear_logs called");
 
     let log_path = get_log_file_path(&app)
-        .ok_or_else(|| "Could not determine log file path".to_string())?;
+        .ok_or_else(|| AppError::Internal("Could not determine log file path".into()))?;
 
     if !log_path.exists() {
         log::debug!("Log file does not exist, nothing to clear");
@@ -274,21 +946,128 @@ pub async fn clear_logs(app: tauri::AppHandle) -> Result<bool, String> {
         .truncate(true)
         .open(&log_path)
         .await
-        .map_err(|e| format!("Failed to clear log file: {}", e))?;
+        .map_err(|e| AppError::Io(format!("Failed to clear log file: {}", e)))?;
 
     // Write a marker so we know the file was cleared
-    let marker = format!("[{}][{}][INFO][synthia] Log file cleared\n",
-        chrono::Local::now().format("%Y-%m-%d"),
-        chrono::Local::now().format("%H:%M:%S"));
+    let marker = format!(
+        "[{}][INFO][synthia] Log file cleared\n",
+        format_ts(&Utc::now(), current_timezone())
+    );
 
     file.write_all(marker.as_bytes())
         .await
-        .map_err(|e| format!("Failed to write clear marker: {}", e))?;
+        .map_err(|e| AppError::Io(format!("Failed to write clear marker: {}", e)))?;
 
     log::info!("Log file cleared successfully");
     Ok(true)
 }
 
+/// Export filtered log entries to a file as JSON or CSV.
+///
+/// # Arguments
+/// * `format` - `"json"` (array of entries) or `"csv"` (header + one row per entry)
+/// * `filter` - Optional criteria; omitted exports every entry
+/// * `path` - Absolute destination path, typically chosen in a save dialog.
+///   Its directory must exist; an existing file is overwritten.
+///
+/// # Returns
+/// A summary with the number of entries written and the file size.
+#[tauri::command]
+pub async fn export_logs(
+    app: tauri::AppHandle,
+    format: ExportFormat,
+    filter: Option<LogFilter>,
+    path: String,
+) -> Result<LogExportSummary, AppError> {
+    let dest = PathBuf::from(&path);
+    if !dest.is_absolute() {
+        return Err(AppError::Validation(format!("Export path must be absolute: {}", path)));
+    }
+    if dest.is_dir() {
+        return Err(AppError::Validation(format!("Export path is a directory: {}", path)));
+    }
+    match dest.parent() {
+        Some(dir) if dir.is_dir() => {}
+        _ => {
+            return Err(AppError::Validation(format!(
+                "Export directory does not exist: {}",
+                path
+            )))
+        }
+    }
+
+    let log_path = get_log_file_path(&app)
+        .ok_or_else(|| AppError::Internal("Could not determine log file path".into()))?;
+
+    let entries = if log_path.exists() {
+        read_log_entries(&log_path).await?
+    } else {
+        Vec::new()
+    };
+    let total = entries.len();
+
+    let filter = filter.unwrap_or_default();
+    let selected: Vec<LogEntry> = entries.into_iter().filter(|e| filter.matches(e)).collect();
+
+    let contents = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&selected)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize logs: {}", e)))?,
+        ExportFormat::Csv => entries_to_csv(&selected),
+    };
+
+    tokio::fs::write(&dest, contents.as_bytes())
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to write export file: {}", e)))?;
+
+    log::info!(
+        "Exported {} of {} log entries as {:?} to {:?}",
+        selected.len(),
+        total,
+        format,
+        dest
+    );
+
+    Ok(LogExportSummary {
+        path,
+        format,
+        exported: selected.len(),
+        total,
+        bytes: contents.len(),
+    })
+}
+
+/// Record counts per level and source, and the error rate over time, for
+/// the summary at the top of the Logs UI.
+///
+/// # Arguments
+/// * `range_secs` - How far back to look from now, in seconds (max 30 days),
+///   split into 60 buckets
+#[tauri::command]
+pub async fn get_log_stats(app: tauri::AppHandle, range_secs: u64) -> Result<LogStats, AppError> {
+    if !(1..=LOG_STATS_MAX_RANGE_SECS).contains(&range_secs) {
+        return Err(AppError::Validation(format!(
+            "Range must be 1-{} seconds, got: {}",
+            LOG_STATS_MAX_RANGE_SECS, range_secs
+        )));
+    }
+    let log_path = get_log_file_path(&app)
+        .ok_or_else(|| AppError::Internal("Could not determine log file path".into()))?;
+    let entries = if log_path.exists() {
+        read_log_entries(&log_path).await?
+    } else {
+        Vec::new()
+    };
+    Ok(compute_log_stats(&entries, Utc::now(), range_secs, current_timezone()))
+}
+
+/// Mark every error logged so far as read, resetting the unread count in
+/// `get_statusbar_data`. Called when the user opens the Logs UI.
+#[tauri::command]
+pub fn mark_errors_read() {
+    let mut alerts = RECENT_ALERTS.lock().unwrap_or_else(|e| e.into_inner());
+    alerts.errors_read = alerts.errors;
+}
+
 /// Get the path to the log file (for debugging/display purposes).
 ///
 /// # Security Note
@@ -296,9 +1075,9 @@ pub async fn clear_logs(app: tauri::AppHandle) -> Result<bool, String> {
 /// It only returns the path string, not file contents. The path is within the
 /// OS-standard app data directory and contains no sensitive information.
 #[tauri::command]
-pub async fn get_log_path(app: tauri::AppHandle) -> Result<String, String> {
+pub async fn get_log_path(app: tauri::AppHandle) -> Result<String, AppError> {
     let log_path = get_log_file_path(&app)
-        .ok_or_else(|| "Could not determine log file path".to_string())?;
+        .ok_or_else(|| AppError::Internal("Could not determine log file path".into()))?;
 
     Ok(log_path.to_string_lossy().to_string())
 }
@@ -314,7 +1093,7 @@ mod tests {
     #[test]
     fn test_parse_bracketed_format() {
         let line = "[2024-02-04][12:34:56][INFO][synthia] Application started";
-        let entry = parse_log_line(line, 0).unwrap();
+        let entry = parse_log_line(line, 0, LogTimezone::Local).unwrap();
 
         assert_eq!(entry.id, "L-0001");
         assert_eq!(entry.level, "INFO");
@@ -324,24 +1103,222 @@ mod tests {
 
     #[test]
     fn test_parse_empty_line() {
-        let entry = parse_log_line("", 0);
+        let entry = parse_log_line("", 0, LogTimezone::Local);
         assert!(entry.is_none());
     }
 
     #[test]
     fn test_parse_whitespace_line() {
-        let entry = parse_log_line("   ", 0);
+        let entry = parse_log_line("   ", 0, LogTimezone::Local);
         assert!(entry.is_none());
     }
 
     #[test]
     fn test_parse_fallback_format() {
         let line = "Some random log message";
-        let entry = parse_log_line(line, 5).unwrap();
+        let entry = parse_log_line(line, 5, LogTimezone::Local).unwrap();
 
         assert_eq!(entry.id, "L-0006");
         assert_eq!(entry.level, "INFO");
         assert_eq!(entry.source, "app");
         assert_eq!(entry.message, "Some random log message");
     }
+
+    #[test]
+    fn test_parse_rfc3339_format_to_utc() {
+        let line = "[2024-02-04T12:34:56.789+02:00][WARN][synthia_lib::pty] bound [::1]:9100";
+        let entry = parse_log_line(line, 0, LogTimezone::Utc).unwrap();
+
+        assert_eq!(entry.ts, "2024-02-04T10:34:56.789Z");
+        assert_eq!(entry.level, "WARN");
+        assert_eq!(entry.source, "synthia_lib::pty");
+        assert_eq!(entry.message, "bound [::1]:9100");
+    }
+
+    #[test]
+    fn test_normalize_ts() {
+        assert_eq!(
+            normalize_ts("2024-02-04T23:30:00-05:00", LogTimezone::Utc),
+            "2024-02-05T04:30:00.000Z"
+        );
+        // Offset-less timestamps are local time and gain the local offset
+        let local = normalize_ts("2024-02-04T12:34:56", LogTimezone::Local);
+        assert!(DateTime::parse_from_rfc3339(&local).is_ok());
+        assert!(local.starts_with("2024-02-04T12:34:56.000"));
+        assert_eq!(normalize_ts("garbage", LogTimezone::Utc), "garbage");
+    }
+
+    fn entry(ts: &str, level: &str, source: &str, message: &str) -> LogEntry {
+        LogEntry {
+            id: "L-0001".into(),
+            ts: ts.into(),
+            level: level.into(),
+            source: source.into(),
+            message: message.into(),
+            meta: None,
+            count: 1,
+            last_ts: None,
+        }
+    }
+
+    #[test]
+    fn test_filter_levels_and_query() {
+        let filter = LogFilter {
+            levels: Some(vec!["warn".into(), "ERROR".into()]),
+            query: Some("PORT".into()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&entry("2024-02-04T12:00:00", "ERROR", "app", "port in use")));
+        assert!(!filter.matches(&entry("2024-02-04T12:00:00", "INFO", "app", "port in use")));
+        assert!(!filter.matches(&entry("2024-02-04T12:00:00", "WARN", "app", "disk full")));
+    }
+
+    #[test]
+    fn test_compute_log_stats() {
+        let now = DateTime::parse_from_rfc3339("2024-02-04T12:00:00Z").unwrap().with_timezone(&Utc);
+        let entries = [
+            // Outside the hour
+            entry("2024-02-04T10:59:59Z", "ERROR", "app", "old"),
+            entry("2024-02-04T11:00:30Z", "info", "app", "start"),
+            entry("2024-02-04T11:00:40Z", "ERROR", "pty", "poll failed"),
+            entry("2024-02-04T11:01:00Z", "ERROR", "pty", "last message repeated 3 times"),
+            entry("2024-02-04T12:00:00Z", "WARN", "net", "slow"),
+            entry("garbage", "INFO", "app", "unparsed"),
+        ];
+        let stats = compute_log_stats(&entries, now, 3600, LogTimezone::Utc);
+        assert_eq!(stats.bucket_secs, 60);
+        assert_eq!(stats.buckets.len(), 60);
+        assert_eq!(stats.total, 6);
+        assert_eq!(stats.levels["ERROR"], 4);
+        assert_eq!(stats.levels["INFO"], 1);
+        assert_eq!(stats.sources[0], LogSourceCount { source: "pty".into(), count: 4 });
+        assert_eq!(stats.other_sources, 0);
+
+        let first = &stats.buckets[0];
+        assert!(first.start.starts_with("2024-02-04T11:00:00"));
+        assert_eq!((first.total, first.errors), (2, 1));
+        assert_eq!(first.error_rate, 0.5);
+        assert_eq!((stats.buckets[1].total, stats.buckets[1].errors), (3, 3));
+        assert_eq!(stats.buckets[59].total, 1);
+    }
+
+    #[test]
+    fn test_filter_time_range() {
+        let filter = LogFilter {
+            since: Some("2024-02-04T12:00".into()),
+            until: Some("2024-02-05".into()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&entry("2024-02-04T11:59:59", "INFO", "app", "m")));
+        assert!(filter.matches(&entry("2024-02-04T12:00:00", "INFO", "app", "m")));
+        assert!(filter.matches(&entry("2024-02-05T23:59:59", "INFO", "app", "m")));
+        assert!(!filter.matches(&entry("2024-02-06T00:00:00", "INFO", "app", "m")));
+    }
+
+    #[test]
+    fn test_entries_to_csv_escapes_fields() {
+        let csv = entries_to_csv(&[entry("2024-02-04T12:00:00", "INFO", "app", "said \"hi\", twice")]);
+        assert_eq!(
+            csv,
+            "id,ts,level,source,message\nL-0001,2024-02-04T12:00:00,INFO,app,\"said \"\"hi\"\", twice\"\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_record_prefix() {
+        assert_eq!(
+            strip_record_prefix("[2024-02-04T12:00:00.000Z][WARN][synthia_lib::pty] low [disk]"),
+            "low [disk]"
+        );
+        assert_eq!(strip_record_prefix("plain message"), "plain message");
+    }
+
+    #[test]
+    fn test_repeat_tracker() {
+        let mut tracker = RepeatTracker::default();
+        let t0 = Instant::now();
+        let poll_failed = |tracker: &mut RepeatTracker, secs| {
+            tracker.observe(Level::Error, "app", "poll failed", t0 + Duration::from_secs(secs))
+        };
+
+        assert_eq!(poll_failed(&mut tracker, 0), (None, true));
+        assert_eq!(poll_failed(&mut tracker, 1), (None, false));
+        assert_eq!(poll_failed(&mut tracker, 2), (None, false));
+        // A long run is summarized periodically
+        let summary = RepeatSummary {
+            level: Level::Error,
+            target: "app".into(),
+            repeats: 3,
+        };
+        assert_eq!(poll_failed(&mut tracker, 30), (Some(summary), false));
+        assert_eq!(poll_failed(&mut tracker, 31), (None, false));
+
+        // A different target ends the run
+        let (summary, write) =
+            tracker.observe(Level::Error, "net", "poll failed", t0 + Duration::from_secs(32));
+        assert_eq!(summary.map(|s| s.repeats), Some(1));
+        assert!(write);
+        assert_eq!(tracker.take_summary(t0), None);
+    }
+
+    #[test]
+    fn test_alert_log() {
+        let mut alerts = AlertLog::new();
+        alerts.repeat("t0".into());
+        for i in 0..MAX_RECENT_ALERTS + 2 {
+            alerts.push(Level::Warn, "app", format!("warning {}", i), "t1".into());
+        }
+        alerts.repeat("t2".into());
+
+        assert_eq!(alerts.entries.len(), MAX_RECENT_ALERTS);
+        assert_eq!(alerts.entries[0].message, "warning 2");
+        let last = alerts.entries.back().unwrap();
+        assert_eq!(last.id, format!("A-{:04}", MAX_RECENT_ALERTS + 2));
+        assert_eq!(last.level, "WARN");
+        assert_eq!((last.count, last.last_ts.as_deref()), (2, Some("t2")));
+        assert_eq!(alerts.unread_errors(), 0);
+
+        alerts.push(Level::Error, "app", "failed".into(), "t3".into());
+        alerts.repeat("t4".into());
+        assert_eq!(alerts.unread_errors(), 2);
+        alerts.errors_read = alerts.errors;
+        alerts.push(Level::Error, "app", "failed again".into(), "t5".into());
+        assert_eq!(alerts.unread_errors(), 1);
+    }
+
+    #[test]
+    fn test_collapse_repeats() {
+        let summary = format!("{}{} times", REPEAT_SUMMARY_PREFIX, 40);
+        assert_eq!(parse_repeat_summary(&summary), Some(40));
+        let entries = vec![
+            entry("2024-02-04T12:00:00", "ERROR", "app", "poll failed"),
+            entry("2024-02-04T12:00:01", "ERROR", "app", &summary),
+            entry("2024-02-04T12:00:02", "ERROR", "app", "poll failed"),
+            entry("2024-02-04T12:00:03", "WARN", "app", "poll failed"),
+            entry("2024-02-04T12:00:04", "INFO", "app", &summary),
+        ];
+
+        let collapsed = collapse_repeats(entries);
+        assert_eq!(collapsed.len(), 3);
+        assert_eq!(collapsed[0].count, 42);
+        assert_eq!(collapsed[0].last_ts.as_deref(), Some("2024-02-04T12:00:02"));
+        assert_eq!(collapsed[1].count, 1);
+        // A summary with nothing to attach to stays as is
+        assert_eq!(collapsed[2].message, summary);
+    }
+
+    #[test]
+    fn test_terminal_mirror_buffers_lines() {
+        let mut mirror = TerminalLogMirror::new("abc");
+        assert_eq!(mirror.target, "pty:abc");
+        assert!(mirror.feed(b"\x1b[32mCompil").is_empty());
+        assert_eq!(mirror.feed(b"ing\x1b[0m foo\r\n\r\n  \nDone"), vec!["Compiling foo"]);
+        assert_eq!(mirror.feed(b"\n"), vec!["Done"]);
+
+        let long = "x".repeat(MIRROR_MAX_LINE_CHARS + 5);
+        let lines = mirror.feed(long.as_bytes());
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].len(), MIRROR_MAX_LINE_CHARS);
+        assert_eq!(mirror.partial, "xxxxx");
+    }
 }
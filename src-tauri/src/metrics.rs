@@ -0,0 +1,905 @@
+//! Historical system metrics.
+//!
+//! A background sampler records CPU and memory usage once per second into a
+//! small SQLite database (`metrics.db` in the app data directory) and
+//! downsamples into coarser tiers so long ranges stay cheap to keep and query:
+//!
+//! | Tier | Resolution | Retention |
+//! |------|------------|-----------|
+//! | `1s` | 1 second   | 1 hour    |
+//! | `1m` | 1 minute   | 7 days    |
+//! | `1h` | 1 hour     | 365 days  |
+//!
+//! On Linux each sample also records pressure stall information (PSI) from
+//! `/proc/pressure/{cpu,memory,io}`: the share of the last 10 seconds in
+//! which some task was stalled on that resource. Utilization alone misses
+//! thrashing (memory at 90% may be fine or may be swapping constantly); PSI
+//! shows it directly. Elsewhere the pressure columns stay empty.
+//!
+//! It also reports Synthia's own footprint (`get_app_resource_usage`):
+//! process CPU and RSS, open PTYs, the screen capture thread's CPU and the
+//! rate of events emitted to the frontend (counted by `emit`).
+//!
+//! Network probe runs (`network_probe`) are kept in the same database, one
+//! row per target plus one per throughput test, for 30 days.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager, State};
+
+use crate::network_probe::NetworkProbeReport;
+use crate::power;
+use crate::pty::PtyState;
+use crate::streaming;
+use crate::tray;
+use crate::{AppError, SystemStats};
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// File name of the metrics database inside the app data directory
+const METRICS_DB_FILE_NAME: &str = "metrics.db";
+
+/// Interval between samples
+const SAMPLE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Longest range that can be queried (matches the coarsest tier's retention)
+const MAX_QUERY_RANGE_SECS: u64 = 365 * 24 * 3600;
+
+/// Window `get_app_resource_usage` measures CPU and event rates over
+/// (must be at least `sysinfo::MINIMUM_CPU_UPDATE_INTERVAL`)
+const USAGE_SAMPLE_WINDOW: Duration = Duration::from_millis(500);
+
+/// Kernel PSI files, one per resource
+#[cfg(target_os = "linux")]
+const PSI_DIR: &str = "/proc/pressure";
+
+/// How long network probe results are kept
+const NETWORK_PROBE_RETENTION_SECS: i64 = 30 * 24 * 3600;
+
+/// Events emitted to the frontend since startup (see `events::emit`)
+static EVENTS_EMITTED: AtomicU64 = AtomicU64::new(0);
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Storage tier / query resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Resolution {
+    #[serde(rename = "1s")]
+    Second,
+    #[serde(rename = "1m")]
+    Minute,
+    #[serde(rename = "1h")]
+    Hour,
+}
+
+impl Resolution {
+    fn table(self) -> &'static str {
+        match self {
+            Resolution::Second => "metrics_1s",
+            Resolution::Minute => "metrics_1m",
+            Resolution::Hour => "metrics_1h",
+        }
+    }
+
+    fn bucket_secs(self) -> i64 {
+        match self {
+            Resolution::Second => 1,
+            Resolution::Minute => 60,
+            Resolution::Hour => 3600,
+        }
+    }
+
+    fn retention_secs(self) -> i64 {
+        match self {
+            Resolution::Second => 3600,
+            Resolution::Minute => 7 * 24 * 3600,
+            Resolution::Hour => 365 * 24 * 3600,
+        }
+    }
+
+    /// Pick a resolution for a range when the caller doesn't specify one:
+    /// the finest tier that still keeps the result to a chartable size.
+    fn auto_for_range(range_secs: u64) -> Self {
+        if range_secs <= 3600 {
+            Resolution::Second
+        } else if range_secs <= 2 * 24 * 3600 {
+            Resolution::Minute
+        } else {
+            Resolution::Hour
+        }
+    }
+}
+
+/// A single data point returned to the frontend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricPoint {
+    /// Unix timestamp (seconds) of the sample or bucket start
+    pub ts: i64,
+    /// CPU usage percentage (0-100)
+    pub cpu: f32,
+    /// Memory usage percentage (0-100)
+    pub mem: f32,
+    /// Time some task was stalled on CPU, in percent (Linux PSI; None elsewhere)
+    pub cpu_pressure: Option<f32>,
+    /// Time some task was stalled on memory, in percent
+    pub mem_pressure: Option<f32>,
+    /// Time some task was stalled on I/O, in percent
+    pub io_pressure: Option<f32>,
+}
+
+/// PSI "some" stall percentages over the last 10 seconds. Fields are None
+/// where the kernel doesn't expose PSI (non-Linux, or `psi=0`).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pressure {
+    pub cpu: Option<f32>,
+    pub mem: Option<f32>,
+    pub io: Option<f32>,
+}
+
+/// Synthia's own resource footprint returned by `get_app_resource_usage`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AppResourceUsage {
+    pub pid: u32,
+    /// CPU used by the whole process, in percent of one core (can exceed 100)
+    pub cpu_percent: f32,
+    /// Resident memory in bytes
+    pub rss_bytes: u64,
+    pub open_ptys: usize,
+    /// CPU used by the screen capture thread, in percent of one core.
+    /// None when no stream is running or the platform can't measure it.
+    pub capture_cpu_percent: Option<f32>,
+    /// Events emitted to the frontend per second over the sample window
+    pub events_per_sec: f64,
+    pub events_total: u64,
+}
+
+/// One stored network probe result returned by `query_network_probes`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NetworkProbeSample {
+    /// Unix timestamp (seconds) of the run
+    pub ts: i64,
+    /// `icmp`, `tcp` or `throughput`
+    pub kind: String,
+    /// Probed host (`host:port` for TCP) or throughput URL
+    pub target: String,
+    /// Average round trip; None for throughput rows and fully lost probes
+    pub latency_ms: Option<f64>,
+    pub loss_percent: Option<f64>,
+    pub throughput_mbps: Option<f64>,
+}
+
+/// Shared state managed by Tauri. The connection is opened in `init`.
+#[derive(Default)]
+pub struct MetricsState {
+    db: Arc<Mutex<Option<Connection>>>,
+    /// Most recent sample taken by the sampler
+    latest: Arc<Mutex<Option<SystemStats>>>,
+}
+
+impl MetricsState {
+    /// The sampler's latest reading (CPU measured over the last second).
+    pub(crate) fn latest_stats(&self) -> Option<SystemStats> {
+        self.latest.lock().ok()?.clone()
+    }
+
+    /// Close the database, which stops the sampler at its next tick. Called
+    /// on app exit.
+    pub(crate) fn close(&self) {
+        let conn = self.db.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(Err((_, e))) = conn.map(Connection::close) {
+            log::warn!("Failed to close metrics database: {}", e);
+        }
+    }
+
+    /// Store a network probe run taken at `ts`. Failures are only logged.
+    pub(crate) fn record_network_probe(&self, ts: i64, report: &NetworkProbeReport) {
+        let Ok(guard) = self.db.lock() else {
+            return;
+        };
+        if let Some(conn) = guard.as_ref() {
+            if let Err(e) = insert_network_probe(conn, ts, report) {
+                log::warn!("Failed to record network probe: {}", e);
+            }
+        }
+    }
+}
+
+// =============================================================================
+// Database
+// =============================================================================
+
+/// Get the path to the metrics database.
+fn get_metrics_db_path(app: &AppHandle) -> Option<PathBuf> {
+    let data_dir = app.path().app_data_dir().ok()?;
+    Some(data_dir.join(METRICS_DB_FILE_NAME))
+}
+
+/// Create the tier tables if they don't exist yet.
+fn create_schema(conn: &Connection) -> rusqlite::Result<()> {
+    for tier in [Resolution::Second, Resolution::Minute, Resolution::Hour] {
+        conn.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (ts INTEGER PRIMARY KEY, cpu REAL NOT NULL, \
+                 mem REAL NOT NULL, cpu_pressure REAL, mem_pressure REAL, io_pressure REAL)",
+                tier.table()
+            ),
+            [],
+        )?;
+        // Databases created before PSI was recorded lack the pressure columns
+        for column in ["cpu_pressure", "mem_pressure", "io_pressure"] {
+            let exists: bool = conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+                params![tier.table(), column],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                conn.execute(
+                    &format!("ALTER TABLE {} ADD COLUMN {} REAL", tier.table(), column),
+                    [],
+                )?;
+            }
+        }
+    }
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS network_probes (ts INTEGER NOT NULL, kind TEXT NOT NULL, \
+         target TEXT NOT NULL, latency_ms REAL, loss_percent REAL, throughput_mbps REAL)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS network_probes_ts ON network_probes (ts)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn insert_sample(
+    conn: &Connection,
+    ts: i64,
+    stats: &SystemStats,
+    pressure: &Pressure,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO metrics_1s (ts, cpu, mem, cpu_pressure, mem_pressure, io_pressure)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![ts, stats.cpu, stats.mem, pressure.cpu, pressure.mem, pressure.io],
+    )?;
+    Ok(())
+}
+
+/// Average the `from` tier into `to` buckets for the two most recent complete
+/// `to` buckets before `now`. Re-aggregating a bucket is idempotent, so a
+/// missed tick (sleep, heavy load) is caught up on the next one.
+fn downsample(conn: &Connection, from: Resolution, to: Resolution, now: i64) -> rusqlite::Result<()> {
+    let bucket = to.bucket_secs();
+    let end = now - now.rem_euclid(bucket);
+    let start = end - 2 * bucket;
+
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO {to} (ts, cpu, mem, cpu_pressure, mem_pressure, io_pressure)
+             SELECT (ts / ?1) * ?1 AS bucket, AVG(cpu), AVG(mem),
+                    AVG(cpu_pressure), AVG(mem_pressure), AVG(io_pressure) FROM {from}
+             WHERE ts >= ?2 AND ts < ?3 GROUP BY bucket",
+            to = to.table(),
+            from = from.table()
+        ),
+        params![bucket, start, end],
+    )?;
+    Ok(())
+}
+
+/// Delete rows older than each tier's retention.
+fn prune(conn: &Connection, now: i64) -> rusqlite::Result<()> {
+    for tier in [Resolution::Second, Resolution::Minute, Resolution::Hour] {
+        conn.execute(
+            &format!("DELETE FROM {} WHERE ts < ?1", tier.table()),
+            params![now - tier.retention_secs()],
+        )?;
+    }
+    conn.execute(
+        "DELETE FROM network_probes WHERE ts < ?1",
+        params![now - NETWORK_PROBE_RETENTION_SECS],
+    )?;
+    Ok(())
+}
+
+fn insert_network_probe(
+    conn: &Connection,
+    ts: i64,
+    report: &NetworkProbeReport,
+) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO network_probes (ts, kind, target, latency_ms, loss_percent, throughput_mbps)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    for r in &report.latency {
+        stmt.execute(params![
+            ts,
+            r.kind.as_str(),
+            r.target,
+            r.avg_ms,
+            r.loss_percent,
+            None::<f64>
+        ])?;
+    }
+    if let Some(t) = &report.throughput {
+        stmt.execute(params![ts, "throughput", t.url, None::<f64>, None::<f64>, t.mbps])?;
+    }
+    Ok(())
+}
+
+fn query_network_probe_range(
+    conn: &Connection,
+    from_ts: i64,
+    to_ts: i64,
+) -> rusqlite::Result<Vec<NetworkProbeSample>> {
+    let mut stmt = conn.prepare(
+        "SELECT ts, kind, target, latency_ms, loss_percent, throughput_mbps FROM network_probes
+         WHERE ts >= ?1 AND ts <= ?2 ORDER BY ts, rowid",
+    )?;
+    let rows = stmt.query_map(params![from_ts, to_ts], |row| {
+        Ok(NetworkProbeSample {
+            ts: row.get(0)?,
+            kind: row.get(1)?,
+            target: row.get(2)?,
+            latency_ms: row.get(3)?,
+            loss_percent: row.get(4)?,
+            throughput_mbps: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn query_range(
+    conn: &Connection,
+    resolution: Resolution,
+    from_ts: i64,
+    to_ts: i64,
+) -> rusqlite::Result<Vec<MetricPoint>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT ts, cpu, mem, cpu_pressure, mem_pressure, io_pressure FROM {}
+         WHERE ts >= ?1 AND ts <= ?2 ORDER BY ts",
+        resolution.table()
+    ))?;
+
+    let rows = stmt.query_map(params![from_ts, to_ts], |row| {
+        Ok(MetricPoint {
+            ts: row.get(0)?,
+            cpu: row.get::<_, f64>(1)? as f32,
+            mem: row.get::<_, f64>(2)? as f32,
+            cpu_pressure: row.get::<_, Option<f64>>(3)?.map(|v| v as f32),
+            mem_pressure: row.get::<_, Option<f64>>(4)?.map(|v| v as f32),
+            io_pressure: row.get::<_, Option<f64>>(5)?.map(|v| v as f32),
+        })
+    })?;
+
+    rows.collect()
+}
+
+// =============================================================================
+// Pressure Stall Information
+// =============================================================================
+
+/// The `some avg10` value of a PSI file, e.g. from
+/// `some avg10=1.23 avg60=0.50 avg300=0.10 total=12345`.
+fn parse_psi_some_avg10(text: &str) -> Option<f32> {
+    text.lines()
+        .find_map(|line| line.strip_prefix("some "))?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
+
+/// Read the current PSI stall percentages.
+#[cfg(target_os = "linux")]
+fn read_pressure() -> Pressure {
+    let read = |resource: &str| {
+        std::fs::read_to_string(format!("{}/{}", PSI_DIR, resource))
+            .ok()
+            .and_then(|text| parse_psi_some_avg10(&text))
+    };
+    Pressure {
+        cpu: read("cpu"),
+        mem: read("memory"),
+        io: read("io"),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_pressure() -> Pressure {
+    Pressure::default()
+}
+
+// =============================================================================
+// Sampler
+// =============================================================================
+
+/// Open the metrics database and start the background sampler thread.
+pub fn init(app: &AppHandle) {
+    let path = match get_metrics_db_path(app) {
+        Some(p) => p,
+        None => {
+            log::error!("Could not determine metrics database path; metrics history disabled");
+            return;
+        }
+    };
+
+    if let Some(dir) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            log::error!("Failed to create data directory for metrics: {}", e);
+            return;
+        }
+    }
+
+    let conn = match Connection::open(&path).and_then(|c| create_schema(&c).map(|_| c)) {
+        Ok(c) => c,
+        Err(e) => {
+            log::error!("Failed to open metrics database {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    log::info!("Metrics history stored at {:?}", path);
+
+    let state = app.state::<MetricsState>();
+    let db = state.db.clone();
+    let latest = state.latest.clone();
+    match db.lock() {
+        Ok(mut guard) => *guard = Some(conn),
+        Err(e) => {
+            log::error!("Failed to lock metrics database: {}", e);
+            return;
+        }
+    }
+
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name("metrics-sampler".into())
+        .spawn(move || run_sampler(app, db, latest));
+    if let Err(e) = spawned {
+        log::error!("Failed to start metrics sampler: {}", e);
+    }
+}
+
+/// Sampler loop: one sample per second (less often in low-power mode), tier
+/// maintenance on minute/hour rollover. Each sample also refreshes the tray
+/// stats.
+fn run_sampler(
+    app: AppHandle,
+    db: Arc<Mutex<Option<Connection>>>,
+    latest: Arc<Mutex<Option<SystemStats>>>,
+) {
+    let mut sys = System::new();
+    let mut last_minute = 0;
+    let mut last_hour = 0;
+
+    loop {
+        std::thread::sleep(if power::is_low_power() {
+            power::LOW_POWER_SAMPLE_INTERVAL
+        } else {
+            SAMPLE_INTERVAL
+        });
+
+        let stats = crate::collect_system_stats(&mut sys);
+        let pressure = read_pressure();
+        let now = chrono::Utc::now().timestamp();
+        if let Ok(mut guard) = latest.lock() {
+            *guard = Some(stats.clone());
+        }
+        tray::update(&app, &stats);
+
+        let guard = match db.lock() {
+            Ok(g) => g,
+            Err(e) => {
+                log::error!("Metrics database lock poisoned, stopping sampler: {}", e);
+                return;
+            }
+        };
+        let conn = match guard.as_ref() {
+            Some(c) => c,
+            None => return,
+        };
+
+        if let Err(e) = insert_sample(conn, now, &stats, &pressure) {
+            log::warn!("Failed to record metrics sample: {}", e);
+        }
+
+        let minute = now / 60;
+        if minute != last_minute {
+            last_minute = minute;
+            if let Err(e) = downsample(conn, Resolution::Second, Resolution::Minute, now) {
+                log::warn!("Failed to downsample metrics to 1m: {}", e);
+            }
+        }
+
+        let hour = now / 3600;
+        if hour != last_hour {
+            last_hour = hour;
+            if let Err(e) = downsample(conn, Resolution::Minute, Resolution::Hour, now) {
+                log::warn!("Failed to downsample metrics to 1h: {}", e);
+            }
+            if let Err(e) = prune(conn, now) {
+                log::warn!("Failed to prune old metrics: {}", e);
+            }
+        }
+    }
+}
+
+// =============================================================================
+// App Resource Usage
+// =============================================================================
+
+/// Count an emitted event for `get_app_resource_usage`. Called by
+/// `events::emit`.
+pub(crate) fn count_event() {
+    EVENTS_EMITTED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// CPU time consumed by the calling thread, where the OS exposes it.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub(crate) fn thread_cpu_time() -> Option<Duration> {
+    use nix::time::{clock_gettime, ClockId};
+    clock_gettime(ClockId::CLOCK_THREAD_CPUTIME_ID)
+        .ok()
+        .map(Duration::from)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub(crate) fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// CPU time over wall time as a percentage of one core.
+fn cpu_percent(cpu: Duration, wall: Duration) -> f32 {
+    if wall.is_zero() {
+        return 0.0;
+    }
+    (cpu.as_secs_f64() / wall.as_secs_f64() * 100.0) as f32
+}
+
+/// Measure the process over `USAGE_SAMPLE_WINDOW` (blocking).
+fn sample_app_usage(open_ptys: usize) -> Result<AppResourceUsage, AppError> {
+    let pid = sysinfo::get_current_pid()
+        .map_err(|e| AppError::Internal(format!("Failed to get own pid: {}", e)))?;
+    let mut sys = System::new();
+    let mut refresh = || {
+        sys.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            true,
+            ProcessRefreshKind::new().with_cpu().with_memory(),
+        );
+    };
+
+    // Process CPU usage is the delta between two refreshes
+    refresh();
+    let start = Instant::now();
+    let events_start = EVENTS_EMITTED.load(Ordering::Relaxed);
+    let capture_start = streaming::capture_cpu_time();
+
+    std::thread::sleep(USAGE_SAMPLE_WINDOW);
+
+    refresh();
+    let wall = start.elapsed();
+    let events_total = EVENTS_EMITTED.load(Ordering::Relaxed);
+    let capture_end = streaming::capture_cpu_time();
+
+    let process = sys
+        .process(pid)
+        .ok_or_else(|| AppError::Internal("Own process not found".into()))?;
+    let capture_cpu_percent = match (capture_start, capture_end) {
+        (Some(a), Some(b)) => Some(cpu_percent(b.saturating_sub(a), wall)),
+        _ => None,
+    };
+
+    Ok(AppResourceUsage {
+        pid: pid.as_u32(),
+        cpu_percent: process.cpu_usage(),
+        rss_bytes: process.memory(),
+        open_ptys,
+        capture_cpu_percent,
+        events_per_sec: (events_total - events_start) as f64 / wall.as_secs_f64(),
+        events_total,
+    })
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Query historical CPU/memory metrics.
+///
+/// # Arguments
+/// * `range_secs` - How far back to look from now, in seconds
+/// * `resolution` - `"1s"`, `"1m"` or `"1h"` (default: picked from the range)
+///
+/// Returns points in ascending time order. Ranges longer than a tier's
+/// retention simply return what is still stored.
+#[tauri::command]
+pub async fn query_metrics(
+    state: State<'_, MetricsState>,
+    range_secs: u64,
+    resolution: Option<Resolution>,
+) -> Result<Vec<MetricPoint>, AppError> {
+    if !(1..=MAX_QUERY_RANGE_SECS).contains(&range_secs) {
+        return Err(AppError::Validation(format!(
+            "Range must be 1-{} seconds, got: {}",
+            MAX_QUERY_RANGE_SECS, range_secs
+        )));
+    }
+
+    let resolution = resolution.unwrap_or_else(|| Resolution::auto_for_range(range_secs));
+    let db = state.db.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let guard = db
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock metrics database: {}", e)))?;
+        let conn = guard
+            .as_ref()
+            .ok_or_else(|| AppError::Internal("Metrics history is not available".into()))?;
+
+        let now = chrono::Utc::now().timestamp();
+        query_range(conn, resolution, now - range_secs as i64, now)
+            .map_err(|e| AppError::Io(format!("Failed to query metrics: {}", e)))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Metrics query task failed: {}", e)))?
+}
+
+/// Query stored network probe results (see `run_network_probe`).
+///
+/// # Arguments
+/// * `range_secs` - How far back to look from now, in seconds (max 30 days)
+///
+/// Returns rows in ascending time order.
+#[tauri::command]
+pub async fn query_network_probes(
+    state: State<'_, MetricsState>,
+    range_secs: u64,
+) -> Result<Vec<NetworkProbeSample>, AppError> {
+    if !(1..=NETWORK_PROBE_RETENTION_SECS as u64).contains(&range_secs) {
+        return Err(AppError::Validation(format!(
+            "Range must be 1-{} seconds, got: {}",
+            NETWORK_PROBE_RETENTION_SECS, range_secs
+        )));
+    }
+
+    let db = state.db.clone();
+    tokio::task::spawn_blocking(move || {
+        let guard = db
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock metrics database: {}", e)))?;
+        let conn = guard
+            .as_ref()
+            .ok_or_else(|| AppError::Internal("Metrics history is not available".into()))?;
+
+        let now = chrono::Utc::now().timestamp();
+        query_network_probe_range(conn, now - range_secs as i64, now)
+            .map_err(|e| AppError::Io(format!("Failed to query network probes: {}", e)))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Network probe query task failed: {}", e)))?
+}
+
+/// Report Synthia's own CPU, memory, PTY count, capture-thread CPU and
+/// event-emit rate, measured over half a second.
+#[tauri::command]
+pub async fn get_app_resource_usage(
+    pty_state: State<'_, PtyState>,
+) -> Result<AppResourceUsage, AppError> {
+    let open_ptys = pty_state.sessions().len();
+
+    tokio::task::spawn_blocking(move || sample_app_usage(open_ptys))
+        .await
+        .map_err(|e| AppError::Internal(format!("Resource usage task failed: {}", e)))?
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+        conn
+    }
+
+    fn stats(cpu: f32, mem: f32) -> SystemStats {
+        SystemStats {
+            cpu,
+            mem,
+            mem_used_gb: 0.0,
+            mem_total_gb: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_cpu_percent() {
+        assert_eq!(cpu_percent(Duration::from_millis(250), Duration::from_millis(500)), 50.0);
+        assert_eq!(cpu_percent(Duration::from_secs(1), Duration::ZERO), 0.0);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_thread_cpu_time_advances() {
+        let before = thread_cpu_time().unwrap();
+        let mut x = 0u64;
+        while thread_cpu_time().unwrap() - before < Duration::from_millis(5) {
+            x = std::hint::black_box(x.wrapping_add(1));
+        }
+        assert!(x > 0);
+    }
+
+    #[test]
+    fn test_insert_and_query_seconds() {
+        let conn = test_db();
+        for i in 0..5 {
+            insert_sample(&conn, 1000 + i, &stats(i as f32, 50.0), &Pressure::default()).unwrap();
+        }
+
+        let points = query_range(&conn, Resolution::Second, 1001, 1003).unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].ts, 1001);
+        assert_eq!(points[2].cpu, 3.0);
+    }
+
+    #[test]
+    fn test_downsample_to_minutes() {
+        let conn = test_db();
+        // One full minute [120, 180) alternating 10% / 30% CPU
+        for i in 0..60 {
+            let cpu = if i % 2 == 0 { 10.0 } else { 30.0 };
+            insert_sample(&conn, 120 + i, &stats(cpu, 40.0), &Pressure::default()).unwrap();
+        }
+
+        downsample(&conn, Resolution::Second, Resolution::Minute, 185).unwrap();
+
+        let points = query_range(&conn, Resolution::Minute, 0, 1000).unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].ts, 120);
+        assert!((points[0].cpu - 20.0).abs() < 0.001);
+        assert!((points[0].mem - 40.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_downsample_skips_incomplete_bucket() {
+        let conn = test_db();
+        insert_sample(&conn, 185, &stats(50.0, 50.0), &Pressure::default()).unwrap();
+
+        // The minute starting at 180 is still in progress at t=190
+        downsample(&conn, Resolution::Second, Resolution::Minute, 190).unwrap();
+        assert!(query_range(&conn, Resolution::Minute, 0, 1000).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_prune_respects_retention() {
+        let conn = test_db();
+        let now = 10_000;
+        insert_sample(&conn, now - 3601, &stats(1.0, 1.0), &Pressure::default()).unwrap();
+        insert_sample(&conn, now - 10, &stats(2.0, 2.0), &Pressure::default()).unwrap();
+
+        prune(&conn, now).unwrap();
+
+        let points = query_range(&conn, Resolution::Second, 0, now).unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].cpu, 2.0);
+    }
+
+    #[test]
+    fn test_parse_psi() {
+        let text = "some avg10=12.34 avg60=5.00 avg300=1.00 total=123456\n\
+                    full avg10=2.00 avg60=1.00 avg300=0.50 total=4567\n";
+        assert_eq!(parse_psi_some_avg10(text), Some(12.34));
+        // The cpu file has no "full" line on older kernels
+        let cpu = "some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n";
+        assert_eq!(parse_psi_some_avg10(cpu), Some(0.0));
+        assert_eq!(parse_psi_some_avg10("full avg10=1.00\n"), None);
+        assert_eq!(parse_psi_some_avg10(""), None);
+    }
+
+    #[test]
+    fn test_pressure_stored_and_downsampled() {
+        let conn = test_db();
+        let pressure = Pressure {
+            cpu: Some(4.0),
+            mem: Some(20.0),
+            io: None,
+        };
+        insert_sample(&conn, 120, &stats(10.0, 40.0), &pressure).unwrap();
+        let higher = Pressure {
+            mem: Some(40.0),
+            ..pressure
+        };
+        insert_sample(&conn, 121, &stats(10.0, 40.0), &higher).unwrap();
+
+        let points = query_range(&conn, Resolution::Second, 0, 1000).unwrap();
+        assert_eq!(points[0].cpu_pressure, Some(4.0));
+        assert_eq!(points[0].io_pressure, None);
+
+        downsample(&conn, Resolution::Second, Resolution::Minute, 185).unwrap();
+        let points = query_range(&conn, Resolution::Minute, 0, 1000).unwrap();
+        assert_eq!(points[0].mem_pressure, Some(30.0));
+        assert_eq!(points[0].io_pressure, None);
+    }
+
+    #[test]
+    fn test_schema_adds_pressure_columns() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE metrics_1s (ts INTEGER PRIMARY KEY, cpu REAL NOT NULL, \
+             mem REAL NOT NULL)",
+            [],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO metrics_1s (ts, cpu, mem) VALUES (5, 1.0, 2.0)", [])
+            .unwrap();
+
+        create_schema(&conn).unwrap();
+        // Idempotent once migrated
+        create_schema(&conn).unwrap();
+
+        let points = query_range(&conn, Resolution::Second, 0, 10).unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].cpu_pressure, None);
+    }
+
+    #[test]
+    fn test_network_probe_rows() {
+        use crate::network_probe::{LatencyResult, ProbeKind, ThroughputResult};
+
+        let conn = test_db();
+        let report = NetworkProbeReport {
+            taken_at: String::new(),
+            latency: vec![LatencyResult {
+                target: "1.1.1.1".into(),
+                kind: ProbeKind::Icmp,
+                sent: 4,
+                received: 3,
+                loss_percent: 25.0,
+                min_ms: Some(9.0),
+                avg_ms: Some(10.5),
+                max_ms: Some(12.0),
+                error: None,
+            }],
+            throughput: Some(ThroughputResult {
+                url: "http://speed.lan/blob".into(),
+                bytes: 1_000_000,
+                duration_ms: 100,
+                mbps: Some(80.0),
+                error: None,
+            }),
+        };
+        insert_network_probe(&conn, 5000, &report).unwrap();
+        insert_network_probe(&conn, 10, &report).unwrap();
+
+        let rows = query_network_probe_range(&conn, 4000, 6000).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].kind, "icmp");
+        assert_eq!((rows[0].latency_ms, rows[0].loss_percent), (Some(10.5), Some(25.0)));
+        assert_eq!((rows[1].kind.as_str(), rows[1].throughput_mbps), ("throughput", Some(80.0)));
+
+        prune(&conn, 10 + NETWORK_PROBE_RETENTION_SECS + 1).unwrap();
+        assert_eq!(query_network_probe_range(&conn, 0, 6000).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_auto_resolution() {
+        assert_eq!(Resolution::auto_for_range(600), Resolution::Second);
+        assert_eq!(Resolution::auto_for_range(24 * 3600), Resolution::Minute);
+        assert_eq!(Resolution::auto_for_range(30 * 24 * 3600), Resolution::Hour);
+    }
+}
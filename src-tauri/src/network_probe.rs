@@ -0,0 +1,551 @@
+//! Network latency and throughput probes for the dashboard.
+//!
+//! `run_network_probe` measures round-trip latency to each target, by ICMP
+//! echo through the system `ping` (raw ICMP sockets need root) or by timing
+//! TCP connects. It can also time a download from `network.throughput_url`.
+//! Each run is stored in the metrics database, so `query_network_probes`
+//! can show whether a slow day was the network.
+
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tauri::State;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::metrics::MetricsState;
+use crate::settings::SettingsState;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Probes sent per target when `count` is omitted
+const DEFAULT_PROBE_COUNT: u32 = 4;
+
+/// Upper bound for `count`
+const MAX_PROBE_COUNT: u32 = 20;
+
+/// Upper bound for the number of targets in one run
+const MAX_PROBE_TARGETS: usize = 16;
+
+/// Wait for one echo reply or TCP connect
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The throughput download stops after this many bytes...
+const THROUGHPUT_MAX_BYTES: u64 = 50 * 1024 * 1024;
+
+/// ...or after this long, whichever comes first
+const THROUGHPUT_MAX_TIME: Duration = Duration::from_secs(10);
+
+/// Longest accepted host name
+const MAX_HOST_LEN: usize = 253;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// How a target's latency is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeKind {
+    /// ICMP echo (`ping`)
+    Icmp,
+    /// Time to establish a TCP connection to `port`
+    Tcp,
+}
+
+impl ProbeKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            ProbeKind::Icmp => "icmp",
+            ProbeKind::Tcp => "tcp",
+        }
+    }
+}
+
+/// A host to probe.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ProbeTarget {
+    pub host: String,
+    pub kind: ProbeKind,
+    /// Required for TCP probes
+    pub port: Option<u16>,
+}
+
+impl ProbeTarget {
+    /// `host` for ICMP, `host:port` for TCP.
+    fn label(&self) -> String {
+        match (self.kind, self.port) {
+            (ProbeKind::Tcp, Some(port)) if self.host.contains(':') => {
+                format!("[{}]:{}", self.host, port)
+            }
+            (ProbeKind::Tcp, Some(port)) => format!("{}:{}", self.host, port),
+            _ => self.host.clone(),
+        }
+    }
+}
+
+/// Latency of one target.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LatencyResult {
+    /// `host`, or `host:port` for TCP
+    pub target: String,
+    pub kind: ProbeKind,
+    pub sent: u32,
+    pub received: u32,
+    /// Percentage of probes without a reply (0-100)
+    pub loss_percent: f64,
+    pub min_ms: Option<f64>,
+    pub avg_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    /// Why no probe could be sent (e.g. unknown host), if so
+    pub error: Option<String>,
+}
+
+/// Result of the download from `network.throughput_url`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ThroughputResult {
+    pub url: String,
+    pub bytes: u64,
+    pub duration_ms: u64,
+    /// Megabits per second; None if the download failed
+    pub mbps: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Result of `run_network_probe`.
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkProbeReport {
+    /// RFC 3339 time the run started
+    pub taken_at: String,
+    /// One entry per target, in request order
+    pub latency: Vec<LatencyResult>,
+    /// Present when a throughput test was requested
+    pub throughput: Option<ThroughputResult>,
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Probe latency to `targets` and optionally measure download throughput.
+/// Targets are probed in parallel; the run is recorded for
+/// `query_network_probes`.
+///
+/// # Arguments
+/// * `targets` - Up to 16 hosts, each probed by ICMP or TCP connect
+/// * `count` - Probes per target (default 4, max 20)
+/// * `throughput` - Also download from `network.throughput_url` (default: false)
+#[tauri::command]
+pub async fn run_network_probe(
+    settings: State<'_, SettingsState>,
+    metrics_state: State<'_, MetricsState>,
+    targets: Vec<ProbeTarget>,
+    count: Option<u32>,
+    throughput: Option<bool>,
+) -> Result<NetworkProbeReport, AppError> {
+    let count = count.unwrap_or(DEFAULT_PROBE_COUNT);
+    check_probe_request(&targets, count)?;
+    let throughput_url = if throughput.unwrap_or(false) {
+        let url = settings.get().network.throughput_url;
+        if url.is_empty() {
+            return Err(AppError::Validation(
+                "Set network.throughput_url to run a throughput test".into(),
+            ));
+        }
+        Some(url)
+    } else {
+        None
+    };
+
+    let taken_at = chrono::Local::now();
+    let handles: Vec<_> = targets
+        .into_iter()
+        .map(|target| tokio::spawn(probe_latency(target, count)))
+        .collect();
+    let throughput = match throughput_url {
+        Some(url) => Some(measure_throughput(&url).await),
+        None => None,
+    };
+    let mut latency = Vec::with_capacity(handles.len());
+    for handle in handles {
+        latency.push(
+            handle
+                .await
+                .map_err(|e| AppError::Internal(format!("Probe task failed: {}", e)))?,
+        );
+    }
+
+    let report = NetworkProbeReport {
+        taken_at: taken_at.to_rfc3339(),
+        latency,
+        throughput,
+    };
+    log::info!(
+        "Network probe of {} target(s) finished in {} ms",
+        report.latency.len(),
+        (chrono::Local::now() - taken_at).num_milliseconds()
+    );
+    metrics_state.record_network_probe(taken_at.timestamp(), &report);
+    Ok(report)
+}
+
+/// Validate targets and count before anything is sent.
+fn check_probe_request(targets: &[ProbeTarget], count: u32) -> Result<(), AppError> {
+    if !(1..=MAX_PROBE_COUNT).contains(&count) {
+        return Err(AppError::Validation(format!(
+            "count must be 1-{}, got: {}",
+            MAX_PROBE_COUNT, count
+        )));
+    }
+    if targets.is_empty() || targets.len() > MAX_PROBE_TARGETS {
+        return Err(AppError::Validation(format!(
+            "Between 1 and {} targets are required, got: {}",
+            MAX_PROBE_TARGETS,
+            targets.len()
+        )));
+    }
+    for target in targets {
+        let host = target.host.as_str();
+        // A leading '-' would be read as an option by `ping`
+        if host.is_empty()
+            || host.len() > MAX_HOST_LEN
+            || host.starts_with('-')
+            || host.chars().any(|c| c.is_whitespace() || c.is_control())
+        {
+            return Err(AppError::Validation(format!("Invalid probe host: {:?}", host)));
+        }
+        if target.kind == ProbeKind::Tcp && !matches!(target.port, Some(p) if p > 0) {
+            return Err(AppError::Validation(format!("TCP probe of {} needs a port", host)));
+        }
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Latency
+// =============================================================================
+
+async fn probe_latency(target: ProbeTarget, count: u32) -> LatencyResult {
+    let samples = match target.kind {
+        ProbeKind::Icmp => ping(&target.host, count).await,
+        ProbeKind::Tcp => tcp_connect_times(&target.host, target.port.unwrap_or(0), count).await,
+    };
+    match samples {
+        Ok(samples) => latency_result(target.label(), target.kind, count, &samples),
+        Err(e) => LatencyResult {
+            error: Some(e.to_string()),
+            ..latency_result(target.label(), target.kind, count, &[])
+        },
+    }
+}
+
+/// Summarize round-trip times of the probes that were answered.
+fn latency_result(target: String, kind: ProbeKind, sent: u32, samples: &[f64]) -> LatencyResult {
+    let received = samples.len().min(sent as usize) as u32;
+    let avg = (!samples.is_empty()).then(|| samples.iter().sum::<f64>() / samples.len() as f64);
+    LatencyResult {
+        target,
+        kind,
+        sent,
+        received,
+        loss_percent: (sent - received) as f64 * 100.0 / sent as f64,
+        min_ms: samples.iter().copied().reduce(f64::min),
+        avg_ms: avg,
+        max_ms: samples.iter().copied().reduce(f64::max),
+        error: None,
+    }
+}
+
+/// Round-trip times of `count` echo requests, via the system `ping`.
+async fn ping(host: &str, count: u32) -> Result<Vec<f64>, AppError> {
+    let count = count.to_string();
+    let mut cmd = tokio::process::Command::new("ping");
+    if cfg!(windows) {
+        let wait_ms = PROBE_TIMEOUT.as_millis().to_string();
+        cmd.args(["-n", &count, "-w", &wait_ms, host]);
+    } else if cfg!(target_os = "macos") {
+        let wait_ms = PROBE_TIMEOUT.as_millis().to_string();
+        cmd.args(["-n", "-c", &count, "-W", &wait_ms, host]);
+    } else {
+        let wait_secs = PROBE_TIMEOUT.as_secs().to_string();
+        cmd.args(["-n", "-c", &count, "-W", &wait_secs, host]);
+    }
+    let output = cmd
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to run ping: {}", e)))?;
+
+    let samples = parse_ping_times(&String::from_utf8_lossy(&output.stdout));
+    // ping exits 1 when nothing answered, and 2 (or more) for errors like an unknown host
+    if samples.is_empty() && output.status.code().is_none_or(|c| c > 1) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Io(format!("ping {} failed: {}", host, stderr.trim())));
+    }
+    Ok(samples)
+}
+
+/// The `time=12.3 ms` (or Windows `time<1ms`) value of each reply line.
+fn parse_ping_times(output: &str) -> Vec<f64> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let start = line.find("time=").or_else(|| line.find("time<"))? + 5;
+            let value: String = line[start..]
+                .chars()
+                .take_while(|c| c.is_ascii_digit() || *c == '.')
+                .collect();
+            value.parse().ok()
+        })
+        .collect()
+}
+
+/// Times of `count` TCP connects to `host:port`, resolved once up front.
+/// Refused or timed-out connects count as lost.
+async fn tcp_connect_times(host: &str, port: u16, count: u32) -> Result<Vec<f64>, AppError> {
+    let addr: SocketAddr = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| AppError::Io(format!("Could not resolve {}: {}", host, e)))?
+        .next()
+        .ok_or_else(|| AppError::Io(format!("Could not resolve {}", host)))?;
+
+    let mut samples = Vec::new();
+    for _ in 0..count {
+        let start = Instant::now();
+        if let Ok(Ok(stream)) = tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(addr)).await
+        {
+            samples.push(start.elapsed().as_secs_f64() * 1000.0);
+            drop(stream);
+        }
+    }
+    Ok(samples)
+}
+
+// =============================================================================
+// Throughput
+// =============================================================================
+
+/// A parsed `http://host[:port][/path]` URL.
+#[derive(Debug, PartialEq)]
+pub(crate) struct HttpUrl {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+/// Parse `network.throughput_url`. There is no TLS client in the
+/// dependency tree, so only plain HTTP is accepted.
+pub(crate) fn parse_http_url(url: &str) -> Result<HttpUrl, AppError> {
+    let invalid = |reason: &str| {
+        AppError::Validation(format!("Invalid network.throughput_url '{}': {}", url, reason))
+    };
+    let rest = url
+        .trim()
+        .strip_prefix("http://")
+        .ok_or_else(|| invalid("expected http://"))?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+        let (host, after) = v6.split_once(']').ok_or_else(|| invalid("unclosed '['"))?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    if host.is_empty() {
+        return Err(invalid("missing host"));
+    }
+    let port = match port {
+        Some(p) => p.parse::<u16>().map_err(|_| invalid("bad port"))?,
+        None => 80,
+    };
+    Ok(HttpUrl {
+        host: host.to_string(),
+        port,
+        path: path.to_string(),
+    })
+}
+
+/// Download `url` for up to 50 MiB or 10 seconds and report the rate.
+async fn measure_throughput(url: &str) -> ThroughputResult {
+    let start = Instant::now();
+    let (bytes, error) = match download(url, start).await {
+        Ok(bytes) => (bytes, None),
+        Err((bytes, e)) => (bytes, Some(e.to_string())),
+    };
+    let elapsed = start.elapsed();
+    let mbps = (error.is_none() && !elapsed.is_zero())
+        .then(|| bytes as f64 * 8.0 / elapsed.as_secs_f64() / 1_000_000.0);
+    ThroughputResult {
+        url: url.to_string(),
+        bytes,
+        duration_ms: elapsed.as_millis() as u64,
+        mbps,
+        error,
+    }
+}
+
+/// GET `url`, counting body bytes. On failure, returns the bytes read so far.
+async fn download(url: &str, start: Instant) -> Result<u64, (u64, AppError)> {
+    let url = parse_http_url(url).map_err(|e| (0, e))?;
+    let mut stream = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        TcpStream::connect((url.host.as_str(), url.port)),
+    )
+    .await
+    .map_err(|_| (0, AppError::Timeout(format!("Connecting to {} timed out", url.host))))?
+    .map_err(|e| (0, io_error(e)))?;
+    let host = if url.host.contains(':') {
+        format!("[{}]:{}", url.host, url.port)
+    } else {
+        format!("{}:{}", url.host, url.port)
+    };
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: synthia\r\nConnection: close\r\n\r\n",
+        url.path, host
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| (0, io_error(e)))?;
+
+    let mut buf = vec![0u8; 64 * 1024];
+    // Response head collected so far; None once the body has started
+    let mut head = Some(Vec::new());
+    let mut body_bytes: u64 = 0;
+    let deadline = tokio::time::Instant::from_std(start + THROUGHPUT_MAX_TIME);
+    while body_bytes < THROUGHPUT_MAX_BYTES {
+        let n = match tokio::time::timeout_at(deadline, stream.read(&mut buf)).await {
+            Err(_) => break,
+            Ok(Ok(n)) => n,
+            Ok(Err(e)) => return Err((body_bytes, io_error(e))),
+        };
+        if n == 0 {
+            break;
+        }
+        let Some(received) = head.as_mut() else {
+            body_bytes += n as u64;
+            continue;
+        };
+        received.extend_from_slice(&buf[..n]);
+        if let Some(end) = received.windows(4).position(|w| w == b"\r\n\r\n") {
+            check_status(&received[..end]).map_err(|e| (0, e))?;
+            body_bytes = (received.len() - end - 4) as u64;
+            head = None;
+        }
+    }
+    if head.is_some() {
+        return Err((0, AppError::Io("Throughput test: no complete HTTP response".into())));
+    }
+    Ok(body_bytes)
+}
+
+fn io_error(e: std::io::Error) -> AppError {
+    AppError::Io(format!("Throughput test: {}", e))
+}
+
+/// Fail unless the status line of a response head is 2xx.
+fn check_status(head: &[u8]) -> Result<(), AppError> {
+    let head = String::from_utf8_lossy(head);
+    let status_line = head.lines().next().unwrap_or("");
+    match status_line.split_whitespace().nth(1).and_then(|c| c.parse::<u16>().ok()) {
+        Some(code) if (200..300).contains(&code) => Ok(()),
+        Some(code) => Err(AppError::Io(format!("Throughput URL returned HTTP {}", code))),
+        None => Err(AppError::Io(format!("Bad HTTP response: {}", status_line.trim()))),
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(host: &str, kind: ProbeKind, port: Option<u16>) -> ProbeTarget {
+        ProbeTarget {
+            host: host.into(),
+            kind,
+            port,
+        }
+    }
+
+    #[test]
+    fn test_check_probe_request() {
+        let ok = [target("1.1.1.1", ProbeKind::Icmp, None)];
+        assert!(check_probe_request(&ok, 4).is_ok());
+        assert!(check_probe_request(&ok, 0).is_err());
+        assert!(check_probe_request(&[], 4).is_err());
+        assert!(check_probe_request(&[target("-f", ProbeKind::Icmp, None)], 4).is_err());
+        assert!(check_probe_request(&[target("a b", ProbeKind::Icmp, None)], 4).is_err());
+        assert!(check_probe_request(&[target("example.com", ProbeKind::Tcp, None)], 4).is_err());
+        assert_eq!(target("::1", ProbeKind::Tcp, Some(22)).label(), "[::1]:22");
+    }
+
+    #[test]
+    fn test_parse_ping_times() {
+        let linux = "PING 1.1.1.1 (1.1.1.1) 56(84) bytes of data.\n\
+            64 bytes from 1.1.1.1: icmp_seq=1 ttl=57 time=11.4 ms\n\
+            64 bytes from 1.1.1.1: icmp_seq=3 ttl=57 time=9.87 ms\n\
+            rtt min/avg/max/mdev = 9.870/10.635/11.400/0.765 ms";
+        assert_eq!(parse_ping_times(linux), vec![11.4, 9.87]);
+        let windows = "Reply from 10.0.0.1: bytes=32 time<1ms TTL=64\r\n\
+            Reply from 10.0.0.1: bytes=32 time=3ms TTL=64";
+        assert_eq!(parse_ping_times(windows), vec![1.0, 3.0]);
+    }
+
+    #[test]
+    fn test_latency_result() {
+        let r = latency_result("h".into(), ProbeKind::Tcp, 4, &[10.0, 30.0, 20.0]);
+        assert_eq!((r.received, r.loss_percent), (3, 25.0));
+        assert_eq!((r.min_ms, r.avg_ms, r.max_ms), (Some(10.0), Some(20.0), Some(30.0)));
+        let lost = latency_result("h".into(), ProbeKind::Icmp, 2, &[]);
+        assert_eq!((lost.loss_percent, lost.avg_ms), (100.0, None));
+    }
+
+    #[test]
+    fn test_parse_http_url() {
+        let url = parse_http_url("http://speed.lan:8080/100MB.bin").unwrap();
+        assert_eq!((url.host.as_str(), url.port), ("speed.lan", 8080));
+        assert_eq!(url.path, "/100MB.bin");
+        assert_eq!(parse_http_url("http://[::1]").unwrap().path, "/");
+        assert!(parse_http_url("https://speed.cloudflare.com").is_err());
+        assert!(parse_http_url("http://:80/").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tcp_probe_and_throughput_against_local_server() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = [0u8; 1024];
+                    if let Ok(n) = socket.read(&mut request).await {
+                        if n > 0 {
+                            let body = vec![b'x'; 100_000];
+                            let head = "HTTP/1.1 200 OK\r\nContent-Length: 100000\r\n\r\n";
+                            let _ = socket.write_all(head.as_bytes()).await;
+                            let _ = socket.write_all(&body).await;
+                        }
+                    }
+                });
+            }
+        });
+
+        let tcp = probe_latency(target("127.0.0.1", ProbeKind::Tcp, Some(port)), 3).await;
+        assert_eq!((tcp.received, tcp.error), (3, None));
+
+        let result = measure_throughput(&format!("http://127.0.0.1:{}/blob", port)).await;
+        assert_eq!((result.bytes, result.error), (100_000, None));
+        assert!(result.mbps.unwrap() > 0.0);
+    }
+}
@@ -0,0 +1,285 @@
+//! On-demand OCR of screen regions.
+//!
+//! Captures a single frame from a display, crops it to the requested region
+//! and runs it through the `tesseract` CLI, returning the recognized text
+//! with word-level bounding boxes. This lets agents read dialog boxes or
+//! terminal content in other applications.
+//!
+//! The CLI is used instead of linking libtesseract so the app has no hard
+//! native dependency — OCR simply reports an error when tesseract is not
+//! installed (`brew install tesseract` / `apt install tesseract-ocr`).
+
+use image::ImageEncoder;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+use crate::streaming;
+use crate::AppError;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Region of a display in captured-frame pixels (physical pixels on HiDPI).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CaptureRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A recognized word with its bounding box in display coordinates.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OcrWord {
+    pub text: String,
+    /// Recognition confidence (0-100)
+    pub confidence: f32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// OCR output returned to the frontend.
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrResult {
+    /// Recognized text with line breaks preserved
+    pub text: String,
+    pub words: Vec<OcrWord>,
+    /// The region that was recognized (full display when no rect was given)
+    pub region: CaptureRect,
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Capture a display and OCR a region of it.
+///
+/// # Arguments
+/// * `display_id` - Display to capture (default: main display)
+/// * `rect` - Region in captured-frame pixels (default: the whole display)
+#[tauri::command]
+pub async fn ocr_screen_region(
+    display_id: Option<u32>,
+    rect: Option<CaptureRect>,
+) -> Result<OcrResult, AppError> {
+    if !scap::is_supported() {
+        return Err(AppError::Capture("Screen capture not supported on this platform".into()));
+    }
+
+    if !scap::has_permission() {
+        return Err(AppError::PermissionDenied("Screen capture permission not granted".into()));
+    }
+
+    log::info!("OCR requested for display {:?}, rect {:?}", display_id, rect);
+
+    tokio::task::spawn_blocking(move || {
+        let target = streaming::find_display_target(display_id)
+            .ok_or_else(|| AppError::NotFound(format!("Display not found: {:?}", display_id)))?;
+        let frame = streaming::capture_single_frame(target)?;
+
+        let frame_w = frame.width as u32;
+        let frame_h = frame.height as u32;
+        let region = match rect {
+            Some(r) => validate_rect(r, frame_w, frame_h)?,
+            None => CaptureRect {
+                x: 0,
+                y: 0,
+                width: frame_w,
+                height: frame_h,
+            },
+        };
+
+        let rgb = crop_bgra_to_rgb(&frame.data, frame_w, region);
+        let tsv = run_tesseract(&rgb, region.width, region.height)?;
+        let (text, mut words) = parse_tesseract_tsv(&tsv);
+
+        // Translate word boxes from crop-relative to display coordinates
+        for w in &mut words {
+            w.x += region.x;
+            w.y += region.y;
+        }
+
+        log::debug!("OCR recognized {} word(s)", words.len());
+        Ok(OcrResult {
+            text,
+            words,
+            region,
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("OCR task failed: {}", e)))?
+}
+
+// =============================================================================
+// Helpers
+// =============================================================================
+
+/// Ensure a rect is non-empty and lies within the frame.
+fn validate_rect(rect: CaptureRect, frame_w: u32, frame_h: u32) -> Result<CaptureRect, AppError> {
+    let fits_x = rect.x.checked_add(rect.width).is_some_and(|r| r <= frame_w);
+    let fits_y = rect.y.checked_add(rect.height).is_some_and(|b| b <= frame_h);
+
+    if rect.width == 0 || rect.height == 0 || !fits_x || !fits_y {
+        return Err(AppError::Validation(format!(
+            "Region {}x{}+{}+{} is outside the {}x{} display",
+            rect.width, rect.height, rect.x, rect.y, frame_w, frame_h
+        )));
+    }
+    Ok(rect)
+}
+
+/// Copy a region of a BGRA frame into a packed RGB buffer.
+fn crop_bgra_to_rgb(data: &[u8], frame_w: u32, rect: CaptureRect) -> Vec<u8> {
+    let row_len = rect.width as usize;
+    let mut rgb = vec![0u8; row_len * rect.height as usize * 3];
+    for (y, out_row) in (rect.y..rect.y + rect.height).zip(rgb.chunks_exact_mut(row_len * 3)) {
+        let row = (y as usize * frame_w as usize + rect.x as usize) * 4;
+        crate::streaming::bgra_row_to_rgb(&data[row..row + row_len * 4], out_row);
+    }
+    rgb
+}
+
+/// Write the image to a temp PNG and run `tesseract <png> stdout tsv`.
+fn run_tesseract(rgb: &[u8], width: u32, height: u32) -> Result<String, AppError> {
+    let path = std::env::temp_dir().join(format!("synthia-ocr-{}.png", uuid::Uuid::new_v4()));
+
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(rgb, width, height, image::ExtendedColorType::Rgb8)
+        .map_err(|e| AppError::Capture(format!("PNG encode failed: {}", e)))?;
+    std::fs::write(&path, &png)
+        .map_err(|e| AppError::Io(format!("Failed to write OCR image: {}", e)))?;
+
+    let output = Command::new("tesseract")
+        .arg(&path)
+        .args(["stdout", "tsv"])
+        .output();
+
+    if let Err(e) = std::fs::remove_file(&path) {
+        log::debug!("Failed to remove OCR temp file {:?}: {}", path, e);
+    }
+
+    let output = output.map_err(|e| {
+        AppError::Io(format!(
+            "Failed to run tesseract ({}). Install it to enable OCR (e.g. `brew install tesseract`)",
+            e
+        ))
+    })?;
+
+    if !output.status.success() {
+        return Err(AppError::Internal(format!(
+            "tesseract failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parse tesseract TSV output into plain text and word boxes.
+///
+/// Columns: level, page, block, par, line, word, left, top, width, height,
+/// conf, text. Only word rows (level 5) with non-empty text are kept; words
+/// sharing a (block, par, line) key are joined into one text line.
+fn parse_tesseract_tsv(tsv: &str) -> (String, Vec<OcrWord>) {
+    let mut words = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+    let mut current_line: Option<(u32, u32, u32)> = None;
+
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.splitn(12, '\t').collect();
+        if cols.len() < 12 || cols[0] != "5" {
+            continue;
+        }
+
+        let text = cols[11].trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        let num = |i: usize| cols[i].parse::<u32>().unwrap_or(0);
+        let line_key = (num(2), num(3), num(4));
+
+        if current_line == Some(line_key) {
+            if let Some(last) = lines.last_mut() {
+                last.push(' ');
+                last.push_str(text);
+            }
+        } else {
+            lines.push(text.to_string());
+            current_line = Some(line_key);
+        }
+
+        words.push(OcrWord {
+            text: text.to_string(),
+            confidence: cols[10].parse().unwrap_or(0.0),
+            x: num(6),
+            y: num(7),
+            width: num(8),
+            height: num(9),
+        });
+    }
+
+    (lines.join("\n"), words)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_TSV: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t400\t100\t-1\t
+4\t1\t1\t1\t1\t0\t10\t10\t200\t20\t-1\t
+5\t1\t1\t1\t1\t1\t10\t10\t60\t20\t95.5\tSave
+5\t1\t1\t1\t1\t2\t80\t10\t70\t20\t91.2\tchanges?
+5\t1\t1\t1\t2\t1\t10\t40\t40\t20\t88.0\tCancel
+5\t1\t1\t1\t2\t2\t60\t40\t30\t20\t-1\t
+";
+
+    #[test]
+    fn test_parse_tesseract_tsv() {
+        let (text, words) = parse_tesseract_tsv(SAMPLE_TSV);
+        assert_eq!(text, "Save changes?\nCancel");
+        assert_eq!(words.len(), 3);
+        assert_eq!(words[0].text, "Save");
+        assert_eq!(words[0].confidence, 95.5);
+        assert_eq!((words[1].x, words[1].y, words[1].width), (80, 10, 70));
+    }
+
+    #[test]
+    fn test_parse_tesseract_tsv_empty() {
+        let (text, words) = parse_tesseract_tsv("");
+        assert!(text.is_empty());
+        assert!(words.is_empty());
+    }
+
+    #[test]
+    fn test_crop_bgra_to_rgb() {
+        // 2x2 frame, pixel value encodes position: B = x, G = y, R = 9
+        let data = vec![0, 0, 9, 255, 1, 0, 9, 255, 0, 1, 9, 255, 1, 1, 9, 255];
+        let rect = CaptureRect { x: 1, y: 1, width: 1, height: 1 };
+        assert_eq!(crop_bgra_to_rgb(&data, 2, rect), vec![9, 1, 1]);
+    }
+
+    #[test]
+    fn test_validate_rect() {
+        let ok = CaptureRect { x: 10, y: 10, width: 100, height: 50 };
+        assert!(validate_rect(ok, 200, 100).is_ok());
+
+        let too_wide = CaptureRect { x: 150, y: 0, width: 100, height: 10 };
+        assert!(validate_rect(too_wide, 200, 100).is_err());
+
+        let empty = CaptureRect { x: 0, y: 0, width: 0, height: 10 };
+        assert!(validate_rect(empty, 200, 100).is_err());
+
+        let overflow = CaptureRect { x: u32::MAX, y: 0, width: 2, height: 1 };
+        assert!(validate_rect(overflow, 200, 100).is_err());
+    }
+}
@@ -0,0 +1,433 @@
+//! Command palette search.
+//!
+//! `search_actions` fuzzily matches a query against everything the palette
+//! can jump to — terminal sessions, recently injected commands, log sources
+//! and built-in app actions — and returns ranked results. Matching runs in
+//! Rust so the palette stays responsive with hundreds of candidates.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::State;
+
+use crate::logging;
+use crate::pty::PtyState;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Results returned when `limit` is omitted
+const DEFAULT_RESULT_LIMIT: usize = 20;
+
+/// Upper bound for `limit`
+const MAX_RESULT_LIMIT: usize = 100;
+
+/// Longest query considered (extra characters are ignored)
+const MAX_QUERY_CHARS: usize = 64;
+
+/// Scoring weights for fuzzy matches
+const SCORE_MATCH: i64 = 16;
+const BONUS_BOUNDARY: i64 = 10;
+const BONUS_CONSECUTIVE: i64 = 8;
+const BONUS_FIRST_CHAR: i64 = 8;
+const PENALTY_GAP: i64 = 1;
+const PENALTY_LEADING_MAX: i64 = 8;
+
+/// Built-in app actions: (id, title)
+const APP_ACTIONS: &[(&str, &str)] = &[
+    ("nav.command_center", "Go to Command Center"),
+    ("nav.settings", "Open Settings"),
+    ("nav.logs", "Open Logs"),
+    ("terminal.new", "New Terminal"),
+    ("stream.start", "Start Screen Stream"),
+    ("stream.stop", "Stop Screen Stream"),
+    ("logs.export", "Export Logs"),
+    ("logs.clear", "Clear Logs"),
+];
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// What a palette result refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteKind {
+    Session,
+    Command,
+    LogSource,
+    Action,
+}
+
+/// A ranked command palette result.
+#[derive(Debug, Clone, Serialize)]
+pub struct PaletteResult {
+    pub kind: PaletteKind,
+    /// Session id, command text, log source, or action id
+    pub id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// Higher is better; 0 for an empty query
+    pub score: i64,
+    /// Character indices in `title` that matched the query (for highlighting)
+    pub positions: Vec<usize>,
+}
+
+/// Something the palette can jump to.
+struct Candidate {
+    kind: PaletteKind,
+    id: String,
+    title: String,
+    subtitle: Option<String>,
+}
+
+/// Log sources cached by log file modification time, so typing in the
+/// palette does not re-read the log file on every keystroke.
+#[derive(Default)]
+pub struct PaletteState {
+    log_sources: Mutex<Option<(SystemTime, Vec<String>)>>,
+}
+
+// =============================================================================
+// Fuzzy Matching
+// =============================================================================
+
+/// A successful fuzzy match.
+#[derive(Debug, PartialEq)]
+struct FuzzyMatch {
+    score: i64,
+    positions: Vec<usize>,
+}
+
+/// Whether position `j` starts a word (start, after a separator, or a
+/// lower→upper camelCase transition).
+fn is_boundary(chars: &[char], j: usize) -> bool {
+    if j == 0 {
+        return true;
+    }
+    let prev = chars[j - 1];
+    matches!(prev, ' ' | '/' | '-' | '_' | '.' | ':')
+        || (prev.is_lowercase() && chars[j].is_uppercase())
+}
+
+/// Score of matching a query character at candidate position `j`.
+fn char_score(chars: &[char], j: usize) -> i64 {
+    let mut score = SCORE_MATCH;
+    if is_boundary(chars, j) {
+        score += BONUS_BOUNDARY;
+    }
+    if j == 0 {
+        score += BONUS_FIRST_CHAR;
+    }
+    score
+}
+
+/// Case-insensitive subsequence match of `query` (already lowercased) in
+/// `candidate`, choosing the best-scoring alignment. Rewards word-boundary
+/// and consecutive matches and penalises gaps. Returns None unless every
+/// query character appears in order.
+fn fuzzy_match(query: &[char], candidate: &str) -> Option<FuzzyMatch> {
+    let chars: Vec<char> = candidate.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let (n, m) = (query.len(), chars.len());
+    if n == 0 || n > m {
+        return None;
+    }
+
+    // best[i][j]: best (score, predecessor) with query[i] matched at chars[j]
+    let mut best: Vec<Vec<Option<(i64, usize)>>> = vec![vec![None; m]; n];
+
+    for j in 0..m {
+        if lower[j] == query[0] {
+            let leading = (j as i64).min(PENALTY_LEADING_MAX);
+            best[0][j] = Some((char_score(&chars, j) - leading, usize::MAX));
+        }
+    }
+
+    for i in 1..n {
+        // Best predecessor at least one character back, with gap penalty applied
+        let mut gap: Option<(i64, usize)> = None;
+        for j in 1..m {
+            if j >= 2 {
+                gap = gap.map(|(s, k)| (s - PENALTY_GAP, k));
+                if let Some((s, _)) = best[i - 1][j - 2] {
+                    let s = s - PENALTY_GAP;
+                    if gap.is_none_or(|(g, _)| s > g) {
+                        gap = Some((s, j - 2));
+                    }
+                }
+            }
+            if lower[j] != query[i] {
+                continue;
+            }
+
+            let consecutive = best[i - 1][j - 1].map(|(s, _)| (s + BONUS_CONSECUTIVE, j - 1));
+            let prev = match (consecutive, gap) {
+                (Some(c), Some(g)) => Some(if c.0 >= g.0 { c } else { g }),
+                (c, g) => c.or(g),
+            };
+            if let Some((s, k)) = prev {
+                best[i][j] = Some((s + char_score(&chars, j), k));
+            }
+        }
+    }
+
+    let (mut j, score) = best[n - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, b)| b.map(|(s, _)| (j, s)))
+        .max_by_key(|&(_, s)| s)?;
+
+    let mut positions = vec![0; n];
+    for i in (0..n).rev() {
+        positions[i] = j;
+        j = best[i][j].map(|(_, k)| k).unwrap_or(usize::MAX);
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Rank candidates against a query. An empty query keeps the given order.
+fn rank(candidates: Vec<Candidate>, query: &str, limit: usize) -> Vec<PaletteResult> {
+    let query: Vec<char> = query
+        .trim()
+        .chars()
+        .take(MAX_QUERY_CHARS)
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+
+    let to_result = |c: Candidate, score: i64, positions: Vec<usize>| PaletteResult {
+        kind: c.kind,
+        id: c.id,
+        title: c.title,
+        subtitle: c.subtitle,
+        score,
+        positions,
+    };
+
+    if query.is_empty() {
+        return candidates
+            .into_iter()
+            .take(limit)
+            .map(|c| to_result(c, 0, Vec::new()))
+            .collect();
+    }
+
+    let mut results: Vec<PaletteResult> = candidates
+        .into_iter()
+        .filter_map(|c| {
+            if let Some(m) = fuzzy_match(&query, &c.title) {
+                return Some(to_result(c, m.score, m.positions));
+            }
+            // Subtitle matches rank below title matches and are not highlighted
+            let sub = c.subtitle.as_deref().and_then(|s| fuzzy_match(&query, s))?;
+            Some(to_result(c, sub.score / 2, Vec::new()))
+        })
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.title.len().cmp(&b.title.len()))
+    });
+    results.truncate(limit);
+    results
+}
+
+// =============================================================================
+// Candidate Sources
+// =============================================================================
+
+fn session_candidates(pty: &PtyState) -> Vec<Candidate> {
+    let sessions = pty.sessions();
+    let mut ids: Vec<&String> = sessions.keys().collect();
+    ids.sort();
+    ids.into_iter()
+        .map(|id| Candidate {
+            kind: PaletteKind::Session,
+            id: id.clone(),
+            title: format!("Terminal {}", id),
+            subtitle: sessions[id].foreground_command(),
+        })
+        .collect()
+}
+
+fn command_candidates(pty: &PtyState) -> Vec<Candidate> {
+    pty.recent_commands()
+        .into_iter()
+        .map(|command| Candidate {
+            kind: PaletteKind::Command,
+            id: command.clone(),
+            title: command,
+            subtitle: Some("Recent command".into()),
+        })
+        .collect()
+}
+
+fn action_candidates() -> Vec<Candidate> {
+    APP_ACTIONS
+        .iter()
+        .map(|(id, title)| Candidate {
+            kind: PaletteKind::Action,
+            id: id.to_string(),
+            title: title.to_string(),
+            subtitle: None,
+        })
+        .collect()
+}
+
+/// Distinct log sources, re-read only when the log file changed.
+async fn log_source_candidates(app: &tauri::AppHandle, palette: &PaletteState) -> Vec<Candidate> {
+    let Some(path) = logging::get_log_file_path(app) else {
+        return Vec::new();
+    };
+    let Ok(modified) = std::fs::metadata(&path).and_then(|m| m.modified()) else {
+        return Vec::new();
+    };
+
+    let cached = palette
+        .log_sources
+        .lock()
+        .ok()
+        .and_then(|c| c.as_ref().filter(|(t, _)| *t == modified).map(|(_, s)| s.clone()));
+
+    let sources = match cached {
+        Some(sources) => sources,
+        None => {
+            let mut sources: Vec<String> = match logging::read_log_entries(&path).await {
+                Ok(entries) => entries.into_iter().map(|e| e.source).collect(),
+                Err(e) => {
+                    log::warn!("Palette could not read log sources: {}", e);
+                    return Vec::new();
+                }
+            };
+            sources.sort();
+            sources.dedup();
+            if let Ok(mut cache) = palette.log_sources.lock() {
+                *cache = Some((modified, sources.clone()));
+            }
+            sources
+        }
+    };
+
+    sources
+        .into_iter()
+        .map(|source| Candidate {
+            kind: PaletteKind::LogSource,
+            title: source.clone(),
+            id: source,
+            subtitle: Some("Log source".into()),
+        })
+        .collect()
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Fuzzy search for the command palette.
+///
+/// # Arguments
+/// * `query` - Text typed into the palette; empty lists everything unranked
+/// * `limit` - Maximum results (default 20, max 100)
+///
+/// # Returns
+/// Results sorted best first, with matched character positions in `title`.
+#[tauri::command]
+pub async fn search_actions(
+    app: tauri::AppHandle,
+    pty: State<'_, PtyState>,
+    palette: State<'_, PaletteState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<PaletteResult>, AppError> {
+    let limit = limit.unwrap_or(DEFAULT_RESULT_LIMIT);
+    if !(1..=MAX_RESULT_LIMIT).contains(&limit) {
+        return Err(AppError::Validation(format!(
+            "Limit must be 1-{}, got: {}",
+            MAX_RESULT_LIMIT, limit
+        )));
+    }
+
+    let mut candidates = action_candidates();
+    candidates.extend(session_candidates(&pty));
+    candidates.extend(command_candidates(&pty));
+    candidates.extend(log_source_candidates(&app, &palette).await);
+
+    Ok(rank(candidates, &query, limit))
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    fn action(title: &str) -> Candidate {
+        Candidate {
+            kind: PaletteKind::Action,
+            id: title.into(),
+            title: title.into(),
+            subtitle: None,
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_requires_subsequence() {
+        assert!(fuzzy_match(&q("lgs"), "Open Logs").is_some());
+        assert!(fuzzy_match(&q("sgl"), "Open Logs").is_none());
+        assert!(fuzzy_match(&q("toolong"), "tool").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_prefers_word_boundaries() {
+        // "ss" should pick the start of "Screen" and "Stream", not "Stop"'s middle
+        let m = fuzzy_match(&q("ss"), "Start Screen Stream").unwrap();
+        assert_eq!(m.positions, vec![0, 6]);
+    }
+
+    #[test]
+    fn test_fuzzy_case_insensitive_positions() {
+        let m = fuzzy_match(&q("cb"), "cargo build").unwrap();
+        assert_eq!(m.positions, vec![0, 6]);
+    }
+
+    #[test]
+    fn test_rank_orders_by_score() {
+        let results = rank(
+            vec![action("Clear Logs"), action("Open Logs"), action("Open Settings")],
+            "logs",
+            10,
+        );
+        assert_eq!(results.len(), 2);
+        // Both match on a word boundary; the shorter title wins the tie
+        assert_eq!(results[0].title, "Open Logs");
+    }
+
+    #[test]
+    fn test_rank_empty_query_keeps_order_and_limit() {
+        let results = rank(vec![action("b"), action("a"), action("c")], "  ", 2);
+        let titles: Vec<&str> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_rank_matches_subtitle() {
+        let mut c = action("Terminal 1");
+        c.subtitle = Some("cargo build".into());
+        let results = rank(vec![c], "cargo", 10);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].positions.is_empty());
+    }
+}
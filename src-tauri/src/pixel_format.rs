@@ -0,0 +1,205 @@
+//! Pixel formats for the screen stream.
+//!
+//! Capture produces BGRA. A client picks the format it wants when it
+//! connects (`ws://127.0.0.1:<port>/?format=yuv420`). The capture thread
+//! converts each published frame once into every format in use, so clients
+//! do not each pay for the conversion. YUV420 is planar I420 (Y, then U,
+//! then V, with BT.601 limited-range coefficients). That is half the size
+//! of BGRA and is what video encoders take as input.
+
+use serde::{Deserialize, Serialize};
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Pixel layout of video message payloads, negotiated per client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PixelFormat {
+    /// 4 bytes per pixel, as captured (the frontend swaps R/B on the GPU)
+    #[default]
+    Bgra,
+    Rgba,
+    /// 3 bytes per pixel
+    Rgb,
+    /// Planar 4:2:0, 1.5 bytes per pixel. Frames and tiles have even sizes
+    /// (an odd last row or column is dropped)
+    Yuv420,
+}
+
+impl PixelFormat {
+    pub(crate) const ALL: [PixelFormat; 4] = [Self::Bgra, Self::Rgba, Self::Rgb, Self::Yuv420];
+
+    /// Parse the `format` query parameter of a stream connection.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "bgra" => Some(Self::Bgra),
+            "rgba" => Some(Self::Rgba),
+            "rgb" => Some(Self::Rgb),
+            "yuv420" | "i420" => Some(Self::Yuv420),
+            _ => None,
+        }
+    }
+
+    /// Position in `ALL`.
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Size of a `width` x `height` BGRA frame in this format.
+    pub(crate) fn frame_size(self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Self::Yuv420 => (width & !1, height & !1),
+            _ => (width, height),
+        }
+    }
+
+    /// Payload bytes of a `width` x `height` region (already `frame_size`d).
+    pub(crate) fn region_len(self, width: usize, height: usize) -> usize {
+        match self {
+            Self::Bgra | Self::Rgba => width * height * 4,
+            Self::Rgb => width * height * 3,
+            Self::Yuv420 => width * height * 3 / 2,
+        }
+    }
+}
+
+// =============================================================================
+// Conversion
+// =============================================================================
+
+/// Append the region at (`x`, `y`) of size `w` x `h` of a BGRA frame that
+/// is `stride` pixels wide, converted to `format`. For YUV420 the region's
+/// position and size must be even.
+pub(crate) fn append_region(
+    frame: &[u8],
+    stride: usize,
+    (x, y, w, h): (usize, usize, usize, usize),
+    format: PixelFormat,
+    out: &mut Vec<u8>,
+) {
+    out.reserve(format.region_len(w, h));
+    let row = |r: usize| {
+        let start = (r * stride + x) * 4;
+        &frame[start..start + w * 4]
+    };
+
+    match format {
+        PixelFormat::Bgra => {
+            for r in y..y + h {
+                out.extend_from_slice(row(r));
+            }
+        }
+        PixelFormat::Rgba => {
+            for r in y..y + h {
+                for px in row(r).chunks_exact(4) {
+                    out.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
+                }
+            }
+        }
+        PixelFormat::Rgb => {
+            for r in y..y + h {
+                for px in row(r).chunks_exact(4) {
+                    out.extend_from_slice(&[px[2], px[1], px[0]]);
+                }
+            }
+        }
+        PixelFormat::Yuv420 => {
+            debug_assert!(x % 2 == 0 && y % 2 == 0 && w % 2 == 0 && h % 2 == 0);
+            for r in y..y + h {
+                out.extend(row(r).chunks_exact(4).map(|px| luma(px[2], px[1], px[0])));
+            }
+            // U plane then V plane, one sample per 2x2 block
+            let start = out.len();
+            out.resize(start + w * h / 2, 0);
+            let (u_plane, v_plane) = out[start..].split_at_mut(w * h / 4);
+            for (i, top_y) in (y..y + h).step_by(2).enumerate() {
+                let (top, bottom) = (row(top_y), row(top_y + 1));
+                for j in 0..w / 2 {
+                    let o = j * 8;
+                    let avg = |c: usize| {
+                        let sum = top[o + c] as u32
+                            + top[o + 4 + c] as u32
+                            + bottom[o + c] as u32
+                            + bottom[o + 4 + c] as u32;
+                        ((sum + 2) / 4) as i32
+                    };
+                    let (b, g, r) = (avg(0), avg(1), avg(2));
+                    u_plane[i * w / 2 + j] = chroma(-38 * r - 74 * g + 112 * b);
+                    v_plane[i * w / 2 + j] = chroma(112 * r - 94 * g - 18 * b);
+                }
+            }
+        }
+    }
+}
+
+/// BT.601 limited-range luma.
+fn luma(r: u8, g: u8, b: u8) -> u8 {
+    let (r, g, b) = (r as i32, g as i32, b as i32);
+    (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8
+}
+
+/// BT.601 limited-range chroma from a weighted sum.
+fn chroma(weighted: i32) -> u8 {
+    (((weighted + 128) >> 8) + 128).clamp(0, 255) as u8
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2x2 BGRA frame: red, green / blue, white.
+    fn quad() -> Vec<u8> {
+        vec![
+            0, 0, 255, 255, 0, 255, 0, 255, //
+            255, 0, 0, 255, 255, 255, 255, 255,
+        ]
+    }
+
+    #[test]
+    fn test_parse_and_sizes() {
+        assert_eq!(PixelFormat::parse("YUV420"), Some(PixelFormat::Yuv420));
+        assert_eq!(PixelFormat::parse("rgb"), Some(PixelFormat::Rgb));
+        assert_eq!(PixelFormat::parse("nv12"), None);
+        assert_eq!(PixelFormat::Yuv420.frame_size(101, 51), (100, 50));
+        assert_eq!(PixelFormat::Rgb.frame_size(101, 51), (101, 51));
+        assert_eq!(PixelFormat::Yuv420.region_len(4, 2), 12);
+    }
+
+    #[test]
+    fn test_append_region_rgb_variants() {
+        let frame = quad();
+        let mut out = Vec::new();
+        append_region(&frame, 2, (1, 0, 1, 2), PixelFormat::Rgba, &mut out);
+        assert_eq!(out, vec![0, 255, 0, 255, 255, 255, 255, 255]);
+
+        out.clear();
+        append_region(&frame, 2, (0, 0, 2, 1), PixelFormat::Rgb, &mut out);
+        assert_eq!(out, vec![255, 0, 0, 0, 255, 0]);
+
+        out.clear();
+        append_region(&frame, 2, (0, 1, 2, 1), PixelFormat::Bgra, &mut out);
+        assert_eq!(out, frame[8..].to_vec());
+    }
+
+    #[test]
+    fn test_append_region_yuv420() {
+        let mut out = Vec::new();
+        append_region(&quad(), 2, (0, 0, 2, 2), PixelFormat::Yuv420, &mut out);
+        assert_eq!(out.len(), 6);
+        // Standard limited-range luma of red, green, blue and white
+        assert_eq!(&out[..4], &[82, 144, 41, 235]);
+        // The block averages to mid grey, which has neutral chroma
+        assert_eq!(&out[4..], &[128, 128]);
+
+        // Black maps to the bottom of the limited range
+        let mut out = Vec::new();
+        append_region(&[0u8; 16], 2, (0, 0, 2, 2), PixelFormat::Yuv420, &mut out);
+        assert_eq!(out, vec![16, 16, 16, 16, 128, 128]);
+    }
+}
@@ -0,0 +1,239 @@
+//! Listening-socket inspector.
+//!
+//! Provides a Tauri command that reports which local ports are in use and
+//! which process owns them, so dev servers can be identified at a glance
+//! and the streaming module can avoid ports that are already taken.
+//!
+//! Uses `lsof` on macOS/Linux and `netstat -ano` on Windows rather than
+//! platform socket APIs — both ship with the OS and their output is stable.
+
+use serde::Serialize;
+use std::process::Command;
+
+use crate::AppError;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// A single listening socket returned to the frontend.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ListeningPort {
+    /// "TCP" or "UDP"
+    pub protocol: String,
+    /// Bound address (e.g. "127.0.0.1", "*", "[::1]")
+    pub address: String,
+    pub port: u16,
+    pub pid: Option<u32>,
+    pub process_name: Option<String>,
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// List all listening TCP sockets and bound UDP sockets on this machine.
+///
+/// Results are sorted by port. Sockets owned by other users may be missing
+/// process details when the OS does not expose them without elevation.
+#[tauri::command]
+pub async fn get_listening_ports() -> Result<Vec<ListeningPort>, AppError> {
+    log::trace!("get_listening_ports() called");
+
+    let mut ports = tokio::task::spawn_blocking(listening_ports)
+        .await
+        .map_err(|e| AppError::Internal(format!("Port scan task failed: {}", e)))??;
+
+    ports.sort_by(|a, b| a.port.cmp(&b.port).then(a.protocol.cmp(&b.protocol)));
+    ports.dedup();
+
+    log::debug!("Found {} listening socket(s)", ports.len());
+    Ok(ports)
+}
+
+// =============================================================================
+// Platform Queries
+// =============================================================================
+
+/// Query the OS for listening sockets (blocking).
+#[cfg(unix)]
+pub fn listening_ports() -> Result<Vec<ListeningPort>, AppError> {
+    // -n/-P: no DNS or service-name lookups (fast, numeric ports)
+    // -F pcPn: machine-readable fields (pid, command, protocol, name)
+    let output = Command::new("lsof")
+        .args(["-nP", "-iTCP", "-sTCP:LISTEN", "-iUDP", "-FpcPn"])
+        .output()
+        .map_err(|e| AppError::Io(format!("Failed to run lsof: {}", e)))?;
+
+    // lsof exits 1 when nothing matched — treat as an empty list
+    Ok(parse_lsof_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Query the OS for listening sockets (blocking).
+#[cfg(windows)]
+pub fn listening_ports() -> Result<Vec<ListeningPort>, AppError> {
+    let output = Command::new("netstat")
+        .args(["-ano"])
+        .output()
+        .map_err(|e| AppError::Io(format!("Failed to run netstat: {}", e)))?;
+
+    let mut ports = parse_netstat_output(&String::from_utf8_lossy(&output.stdout));
+
+    // netstat only reports PIDs — resolve names via sysinfo
+    let mut sys = sysinfo::System::new();
+    sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+    for p in &mut ports {
+        if let Some(pid) = p.pid {
+            p.process_name = sys
+                .process(sysinfo::Pid::from_u32(pid))
+                .map(|proc_| proc_.name().to_string_lossy().to_string());
+        }
+    }
+
+    Ok(ports)
+}
+
+// =============================================================================
+// Output Parsing
+// =============================================================================
+
+/// Split a socket name like `127.0.0.1:9100`, `*:3000` or `[::1]:5432`
+/// into address and port. Returns None for unbound (`*:*`) names.
+fn split_host_port(name: &str) -> Option<(String, u16)> {
+    let (addr, port) = name.rsplit_once(':')?;
+    let port = port.parse().ok()?;
+    Some((addr.to_string(), port))
+}
+
+/// Parse `lsof -F pcPn` output.
+///
+/// Each process block starts with `p<pid>` followed by `c<command>`; each
+/// file in that process then has `P<protocol>` and `n<name>` lines.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn parse_lsof_output(output: &str) -> Vec<ListeningPort> {
+    let mut ports = Vec::new();
+    let mut pid: Option<u32> = None;
+    let mut command: Option<String> = None;
+    let mut protocol = String::new();
+
+    for line in output.lines() {
+        let (tag, value) = match line.chars().next() {
+            Some(c) => (c, &line[c.len_utf8()..]),
+            None => continue,
+        };
+
+        match tag {
+            'p' => {
+                pid = value.parse().ok();
+                command = None;
+            }
+            'c' => command = Some(value.to_string()),
+            'P' => protocol = value.to_uppercase(),
+            'n' => {
+                // Connected UDP sockets show "local->remote"; only the local side matters
+                let local = value.split("->").next().unwrap_or(value);
+                if let Some((address, port)) = split_host_port(local) {
+                    ports.push(ListeningPort {
+                        protocol: protocol.clone(),
+                        address,
+                        port,
+                        pid,
+                        process_name: command.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ports
+}
+
+/// Parse `netstat -ano` output (Windows format).
+///
+/// Keeps TCP rows in the LISTENING state and all UDP rows (UDP has no
+/// state column; every row is a bound socket).
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_netstat_output(output: &str) -> Vec<ListeningPort> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            let protocol = cols.first()?.to_uppercase();
+
+            let pid_col = match (protocol.as_str(), cols.len()) {
+                ("TCP", 5) if cols[3] == "LISTENING" => cols[4],
+                ("UDP", 4) => cols[3],
+                _ => return None,
+            };
+
+            let (address, port) = split_host_port(cols[1])?;
+            Some(ListeningPort {
+                protocol,
+                address,
+                port,
+                pid: pid_col.parse().ok(),
+                process_name: None,
+            })
+        })
+        .collect()
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_LSOF: &str = "p512\ncnode\nf23\nPTCP\nn*:3000\nf24\nPTCP\nn[::1]:3000\np901\ncsynthia\nf9\nPTCP\nn127.0.0.1:9100\np77\ncmDNSResponder\nf5\nPUDP\nn*:5353\n";
+
+    const SAMPLE_NETSTAT: &str = r#"
+Active Connections
+
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1044
+  TCP    127.0.0.1:9100         0.0.0.0:0              LISTENING       6120
+  TCP    192.168.1.5:50123      140.82.112.4:443       ESTABLISHED     7788
+  UDP    0.0.0.0:5353           *:*                                    2210
+"#;
+
+    #[test]
+    fn test_parse_lsof_output() {
+        let ports = parse_lsof_output(SAMPLE_LSOF);
+        assert_eq!(ports.len(), 4);
+
+        assert_eq!(ports[0].port, 3000);
+        assert_eq!(ports[0].address, "*");
+        assert_eq!(ports[0].pid, Some(512));
+        assert_eq!(ports[0].process_name.as_deref(), Some("node"));
+
+        assert_eq!(ports[1].address, "[::1]");
+        assert_eq!(ports[2].process_name.as_deref(), Some("synthia"));
+        assert_eq!(ports[3].protocol, "UDP");
+        assert_eq!(ports[3].port, 5353);
+    }
+
+    #[test]
+    fn test_parse_lsof_empty() {
+        assert!(parse_lsof_output("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_netstat_output() {
+        let ports = parse_netstat_output(SAMPLE_NETSTAT);
+        assert_eq!(ports.len(), 3); // ESTABLISHED row excluded
+
+        assert_eq!(ports[0].port, 135);
+        assert_eq!(ports[0].pid, Some(1044));
+        assert_eq!(ports[1].address, "127.0.0.1");
+        assert_eq!(ports[2].protocol, "UDP");
+    }
+
+    #[test]
+    fn test_split_host_port_rejects_wildcard_port() {
+        assert_eq!(split_host_port("*:*"), None);
+        assert_eq!(split_host_port("[::]:8080"), Some(("[::]".to_string(), 8080)));
+    }
+}
@@ -0,0 +1,252 @@
+//! Low-power mode.
+//!
+//! While low power is in effect Synthia trades responsiveness for battery:
+//!
+//! - the metrics sampler records every `LOW_POWER_SAMPLE_INTERVAL` instead
+//!   of every second (tray stats refresh at the same pace)
+//! - the stream publishes at most `LOW_POWER_MAX_FPS` frames per second
+//! - foreground watchers of sessions without input for
+//!   `LOW_POWER_IDLE_AFTER` pause between polls for
+//!   `LOW_POWER_FOREGROUND_POLL_INTERVAL`
+//!
+//! `set_power_mode` forces low or normal power; in the default `auto` mode
+//! low power follows the battery, polled every `BATTERY_POLL_INTERVAL`
+//! (`/sys/class/power_supply` on Linux, `pmset` on macOS; Windows is treated
+//! as always on AC). `power-mode-changed` is emitted whenever low power
+//! starts or stops. The mode is not persisted.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::events;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// How often `auto` mode checks whether the machine runs on battery
+const BATTERY_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Metrics sampling interval in low power
+pub(crate) const LOW_POWER_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Stream frame rate cap in low power
+pub(crate) const LOW_POWER_MAX_FPS: u32 = 10;
+
+/// Input idle time after which a session's foreground watcher slows down
+pub(crate) const LOW_POWER_IDLE_AFTER: Duration = Duration::from_secs(60);
+
+/// Foreground poll interval of idle sessions in low power
+pub(crate) const LOW_POWER_FOREGROUND_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// `PowerMode` as stored in `MODE`
+static MODE: AtomicU8 = AtomicU8::new(PowerMode::Auto as u8);
+
+/// Last battery check: on battery (false when unknown)
+static ON_BATTERY: AtomicBool = AtomicBool::new(false);
+
+/// Whether low power is in effect, kept in sync by `refresh`
+static LOW_POWER: AtomicBool = AtomicBool::new(false);
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Power mode requested with `set_power_mode`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerMode {
+    /// Low power while on battery
+    #[default]
+    Auto = 0,
+    Low = 1,
+    Normal = 2,
+}
+
+impl PowerMode {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Low,
+            2 => Self::Normal,
+            _ => Self::Auto,
+        }
+    }
+}
+
+/// Returned by `get_power_status` and `set_power_mode`, and the payload of
+/// the `power-mode-changed` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PowerStatus {
+    pub mode: PowerMode,
+    /// Running on battery (false if it cannot be detected)
+    pub on_battery: bool,
+    /// Low power is in effect
+    pub low_power: bool,
+}
+
+// =============================================================================
+// State
+// =============================================================================
+
+/// Low power is in effect.
+pub(crate) fn is_low_power() -> bool {
+    LOW_POWER.load(Ordering::Relaxed)
+}
+
+fn status() -> PowerStatus {
+    PowerStatus {
+        mode: PowerMode::from_u8(MODE.load(Ordering::Relaxed)),
+        on_battery: ON_BATTERY.load(Ordering::Relaxed),
+        low_power: is_low_power(),
+    }
+}
+
+fn effective_low_power(mode: PowerMode, on_battery: bool) -> bool {
+    match mode {
+        PowerMode::Auto => on_battery,
+        PowerMode::Low => true,
+        PowerMode::Normal => false,
+    }
+}
+
+/// Recompute low power and emit `power-mode-changed` if it flipped.
+fn refresh(app: &AppHandle) {
+    let current = status();
+    let low_power = effective_low_power(current.mode, current.on_battery);
+    if LOW_POWER.swap(low_power, Ordering::Relaxed) == low_power {
+        return;
+    }
+    log::info!(
+        "Low-power mode {} (mode {:?}, on battery: {})",
+        if low_power { "on" } else { "off" },
+        current.mode,
+        current.on_battery
+    );
+    let _ = events::emit(
+        app,
+        "power-mode-changed",
+        PowerStatus {
+            low_power,
+            ..current
+        },
+    );
+}
+
+/// Start the battery poller. Failures are logged; low power then only
+/// follows `set_power_mode`.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name("power-monitor".into())
+        .spawn(move || loop {
+            ON_BATTERY.store(on_battery().unwrap_or(false), Ordering::Relaxed);
+            refresh(&app);
+            std::thread::sleep(BATTERY_POLL_INTERVAL);
+        });
+    if let Err(e) = spawned {
+        log::error!("Failed to start power monitor: {}", e);
+    }
+}
+
+// =============================================================================
+// Battery Detection
+// =============================================================================
+
+/// Whether the machine runs on battery; None if unknown (e.g. a desktop).
+#[cfg(target_os = "linux")]
+fn on_battery() -> Option<bool> {
+    let entries = std::fs::read_dir("/sys/class/power_supply").ok()?;
+    let mut found = None;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let read = |name: &str| {
+            std::fs::read_to_string(path.join(name))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+        match read("type").as_str() {
+            // Any connected charger means AC power
+            "Mains" | "USB" if read("online") == "1" => return Some(false),
+            "Battery" => {
+                let discharging = read("status") == "Discharging";
+                found = Some(found.unwrap_or(false) || discharging);
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+#[cfg(target_os = "macos")]
+fn on_battery() -> Option<bool> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()?;
+    parse_pmset(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn on_battery() -> Option<bool> {
+    None
+}
+
+/// Power source from `pmset -g batt`, e.g. `Now drawing from 'Battery Power'`.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_pmset(output: &str) -> Option<bool> {
+    let first = output.lines().next()?;
+    if first.contains("'Battery Power'") {
+        Some(true)
+    } else if first.contains("'AC Power'") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Current power mode and whether low power is in effect.
+#[tauri::command]
+pub fn get_power_status() -> PowerStatus {
+    status()
+}
+
+/// Force low or normal power, or follow the battery (`auto`).
+#[tauri::command]
+pub fn set_power_mode(app: AppHandle, mode: PowerMode) -> PowerStatus {
+    MODE.store(mode as u8, Ordering::Relaxed);
+    log::info!("Power mode set to {:?}", mode);
+    refresh(&app);
+    status()
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_low_power() {
+        assert!(effective_low_power(PowerMode::Auto, true));
+        assert!(!effective_low_power(PowerMode::Auto, false));
+        assert!(effective_low_power(PowerMode::Low, false));
+        assert!(!effective_low_power(PowerMode::Normal, true));
+        assert_eq!(PowerMode::from_u8(PowerMode::Normal as u8), PowerMode::Normal);
+    }
+
+    #[test]
+    fn test_parse_pmset() {
+        let battery = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1)\t82%; discharging";
+        assert_eq!(parse_pmset(battery), Some(true));
+        assert_eq!(parse_pmset("Now drawing from 'AC Power'\n"), Some(false));
+        assert_eq!(parse_pmset(""), None);
+    }
+}
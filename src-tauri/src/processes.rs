@@ -0,0 +1,360 @@
+//! Top processes for the Infrastructure widget.
+//!
+//! The widget polls every couple of seconds, so this keeps one `System`
+//! alive across calls and refreshes only process CPU and memory. Per-process
+//! CPU usage is measured between refreshes, so the first call after launch
+//! reports 0% for everything and later calls are accurate.
+//!
+//! A process can also be deprioritized (`set_process_priority`, unix nice
+//! values) or pinned to CPUs (`set_process_affinity`, Linux only: macOS has
+//! no affinity API). Raising priority (a lower nice value) usually needs
+//! root and fails with `permission_denied`.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::State;
+
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Processes returned by `get_top_processes` when `n` is omitted
+const DEFAULT_TOP_PROCESSES: usize = 5;
+
+/// Upper bound for `get_top_processes` `n`
+const MAX_TOP_PROCESSES: usize = 50;
+
+/// Range of unix nice values (higher is lower priority)
+const NICE_RANGE: std::ops::RangeInclusive<i32> = -20..=19;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Ordering of `get_top_processes`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    Memory,
+}
+
+/// One row of the top-processes list.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TopProcess {
+    pub pid: u32,
+    pub name: String,
+    /// Percent of one core since the previous call (can exceed 100)
+    pub cpu_percent: f32,
+    /// Resident memory
+    pub memory_bytes: u64,
+}
+
+/// Scheduling settings of a process, returned by the priority commands.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProcessPriority {
+    pub pid: u32,
+    /// Nice value, -20 (highest priority) to 19 (lowest)
+    pub nice: i32,
+    /// CPUs the process may run on, or None where affinity is unsupported
+    pub affinity: Option<Vec<usize>>,
+}
+
+/// Shared state managed by Tauri: the `System` reused between polls.
+#[derive(Default)]
+pub struct ProcessesState {
+    system: Arc<Mutex<System>>,
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// The `n` processes using the most CPU or memory.
+///
+/// # Arguments
+/// * `n` - Processes to return (default 5, max 50)
+/// * `sort` - `cpu` (default) or `memory`
+#[tauri::command]
+pub async fn get_top_processes(
+    state: State<'_, ProcessesState>,
+    n: Option<usize>,
+    sort: Option<ProcessSort>,
+) -> Result<Vec<TopProcess>, AppError> {
+    let n = n.unwrap_or(DEFAULT_TOP_PROCESSES);
+    if !(1..=MAX_TOP_PROCESSES).contains(&n) {
+        return Err(AppError::Validation(format!(
+            "n must be 1-{}, got: {}",
+            MAX_TOP_PROCESSES, n
+        )));
+    }
+    let sort = sort.unwrap_or_default();
+
+    let system = Arc::clone(&state.system);
+    let processes = tokio::task::spawn_blocking(move || {
+        let mut system = system
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock process table: {}", e)))?;
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::new().with_cpu().with_memory(),
+        );
+        Ok::<_, AppError>(
+            system
+                .processes()
+                .iter()
+                .map(|(pid, p)| TopProcess {
+                    pid: pid.as_u32(),
+                    name: p.name().to_string_lossy().to_string(),
+                    cpu_percent: p.cpu_usage(),
+                    memory_bytes: p.memory(),
+                })
+                .collect::<Vec<_>>(),
+        )
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Process scan task failed: {}", e)))??;
+
+    Ok(top_processes(processes, sort, n))
+}
+
+/// Current nice value and CPU affinity of a process.
+#[tauri::command]
+pub fn get_process_priority(pid: u32) -> Result<ProcessPriority, AppError> {
+    check_pid(pid)?;
+    priority_of(pid)
+}
+
+/// Change a process's nice value, e.g. 10 to let a background build yield
+/// to interactive work.
+///
+/// # Arguments
+/// * `pid` - Target process
+/// * `nice` - -20 (highest priority) to 19 (lowest)
+#[tauri::command]
+pub fn set_process_priority(pid: u32, nice: i32) -> Result<ProcessPriority, AppError> {
+    check_pid(pid)?;
+    if !NICE_RANGE.contains(&nice) {
+        return Err(AppError::Validation(format!(
+            "nice must be {}..={}, got: {}",
+            NICE_RANGE.start(),
+            NICE_RANGE.end(),
+            nice
+        )));
+    }
+    set_nice(pid, nice)?;
+    log::info!("Set nice of process {} to {}", pid, nice);
+    priority_of(pid)
+}
+
+/// Restrict a process to the given CPUs (0-based indices). Applies to all
+/// of its current threads and is inherited by processes it starts later.
+/// Linux only.
+#[tauri::command]
+pub fn set_process_affinity(pid: u32, cpus: Vec<usize>) -> Result<ProcessPriority, AppError> {
+    check_pid(pid)?;
+    if cpus.is_empty() {
+        return Err(AppError::Validation("At least one CPU is required".into()));
+    }
+    set_affinity(pid, &cpus)?;
+    log::info!("Set CPU affinity of process {} to {:?}", pid, cpus);
+    priority_of(pid)
+}
+
+/// Reject pid 0, which the OS APIs read as "the calling process".
+fn check_pid(pid: u32) -> Result<(), AppError> {
+    if pid == 0 {
+        return Err(AppError::Validation("pid must not be 0".into()));
+    }
+    Ok(())
+}
+
+fn priority_of(pid: u32) -> Result<ProcessPriority, AppError> {
+    Ok(ProcessPriority {
+        pid,
+        nice: get_nice(pid)?,
+        affinity: get_affinity(pid)?,
+    })
+}
+
+/// Map an errno from a scheduling call on `pid` to an `AppError`.
+#[cfg(unix)]
+fn sched_error(pid: u32, e: nix::errno::Errno) -> AppError {
+    match e {
+        nix::errno::Errno::ESRCH => AppError::NotFound(format!("No process with pid {}", pid)),
+        nix::errno::Errno::EPERM | nix::errno::Errno::EACCES => AppError::PermissionDenied(
+            format!("Not allowed to change scheduling of process {}", pid),
+        ),
+        e => AppError::Io(format!("Scheduling call for process {} failed: {}", pid, e)),
+    }
+}
+
+#[cfg(unix)]
+fn get_nice(pid: u32) -> Result<i32, AppError> {
+    use nix::errno::Errno;
+    // -1 is a valid nice value, so errors are only visible through errno
+    Errno::clear();
+    // SAFETY: getpriority only reads its integer arguments
+    let nice = unsafe { nix::libc::getpriority(nix::libc::PRIO_PROCESS, pid as nix::libc::id_t) };
+    match Errno::last_raw() {
+        0 => Ok(nice),
+        _ => Err(sched_error(pid, Errno::last())),
+    }
+}
+
+#[cfg(unix)]
+fn set_nice(pid: u32, nice: i32) -> Result<(), AppError> {
+    // SAFETY: setpriority only reads its integer arguments
+    let res =
+        unsafe { nix::libc::setpriority(nix::libc::PRIO_PROCESS, pid as nix::libc::id_t, nice) };
+    nix::errno::Errno::result(res)
+        .map(drop)
+        .map_err(|e| sched_error(pid, e))
+}
+
+#[cfg(not(unix))]
+fn get_nice(_pid: u32) -> Result<i32, AppError> {
+    Err(AppError::Io("Process priority is not supported on this platform".into()))
+}
+
+#[cfg(not(unix))]
+fn set_nice(_pid: u32, _nice: i32) -> Result<(), AppError> {
+    Err(AppError::Io("Process priority is not supported on this platform".into()))
+}
+
+#[cfg(target_os = "linux")]
+fn get_affinity(pid: u32) -> Result<Option<Vec<usize>>, AppError> {
+    use nix::sched::{sched_getaffinity, CpuSet};
+    let set = sched_getaffinity(nix::unistd::Pid::from_raw(pid as i32))
+        .map_err(|e| sched_error(pid, e))?;
+    Ok(Some(
+        (0..CpuSet::count())
+            .filter(|&cpu| set.is_set(cpu).unwrap_or(false))
+            .collect(),
+    ))
+}
+
+/// Affinity is per thread on Linux, so every thread listed in
+/// `/proc/<pid>/task` is updated (like `taskset -a`).
+#[cfg(target_os = "linux")]
+fn set_affinity(pid: u32, cpus: &[usize]) -> Result<(), AppError> {
+    use nix::sched::{sched_setaffinity, CpuSet};
+    let mut set = CpuSet::new();
+    for &cpu in cpus {
+        set.set(cpu)
+            .map_err(|_| AppError::Validation(format!("CPU index out of range: {}", cpu)))?;
+    }
+
+    let mut threads: Vec<i32> = std::fs::read_dir(format!("/proc/{}/task", pid))
+        .map_err(|_| AppError::NotFound(format!("No process with pid {}", pid)))?
+        .filter_map(|e| e.ok()?.file_name().to_str()?.parse().ok())
+        .collect();
+    // The main thread first, so a bad CPU list fails before anything changed
+    threads.sort_by_key(|&tid| tid != pid as i32);
+    for tid in threads {
+        match sched_setaffinity(nix::unistd::Pid::from_raw(tid), &set) {
+            // A thread that exited meanwhile
+            Err(nix::errno::Errno::ESRCH) if tid != pid as i32 => {}
+            Err(nix::errno::Errno::EINVAL) => {
+                return Err(AppError::Validation(format!(
+                    "None of the CPUs {:?} are available",
+                    cpus
+                )));
+            }
+            result => result.map_err(|e| sched_error(pid, e))?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn get_affinity(_pid: u32) -> Result<Option<Vec<usize>>, AppError> {
+    Ok(None)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_affinity(_pid: u32, _cpus: &[usize]) -> Result<(), AppError> {
+    Err(AppError::Io("CPU affinity is not supported on this platform".into()))
+}
+
+/// Sort by `sort` (descending, ties by pid) and keep the first `n`. The
+/// Windows idle pseudo-process (pid 0) is skipped.
+fn top_processes(mut processes: Vec<TopProcess>, sort: ProcessSort, n: usize) -> Vec<TopProcess> {
+    processes.retain(|p| p.pid != 0);
+    processes.sort_by(|a, b| {
+        let order = match sort {
+            ProcessSort::Cpu => b.cpu_percent.total_cmp(&a.cpu_percent),
+            ProcessSort::Memory => b.memory_bytes.cmp(&a.memory_bytes),
+        };
+        order.then(a.pid.cmp(&b.pid))
+    });
+    processes.truncate(n);
+    processes
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, cpu_percent: f32, memory_bytes: u64) -> TopProcess {
+        TopProcess {
+            pid,
+            name: format!("p{}", pid),
+            cpu_percent,
+            memory_bytes,
+        }
+    }
+
+    #[test]
+    fn test_top_processes_sorting() {
+        let all = vec![
+            process(0, 99.0, 0),
+            process(10, 5.0, 300),
+            process(11, 50.0, 100),
+            process(12, 5.0, 900),
+        ];
+
+        let by_cpu: Vec<u32> = top_processes(all.clone(), ProcessSort::Cpu, 5)
+            .iter()
+            .map(|p| p.pid)
+            .collect();
+        assert_eq!(by_cpu, vec![11, 10, 12]);
+
+        let by_mem: Vec<u32> = top_processes(all, ProcessSort::Memory, 2)
+            .iter()
+            .map(|p| p.pid)
+            .collect();
+        assert_eq!(by_mem, vec![12, 10]);
+    }
+
+    #[test]
+    fn test_priority_validation() {
+        assert!(matches!(set_process_priority(0, 5), Err(AppError::Validation(_))));
+        let pid = std::process::id();
+        assert!(matches!(set_process_priority(pid, 20), Err(AppError::Validation(_))));
+        assert!(matches!(set_process_affinity(pid, vec![]), Err(AppError::Validation(_))));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_priority_roundtrip_on_own_process() {
+        let pid = std::process::id();
+        let current = get_process_priority(pid).unwrap();
+        let cpus = current.affinity.clone().unwrap();
+        assert!(!cpus.is_empty());
+
+        // Re-applying the current values needs no privileges
+        assert_eq!(set_process_priority(pid, current.nice).unwrap(), current);
+        assert_eq!(set_process_affinity(pid, cpus).unwrap(), current);
+        assert!(matches!(get_process_priority(u32::MAX / 2), Err(AppError::NotFound(_))));
+    }
+}
@@ -5,41 +5,713 @@
 //! to the frontend via Tauri events.
 
 use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
-use serde::Serialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::io::{Read, Write};
-use std::sync::{Arc, Mutex};
-use tauri::{Emitter, State};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::broadcast;
+
+use crate::ansi::{self, AnsiStripper};
+use crate::events;
+use crate::history;
+use crate::logging::{self, TerminalLogMirror};
+use crate::power;
+use crate::rate_limit::{RateLimitKind, RateLimiter};
+use crate::redact;
+use crate::limits::{self, ResourceLimits};
+use crate::recording::{self, RecorderSlot};
+use crate::sandbox::{self, SandboxInfo};
+use crate::screen::ScreenModel;
+use crate::settings::{EnvProfile, SettingsState};
+use crate::shells;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Escape sequences an application emits to turn bracketed paste mode on/off
+const BRACKETED_PASTE_ENABLE: &str = "\x1b[?2004h";
+const BRACKETED_PASTE_DISABLE: &str = "\x1b[?2004l";
+
+/// Sequences wrapped around pasted text while bracketed paste mode is on
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+/// Output chunks buffered per `wait_for_output` waiter before it skips ahead
+const OUTPUT_CHANNEL_CAPACITY: usize = 256;
+
+/// Longest a `wait_for_output` call may block (10 minutes)
+const MAX_WAIT_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+
+/// Plain-text output retained while waiting for a match (older text is dropped)
+const WAIT_BUFFER_MAX_BYTES: usize = 64 * 1024;
+
+/// Characters of output included on each side of a match
+const MATCH_CONTEXT_CHARS: usize = 500;
+
+/// Plain-text transcript retained per session (oldest text is dropped first)
+const TRANSCRIPT_MAX_BYTES: usize = 256 * 1024;
+
+/// Raw output retained per session for `replay_output` (oldest chunks are dropped first)
+const SCROLLBACK_MAX_BYTES: usize = 1024 * 1024;
+
+/// Characters returned by `get_transcript` when `max_chars` is omitted
+const DEFAULT_TRANSCRIPT_CHARS: usize = 4000;
+
+/// How often each session's foreground process group is checked
+const FOREGROUND_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Maximum length of a session lock owner name
+const MAX_LOCK_OWNER_LEN: usize = 64;
+
+/// Input is queued and written to the PTY in chunks of at most this size, so
+/// a large paste is fed at the rate the foreground app reads it
+const WRITE_CHUNK_BYTES: usize = 4096;
+
+/// Silence after which new output counts as a fresh activity burst
+const ACTIVITY_IDLE_GAP: Duration = Duration::from_secs(2);
+
+/// Minimum time between `terminal-activity` events during one burst
+const ACTIVITY_EMIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Seconds of output averaged for `SessionStats::read_bytes_per_sec`
+const IO_RATE_WINDOW_SECS: u64 = 5;
+
+/// Distinct injected commands remembered for the command palette
+const MAX_RECENT_COMMANDS: usize = 200;
+
+/// Directory under the app data dir holding saved session transcripts
+const SAVED_TRANSCRIPTS_DIR: &str = "transcripts";
+
+/// Transcript tail saved per session by `terminal.persist_scrollback`
+const SAVED_TRANSCRIPT_BYTES: usize = 64 * 1024;
+
+/// Marker appended after a restored transcript
+const RESTORED_MARKER: &str = "--- restored from previous launch ---\n";
+
+/// Largest rows/cols accepted for a new session's initial size
+const MAX_INITIAL_DIMENSION: u16 = 1000;
+
+/// Most extra shell arguments accepted by `spawn_terminal`
+const MAX_SHELL_ARGS: usize = 32;
+
+/// Most paths accepted by one `inject_paths` call
+const MAX_INJECTED_PATHS: usize = 256;
+
+/// Longest output line still considered a possible password prompt
+const MAX_PROMPT_CHARS: usize = 200;
+
+/// Words that mark a line ending in `:` as a password prompt
+const PROMPT_KEYWORDS: [&str; 3] = ["password", "passphrase", "passcode"];
+
+/// Cap on a shell integration OSC payload and on an echoed command line
+const SHELL_MARK_MAX_BYTES: usize = 4096;
+
+/// Upper bound on `spawn_terminal` initial commands
+const MAX_INITIAL_COMMANDS: usize = 16;
+
+/// Quiet period after the shell's first output that counts as "prompt shown"
+const PROMPT_SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// Initial commands are typed after this long even if no prompt was seen
+const PROMPT_MAX_WAIT: Duration = Duration::from_secs(10);
+
+/// Matches returned by `search_terminal` before it reports `truncated`
+const MAX_SEARCH_MATCHES: usize = 1000;
+
+/// Compiled size limit for `search_terminal` patterns
+const SEARCH_REGEX_SIZE_LIMIT: usize = 1024 * 1024;
+
+/// Longest accepted environment profile name
+const MAX_PROFILE_NAME_LEN: usize = 64;
+
+/// How often the idle cleanup policy (`terminal.idle_kill_hours`) runs
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Finished-command reports buffered per `inject_commands` waiter
+const FINISHED_CHANNEL_CAPACITY: usize = 16;
+
+/// Most commands accepted by one `inject_commands` call
+const MAX_BATCH_COMMANDS: usize = 64;
+
+/// Pause before each batch command after the first when it sets no `delay_ms`
+const DEFAULT_BATCH_DELAY_MS: u64 = 100;
+
+/// Wait for a batch command to finish when it needs its exit code but sets
+/// no `timeout_ms` (1 minute)
+const DEFAULT_BATCH_TIMEOUT_MS: u64 = 60 * 1000;
 
 // =============================================================================
 // Types
 // =============================================================================
 
-/// A single PTY session with its writer, master handle, and child process.
+/// A single PTY session with its input channel, master handle, and child process.
 pub struct PtySession {
-    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    /// Input queue drained by the session's dedicated writer thread
+    input_tx: mpsc::Sender<Vec<u8>>,
     master: Box<dyn MasterPty + Send>,
     child: Box<dyn portable_pty::Child + Send>,
+    /// Whether the foreground app has enabled bracketed paste (tracked from output)
+    bracketed_paste: Arc<AtomicBool>,
+    /// Raw output chunks for backend consumers such as `wait_for_output`
+    output_tx: broadcast::Sender<String>,
+    /// Commands reported finished by shell integration, for `inject_commands`
+    finished_tx: broadcast::Sender<CommandFinished>,
+    /// ANSI-stripped recent output for `get_transcript`
+    transcript: Arc<Mutex<Transcript>>,
+    /// Raw recent output chunks for `replay_output`
+    scrollback: Arc<Mutex<Scrollback>>,
+    /// Emulated visible screen for `get_visible_screen`
+    screen: Arc<ScreenModel>,
+    /// asciicast recording started with `start_session_recording`
+    recorder: RecorderSlot,
+    /// Process currently in the foreground of the PTY (updated by a poller)
+    foreground: Option<ForegroundProcess>,
+    /// Exclusive input lock held by an agent or user, if any
+    lock: Option<SessionLock>,
+    /// A password prompt is pending and `secure-input-requested` was emitted
+    secure_prompt: Arc<AtomicBool>,
+    /// How `inject_paths` quotes paths for the session's shell
+    quoting: QuoteStyle,
+    /// Program of the session's shell, e.g. `/bin/zsh`
+    shell_program: String,
+    /// Byte counters updated by the reader and writer threads
+    io: Arc<SessionIo>,
+    /// Open `subscribe_terminal` subscriptions; `pty-output-*` and
+    /// `terminal-output-captured` events are only emitted while there is at
+    /// least one
+    viewers: Arc<AtomicUsize>,
+    /// Name of the environment profile the session was spawned with
+    profile: Option<String>,
+    /// Set for sessions started by `spawn_sandboxed_terminal`
+    sandbox: Option<SandboxInfo>,
+    /// Resource limits the shell was spawned with
+    limits: Option<ResourceLimits>,
+    /// Never killed by the idle cleanup policy (`set_idle_exempt`)
+    idle_exempt: bool,
+    /// When `terminal-idle-warning` was emitted for the current idle period
+    idle_warned_at: Option<Instant>,
+    /// Set once the shell has exited; the session stays listed as dead
+    exit: Option<TerminalExit>,
+}
+
+impl PtySession {
+    /// Command line of the foreground process, if known.
+    pub(crate) fn foreground_command(&self) -> Option<String> {
+        self.foreground.as_ref().map(|f| f.command.clone())
+    }
+
+    /// Whether the shell itself is in the foreground, i.e. sitting at its
+    /// prompt. False when unknown (always on Windows) or after it exited.
+    fn at_prompt(&self) -> bool {
+        self.exit.is_none()
+            && self
+                .foreground
+                .as_ref()
+                .is_some_and(|f| Some(f.pid) == self.child.process_id())
+    }
+}
+
+/// Size-capped, ANSI-stripped transcript of a session's output.
+#[derive(Default)]
+struct Transcript {
+    stripper: AnsiStripper,
+    text: String,
+}
+
+impl Transcript {
+    /// Append a raw output chunk, dropping the oldest text past the cap.
+    fn append(&mut self, raw: &str) {
+        let plain = self.stripper.feed(raw.as_bytes());
+        self.text.push_str(&plain);
+        trim_front(&mut self.text, TRANSCRIPT_MAX_BYTES);
+    }
+
+    /// Seed the transcript with text saved by a previous launch.
+    fn restore(&mut self, saved: &str) {
+        self.text = saved.to_string();
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+        self.text.push_str(RESTORED_MARKER);
+        trim_front(&mut self.text, TRANSCRIPT_MAX_BYTES);
+    }
+}
+
+/// Size-capped raw output chunks, numbered so the frontend can detect gaps
+/// in the `pty-output-{id}` event stream and replay what it missed.
+#[derive(Default)]
+struct Scrollback {
+    chunks: VecDeque<PtyOutput>,
+    /// Bytes of `data` currently retained
+    bytes: usize,
+    /// Sequence number of the last chunk (the first chunk is 1)
+    seq: u64,
+    /// Output bytes produced since the session started
+    offset: u64,
+}
+
+impl Scrollback {
+    /// Number and retain a chunk, dropping the oldest chunks past the cap.
+    fn push(&mut self, data: String, raw_len: usize) -> PtyOutput {
+        self.seq += 1;
+        let chunk = PtyOutput {
+            seq: self.seq,
+            offset: self.offset,
+            data,
+        };
+        self.offset += raw_len as u64;
+        self.bytes += chunk.data.len();
+        self.chunks.push_back(chunk.clone());
+        while self.bytes > SCROLLBACK_MAX_BYTES && self.chunks.len() > 1 {
+            if let Some(old) = self.chunks.pop_front() {
+                self.bytes -= old.data.len();
+            }
+        }
+        chunk
+    }
+
+    /// Retained chunks with `seq >= from_seq`, and whether they start exactly
+    /// at `from_seq` (false when older output was already dropped).
+    fn since(&self, from_seq: u64) -> (Vec<PtyOutput>, bool) {
+        let chunks: Vec<PtyOutput> = self
+            .chunks
+            .iter()
+            .filter(|c| c.seq >= from_seq)
+            .cloned()
+            .collect();
+        let first_retained = self.chunks.front().map_or(self.seq + 1, |c| c.seq);
+        (chunks, from_seq >= first_retained)
+    }
 }
 
 /// Shared state holding all active PTY sessions.
 pub struct PtyState {
-    pub sessions: Mutex<HashMap<String, PtySession>>,
+    /// Locked through `sessions()`, which survives poisoning
+    sessions: Mutex<HashMap<String, PtySession>>,
+    /// Injected commands, most recent first and without duplicates
+    recent_commands: Mutex<VecDeque<String>>,
+    /// Throttles `inject_command(s)` and `write_terminal`
+    pub(crate) rate_limiter: RateLimiter,
 }
 
 impl Default for PtyState {
     fn default() -> Self {
         Self {
             sessions: Mutex::new(HashMap::new()),
+            recent_commands: Mutex::new(VecDeque::new()),
+            rate_limiter: RateLimiter::default(),
+        }
+    }
+}
+
+impl PtyState {
+    /// Lock the session map.
+    ///
+    /// A panic while the lock was held poisons it; the map itself is still
+    /// intact (at worst one session is half-updated), so the poison is
+    /// logged and cleared instead of failing every later terminal command.
+    pub(crate) fn sessions(&self) -> MutexGuard<'_, HashMap<String, PtySession>> {
+        self.sessions.lock().unwrap_or_else(|e| {
+            log::error!("Session map lock poisoned by a panic, recovering");
+            self.sessions.clear_poison();
+            e.into_inner()
+        })
+    }
+
+    /// Injected commands, most recent first.
+    pub(crate) fn recent_commands(&self) -> Vec<String> {
+        self.recent_commands
+            .lock()
+            .map(|r| r.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn remember_command(&self, command: &str) {
+        let command = command.trim();
+        if command.is_empty() {
+            return;
+        }
+        if let Ok(mut recent) = self.recent_commands.lock() {
+            recent.retain(|c| c != command);
+            recent.push_front(command.to_string());
+            recent.truncate(MAX_RECENT_COMMANDS);
+        }
+    }
+}
+
+/// Which control characters `write_terminal` accepts (`terminal.control_chars`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlCharPolicy {
+    /// Accept everything. The default, because xterm.js also answers
+    /// terminal queries (colors, DECRQSS) through the same input path.
+    #[default]
+    Allow,
+    /// Accept keystrokes (including arrow/function key sequences) but reject
+    /// string sequences (OSC, DCS, APC, PM, SOS) and C1 controls, which no
+    /// keyboard produces and which can spoof terminal replies
+    Standard,
+    /// Only printable text, tab, backspace and line breaks (for agent-only setups)
+    Printable,
+}
+
+/// Initial PTY size for `spawn_terminal`, so the first prompt is laid out
+/// for the panel it is shown in instead of 80x24.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct TerminalSize {
+    pub rows: u16,
+    pub cols: u16,
+    #[serde(default)]
+    pub pixel_width: u16,
+    #[serde(default)]
+    pub pixel_height: u16,
+}
+
+impl Default for TerminalSize {
+    fn default() -> Self {
+        Self {
+            rows: 24,
+            cols: 80,
+            pixel_width: 0,
+            pixel_height: 0,
         }
     }
 }
 
+impl TerminalSize {
+    fn validate(&self) -> Result<(), AppError> {
+        let range = 1..=MAX_INITIAL_DIMENSION;
+        if !range.contains(&self.rows) || !range.contains(&self.cols) {
+            return Err(AppError::Validation(format!(
+                "Terminal size must be 1-{} rows and columns, got: {}x{}",
+                MAX_INITIAL_DIMENSION, self.cols, self.rows
+            )));
+        }
+        Ok(())
+    }
+}
+
 /// Information about a terminal session returned to the frontend.
 #[derive(Debug, Serialize, Clone)]
 pub struct TerminalInfo {
     pub session_id: String,
+    /// False once the shell has exited (see `exit`)
     pub is_alive: bool,
+    /// How the shell exited, if it has
+    pub exit: Option<TerminalExit>,
+    /// Foreground process of the session, if known (always None on Windows)
+    pub foreground: Option<ForegroundProcess>,
+    /// Input lock, if someone holds one
+    pub lock: Option<SessionLock>,
+    /// Bytes read and written so far
+    pub stats: SessionStats,
+    /// Viewers subscribed to the session's output events
+    pub viewers: usize,
+    /// Never killed by the idle cleanup policy
+    pub idle_exempt: bool,
+    /// Environment profile the session was spawned with
+    pub profile: Option<String>,
+    /// Sandbox of a `spawn_sandboxed_terminal` session
+    pub sandbox: Option<SandboxInfo>,
+    /// Resource limits the shell was spawned with
+    pub limits: Option<ResourceLimits>,
+}
+
+/// Result of `spawn_sandboxed_terminal`.
+#[derive(Debug, Serialize, Clone)]
+pub struct SandboxedTerminal {
+    pub session_id: String,
+    pub sandbox: SandboxInfo,
+}
+
+/// I/O counters of a session, returned by `get_session_stats` and part of
+/// `list_terminals`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SessionStats {
+    /// Output read from the PTY since the session started
+    pub bytes_read: u64,
+    /// Input written to the PTY
+    pub bytes_written: u64,
+    /// Output rate over the last 5 complete seconds
+    pub read_bytes_per_sec: f64,
+    /// Most output in any one second so far
+    pub peak_read_bytes_per_sec: u64,
+    /// RFC 3339 timestamp of when the session was spawned
+    pub started_at: String,
+    pub uptime_secs: u64,
+}
+
+/// Exclusive input lock on a session. While held, only input from `owner`
+/// is accepted by `write_terminal`, `paste_terminal` and the inject commands.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SessionLock {
+    pub owner: String,
+    /// RFC 3339 timestamp of when the lock was acquired
+    pub acquired_at: String,
+}
+
+/// The process group leader in the foreground of a PTY, e.g. the shell
+/// itself or `cargo build` while it runs.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ForegroundProcess {
+    pub pid: u32,
+    /// Executable name (`zsh`, `cargo`, ...)
+    pub name: String,
+    /// Full command line, or the name if it is unavailable
+    pub command: String,
+}
+
+/// Payload of the `terminal-foreground-changed` event.
+#[derive(Debug, Serialize, Clone)]
+pub struct ForegroundChanged {
+    pub session_id: String,
+    pub foreground: Option<ForegroundProcess>,
+}
+
+/// Payload of the `terminal-bell` event.
+#[derive(Debug, Serialize, Clone)]
+pub struct TerminalBell {
+    pub session_id: String,
+    /// Bells in the output chunk that triggered the event
+    pub count: u32,
+    /// Bells since the session started
+    pub total: u64,
+}
+
+/// Payload of the `terminal-activity` event, emitted when output starts
+/// after a quiet period and then at most once a second while it continues.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TerminalActivity {
+    pub session_id: String,
+    /// True for the first event of a burst (output after a quiet period)
+    pub burst_start: bool,
+    /// Output bytes since the previous event for this session
+    pub bytes: u64,
+    /// Milliseconds since the burst started
+    pub burst_ms: u64,
+}
+
+/// Payload of the `terminal-idle-warning` and `terminal-idle-killed` events.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TerminalIdle {
+    pub session_id: String,
+    /// Seconds since the last input
+    pub idle_secs: u64,
+    /// Seconds until the session is killed (0 once it was)
+    pub kill_in_secs: u64,
+}
+
+/// Payload of the `terminal-exited` event, also kept in `TerminalInfo::exit`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TerminalExit {
+    pub session_id: String,
+    /// Exit code of the shell (1 when it was killed by a signal)
+    pub exit_code: u32,
+    /// Name of the signal that killed the shell, if any
+    pub signal: Option<String>,
+    pub success: bool,
+    /// RFC 3339 timestamp of when the exit was noticed
+    pub exited_at: String,
+}
+
+/// Plain-text transcript slice returned by `get_transcript`.
+#[derive(Debug, Serialize, Clone)]
+pub struct TranscriptSlice {
+    pub session_id: String,
+    pub text: String,
+    /// Characters currently retained for the session
+    pub total_chars: usize,
+    /// Whether `text` is shorter than the retained transcript
+    pub truncated: bool,
+}
+
+/// Result of a successful `wait_for_output` call.
+#[derive(Debug, Serialize, Clone)]
+pub struct OutputMatch {
+    /// The text matched by the pattern (ANSI-stripped)
+    pub matched: String,
+    /// Plain-text output surrounding the match
+    pub context: String,
+    /// Milliseconds waited before the match arrived
+    pub elapsed_ms: u64,
+}
+
+/// One match of `search_terminal`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TerminalSearchMatch {
+    /// Line of the transcript, 0 for the oldest retained line
+    pub line: usize,
+    /// Character column where the match starts
+    pub column: usize,
+    /// Length of the match in characters
+    pub length: usize,
+    /// The whole line, ANSI-stripped
+    pub text: String,
+}
+
+/// Result of `search_terminal`.
+#[derive(Debug, Serialize, Clone)]
+pub struct TerminalSearchResult {
+    pub session_id: String,
+    /// Matches in transcript order (oldest first)
+    pub matches: Vec<TerminalSearchMatch>,
+    /// Lines in the searched transcript; `total_lines - 1 - line` is a
+    /// match's distance from the bottom of the terminal
+    pub total_lines: usize,
+    /// Whether matching stopped at the match cap
+    pub truncated: bool,
+}
+
+/// Result of `subscribe_terminal` / `unsubscribe_terminal`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TerminalSubscription {
+    pub session_id: String,
+    /// Subscriptions now open
+    pub viewers: usize,
+    /// Sequence number of the latest output chunk (0 before any output).
+    /// A viewer behind it fetches the rest with `replay_output`.
+    pub last_seq: u64,
+}
+
+/// Payload of the `pty-output-{session_id}` event and a `replay_output` chunk.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PtyOutput {
+    /// Per-session chunk number, starting at 1 and increasing by 1
+    pub seq: u64,
+    /// Session output bytes emitted before this chunk
+    pub offset: u64,
+    pub data: String,
+}
+
+/// Result of `replay_output`.
+#[derive(Debug, Serialize, Clone)]
+pub struct OutputReplay {
+    pub chunks: Vec<PtyOutput>,
+    /// False if some requested output was already dropped from scrollback,
+    /// in which case `chunks` starts at the oldest retained chunk
+    pub complete: bool,
+}
+
+/// Why a session is believed to be waiting for a password.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SecureInputReason {
+    /// The foreground program turned off terminal echo (unix only)
+    EchoOff,
+    /// The output ends in a line that looks like a password prompt
+    PromptPattern,
+}
+
+/// Payload of the `secure-input-requested` event. Answer it with
+/// `write_terminal_secure` rather than `write_terminal`.
+#[derive(Debug, Serialize, Clone)]
+pub struct SecureInputRequested {
+    pub session_id: String,
+    /// The prompt line, e.g. `[sudo] password for sam:`, if known
+    pub prompt: Option<String>,
+    pub reason: SecureInputReason,
+}
+
+/// Payload of the `command-finished` event. Only sessions whose shell emits
+/// OSC 133 shell integration marks (fish 4+, starship, oh-my-posh, or the
+/// iTerm2, WezTerm and VS Code integration scripts) report commands.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct CommandFinished {
+    pub session_id: String,
+    /// The command line as the shell reported or echoed it, if known
+    pub command: Option<String>,
+    /// None if the shell did not report a status
+    pub exit_code: Option<i32>,
+    /// Milliseconds from the command starting to the shell reporting its end
+    pub duration_ms: u64,
+}
+
+/// One entry of an `inject_commands` batch: either a bare command line or an
+/// object with options.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum BatchCommand {
+    Plain(String),
+    WithOptions(BatchCommandOptions),
+}
+
+/// A batch command with its options. Options that need the command's exit
+/// status (`stop_on_failure`, `expected_exit_code`, `timeout_ms`) make the
+/// batch wait for it to finish, which requires OSC 133 shell integration
+/// (see `CommandFinished`); without it such commands time out.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct BatchCommandOptions {
+    pub command: String,
+    /// Skip the rest of the batch unless this command succeeds
+    #[serde(default)]
+    pub stop_on_failure: bool,
+    /// Exit code that counts as success (default 0)
+    pub expected_exit_code: Option<i32>,
+    /// Longest wait for the command to finish (default 1 minute, at most 10)
+    pub timeout_ms: Option<u64>,
+    /// Pause before sending the command (default 100 ms, 0 for the first)
+    pub delay_ms: Option<u64>,
+}
+
+impl BatchCommand {
+    fn options(self) -> BatchCommandOptions {
+        match self {
+            Self::Plain(command) => BatchCommandOptions {
+                command,
+                stop_on_failure: false,
+                expected_exit_code: None,
+                timeout_ms: None,
+                delay_ms: None,
+            },
+            Self::WithOptions(options) => options,
+        }
+    }
+}
+
+impl BatchCommandOptions {
+    /// Whether the batch has to wait for this command's exit status.
+    fn waits(&self) -> bool {
+        self.stop_on_failure || self.expected_exit_code.is_some() || self.timeout_ms.is_some()
+    }
+}
+
+/// Outcome of one command of an `inject_commands` batch.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchCommandStatus {
+    /// Sent without waiting for it to finish
+    Sent,
+    /// Finished with the expected exit code
+    Succeeded,
+    /// Finished with another exit code, the shell reported none, or it
+    /// could not be sent (see `error`)
+    Failed,
+    /// Did not finish within its timeout
+    TimedOut,
+    /// Not sent because an earlier `stop_on_failure` command failed, or an
+    /// earlier command could not be sent
+    Skipped,
+}
+
+/// Per-command entry of the `inject_commands` result.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BatchCommandResult {
+    pub command: String,
+    pub status: BatchCommandStatus,
+    /// Exit code reported by the shell, if the batch waited for it
+    pub exit_code: Option<i32>,
+    /// Run time reported by the shell, if the batch waited for it
+    pub duration_ms: Option<u64>,
+    /// Why the command could not be sent (e.g. the session ended mid-batch)
+    pub error: Option<String>,
 }
 
 /// Structured output event for AI agent consumption.
@@ -56,34 +728,189 @@ pub struct TerminalOutput {
 
 /// Spawn a new terminal shell session.
 ///
-/// Creates a PTY, spawns the user's default shell, and starts streaming
-/// output to the frontend via `pty-output-{session_id}` events.
+/// Creates a PTY, spawns the requested shell (or `terminal.shell`, or the
+/// platform default), and streams its output to the frontend via
+/// `pty-output-{session_id}` events while a viewer is subscribed
+/// (`subscribe_terminal`).
 ///
 /// # Arguments
 /// * `session_id` - Optional session ID (generated if not provided)
 /// * `cwd` - Optional working directory for the shell (defaults to $HOME)
+/// * `shell` - Optional shell id from `list_available_shells`
+/// * `size` - Optional initial size (defaults to 80x24)
+/// * `args` - Optional extra arguments appended to the shell's, e.g. `--login`
+/// * `initial_command` / `initial_commands` - Optional commands to run once the
+///   shell shows its first prompt (`initial_command` first). Not run when an
+///   existing session is reused
+/// * `profile` - Optional name of a `terminal.profiles` environment to start
+///   the shell with; its `init_commands` run before the initial commands
+/// * `limits` - Optional memory, CPU time and priority limits applied to the
+///   shell and everything it runs (see `limits`)
 ///
-/// Returns the session ID (generated if not provided).
+/// With `terminal.persist_scrollback` on, the transcript saved for the same
+/// session id when the app last exited is restored for `get_transcript`.
+///
+/// Returns the session ID (generated if not provided). Fails with
+/// `session_limit_reached` when `terminal.max_sessions` sessions are open.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn spawn_terminal(
-    app: tauri::AppHandle,
+    app: AppHandle,
+    state: State<'_, PtyState>,
+    settings: State<'_, SettingsState>,
+    session_id: Option<String>,
+    cwd: Option<String>,
+    shell: Option<String>,
+    size: Option<TerminalSize>,
+    args: Option<Vec<String>>,
+    initial_command: Option<String>,
+    initial_commands: Option<Vec<String>>,
+    profile: Option<String>,
+    limits: Option<ResourceLimits>,
+) -> Result<String, AppError> {
+    let request = SpawnRequest {
+        session_id,
+        cwd,
+        shell,
+        size,
+        args,
+        initial_command,
+        initial_commands,
+        profile,
+        limits,
+        sandboxed: false,
+    };
+    spawn_session(app, &state, &settings, request).await
+}
+
+/// Spawn a terminal session for agent experimentation, with the shell
+/// confined by the best sandbox available (see `sandbox`): firejail on
+/// Linux, sandbox-exec on macOS, restricted bash otherwise.
+///
+/// Takes the same arguments as `spawn_terminal`; `cwd` is the only
+/// directory under HOME the shell can write to, so it may not be HOME or
+/// contain it. Without `cwd` the shell starts in a fresh directory under the
+/// app data dir. Reusing an existing session id only succeeds if that
+/// session is sandboxed too.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn spawn_sandboxed_terminal(
+    app: AppHandle,
     state: State<'_, PtyState>,
+    settings: State<'_, SettingsState>,
     session_id: Option<String>,
     cwd: Option<String>,
-) -> Result<String, String> {
+    shell: Option<String>,
+    size: Option<TerminalSize>,
+    args: Option<Vec<String>>,
+    initial_command: Option<String>,
+    initial_commands: Option<Vec<String>>,
+    profile: Option<String>,
+    limits: Option<ResourceLimits>,
+) -> Result<SandboxedTerminal, AppError> {
+    let request = SpawnRequest {
+        session_id,
+        cwd,
+        shell,
+        size,
+        args,
+        initial_command,
+        initial_commands,
+        profile,
+        limits,
+        sandboxed: true,
+    };
+    let session_id = spawn_session(app, &state, &settings, request).await?;
+    let sandbox = state
+        .sessions()
+        .get(&session_id)
+        .and_then(|s| s.sandbox.clone())
+        .ok_or_else(|| AppError::SessionNotFound(session_id.clone()))?;
+    Ok(SandboxedTerminal {
+        session_id,
+        sandbox,
+    })
+}
+
+/// Arguments of `spawn_terminal` and `spawn_sandboxed_terminal`.
+struct SpawnRequest {
+    session_id: Option<String>,
+    cwd: Option<String>,
+    shell: Option<String>,
+    size: Option<TerminalSize>,
+    args: Option<Vec<String>>,
+    initial_command: Option<String>,
+    initial_commands: Option<Vec<String>>,
+    profile: Option<String>,
+    limits: Option<ResourceLimits>,
+    sandboxed: bool,
+}
+
+async fn spawn_session(
+    app: AppHandle,
+    state: &PtyState,
+    settings: &SettingsState,
+    request: SpawnRequest,
+) -> Result<String, AppError> {
+    let SpawnRequest {
+        session_id,
+        cwd,
+        shell,
+        size,
+        args,
+        initial_command,
+        initial_commands,
+        profile,
+        limits,
+        sandboxed,
+    } = request;
     let session_id = session_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let size = size.unwrap_or_default();
+    size.validate()?;
+    let extra_args = args.unwrap_or_default();
+    check_shell_args(&extra_args)?;
+    let limits = limits.filter(|l| !l.is_empty());
+    if let Some(limits) = &limits {
+        limits.validate()?;
+    }
+    let profile = match profile {
+        Some(name) => Some(
+            settings
+                .get()
+                .terminal
+                .profiles
+                .into_iter()
+                .find(|p| p.name == name)
+                .ok_or_else(|| AppError::NotFound(format!("Environment profile not found: {}", name)))?,
+        ),
+        None => None,
+    };
+    let initial_commands: Vec<String> = profile
+        .iter()
+        .flat_map(|p| p.init_commands.iter().cloned())
+        .chain(initial_command)
+        .chain(initial_commands.unwrap_or_default())
+        .collect();
+    check_initial_commands(&initial_commands)?;
 
     // Idempotent: if session already exists, return it without spawning a new one.
     // This prevents session resets when multiple UI components share the same session
     // (e.g. panel card + focus dialog).
+    let max_sessions = settings.get().terminal.max_sessions;
     {
-        let sessions = state
-            .sessions
-            .lock()
-            .map_err(|e| format!("Failed to lock sessions: {}", e))?;
-        if sessions.contains_key(&session_id) {
-            log::info!("Session {} already exists, reusing", session_id);
-            return Ok(session_id);
+        let sessions = state.sessions();
+        if let Some(existing) = sessions.get(&session_id) {
+            return reuse_session(existing, session_id, sandboxed);
+        }
+
+        // Guard against runaway callers (e.g. an agent loop) spawning unbounded shells
+        if sessions.len() >= max_sessions {
+            log::warn!(
+                "Refusing to spawn session {}: limit of {} sessions reached",
+                session_id,
+                max_sessions
+            );
+            return Err(AppError::SessionLimitReached(max_sessions));
         }
     }
 
@@ -93,43 +920,110 @@ pub async fn spawn_terminal(
 
     let pair = pty_system
         .openpty(PtySize {
-            rows: 24,
-            cols: 80,
-            pixel_width: 0,
-            pixel_height: 0,
+            rows: size.rows,
+            cols: size.cols,
+            pixel_width: size.pixel_width,
+            pixel_height: size.pixel_height,
         })
-        .map_err(|e| format!("Failed to open PTY: {}", e))?;
-
-    // Determine shell from environment
-    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
+        .map_err(|e| AppError::Io(format!("Failed to open PTY: {}", e)))?;
 
-    let mut cmd = CommandBuilder::new(&shell);
-    cmd.env("TERM", "xterm-256color");
+    // Explicit request, then the configured shell, then the platform default
+    let shell_id = shell.unwrap_or_else(|| settings.get().terminal.shell);
+    let shell = tokio::task::spawn_blocking(move || shells::resolve_shell(Some(&shell_id)))
+        .await
+        .map_err(|e| AppError::Internal(format!("Shell lookup task failed: {}", e)))??;
 
     // Use provided cwd, or fall back to $HOME
-    if let Some(ref dir) = cwd {
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .ok()
+        .map(PathBuf::from);
+    let mut work_dir = if let Some(ref dir) = cwd {
         // Validate cwd exists and is a directory (prevents path traversal attacks)
         let path = std::path::Path::new(dir);
         if !path.exists() {
-            return Err(format!("Working directory does not exist: {}", dir));
+            return Err(AppError::Validation(format!("Working directory does not exist: {}", dir)));
         }
         if !path.is_dir() {
-            return Err(format!("Working directory is not a directory: {}", dir));
+            return Err(AppError::Validation(format!(
+                "Working directory is not a directory: {}",
+                dir
+            )));
         }
         // Canonicalize to resolve symlinks and normalize path
         let canonical = path
             .canonicalize()
-            .map_err(|e| format!("Failed to resolve working directory: {}", e))?;
-        cmd.cwd(&canonical);
+            .map_err(|e| AppError::Io(format!("Failed to resolve working directory: {}", e)))?;
         log::info!("Using cwd for session {}: {:?}", session_id, canonical);
-    } else if let Ok(home) = std::env::var("HOME") {
-        cmd.cwd(&home);
+        Some(canonical)
+    } else if sandboxed {
+        None
+    } else {
+        home.clone()
+    };
+
+    let sandbox = if sandboxed {
+        let data_dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| AppError::Io(format!("Failed to resolve app data dir: {}", e)))?;
+        // A fresh directory rather than HOME, which must stay read-only
+        let dir = match work_dir.take() {
+            Some(dir) => dir,
+            None => sandbox::fresh_work_dir(&data_dir)?,
+        };
+        let plan = sandbox::plan(&shell, &extra_args, &dir, home.as_deref(), &data_dir)?;
+        work_dir = Some(dir);
+        Some(plan)
+    } else {
+        None
+    };
+
+    let (program, program_args) = match &sandbox {
+        Some(plan) => (plan.program.clone(), plan.args.clone()),
+        None => {
+            let args = shell.args.iter().chain(&extra_args).cloned().collect();
+            (shell.program.clone(), args)
+        }
+    };
+    // Limits wrap the sandbox too, so they cover the sandbox launcher
+    let (program, program_args) = match &limits {
+        Some(limits) => {
+            log::info!("Limiting session {}: {:?}", session_id, limits);
+            limits::wrap(&program, &program_args, limits)
+        }
+        None => (program, program_args),
+    };
+    let mut cmd = CommandBuilder::new(&program);
+    cmd.args(&program_args);
+    cmd.env("TERM", "xterm-256color");
+    if let Some(profile) = &profile {
+        apply_profile(&mut cmd, profile)?;
+        log::info!("Applying environment profile {} to session {}", profile.name, session_id);
+    }
+    // After the profile, so a profile cannot widen the sandbox's PATH
+    if let Some(plan) = &sandbox {
+        for (key, value) in &plan.env {
+            cmd.env(key, value);
+        }
+        log::info!(
+            "Sandboxing session {} with {:?}: {}",
+            session_id,
+            plan.info.backend,
+            plan.info.restrictions.join("; ")
+        );
+    }
+    if let Some(dir) = &work_dir {
+        cmd.cwd(dir);
     }
+    // The shell inside the sandbox (rbash replaces the requested one)
+    let shell = sandbox.as_ref().map_or(shell, |plan| plan.shell.clone());
+    let start_dir = cmd.get_cwd().map(|dir| dir.to_string_lossy().into_owned());
 
     let child = pair
         .slave
         .spawn_command(cmd)
-        .map_err(|e| format!("Failed to spawn shell: {}", e))?;
+        .map_err(|e| AppError::Io(format!("Failed to spawn shell: {}", e)))?;
 
     // Drop slave after spawning — required for proper EOF behavior
     drop(pair.slave);
@@ -137,32 +1031,140 @@ pub async fn spawn_terminal(
     let writer = pair
         .master
         .take_writer()
-        .map_err(|e| format!("Failed to get PTY writer: {}", e))?;
+        .map_err(|e| AppError::Io(format!("Failed to get PTY writer: {}", e)))?;
 
     let mut reader = pair
         .master
         .try_clone_reader()
-        .map_err(|e| format!("Failed to get PTY reader: {}", e))?;
+        .map_err(|e| AppError::Io(format!("Failed to get PTY reader: {}", e)))?;
 
-    let writer = Arc::new(Mutex::new(writer));
+    // Input goes through a channel to a dedicated writer thread so callers never
+    // block on the PTY (or each other) and keystroke latency is independent of
+    // output volume. The thread exits once the session (and its sender) is dropped.
+    let (input_tx, input_rx) = mpsc::channel::<Vec<u8>>();
+    let writer_sid = session_id.clone();
+    let io = Arc::new(SessionIo::new());
+    let writer_io = Arc::clone(&io);
+    let reader_io = Arc::clone(&io);
+    std::thread::Builder::new()
+        .name(format!("pty-writer-{}", session_id))
+        .spawn(move || run_writer(writer, input_rx, writer_sid, writer_io))
+        .map_err(|e| AppError::Io(format!("Failed to start PTY writer: {}", e)))?;
+
+    let bracketed_paste = Arc::new(AtomicBool::new(false));
+    let (output_tx, _) = broadcast::channel::<String>(OUTPUT_CHANNEL_CAPACITY);
+    let reader_output_tx = output_tx.clone();
+    // Subscribed before the reader starts so the first prompt is not missed
+    let initial_rx = output_tx.subscribe();
+    let (finished_tx, _) = broadcast::channel::<CommandFinished>(FINISHED_CHANNEL_CAPACITY);
+    let reader_finished_tx = finished_tx.clone();
+    // Continue the transcript of the same session from the previous launch.
+    // Checked first: with the setting off, a saved file is left alone.
+    let mut transcript = Transcript::default();
+    let saved = if settings.get().terminal.persist_scrollback {
+        transcripts_dir(&app).and_then(|dir| take_saved_transcript(&dir, &session_id))
+    } else {
+        None
+    };
+    if let Some(text) = saved {
+        log::info!("Restored {} bytes of transcript for session {}", text.len(), session_id);
+        transcript.restore(&text);
+    }
+    let transcript = Arc::new(Mutex::new(transcript));
+    let reader_transcript = Arc::clone(&transcript);
+    let scrollback = Arc::new(Mutex::new(Scrollback::default()));
+    let reader_scrollback = Arc::clone(&scrollback);
+    let screen = Arc::new(ScreenModel::new(size.rows, size.cols));
+    let reader_screen = Arc::clone(&screen);
+    let recorder = RecorderSlot::default();
+    let reader_recorder = Arc::clone(&recorder);
+    let mut bells = BellScanner::default();
+    let mut paste_mode = PasteModeScanner::default();
+    let mut activity = ActivityTracker::default();
+    let mut log_mirror = TerminalLogMirror::new(&session_id);
+    let mut prompts = PromptDetector::default();
+    let mut commands = CommandTracker::default();
+    let secure_prompt = Arc::new(AtomicBool::new(false));
+    let reader_secure_prompt = Arc::clone(&secure_prompt);
+    let viewers = Arc::new(AtomicUsize::new(0));
+    let reader_viewers = Arc::clone(&viewers);
+    let shell_pid = child.process_id();
+    let profile_name = profile.map(|p| p.name);
 
-    // Store session
+    let mut session = PtySession {
+        input_tx,
+        master: pair.master,
+        child,
+        bracketed_paste: Arc::clone(&bracketed_paste),
+        output_tx,
+        finished_tx,
+        transcript,
+        scrollback,
+        screen,
+        recorder,
+        foreground: None,
+        lock: None,
+        secure_prompt,
+        quoting: QuoteStyle::for_shell(&shell),
+        shell_program: shell.program.clone(),
+        io,
+        viewers,
+        profile: profile_name.clone(),
+        sandbox: sandbox.map(|plan| plan.info),
+        limits,
+        idle_exempt: false,
+        idle_warned_at: None,
+        exit: None,
+    };
+    // Store session. The checks above ran before the awaits, so a concurrent
+    // spawn may have taken the id or the last slot since; the new shell is
+    // killed then instead of replacing (and leaking) the other session.
     {
-        let mut sessions = state
-            .sessions
-            .lock()
-            .map_err(|e| format!("Failed to lock sessions: {}", e))?;
+        let mut sessions = state.sessions();
+        let rejected = match sessions.get(&session_id) {
+            Some(existing) => Some(reuse_session(existing, session_id.clone(), sandboxed)),
+            None if sessions.len() >= max_sessions => {
+                Some(Err(AppError::SessionLimitReached(max_sessions)))
+            }
+            None => None,
+        };
+        match rejected {
+            None => {
+                sessions.insert(session_id.clone(), session);
+            }
+            Some(result) => {
+                drop(sessions);
+                log::warn!("Session {} spawned concurrently, killing the new shell", session_id);
+                kill_session(&session_id, &mut session);
+                return result;
+            }
+        }
+    }
 
-        sessions.insert(
+    if !initial_commands.is_empty() {
+        tokio::spawn(run_initial_commands(
+            app.clone(),
             session_id.clone(),
-            PtySession {
-                writer: Arc::clone(&writer),
-                master: pair.master,
-                child,
-            },
-        );
+            initial_rx,
+            initial_commands,
+        ));
     }
 
+    let fg_app = app.clone();
+    let fg_sid = session_id.clone();
+    std::thread::Builder::new()
+        .name(format!("pty-foreground-{}", session_id))
+        .spawn(move || watch_foreground(fg_app, fg_sid))
+        .map_err(|e| AppError::Io(format!("Failed to start foreground watcher: {}", e)))?;
+
+    history::session_started(
+        &app,
+        &session_id,
+        &shell.program,
+        start_dir.as_deref(),
+        profile_name.as_deref(),
+    );
+
     // Spawn blocking reader that streams output to frontend via events
     let event_name = format!("pty-output-{}", session_id);
     let sid = session_id.clone();
@@ -175,18 +1177,114 @@ pub async fn spawn_terminal(
                     break;
                 }
                 Ok(n) => {
+                    reader_io.record_read(n, Instant::now());
                     let data = String::from_utf8_lossy(&buf[..n]).to_string();
-                    // Raw output for xterm.js rendering
-                    if app.emit(&event_name, &data).is_err() {
+                    if let Some(enabled) = paste_mode.feed(&data) {
+                        bracketed_paste.store(enabled, Ordering::Relaxed);
+                    }
+                    // Backend waiters; an error only means nobody is listening
+                    let _ = reader_output_tx.send(data.clone());
+                    if let Ok(mut t) = reader_transcript.lock() {
+                        t.append(&data);
+                    }
+                    reader_screen.feed(&buf[..n]);
+                    let mut rec = reader_recorder.lock().unwrap_or_else(|e| e.into_inner());
+                    if let Some(rec) = rec.as_mut() {
+                        rec.output(&data);
+                    }
+                    drop(rec);
+                    if logging::capture_terminal() {
+                        log_mirror.write(&buf[..n]);
+                    }
+                    // A new line after a prompt means it was answered (or abandoned)
+                    if data.contains('\n') {
+                        reader_secure_prompt.store(false, Ordering::Relaxed);
+                    }
+                    if let Some(prompt) = prompts.feed(&buf[..n]) {
+                        if !reader_secure_prompt.swap(true, Ordering::Relaxed) {
+                            log::info!("Session {} is showing a password prompt", sid);
+                            let _ = events::emit(
+                                &app,
+                                "secure-input-requested",
+                                SecureInputRequested {
+                                    session_id: sid.clone(),
+                                    prompt: Some(prompt),
+                                    reason: SecureInputReason::PromptPattern,
+                                },
+                            );
+                        }
+                    }
+                    let finished = commands.feed(&buf[..n], Instant::now());
+                    for (command, exit_code, duration_ms) in finished {
+                        log::debug!(
+                            "Session {} finished a command (exit {:?}, {} ms)",
+                            sid,
+                            exit_code,
+                            duration_ms
+                        );
+                        let finished = CommandFinished {
+                            session_id: sid.clone(),
+                            command,
+                            exit_code,
+                            duration_ms,
+                        };
+                        history::command_finished(
+                            &app,
+                            &sid,
+                            shell_pid,
+                            finished.command.as_deref(),
+                            finished.exit_code,
+                            finished.duration_ms,
+                        );
+                        let _ = reader_finished_tx.send(finished.clone());
+                        let _ = events::emit(&app, "command-finished", finished);
+                    }
+                    // Attention signals so the UI can badge background sessions
+                    let count = bells.feed(&buf[..n]);
+                    if count > 0 {
+                        let _ = events::emit(
+                            &app,
+                            "terminal-bell",
+                            TerminalBell {
+                                session_id: sid.clone(),
+                                count,
+                                total: bells.total,
+                            },
+                        );
+                    }
+                    if let Some((burst_start, bytes, burst_ms)) = activity.record(n, Instant::now()) {
+                        let _ = events::emit(
+                            &app,
+                            "terminal-activity",
+                            TerminalActivity {
+                                session_id: sid.clone(),
+                                burst_start,
+                                bytes,
+                                burst_ms,
+                            },
+                        );
+                    }
+                    // Raw output for xterm.js rendering, numbered for gap detection.
+                    // Without a viewer it only goes to scrollback, for `replay_output`.
+                    let chunk = match reader_scrollback.lock() {
+                        Ok(mut sb) => sb.push(data.clone(), n),
+                        Err(_) => break,
+                    };
+                    if reader_viewers.load(Ordering::Relaxed) == 0 {
+                        continue;
+                    }
+                    if events::emit(&app, &event_name, &chunk).is_err() {
                         log::warn!("Failed to emit PTY output for session: {}", sid);
                         break;
                     }
-                    // Structured output for AI agent consumption
-                    let _ = app.emit(
+                    // Structured output for AI agent consumption, with secrets
+                    // masked; also only while the session is subscribed
+                    let _ = events::emit(
+                        &app,
                         "terminal-output-captured",
                         TerminalOutput {
                             session_id: sid.clone(),
-                            data: data.clone(),
+                            data: redact::redact(&data).into_owned(),
                             timestamp: chrono::Local::now().to_rfc3339(),
                         },
                     );
@@ -197,47 +1295,334 @@ pub async fn spawn_terminal(
                 }
             }
         }
+        if logging::capture_terminal() {
+            log_mirror.flush();
+        }
+        recording::finish(&reader_recorder, &sid);
         // Emit a close event so the frontend knows the session ended
-        let _ = app.emit(&format!("pty-close-{}", sid), ());
+        let _ = events::emit(&app, &format!("pty-close-{}", sid), ());
     });
 
-    log::info!("Terminal session {} started with shell: {}", session_id, shell);
+    log::info!(
+        "Terminal session {} started with shell: {} ({}x{})",
+        session_id,
+        shell.program,
+        size.cols,
+        size.rows
+    );
 
     Ok(session_id)
 }
 
-/// Write data to a terminal session's stdin.
-#[tauri::command]
-pub fn write_terminal(
-    state: State<'_, PtyState>,
+/// Result of spawning an id that is already taken: the existing session is
+/// reused, unless a sandbox was asked for and it is not sandboxed.
+fn reuse_session(
+    existing: &PtySession,
     session_id: String,
-    data: String,
-) -> Result<(), String> {
-    let sessions = state
-        .sessions
-        .lock()
-        .map_err(|e| format!("Failed to lock sessions: {}", e))?;
-
-    let session = sessions
-        .get(&session_id)
-        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    sandboxed: bool,
+) -> Result<String, AppError> {
+    if sandboxed && existing.sandbox.is_none() {
+        return Err(AppError::Validation(format!(
+            "Session {} already exists and is not sandboxed",
+            session_id
+        )));
+    }
+    log::info!("Session {} already exists, reusing", session_id);
+    Ok(session_id)
+}
 
-    let mut writer = session
-        .writer
-        .lock()
-        .map_err(|e| format!("Failed to lock writer: {}", e))?;
+/// Reject extra shell arguments that are too many or contain NUL bytes.
+fn check_shell_args(args: &[String]) -> Result<(), AppError> {
+    if args.len() > MAX_SHELL_ARGS {
+        return Err(AppError::Validation(format!(
+            "At most {} shell arguments are allowed, got: {}",
+            MAX_SHELL_ARGS,
+            args.len()
+        )));
+    }
+    if args.iter().any(|a| a.contains('\0')) {
+        return Err(AppError::Validation("Shell arguments must not contain NUL".into()));
+    }
+    Ok(())
+}
+
+/// Reject environment profiles with a missing or duplicate name, malformed
+/// variable names, NUL bytes, or invalid init commands.
+pub(crate) fn check_env_profiles(profiles: &[EnvProfile]) -> Result<(), AppError> {
+    let invalid = |msg: String| Err(AppError::Validation(msg));
+    let bad_var = |key: &String| key.is_empty() || key.contains(['=', '\0']);
+    let mut names = std::collections::HashSet::new();
+    for p in profiles {
+        if p.name.trim().is_empty() || p.name.len() > MAX_PROFILE_NAME_LEN {
+            return invalid(format!(
+                "Profile names must be 1-{} characters, got: {:?}",
+                MAX_PROFILE_NAME_LEN, p.name
+            ));
+        }
+        if !names.insert(p.name.as_str()) {
+            return invalid(format!("Duplicate profile name: {}", p.name));
+        }
+        if let Some(key) = p.env.keys().chain(&p.unset).find(|&k| bad_var(k)) {
+            return invalid(format!("Invalid variable name in profile {}: {:?}", p.name, key));
+        }
+        if p.env.values().chain(&p.path_prepend).any(|v| v.contains('\0')) {
+            return invalid(format!("Profile {} contains a NUL byte", p.name));
+        }
+        check_initial_commands(&p.init_commands)?;
+    }
+    Ok(())
+}
+
+/// Apply a profile's environment to the shell command: remove `unset`, set
+/// `env`, then put `path_prepend` in front of `PATH`.
+fn apply_profile(cmd: &mut CommandBuilder, profile: &EnvProfile) -> Result<(), AppError> {
+    for key in &profile.unset {
+        cmd.env_remove(key);
+    }
+    for (key, value) in &profile.env {
+        cmd.env(key, value);
+    }
+    if !profile.path_prepend.is_empty() {
+        let inherited: Vec<PathBuf> = cmd
+            .get_env("PATH")
+            .map(|path| std::env::split_paths(path).collect())
+            .unwrap_or_default();
+        let path = std::env::join_paths(
+            profile.path_prepend.iter().map(PathBuf::from).chain(inherited),
+        )
+        .map_err(|e| {
+            AppError::Validation(format!("Invalid PATH entry in profile {}: {}", profile.name, e))
+        })?;
+        cmd.env("PATH", path);
+    }
+    Ok(())
+}
+
+/// Reject initial commands that are too many, blank, or contain control
+/// characters (a line break would run a second, unlogged command).
+fn check_initial_commands(commands: &[String]) -> Result<(), AppError> {
+    if commands.len() > MAX_INITIAL_COMMANDS {
+        return Err(AppError::Validation(format!(
+            "At most {} initial commands are allowed, got: {}",
+            MAX_INITIAL_COMMANDS,
+            commands.len()
+        )));
+    }
+    if commands.iter().any(|c| c.trim().is_empty()) {
+        return Err(AppError::Validation("Initial commands must not be empty".into()));
+    }
+    if commands.iter().any(|c| c.chars().any(|ch| ch.is_control() && ch != '\t')) {
+        return Err(AppError::Validation(
+            "Initial commands must not contain control characters".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Type a new session's initial commands once its first prompt is up: on an
+/// OSC 133 prompt-end mark, after the first output is followed by a quiet
+/// spell, or after `PROMPT_MAX_WAIT` at the latest.
+async fn run_initial_commands(
+    app: AppHandle,
+    session_id: String,
+    mut output_rx: broadcast::Receiver<String>,
+    commands: Vec<String>,
+) {
+    let deadline = tokio::time::Instant::now() + PROMPT_MAX_WAIT;
+    let mut seen_output = false;
+    loop {
+        let wait_until = if seen_output {
+            deadline.min(tokio::time::Instant::now() + PROMPT_SETTLE_TIME)
+        } else {
+            deadline
+        };
+        match tokio::time::timeout_at(wait_until, output_rx.recv()).await {
+            Ok(Ok(chunk)) if is_prompt_end(&chunk) => break,
+            Ok(Ok(_)) | Ok(Err(broadcast::error::RecvError::Lagged(_))) => seen_output = true,
+            Ok(Err(broadcast::error::RecvError::Closed)) => {
+                log::warn!("Session {} closed before its initial commands ran", session_id);
+                return;
+            }
+            Err(_) if seen_output => break,
+            Err(_) => {
+                log::warn!(
+                    "No prompt from session {} within {:?}; typing initial commands anyway",
+                    session_id,
+                    PROMPT_MAX_WAIT
+                );
+                break;
+            }
+        }
+    }
 
-    writer
-        .write_all(data.as_bytes())
-        .map_err(|e| format!("Failed to write to PTY: {}", e))?;
+    let state = app.state::<PtyState>();
+    for command in &commands {
+        log::info!("Running initial command in session {}: {}", session_id, command);
+        if let Err(e) = inject_into_session(state.inner(), &session_id, command, None) {
+            log::warn!("Initial command for session {} failed: {}", session_id, e);
+            return;
+        }
+    }
+}
+
+/// Whether an output chunk contains an OSC 133 prompt-end mark.
+fn is_prompt_end(chunk: &str) -> bool {
+    chunk.contains("\x1b]133;B") || chunk.contains("\x1b]633;B")
+}
+
+/// Write data to a terminal session's stdin.
+///
+/// Input is queued to the session's writer thread and the command returns
+/// immediately; write failures are logged by the writer thread.
+///
+/// Fails with a validation error if `data` exceeds `terminal.max_write_bytes`
+/// or contains control characters rejected by `terminal.control_chars`, and
+/// with `rate_limited` when `owner` exceeds `terminal.rate_limit` writes.
+#[tauri::command]
+pub async fn write_terminal(
+    state: State<'_, PtyState>,
+    settings: State<'_, SettingsState>,
+    session_id: String,
+    data: String,
+    owner: Option<String>,
+) -> Result<(), AppError> {
+    let terminal = settings.get().terminal;
+    check_write_size(data.len(), terminal.max_write_bytes)?;
+    check_control_chars(&data, terminal.control_chars)?;
+    state.rate_limiter.check(
+        &terminal.rate_limit,
+        RateLimitKind::Write,
+        owner.as_deref(),
+        &session_id,
+        1,
+    )?;
+    send_input(&state, &session_id, data.into_bytes(), owner.as_deref())
+}
+
+/// Answer a password prompt in a terminal session.
+///
+/// `secret` is sent followed by a carriage return. Unlike `write_terminal`,
+/// the input is never logged, mirrored or recorded anywhere, and on unix the
+/// write is refused while the terminal is echoing input, so a secret cannot
+/// end up on screen, in the transcript or in the log by accident.
+///
+/// Fails with a validation error if `secret` is empty or contains control
+/// characters.
+#[tauri::command]
+pub fn write_terminal_secure(
+    state: State<'_, PtyState>,
+    session_id: String,
+    secret: String,
+    owner: Option<String>,
+) -> Result<(), AppError> {
+    check_secret(&secret)?;
 
-    writer
-        .flush()
-        .map_err(|e| format!("Failed to flush PTY: {}", e))?;
+    {
+        let sessions = state.sessions();
+        let session = sessions
+            .get(&session_id)
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+        if echo_enabled(session.master.as_ref()) == Some(true) {
+            return Err(AppError::Validation(
+                "Terminal is echoing input; refusing to send a secret".into(),
+            ));
+        }
+        session.secure_prompt.store(false, Ordering::Relaxed);
+    }
 
+    let mut data = secret.into_bytes();
+    data.push(b'\r');
+    queue_input(&state, &session_id, data, owner.as_deref(), true)?;
+    log::info!("Sent secure input to session {}", session_id);
     Ok(())
 }
 
+/// Paste text into a terminal session.
+///
+/// Control characters (including ESC) are stripped so pasted content cannot
+/// smuggle escape sequences, and newlines are sent as carriage returns like a
+/// real terminal paste. When the running application has enabled bracketed
+/// paste mode, the text is wrapped in paste markers so shells and REPLs insert
+/// multi-line content without executing each line.
+#[tauri::command]
+pub fn paste_terminal(
+    state: State<'_, PtyState>,
+    settings: State<'_, SettingsState>,
+    session_id: String,
+    text: String,
+    owner: Option<String>,
+) -> Result<(), AppError> {
+    check_write_size(text.len(), settings.get().terminal.max_write_bytes)?;
+
+    let sessions = state.sessions();
+
+    let session = sessions
+        .get(&session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+
+    let bracketed = session.bracketed_paste.load(Ordering::Relaxed);
+    drop(sessions);
+    let payload = prepare_paste(&text, bracketed);
+
+    log::debug!(
+        "Pasting {} bytes into session {} (bracketed: {})",
+        payload.len(),
+        session_id,
+        bracketed
+    );
+
+    send_input(&state, &session_id, payload.into_bytes(), owner.as_deref())
+}
+
+/// Type file paths into a terminal session, e.g. for files dropped onto it.
+///
+/// Each path is quoted for the session's shell (POSIX shells, PowerShell or
+/// cmd) when it contains spaces, quotes or other special characters, and
+/// the paths are written space-separated with a trailing space, without a
+/// newline, so nothing runs until the user presses Enter. In WSL sessions
+/// Windows paths are translated to their `/mnt/<drive>` form.
+///
+/// Fails with a validation error if `paths` is empty, has more than 256
+/// entries, or a path contains control characters.
+#[tauri::command]
+pub fn inject_paths(
+    state: State<'_, PtyState>,
+    settings: State<'_, SettingsState>,
+    session_id: String,
+    paths: Vec<String>,
+    owner: Option<String>,
+) -> Result<(), AppError> {
+    if paths.is_empty() || paths.len() > MAX_INJECTED_PATHS {
+        return Err(AppError::Validation(format!(
+            "Expected 1-{} paths, got: {}",
+            MAX_INJECTED_PATHS,
+            paths.len()
+        )));
+    }
+    if let Some(path) = paths.iter().find(|p| p.is_empty() || p.chars().any(char::is_control)) {
+        return Err(AppError::Validation(format!("Invalid path: {:?}", path)));
+    }
+
+    let quoting = {
+        let sessions = state.sessions();
+        sessions
+            .get(&session_id)
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?
+            .quoting
+    };
+
+    let mut text = paths
+        .iter()
+        .map(|p| quoting.quote_path(p))
+        .collect::<Vec<_>>()
+        .join(" ");
+    text.push(' ');
+    check_write_size(text.len(), settings.get().terminal.max_write_bytes)?;
+
+    log::info!("Injecting {} path(s) into session {}", paths.len(), session_id);
+    send_input(&state, &session_id, text.into_bytes(), owner.as_deref())
+}
+
 /// Resize a terminal session's PTY.
 #[tauri::command]
 pub fn resize_terminal(
@@ -245,15 +1630,12 @@ pub fn resize_terminal(
     session_id: String,
     rows: u16,
     cols: u16,
-) -> Result<(), String> {
-    let sessions = state
-        .sessions
-        .lock()
-        .map_err(|e| format!("Failed to lock sessions: {}", e))?;
+) -> Result<(), AppError> {
+    let sessions = state.sessions();
 
     let session = sessions
         .get(&session_id)
-        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+        .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
 
     session
         .master
@@ -263,7 +1645,11 @@ pub fn resize_terminal(
             pixel_width: 0,
             pixel_height: 0,
         })
-        .map_err(|e| format!("Failed to resize PTY: {}", e))?;
+        .map_err(|e| AppError::Io(format!("Failed to resize PTY: {}", e)))?;
+    session.screen.resize(rows, cols);
+    if let Some(rec) = session.recorder.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        rec.resize(cols, rows);
+    }
 
     log::debug!(
         "Resized session {} to {}x{}",
@@ -281,8 +1667,13 @@ pub fn resize_terminal(
 /// to ensure all child processes (e.g. `claude`, `npm`) are terminated,
 /// not just the direct shell. This matches the Alacritty/WezTerm pattern.
 ///
+/// On Windows there are no process groups to signal, so the tree is torn
+/// down with `taskkill /T /F`, which walks parent-PID links to reach
+/// grandchildren (node, cargo) that `child.kill()` would leave orphaned.
+///
 /// Safe implementation via the `nix` crate — no `unsafe` blocks required.
 fn kill_session(session_id: &str, session: &mut PtySession) {
+    recording::finish(&session.recorder, session_id);
     if let Some(raw_pid) = session.child.process_id() {
         #[cfg(unix)]
         {
@@ -308,11 +1699,25 @@ fn kill_session(session_id: &str, session: &mut PtySession) {
             }
         }
 
-        // Fallback for non-unix (Windows)
+        // Windows walks the tree with taskkill; other non-unix targets can
+        // only kill the direct child
         #[cfg(not(unix))]
         {
-            if let Err(e) = session.child.kill() {
-                log::warn!("Failed to kill child for session {}: {}", session_id, e);
+            #[cfg(windows)]
+            let tree_killed = kill_process_tree_windows(raw_pid);
+            #[cfg(not(windows))]
+            let tree_killed: Result<(), AppError> =
+                Err(AppError::Internal("no process tree kill on this platform".into()));
+            if let Err(e) = tree_killed {
+                log::warn!(
+                    "Killing the process tree failed for session {} ({}), falling back to \
+                     child.kill()",
+                    session_id,
+                    e
+                );
+                if let Err(e) = session.child.kill() {
+                    log::warn!("Failed to kill child for session {}: {}", session_id, e);
+                }
             }
         }
     } else {
@@ -328,42 +1733,71 @@ fn kill_session(session_id: &str, session: &mut PtySession) {
     }
 
     log::info!("Killed terminal session: {}", session_id);
-    // Dropping session releases master PTY, writer, etc.
+    // Dropping session releases master PTY and closes the input channel
+}
+
+/// Terminate a process and all of its descendants on Windows.
+///
+/// Runs `taskkill /PID <pid> /T /F` without flashing a console window.
+#[cfg(windows)]
+pub(crate) fn kill_process_tree_windows(pid: u32) -> Result<(), AppError> {
+    use std::os::windows::process::CommandExt;
+
+    /// Process creation flag that suppresses the console window
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    log::debug!("Running taskkill /T /F for process tree {}", pid);
+    let output = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| AppError::Io(format!("failed to run taskkill: {}", e)))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(AppError::Io(String::from_utf8_lossy(&output.stderr).trim().to_string()))
+    }
+}
+
+/// Take a session out of the map along with what is kept per session id
+/// elsewhere: its rate limit budgets and a transcript saved by a previous
+/// launch. The session's processes are left to the caller.
+fn remove_session(app: &AppHandle, state: &PtyState, session_id: &str) -> Option<PtySession> {
+    let session = state.sessions().remove(session_id);
+    state.rate_limiter.forget_session(session_id);
+    if let Some(dir) = transcripts_dir(app) {
+        take_saved_transcript(&dir, session_id);
+    }
+    session
 }
 
 /// Kill a terminal session and clean up all child processes.
+///
+/// A transcript saved for the session by a previous launch is discarded.
 #[tauri::command]
 pub fn kill_terminal(
+    app: AppHandle,
     state: State<'_, PtyState>,
     session_id: String,
-) -> Result<(), String> {
-    let mut sessions = state
-        .sessions
-        .lock()
-        .map_err(|e| format!("Failed to lock sessions: {}", e))?;
-
-    let mut session = sessions
-        .remove(&session_id)
-        .ok_or_else(|| format!("Session not found: {}", session_id))?;
+) -> Result<(), AppError> {
+    let mut session = remove_session(&app, &state, &session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
 
     kill_session(&session_id, &mut session);
+    history::session_ended(&app, &session_id, None);
 
     Ok(())
 }
 
 /// Kill all active PTY sessions. Called on app exit to prevent leaked processes.
-pub fn kill_all_sessions(state: &PtyState) {
-    let mut sessions = match state.sessions.lock() {
-        Ok(s) => s,
-        Err(e) => {
-            log::error!("Failed to lock sessions for cleanup: {}", e);
-            return;
-        }
-    };
+/// Returns how many were killed.
+pub fn kill_all_sessions(state: &PtyState) -> usize {
+    let mut sessions = state.sessions();
 
     let count = sessions.len();
     if count == 0 {
-        return;
+        return 0;
     }
 
     let ids: Vec<String> = sessions.keys().cloned().collect();
@@ -374,31 +1808,303 @@ pub fn kill_all_sessions(state: &PtyState) {
     }
 
     log::info!("App exit: killed {} PTY session(s)", count);
+    count
+}
+
+// =============================================================================
+// Idle Cleanup
+// =============================================================================
+
+/// Start the idle cleanup policy. It does nothing while
+/// `terminal.idle_kill_hours` is 0, so it is always running.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let terminal = app.state::<SettingsState>().get().terminal;
+            if terminal.idle_kill_hours == 0 {
+                continue;
+            }
+            let policy = IdlePolicy {
+                limit: Duration::from_secs(terminal.idle_kill_hours * 3600),
+                warning: Duration::from_secs(terminal.idle_warning_mins * 60),
+            };
+            cleanup_idle_sessions(&app, &policy);
+        }
+    });
+}
+
+/// Idle period after which a session is killed, and the notice given.
+struct IdlePolicy {
+    limit: Duration,
+    warning: Duration,
+}
+
+/// What the idle cleanup does with a session on one check.
+#[derive(Debug, PartialEq, Eq)]
+enum IdleAction {
+    /// Not idle (or not eligible): forget any earlier warning
+    Reset,
+    Warn,
+    /// Warned, waiting for the limit or the end of the notice period
+    Wait,
+    Kill,
+}
+
+/// Decide the fate of a session. Only `eligible` sessions (shell at its
+/// prompt, not exempt) count as idle, and a session is only killed once it
+/// has been warned for the whole notice period, even if the policy was
+/// enabled after it had already gone idle.
+fn idle_action(
+    eligible: bool,
+    idle: Duration,
+    warned_for: Option<Duration>,
+    policy: &IdlePolicy,
+) -> IdleAction {
+    if !eligible || idle + policy.warning < policy.limit {
+        return IdleAction::Reset;
+    }
+    match warned_for {
+        None => IdleAction::Warn,
+        Some(w) if idle >= policy.limit && w >= policy.warning => IdleAction::Kill,
+        Some(_) => IdleAction::Wait,
+    }
+}
+
+/// Warn about and kill idle sessions, emitting `terminal-idle-warning` and
+/// `terminal-idle-killed`.
+fn cleanup_idle_sessions(app: &AppHandle, policy: &IdlePolicy) {
+    let state = app.state::<PtyState>();
+    let now = Instant::now();
+    let mut warned = Vec::new();
+    let mut killed = Vec::new();
+    {
+        let mut sessions = state.sessions();
+        for (id, session) in sessions.iter_mut() {
+            let idle = session.io.input_idle(now);
+            let eligible = !session.idle_exempt && session.at_prompt();
+            let warned_for = session.idle_warned_at.map(|t| now.saturating_duration_since(t));
+            match idle_action(eligible, idle, warned_for, policy) {
+                IdleAction::Reset => session.idle_warned_at = None,
+                IdleAction::Warn => {
+                    session.idle_warned_at = Some(now);
+                    let kill_in = policy.limit.saturating_sub(idle).max(policy.warning);
+                    warned.push(TerminalIdle {
+                        session_id: id.clone(),
+                        idle_secs: idle.as_secs(),
+                        kill_in_secs: kill_in.as_secs(),
+                    });
+                }
+                IdleAction::Wait => {}
+                IdleAction::Kill => killed.push(TerminalIdle {
+                    session_id: id.clone(),
+                    idle_secs: idle.as_secs(),
+                    kill_in_secs: 0,
+                }),
+            }
+        }
+        for event in &killed {
+            if let Some(mut session) = sessions.remove(&event.session_id) {
+                kill_session(&event.session_id, &mut session);
+            }
+        }
+    }
+
+    for event in warned {
+        log::info!(
+            "Session {} idle for {}s, killing it in {}s",
+            event.session_id,
+            event.idle_secs,
+            event.kill_in_secs
+        );
+        let _ = events::emit(app, "terminal-idle-warning", event);
+    }
+    for event in killed {
+        history::session_ended(app, &event.session_id, None);
+        log::info!("Killed session {} after {}s idle", event.session_id, event.idle_secs);
+        let _ = events::emit(app, "terminal-idle-killed", event);
+    }
+}
+
+// =============================================================================
+// Transcript Persistence
+// =============================================================================
+
+/// Get the directory holding saved session transcripts.
+fn transcripts_dir(app: &AppHandle) -> Option<PathBuf> {
+    let data_dir = app.path().app_data_dir().ok()?;
+    Some(data_dir.join(SAVED_TRANSCRIPTS_DIR))
+}
+
+/// Save the transcript tail of every open session, when
+/// `terminal.persist_scrollback` is on. Called on app exit, before the
+/// sessions are killed.
+pub fn save_transcripts(app: &AppHandle, state: &PtyState) {
+    if !app.state::<SettingsState>().get().terminal.persist_scrollback {
+        return;
+    }
+    let Some(dir) = transcripts_dir(app) else {
+        log::error!("Could not determine transcripts directory");
+        return;
+    };
+
+    let transcripts: Vec<(String, Arc<Mutex<Transcript>>)> = state
+        .sessions()
+        .iter()
+        .map(|(id, s)| (id.clone(), Arc::clone(&s.transcript)))
+        .collect();
+
+    let mut saved = 0;
+    for (id, transcript) in transcripts {
+        let Ok(transcript) = transcript.lock() else {
+            continue;
+        };
+        match write_saved_transcript(&dir, &id, &transcript.text) {
+            Ok(true) => saved += 1,
+            Ok(false) => {}
+            Err(e) => log::error!("Failed to save transcript of session {}: {}", id, e),
+        }
+    }
+    log::info!("Saved {} session transcript(s)", saved);
+}
+
+/// File a session's transcript is saved to. Only ids that are safe as file
+/// names are persisted (generated UUIDs and the UI's `terminal-<panel>` ids).
+fn saved_transcript_path(dir: &Path, session_id: &str) -> Option<PathBuf> {
+    let safe = !session_id.is_empty()
+        && session_id.len() <= 128
+        && session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    safe.then(|| dir.join(format!("{}.txt", session_id)))
+}
+
+/// Write the last 64 KiB of `text`. Returns false if the session id cannot
+/// be persisted or there is nothing to save.
+fn write_saved_transcript(dir: &Path, session_id: &str, text: &str) -> std::io::Result<bool> {
+    let Some(path) = saved_transcript_path(dir, session_id) else {
+        return Ok(false);
+    };
+    let mut tail = text.to_string();
+    trim_front(&mut tail, SAVED_TRANSCRIPT_BYTES);
+    if tail.trim().is_empty() {
+        return Ok(false);
+    }
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(path, tail)?;
+    Ok(true)
+}
+
+/// Read and delete a saved transcript, so it is restored at most once.
+fn take_saved_transcript(dir: &Path, session_id: &str) -> Option<String> {
+    let path = saved_transcript_path(dir, session_id)?;
+    let text = std::fs::read_to_string(&path).ok()?;
+    if let Err(e) = std::fs::remove_file(&path) {
+        log::warn!("Failed to remove saved transcript {:?}: {}", path, e);
+    }
+    Some(text)
 }
 
 /// List all active terminal sessions.
 ///
 /// # Security Note
 /// This is a diagnostic command for AI agents and development tooling.
-/// Only returns session IDs and alive status — no sensitive data exposed.
+/// Only returns session IDs, status and I/O counters — no sensitive data exposed.
 #[tauri::command]
 pub fn list_terminals(
     state: State<'_, PtyState>,
-) -> Result<Vec<TerminalInfo>, String> {
-    let sessions = state
-        .sessions
-        .lock()
-        .map_err(|e| format!("Failed to lock sessions: {}", e))?;
+) -> Result<Vec<TerminalInfo>, AppError> {
+    Ok(terminal_infos(&state))
+}
+
+/// Info on every open session, for `list_terminals` and the dashboard.
+pub(crate) fn terminal_infos(state: &PtyState) -> Vec<TerminalInfo> {
+    state
+        .sessions()
+        .iter()
+        .map(|(id, session)| TerminalInfo {
+            session_id: id.clone(),
+            is_alive: session.exit.is_none(),
+            exit: session.exit.clone(),
+            foreground: session.foreground.clone(),
+            lock: session.lock.clone(),
+            stats: session.io.stats(Instant::now()),
+            viewers: session.viewers.load(Ordering::Relaxed),
+            idle_exempt: session.idle_exempt,
+            profile: session.profile.clone(),
+            sandbox: session.sandbox.clone(),
+            limits: session.limits,
+        })
+        .collect()
+}
+
+/// Shell and foreground process of an open session (see `session_processes`).
+pub(crate) struct SessionProcess {
+    pub session_id: String,
+    pub shell_pid: Option<u32>,
+    pub foreground: Option<ForegroundProcess>,
+    /// Time since the last input
+    pub input_idle: Duration,
+}
 
-    let terminals: Vec<TerminalInfo> = sessions
-        .keys()
-        .map(|id| TerminalInfo {
+/// Processes of every open session, sorted by id, for `get_agent_context`.
+pub(crate) fn session_processes(state: &PtyState) -> Vec<SessionProcess> {
+    let sessions = state.sessions();
+    let now = Instant::now();
+    let mut processes: Vec<SessionProcess> = sessions
+        .iter()
+        .map(|(id, session)| SessionProcess {
             session_id: id.clone(),
-            is_alive: true,
+            shell_pid: session.child.process_id(),
+            foreground: session.foreground.clone(),
+            input_idle: session.io.input_idle(now),
         })
         .collect();
+    processes.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+    processes
+}
+
+/// Shell program and shell pid of a session, for `get_completions` and file
+/// transfers.
+pub(crate) fn session_shell(
+    state: &PtyState,
+    session_id: &str,
+) -> Result<(String, Option<u32>), AppError> {
+    let sessions = state.sessions();
+    let session = sessions
+        .get(session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+    if session.exit.is_some() {
+        return Err(AppError::Validation(format!("Session {} has exited", session_id)));
+    }
+    Ok((session.shell_program.clone(), session.child.process_id()))
+}
+
+/// The process in the foreground of a session, for `create_port_forward`.
+pub(crate) fn session_foreground(
+    state: &PtyState,
+    session_id: &str,
+) -> Result<Option<ForegroundProcess>, AppError> {
+    let sessions = state.sessions();
+    let session = sessions
+        .get(session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+    Ok(session.foreground.clone())
+}
 
-    Ok(terminals)
+/// Bytes read and written by a session, and its current output rate.
+#[tauri::command]
+pub fn get_session_stats(
+    state: State<'_, PtyState>,
+    session_id: String,
+) -> Result<SessionStats, AppError> {
+    let sessions = state.sessions();
+    let session = sessions
+        .get(&session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+    Ok(session.io.stats(Instant::now()))
 }
 
 // =============================================================================
@@ -414,11 +2120,110 @@ pub fn list_terminals(
 // - No shell expansion or injection risks — commands are written as-is to PTY
 // - Session IDs must match existing sessions (no arbitrary session creation)
 // - All commands are logged for auditability
-// - No rate limiting on command injection — callers should throttle if needed
-// - No command length limit enforced — PTY write is unbounded; consider adding
-//   a max length guard if exposing to untrusted input sources
+// - inject_command, inject_commands and write_terminal are rate limited per
+//   caller (`owner`) and session by the token buckets of
+//   `terminal.rate_limit`; refused calls fail with `rate_limited`
+// - write_terminal, paste_terminal and inject_paths payloads are capped at
+//   `terminal.max_write_bytes` (1 MiB by default); injected commands are
+//   written as given
 // =============================================================================
 
+/// Take the exclusive input lock on a session.
+///
+/// While held, input from anyone but `owner` (agents passing a different
+/// `owner`, or the UI passing none) is rejected with `session_locked`.
+/// Re-acquiring a lock you already hold succeeds and keeps `acquired_at`.
+///
+/// # Arguments
+/// * `owner` - Name identifying the holder, e.g. `"claude-agent-1"`
+#[tauri::command]
+pub fn acquire_session_lock(
+    state: State<'_, PtyState>,
+    session_id: String,
+    owner: String,
+) -> Result<SessionLock, AppError> {
+    if owner.trim().is_empty() || owner.len() > MAX_LOCK_OWNER_LEN {
+        return Err(AppError::Validation(format!(
+            "Lock owner must be 1-{} characters",
+            MAX_LOCK_OWNER_LEN
+        )));
+    }
+
+    let mut sessions = state.sessions();
+
+    let session = sessions
+        .get_mut(&session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.clone()))?;
+
+    match &session.lock {
+        Some(lock) if lock.owner == owner => Ok(lock.clone()),
+        Some(lock) => Err(AppError::SessionLocked(lock.owner.clone())),
+        None => {
+            let lock = SessionLock {
+                owner,
+                acquired_at: chrono::Local::now().to_rfc3339(),
+            };
+            log::info!("Session {} locked by {}", session_id, lock.owner);
+            session.lock = Some(lock.clone());
+            Ok(lock)
+        }
+    }
+}
+
+/// Release a session's input lock.
+///
+/// Releasing an unlocked session is a no-op. Only the holder may release
+/// unless `force` is true (lets a human break a lock left by a crashed agent).
+#[tauri::command]
+pub fn release_session_lock(
+    state: State<'_, PtyState>,
+    session_id: String,
+    owner: String,
+    force: Option<bool>,
+) -> Result<(), AppError> {
+    let mut sessions = state.sessions();
+
+    let session = sessions
+        .get_mut(&session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.clone()))?;
+
+    match &session.lock {
+        Some(lock) if lock.owner != owner && !force.unwrap_or(false) => {
+            Err(AppError::SessionLocked(lock.owner.clone()))
+        }
+        Some(lock) => {
+            log::info!(
+                "Session {} lock held by {} released by {}",
+                session_id,
+                lock.owner,
+                owner
+            );
+            session.lock = None;
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Exempt a session from the idle cleanup policy (`terminal.idle_kill_hours`),
+/// or make it subject to it again.
+#[tauri::command]
+pub fn set_idle_exempt(
+    state: State<'_, PtyState>,
+    session_id: String,
+    exempt: bool,
+) -> Result<(), AppError> {
+    let mut sessions = state.sessions();
+
+    let session = sessions
+        .get_mut(&session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.clone()))?;
+    session.idle_exempt = exempt;
+    session.idle_warned_at = None;
+    log::info!("Session {} idle exempt: {}", session_id, exempt);
+    Ok(())
+}
+
 /// Inject a single command into a terminal session.
 ///
 /// Appends a newline to execute the command. The command appears in the
@@ -427,105 +2232,2074 @@ pub fn list_terminals(
 /// # Security Note
 /// This command is intended for AI agent integration. The injected command
 /// runs with user privileges. All injections are logged for auditability.
+/// Injections are throttled per `owner` and session by `terminal.rate_limit`
+/// (`rate_limited` error).
 #[tauri::command]
 pub fn inject_command(
     state: State<'_, PtyState>,
+    settings: State<'_, SettingsState>,
     session_id: String,
     command: String,
-) -> Result<(), String> {
+    owner: Option<String>,
+) -> Result<(), AppError> {
+    state.rate_limiter.check(
+        &settings.get().terminal.rate_limit,
+        RateLimitKind::Inject,
+        owner.as_deref(),
+        &session_id,
+        1,
+    )?;
     log::info!(
         "Injecting command into session {}: {}",
         session_id,
         command
     );
 
-    let sessions = state
-        .sessions
-        .lock()
-        .map_err(|e| format!("Failed to lock sessions: {}", e))?;
-
-    let session = sessions
-        .get(&session_id)
-        .ok_or_else(|| format!("Session not found: {}", session_id))?;
-
-    let mut writer = session
-        .writer
-        .lock()
-        .map_err(|e| format!("Failed to lock writer: {}", e))?;
-
-    // Write command followed by newline to execute
-    writer
-        .write_all(command.as_bytes())
-        .map_err(|e| format!("Failed to write command: {}", e))?;
-    writer
-        .write_all(b"\n")
-        .map_err(|e| format!("Failed to write newline: {}", e))?;
-    writer
-        .flush()
-        .map_err(|e| format!("Failed to flush: {}", e))?;
+    inject_into_session(&state, &session_id, &command, owner.as_deref())
+}
 
+/// Write a command followed by a newline into a session.
+///
+/// Shared by the inject commands and backend subsystems (e.g. the scheduler)
+/// that run commands without going through IPC. Callers handle audit logging.
+/// Fails with `session_locked` if the session is locked by someone other than
+/// `owner`.
+pub fn inject_into_session(
+    state: &PtyState,
+    session_id: &str,
+    command: &str,
+    owner: Option<&str>,
+) -> Result<(), AppError> {
+    // Command followed by newline to execute, queued as one chunk so it
+    // cannot interleave with concurrent keystrokes
+    let mut input = command.as_bytes().to_vec();
+    input.push(b'\n');
+    send_input(state, session_id, input, owner)?;
+    state.remember_command(command);
     Ok(())
 }
 
-/// Inject multiple commands sequentially into a terminal session.
+/// Wait until a session prints output matching a regex.
 ///
-/// Each command is sent with a newline. A short delay between commands
-/// allows the shell to process each one.
+/// Only output produced after the call starts is considered. Output is
+/// ANSI-stripped before matching, so patterns see the visible text (a
+/// colored prompt still matches `\$ $`). Fails with `timeout` if nothing
+/// matches within `timeout_ms`. Patterns see the raw output; the returned
+/// text has `terminal.redaction` secrets masked.
 ///
-/// # Security Note
-/// This command is intended for AI agent integration. Each injected command
-/// runs with user privileges. All injections are logged for auditability.
+/// # Arguments
+/// * `pattern` - Regular expression (Rust `regex` syntax)
+/// * `timeout_ms` - Maximum wait, 1 ms to 10 minutes
 #[tauri::command]
-pub async fn inject_commands(
+pub async fn wait_for_output(
     state: State<'_, PtyState>,
     session_id: String,
-    commands: Vec<String>,
-) -> Result<(), String> {
-    log::info!(
-        "Injecting {} commands into session {}",
-        commands.len(),
+    pattern: String,
+    timeout_ms: u64,
+) -> Result<OutputMatch, AppError> {
+    if !(1..=MAX_WAIT_TIMEOUT_MS).contains(&timeout_ms) {
+        return Err(AppError::Validation(format!(
+            "Timeout must be 1-{} ms, got: {}",
+            MAX_WAIT_TIMEOUT_MS, timeout_ms
+        )));
+    }
+
+    let re = regex::Regex::new(&pattern)
+        .map_err(|e| AppError::Validation(format!("Invalid pattern: {}", e)))?;
+
+    let mut output_rx = {
+        let sessions = state.sessions();
+        sessions
+            .get(&session_id)
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?
+            .output_tx
+            .subscribe()
+    };
+
+    log::debug!(
+        "Waiting up to {}ms for /{}/ in session {}",
+        timeout_ms,
+        pattern,
         session_id
     );
 
-    for (i, command) in commands.iter().enumerate() {
-        log::debug!(
-            "Injecting command {}/{}: {}",
-            i + 1,
-            commands.len(),
-            command
-        );
-
-        {
-            let sessions = state
-                .sessions
-                .lock()
-                .map_err(|e| format!("Failed to lock sessions: {}", e))?;
+    let start = Instant::now();
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout_ms);
+    let mut stripper = AnsiStripper::new();
+    let mut buffer = String::new();
 
-            let session = sessions
-                .get(&session_id)
-                .ok_or_else(|| format!("Session not found: {}", session_id))?;
+    loop {
+        let chunk = match tokio::time::timeout_at(deadline, output_rx.recv()).await {
+            Ok(Ok(chunk)) => chunk,
+            Ok(Err(broadcast::error::RecvError::Lagged(n))) => {
+                log::warn!("wait_for_output on {} skipped {} chunk(s)", session_id, n);
+                continue;
+            }
+            Ok(Err(broadcast::error::RecvError::Closed)) => {
+                return Err(AppError::Io(format!(
+                    "Session {} closed before output matched",
+                    session_id
+                )));
+            }
+            Err(_) => {
+                return Err(AppError::Timeout(format!(
+                    "No output matching /{}/ in session {} within {} ms",
+                    pattern, session_id, timeout_ms
+                )));
+            }
+        };
 
-            let mut writer = session
-                .writer
-                .lock()
-                .map_err(|e| format!("Failed to lock writer: {}", e))?;
+        buffer.push_str(&stripper.feed(chunk.as_bytes()));
 
-            writer
-                .write_all(command.as_bytes())
-                .map_err(|e| format!("Failed to write command: {}", e))?;
-            writer
-                .write_all(b"\n")
-                .map_err(|e| format!("Failed to write newline: {}", e))?;
-            writer
-                .flush()
-                .map_err(|e| format!("Failed to flush: {}", e))?;
+        if let Some(m) = re.find(&buffer) {
+            return Ok(OutputMatch {
+                matched: redact::redact(m.as_str()).into_owned(),
+                context: redact::redact(&match_context(&buffer, m.start(), m.end())).into_owned(),
+                elapsed_ms: start.elapsed().as_millis() as u64,
+            });
         }
 
-        // Brief delay between commands to let the shell process each one
-        if i < commands.len() - 1 {
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        }
+        trim_front(&mut buffer, WAIT_BUFFER_MAX_BYTES);
     }
+}
 
-    Ok(())
+/// Get readable recent output of a session without escape sequences.
+///
+/// # Arguments
+/// * `max_chars` - Maximum characters to return (default: 4000)
+/// * `from_end` - Take the most recent output (default) or the oldest retained
+///
+/// Sessions retain the last 256 KiB of plain text. Secrets matching
+/// `terminal.redaction` rules are masked.
+#[tauri::command]
+pub fn get_transcript(
+    state: State<'_, PtyState>,
+    session_id: String,
+    max_chars: Option<usize>,
+    from_end: Option<bool>,
+) -> Result<TranscriptSlice, AppError> {
+    let transcript = {
+        let sessions = state.sessions();
+        let session = sessions
+            .get(&session_id)
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+        Arc::clone(&session.transcript)
+    };
+
+    let transcript = transcript
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock transcript: {}", e)))?;
+
+    // Redacted as a whole, so a slice boundary cannot cut a secret in half
+    let redacted = redact::redact(&transcript.text);
+    let max_chars = max_chars.unwrap_or(DEFAULT_TRANSCRIPT_CHARS);
+    let total_chars = redacted.chars().count();
+    let text = slice_chars(&redacted, max_chars, from_end.unwrap_or(true));
+
+    Ok(TranscriptSlice {
+        session_id,
+        truncated: max_chars < total_chars,
+        total_chars,
+        text: text.to_string(),
+    })
+}
+
+/// Search a session's retained output, for a find bar that covers more
+/// history than xterm.js keeps.
+///
+/// Searches the ANSI-stripped transcript (the last 256 KiB of plain text)
+/// line by line; matches never span lines.
+///
+/// # Arguments
+/// * `query` - Text to find, or a pattern (Rust `regex` syntax) with `regex`
+/// * `regex` - Treat `query` as a regular expression (default: false)
+/// * `case_sensitive` - Match case exactly (default: false)
+///
+/// Returns at most 1000 matches.
+#[tauri::command]
+pub fn search_terminal(
+    state: State<'_, PtyState>,
+    session_id: String,
+    query: String,
+    regex: Option<bool>,
+    case_sensitive: Option<bool>,
+) -> Result<TerminalSearchResult, AppError> {
+    if query.is_empty() {
+        return Err(AppError::Validation("Search query is empty".into()));
+    }
+    let pattern = if regex.unwrap_or(false) {
+        query.clone()
+    } else {
+        regex::escape(&query)
+    };
+    let re = regex::RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive.unwrap_or(false))
+        .size_limit(SEARCH_REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| AppError::Validation(format!("Invalid pattern: {}", e)))?;
+
+    let transcript = {
+        let sessions = state.sessions();
+        let session = sessions
+            .get(&session_id)
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+        Arc::clone(&session.transcript)
+    };
+    let transcript = transcript
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock transcript: {}", e)))?;
+
+    let (matches, total_lines, truncated) = search_lines(&transcript.text, &re);
+    log::debug!(
+        "Search for {:?} in session {} found {} match(es)",
+        query,
+        session_id,
+        matches.len()
+    );
+    Ok(TerminalSearchResult {
+        session_id,
+        matches,
+        total_lines,
+        truncated,
+    })
+}
+
+/// Start receiving `pty-output-{session_id}` and `terminal-output-captured`
+/// events for a session.
+///
+/// Output of sessions nobody views is only kept in scrollback (the last
+/// 1 MiB), so hidden sessions do not flood the event bus. A new viewer
+/// catches up with `replay_output` from its last seen chunk to `last_seq`.
+/// Each call must be paired with `unsubscribe_terminal`.
+#[tauri::command]
+pub fn subscribe_terminal(
+    state: State<'_, PtyState>,
+    session_id: String,
+) -> Result<TerminalSubscription, AppError> {
+    update_viewers(&state, session_id, true)
+}
+
+/// Release a `subscribe_terminal` subscription. Output events stop once
+/// the last viewer is gone.
+#[tauri::command]
+pub fn unsubscribe_terminal(
+    state: State<'_, PtyState>,
+    session_id: String,
+) -> Result<TerminalSubscription, AppError> {
+    update_viewers(&state, session_id, false)
+}
+
+fn update_viewers(
+    state: &PtyState,
+    session_id: String,
+    subscribe: bool,
+) -> Result<TerminalSubscription, AppError> {
+    let (viewers, scrollback) = {
+        let sessions = state.sessions();
+        let session = sessions
+            .get(&session_id)
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+        let viewers = if subscribe {
+            session.viewers.fetch_add(1, Ordering::Relaxed) + 1
+        } else {
+            // Unbalanced unsubscribes stop at zero
+            let previous = session
+                .viewers
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| v.checked_sub(1))
+                .unwrap_or(0);
+            previous.saturating_sub(1)
+        };
+        (viewers, Arc::clone(&session.scrollback))
+    };
+    // Read after the count changed, so no chunk falls between replay and live events
+    let last_seq = scrollback
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock scrollback: {}", e)))?
+        .seq;
+
+    log::debug!("Session {} has {} viewer(s)", session_id, viewers);
+    Ok(TerminalSubscription {
+        session_id,
+        viewers,
+        last_seq,
+    })
+}
+
+/// Re-fetch raw output chunks starting at `from_seq`, for a frontend that
+/// noticed a gap in `pty-output-{session_id}` sequence numbers.
+///
+/// Sessions retain the last 1 MiB of raw output.
+#[tauri::command]
+pub fn replay_output(
+    state: State<'_, PtyState>,
+    session_id: String,
+    from_seq: u64,
+) -> Result<OutputReplay, AppError> {
+    let scrollback = {
+        let sessions = state.sessions();
+        let session = sessions
+            .get(&session_id)
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+        Arc::clone(&session.scrollback)
+    };
+
+    let scrollback = scrollback
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock scrollback: {}", e)))?;
+    let (chunks, complete) = scrollback.since(from_seq);
+
+    log::debug!(
+        "Replaying {} chunk(s) from seq {} for session {} (complete: {})",
+        chunks.len(),
+        from_seq,
+        session_id,
+        complete
+    );
+    Ok(OutputReplay { chunks, complete })
+}
+
+/// Recorder slot and current size (cols, rows) of a session, for
+/// `start_session_recording`.
+pub(crate) fn session_recorder(
+    state: &PtyState,
+    session_id: &str,
+) -> Result<(RecorderSlot, (u16, u16)), AppError> {
+    let sessions = state.sessions();
+    let session = sessions
+        .get(session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+    if session.exit.is_some() {
+        return Err(AppError::Validation(format!("Session {} has exited", session_id)));
+    }
+    let size = session
+        .master
+        .get_size()
+        .map_err(|e| AppError::Io(format!("Failed to get PTY size: {}", e)))?;
+    Ok((Arc::clone(&session.recorder), (size.cols, size.rows)))
+}
+
+/// The emulated screen of a session, for `get_visible_screen`.
+pub(crate) fn session_screen(
+    state: &PtyState,
+    session_id: &str,
+) -> Result<Arc<ScreenModel>, AppError> {
+    let sessions = state.sessions();
+    let session = sessions
+        .get(session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+    Ok(Arc::clone(&session.screen))
+}
+
+/// Raw output a session still retains (its last 1 MiB), oldest first.
+pub(crate) fn retained_output(state: &PtyState, session_id: &str) -> Result<String, AppError> {
+    let scrollback = {
+        let sessions = state.sessions();
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+        Arc::clone(&session.scrollback)
+    };
+
+    let scrollback = scrollback
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock scrollback: {}", e)))?;
+    Ok(scrollback.chunks.iter().map(|c| c.data.as_str()).collect())
+}
+
+// =============================================================================
+// Output Matching Helpers
+// =============================================================================
+
+/// Matches of `re` in each line of `text` (up to `MAX_SEARCH_MATCHES`), the
+/// number of lines, and whether the cap was hit.
+fn search_lines(text: &str, re: &regex::Regex) -> (Vec<TerminalSearchMatch>, usize, bool) {
+    let mut matches = Vec::new();
+    let mut total_lines = 0;
+    for (line_no, line) in text.split('\n').enumerate() {
+        total_lines = line_no + 1;
+        for m in re.find_iter(line).filter(|m| !m.is_empty()) {
+            if matches.len() == MAX_SEARCH_MATCHES {
+                return (matches, text.split('\n').count(), true);
+            }
+            matches.push(TerminalSearchMatch {
+                line: line_no,
+                column: line[..m.start()].chars().count(),
+                length: m.as_str().chars().count(),
+                text: line.to_string(),
+            });
+        }
+    }
+    (matches, total_lines, false)
+}
+
+/// Up to `max_chars` characters from the start or end of `text`.
+fn slice_chars(text: &str, max_chars: usize, from_end: bool) -> &str {
+    if max_chars == 0 {
+        return "";
+    }
+    if from_end {
+        let start = text
+            .char_indices()
+            .rev()
+            .nth(max_chars - 1)
+            .map_or(0, |(i, _)| i);
+        &text[start..]
+    } else {
+        let end = text
+            .char_indices()
+            .nth(max_chars)
+            .map_or(text.len(), |(i, _)| i);
+        &text[..end]
+    }
+}
+
+/// Text from up to `MATCH_CONTEXT_CHARS` characters before the match to the
+/// same number after it.
+fn match_context(text: &str, start: usize, end: usize) -> String {
+    let before = text[..start]
+        .char_indices()
+        .rev()
+        .nth(MATCH_CONTEXT_CHARS - 1)
+        .map_or(0, |(i, _)| i);
+    let after = text[end..]
+        .char_indices()
+        .nth(MATCH_CONTEXT_CHARS)
+        .map_or(text.len(), |(i, _)| end + i);
+    text[before..after].to_string()
+}
+
+/// Drop text from the front so at most `max_bytes` remain (on a char boundary).
+fn trim_front(text: &mut String, max_bytes: usize) {
+    if text.len() <= max_bytes {
+        return;
+    }
+    let mut cut = text.len() - max_bytes;
+    while !text.is_char_boundary(cut) {
+        cut += 1;
+    }
+    text.drain(..cut);
+}
+
+// =============================================================================
+// Input Path
+// =============================================================================
+
+/// Queue bytes for a session's writer thread.
+///
+/// The sessions lock is held only long enough to enqueue; the actual PTY
+/// write happens on the writer thread.
+fn send_input(
+    state: &PtyState,
+    session_id: &str,
+    data: Vec<u8>,
+    owner: Option<&str>,
+) -> Result<(), AppError> {
+    queue_input(state, session_id, data, owner, false)
+}
+
+/// `send_input`, with `secret` input (and input sent while the session is
+/// reading a password) kept out of the session's recording.
+fn queue_input(
+    state: &PtyState,
+    session_id: &str,
+    data: Vec<u8>,
+    owner: Option<&str>,
+    secret: bool,
+) -> Result<(), AppError> {
+    let sessions = state.sessions();
+
+    let session = sessions
+        .get(session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+
+    check_lock(session.lock.as_ref(), owner)?;
+
+    if let Some(rec) = session.recorder.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        let secure = secret
+            || session.secure_prompt.load(Ordering::Relaxed)
+            || password_mode(session.master.as_ref());
+        rec.input(&data, secure);
+    }
+
+    // Queued in chunks so the writer never blocks on one huge write
+    for chunk in data.chunks(WRITE_CHUNK_BYTES) {
+        session
+            .input_tx
+            .send(chunk.to_vec())
+            .map_err(|_| AppError::Io(format!("Input closed for session: {}", session_id)))?;
+    }
+    Ok(())
+}
+
+/// Reject writes larger than `max_bytes`.
+fn check_write_size(len: usize, max_bytes: usize) -> Result<(), AppError> {
+    if len > max_bytes {
+        return Err(AppError::Validation(format!(
+            "Write of {} bytes exceeds terminal.max_write_bytes ({})",
+            len, max_bytes
+        )));
+    }
+    Ok(())
+}
+
+/// Reject input containing control characters not allowed by `policy`.
+fn check_control_chars(data: &str, policy: ControlCharPolicy) -> Result<(), AppError> {
+    let rejected = match policy {
+        ControlCharPolicy::Allow => None,
+        ControlCharPolicy::Standard => {
+            let mut prev_esc = false;
+            data.chars().find(|&c| {
+                // ESC followed by ] P _ ^ X opens a string sequence
+                let string_seq = prev_esc && matches!(c, ']' | 'P' | '_' | '^' | 'X');
+                prev_esc = c == '\x1b';
+                string_seq || ('\u{80}'..='\u{9f}').contains(&c)
+            })
+        }
+        ControlCharPolicy::Printable => data
+            .chars()
+            .find(|&c| c.is_control() && !matches!(c, '\t' | '\r' | '\n' | '\x08' | '\x7f')),
+    };
+
+    match rejected {
+        Some(c) => Err(AppError::Validation(format!(
+            "Input contains a control sequence not allowed by terminal.control_chars ({:?})",
+            c
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Reject secrets that are empty or contain control characters, which
+/// could submit early or inject further input after the password.
+fn check_secret(secret: &str) -> Result<(), AppError> {
+    if secret.is_empty() {
+        return Err(AppError::Validation("Secret must not be empty".into()));
+    }
+    if secret.chars().any(char::is_control) {
+        return Err(AppError::Validation(
+            "Secret must not contain control characters".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Writer thread body: drain queued input into the PTY until the channel closes.
+///
+/// Chunks that queued up while a write was in flight are coalesced into a
+/// single write + flush to keep syscalls down during fast typing or pastes.
+fn run_writer(
+    mut writer: Box<dyn Write + Send>,
+    rx: mpsc::Receiver<Vec<u8>>,
+    session_id: String,
+    io: Arc<SessionIo>,
+) {
+    while let Ok(mut buf) = rx.recv() {
+        // Coalesce queued keystrokes, but keep each write around one chunk
+        while buf.len() < WRITE_CHUNK_BYTES {
+            match rx.try_recv() {
+                Ok(more) => buf.extend_from_slice(&more),
+                Err(_) => break,
+            }
+        }
+
+        if let Err(e) = writer.write_all(&buf).and_then(|_| writer.flush()) {
+            log::error!("PTY write error for session {}: {}", session_id, e);
+            break;
+        }
+        io.record_write(buf.len(), Instant::now());
+    }
+    log::debug!("PTY writer stopped for session: {}", session_id);
+}
+
+/// Inject multiple commands sequentially into a terminal session.
+///
+/// Each command is sent with a newline after an optional delay (100 ms
+/// between commands by default). Plain commands are sent without waiting
+/// for them to finish; commands with `stop_on_failure`, `expected_exit_code`
+/// or `timeout_ms` are waited for through OSC 133 shell integration, and a
+/// failed or timed-out `stop_on_failure` command skips the rest. This gives
+/// "run migrate, then seed only if it succeeded" chains.
+///
+/// Returns one result per command, in order. If a command cannot be sent
+/// after earlier ones were (e.g. the session ended or was locked), it is
+/// reported as failed with its `error` and the rest as skipped; an error is
+/// only returned when nothing was sent.
+///
+/// # Security Note
+/// This command is intended for AI agent integration. Each injected command
+/// runs with user privileges. All injections are logged for auditability.
+/// The whole batch is charged against `terminal.rate_limit` up front, one
+/// token per command, so a refused batch injects nothing.
+#[tauri::command]
+pub async fn inject_commands(
+    state: State<'_, PtyState>,
+    settings: State<'_, SettingsState>,
+    session_id: String,
+    commands: Vec<BatchCommand>,
+    owner: Option<String>,
+) -> Result<Vec<BatchCommandResult>, AppError> {
+    let commands: Vec<BatchCommandOptions> =
+        commands.into_iter().map(BatchCommand::options).collect();
+    check_batch(&commands)?;
+    state.rate_limiter.check(
+        &settings.get().terminal.rate_limit,
+        RateLimitKind::Inject,
+        owner.as_deref(),
+        &session_id,
+        commands.len() as u32,
+    )?;
+    let total = commands.len();
+    log::info!("Injecting {} commands into session {}", total, session_id);
+
+    let mut results = Vec::with_capacity(commands.len());
+    let mut stopped = false;
+    for (i, options) in commands.into_iter().enumerate() {
+        if stopped {
+            results.push(BatchCommandResult {
+                command: options.command,
+                status: BatchCommandStatus::Skipped,
+                exit_code: None,
+                duration_ms: None,
+                error: None,
+            });
+            continue;
+        }
+
+        let default_delay = if i == 0 { 0 } else { DEFAULT_BATCH_DELAY_MS };
+        let delay = options.delay_ms.unwrap_or(default_delay);
+        if delay > 0 {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+        }
+
+        log::debug!("Injecting command {}/{}: {}", i + 1, total, options.command);
+        let (status, finished) =
+            match run_batch_command(&state, &session_id, &options, owner.as_deref()).await {
+                Ok(outcome) => outcome,
+                // Nothing sent yet, so the batch fails as a whole
+                Err(e) if results.is_empty() => return Err(e),
+                // Part of the batch already ran: report it and skip the rest
+                Err(e) => {
+                    log::warn!(
+                        "Batch command {}/{} in session {} could not be sent: {}",
+                        i + 1,
+                        total,
+                        session_id,
+                        e
+                    );
+                    stopped = true;
+                    results.push(BatchCommandResult {
+                        command: options.command,
+                        status: BatchCommandStatus::Failed,
+                        exit_code: None,
+                        duration_ms: None,
+                        error: Some(e.to_string()),
+                    });
+                    continue;
+                }
+            };
+        if options.waits() {
+            log::info!(
+                "Batch command {}/{} in session {}: {:?}",
+                i + 1,
+                total,
+                session_id,
+                status
+            );
+        }
+        stopped = options.stop_on_failure && status != BatchCommandStatus::Succeeded;
+        results.push(BatchCommandResult {
+            command: options.command,
+            status,
+            exit_code: finished.as_ref().and_then(|f| f.exit_code),
+            duration_ms: finished.map(|f| f.duration_ms),
+            error: None,
+        });
+    }
+
+    Ok(results)
+}
+
+/// Send one batch command and, if its options ask for it, wait for the
+/// shell's finished report (None if it timed out).
+async fn run_batch_command(
+    state: &PtyState,
+    session_id: &str,
+    options: &BatchCommandOptions,
+    owner: Option<&str>,
+) -> Result<(BatchCommandStatus, Option<CommandFinished>), AppError> {
+    if !options.waits() {
+        inject_into_session(state, session_id, &options.command, owner)?;
+        return Ok((BatchCommandStatus::Sent, None));
+    }
+
+    // Subscribed before sending so a fast command's report is not missed
+    let mut finished_rx = {
+        let sessions = state.sessions();
+        sessions
+            .get(session_id)
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?
+            .finished_tx
+            .subscribe()
+    };
+    inject_into_session(state, session_id, &options.command, owner)?;
+
+    let timeout_ms = options.timeout_ms.unwrap_or(DEFAULT_BATCH_TIMEOUT_MS);
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout_ms);
+    let finished = loop {
+        match tokio::time::timeout_at(deadline, finished_rx.recv()).await {
+            Ok(Ok(finished)) => break Some(finished),
+            Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+            Ok(Err(broadcast::error::RecvError::Closed)) | Err(_) => break None,
+        }
+    };
+    let expected = options.expected_exit_code.unwrap_or(0);
+    Ok((batch_status(finished.as_ref(), expected), finished))
+}
+
+/// Validate a batch before anything is sent.
+fn check_batch(commands: &[BatchCommandOptions]) -> Result<(), AppError> {
+    if commands.is_empty() || commands.len() > MAX_BATCH_COMMANDS {
+        return Err(AppError::Validation(format!(
+            "Batch must have 1-{} commands, got: {}",
+            MAX_BATCH_COMMANDS,
+            commands.len()
+        )));
+    }
+    for options in commands {
+        if let Some(ms) = options
+            .timeout_ms
+            .filter(|ms| !(1..=MAX_WAIT_TIMEOUT_MS).contains(ms))
+        {
+            return Err(AppError::Validation(format!(
+                "timeout_ms must be 1-{}, got: {}",
+                MAX_WAIT_TIMEOUT_MS, ms
+            )));
+        }
+        if let Some(ms) = options.delay_ms.filter(|&ms| ms > MAX_WAIT_TIMEOUT_MS) {
+            return Err(AppError::Validation(format!(
+                "delay_ms must be at most {}, got: {}",
+                MAX_WAIT_TIMEOUT_MS, ms
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Status of a waited-for batch command from its finished report (None if
+/// it timed out). A missing exit code cannot confirm success.
+fn batch_status(finished: Option<&CommandFinished>, expected: i32) -> BatchCommandStatus {
+    match finished {
+        None => BatchCommandStatus::TimedOut,
+        Some(f) if f.exit_code == Some(expected) => BatchCommandStatus::Succeeded,
+        Some(_) => BatchCommandStatus::Failed,
+    }
+}
+
+/// Reject input when the session is locked by someone other than `owner`.
+fn check_lock(lock: Option<&SessionLock>, owner: Option<&str>) -> Result<(), AppError> {
+    match lock {
+        Some(lock) if owner != Some(lock.owner.as_str()) => {
+            Err(AppError::SessionLocked(lock.owner.clone()))
+        }
+        _ => Ok(()),
+    }
+}
+
+// =============================================================================
+// Foreground Process
+// =============================================================================
+
+/// Poller thread body: track the session's foreground process group and emit
+/// `terminal-foreground-changed` when it changes, and emit
+/// `secure-input-requested` when a program starts reading a password with
+/// echo turned off. Exits with the session.
+fn watch_foreground(app: AppHandle, session_id: String) {
+    let mut system = System::new();
+    let mut last_pid: Option<u32> = None;
+    let mut last_password_mode = false;
+    let mut input_idle = Duration::ZERO;
+
+    loop {
+        // Low-power mode slows the watchers of idle sessions
+        std::thread::sleep(
+            if power::is_low_power() && input_idle >= power::LOW_POWER_IDLE_AFTER {
+                power::LOW_POWER_FOREGROUND_POLL_INTERVAL
+            } else {
+                FOREGROUND_POLL_INTERVAL
+            },
+        );
+
+        let state = app.state::<PtyState>();
+        if let Some(exit) = check_exited(&state, &session_id) {
+            on_session_exited(&app, exit);
+            return;
+        }
+        let (pid, password_mode, secure_prompt, transcript) = {
+            let sessions = state.sessions();
+            match sessions.get(&session_id) {
+                Some(session) => {
+                    input_idle = session.io.input_idle(Instant::now());
+                    (
+                        foreground_pid(session.master.as_ref()),
+                        password_mode(session.master.as_ref()),
+                        Arc::clone(&session.secure_prompt),
+                        Arc::clone(&session.transcript),
+                    )
+                }
+                None => return,
+            }
+        };
+
+        if password_mode != last_password_mode {
+            last_password_mode = password_mode;
+            if !password_mode {
+                secure_prompt.store(false, Ordering::Relaxed);
+            } else if !secure_prompt.swap(true, Ordering::Relaxed) {
+                let prompt = transcript.lock().ok().and_then(|t| last_line(&t.text));
+                log::info!("Session {} turned off echo for a password", session_id);
+                let _ = events::emit(
+                    &app,
+                    "secure-input-requested",
+                    SecureInputRequested {
+                        session_id: session_id.clone(),
+                        prompt,
+                        reason: SecureInputReason::EchoOff,
+                    },
+                );
+            }
+        }
+
+        if pid == last_pid {
+            continue;
+        }
+        last_pid = pid;
+
+        // Resolve the name outside the sessions lock
+        let foreground = pid.and_then(|pid| describe_process(&mut system, pid));
+        {
+            let mut sessions = state.sessions();
+            match sessions.get_mut(&session_id) {
+                Some(session) => session.foreground = foreground.clone(),
+                None => return,
+            }
+        }
+
+        log::debug!(
+            "Foreground of session {} is now {:?}",
+            session_id,
+            foreground.as_ref().map(|f| &f.command)
+        );
+        let _ = events::emit(
+            &app,
+            "terminal-foreground-changed",
+            ForegroundChanged {
+                session_id: session_id.clone(),
+                foreground,
+            },
+        );
+    }
+}
+
+/// Record the exit of a session's shell if it has ended since the last check
+/// (reaping it, so no zombie is left behind). None while it still runs.
+fn check_exited(state: &PtyState, session_id: &str) -> Option<TerminalExit> {
+    let mut sessions = state.sessions();
+    let session = sessions.get_mut(session_id)?;
+    if session.exit.is_some() {
+        return None;
+    }
+    let status = match session.child.try_wait() {
+        Ok(Some(status)) => status,
+        Ok(None) => return None,
+        Err(e) => {
+            log::warn!("Failed to poll shell of session {}: {}", session_id, e);
+            return None;
+        }
+    };
+    let exit = TerminalExit {
+        session_id: session_id.to_string(),
+        exit_code: status.exit_code(),
+        signal: status.signal().map(str::to_string),
+        success: status.success(),
+        exited_at: chrono::Local::now().to_rfc3339(),
+    };
+    session.exit = Some(exit.clone());
+    session.foreground = None;
+    Some(exit)
+}
+
+/// Announce a dead session and, with `terminal.auto_remove_dead`, drop it.
+fn on_session_exited(app: &AppHandle, exit: TerminalExit) {
+    log::info!(
+        "Shell of session {} exited with code {}{}",
+        exit.session_id,
+        exit.exit_code,
+        exit.signal.as_ref().map(|s| format!(" ({})", s)).unwrap_or_default()
+    );
+    history::session_ended(app, &exit.session_id, Some(exit.exit_code));
+    if app.state::<SettingsState>().get().terminal.auto_remove_dead {
+        // The shell is already reaped; dropping the session closes the PTY
+        remove_session(app, &app.state::<PtyState>(), &exit.session_id);
+    }
+    let _ = events::emit(app, "terminal-exited", exit);
+}
+
+/// Foreground process group of the PTY (`tcgetpgrp` on the master).
+#[cfg(unix)]
+fn foreground_pid(master: &(dyn MasterPty + Send)) -> Option<u32> {
+    master.process_group_leader().map(|pid| pid as u32)
+}
+
+/// ConPTY has no notion of a foreground process group.
+#[cfg(not(unix))]
+fn foreground_pid(_master: &(dyn MasterPty + Send)) -> Option<u32> {
+    None
+}
+
+/// Whether the PTY is echoing input, None where termios is unavailable.
+#[cfg(unix)]
+fn echo_enabled(master: &(dyn MasterPty + Send)) -> Option<bool> {
+    use nix::sys::termios::LocalFlags;
+
+    let termios = master.get_termios()?;
+    Some(termios.local_flags.bits() & LocalFlags::ECHO.bits() != 0)
+}
+
+#[cfg(not(unix))]
+fn echo_enabled(_master: &(dyn MasterPty + Send)) -> Option<bool> {
+    None
+}
+
+/// Echo off with line editing still on: how `sudo`, `ssh` and `passwd` read
+/// a password. Full-screen programs turn off both and are not matched.
+#[cfg(unix)]
+fn password_mode(master: &(dyn MasterPty + Send)) -> bool {
+    use nix::sys::termios::LocalFlags;
+
+    master.get_termios().is_some_and(|t| {
+        let flags = t.local_flags.bits();
+        flags & LocalFlags::ECHO.bits() == 0 && flags & LocalFlags::ICANON.bits() != 0
+    })
+}
+
+#[cfg(not(unix))]
+fn password_mode(_master: &(dyn MasterPty + Send)) -> bool {
+    false
+}
+
+/// Last non-blank line of `text`, trimmed.
+fn last_line(text: &str) -> Option<String> {
+    text.lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Look up a process's name and command line.
+fn describe_process(system: &mut System, pid: u32) -> Option<ForegroundProcess> {
+    let sys_pid = sysinfo::Pid::from_u32(pid);
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[sys_pid]),
+        true,
+        ProcessRefreshKind::new().with_cmd(UpdateKind::Always),
+    );
+    let process = system.process(sys_pid)?;
+
+    // Login shells are reported as `-zsh`
+    let name = process
+        .name()
+        .to_string_lossy()
+        .trim_start_matches('-')
+        .to_string();
+    let command = process
+        .cmd()
+        .iter()
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Some(ForegroundProcess {
+        pid,
+        command: if command.is_empty() { name.clone() } else { command },
+        name,
+    })
+}
+
+// =============================================================================
+// Attention Helpers
+// =============================================================================
+
+/// Counts BEL characters in PTY output, ignoring the BEL that terminates
+/// OSC sequences (e.g. `ESC ] 0 ; title BEL`), which shells emit on every
+/// prompt. State carries across chunks since sequences may be split.
+#[derive(Default)]
+struct BellScanner {
+    /// Previous byte was ESC
+    escape: bool,
+    /// Inside an OSC sequence, waiting for BEL or ST
+    in_osc: bool,
+    /// Bells since the session started
+    total: u64,
+}
+
+impl BellScanner {
+    /// Scan a chunk and return the number of audible bells in it.
+    fn feed(&mut self, data: &[u8]) -> u32 {
+        let mut count = 0;
+        for &b in data {
+            let escaped = std::mem::take(&mut self.escape);
+            match b {
+                0x1b => self.escape = true,
+                b']' if escaped => self.in_osc = true,
+                b'\\' if escaped => self.in_osc = false,
+                0x07 if self.in_osc => self.in_osc = false,
+                0x07 => count += 1,
+                _ => {}
+            }
+        }
+        self.total += count as u64;
+        count
+    }
+}
+
+/// Watches the unterminated last line of output for a password prompt such
+/// as `[sudo] password for sam:` or `Enter passphrase for key '...':`.
+/// Works where termios is unavailable (Windows, or a password read by the
+/// remote end of an `ssh` session, whose local terminal is in raw mode).
+#[derive(Default)]
+struct PromptDetector {
+    stripper: AnsiStripper,
+    line: String,
+    /// The current line was already reported
+    reported: bool,
+}
+
+impl PromptDetector {
+    /// Scan a chunk and return the prompt line when one has just appeared.
+    fn feed(&mut self, data: &[u8]) -> Option<String> {
+        for c in self.stripper.feed(data).chars() {
+            match c {
+                '\n' | '\r' => {
+                    self.line.clear();
+                    self.reported = false;
+                }
+                // Only the tail matters; keep memory bounded on long lines
+                _ if self.line.len() < MAX_PROMPT_CHARS * 4 => self.line.push(c),
+                _ => {}
+            }
+        }
+
+        if self.reported || !is_password_prompt(&self.line) {
+            return None;
+        }
+        self.reported = true;
+        Some(self.line.trim().to_string())
+    }
+}
+
+/// Whether a line looks like a password prompt: short, ending in `:`, and
+/// naming a password, passphrase, passcode or PIN.
+fn is_password_prompt(line: &str) -> bool {
+    let line = line.trim();
+    let Some(label) = line.strip_suffix(':') else {
+        return false;
+    };
+    if line.chars().count() > MAX_PROMPT_CHARS {
+        return false;
+    }
+    let label = label.to_lowercase();
+    PROMPT_KEYWORDS.iter().any(|k| label.contains(k))
+        || label == "pin"
+        || label.ends_with(" pin")
+}
+
+/// Groups output into bursts and rate-limits activity events.
+#[derive(Default)]
+struct ActivityTracker {
+    last_output: Option<Instant>,
+    burst_started: Option<Instant>,
+    last_emit: Option<Instant>,
+    /// Bytes since the last emitted event
+    pending_bytes: u64,
+}
+
+impl ActivityTracker {
+    /// Record an output chunk. Returns `(burst_start, bytes, burst_ms)` when
+    /// an activity event should be emitted.
+    fn record(&mut self, bytes: usize, now: Instant) -> Option<(bool, u64, u64)> {
+        let idle = self
+            .last_output
+            .is_none_or(|t| now.duration_since(t) >= ACTIVITY_IDLE_GAP);
+        self.last_output = Some(now);
+        self.pending_bytes += bytes as u64;
+
+        if idle {
+            self.burst_started = Some(now);
+        } else if self
+            .last_emit
+            .is_some_and(|t| now.duration_since(t) < ACTIVITY_EMIT_INTERVAL)
+        {
+            return None;
+        }
+
+        self.last_emit = Some(now);
+        let burst_ms = self
+            .burst_started
+            .map(|t| now.duration_since(t).as_millis() as u64)
+            .unwrap_or(0);
+        Some((idle, std::mem::take(&mut self.pending_bytes), burst_ms))
+    }
+}
+
+/// Byte counters of a session, shared with its reader and writer threads.
+struct SessionIo {
+    started: Instant,
+    started_at: chrono::DateTime<chrono::Local>,
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    /// Milliseconds after `started` of the last input written
+    last_input_ms: AtomicU64,
+    /// Output per second since start, for the last `IO_RATE_WINDOW_SECS`
+    rate: Mutex<OutputRate>,
+}
+
+/// Output bytes per whole second of a session's life.
+#[derive(Default)]
+struct OutputRate {
+    /// `(second, bytes)`, oldest first
+    seconds: VecDeque<(u64, u64)>,
+    /// Largest count of a second that has ended and left `seconds`
+    peak: u64,
+}
+
+impl SessionIo {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            started_at: chrono::Local::now(),
+            bytes_read: AtomicU64::new(0),
+            bytes_written: AtomicU64::new(0),
+            last_input_ms: AtomicU64::new(0),
+            rate: Mutex::new(OutputRate::default()),
+        }
+    }
+
+    fn record_write(&self, bytes: usize, now: Instant) {
+        self.bytes_written.fetch_add(bytes as u64, Ordering::Relaxed);
+        let since_start = now.saturating_duration_since(self.started).as_millis() as u64;
+        self.last_input_ms.store(since_start, Ordering::Relaxed);
+    }
+
+    /// Time since the last input, or since the session started if none.
+    fn input_idle(&self, now: Instant) -> Duration {
+        let last = self.started + Duration::from_millis(self.last_input_ms.load(Ordering::Relaxed));
+        now.saturating_duration_since(last)
+    }
+
+    fn record_read(&self, bytes: usize, now: Instant) {
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+        let second = now.saturating_duration_since(self.started).as_secs();
+        let Ok(mut rate) = self.rate.lock() else {
+            return;
+        };
+        match rate.seconds.back_mut() {
+            Some((s, count)) if *s == second => *count += bytes as u64,
+            _ => rate.seconds.push_back((second, bytes as u64)),
+        }
+        while rate
+            .seconds
+            .front()
+            .is_some_and(|(s, _)| s + IO_RATE_WINDOW_SECS < second)
+        {
+            if let Some((_, count)) = rate.seconds.pop_front() {
+                rate.peak = rate.peak.max(count);
+            }
+        }
+    }
+
+    /// Counters as of `now`. Only whole seconds count towards the rates, so
+    /// the second in progress does not drag the average down.
+    fn stats(&self, now: Instant) -> SessionStats {
+        let uptime = now.saturating_duration_since(self.started);
+        let second = uptime.as_secs();
+        let (recent, peak) = match self.rate.lock() {
+            Ok(rate) => {
+                let ended = rate.seconds.iter().filter(|(s, _)| *s < second);
+                let recent: u64 = ended
+                    .clone()
+                    .filter(|(s, _)| s + IO_RATE_WINDOW_SECS >= second)
+                    .map(|(_, count)| count)
+                    .sum();
+                let peak = ended.map(|(_, count)| *count).fold(rate.peak, u64::max);
+                (recent, peak)
+            }
+            Err(_) => (0, 0),
+        };
+        SessionStats {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            read_bytes_per_sec: recent as f64 / IO_RATE_WINDOW_SECS.min(second.max(1)) as f64,
+            peak_read_bytes_per_sec: peak,
+            started_at: self.started_at.to_rfc3339(),
+            uptime_secs: second,
+        }
+    }
+}
+
+// =============================================================================
+// Shell Integration
+// =============================================================================
+
+/// Follows OSC 133 marks (`A` prompt start, `B` prompt end, `C` command
+/// start, `D;<status>` command end) to time commands. The command text is
+/// taken from a VS Code `633;E` mark when present, else from the output
+/// echoed between `B` and `C`, which is the command line as typed.
+#[derive(Default)]
+struct CommandTracker {
+    /// Previous byte was ESC
+    escape: bool,
+    /// Payload of the OSC sequence being read
+    osc: Option<Vec<u8>>,
+    /// Raw output since the prompt ended, while the command line is edited
+    command_line: Option<Vec<u8>>,
+    command: Option<String>,
+    started: Option<Instant>,
+}
+
+impl CommandTracker {
+    /// Scan a chunk and return `(command, exit_code, duration_ms)` for each
+    /// command that finished in it.
+    fn feed(&mut self, data: &[u8], now: Instant) -> Vec<(Option<String>, Option<i32>, u64)> {
+        let mut finished = Vec::new();
+        for &b in data {
+            let escaped = std::mem::take(&mut self.escape);
+            if let Some(payload) = &mut self.osc {
+                match b {
+                    // Terminated by BEL or ST
+                    0x07 | b'\\' if b == 0x07 || escaped => {
+                        let payload = self.osc.take().unwrap_or_default();
+                        finished.extend(self.mark(&payload, now));
+                    }
+                    // Any other escape aborts the sequence
+                    _ if escaped => self.osc = None,
+                    0x1b => self.escape = true,
+                    _ if payload.len() < SHELL_MARK_MAX_BYTES => payload.push(b),
+                    _ => {}
+                }
+                continue;
+            }
+
+            match b {
+                0x1b => self.escape = true,
+                b']' if escaped => {
+                    self.osc = Some(Vec::new());
+                    continue;
+                }
+                _ => {}
+            }
+            if let Some(line) = &mut self.command_line {
+                if line.len() < SHELL_MARK_MAX_BYTES {
+                    line.push(b);
+                }
+            }
+        }
+        finished
+    }
+
+    /// Apply one OSC payload; returns a finished command for a `D` mark.
+    fn mark(&mut self, payload: &[u8], now: Instant) -> Option<(Option<String>, Option<i32>, u64)> {
+        let payload = String::from_utf8_lossy(payload);
+        let mut fields = payload.split(';');
+        let (Some("133" | "633"), Some(kind)) = (fields.next(), fields.next()) else {
+            return None;
+        };
+        match kind {
+            "A" => {
+                self.command_line = None;
+                self.command = None;
+                self.started = None;
+            }
+            "B" => self.command_line = Some(Vec::new()),
+            "E" => self.command = fields.next().map(unescape_mark_value),
+            "C" => {
+                self.started = Some(now);
+                let echoed = self.command_line.take().map(|line| {
+                    // Drop the ESC of the `ESC ]` that opened this mark
+                    let line = line.strip_suffix(&[0x1b]).unwrap_or(&line);
+                    ansi::strip(&String::from_utf8_lossy(line)).trim().to_string()
+                });
+                if self.command.is_none() {
+                    self.command = echoed.filter(|c| !c.is_empty());
+                }
+            }
+            "D" => {
+                // Shells also send D for an empty command line; nothing ran
+                let started = self.started.take()?;
+                let exit_code = fields.next().and_then(|c| c.trim().parse().ok());
+                let duration_ms = now.duration_since(started).as_millis() as u64;
+                return Some((self.command.take(), exit_code, duration_ms));
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Decode a `633` mark value, in which `\\` and `\xHH` escapes stand for
+/// backslashes, semicolons and control characters.
+fn unescape_mark_value(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        if b != b'\\' {
+            bytes.push(b);
+        } else if let Some(tail) = rest.strip_prefix(b"\\") {
+            bytes.push(b'\\');
+            rest = tail;
+        } else if let Some(code) = rest
+            .strip_prefix(b"x")
+            .and_then(|hex| hex.get(..2))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok())
+        {
+            bytes.push(code);
+            rest = &rest[3..];
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+// =============================================================================
+// Paste Helpers
+// =============================================================================
+
+/// Find the last bracketed-paste mode toggle in a chunk of PTY output.
+///
+/// Returns `Some(true)` if the app enabled it, `Some(false)` if it disabled
+/// it, or `None` if the chunk contains neither sequence.
+fn scan_bracketed_paste_mode(data: &str) -> Option<bool> {
+    let enabled_at = data.rfind(BRACKETED_PASTE_ENABLE);
+    let disabled_at = data.rfind(BRACKETED_PASTE_DISABLE);
+    match (enabled_at, disabled_at) {
+        (Some(e), Some(d)) => Some(e > d),
+        (Some(_), None) => Some(true),
+        (None, Some(_)) => Some(false),
+        (None, None) => None,
+    }
+}
+
+/// Tracks bracketed-paste toggles across chunks of PTY output, keeping the
+/// end of the previous chunk so a sequence split between reads is found.
+#[derive(Default)]
+struct PasteModeScanner {
+    /// Up to one byte less than a toggle sequence, too short to hold one
+    tail: String,
+}
+
+impl PasteModeScanner {
+    /// Scan a chunk; see `scan_bracketed_paste_mode`.
+    fn feed(&mut self, data: &str) -> Option<bool> {
+        let joined = std::mem::take(&mut self.tail) + data;
+        let found = scan_bracketed_paste_mode(&joined);
+        let mut start = joined.len().saturating_sub(BRACKETED_PASTE_ENABLE.len() - 1);
+        while !joined.is_char_boundary(start) {
+            start += 1;
+        }
+        self.tail = joined[start..].to_string();
+        found
+    }
+}
+
+/// Sanitize pasted text and optionally wrap it in bracketed paste markers.
+///
+/// Keeps tabs and line breaks (normalized to `\r`), drops every other
+/// control character so the paste cannot inject escape sequences such as a
+/// premature paste-end marker.
+fn prepare_paste(text: &str, bracketed: bool) -> String {
+    let normalized = text.replace("\r\n", "\r").replace('\n', "\r");
+    let cleaned: String = normalized
+        .chars()
+        .filter(|c| !c.is_control() || *c == '\r' || *c == '\t')
+        .collect();
+
+    if bracketed {
+        format!("{}{}{}", PASTE_START, cleaned, PASTE_END)
+    } else {
+        cleaned
+    }
+}
+
+// =============================================================================
+// Path Quoting
+// =============================================================================
+
+/// Quoting rules of a session's shell, for `inject_paths`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum QuoteStyle {
+    /// sh, bash, zsh, fish: single quotes, `'` written as `'\''`
+    Posix,
+    /// PowerShell: single quotes, quote characters doubled
+    PowerShell,
+    /// cmd: double quotes (which Windows file names cannot contain)
+    Cmd,
+    /// A WSL distribution: Windows paths translated, then POSIX quoting
+    Wsl,
+}
+
+impl QuoteStyle {
+    fn for_shell(shell: &shells::ShellInfo) -> Self {
+        if shell.id.starts_with("wsl:") {
+            return Self::Wsl;
+        }
+        // Split by hand: `Path` only knows the host's separators
+        let file = shell.program.rsplit(['/', '\\']).next().unwrap_or_default();
+        let file = file.to_lowercase();
+        match file.strip_suffix(".exe").unwrap_or(&file) {
+            "pwsh" | "powershell" => Self::PowerShell,
+            "cmd" => Self::Cmd,
+            _ => Self::Posix,
+        }
+    }
+
+    /// Quote `path` if it contains anything but plainly safe characters.
+    fn quote_path(self, path: &str) -> String {
+        match self {
+            Self::Posix => quote_posix(path),
+            Self::Wsl => quote_posix(&wsl_path(path)),
+            Self::PowerShell => {
+                if is_plain_path(path) {
+                    return path.to_string();
+                }
+                // PowerShell also treats typographic single quotes as quotes
+                let mut quoted = String::from("'");
+                for c in path.chars() {
+                    if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+                        quoted.push(c);
+                    }
+                    quoted.push(c);
+                }
+                quoted.push('\'');
+                quoted
+            }
+            Self::Cmd if is_plain_path(path) => path.to_string(),
+            Self::Cmd => format!("\"{}\"", path),
+        }
+    }
+}
+
+/// Letters, digits and path punctuation that no supported shell treats
+/// specially.
+fn is_plain_path(path: &str) -> bool {
+    path.chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '\\' | '.' | '_' | '-' | ':'))
+}
+
+fn quote_posix(path: &str) -> String {
+    let plain = path.chars().all(|c| {
+        c.is_ascii_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | '+' | ',' | '=' | '@')
+    });
+    if plain {
+        path.to_string()
+    } else {
+        format!("'{}'", path.replace('\'', "'\\''"))
+    }
+}
+
+/// Translate a Windows path for use inside WSL: `C:\x` becomes `/mnt/c/x`
+/// and `\\wsl$\<distro>\x` becomes `/x`. Other paths are returned as is.
+fn wsl_path(path: &str) -> String {
+    let unc = ["\\\\wsl$\\", "\\\\wsl.localhost\\"]
+        .iter()
+        .find_map(|prefix| path.strip_prefix(prefix));
+    if let Some(rest) = unc {
+        let inner = rest.split_once('\\').map(|(_, p)| p).unwrap_or("");
+        return format!("/{}", inner.replace('\\', "/"));
+    }
+
+    let mut chars = path.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(drive), Some(':'), None | Some('\\' | '/')) if drive.is_ascii_alphabetic() => {
+            let rest = path[2..].replace('\\', "/");
+            let rest = if rest.is_empty() { "/" } else { &rest };
+            format!("/mnt/{}{}", drive.to_ascii_lowercase(), rest)
+        }
+        _ => path.to_string(),
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_lock() {
+        let lock = SessionLock {
+            owner: "agent-1".into(),
+            acquired_at: String::new(),
+        };
+        assert!(check_lock(None, None).is_ok());
+        assert!(check_lock(None, Some("agent-2")).is_ok());
+        assert!(check_lock(Some(&lock), Some("agent-1")).is_ok());
+
+        let err = check_lock(Some(&lock), None).unwrap_err();
+        assert_eq!(err.to_string(), "Session is locked by agent-1");
+        assert!(check_lock(Some(&lock), Some("agent-2")).is_err());
+    }
+
+    #[test]
+    fn test_describe_process_current() {
+        let mut system = System::new();
+        let fg = describe_process(&mut system, std::process::id()).unwrap();
+        assert_eq!(fg.pid, std::process::id());
+        assert!(!fg.name.is_empty());
+        assert!(!fg.command.is_empty());
+    }
+
+    #[test]
+    fn test_describe_process_missing() {
+        let mut system = System::new();
+        assert!(describe_process(&mut system, u32::MAX - 1).is_none());
+    }
+
+    #[test]
+    fn test_bell_scanner_ignores_osc_terminator() {
+        let mut scanner = BellScanner::default();
+        assert_eq!(scanner.feed(b"\x1b]0;title\x07$ "), 0);
+        assert_eq!(scanner.feed(b"done\x07\x07"), 2);
+        // OSC split across chunks, terminated by ST
+        assert_eq!(scanner.feed(b"\x1b]2;ti"), 0);
+        assert_eq!(scanner.feed(b"tle\x1b\\\x07"), 1);
+        assert_eq!(scanner.total, 3);
+    }
+
+    #[test]
+    fn test_check_initial_commands() {
+        let ok = vec!["cd api".to_string(), "npm run dev\t".to_string()];
+        assert!(check_initial_commands(&ok).is_ok());
+        assert!(check_initial_commands(&[]).is_ok());
+        assert!(check_initial_commands(&["  ".to_string()]).is_err());
+        assert!(check_initial_commands(&["ls\nrm -rf x".to_string()]).is_err());
+        assert!(check_initial_commands(&vec!["ls".to_string(); MAX_INITIAL_COMMANDS + 1]).is_err());
+        assert!(is_prompt_end("\x1b]133;A\x07~/api $ \x1b]133;B\x07"));
+        assert!(!is_prompt_end("~/api $ "));
+    }
+
+    #[test]
+    fn test_search_lines() {
+        let text = "$ cargo test\nerror[E0308]: mismatched types\n  Error: café ERROR\n$ ";
+        let re = regex::RegexBuilder::new("error")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        let (matches, total_lines, truncated) = search_lines(text, &re);
+        assert_eq!(total_lines, 4);
+        assert!(!truncated);
+        let found: Vec<(usize, usize)> = matches.iter().map(|m| (m.line, m.column)).collect();
+        assert_eq!(found, vec![(1, 0), (2, 2), (2, 14)]);
+        assert_eq!(matches[2].length, 5);
+        assert_eq!(matches[1].text, "  Error: café ERROR");
+
+        let many = "x\n".repeat(MAX_SEARCH_MATCHES + 10);
+        let (matches, total_lines, truncated) =
+            search_lines(&many, &regex::Regex::new("x").unwrap());
+        assert_eq!(matches.len(), MAX_SEARCH_MATCHES);
+        assert_eq!(total_lines, MAX_SEARCH_MATCHES + 11);
+        assert!(truncated);
+    }
+
+    #[test]
+    fn test_command_tracker() {
+        let mut tracker = CommandTracker::default();
+        let t0 = Instant::now();
+        // Prompt, typed command (with a correction), then output and status
+        assert!(tracker
+            .feed(b"\x1b]133;A\x07$ \x1b]133;B\x07npm tesy\x08 \x08t", t0)
+            .is_empty());
+        assert!(tracker.feed(b"\r\n\x1b]133;C\x07FAIL src/app.test.ts\r\n", t0).is_empty());
+        let t1 = t0 + Duration::from_millis(1500);
+        let finished = tracker.feed(b"\x1b]133;D;1\x1b\\\x1b]133;A\x07$ ", t1);
+        assert_eq!(finished, vec![(Some("npm test".to_string()), Some(1), 1500)]);
+
+        // Enter on an empty prompt reports D without C
+        assert!(tracker.feed(b"\x1b]133;B\x07\r\n\x1b]133;D\x07", t0).is_empty());
+
+        // VS Code marks carry the command line explicitly, split across chunks
+        tracker.feed(b"\x1b]633;B\x07ls\x1b]633;E;echo a\\x3bb;nonce\x07\x1b]6", t0);
+        tracker.feed(b"33;C\x07a\r\nb\r\n", t0);
+        let finished = tracker.feed(b"\x1b]633;D;0\x07", t0);
+        assert_eq!(finished, vec![(Some("echo a;b".to_string()), Some(0), 0)]);
+    }
+
+    #[test]
+    fn test_unescape_mark_value() {
+        assert_eq!(unescape_mark_value(r"cd C:\\x\x3b ls"), r"cd C:\x; ls");
+        assert_eq!(unescape_mark_value(r"trailing\x4"), r"trailing\x4");
+    }
+
+    #[test]
+    fn test_activity_tracker_bursts() {
+        let mut tracker = ActivityTracker::default();
+        let t0 = Instant::now();
+        assert_eq!(tracker.record(10, t0), Some((true, 10, 0)));
+        // Rate-limited within the burst; bytes accumulate
+        assert_eq!(tracker.record(5, t0 + Duration::from_millis(300)), None);
+        assert_eq!(
+            tracker.record(5, t0 + Duration::from_millis(1100)),
+            Some((false, 10, 1100))
+        );
+        // Output after a quiet period starts a new burst
+        assert_eq!(tracker.record(1, t0 + Duration::from_secs(5)), Some((true, 1, 0)));
+    }
+
+    #[test]
+    fn test_session_io_rates() {
+        let io = SessionIo::new();
+        let t0 = io.started;
+        io.record_read(1000, t0);
+        io.record_read(500, t0 + Duration::from_millis(900));
+        io.record_read(4000, t0 + Duration::from_millis(1500));
+        io.bytes_written.fetch_add(12, Ordering::Relaxed);
+
+        // During the first second nothing has ended yet
+        let stats = io.stats(t0 + Duration::from_millis(950));
+        assert_eq!((stats.bytes_read, stats.bytes_written), (5500, 12));
+        assert_eq!((stats.read_bytes_per_sec, stats.peak_read_bytes_per_sec), (0.0, 0));
+
+        let stats = io.stats(t0 + Duration::from_secs(2));
+        assert_eq!(stats.read_bytes_per_sec, 2750.0);
+        assert_eq!(stats.peak_read_bytes_per_sec, 4000);
+
+        // Old seconds leave the average but keep the peak
+        io.record_read(10, t0 + Duration::from_secs(20));
+        let stats = io.stats(t0 + Duration::from_secs(21));
+        assert_eq!(stats.read_bytes_per_sec, 2.0);
+        assert_eq!((stats.peak_read_bytes_per_sec, stats.uptime_secs), (4000, 21));
+    }
+
+    #[test]
+    fn test_scan_bracketed_paste_mode() {
+        assert_eq!(scan_bracketed_paste_mode("plain output"), None);
+        assert_eq!(scan_bracketed_paste_mode("\x1b[?2004hprompt$ "), Some(true));
+        assert_eq!(scan_bracketed_paste_mode("\x1b[?2004l\r\n"), Some(false));
+        // Last toggle wins
+        assert_eq!(
+            scan_bracketed_paste_mode("\x1b[?2004l...\x1b[?2004h"),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_paste_mode_scanner_split_sequence() {
+        let mut scanner = PasteModeScanner::default();
+        assert_eq!(scanner.feed("prompt\x1b[?20"), None);
+        assert_eq!(scanner.feed("04h$ "), Some(true));
+        // Split right after ESC, with multi-byte text before it
+        assert_eq!(scanner.feed("héllo\x1b"), None);
+        assert_eq!(scanner.feed("[?2004l\r\n"), Some(false));
+        // A toggle already reported is not reported again
+        assert_eq!(scanner.feed("more output"), None);
+    }
+
+    #[test]
+    fn test_prepare_paste_plain() {
+        assert_eq!(prepare_paste("echo a\necho b", false), "echo a\recho b");
+        assert_eq!(prepare_paste("a\r\nb", false), "a\rb");
+    }
+
+    #[test]
+    fn test_prepare_paste_bracketed() {
+        assert_eq!(
+            prepare_paste("line1\nline2", true),
+            "\x1b[200~line1\rline2\x1b[201~"
+        );
+    }
+
+    #[test]
+    fn test_prepare_paste_strips_control_characters() {
+        // Embedded paste-end marker must not survive (ESC is stripped)
+        assert_eq!(prepare_paste("a\x1b[201~b\x07\tc", true), "\x1b[200~a[201~b\tc\x1b[201~");
+    }
+
+    /// Writer that appends into a shared buffer so the test can inspect it.
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_idle_action() {
+        let hour = Duration::from_secs(3600);
+        let min = Duration::from_secs(60);
+        let policy = IdlePolicy {
+            limit: 8 * hour,
+            warning: 15 * min,
+        };
+        assert_eq!(idle_action(true, 7 * hour, None, &policy), IdleAction::Reset);
+        assert_eq!(idle_action(true, 8 * hour - 10 * min, None, &policy), IdleAction::Warn);
+        assert_eq!(idle_action(true, 8 * hour - 5 * min, Some(5 * min), &policy), IdleAction::Wait);
+        assert_eq!(idle_action(true, 8 * hour, Some(15 * min), &policy), IdleAction::Kill);
+        // Exempt, busy or typed-into sessions drop the warning
+        assert_eq!(idle_action(false, 9 * hour, Some(30 * min), &policy), IdleAction::Reset);
+        // Idle long before the policy was enabled: full notice first
+        assert_eq!(idle_action(true, 20 * hour, None, &policy), IdleAction::Warn);
+        assert_eq!(idle_action(true, 20 * hour, Some(min), &policy), IdleAction::Wait);
+    }
+
+    #[test]
+    fn test_session_io_input_idle() {
+        let io = SessionIo::new();
+        let t0 = io.started;
+        assert_eq!(io.input_idle(t0 + Duration::from_secs(30)), Duration::from_secs(30));
+        io.record_write(3, t0 + Duration::from_secs(10));
+        assert_eq!(io.input_idle(t0 + Duration::from_secs(30)), Duration::from_secs(20));
+        assert_eq!(io.bytes_written.load(Ordering::Relaxed), 3);
+    }
+
+    fn profile(name: &str) -> EnvProfile {
+        EnvProfile {
+            name: name.into(),
+            env: [("VIRTUAL_ENV".to_string(), "/opt/venv".to_string())].into(),
+            unset: vec!["AWS_SECRET_ACCESS_KEY".into()],
+            path_prepend: vec!["/opt/venv/bin".into()],
+            init_commands: vec!["source /opt/venv/bin/activate".into()],
+        }
+    }
+
+    #[test]
+    fn test_check_env_profiles() {
+        assert!(check_env_profiles(&[profile("python-venv"), profile("node18")]).is_ok());
+        assert!(check_env_profiles(&[profile("a"), profile("a")]).is_err());
+        assert!(check_env_profiles(&[profile(" ")]).is_err());
+
+        let mut bad = profile("bad");
+        bad.unset.push("FOO=bar".into());
+        assert!(check_env_profiles(&[bad]).is_err());
+        let mut bad = profile("bad");
+        bad.init_commands.push("ls\nrm -rf /".into());
+        assert!(check_env_profiles(&[bad]).is_err());
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let mut cmd = CommandBuilder::new("sh");
+        cmd.env("PATH", "/usr/bin");
+        cmd.env("AWS_SECRET_ACCESS_KEY", "secret");
+        apply_profile(&mut cmd, &profile("python-venv")).unwrap();
+
+        assert_eq!(cmd.get_env("AWS_SECRET_ACCESS_KEY"), None);
+        assert_eq!(cmd.get_env("VIRTUAL_ENV").unwrap(), "/opt/venv");
+        let expected = std::env::join_paths(["/opt/venv/bin", "/usr/bin"]).unwrap();
+        assert_eq!(cmd.get_env("PATH").unwrap(), expected.as_os_str());
+    }
+
+    #[test]
+    fn test_sessions_lock_recovers_from_poison() {
+        let state = Arc::new(PtyState::default());
+        let poisoner = Arc::clone(&state);
+        let _ = std::thread::spawn(move || {
+            let _sessions = poisoner.sessions();
+            panic!("panic while holding the sessions lock");
+        })
+        .join();
+        assert!(state.sessions.is_poisoned());
+
+        assert!(state.sessions().is_empty());
+        assert!(!state.sessions.is_poisoned());
+        // Callers that used to fail on a poisoned lock now just read the map
+        assert!(terminal_infos(&state).is_empty());
+        assert!(session_processes(&state).is_empty());
+    }
+
+    #[test]
+    fn test_run_writer_preserves_order_and_stops_on_close() {
+        let out = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = mpsc::channel();
+        tx.send(b"ls".to_vec()).unwrap();
+        tx.send(b" -la\n".to_vec()).unwrap();
+        drop(tx);
+
+        // Returns once the channel is closed and drained
+        let io = Arc::new(SessionIo::new());
+        run_writer(Box::new(SharedBuf(Arc::clone(&out))), rx, "test".into(), Arc::clone(&io));
+        assert_eq!(out.lock().unwrap().as_slice(), b"ls -la\n");
+        assert_eq!(io.bytes_written.load(Ordering::Relaxed), 7);
+    }
+
+    #[test]
+    fn test_check_control_chars() {
+        let keys = "ls -la\r\x1b[A\x03\x1bOP\x1bb";
+        assert!(check_control_chars(keys, ControlCharPolicy::Standard).is_ok());
+        assert!(check_control_chars("\x1b]52;c;aGk=\x07", ControlCharPolicy::Standard).is_err());
+        assert!(check_control_chars("\x1bPq\x1b\\", ControlCharPolicy::Standard).is_err());
+        assert!(check_control_chars("\u{9b}31m", ControlCharPolicy::Standard).is_err());
+        assert!(check_control_chars("\x1b]52;c;aGk=\x07", ControlCharPolicy::Allow).is_ok());
+
+        assert!(check_control_chars("echo hi\t\r\n\x7f", ControlCharPolicy::Printable).is_ok());
+        assert!(check_control_chars("\x1b[A", ControlCharPolicy::Printable).is_err());
+        assert!(check_control_chars("\x03", ControlCharPolicy::Printable).is_err());
+    }
+
+    #[test]
+    fn test_check_write_size() {
+        assert!(check_write_size(10, 10).is_ok());
+        assert!(matches!(check_write_size(11, 10), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_batch_commands() {
+        let batch: Vec<BatchCommand> = serde_json::from_str(
+            r#"["npm run migrate", {"command": "npm run seed", "stop_on_failure": true}]"#,
+        )
+        .unwrap();
+        let options: Vec<_> = batch.into_iter().map(BatchCommand::options).collect();
+        assert_eq!(options[0].command, "npm run migrate");
+        assert!(!options[0].waits());
+        assert!(options[1].stop_on_failure && options[1].waits());
+        assert!(check_batch(&options).is_ok());
+        assert!(check_batch(&[]).is_err());
+
+        let mut bad = options[1].clone();
+        bad.timeout_ms = Some(0);
+        assert!(check_batch(&[bad.clone()]).is_err());
+        bad.timeout_ms = None;
+        bad.delay_ms = Some(MAX_WAIT_TIMEOUT_MS + 1);
+        assert!(check_batch(&[bad]).is_err());
+
+        let finished = |exit_code| CommandFinished {
+            session_id: "s".into(),
+            command: None,
+            exit_code,
+            duration_ms: 5,
+        };
+        assert_eq!(batch_status(None, 0), BatchCommandStatus::TimedOut);
+        assert_eq!(batch_status(Some(&finished(Some(0))), 0), BatchCommandStatus::Succeeded);
+        assert_eq!(batch_status(Some(&finished(Some(1))), 0), BatchCommandStatus::Failed);
+        assert_eq!(batch_status(Some(&finished(Some(1))), 1), BatchCommandStatus::Succeeded);
+        assert_eq!(batch_status(Some(&finished(None)), 0), BatchCommandStatus::Failed);
+    }
+
+    #[test]
+    fn test_match_context_bounds() {
+        let text = format!("{}MATCH{}", "a".repeat(600), "b".repeat(600));
+        let ctx = match_context(&text, 600, 605);
+        assert_eq!(ctx.len(), MATCH_CONTEXT_CHARS * 2 + 5);
+        assert!(ctx.starts_with('a') && ctx.ends_with('b'));
+
+        // Short text is returned whole
+        assert_eq!(match_context("xx$ yy", 2, 4), "xx$ yy");
+    }
+
+    #[test]
+    fn test_trim_front_respects_char_boundaries() {
+        let mut text = "ééé".to_string(); // 6 bytes
+        trim_front(&mut text, 3);
+        assert_eq!(text, "é");
+
+        let mut short = "abc".to_string();
+        trim_front(&mut short, 10);
+        assert_eq!(short, "abc");
+    }
+
+    #[test]
+    fn test_slice_chars() {
+        assert_eq!(slice_chars("hello world", 5, true), "world");
+        assert_eq!(slice_chars("hello world", 5, false), "hello");
+        assert_eq!(slice_chars("héllo", 4, true), "éllo");
+        assert_eq!(slice_chars("abc", 10, true), "abc");
+        assert_eq!(slice_chars("abc", 0, true), "");
+        assert_eq!(slice_chars("abc", 0, false), "");
+    }
+
+    #[test]
+    fn test_scrollback_sequence_and_replay() {
+        let mut sb = Scrollback::default();
+        let a = sb.push("ab".into(), 2);
+        let b = sb.push("cde".into(), 3);
+        assert_eq!((a.seq, a.offset), (1, 0));
+        assert_eq!((b.seq, b.offset), (2, 2));
+
+        let (chunks, complete) = sb.since(2);
+        assert!(complete);
+        assert_eq!(chunks, vec![b]);
+        // Nothing new yet
+        assert_eq!(sb.since(3), (Vec::new(), true));
+
+        // Overflow drops the oldest chunks; replay from them is incomplete
+        sb.push("x".repeat(SCROLLBACK_MAX_BYTES), SCROLLBACK_MAX_BYTES);
+        let (chunks, complete) = sb.since(1);
+        assert!(!complete);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].seq, 3);
+    }
+
+    #[test]
+    fn test_transcript_strips_and_caps() {
+        let mut t = Transcript::default();
+        t.append("\x1b[32mok\x1b[0m\r\n");
+        assert_eq!(t.text, "ok\n");
+
+        t.append(&"x".repeat(TRANSCRIPT_MAX_BYTES));
+        assert_eq!(t.text.len(), TRANSCRIPT_MAX_BYTES);
+        assert!(t.text.chars().all(|c| c == 'x'));
+    }
+
+    #[test]
+    fn test_is_password_prompt() {
+        assert!(is_password_prompt("[sudo] password for sam: "));
+        assert!(is_password_prompt("Password:"));
+        assert!(is_password_prompt("Enter passphrase for key '/home/sam/.ssh/id_ed25519':"));
+        assert!(is_password_prompt("Enter PIN:"));
+        assert!(!is_password_prompt("Password changed"));
+        assert!(!is_password_prompt("spin:"));
+        assert!(!is_password_prompt(&format!("{}password:", "x".repeat(MAX_PROMPT_CHARS))));
+    }
+
+    #[test]
+    fn test_prompt_detector_reports_once_per_line() {
+        let mut d = PromptDetector::default();
+        assert_eq!(d.feed(b"$ sudo ls\r\n[sudo] pass"), None);
+        assert_eq!(
+            d.feed(b"word for sam: ").as_deref(),
+            Some("[sudo] password for sam:")
+        );
+        assert_eq!(d.feed(b""), None);
+
+        // Wrong password: sudo prints a newline and asks again
+        assert_eq!(d.feed(b"\r\nSorry, try again.\r\n"), None);
+        assert!(d.feed(b"\x1b[1m[sudo] password for sam:\x1b[0m ").is_some());
+    }
+
+    #[test]
+    fn test_check_secret() {
+        assert!(check_secret("hunter2").is_ok());
+        assert!(check_secret("").is_err());
+        assert!(check_secret("pw\rrm -rf ~\r").is_err());
+        assert!(check_secret("pw\x1b[A").is_err());
+    }
+
+    #[test]
+    fn test_last_line() {
+        assert_eq!(
+            last_line("a\n[sudo] password for sam: \n\n").as_deref(),
+            Some("[sudo] password for sam:")
+        );
+        assert_eq!(last_line(" \n"), None);
+    }
+
+    #[test]
+    fn test_saved_transcript_roundtrip() {
+        let dir = std::env::temp_dir()
+            .join(format!("synthia-transcripts-{}", uuid::Uuid::new_v4()));
+
+        assert!(write_saved_transcript(&dir, "terminal-1", "$ ls\nsrc\n").unwrap());
+        assert!(!write_saved_transcript(&dir, "../escape", "x").unwrap());
+        assert!(!write_saved_transcript(&dir, "terminal-2", " \n").unwrap());
+
+        assert_eq!(take_saved_transcript(&dir, "terminal-1").as_deref(), Some("$ ls\nsrc\n"));
+        // Consumed: a second spawn starts fresh
+        assert_eq!(take_saved_transcript(&dir, "terminal-1"), None);
+        assert_eq!(take_saved_transcript(&dir, "terminal-2"), None);
+
+        let big = "y".repeat(SAVED_TRANSCRIPT_BYTES * 2);
+        write_saved_transcript(&dir, "terminal-3", &big).unwrap();
+        let restored = take_saved_transcript(&dir, "terminal-3").unwrap();
+        assert_eq!(restored.len(), SAVED_TRANSCRIPT_BYTES);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_transcript_restore() {
+        let mut t = Transcript::default();
+        t.restore("$ make\ndone");
+        t.append("$ ");
+        assert_eq!(t.text, format!("$ make\ndone\n{}$ ", RESTORED_MARKER));
+    }
+
+    #[test]
+    fn test_terminal_size_validation() {
+        assert!(TerminalSize::default().validate().is_ok());
+        let size: TerminalSize = serde_json::from_str(r#"{"rows":50,"cols":200}"#).unwrap();
+        assert_eq!((size.rows, size.cols, size.pixel_width), (50, 200, 0));
+        assert!(size.validate().is_ok());
+
+        let zero = TerminalSize { rows: 0, ..size };
+        assert!(zero.validate().is_err());
+        let huge = TerminalSize { cols: MAX_INITIAL_DIMENSION + 1, ..size };
+        assert!(huge.validate().is_err());
+    }
+
+    #[test]
+    fn test_check_shell_args() {
+        assert!(check_shell_args(&[]).is_ok());
+        assert!(check_shell_args(&["--login".into(), "-i".into()]).is_ok());
+        assert!(check_shell_args(&["bad\0arg".into()]).is_err());
+        assert!(check_shell_args(&vec!["-i".to_string(); MAX_SHELL_ARGS + 1]).is_err());
+    }
+
+    #[test]
+    fn test_quote_path_posix() {
+        let q = |p| QuoteStyle::Posix.quote_path(p);
+        assert_eq!(q("/home/sam/src/main.rs"), "/home/sam/src/main.rs");
+        assert_eq!(q("/tmp/My Files/a b.txt"), "'/tmp/My Files/a b.txt'");
+        assert_eq!(q("/tmp/it's $HOME"), r"'/tmp/it'\''s $HOME'");
+        assert_eq!(q("/tmp/café.png"), "'/tmp/café.png'");
+    }
+
+    #[test]
+    fn test_quote_path_windows_shells() {
+        let ps = |p| QuoteStyle::PowerShell.quote_path(p);
+        assert_eq!(ps(r"C:\src\main.rs"), r"C:\src\main.rs");
+        assert_eq!(ps(r"C:\My Files\it's.txt"), r"'C:\My Files\it''s.txt'");
+        assert_eq!(ps("C:\\a\u{2019}b"), "'C:\\a\u{2019}\u{2019}b'");
+
+        let cmd = |p| QuoteStyle::Cmd.quote_path(p);
+        assert_eq!(cmd(r"C:\src\main.rs"), r"C:\src\main.rs");
+        assert_eq!(cmd(r"C:\My Files\a&b.txt"), r#""C:\My Files\a&b.txt""#);
+    }
+
+    #[test]
+    fn test_wsl_path() {
+        assert_eq!(wsl_path(r"C:\Users\sam\a b.txt"), "/mnt/c/Users/sam/a b.txt");
+        assert_eq!(wsl_path("D:"), "/mnt/d/");
+        assert_eq!(wsl_path(r"\\wsl$\Ubuntu\home\sam"), "/home/sam");
+        assert_eq!(wsl_path(r"\\wsl.localhost\Ubuntu\etc\hosts"), "/etc/hosts");
+        assert_eq!(wsl_path("/already/posix"), "/already/posix");
+        assert_eq!(QuoteStyle::Wsl.quote_path(r"C:\My Files"), "'/mnt/c/My Files'");
+    }
+
+    #[test]
+    fn test_quote_style_for_shell() {
+        let shell = |id: &str, program: &str| shells::ShellInfo {
+            id: id.into(),
+            name: String::new(),
+            program: program.into(),
+            args: Vec::new(),
+            is_default: false,
+        };
+        assert_eq!(QuoteStyle::for_shell(&shell("/bin/zsh", "/bin/zsh")), QuoteStyle::Posix);
+        assert_eq!(
+            QuoteStyle::for_shell(&shell("pwsh", r"C:\Program Files\PowerShell\7\pwsh.exe")),
+            QuoteStyle::PowerShell
+        );
+        assert_eq!(
+            QuoteStyle::for_shell(&shell("cmd", r"C:\Windows\System32\cmd.exe")),
+            QuoteStyle::Cmd
+        );
+        assert_eq!(QuoteStyle::for_shell(&shell("wsl:Ubuntu", "wsl.exe")), QuoteStyle::Wsl);
+    }
 }
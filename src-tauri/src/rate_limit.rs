@@ -0,0 +1,203 @@
+//! Rate limiting of agent-facing terminal commands.
+//!
+//! `inject_command`, `inject_commands` and `write_terminal` draw from token
+//! buckets keyed by caller (the `owner` argument, `anonymous` without one)
+//! and session, so a runaway agent loop is refused with `rate_limited`
+//! instead of flooding a shell. Injections and writes have separate buckets
+//! sized by `terminal.rate_limit`; a bucket refills at its per-second rate
+//! up to its burst.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::settings::RateLimitSettings;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Caller name used for requests without an `owner`
+const ANONYMOUS_CALLER: &str = "anonymous";
+
+/// Bucket count above which full (idle) buckets are dropped
+const MAX_IDLE_BUCKETS: usize = 1024;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// What a request spends tokens on; each kind has its own bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitKind {
+    /// `inject_command` / `inject_commands`, one token per command
+    Inject,
+    /// `write_terminal`, one token per call
+    Write,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    /// Refill for the time passed, then take `cost` tokens if available.
+    /// Returns how long to wait otherwise.
+    fn take(&mut self, cost: f64, rate: f64, burst: f64, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst);
+        self.refilled_at = now;
+        if self.tokens >= cost {
+            self.tokens -= cost;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((cost - self.tokens) / rate))
+        }
+    }
+
+    fn is_full(&self, rate: f64, burst: f64, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens + elapsed * rate >= burst
+    }
+}
+
+type BucketKey = (RateLimitKind, String, String);
+
+/// Token buckets per kind, caller and session.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<BucketKey, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Spend `cost` tokens of `owner`'s bucket for `session_id`.
+    ///
+    /// Fails with `rate_limited` (carrying the wait until enough tokens are
+    /// back) when the bucket is short, and with a validation error when
+    /// `cost` exceeds the burst so it could never succeed.
+    pub fn check(
+        &self,
+        limits: &RateLimitSettings,
+        kind: RateLimitKind,
+        owner: Option<&str>,
+        session_id: &str,
+        cost: u32,
+    ) -> Result<(), AppError> {
+        if !limits.enabled {
+            return Ok(());
+        }
+        let (rate, burst) = match kind {
+            RateLimitKind::Inject => (limits.commands_per_sec, limits.command_burst),
+            RateLimitKind::Write => (limits.writes_per_sec, limits.write_burst),
+        };
+        if cost > burst {
+            return Err(AppError::Validation(format!(
+                "Request needs {} tokens but the rate limit burst is {}",
+                cost, burst
+            )));
+        }
+        let (rate, burst) = (f64::from(rate), f64::from(burst));
+        let caller = owner.unwrap_or(ANONYMOUS_CALLER);
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            buckets.retain(|_, b| !b.is_full(rate, burst, now));
+        }
+        let bucket = buckets
+            .entry((kind, caller.to_string(), session_id.to_string()))
+            .or_insert(Bucket {
+                tokens: burst,
+                refilled_at: now,
+            });
+        bucket.take(f64::from(cost), rate, burst, now).map_err(|wait| {
+            log::warn!(
+                "Rate limited {:?} by {} on session {} (retry in {} ms)",
+                kind,
+                caller,
+                session_id,
+                wait.as_millis()
+            );
+            AppError::RateLimited(wait.as_millis().max(1) as u64)
+        })
+    }
+
+    /// Forget the buckets of a closed session.
+    pub fn forget_session(&self, session_id: &str) {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets.retain(|(_, _, id), _| id != session_id);
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> RateLimitSettings {
+        RateLimitSettings {
+            enabled: true,
+            commands_per_sec: 2,
+            command_burst: 3,
+            writes_per_sec: 100,
+            write_burst: 100,
+        }
+    }
+
+    #[test]
+    fn test_bucket_refills_at_rate() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 2.0,
+            refilled_at: start,
+        };
+        assert!(bucket.take(2.0, 4.0, 2.0, start).is_ok());
+        let wait = bucket.take(1.0, 4.0, 2.0, start).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(250));
+        assert!(bucket.take(1.0, 4.0, 2.0, start + Duration::from_millis(250)).is_ok());
+        // Refill stops at the burst
+        assert!(bucket.take(3.0, 4.0, 2.0, start + Duration::from_secs(10)).is_err());
+    }
+
+    #[test]
+    fn test_limits_per_caller_and_session() {
+        let limiter = RateLimiter::default();
+        let limits = limits();
+        for _ in 0..3 {
+            limiter.check(&limits, RateLimitKind::Inject, Some("agent"), "s1", 1).unwrap();
+        }
+        let err = limiter
+            .check(&limits, RateLimitKind::Inject, Some("agent"), "s1", 1)
+            .unwrap_err();
+        assert_eq!(err.code(), "rate_limited");
+
+        // Other callers, sessions and kinds have their own buckets
+        limiter.check(&limits, RateLimitKind::Inject, None, "s1", 1).unwrap();
+        limiter.check(&limits, RateLimitKind::Inject, Some("agent"), "s2", 1).unwrap();
+        limiter.check(&limits, RateLimitKind::Write, Some("agent"), "s1", 1).unwrap();
+
+        limiter.forget_session("s1");
+        limiter.check(&limits, RateLimitKind::Inject, Some("agent"), "s1", 1).unwrap();
+    }
+
+    #[test]
+    fn test_oversized_and_disabled() {
+        let limiter = RateLimiter::default();
+        let mut limits = limits();
+        let err = limiter
+            .check(&limits, RateLimitKind::Inject, None, "s1", 4)
+            .unwrap_err();
+        assert_eq!(err.code(), "validation");
+
+        limits.enabled = false;
+        for _ in 0..10 {
+            limiter.check(&limits, RateLimitKind::Inject, None, "s1", 3).unwrap();
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! Opt-in asciicast recording of terminal sessions.
+//!
+//! `start_session_recording` writes a session's output to an asciicast v2
+//! file (playable with `replay_recording` or asciinema) until
+//! `stop_session_recording` or the session ends. With `record_input` the
+//! bytes written to the session are recorded too, as `"i"` events with
+//! their timestamps, so an interactive session can be audited keystroke by
+//! keystroke.
+//!
+//! Secrets never reach the file: input sent with `write_terminal_secure`,
+//! or while a password prompt is pending or the terminal is reading a
+//! password (echo off), is left out and an `"m"` marker records the gap.
+
+use chrono::Local;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Manager, State};
+
+use crate::pty::{self, PtyState};
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Directory under the app data directory for recordings without a path
+const RECORDINGS_DIR: &str = "recordings";
+
+/// Marker written in place of secure input
+const SECURE_INPUT_MARKER: &str = "secure input omitted";
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// A session's recorder, shared by its reader thread and the input path.
+/// `None` while the session is not being recorded.
+pub(crate) type RecorderSlot = Arc<Mutex<Option<SessionRecorder>>>;
+
+/// An asciicast file being written.
+pub(crate) struct SessionRecorder {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    started: Instant,
+    started_at: String,
+    record_input: bool,
+    events: u64,
+    omitted_inputs: u64,
+}
+
+/// Result of `start_session_recording` and `stop_session_recording`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordingStatus {
+    pub session_id: String,
+    pub path: String,
+    /// Input is recorded as well as output
+    pub record_input: bool,
+    /// RFC 3339 time the recording started
+    pub started_at: String,
+    /// Events written so far
+    pub events: u64,
+    /// Secure inputs left out
+    pub omitted_inputs: u64,
+}
+
+/// asciicast v2 header line.
+#[derive(Serialize)]
+struct Header {
+    version: u32,
+    width: u16,
+    height: u16,
+    timestamp: i64,
+    env: HeaderEnv,
+}
+
+#[derive(Serialize)]
+struct HeaderEnv {
+    #[serde(rename = "TERM")]
+    term: &'static str,
+}
+
+impl SessionRecorder {
+    fn create(path: &Path, cols: u16, rows: u16, record_input: bool) -> Result<Self, AppError> {
+        // Never overwrite an existing recording
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|e| AppError::Io(format!("Failed to create {}: {}", path.display(), e)))?;
+        let now = Local::now();
+        let header = Header {
+            version: 2,
+            width: cols,
+            height: rows,
+            timestamp: now.timestamp(),
+            env: HeaderEnv {
+                term: "xterm-256color",
+            },
+        };
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &header)
+            .map_err(|e| AppError::Io(format!("Failed to write recording header: {}", e)))?;
+        writer
+            .write_all(b"\n")
+            .map_err(|e| AppError::Io(format!("Failed to write recording header: {}", e)))?;
+        Ok(Self {
+            writer,
+            path: path.to_path_buf(),
+            started: Instant::now(),
+            started_at: now.to_rfc3339(),
+            record_input,
+            events: 0,
+            omitted_inputs: 0,
+        })
+    }
+
+    fn event(&mut self, code: &str, data: &str) {
+        let t = (self.started.elapsed().as_secs_f64() * 1e6).round() / 1e6;
+        let written = serde_json::to_writer(&mut self.writer, &(t, code, data))
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"));
+        match written {
+            Ok(()) => self.events += 1,
+            Err(e) => log::warn!("Failed to write to recording {}: {}", self.path.display(), e),
+        }
+    }
+
+    pub(crate) fn output(&mut self, data: &str) {
+        self.event("o", data);
+    }
+
+    /// Record input, or only a marker for `secure` input.
+    pub(crate) fn input(&mut self, data: &[u8], secure: bool) {
+        if !self.record_input {
+            return;
+        }
+        if secure {
+            self.omitted_inputs += 1;
+            self.event("m", SECURE_INPUT_MARKER);
+        } else {
+            self.event("i", &String::from_utf8_lossy(data));
+        }
+    }
+
+    pub(crate) fn resize(&mut self, cols: u16, rows: u16) {
+        self.event("r", &format!("{}x{}", cols, rows));
+    }
+
+    fn status(&self, session_id: String) -> RecordingStatus {
+        RecordingStatus {
+            session_id,
+            path: self.path.to_string_lossy().into_owned(),
+            record_input: self.record_input,
+            started_at: self.started_at.clone(),
+            events: self.events,
+            omitted_inputs: self.omitted_inputs,
+        }
+    }
+
+    fn finish(mut self, session_id: String) -> RecordingStatus {
+        if let Err(e) = self.writer.flush() {
+            log::warn!("Failed to flush recording {}: {}", self.path.display(), e);
+        }
+        self.status(session_id)
+    }
+}
+
+// =============================================================================
+// Lifecycle
+// =============================================================================
+
+/// `session_id` made safe for a file name.
+fn file_stem(session_id: &str) -> String {
+    session_id
+        .chars()
+        .take(64)
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+fn default_path(app: &AppHandle, session_id: &str) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::Internal(format!("Could not determine data directory: {}", e)))?
+        .join(RECORDINGS_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| AppError::Io(format!("Failed to create {}: {}", dir.display(), e)))?;
+    let name = format!(
+        "{}-{}.cast",
+        file_stem(session_id),
+        Local::now().format("%Y%m%d-%H%M%S")
+    );
+    Ok(dir.join(name))
+}
+
+fn check_path(path: &str) -> Result<PathBuf, AppError> {
+    let path = PathBuf::from(path);
+    if !path.is_absolute() {
+        return Err(AppError::Validation(format!(
+            "Recording path must be absolute: {}",
+            path.display()
+        )));
+    }
+    match path.parent() {
+        Some(dir) if dir.is_dir() => Ok(path),
+        _ => Err(AppError::Validation(format!(
+            "Recording directory does not exist: {}",
+            path.display()
+        ))),
+    }
+}
+
+/// Finish the recording in `slot`, if any. Called when a session ends and
+/// on app exit.
+pub(crate) fn finish(slot: &RecorderSlot, session_id: &str) -> Option<RecordingStatus> {
+    let recorder = slot.lock().unwrap_or_else(|e| e.into_inner()).take()?;
+    let status = recorder.finish(session_id.to_string());
+    log::info!(
+        "Recording of session {} saved to {} ({} events)",
+        session_id,
+        status.path,
+        status.events
+    );
+    Some(status)
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Start recording a session to an asciicast v2 file.
+///
+/// # Arguments
+/// * `path` - Absolute path of a new file (default: `recordings/` in the app
+///   data directory); an existing file is never overwritten
+/// * `record_input` - Also record the bytes written to the session (default
+///   false). Secure input is always left out
+#[tauri::command]
+pub fn start_session_recording(
+    app: AppHandle,
+    state: State<'_, PtyState>,
+    session_id: String,
+    path: Option<String>,
+    record_input: Option<bool>,
+) -> Result<RecordingStatus, AppError> {
+    let (slot, (cols, rows)) = pty::session_recorder(&state, &session_id)?;
+    let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+    if slot.is_some() {
+        return Err(AppError::Validation(format!(
+            "Session {} is already being recorded",
+            session_id
+        )));
+    }
+    let path = match path {
+        Some(path) => check_path(&path)?,
+        None => default_path(&app, &session_id)?,
+    };
+    let record_input = record_input.unwrap_or(false);
+    let recorder = SessionRecorder::create(&path, cols, rows, record_input)?;
+    log::info!(
+        "Recording session {} to {} (input: {})",
+        session_id,
+        path.display(),
+        record_input
+    );
+    let status = recorder.status(session_id);
+    *slot = Some(recorder);
+    Ok(status)
+}
+
+/// Stop recording a session and close its file.
+#[tauri::command]
+pub fn stop_session_recording(
+    state: State<'_, PtyState>,
+    session_id: String,
+) -> Result<RecordingStatus, AppError> {
+    let (slot, _) = pty::session_recorder(&state, &session_id)?;
+    finish(&slot, &session_id).ok_or_else(|| {
+        AppError::Validation(format!("Session {} is not being recorded", session_id))
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_excludes_secure_input() {
+        let dir = std::env::temp_dir().join(format!("synthia-rec-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("s.cast");
+
+        let mut recorder = SessionRecorder::create(&path, 80, 24, true).unwrap();
+        recorder.output("$ ");
+        recorder.input(b"sudo ls\r", false);
+        recorder.output("Password: ");
+        recorder.input(b"hunter2\r", true);
+        recorder.resize(100, 30);
+        let status = recorder.finish("s".into());
+        assert_eq!((status.events, status.omitted_inputs), (5, 1));
+        assert!(SessionRecorder::create(&path, 80, 24, true).is_err());
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(!text.contains("hunter2"));
+        let lines: Vec<serde_json::Value> =
+            text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 80);
+        assert_eq!(lines[0]["height"], 24);
+        let events: Vec<(&str, &str)> = lines[1..]
+            .iter()
+            .map(|e| (e[1].as_str().unwrap(), e[2].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                ("o", "$ "),
+                ("i", "sudo ls\r"),
+                ("o", "Password: "),
+                ("m", SECURE_INPUT_MARKER),
+                ("r", "100x30"),
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_output_only_recording() {
+        let dir = std::env::temp_dir().join(format!("synthia-rec-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("s.cast");
+        let mut recorder = SessionRecorder::create(&path, 80, 24, false).unwrap();
+        recorder.input(b"ls\r", false);
+        recorder.output("file.txt\r\n");
+        assert_eq!(recorder.finish("s".into()).events, 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(file_stem("terminal-1/../x"), "terminal-1____x");
+    }
+}
@@ -0,0 +1,365 @@
+//! Keyboard and mouse input from stream viewers (remote control).
+//!
+//! With `stream.remote_control` on, viewers may send input events as text
+//! messages on the stream WebSocket:
+//!
+//! ```json
+//! {"type": "input", "event": {"kind": "pointer_move", "x": 0.5, "y": 0.25}}
+//! ```
+//!
+//! Nothing is injected until the user grants control to that viewer with
+//! `set_stream_client_control`; a viewer can ask for it by sending
+//! `{"type": "request_control"}`, which emits `stream-control-requested`.
+//! Grants last until revoked or the viewer disconnects, and viewers are told
+//! about changes with `{"type": "control", "allowed": true|false}`.
+//!
+//! Events are injected with enigo (CGEvent on macOS, SendInput on Windows,
+//! XTest on Linux) on a dedicated thread. Pointer positions are fractions of
+//! the streamed frame and are mapped onto the main display, so pointer
+//! events are dropped for streams of other displays.
+
+use enigo::{Axis, Button, Coordinate, Direction, Enigo, Key, Keyboard, Mouse, Settings};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use tauri::AppHandle;
+
+use crate::events;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Events buffered for the injection thread before viewers' input is dropped
+const INPUT_QUEUE_CAPACITY: usize = 256;
+
+/// Longest `text` event accepted, in characters
+const MAX_TEXT_CHARS: usize = 1024;
+
+/// Largest scroll step accepted per event, in lines
+const MAX_SCROLL_LINES: i32 = 50;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Input event sent by a viewer that has control.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum InputEvent {
+    /// Move the pointer; `x` and `y` are fractions (0-1) of the frame
+    PointerMove { x: f64, y: f64 },
+    PointerButton {
+        button: PointerButton,
+        action: PressAction,
+    },
+    /// Scroll by whole lines (positive = right / down)
+    Scroll {
+        #[serde(default)]
+        dx: i32,
+        #[serde(default)]
+        dy: i32,
+    },
+    /// Press or release a key: a single character or a name from
+    /// `parse_key` (e.g. `"Enter"`, `"ArrowLeft"`, `"Meta"`)
+    Key { key: String, action: PressAction },
+    /// Type a string
+    Text { text: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PointerButton {
+    Left,
+    Right,
+    Middle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PressAction {
+    Press,
+    Release,
+    /// Press and release
+    Click,
+}
+
+/// Payload of the `stream-control-requested` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamControlRequest {
+    pub client_id: u64,
+    pub addr: String,
+}
+
+/// Input channel of a stream with remote control enabled. Dropping the last
+/// clone stops the injection thread.
+pub(crate) struct RemoteInput {
+    app: AppHandle,
+    tx: mpsc::SyncSender<InputEvent>,
+    /// Pointer positions can be mapped (the stream shows the main display)
+    pointer: Arc<AtomicBool>,
+}
+
+impl InputEvent {
+    /// Check ranges before anything reaches the injection thread.
+    pub(crate) fn validate(&self) -> Result<(), AppError> {
+        match self {
+            Self::PointerMove { x, y } => {
+                let in_frame = |v: &f64| v.is_finite() && (0.0..=1.0).contains(v);
+                if !in_frame(x) || !in_frame(y) {
+                    return Err(AppError::Validation(format!(
+                        "Pointer position must be within 0-1, got: ({}, {})",
+                        x, y
+                    )));
+                }
+            }
+            Self::Scroll { dx, dy } => {
+                if dx.abs() > MAX_SCROLL_LINES || dy.abs() > MAX_SCROLL_LINES {
+                    return Err(AppError::Validation(format!(
+                        "Scroll must be at most {} lines, got: ({}, {})",
+                        MAX_SCROLL_LINES, dx, dy
+                    )));
+                }
+            }
+            Self::Key { key, .. } => {
+                if parse_key(key).is_none() {
+                    return Err(AppError::Validation(format!("Unknown key: {:?}", key)));
+                }
+            }
+            Self::Text { text } => {
+                if text.chars().count() > MAX_TEXT_CHARS {
+                    return Err(AppError::Validation(format!(
+                        "Text input must be at most {} characters",
+                        MAX_TEXT_CHARS
+                    )));
+                }
+            }
+            Self::PointerButton { .. } => {}
+        }
+        Ok(())
+    }
+
+    fn is_pointer(&self) -> bool {
+        matches!(self, Self::PointerMove { .. } | Self::PointerButton { .. })
+    }
+}
+
+// =============================================================================
+// Injection
+// =============================================================================
+
+impl RemoteInput {
+    /// Start the injection thread. `pointer` is false when the stream does
+    /// not show the main display, so positions cannot be mapped.
+    pub(crate) fn start(app: AppHandle, pointer: bool) -> Result<Self, AppError> {
+        let (tx, rx) = mpsc::sync_channel::<InputEvent>(INPUT_QUEUE_CAPACITY);
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<(), AppError>>();
+        let pointer = Arc::new(AtomicBool::new(pointer));
+        let thread_pointer = pointer.clone();
+
+        std::thread::Builder::new()
+            .name("remote-input".into())
+            .spawn(move || {
+                // Enigo is !Send on some platforms, so it lives on this thread
+                let mut enigo = match Enigo::new(&Settings::default()) {
+                    Ok(e) => e,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(AppError::Internal(format!(
+                            "Input injection unavailable: {}",
+                            e
+                        ))));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+
+                // Ends once every RemoteInput clone is dropped
+                for event in rx {
+                    if event.is_pointer() && !thread_pointer.load(Ordering::Relaxed) {
+                        log::debug!("Dropping pointer input: stream is not of the main display");
+                        continue;
+                    }
+                    if let Err(e) = inject(&mut enigo, &event) {
+                        log::warn!("Failed to inject {:?}: {}", event, e);
+                    }
+                }
+                log::debug!("Remote input thread stopped");
+            })
+            .map_err(|e| AppError::Internal(format!("Failed to spawn input thread: {}", e)))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| AppError::Internal("Input thread exited during startup".into()))??;
+        Ok(Self { app, tx, pointer })
+    }
+
+    /// Follow a restart of the stream onto another display.
+    pub(crate) fn set_pointer(&self, pointer: bool) {
+        self.pointer.store(pointer, Ordering::Relaxed);
+    }
+
+    /// Queue a validated event from a viewer that has control.
+    pub(crate) fn send(&self, client_id: u64, event: InputEvent) {
+        if let Err(e) = event.validate() {
+            log::debug!("Ignoring input from stream client {}: {}", client_id, e);
+            return;
+        }
+        if self.tx.try_send(event).is_err() {
+            log::debug!("Input queue full, dropping event from stream client {}", client_id);
+        }
+    }
+
+    /// Tell the frontend a viewer asks for control.
+    pub(crate) fn request_control(&self, client_id: u64, addr: SocketAddr) {
+        log::info!("Stream client {} ({}) requests remote control", client_id, addr);
+        let _ = events::emit(
+            &self.app,
+            "stream-control-requested",
+            StreamControlRequest {
+                client_id,
+                addr: addr.to_string(),
+            },
+        );
+    }
+}
+
+fn inject(enigo: &mut Enigo, event: &InputEvent) -> enigo::InputResult<()> {
+    match event {
+        InputEvent::PointerMove { x, y } => {
+            let (width, height) = enigo.main_display()?;
+            let px = (x * (width - 1).max(0) as f64).round() as i32;
+            let py = (y * (height - 1).max(0) as f64).round() as i32;
+            enigo.move_mouse(px, py, Coordinate::Abs)
+        }
+        InputEvent::PointerButton { button, action } => {
+            let button = match button {
+                PointerButton::Left => Button::Left,
+                PointerButton::Right => Button::Right,
+                PointerButton::Middle => Button::Middle,
+            };
+            enigo.button(button, direction(*action))
+        }
+        InputEvent::Scroll { dx, dy } => {
+            if *dx != 0 {
+                enigo.scroll(*dx, Axis::Horizontal)?;
+            }
+            if *dy != 0 {
+                enigo.scroll(*dy, Axis::Vertical)?;
+            }
+            Ok(())
+        }
+        InputEvent::Key { key, action } => match parse_key(key) {
+            Some(k) => enigo.key(k, direction(*action)),
+            None => Ok(()),
+        },
+        InputEvent::Text { text } => enigo.text(text),
+    }
+}
+
+fn direction(action: PressAction) -> Direction {
+    match action {
+        PressAction::Press => Direction::Press,
+        PressAction::Release => Direction::Release,
+        PressAction::Click => Direction::Click,
+    }
+}
+
+/// Key for a single character or a DOM `KeyboardEvent.key` name.
+fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(if c == ' ' { Key::Space } else { Key::Unicode(c) });
+    }
+    Some(match name {
+        "Enter" => Key::Return,
+        "Tab" => Key::Tab,
+        "Escape" => Key::Escape,
+        "Backspace" => Key::Backspace,
+        "Delete" => Key::Delete,
+        "ArrowUp" => Key::UpArrow,
+        "ArrowDown" => Key::DownArrow,
+        "ArrowLeft" => Key::LeftArrow,
+        "ArrowRight" => Key::RightArrow,
+        "Home" => Key::Home,
+        "End" => Key::End,
+        "PageUp" => Key::PageUp,
+        "PageDown" => Key::PageDown,
+        "Shift" => Key::Shift,
+        "Control" => Key::Control,
+        "Alt" => Key::Alt,
+        "Meta" => Key::Meta,
+        "CapsLock" => Key::CapsLock,
+        "F1" => Key::F1,
+        "F2" => Key::F2,
+        "F3" => Key::F3,
+        "F4" => Key::F4,
+        "F5" => Key::F5,
+        "F6" => Key::F6,
+        "F7" => Key::F7,
+        "F8" => Key::F8,
+        "F9" => Key::F9,
+        "F10" => Key::F10,
+        "F11" => Key::F11,
+        "F12" => Key::F12,
+        _ => return None,
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_event_parse() {
+        let event: InputEvent =
+            serde_json::from_str(r#"{"kind":"pointer_move","x":0.5,"y":0.25}"#).unwrap();
+        assert_eq!(event, InputEvent::PointerMove { x: 0.5, y: 0.25 });
+
+        let event: InputEvent =
+            serde_json::from_str(r#"{"kind":"pointer_button","button":"left","action":"click"}"#)
+                .unwrap();
+        assert_eq!(
+            event,
+            InputEvent::PointerButton {
+                button: PointerButton::Left,
+                action: PressAction::Click,
+            }
+        );
+
+        let event: InputEvent = serde_json::from_str(r#"{"kind":"scroll","dy":-3}"#).unwrap();
+        assert_eq!(event, InputEvent::Scroll { dx: 0, dy: -3 });
+    }
+
+    #[test]
+    fn test_input_event_validate() {
+        assert!(InputEvent::PointerMove { x: 1.0, y: 0.0 }.validate().is_ok());
+        assert!(InputEvent::PointerMove { x: 1.5, y: 0.0 }.validate().is_err());
+        assert!(InputEvent::PointerMove { x: f64::NAN, y: 0.0 }.validate().is_err());
+        assert!(InputEvent::Scroll { dx: 0, dy: 500 }.validate().is_err());
+        let key = |k: &str| InputEvent::Key {
+            key: k.into(),
+            action: PressAction::Click,
+        };
+        assert!(key("a").validate().is_ok());
+        assert!(key("ArrowLeft").validate().is_ok());
+        assert!(key("Hyper").validate().is_err());
+        let text = InputEvent::Text {
+            text: "x".repeat(MAX_TEXT_CHARS + 1),
+        };
+        assert!(text.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("Enter"), Some(Key::Return));
+        assert_eq!(parse_key(" "), Some(Key::Space));
+        assert_eq!(parse_key("é"), Some(Key::Unicode('é')));
+        assert_eq!(parse_key(""), None);
+    }
+}
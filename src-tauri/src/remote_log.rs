@@ -0,0 +1,536 @@
+//! Remote log shipping.
+//!
+//! Opt-in exporter (`logging.remote`) that forwards log records at or above
+//! a level to a central collector, so several Synthia machines can feed one
+//! aggregator. Supported endpoints:
+//! - `udp://host[:514]` — syslog (RFC 5424), one datagram per record
+//! - `tcp://host[:514]` — syslog (RFC 5424) with octet-counting framing
+//! - `http://host[:port][/path]` — POST of a JSON array of records
+//!
+//! The logger only queues records (never blocks); a background thread sends
+//! them in batches and retries a failed batch with backoff before dropping
+//! it. There is no TLS client in the dependency tree, so HTTPS collectors
+//! need a local forwarder (e.g. Vector or Fluent Bit) in front of them.
+
+use chrono::{SecondsFormat, Utc};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri_plugin_log::fern;
+
+use crate::logging;
+use crate::settings::RemoteLogSettings;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Records queued for shipping; newer records are dropped when full
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// Waits between delivery attempts of a batch (then it is dropped)
+const RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(4),
+];
+
+/// Connect/read/write timeout for collector connections
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default port for syslog endpoints
+const SYSLOG_PORT: u16 = 514;
+
+/// APP-NAME field of syslog messages
+const APP_NAME: &str = "synthia";
+
+/// Records from this module are never shipped, so delivery failures
+/// cannot feed back into the queue
+const OWN_TARGET: &str = module_path!();
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// How records are sent to the collector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    SyslogUdp,
+    SyslogTcp,
+    Http,
+}
+
+/// A parsed `logging.remote.endpoint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Endpoint {
+    transport: Transport,
+    host: String,
+    port: u16,
+    /// Request path for HTTP endpoints
+    path: String,
+}
+
+impl Endpoint {
+    /// Parse `udp://`, `tcp://` or `http://` endpoint URLs.
+    pub(crate) fn parse(url: &str) -> Result<Self, AppError> {
+        let invalid = |reason: &str| {
+            AppError::Validation(format!("Invalid logging.remote.endpoint '{}': {}", url, reason))
+        };
+
+        let (scheme, rest) = url
+            .trim()
+            .split_once("://")
+            .ok_or_else(|| invalid("expected udp://, tcp:// or http://"))?;
+        let (transport, default_port) = match scheme.to_ascii_lowercase().as_str() {
+            "udp" => (Transport::SyslogUdp, SYSLOG_PORT),
+            "tcp" => (Transport::SyslogTcp, SYSLOG_PORT),
+            "http" => (Transport::Http, 80),
+            "https" => return Err(invalid("https is not supported, use a local http forwarder")),
+            _ => return Err(invalid("expected udp://, tcp:// or http://")),
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+
+        // `[v6]:port`, `host:port` or a bare host
+        let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+            let (host, after) = v6.split_once(']').ok_or_else(|| invalid("unclosed '['"))?;
+            (host, after.strip_prefix(':'))
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            }
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+        let port = match port {
+            Some(p) => p.parse::<u16>().map_err(|_| invalid("bad port"))?,
+            None => default_port,
+        };
+
+        Ok(Self {
+            transport,
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+/// A record waiting to be shipped (also the JSON shape sent to HTTP collectors).
+#[derive(Debug, Clone, Serialize)]
+struct ShippedEntry {
+    /// RFC 3339, always UTC so machines in different zones line up
+    ts: String,
+    level: String,
+    source: String,
+    message: String,
+    host: String,
+    #[serde(skip)]
+    severity: u8,
+}
+
+/// Settings the delivery thread works from.
+struct Config {
+    endpoint: Endpoint,
+    batch_size: usize,
+    flush_interval: Duration,
+}
+
+/// Process-wide shipper, created the first time shipping is enabled.
+struct Shipper {
+    /// `LevelFilter as usize`; 0 (Off) while shipping is disabled
+    level: AtomicUsize,
+    config: Mutex<Option<Config>>,
+    tx: SyncSender<ShippedEntry>,
+    host: String,
+    /// Records dropped because the queue was full, reported on next delivery
+    dropped: AtomicU64,
+}
+
+static SHIPPER: OnceLock<Shipper> = OnceLock::new();
+
+/// The `log::Log` half: filters and queues records for the delivery thread.
+struct RemoteLogger;
+
+static LOGGER: RemoteLogger = RemoteLogger;
+
+impl Log for RemoteLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let Some(shipper) = SHIPPER.get() else {
+            return false;
+        };
+        metadata.level() as usize <= shipper.level.load(Ordering::Relaxed)
+            && !metadata.target().starts_with(OWN_TARGET)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let Some(shipper) = SHIPPER.get() else {
+            return;
+        };
+
+        // Records arrive already formatted by `logging::format_record`
+        let line = record.args().to_string();
+        let entry = ShippedEntry {
+            ts: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            level: record.level().to_string(),
+            source: record.target().to_string(),
+            message: logging::strip_record_prefix(&line).to_string(),
+            host: shipper.host.clone(),
+            severity: syslog_severity(record.level()),
+        };
+        if shipper.tx.try_send(entry).is_err() {
+            shipper.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+// =============================================================================
+// Setup
+// =============================================================================
+
+/// Log target output for tauri-plugin-log; idle until `configure` enables it.
+pub(crate) fn dispatch() -> fern::Dispatch {
+    fern::Dispatch::new().chain(&LOGGER as &'static dyn Log)
+}
+
+/// Apply the `logging.remote` settings (already validated).
+pub(crate) fn configure(settings: &RemoteLogSettings) {
+    let endpoint = match Endpoint::parse(&settings.endpoint) {
+        Ok(endpoint) if settings.enabled => endpoint,
+        _ => {
+            if let Some(shipper) = SHIPPER.get() {
+                shipper.level.store(LevelFilter::Off as usize, Ordering::Relaxed);
+                *lock_config(shipper) = None;
+            }
+            return;
+        }
+    };
+
+    let shipper = SHIPPER.get_or_init(start_shipper);
+    *lock_config(shipper) = Some(Config {
+        endpoint,
+        batch_size: settings.batch_size,
+        flush_interval: Duration::from_secs(settings.flush_secs),
+    });
+    let level = LevelFilter::from_str(&settings.level).unwrap_or(LevelFilter::Warn);
+    shipper.level.store(level as usize, Ordering::Relaxed);
+    log::info!("Shipping logs at {} and above to {}", level, settings.endpoint);
+}
+
+fn lock_config(shipper: &Shipper) -> std::sync::MutexGuard<'_, Option<Config>> {
+    // Config is replaced wholesale, so a poisoned lock is still usable
+    shipper.config.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn start_shipper() -> Shipper {
+    let (tx, rx) = mpsc::sync_channel(QUEUE_CAPACITY);
+    if let Err(e) = std::thread::Builder::new()
+        .name("log-shipper".into())
+        .spawn(move || run_delivery(rx))
+    {
+        log::error!("Failed to start log shipper: {}", e);
+    }
+    Shipper {
+        level: AtomicUsize::new(LevelFilter::Off as usize),
+        config: Mutex::new(None),
+        tx,
+        host: sysinfo::System::host_name().unwrap_or_else(|| "localhost".into()),
+        dropped: AtomicU64::new(0),
+    }
+}
+
+// =============================================================================
+// Delivery
+// =============================================================================
+
+/// Collect queued records into batches and send each when it is full or
+/// `flush_interval` after its first record.
+fn run_delivery(rx: Receiver<ShippedEntry>) {
+    let mut batch: Vec<ShippedEntry> = Vec::new();
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let (batch_size, flush_interval) = SHIPPER
+            .get()
+            .and_then(|s| lock_config(s).as_ref().map(|c| (c.batch_size, c.flush_interval)))
+            .unwrap_or((1, Duration::from_secs(1)));
+        let wait = deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+            .unwrap_or(flush_interval);
+
+        match rx.recv_timeout(wait) {
+            Ok(entry) => {
+                deadline.get_or_insert_with(|| Instant::now() + flush_interval);
+                batch.push(entry);
+                if batch.len() < batch_size {
+                    continue;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) if batch.is_empty() => continue,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        let Some(shipper) = SHIPPER.get() else {
+            return;
+        };
+        let endpoint = lock_config(shipper).as_ref().map(|c| c.endpoint.clone());
+        // Disabled while the batch was filling: discard it
+        if let Some(endpoint) = endpoint {
+            let dropped = shipper.dropped.swap(0, Ordering::Relaxed);
+            if dropped > 0 {
+                log::warn!("Log shipping queue full, dropped {} record(s)", dropped);
+            }
+            if let Err(e) = deliver(&batch, &RETRY_DELAYS, |b| send_batch(&endpoint, b)) {
+                log::warn!("Dropping {} log record(s) after retries: {}", batch.len(), e);
+            }
+        }
+        batch.clear();
+        deadline = None;
+    }
+}
+
+/// Send a batch, waiting out `delays` between failed attempts.
+fn deliver<F>(batch: &[ShippedEntry], delays: &[Duration], mut send: F) -> Result<(), AppError>
+where
+    F: FnMut(&[ShippedEntry]) -> Result<(), AppError>,
+{
+    let mut result = send(batch);
+    for delay in delays {
+        let Err(e) = &result else {
+            break;
+        };
+        log::debug!("Log shipping failed, retrying in {:?}: {}", delay, e);
+        std::thread::sleep(*delay);
+        result = send(batch);
+    }
+    result
+}
+
+fn send_batch(endpoint: &Endpoint, batch: &[ShippedEntry]) -> Result<(), AppError> {
+    match endpoint.transport {
+        Transport::SyslogUdp => {
+            let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(io_error)?;
+            socket
+                .connect((endpoint.host.as_str(), endpoint.port))
+                .map_err(io_error)?;
+            for entry in batch {
+                socket.send(syslog_message(entry).as_bytes()).map_err(io_error)?;
+            }
+            Ok(())
+        }
+        Transport::SyslogTcp => {
+            let mut stream = connect(endpoint)?;
+            let mut buf = String::new();
+            for entry in batch {
+                let msg = syslog_message(entry);
+                // RFC 6587 octet counting: `<len> <msg>`
+                buf.push_str(&format!("{} {}", msg.len(), msg));
+            }
+            stream.write_all(buf.as_bytes()).map_err(io_error)
+        }
+        Transport::Http => {
+            let body = serde_json::to_vec(batch)
+                .map_err(|e| AppError::Internal(format!("Failed to encode log batch: {}", e)))?;
+            http_post(endpoint, &body)
+        }
+    }
+}
+
+fn io_error(e: std::io::Error) -> AppError {
+    AppError::Io(format!("Log shipping: {}", e))
+}
+
+fn connect(endpoint: &Endpoint) -> Result<TcpStream, AppError> {
+    let addr = (endpoint.host.as_str(), endpoint.port)
+        .to_socket_addrs()
+        .map_err(io_error)?
+        .next()
+        .ok_or_else(|| AppError::Io(format!("Could not resolve {}", endpoint.host)))?;
+    let stream = TcpStream::connect_timeout(&addr, IO_TIMEOUT).map_err(io_error)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT)).map_err(io_error)?;
+    stream.set_write_timeout(Some(IO_TIMEOUT)).map_err(io_error)?;
+    Ok(stream)
+}
+
+/// Minimal HTTP/1.1 POST; succeeds on any 2xx status.
+fn http_post(endpoint: &Endpoint, body: &[u8]) -> Result<(), AppError> {
+    let mut stream = connect(endpoint)?;
+    let host = if endpoint.host.contains(':') {
+        format!("[{}]:{}", endpoint.host, endpoint.port)
+    } else {
+        format!("{}:{}", endpoint.host, endpoint.port)
+    };
+    let head = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        endpoint.path,
+        host,
+        APP_NAME,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).map_err(io_error)?;
+    stream.write_all(body).map_err(io_error)?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut status_line)
+        .map_err(io_error)?;
+    match status_line.split_whitespace().nth(1).and_then(|c| c.parse::<u16>().ok()) {
+        Some(code) if (200..300).contains(&code) => Ok(()),
+        Some(code) => Err(AppError::Io(format!("Log collector returned HTTP {}", code))),
+        None => Err(AppError::Io(format!("Bad HTTP response: {}", status_line.trim()))),
+    }
+}
+
+// =============================================================================
+// Syslog Formatting
+// =============================================================================
+
+/// RFC 5424 severity for a log level.
+fn syslog_severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// Format an RFC 5424 message (facility "user").
+fn syslog_message(entry: &ShippedEntry) -> String {
+    const FACILITY_USER: u8 = 1;
+    format!(
+        "<{}>1 {} {} {} {} - - {}: {}",
+        FACILITY_USER * 8 + entry.severity,
+        entry.ts,
+        entry.host,
+        APP_NAME,
+        std::process::id(),
+        entry.source,
+        entry.message
+    )
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    fn entry(message: &str) -> ShippedEntry {
+        ShippedEntry {
+            ts: "2026-01-02T03:04:05.678Z".into(),
+            level: "WARN".into(),
+            source: "synthia_lib::pty".into(),
+            message: message.into(),
+            host: "build-box".into(),
+            severity: syslog_severity(Level::Warn),
+        }
+    }
+
+    #[test]
+    fn test_endpoint_parse() {
+        let e = Endpoint::parse("udp://logs.lan").unwrap();
+        assert_eq!((e.transport, e.host.as_str(), e.port), (Transport::SyslogUdp, "logs.lan", 514));
+
+        let e = Endpoint::parse("tcp://10.0.0.2:6514").unwrap();
+        assert_eq!((e.transport, e.port), (Transport::SyslogTcp, 6514));
+
+        let e = Endpoint::parse("http://[::1]:8686/logs").unwrap();
+        assert_eq!((e.host.as_str(), e.port, e.path.as_str()), ("::1", 8686, "/logs"));
+
+        let e = Endpoint::parse("http://collector").unwrap();
+        assert_eq!((e.port, e.path.as_str()), (80, "/"));
+
+        assert!(Endpoint::parse("https://collector").is_err());
+        assert!(Endpoint::parse("collector:514").is_err());
+        assert!(Endpoint::parse("udp://:514").is_err());
+        assert!(Endpoint::parse("udp://host:99999").is_err());
+    }
+
+    #[test]
+    fn test_syslog_message() {
+        let msg = syslog_message(&entry("disk almost full"));
+        let expected = format!(
+            "<12>1 2026-01-02T03:04:05.678Z build-box synthia {} - - synthia_lib::pty: disk almost full",
+            std::process::id()
+        );
+        assert_eq!(msg, expected);
+    }
+
+    #[test]
+    fn test_deliver_retries_until_success() {
+        let mut attempts = 0;
+        let delays = [Duration::ZERO; 3];
+        let result = deliver(&[entry("a")], &delays, |_| {
+            attempts += 1;
+            if attempts < 3 {
+                Err(AppError::Io("refused".into()))
+            } else {
+                Ok(())
+            }
+        });
+        assert!(result.is_ok());
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        let result = deliver(&[entry("a")], &delays, |_| {
+            attempts += 1;
+            Err(AppError::Io("refused".into()))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 4);
+    }
+
+    #[test]
+    fn test_http_post_sends_json_batch() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // Read until the JSON body is complete
+            while !request.ends_with(b"]") {
+                let n = conn.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            conn.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let endpoint = Endpoint::parse(&format!("http://127.0.0.1:{}/ingest", port)).unwrap();
+        send_batch(&endpoint, &[entry("one"), entry("two")]).unwrap();
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /ingest HTTP/1.1\r\n"));
+        assert!(request.contains("Content-Type: application/json"));
+        assert!(request.contains(r#""message":"one""#));
+        assert!(request.contains(r#""host":"build-box""#));
+        assert!(!request.contains("severity"));
+    }
+}
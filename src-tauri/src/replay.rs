@@ -0,0 +1,380 @@
+//! Playback of asciicast terminal recordings.
+//!
+//! `replay_recording` plays an asciicast file (v2 or v3, as written by
+//! asciinema) into a virtual session: its output is emitted as
+//! `pty-output-{session_id}` chunks, numbered like a live session's, and
+//! `pty-close-{session_id}` follows the last one. The terminal UI can thus
+//! show a recorded agent run with the same rendering path as a live shell.
+//! A virtual session has no PTY: it takes no input and is not listed by
+//! `list_terminals`.
+//!
+//! Pauses longer than the recording's `idle_time_limit` (or
+//! `DEFAULT_IDLE_LIMIT_SECS`) are shortened to it, and all timing is
+//! divided by `speed`. Input, marker and resize events are skipped.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::oneshot;
+
+use crate::events;
+use crate::pty::PtyOutput;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Largest recording accepted
+const MAX_RECORDING_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Accepted playback speed range
+const MIN_SPEED: f64 = 0.1;
+const MAX_SPEED: f64 = 16.0;
+
+/// Longest pause played when the recording sets no `idle_time_limit`
+const DEFAULT_IDLE_LIMIT_SECS: f64 = 2.0;
+
+/// Output events closer together than this are emitted as one chunk
+const COALESCE_WINDOW: Duration = Duration::from_millis(10);
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Returned by `replay_recording` once playback has started.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingReplay {
+    pub session_id: String,
+    /// Terminal size the recording was made at
+    pub cols: u16,
+    pub rows: u16,
+    /// Playback duration at the requested speed, in seconds
+    pub duration_secs: f64,
+    /// Output events in the recording
+    pub events: usize,
+}
+
+/// Parsed recording: terminal size and output with absolute times (seconds).
+#[derive(Debug, PartialEq)]
+struct Recording {
+    cols: u16,
+    rows: u16,
+    idle_time_limit: Option<f64>,
+    output: Vec<(f64, String)>,
+}
+
+/// Header line of an asciicast file; v2 has `width`/`height`, v3 `term`.
+#[derive(Debug, Deserialize)]
+struct Header {
+    version: u8,
+    width: Option<u16>,
+    height: Option<u16>,
+    term: Option<TermSize>,
+    idle_time_limit: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TermSize {
+    cols: u16,
+    rows: u16,
+}
+
+/// Shared state managed by Tauri: stop handles of running replays.
+#[derive(Default)]
+pub struct ReplayState {
+    replays: Mutex<HashMap<String, oneshot::Sender<()>>>,
+}
+
+impl ReplayState {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, oneshot::Sender<()>>> {
+        // Holds only stop handles, so a poisoned lock is still usable
+        self.replays.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// =============================================================================
+// Parsing
+// =============================================================================
+
+/// Parse an asciicast v2 or v3 file.
+fn parse_recording(text: &str) -> Result<Recording, AppError> {
+    let mut lines = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+    let (_, header_line) = lines
+        .next()
+        .ok_or_else(|| AppError::Validation("Recording is empty".into()))?;
+    let header: Header = serde_json::from_str(header_line)
+        .map_err(|e| AppError::Validation(format!("Invalid asciicast header: {}", e)))?;
+    let (cols, rows) = match (header.version, &header.term) {
+        (2, _) => (header.width.unwrap_or(80), header.height.unwrap_or(24)),
+        (3, Some(term)) => (term.cols, term.rows),
+        (3, None) => (80, 24),
+        (v, _) => {
+            return Err(AppError::Validation(format!(
+                "Unsupported asciicast version: {}",
+                v
+            )))
+        }
+    };
+
+    let mut output = Vec::new();
+    let mut time = 0.0_f64;
+    for (index, line) in lines {
+        // v3 lines may be comments
+        if line.starts_with('#') {
+            continue;
+        }
+        let (t, code, data): (f64, String, String) = serde_json::from_str(line).map_err(|e| {
+            AppError::Validation(format!("Invalid asciicast event on line {}: {}", index + 1, e))
+        })?;
+        if !t.is_finite() || t < 0.0 {
+            return Err(AppError::Validation(format!(
+                "Invalid event time on line {}: {}",
+                index + 1,
+                t
+            )));
+        }
+        // v2 times are absolute, v3 times are intervals since the previous event
+        time = if header.version == 2 { t.max(time) } else { time + t };
+        if code == "o" {
+            output.push((time, data));
+        }
+    }
+
+    Ok(Recording {
+        cols,
+        rows,
+        idle_time_limit: header.idle_time_limit.filter(|l| l.is_finite() && *l > 0.0),
+        output,
+    })
+}
+
+/// Playback offset of every output event, with pauses capped at
+/// `idle_limit` and divided by `speed`.
+fn schedule(output: &[(f64, String)], idle_limit: f64, speed: f64) -> Vec<Duration> {
+    let mut previous = 0.0;
+    let mut elapsed = 0.0;
+    output
+        .iter()
+        .map(|(time, _)| {
+            elapsed += (time - previous).min(idle_limit) / speed;
+            previous = *time;
+            Duration::from_secs_f64(elapsed)
+        })
+        .collect()
+}
+
+fn load_recording(path: &Path) -> Result<Recording, AppError> {
+    let metadata = std::fs::metadata(path).map_err(|e| {
+        AppError::NotFound(format!("Recording not found: {}: {}", path.display(), e))
+    })?;
+    if metadata.len() > MAX_RECORDING_BYTES {
+        return Err(AppError::Validation(format!(
+            "Recording is larger than {} MiB: {}",
+            MAX_RECORDING_BYTES / (1024 * 1024),
+            path.display()
+        )));
+    }
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| AppError::Io(format!("Failed to read recording: {}", e)))?;
+    parse_recording(&text)
+}
+
+// =============================================================================
+// Playback
+// =============================================================================
+
+/// Emit the recording's output on schedule until done or stopped.
+async fn play(
+    app: AppHandle,
+    session_id: String,
+    recording: Recording,
+    offsets: Vec<Duration>,
+    mut stop_rx: oneshot::Receiver<()>,
+) {
+    let event_name = format!("pty-output-{}", session_id);
+    let start = tokio::time::Instant::now();
+    let mut seq = 0;
+    let mut offset = 0;
+    let mut events = recording.output.into_iter().zip(offsets).peekable();
+    let mut stopped = false;
+
+    while let Some(((_, mut data), at)) = events.next() {
+        tokio::select! {
+            _ = tokio::time::sleep_until(start + at) => {}
+            _ = &mut stop_rx => {
+                log::info!("Replay {} stopped", session_id);
+                stopped = true;
+                break;
+            }
+        }
+        while let Some(((_, more), _)) = events.next_if(|(_, next)| *next <= at + COALESCE_WINDOW) {
+            data.push_str(&more);
+        }
+
+        seq += 1;
+        let chunk = PtyOutput {
+            seq,
+            offset,
+            data,
+        };
+        offset += chunk.data.len() as u64;
+        if events::emit(&app, &event_name, &chunk).is_err() {
+            log::warn!("Failed to emit replay output for session: {}", session_id);
+            break;
+        }
+    }
+
+    // stop_replay already removed a stopped replay, and the id may be reused since
+    if !stopped {
+        app.state::<ReplayState>().lock().remove(&session_id);
+    }
+    let _ = events::emit(&app, &format!("pty-close-{}", session_id), ());
+    log::info!("Replay {} finished after {} chunk(s)", session_id, seq);
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Play an asciicast recording into a new virtual session.
+///
+/// # Arguments
+/// * `path` - asciicast v2 or v3 file
+/// * `speed` - Playback speed factor, 0.1-16 (default 1, e.g. 2 for 2x)
+/// * `session_id` - Id of the virtual session (default `replay-<uuid>`);
+///   subscribe to its output events before calling
+#[tauri::command]
+pub async fn replay_recording(
+    app: AppHandle,
+    state: State<'_, ReplayState>,
+    path: String,
+    speed: Option<f64>,
+    session_id: Option<String>,
+) -> Result<RecordingReplay, AppError> {
+    let speed = speed.unwrap_or(1.0);
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        return Err(AppError::Validation(format!(
+            "Speed must be {}-{}, got: {}",
+            MIN_SPEED, MAX_SPEED, speed
+        )));
+    }
+    let session_id = session_id.unwrap_or_else(|| format!("replay-{}", uuid::Uuid::new_v4()));
+
+    let load_path = path.clone();
+    let recording = tokio::task::spawn_blocking(move || load_recording(Path::new(&load_path)))
+        .await
+        .map_err(|e| AppError::Internal(format!("Recording load task failed: {}", e)))??;
+
+    let idle_limit = recording.idle_time_limit.unwrap_or(DEFAULT_IDLE_LIMIT_SECS);
+    let offsets = schedule(&recording.output, idle_limit, speed);
+    let replay = RecordingReplay {
+        session_id: session_id.clone(),
+        cols: recording.cols,
+        rows: recording.rows,
+        duration_secs: offsets.last().map_or(0.0, Duration::as_secs_f64),
+        events: recording.output.len(),
+    };
+
+    let (stop_tx, stop_rx) = oneshot::channel();
+    {
+        let mut replays = state.lock();
+        if replays.contains_key(&session_id) {
+            return Err(AppError::Validation(format!(
+                "Session {} is already replaying",
+                session_id
+            )));
+        }
+        replays.insert(session_id.clone(), stop_tx);
+    }
+
+    log::info!(
+        "Replaying {} into session {} at {}x ({} events, {:.1}s)",
+        path,
+        session_id,
+        speed,
+        replay.events,
+        replay.duration_secs
+    );
+    tauri::async_runtime::spawn(play(app, session_id, recording, offsets, stop_rx));
+    Ok(replay)
+}
+
+/// Stop a running replay; its session closes as if the recording ended.
+#[tauri::command]
+pub fn stop_replay(state: State<'_, ReplayState>, session_id: String) -> Result<(), AppError> {
+    let stop_tx = state
+        .lock()
+        .remove(&session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.clone()))?;
+    let _ = stop_tx.send(());
+    Ok(())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_v2() {
+        let text = concat!(
+            r#"{"version": 2, "width": 100, "height": 30, "idle_time_limit": 1.5}"#,
+            "\n",
+            r#"[0.5, "o", "$ ls\r\n"]"#,
+            "\n",
+            r#"[0.7, "i", "q"]"#,
+            "\n",
+            r#"[1.25, "o", "a.txt\r\n"]"#,
+            "\n",
+        );
+        let recording = parse_recording(text).unwrap();
+        assert_eq!((recording.cols, recording.rows), (100, 30));
+        assert_eq!(recording.idle_time_limit, Some(1.5));
+        assert_eq!(
+            recording.output,
+            vec![(0.5, "$ ls\r\n".to_string()), (1.25, "a.txt\r\n".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_v3_intervals() {
+        let text = concat!(
+            r#"{"version": 3, "term": {"cols": 120, "rows": 40}}"#,
+            "\n# comment\n",
+            r#"[0.5, "o", "a"]"#,
+            "\n",
+            r#"[0.25, "o", "b"]"#,
+        );
+        let recording = parse_recording(text).unwrap();
+        assert_eq!((recording.cols, recording.rows), (120, 40));
+        assert_eq!(recording.output, vec![(0.5, "a".into()), (0.75, "b".into())]);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_input() {
+        assert!(parse_recording("").is_err());
+        assert!(parse_recording(r#"{"version": 1}"#).is_err());
+        assert!(parse_recording("{\"version\": 2}\nnot json").is_err());
+    }
+
+    #[test]
+    fn test_schedule_caps_pauses_and_applies_speed() {
+        let output = vec![(1.0, String::new()), (11.0, String::new()), (12.0, String::new())];
+        let offsets = schedule(&output, 2.0, 2.0);
+        assert_eq!(
+            offsets,
+            vec![
+                Duration::from_millis(500),
+                Duration::from_millis(1500),
+                Duration::from_millis(2000),
+            ]
+        );
+    }
+}
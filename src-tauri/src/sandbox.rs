@@ -0,0 +1,447 @@
+//! Restricted shells for agent sessions (`spawn_sandboxed_terminal`).
+//!
+//! The shell is wrapped in the strongest sandbox the machine offers:
+//!
+//! - **firejail** (Linux): HOME read-only except the working directory,
+//!   credential directories hidden, private `/tmp`
+//! - **sandbox-exec** (macOS): writes denied under HOME except the working
+//!   directory, credential directories unreadable
+//! - **rbash** otherwise: bash in restricted mode, which forbids `cd`,
+//!   changing `PATH`, running commands by path and redirecting output.
+//!   `PATH` is a directory of links to read-only tools (`RBASH_TOOLS`), as
+//!   anything that can run a shell or write files would undo the
+//!   restrictions. Nothing hides files from it, so it is no isolation.
+//!
+//! firejail and sandbox-exec limit `PATH` to `SANDBOX_PATH`. The working
+//! directory is the one writable place, so it may not be HOME or contain
+//! it; by default it is a fresh directory under the app data dir. The
+//! session's `restrictions` list what was actually applied, so callers can
+//! tell a full sandbox from the rbash fallback.
+
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::shells::{self, ShellInfo};
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// `PATH` inside a sandboxed session
+const SANDBOX_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+/// Directories under HOME holding credentials, hidden from the sandbox
+const SENSITIVE_DIRS: &[&str] = &[".ssh", ".gnupg", ".aws", ".kube", ".docker", ".config/gh"];
+
+/// Set in the sandboxed shell's environment to the backend name
+const SANDBOX_ENV: &str = "SYNTHIA_SANDBOX";
+
+/// Sandbox state under the app data dir: `bin` for rbash, `work-*` for
+/// default working directories
+const SANDBOX_DIR: &str = "sandbox";
+
+/// Programs linked into rbash's `PATH`. Only tools that can neither start
+/// other programs nor write files: no shells, interpreters, editors, pagers,
+/// `find`, `xargs`, `env`, `sed`, `awk`, `sort` (`-o`), `file` (`-C`) or `tee`.
+const RBASH_TOOLS: &[&str] = &[
+    "basename", "cat", "clear", "cmp", "cut", "date", "df", "diff", "dirname", "du", "grep",
+    "head", "id", "ls", "realpath", "stat", "tail", "tr", "uname", "wc", "whoami",
+];
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// How a sandboxed session is confined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxBackend {
+    Firejail,
+    SandboxExec,
+    Rbash,
+}
+
+impl SandboxBackend {
+    fn as_str(self) -> &'static str {
+        match self {
+            SandboxBackend::Firejail => "firejail",
+            SandboxBackend::SandboxExec => "sandbox_exec",
+            SandboxBackend::Rbash => "rbash",
+        }
+    }
+}
+
+/// Sandbox of a session, part of `list_terminals` and the result of
+/// `spawn_sandboxed_terminal`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SandboxInfo {
+    pub backend: SandboxBackend,
+    /// Human-readable restrictions in effect
+    pub restrictions: Vec<String>,
+}
+
+/// The command that starts a shell inside the sandbox.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SandboxPlan {
+    pub program: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    /// Shell running inside, for quoting and completions
+    pub shell: ShellInfo,
+    pub info: SandboxInfo,
+}
+
+// =============================================================================
+// Planning
+// =============================================================================
+
+/// Pick the backend available here and build the command running `shell`
+/// (with `extra_args`) confined to `work_dir`. `data_dir` is the app data
+/// dir, where rbash's tool directory is kept.
+pub(crate) fn plan(
+    shell: &ShellInfo,
+    extra_args: &[String],
+    work_dir: &Path,
+    home: Option<&Path>,
+    data_dir: &Path,
+) -> Result<SandboxPlan, AppError> {
+    check_work_dir(work_dir, home)?;
+    if cfg!(target_os = "linux") {
+        if let Some(firejail) = shells::find_on_path("firejail") {
+            return Ok(firejail_plan(&firejail, shell, extra_args, work_dir, home));
+        }
+    }
+    if cfg!(target_os = "macos") {
+        let sandbox_exec = Path::new("/usr/bin/sandbox-exec");
+        if sandbox_exec.is_file() {
+            return Ok(sandbox_exec_plan(
+                &sandbox_exec.to_string_lossy(),
+                shell,
+                extra_args,
+                work_dir,
+                home,
+            ));
+        }
+    }
+    if cfg!(unix) {
+        let bash = shells::find_on_path("bash").ok_or_else(|| {
+            AppError::NotFound("No sandbox available: install firejail or bash".into())
+        })?;
+        let bin = data_dir.join(SANDBOX_DIR).join("bin");
+        let tools = link_tools(&bin)
+            .map_err(|e| AppError::Io(format!("Failed to prepare rbash tools: {}", e)))?;
+        return Ok(rbash_plan(&bash, &bin, &tools));
+    }
+    Err(AppError::Unsupported("Sandboxed terminals are not supported on this platform".into()))
+}
+
+/// Refuse a working directory that is HOME or contains it (such as `/`):
+/// it stays writable, which would undo the read-only HOME.
+fn check_work_dir(work_dir: &Path, home: Option<&Path>) -> Result<(), AppError> {
+    let Some(home) = home else {
+        return Ok(());
+    };
+    // `work_dir` is canonical already; HOME may be reached through a symlink
+    let home = home.canonicalize().unwrap_or_else(|_| home.to_path_buf());
+    if home.starts_with(work_dir) {
+        return Err(AppError::Validation(format!(
+            "A sandboxed session cannot use {} as its working directory: it contains HOME",
+            work_dir.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Create a fresh, empty working directory under the app data dir, used
+/// when a sandboxed session is spawned without `cwd`.
+pub(crate) fn fresh_work_dir(data_dir: &Path) -> Result<PathBuf, AppError> {
+    let dir = data_dir
+        .join(SANDBOX_DIR)
+        .join(format!("work-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| AppError::Io(format!("Failed to create sandbox directory: {}", e)))?;
+    dir.canonicalize()
+        .map_err(|e| AppError::Io(format!("Failed to resolve sandbox directory: {}", e)))
+}
+
+/// Link every `RBASH_TOOLS` program found on `SANDBOX_PATH` into `bin`.
+/// Returns the names of the linked tools.
+#[cfg(unix)]
+fn link_tools(bin: &Path) -> io::Result<Vec<String>> {
+    std::fs::create_dir_all(bin)?;
+    let mut linked = Vec::new();
+    for &tool in RBASH_TOOLS {
+        let Some(target) = std::env::split_paths(SANDBOX_PATH)
+            .map(|dir| dir.join(tool))
+            .find(|candidate| candidate.is_file())
+        else {
+            continue;
+        };
+        let link = bin.join(tool);
+        // Replace what is there, so a stale entry cannot shadow the tool
+        match std::fs::symlink_metadata(&link) {
+            Ok(meta) if meta.is_dir() => std::fs::remove_dir_all(&link)?,
+            Ok(_) => std::fs::remove_file(&link)?,
+            Err(_) => {}
+        }
+        match std::os::unix::fs::symlink(&target, &link) {
+            // Another session linked it at the same time
+            Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+            _ => linked.push(tool.to_string()),
+        }
+    }
+    Ok(linked)
+}
+
+#[cfg(not(unix))]
+fn link_tools(_bin: &Path) -> io::Result<Vec<String>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "rbash needs unix symlinks"))
+}
+
+fn base_env(backend: SandboxBackend, path: &str) -> Vec<(String, String)> {
+    vec![
+        ("PATH".into(), path.into()),
+        (SANDBOX_ENV.into(), backend.as_str().into()),
+    ]
+}
+
+fn sensitive_paths(home: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    SENSITIVE_DIRS.iter().map(move |dir| home.join(dir))
+}
+
+fn firejail_plan(
+    firejail: &str,
+    shell: &ShellInfo,
+    extra_args: &[String],
+    work_dir: &Path,
+    home: Option<&Path>,
+) -> SandboxPlan {
+    let mut args = vec!["--quiet".to_string(), "--noprofile".into(), "--private-tmp".into()];
+    let mut restrictions = vec!["private /tmp".to_string()];
+    if let Some(home) = home {
+        args.push(format!("--read-only={}", home.display()));
+        for path in sensitive_paths(home) {
+            args.push(format!("--blacklist={}", path.display()));
+        }
+        restrictions.push(format!("{} is read-only", home.display()));
+        restrictions.push(format!("credential directories hidden ({})", SENSITIVE_DIRS.join(", ")));
+    }
+    args.push(format!("--read-write={}", work_dir.display()));
+    restrictions.push(format!("writable: {}", work_dir.display()));
+    restrictions.push(format!("PATH limited to {}", SANDBOX_PATH));
+    args.push("--".into());
+    args.push(shell.program.clone());
+    args.extend(shell.args.iter().cloned());
+    args.extend(extra_args.iter().cloned());
+
+    SandboxPlan {
+        program: firejail.to_string(),
+        args,
+        env: base_env(SandboxBackend::Firejail, SANDBOX_PATH),
+        shell: shell.clone(),
+        info: SandboxInfo {
+            backend: SandboxBackend::Firejail,
+            restrictions,
+        },
+    }
+}
+
+/// Quote a path for a sandbox profile string literal.
+fn sbpl_string(path: &Path) -> String {
+    let escaped = path.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", escaped)
+}
+
+/// Seatbelt profile: everything allowed except writes under HOME (other
+/// than `work_dir`) and reading credential directories. Later rules win.
+fn sandbox_profile(work_dir: &Path, home: Option<&Path>) -> String {
+    let mut profile = String::from("(version 1)\n(allow default)\n");
+    if let Some(home) = home {
+        profile.push_str(&format!("(deny file-write* (subpath {}))\n", sbpl_string(home)));
+    }
+    profile.push_str(&format!("(allow file-write* (subpath {}))\n", sbpl_string(work_dir)));
+    if let Some(home) = home {
+        for path in sensitive_paths(home) {
+            profile.push_str(&format!(
+                "(deny file-read* file-write* (subpath {}))\n",
+                sbpl_string(&path)
+            ));
+        }
+    }
+    profile
+}
+
+fn sandbox_exec_plan(
+    sandbox_exec: &str,
+    shell: &ShellInfo,
+    extra_args: &[String],
+    work_dir: &Path,
+    home: Option<&Path>,
+) -> SandboxPlan {
+    let mut args = vec!["-p".to_string(), sandbox_profile(work_dir, home), shell.program.clone()];
+    args.extend(shell.args.iter().cloned());
+    args.extend(extra_args.iter().cloned());
+
+    let mut restrictions = Vec::new();
+    if let Some(home) = home {
+        restrictions.push(format!("{} is read-only", home.display()));
+        restrictions.push(format!("credential directories hidden ({})", SENSITIVE_DIRS.join(", ")));
+    }
+    restrictions.push(format!("writable: {}", work_dir.display()));
+    restrictions.push(format!("PATH limited to {}", SANDBOX_PATH));
+
+    SandboxPlan {
+        program: sandbox_exec.to_string(),
+        args,
+        env: base_env(SandboxBackend::SandboxExec, SANDBOX_PATH),
+        shell: shell.clone(),
+        info: SandboxInfo {
+            backend: SandboxBackend::SandboxExec,
+            restrictions,
+        },
+    }
+}
+
+/// Restricted bash, whatever shell was asked for, with only the `tools`
+/// linked into `bin` on `PATH`. Startup files are skipped because they could
+/// undo the restrictions before they take effect.
+fn rbash_plan(bash: &str, bin: &Path, tools: &[String]) -> SandboxPlan {
+    let args = vec!["--restricted".to_string(), "--noprofile".into(), "--norc".into()];
+    SandboxPlan {
+        program: bash.to_string(),
+        args: args.clone(),
+        env: base_env(SandboxBackend::Rbash, &bin.to_string_lossy()),
+        shell: ShellInfo {
+            id: "rbash".into(),
+            name: "rbash".into(),
+            program: bash.to_string(),
+            args,
+            is_default: false,
+        },
+        info: SandboxInfo {
+            backend: SandboxBackend::Rbash,
+            restrictions: vec![
+                "restricted bash: no cd, no PATH changes, no commands by path, no redirects"
+                    .into(),
+                format!("PATH limited to read-only tools: {}", tools.join(", ")),
+                "no isolation: every file the user can read, credentials included, is readable"
+                    .into(),
+            ],
+        },
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zsh() -> ShellInfo {
+        ShellInfo {
+            id: "zsh".into(),
+            name: "zsh".into(),
+            program: "/bin/zsh".into(),
+            args: vec!["-l".into()],
+            is_default: true,
+        }
+    }
+
+    #[test]
+    fn test_firejail_plan() {
+        let plan = firejail_plan(
+            "/usr/bin/firejail",
+            &zsh(),
+            &["-i".into()],
+            Path::new("/home/u/work"),
+            Some(Path::new("/home/u")),
+        );
+        assert!(plan.args.contains(&"--read-only=/home/u".to_string()));
+        assert!(plan.args.contains(&"--blacklist=/home/u/.ssh".to_string()));
+        assert!(plan.args.contains(&"--read-write=/home/u/work".to_string()));
+        // The shell and its arguments follow the separator
+        let shell_at = plan.args.iter().position(|a| a == "--").unwrap();
+        assert_eq!(plan.args[shell_at + 1..], ["/bin/zsh", "-l", "-i"]);
+        assert!(plan.env.contains(&("PATH".into(), SANDBOX_PATH.into())));
+        assert_eq!(plan.shell, zsh());
+    }
+
+    #[test]
+    fn test_sandbox_profile() {
+        let home = Path::new("/Users/u");
+        let profile = sandbox_profile(&home.join("my \"work\""), Some(home));
+        let deny_home = profile.find("(deny file-write* (subpath \"/Users/u\"))").unwrap();
+        let allow_work = profile
+            .find("(allow file-write* (subpath \"/Users/u/my \\\"work\\\"\"))")
+            .unwrap();
+        // The working directory exception must come after the HOME rule
+        assert!(allow_work > deny_home);
+        assert!(profile.contains("(deny file-read* file-write* (subpath \"/Users/u/.aws\"))"));
+    }
+
+    #[test]
+    fn test_rbash_plan() {
+        let plan = rbash_plan("/bin/bash", Path::new("/data/sandbox/bin"), &["cat".into()]);
+        assert_eq!(plan.args[0], "--restricted");
+        assert_eq!(plan.shell.program, "/bin/bash");
+        assert_eq!(plan.info.backend, SandboxBackend::Rbash);
+        assert!(plan.env.contains(&(SANDBOX_ENV.into(), "rbash".into())));
+        // Only the curated tools, never the system bin directories
+        assert!(plan.env.contains(&("PATH".into(), "/data/sandbox/bin".into())));
+        assert!(plan.info.restrictions.iter().any(|r| r.starts_with("no isolation")));
+    }
+
+    #[test]
+    fn test_check_work_dir() {
+        let home = Some(Path::new("/home/u"));
+        assert!(check_work_dir(Path::new("/home/u/work"), home).is_ok());
+        assert!(check_work_dir(Path::new("/srv/work"), home).is_ok());
+        // HOME itself, or anything above it, would make HOME writable
+        for dir in ["/home/u", "/home", "/"] {
+            let err = check_work_dir(Path::new(dir), home).unwrap_err();
+            assert!(matches!(err, AppError::Validation(_)), "{}", dir);
+        }
+        assert!(check_work_dir(Path::new("/"), None).is_ok());
+    }
+
+    #[test]
+    fn test_plan_rejects_home_as_cwd() {
+        let home = Path::new("/home/u");
+        let err = plan(&zsh(), &[], home, Some(home), Path::new("/data")).unwrap_err();
+        assert!(matches!(err, AppError::Validation(_)));
+    }
+
+    #[test]
+    fn test_fresh_work_dir() {
+        let data = std::env::temp_dir().join(format!("synthia-sandbox-{}", uuid::Uuid::new_v4()));
+        let first = fresh_work_dir(&data).unwrap();
+        let second = fresh_work_dir(&data).unwrap();
+        assert!(first.is_dir());
+        assert_ne!(first, second);
+        assert_eq!(std::fs::read_dir(&first).unwrap().count(), 0);
+        std::fs::remove_dir_all(&data).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_link_tools() {
+        let bin = std::env::temp_dir().join(format!("synthia-rbash-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&bin).unwrap();
+        // A planted entry is replaced
+        std::fs::write(bin.join("cat"), "#!/bin/sh\n").unwrap();
+        let tools = link_tools(&bin).unwrap();
+        assert!(tools.iter().all(|t| RBASH_TOOLS.contains(&t.as_str())));
+        for entry in std::fs::read_dir(&bin).unwrap() {
+            let entry = entry.unwrap();
+            assert!(entry.file_type().unwrap().is_symlink());
+            assert!(RBASH_TOOLS.contains(&entry.file_name().to_str().unwrap()));
+        }
+        // Linking again is fine
+        assert_eq!(link_tools(&bin).unwrap(), tools);
+        std::fs::remove_dir_all(&bin).unwrap();
+    }
+}
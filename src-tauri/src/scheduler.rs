@@ -0,0 +1,633 @@
+//! Scheduled command execution.
+//!
+//! Lets users register recurring jobs — "every 15m run `git fetch` in
+//! session X", "at 18:00 stop the stream", "every hour run `backup.rhai`".
+//! Schedules and their run history are persisted to `schedules.json` in the
+//! app data directory and evaluated by a background task once per second.
+
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, MutexGuard};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Mutex;
+
+use crate::pty;
+use crate::scripting;
+use crate::streaming;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// File name of the schedule store inside the app data directory
+const SCHEDULES_FILE_NAME: &str = "schedules.json";
+
+/// Shortest allowed interval for recurring jobs (prevents accidental hammering)
+const MIN_INTERVAL_SECS: u64 = 10;
+
+/// Longest allowed interval for recurring jobs (one year)
+const MAX_INTERVAL_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Maximum run-history entries kept on disk (oldest are dropped first)
+const MAX_HISTORY_ENTRIES: usize = 500;
+
+/// How often the background task checks for due jobs
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// When a schedule fires.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleSpec {
+    /// Run repeatedly every `seconds` seconds
+    Every { seconds: u64 },
+    /// Run once a day at local time `time` ("HH:MM")
+    Daily { time: String },
+}
+
+/// What a schedule does when it fires.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleAction {
+    /// Inject a command (plus newline) into an existing terminal session
+    RunCommand { session_id: String, command: String },
+    /// Stop the local screen stream if it is running
+    StopStream,
+    /// Run a script file (see `scripting`), read each time the schedule fires
+    RunScript { path: String },
+}
+
+/// A registered schedule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    pub name: String,
+    pub spec: ScheduleSpec,
+    pub action: ScheduleAction,
+    pub enabled: bool,
+    pub created_at: DateTime<Local>,
+    pub last_run: Option<DateTime<Local>>,
+    pub next_run: DateTime<Local>,
+}
+
+/// Outcome of a single schedule execution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    pub schedule_id: String,
+    pub schedule_name: String,
+    pub started_at: DateTime<Local>,
+    pub success: bool,
+    pub message: String,
+}
+
+/// On-disk representation of all schedules and their history.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScheduleStore {
+    schedules: Vec<Schedule>,
+    history: Vec<ScheduleRun>,
+}
+
+/// Shared state managed by Tauri.
+#[derive(Default)]
+pub struct SchedulerState {
+    /// Locked by `init` until `schedules.json` is loaded, so commands wait
+    /// for it rather than being overwritten by it
+    store: Arc<Mutex<ScheduleStore>>,
+    /// Ids of schedules whose action is still running
+    running: std::sync::Mutex<HashSet<String>>,
+}
+
+impl SchedulerState {
+    fn running(&self) -> MutexGuard<'_, HashSet<String>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// =============================================================================
+// Schedule Store Location
+// =============================================================================
+
+/// Get the path to the persisted schedule store.
+fn get_schedules_path(app: &AppHandle) -> Option<PathBuf> {
+    let data_dir = app.path().app_data_dir().ok()?;
+    Some(data_dir.join(SCHEDULES_FILE_NAME))
+}
+
+/// Write the store to disk, creating the data directory if needed.
+async fn save_store(app: &AppHandle, store: &ScheduleStore) -> Result<(), AppError> {
+    let path = get_schedules_path(app)
+        .ok_or_else(|| AppError::Internal("Could not determine schedules file path".into()))?;
+
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| AppError::Io(format!("Failed to create data directory: {}", e)))?;
+    }
+
+    let json = serde_json::to_string_pretty(store)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize schedules: {}", e)))?;
+
+    tokio::fs::write(&path, json)
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to write schedules file: {}", e)))
+}
+
+/// Read the store at `path`. A missing file yields an empty store.
+async fn load_store(path: &Path) -> Result<ScheduleStore, AppError> {
+    if !path.exists() {
+        return Ok(ScheduleStore::default());
+    }
+
+    let json = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to read schedules file: {}", e)))?;
+
+    serde_json::from_str(&json)
+        .map_err(|e| AppError::Internal(format!("Failed to parse schedules file: {}", e)))
+}
+
+/// Load the store at `path`. An unreadable file is moved to
+/// `schedules.json.bak` and an empty store returned, so the next save
+/// does not overwrite the user's schedules.
+async fn load_store_or_back_up(path: &Path) -> ScheduleStore {
+    let e = match load_store(path).await {
+        Ok(store) => return store,
+        Err(e) => e,
+    };
+    log::error!("Failed to load schedules: {}", e);
+    let backup = path.with_extension("json.bak");
+    match tokio::fs::rename(path, &backup).await {
+        Ok(()) => log::warn!("Moved the unreadable schedules file to {}", backup.display()),
+        Err(e) => log::error!("Failed to back up the schedules file: {}", e),
+    }
+    ScheduleStore::default()
+}
+
+// =============================================================================
+// Timing
+// =============================================================================
+
+/// Parse a "HH:MM" time of day.
+fn parse_time_of_day(time: &str) -> Result<NaiveTime, AppError> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| AppError::Validation(format!("Invalid time of day (expected HH:MM): {}", time)))
+}
+
+/// Validate a schedule spec before accepting it.
+fn validate_spec(spec: &ScheduleSpec) -> Result<(), AppError> {
+    match spec {
+        ScheduleSpec::Every { seconds } => {
+            if !(MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(seconds) {
+                return Err(AppError::Validation(format!(
+                    "Interval must be {}-{} seconds, got: {}",
+                    MIN_INTERVAL_SECS, MAX_INTERVAL_SECS, seconds
+                )));
+            }
+            Ok(())
+        }
+        ScheduleSpec::Daily { time } => parse_time_of_day(time).map(|_| ()),
+    }
+}
+
+/// Validate a schedule action before accepting it. A script must exist and
+/// be a file; it is read again each time the schedule fires.
+fn validate_action(action: &ScheduleAction) -> Result<(), AppError> {
+    match action {
+        ScheduleAction::RunScript { path } => scripting::check_script_path(Path::new(path)),
+        ScheduleAction::RunCommand { .. } | ScheduleAction::StopStream => Ok(()),
+    }
+}
+
+/// Compute the next time a spec fires strictly after `after`.
+///
+/// Returns None if the spec is invalid or the time is out of range. Daily
+/// times that fall into a DST gap fire at the first valid instant after the
+/// gap.
+fn next_run_after<Tz: TimeZone>(spec: &ScheduleSpec, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    match spec {
+        // Checked: a hand-edited store can hold any interval
+        ScheduleSpec::Every { seconds } => {
+            let interval = chrono::Duration::try_seconds(i64::try_from(*seconds).ok()?)?;
+            after.clone().checked_add_signed(interval)
+        }
+        ScheduleSpec::Daily { time } => {
+            let time = parse_time_of_day(time).ok()?;
+            let tz = after.timezone();
+            let mut date = after.date_naive();
+
+            // Today if still ahead, otherwise tomorrow (two days covers DST edge cases)
+            for _ in 0..3 {
+                let naive = date.and_time(time);
+                let candidate = tz
+                    .from_local_datetime(&naive)
+                    .earliest()
+                    .or_else(|| tz.from_local_datetime(&(naive + chrono::Duration::hours(1))).earliest());
+                if let Some(c) = candidate {
+                    if c > *after {
+                        return Some(c);
+                    }
+                }
+                date = date.succ_opt()?;
+            }
+            None
+        }
+    }
+}
+
+// =============================================================================
+// Background Runner
+// =============================================================================
+
+/// Load persisted schedules and start the background runner.
+///
+/// Jobs whose next run passed while the app was closed are not replayed;
+/// they are rescheduled from the current time. Commands wait for the load.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    // Taken before returning, so no command can get in before the load
+    let store = Arc::clone(&app.state::<SchedulerState>().store);
+    let early_guard = store.clone().try_lock_owned();
+    tauri::async_runtime::spawn(async move {
+        let mut guard = match early_guard {
+            Ok(guard) => guard,
+            Err(_) => store.lock_owned().await,
+        };
+        match get_schedules_path(&app) {
+            Some(path) => {
+                let mut loaded = load_store_or_back_up(&path).await;
+                let now = Local::now();
+                for s in loaded.schedules.iter_mut().filter(|s| s.next_run <= now) {
+                    if let Some(next) = next_run_after(&s.spec, &now) {
+                        s.next_run = next;
+                    }
+                }
+                log::info!("Loaded {} schedule(s)", loaded.schedules.len());
+                *guard = loaded;
+            }
+            None => log::error!("Could not determine schedules file path"),
+        }
+        drop(guard);
+
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            run_due_jobs(&app).await;
+        }
+    });
+}
+
+/// Execute every enabled schedule whose next run time has passed.
+///
+/// Each job runs in its own task, so a long script does not hold up the
+/// others. A schedule whose previous run has not finished is skipped.
+async fn run_due_jobs(app: &AppHandle) {
+    let state = app.state::<SchedulerState>();
+    let now = Local::now();
+
+    // Advance due schedules under the lock, execute outside it
+    let due: Vec<Schedule> = {
+        let mut store = state.store.lock().await;
+        let mut running = state.running();
+        let mut due = Vec::new();
+        for s in store
+            .schedules
+            .iter_mut()
+            .filter(|s| s.enabled && s.next_run <= now)
+        {
+            s.last_run = Some(now);
+            s.next_run = next_run_after(&s.spec, &now)
+                .unwrap_or_else(|| now + chrono::Duration::days(1));
+            if running.insert(s.id.clone()) {
+                due.push(s.clone());
+            } else {
+                log::warn!("Schedule '{}' is still running, skipping this run", s.name);
+            }
+        }
+        due
+    };
+
+    for job in due {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            log::info!("Running schedule '{}' ({})", job.name, job.id);
+            let result = execute_action(&app, &job.action).await;
+            if let Err(ref e) = result {
+                log::warn!("Schedule '{}' failed: {}", job.name, e);
+            }
+            let run = ScheduleRun {
+                schedule_id: job.id,
+                schedule_name: job.name,
+                started_at: now,
+                success: result.is_ok(),
+                message: result.unwrap_or_else(|e| e.to_string()),
+            };
+            record_run(&app, run).await;
+        });
+    }
+}
+
+/// Add a finished run to the history and persist the store, which also
+/// saves the advanced `next_run` times.
+async fn record_run(app: &AppHandle, run: ScheduleRun) {
+    let state = app.state::<SchedulerState>();
+    state.running().remove(&run.schedule_id);
+
+    let mut store = state.store.lock().await;
+    store.history.push(run);
+    let overflow = store.history.len().saturating_sub(MAX_HISTORY_ENTRIES);
+    store.history.drain(..overflow);
+
+    if let Err(e) = save_store(app, &store).await {
+        log::error!("Failed to persist schedules: {}", e);
+    }
+}
+
+/// Perform a schedule's action, returning a short human-readable result.
+async fn execute_action(app: &AppHandle, action: &ScheduleAction) -> Result<String, AppError> {
+    match action {
+        ScheduleAction::RunCommand {
+            session_id,
+            command,
+        } => {
+            log::info!(
+                "Scheduled injection into session {}: {}",
+                session_id,
+                command
+            );
+            let pty_state = app.state::<pty::PtyState>();
+            pty::inject_into_session(pty_state.inner(), session_id, command, None)?;
+            Ok(format!("Ran `{}` in session {}", command, session_id))
+        }
+        ScheduleAction::StopStream => {
+            let stream_state = app.state::<streaming::StreamingState>();
+            streaming::stop_stream(stream_state.inner()).await?;
+            Ok("Stream stopped".to_string())
+        }
+        ScheduleAction::RunScript { path } => {
+            let source = scripting::read_script(Path::new(path)).await?;
+            let run = scripting::run(app, path.clone(), source, scripting::DEFAULT_TIMEOUT).await;
+            match run.error {
+                None => Ok(format!("Ran script {}", path)),
+                Some(e) => Err(AppError::Internal(format!(
+                    "Script {} failed: {}",
+                    path,
+                    e.lines().next().unwrap_or_default()
+                ))),
+            }
+        }
+    }
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Register a new schedule.
+///
+/// # Arguments
+/// * `name` - Human-readable label shown in the UI
+/// * `spec` - When to run (`{type: "every", seconds}` or `{type: "daily", time: "HH:MM"}`)
+/// * `action` - What to run (`{type: "run_command", session_id, command}`,
+///   `{type: "stop_stream"}` or `{type: "run_script", path}`)
+/// * `enabled` - Whether the schedule is active (default: true)
+#[tauri::command]
+pub async fn add_schedule(
+    app: AppHandle,
+    state: State<'_, SchedulerState>,
+    name: String,
+    spec: ScheduleSpec,
+    action: ScheduleAction,
+    enabled: Option<bool>,
+) -> Result<Schedule, AppError> {
+    validate_spec(&spec)?;
+    validate_action(&action)?;
+
+    if name.trim().is_empty() {
+        return Err(AppError::Validation("Schedule name must not be empty".into()));
+    }
+
+    let now = Local::now();
+    let next_run = next_run_after(&spec, &now)
+        .ok_or_else(|| AppError::Internal("Could not compute next run time".into()))?;
+
+    let schedule = Schedule {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        spec,
+        action,
+        enabled: enabled.unwrap_or(true),
+        created_at: now,
+        last_run: None,
+        next_run,
+    };
+
+    let mut store = state.store.lock().await;
+    store.schedules.push(schedule.clone());
+    save_store(&app, &store).await?;
+
+    log::info!(
+        "Added schedule '{}' ({}), next run at {}",
+        schedule.name,
+        schedule.id,
+        schedule.next_run.to_rfc3339()
+    );
+
+    Ok(schedule)
+}
+
+/// Registered schedules, for `export_config`.
+pub(crate) async fn export_schedules(state: &SchedulerState) -> Vec<Schedule> {
+    state.store.lock().await.schedules.clone()
+}
+
+/// Add imported schedules (`import_config`) with their next run computed
+/// from now. Schedules whose id exists or whose spec is invalid are skipped
+/// with a note in `skipped`. Returns how many were added.
+pub(crate) async fn import_schedules(
+    app: &AppHandle,
+    state: &SchedulerState,
+    imported: Vec<Schedule>,
+    skipped: &mut Vec<String>,
+) -> Result<usize, AppError> {
+    let now = Local::now();
+    let mut store = state.store.lock().await;
+    let mut added = 0;
+    for mut schedule in imported {
+        if store.schedules.iter().any(|s| s.id == schedule.id) {
+            skipped.push(format!("Schedule '{}': already registered", schedule.name));
+            continue;
+        }
+        if schedule.name.trim().is_empty() {
+            skipped.push("Schedule with an empty name".into());
+            continue;
+        }
+        let Some(next_run) = validate_spec(&schedule.spec)
+            .ok()
+            .and_then(|()| next_run_after(&schedule.spec, &now))
+        else {
+            skipped.push(format!("Schedule '{}': invalid timing", schedule.name));
+            continue;
+        };
+        if let Err(e) = validate_action(&schedule.action) {
+            skipped.push(format!("Schedule '{}': {}", schedule.name, e));
+            continue;
+        }
+        schedule.next_run = next_run;
+        schedule.last_run = None;
+        store.schedules.push(schedule);
+        added += 1;
+    }
+    if added > 0 {
+        save_store(app, &store).await?;
+    }
+    Ok(added)
+}
+
+/// List all registered schedules.
+#[tauri::command]
+pub async fn list_schedules(state: State<'_, SchedulerState>) -> Result<Vec<Schedule>, AppError> {
+    Ok(state.store.lock().await.schedules.clone())
+}
+
+/// Remove a schedule. Its run history is kept.
+#[tauri::command]
+pub async fn remove_schedule(
+    app: AppHandle,
+    state: State<'_, SchedulerState>,
+    id: String,
+) -> Result<(), AppError> {
+    let mut store = state.store.lock().await;
+
+    let before = store.schedules.len();
+    store.schedules.retain(|s| s.id != id);
+    if store.schedules.len() == before {
+        return Err(AppError::NotFound(format!("Schedule not found: {}", id)));
+    }
+
+    save_store(&app, &store).await?;
+    log::info!("Removed schedule {}", id);
+    Ok(())
+}
+
+/// Get schedule run history, most recent first.
+///
+/// # Arguments
+/// * `schedule_id` - Only return runs of this schedule (default: all)
+/// * `limit` - Maximum number of entries to return (default: 100)
+#[tauri::command]
+pub async fn get_schedule_history(
+    state: State<'_, SchedulerState>,
+    schedule_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ScheduleRun>, AppError> {
+    let store = state.store.lock().await;
+
+    Ok(store
+        .history
+        .iter()
+        .rev()
+        .filter(|r| schedule_id.as_ref().is_none_or(|id| &r.schedule_id == id))
+        .take(limit.unwrap_or(100))
+        .cloned()
+        .collect())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_next_run_every() {
+        let spec = ScheduleSpec::Every { seconds: 900 };
+        let next = next_run_after(&spec, &utc("2026-03-01T10:00:00Z")).unwrap();
+        assert_eq!(next, utc("2026-03-01T10:15:00Z"));
+    }
+
+    #[test]
+    fn test_next_run_daily_later_today() {
+        let spec = ScheduleSpec::Daily { time: "18:00".into() };
+        let next = next_run_after(&spec, &utc("2026-03-01T10:00:00Z")).unwrap();
+        assert_eq!(next, utc("2026-03-01T18:00:00Z"));
+    }
+
+    #[test]
+    fn test_next_run_daily_rolls_to_tomorrow() {
+        let spec = ScheduleSpec::Daily { time: "18:00".into() };
+        // Exactly at the scheduled time counts as already run
+        let next = next_run_after(&spec, &utc("2026-03-01T18:00:00Z")).unwrap();
+        assert_eq!(next, utc("2026-03-02T18:00:00Z"));
+    }
+
+    #[test]
+    fn test_next_run_every_out_of_range() {
+        let after = utc("2026-03-01T10:00:00Z");
+        assert!(next_run_after(&ScheduleSpec::Every { seconds: u64::MAX }, &after).is_none());
+        let spec = ScheduleSpec::Every { seconds: i64::MAX as u64 };
+        assert!(next_run_after(&spec, &after).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_store_is_backed_up() {
+        let dir = std::env::temp_dir().join(format!("synthia-sched-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SCHEDULES_FILE_NAME);
+        std::fs::write(&path, "{not json").unwrap();
+
+        let store = load_store_or_back_up(&path).await;
+        assert!(store.schedules.is_empty());
+        assert!(!path.exists());
+        let backup = dir.join("schedules.json.bak");
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), "{not json");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_action() {
+        assert!(validate_action(&ScheduleAction::StopStream).is_ok());
+        let missing = ScheduleAction::RunScript { path: "/nonexistent/job.rhai".into() };
+        assert!(matches!(validate_action(&missing), Err(AppError::NotFound(_))));
+        let dir = ScheduleAction::RunScript {
+            path: std::env::temp_dir().to_string_lossy().into_owned(),
+        };
+        assert!(matches!(validate_action(&dir), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_validate_spec() {
+        assert!(validate_spec(&ScheduleSpec::Every { seconds: 900 }).is_ok());
+        assert!(validate_spec(&ScheduleSpec::Every { seconds: 1 }).is_err());
+        assert!(validate_spec(&ScheduleSpec::Every { seconds: MAX_INTERVAL_SECS }).is_ok());
+        assert!(validate_spec(&ScheduleSpec::Every { seconds: MAX_INTERVAL_SECS + 1 }).is_err());
+        assert!(validate_spec(&ScheduleSpec::Daily { time: "07:30".into() }).is_ok());
+        assert!(validate_spec(&ScheduleSpec::Daily { time: "25:00".into() }).is_err());
+        assert!(validate_spec(&ScheduleSpec::Daily { time: "6pm".into() }).is_err());
+    }
+
+    #[test]
+    fn test_spec_serialization_format() {
+        let spec: ScheduleSpec = serde_json::from_str(r#"{"type":"every","seconds":60}"#).unwrap();
+        assert_eq!(spec, ScheduleSpec::Every { seconds: 60 });
+
+        let action: ScheduleAction = serde_json::from_str(r#"{"type":"stop_stream"}"#).unwrap();
+        assert_eq!(action, ScheduleAction::StopStream);
+
+        let action: ScheduleAction =
+            serde_json::from_str(r#"{"type":"run_script","path":"/tmp/job.rhai"}"#).unwrap();
+        assert_eq!(action, ScheduleAction::RunScript { path: "/tmp/job.rhai".into() });
+    }
+}
@@ -0,0 +1,137 @@
+//! Server-side model of each session's visible screen.
+//!
+//! Every session feeds its raw output into a headless `vt100` emulator kept
+//! at the session's size, so `get_visible_screen` can return what the screen
+//! shows right now, as text with the cursor position. Unlike the transcript,
+//! this follows full-screen programs (vim, htop, less) that redraw in place
+//! and on the alternate screen.
+//!
+//! The model keeps no scrollback; `get_transcript` and
+//! `render_terminal_image` cover history.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::pty::{self, PtyState};
+use crate::AppError;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Headless emulator of one session, fed by its PTY reader.
+pub(crate) struct ScreenModel {
+    parser: Mutex<vt100::Parser>,
+}
+
+/// Result of `get_visible_screen`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VisibleScreen {
+    pub session_id: String,
+    pub rows: u16,
+    pub cols: u16,
+    /// One entry per screen row, trailing blanks removed
+    pub lines: Vec<String>,
+    /// Zero-based cursor position
+    pub cursor_row: u16,
+    pub cursor_col: u16,
+    pub cursor_visible: bool,
+    /// A full-screen program is using the alternate screen
+    pub alternate_screen: bool,
+    /// Window title set by the running program, if any
+    pub title: Option<String>,
+}
+
+impl ScreenModel {
+    pub(crate) fn new(rows: u16, cols: u16) -> Self {
+        Self {
+            parser: Mutex::new(vt100::Parser::new(rows.max(1), cols.max(1), 0)),
+        }
+    }
+
+    fn parser(&self) -> std::sync::MutexGuard<'_, vt100::Parser> {
+        self.parser.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Apply a chunk of raw output. Sequences split across chunks are
+    /// carried over by the parser.
+    pub(crate) fn feed(&self, bytes: &[u8]) {
+        self.parser().process(bytes);
+    }
+
+    /// Follow a resize of the session's PTY.
+    pub(crate) fn resize(&self, rows: u16, cols: u16) {
+        self.parser().set_size(rows.max(1), cols.max(1));
+    }
+
+    fn snapshot(&self, session_id: String) -> VisibleScreen {
+        let parser = self.parser();
+        let screen = parser.screen();
+        let (rows, cols) = screen.size();
+        let (cursor_row, cursor_col) = screen.cursor_position();
+        VisibleScreen {
+            session_id,
+            rows,
+            cols,
+            lines: screen.rows(0, cols).map(|row| row.trim_end().to_string()).collect(),
+            cursor_row,
+            cursor_col,
+            cursor_visible: !screen.hide_cursor(),
+            alternate_screen: screen.alternate_screen(),
+            title: Some(screen.title().to_string()).filter(|t| !t.is_empty()),
+        }
+    }
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// The session's screen as it looks now: one line of text per row, the
+/// cursor position, and whether a full-screen program (alternate screen)
+/// is showing.
+#[tauri::command]
+pub fn get_visible_screen(
+    state: State<'_, PtyState>,
+    session_id: String,
+) -> Result<VisibleScreen, AppError> {
+    let model = pty::session_screen(&state, &session_id)?;
+    Ok(model.snapshot(session_id))
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_follows_redraws() {
+        let model = ScreenModel::new(4, 20);
+        model.feed(b"$ ls\r\nfile.txt\r\n$ ");
+        let screen = model.snapshot("s1".into());
+        assert_eq!(screen.lines, ["$ ls", "file.txt", "$", ""]);
+        assert_eq!((screen.cursor_row, screen.cursor_col), (2, 2));
+        assert!(!screen.alternate_screen);
+
+        // A full-screen program switches to the alternate screen and draws
+        // in place; split escape sequences are carried over between chunks
+        model.feed(b"\x1b[?1049h\x1b[H\x1b[2J\x1b]0;htop\x07CPU 12%\x1b[3;1");
+        model.feed(b"HMem 40%");
+        let screen = model.snapshot("s1".into());
+        assert!(screen.alternate_screen);
+        assert_eq!(screen.lines, ["CPU 12%", "", "Mem 40%", ""]);
+        assert_eq!(screen.title.as_deref(), Some("htop"));
+
+        // Leaving restores the shell screen
+        model.feed(b"\x1b[?1049l");
+        assert_eq!(model.snapshot("s1".into()).lines[1], "file.txt");
+
+        model.resize(10, 40);
+        let screen = model.snapshot("s1".into());
+        assert_eq!((screen.rows, screen.cols), (10, 40));
+    }
+}
@@ -0,0 +1,702 @@
+//! Embedded scripting for automation (`run_script`).
+//!
+//! Scripts are written in [Rhai](https://rhai.rs) and can sequence steps and
+//! branch on output, which a single schedule action or UI click cannot:
+//!
+//! ```text
+//! let s = spawn_terminal("/home/sam/repo");
+//! run(s, "cargo build");
+//! let line = wait_for(s, "Finished|error", 600000);
+//! if line.starts_with("Finished") { start_stream(0, 30, 80); }
+//! ```
+//!
+//! Bindings run the handlers of the Tauri commands of the same name, so
+//! limits, rate limits and session locks apply (a script writes as owner
+//! `script:<run_id>`). Each run gets a fresh engine on a blocking thread,
+//! with operation, nesting and size limits, a timeout and `cancel_script`.
+//! There is no file, network or module access other than through the
+//! bindings. `print` and `debug` lines are collected in the run's `output`
+//! and logged with target `script:<run_id>`.
+//!
+//! Scripts can also run on a timer through the scheduler's `run_script`
+//! action.
+
+use chrono::{DateTime, Local};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::{pty, streaming, AppError};
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Timeout of a run unless `timeout_secs` says otherwise
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Longest allowed `timeout_secs`
+const MAX_TIMEOUT_SECS: u64 = 3600;
+
+/// Largest script accepted, inline or from a file
+const MAX_SCRIPT_BYTES: u64 = 1024 * 1024;
+
+/// `print`/`debug` output kept per run; later lines are dropped
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// Finished runs kept for `list_script_runs`
+const MAX_RUNS: usize = 50;
+
+/// Engine limits: operations per run, call depth, expression nesting, and
+/// the size of strings, arrays and maps a script can build
+const MAX_OPERATIONS: u64 = 100_000_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_STRING_BYTES: usize = 1024 * 1024;
+const MAX_COLLECTION_LEN: usize = 100_000;
+
+/// How often blocking bindings (`sleep`, `wait_for`) check for cancellation
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Label of runs started with `source` instead of `path`
+const INLINE_LABEL: &str = "inline";
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// A script run, returned by `run_script` and `list_script_runs`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptRun {
+    pub id: String,
+    /// Script path, or `inline` for `source`
+    pub source_label: String,
+    pub started_at: DateTime<Local>,
+    /// None while the script is running
+    pub finished_at: Option<DateTime<Local>>,
+    pub success: bool,
+    /// `print` and `debug` lines, at most 64 KiB
+    pub output: String,
+    pub error: Option<String>,
+}
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Cancellation and deadline of one run, checked by the engine between
+/// operations and by blocking bindings while they wait.
+struct RunControl {
+    cancelled: AtomicBool,
+    deadline: Instant,
+}
+
+impl RunControl {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            cancelled: AtomicBool::new(false),
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    /// Why the run has to stop, if it has to
+    fn stop_reason(&self) -> Option<&'static str> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            Some("cancelled")
+        } else if Instant::now() >= self.deadline {
+            Some("timed out")
+        } else {
+            None
+        }
+    }
+}
+
+/// Collected `print`/`debug` output of a run.
+#[derive(Default)]
+struct ScriptOutput {
+    text: String,
+    truncated: bool,
+}
+
+impl ScriptOutput {
+    fn push_line(&mut self, line: &str) {
+        if self.truncated {
+            return;
+        }
+        if self.text.len() + line.len() + 1 > MAX_OUTPUT_BYTES {
+            self.truncated = true;
+            self.text.push_str("[output truncated]\n");
+            return;
+        }
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+}
+
+struct RunEntry {
+    run: ScriptRun,
+    control: Arc<RunControl>,
+}
+
+/// Shared state managed by Tauri.
+#[derive(Default)]
+pub struct ScriptingState {
+    /// Oldest first; running scripts are never dropped
+    runs: Mutex<VecDeque<RunEntry>>,
+}
+
+impl ScriptingState {
+    fn runs(&self) -> MutexGuard<'_, VecDeque<RunEntry>> {
+        // Runs hold plain data, so a poisoned lock is still usable
+        self.runs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn start(&self, run: ScriptRun, control: Arc<RunControl>) {
+        let mut runs = self.runs();
+        runs.push_back(RunEntry { run, control });
+        while runs.len() > MAX_RUNS {
+            match runs.iter().position(|e| e.run.finished_at.is_some()) {
+                Some(oldest) => runs.remove(oldest),
+                None => break,
+            };
+        }
+    }
+
+    fn finish(&self, id: &str, output: String, error: Option<String>) -> Option<ScriptRun> {
+        let mut runs = self.runs();
+        let entry = runs.iter_mut().find(|e| e.run.id == id)?;
+        entry.run.finished_at = Some(Local::now());
+        entry.run.success = error.is_none();
+        entry.run.output = output;
+        entry.run.error = error;
+        Some(entry.run.clone())
+    }
+}
+
+// =============================================================================
+// Engine
+// =============================================================================
+
+fn script_error(e: AppError) -> Box<EvalAltResult> {
+    e.to_string().into()
+}
+
+fn stopped_error(reason: &str) -> Box<EvalAltResult> {
+    format!("Script {}", reason).into()
+}
+
+/// Convert an integer argument, e.g. a port or timeout.
+fn int_arg<T: TryFrom<i64>>(name: &str, value: i64) -> ScriptResult<T> {
+    T::try_from(value).map_err(|_| format!("Invalid {}: {}", name, value).into())
+}
+
+/// Wait for an async handler on the script's thread, giving up when the run
+/// is cancelled or times out.
+fn block_on<T>(
+    control: &RunControl,
+    future: impl Future<Output = Result<T, AppError>>,
+) -> ScriptResult<T> {
+    tauri::async_runtime::block_on(async {
+        tokio::select! {
+            result = future => result.map_err(script_error),
+            reason = until_stopped(control) => Err(stopped_error(reason)),
+        }
+    })
+}
+
+async fn until_stopped(control: &RunControl) -> &'static str {
+    loop {
+        if let Some(reason) = control.stop_reason() {
+            return reason;
+        }
+        tokio::time::sleep(STOP_POLL_INTERVAL).await;
+    }
+}
+
+/// An engine with limits, output capture, stop checks and `sleep`, but no
+/// app bindings. `import` is refused, as it would read files.
+fn base_engine(
+    run_id: &str,
+    control: &Arc<RunControl>,
+    output: &Arc<Mutex<ScriptOutput>>,
+) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH)
+        .set_max_string_size(MAX_STRING_BYTES)
+        .set_max_array_size(MAX_COLLECTION_LEN)
+        .set_max_map_size(MAX_COLLECTION_LEN);
+
+    let progress = Arc::clone(control);
+    engine.on_progress(move |_| progress.stop_reason().map(Into::into));
+
+    let target = format!("script:{}", run_id);
+    let print_output = Arc::clone(output);
+    let print_target = target.clone();
+    engine.on_print(move |line| {
+        log::info!(target: print_target.as_str(), "{}", line);
+        print_output.lock().unwrap_or_else(|e| e.into_inner()).push_line(line);
+    });
+    let debug_output = Arc::clone(output);
+    engine.on_debug(move |line, _, pos| {
+        log::debug!(target: target.as_str(), "{} ({})", line, pos);
+        debug_output.lock().unwrap_or_else(|e| e.into_inner()).push_line(line);
+    });
+
+    let sleep_control = Arc::clone(control);
+    engine.register_fn("sleep", move |ms: i64| -> ScriptResult<()> {
+        let ms: u64 = int_arg("sleep duration", ms)?;
+        block_on(&sleep_control, async {
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Ok(())
+        })
+    });
+    engine
+}
+
+/// What a script can do to the app, through the Tauri command handlers.
+#[derive(Clone)]
+struct Bindings {
+    app: AppHandle,
+    /// Session lock owner and rate limit key of the run
+    owner: String,
+    control: Arc<RunControl>,
+}
+
+impl Bindings {
+    fn spawn_terminal(&self, cwd: Option<&str>, shell: Option<&str>) -> ScriptResult<String> {
+        let app = &self.app;
+        block_on(
+            &self.control,
+            pty::spawn_terminal(
+                app.clone(),
+                app.state(),
+                app.state(),
+                None,
+                cwd.map(str::to_string),
+                shell.map(str::to_string),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+        )
+    }
+
+    fn run(&self, session_id: &str, command: &str) -> ScriptResult<()> {
+        let app = &self.app;
+        pty::inject_command(
+            app.state(),
+            app.state(),
+            session_id.to_string(),
+            command.to_string(),
+            Some(self.owner.clone()),
+        )
+        .map_err(script_error)
+    }
+
+    fn wait_for(&self, session_id: &str, pattern: &str, timeout_ms: i64) -> ScriptResult<String> {
+        let timeout_ms = int_arg("timeout", timeout_ms)?;
+        let found = block_on(
+            &self.control,
+            pty::wait_for_output(
+                self.app.state(),
+                session_id.to_string(),
+                pattern.to_string(),
+                timeout_ms,
+            ),
+        )?;
+        Ok(found.matched)
+    }
+
+    fn transcript(&self, session_id: &str, max_chars: i64) -> ScriptResult<String> {
+        let max_chars = int_arg("max_chars", max_chars)?;
+        pty::get_transcript(self.app.state(), session_id.to_string(), Some(max_chars), None)
+            .map(|slice| slice.text)
+            .map_err(script_error)
+    }
+
+    fn kill_terminal(&self, session_id: &str) -> ScriptResult<()> {
+        pty::kill_terminal(self.app.clone(), self.app.state(), session_id.to_string())
+            .map_err(script_error)
+    }
+
+    fn stats(&self) -> ScriptResult<Dynamic> {
+        rhai::serde::to_dynamic(block_on(&self.control, crate::get_system_stats())?)
+    }
+
+    fn start_stream(&self, port: i64, fps: i64, quality: i64) -> ScriptResult<Dynamic> {
+        let app = &self.app;
+        let status = block_on(
+            &self.control,
+            streaming::start_local_stream(
+                app.clone(),
+                app.state(),
+                int_arg("port", port)?,
+                int_arg("quality", quality)?,
+                int_arg("fps", fps)?,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ),
+        )?;
+        rhai::serde::to_dynamic(status)
+    }
+
+    fn stop_stream(&self) -> ScriptResult<()> {
+        block_on(&self.control, streaming::stop_local_stream(self.app.state()))
+    }
+
+    fn stream_status(&self) -> ScriptResult<Dynamic> {
+        let status = block_on(&self.control, streaming::get_stream_status(self.app.state()))?;
+        rhai::serde::to_dynamic(status)
+    }
+}
+
+/// Register the app bindings (see the module docs) on `engine`.
+fn register_bindings(engine: &mut Engine, bindings: Bindings) {
+    let b = bindings.clone();
+    engine.register_fn("spawn_terminal", move || b.spawn_terminal(None, None));
+    let b = bindings.clone();
+    engine.register_fn("spawn_terminal", move |cwd: &str| b.spawn_terminal(Some(cwd), None));
+    let b = bindings.clone();
+    engine.register_fn("spawn_terminal", move |cwd: &str, shell: &str| {
+        b.spawn_terminal(Some(cwd), Some(shell))
+    });
+    let b = bindings.clone();
+    engine.register_fn("run", move |session: &str, command: &str| b.run(session, command));
+    let b = bindings.clone();
+    engine.register_fn("wait_for", move |session: &str, pattern: &str, timeout_ms: i64| {
+        b.wait_for(session, pattern, timeout_ms)
+    });
+    let b = bindings.clone();
+    engine.register_fn("transcript", move |session: &str, max_chars: i64| {
+        b.transcript(session, max_chars)
+    });
+    let b = bindings.clone();
+    engine.register_fn("kill_terminal", move |session: &str| b.kill_terminal(session));
+    let b = bindings.clone();
+    engine.register_fn("stats", move || b.stats());
+    let b = bindings.clone();
+    engine.register_fn("start_stream", move |port: i64, fps: i64, quality: i64| {
+        b.start_stream(port, fps, quality)
+    });
+    let b = bindings.clone();
+    engine.register_fn("stop_stream", move || b.stop_stream());
+    engine.register_fn("stream_status", move || bindings.stream_status());
+}
+
+/// Compile and run `source`, returning the error message on failure.
+fn execute(engine: &Engine, source: &str) -> Result<(), String> {
+    let ast = engine.compile(source).map_err(|e| format!("Syntax error: {}", e))?;
+    engine.run_ast(&ast).map_err(|e| match *e {
+        EvalAltResult::ErrorTerminated(reason, _) => format!("Script {}", reason),
+        e => e.to_string(),
+    })
+}
+
+// =============================================================================
+// Running
+// =============================================================================
+
+/// Read a script file, refusing anything but a regular file of at most
+/// `MAX_SCRIPT_BYTES`.
+pub(crate) async fn read_script(path: &Path) -> Result<String, AppError> {
+    check_script_path(path)?;
+    let meta = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to read script {}: {}", path.display(), e)))?;
+    if meta.len() > MAX_SCRIPT_BYTES {
+        return Err(AppError::Validation(format!(
+            "Script {} is larger than {} bytes",
+            path.display(),
+            MAX_SCRIPT_BYTES
+        )));
+    }
+    tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to read script {}: {}", path.display(), e)))
+}
+
+/// Check that a script path exists and is a file.
+pub(crate) fn check_script_path(path: &Path) -> Result<(), AppError> {
+    if !path.exists() {
+        return Err(AppError::NotFound(format!("Script not found: {}", path.display())));
+    }
+    if !path.is_file() {
+        return Err(AppError::Validation(format!("Script is not a file: {}", path.display())));
+    }
+    Ok(())
+}
+
+/// Run a script to completion on a blocking thread and record the run.
+/// Shared by `run_script` and the scheduler.
+pub(crate) async fn run(
+    app: &AppHandle,
+    source_label: String,
+    source: String,
+    timeout: Duration,
+) -> ScriptRun {
+    let state = app.state::<ScriptingState>();
+    let id = uuid::Uuid::new_v4().to_string();
+    let control = Arc::new(RunControl::new(timeout));
+    let started = ScriptRun {
+        id: id.clone(),
+        source_label,
+        started_at: Local::now(),
+        finished_at: None,
+        success: false,
+        output: String::new(),
+        error: None,
+    };
+    log::info!("Running script {} ({})", started.source_label, id);
+    state.start(started.clone(), Arc::clone(&control));
+
+    let output = Arc::new(Mutex::new(ScriptOutput::default()));
+    let bindings = Bindings {
+        app: app.clone(),
+        owner: format!("script:{}", id),
+        control: Arc::clone(&control),
+    };
+    let task_id = id.clone();
+    let task_output = Arc::clone(&output);
+    // The engine is not `Send`, so it is built on the thread running it
+    let result = tokio::task::spawn_blocking(move || {
+        let mut engine = base_engine(&task_id, &control, &task_output);
+        register_bindings(&mut engine, bindings);
+        execute(&engine, &source)
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Script task failed: {}", e)));
+
+    let output = std::mem::take(&mut output.lock().unwrap_or_else(|e| e.into_inner()).text);
+    match &result {
+        Ok(()) => log::info!("Script {} finished", id),
+        Err(e) => log::warn!("Script {} failed: {}", id, e),
+    }
+    let error = result.err();
+    // Only missing if the run was dropped, which never happens while it runs
+    state.finish(&id, output.clone(), error.clone()).unwrap_or(ScriptRun {
+        finished_at: Some(Local::now()),
+        success: error.is_none(),
+        output,
+        error,
+        ..started
+    })
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Run a Rhai script and wait for it to finish.
+///
+/// # Arguments
+/// * `path` / `source` - Exactly one: a script file, or the script itself
+/// * `timeout_secs` - Stop the script after this long, 1-3600 (default: 300)
+///
+/// A script that fails is still a run: the result has `success` false and
+/// `error` set. While running, the script is listed by `list_script_runs`
+/// and can be stopped with `cancel_script`.
+#[tauri::command]
+pub async fn run_script(
+    app: AppHandle,
+    path: Option<String>,
+    source: Option<String>,
+    timeout_secs: Option<u64>,
+) -> Result<ScriptRun, AppError> {
+    let timeout_secs = timeout_secs.unwrap_or(DEFAULT_TIMEOUT.as_secs());
+    if !(1..=MAX_TIMEOUT_SECS).contains(&timeout_secs) {
+        return Err(AppError::Validation(format!(
+            "Timeout must be 1-{} seconds, got: {}",
+            MAX_TIMEOUT_SECS, timeout_secs
+        )));
+    }
+    let (label, source) = match (path, source) {
+        (Some(path), None) => {
+            let source = read_script(Path::new(&path)).await?;
+            (path, source)
+        }
+        (None, Some(source)) => {
+            if source.len() as u64 > MAX_SCRIPT_BYTES {
+                return Err(AppError::Validation(format!(
+                    "Script is larger than {} bytes",
+                    MAX_SCRIPT_BYTES
+                )));
+            }
+            (INLINE_LABEL.to_string(), source)
+        }
+        _ => return Err(AppError::Validation("Pass exactly one of path and source".into())),
+    };
+    Ok(run(&app, label, source, Duration::from_secs(timeout_secs)).await)
+}
+
+/// Stop a running script. It ends with the error `Script cancelled`.
+#[tauri::command]
+pub fn cancel_script(state: State<'_, ScriptingState>, run_id: String) -> Result<(), AppError> {
+    let runs = state.runs();
+    let entry = runs
+        .iter()
+        .find(|e| e.run.id == run_id && e.run.finished_at.is_none())
+        .ok_or_else(|| AppError::NotFound(format!("No running script: {}", run_id)))?;
+    entry.control.cancelled.store(true, Ordering::Relaxed);
+    log::info!("Cancelling script {}", run_id);
+    Ok(())
+}
+
+/// Recent script runs, running ones included, most recent first.
+///
+/// # Arguments
+/// * `limit` - Maximum number of runs to return (default: all, at most 50
+///   finished runs are kept)
+#[tauri::command]
+pub fn list_script_runs(
+    state: State<'_, ScriptingState>,
+    limit: Option<usize>,
+) -> Vec<ScriptRun> {
+    state
+        .runs()
+        .iter()
+        .rev()
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|e| e.run.clone())
+        .collect()
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(timeout: Duration) -> (Engine, Arc<RunControl>, Arc<Mutex<ScriptOutput>>) {
+        let control = Arc::new(RunControl::new(timeout));
+        let output = Arc::new(Mutex::new(ScriptOutput::default()));
+        (base_engine("test", &control, &output), control, output)
+    }
+
+    fn run_entry(id: &str, finished: bool) -> (ScriptRun, Arc<RunControl>) {
+        let run = ScriptRun {
+            id: id.into(),
+            source_label: INLINE_LABEL.into(),
+            started_at: Local::now(),
+            finished_at: finished.then(Local::now),
+            success: finished,
+            output: String::new(),
+            error: None,
+        };
+        (run, Arc::new(RunControl::new(DEFAULT_TIMEOUT)))
+    }
+
+    #[test]
+    fn test_print_is_collected() {
+        let (engine, _, output) = engine(DEFAULT_TIMEOUT);
+        execute(&engine, r#"print("a"); let x = 40 + 2; debug(x); print(`x=${x}`);"#).unwrap();
+        assert_eq!(output.lock().unwrap().text, "a\n42\nx=42\n");
+    }
+
+    #[test]
+    fn test_output_is_capped() {
+        let mut output = ScriptOutput::default();
+        let line = "x".repeat(1000);
+        for _ in 0..100 {
+            output.push_line(&line);
+        }
+        assert!(output.text.len() <= MAX_OUTPUT_BYTES);
+        assert!(output.text.ends_with("[output truncated]\n"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let (engine, _, _) = engine(DEFAULT_TIMEOUT);
+        assert!(execute(&engine, "let x = ;").unwrap_err().starts_with("Syntax error"));
+        let err = execute(&engine, r#"throw "boom";"#).unwrap_err();
+        assert!(err.contains("boom"), "{}", err);
+        // No module access
+        assert!(execute(&engine, r#"import "other" as o;"#).is_err());
+    }
+
+    #[test]
+    fn test_timeout_stops_a_loop() {
+        let (engine, _, _) = engine(Duration::from_millis(50));
+        assert_eq!(execute(&engine, "loop { }").unwrap_err(), "Script timed out");
+    }
+
+    #[test]
+    fn test_cancel_stops_sleep() {
+        let (engine, control, _) = engine(DEFAULT_TIMEOUT);
+        let canceller = Arc::clone(&control);
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            canceller.cancelled.store(true, Ordering::Relaxed);
+        });
+        let started = Instant::now();
+        // Catching the error does not help; the next operation stops the run
+        let err = execute(&engine, "try { sleep(60000); } catch { } loop { }").unwrap_err();
+        assert_eq!(err, "Script cancelled");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_sleep_rejects_negative() {
+        let (engine, _, _) = engine(DEFAULT_TIMEOUT);
+        let err = execute(&engine, "sleep(-1);").unwrap_err();
+        assert!(err.contains("Invalid sleep duration"), "{}", err);
+    }
+
+    #[test]
+    fn test_finished_runs_are_trimmed() {
+        let state = ScriptingState::default();
+        let (running, control) = run_entry("running", false);
+        state.start(running, control);
+        for i in 0..MAX_RUNS + 5 {
+            let (run, control) = run_entry(&i.to_string(), true);
+            state.start(run, control);
+        }
+        let runs = state.runs();
+        assert_eq!(runs.len(), MAX_RUNS);
+        // The running script stays, the oldest finished ones go
+        assert_eq!(runs[0].run.id, "running");
+        assert_eq!(runs[1].run.id, "6");
+    }
+
+    #[test]
+    fn test_finish_records_the_result() {
+        let state = ScriptingState::default();
+        let (run, control) = run_entry("a", false);
+        state.start(run, control);
+        let run = state.finish("a", "out\n".into(), Some("boom".into())).unwrap();
+        assert!(run.finished_at.is_some());
+        assert!(!run.success);
+        assert_eq!((run.output.as_str(), run.error.as_deref()), ("out\n", Some("boom")));
+        assert!(state.finish("missing", String::new(), None).is_none());
+    }
+
+    #[test]
+    fn test_check_script_path() {
+        let dir = std::env::temp_dir().join(format!("synthia-script-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("job.rhai");
+        std::fs::write(&file, "print(1);").unwrap();
+        assert!(check_script_path(&file).is_ok());
+        assert!(matches!(check_script_path(&dir), Err(AppError::Validation(_))));
+        assert!(matches!(check_script_path(&dir.join("nope.rhai")), Err(AppError::NotFound(_))));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+//! Secure credential storage backed by the OS keychain.
+//!
+//! Secrets (SSH passphrases, stream tokens, API keys) are stored in the
+//! macOS Keychain, Windows Credential Manager, or the Secret Service on
+//! Linux — never in `settings.json`. Values are never logged.
+
+use keyring::Entry;
+
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Keychain service name all Synthia secrets are stored under
+const SECRETS_SERVICE: &str = "com.sameehshkeer.synthia";
+
+/// Maximum secret key length
+const MAX_KEY_LEN: usize = 128;
+
+/// Maximum secret value size (keychains reject very large items)
+const MAX_VALUE_BYTES: usize = 8 * 1024;
+
+// =============================================================================
+// Keychain Access
+// =============================================================================
+
+/// Keys are namespaced identifiers like `ssh/prod-box` or `stream.token`.
+fn validate_key(key: &str) -> Result<(), AppError> {
+    let valid_chars = key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/' | ':'));
+
+    if key.is_empty() || key.len() > MAX_KEY_LEN || !valid_chars {
+        return Err(AppError::Validation(format!(
+            "Secret key must be 1-{} characters of A-Z, a-z, 0-9, '.', '_', '-', '/', ':'",
+            MAX_KEY_LEN
+        )));
+    }
+    Ok(())
+}
+
+fn entry(key: &str) -> Result<Entry, AppError> {
+    Entry::new(SECRETS_SERVICE, key)
+        .map_err(|e| AppError::Internal(format!("Failed to open keychain entry: {}", e)))
+}
+
+/// Read a secret (blocking). Returns None if it does not exist.
+///
+/// Exposed to backend subsystems that need credentials without a round trip
+/// through the frontend.
+pub(crate) fn read_secret(key: &str) -> Result<Option<String>, AppError> {
+    validate_key(key)?;
+    match entry(key)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(AppError::Internal(format!("Failed to read secret: {}", e))),
+    }
+}
+
+/// Run a blocking keychain operation off the async runtime (Secret Service
+/// and Keychain calls may wait on an unlock prompt).
+async fn run_blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Internal(format!("Keychain task failed: {}", e)))?
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Store (or overwrite) a secret in the OS keychain.
+#[tauri::command]
+pub async fn set_secret(key: String, value: String) -> Result<(), AppError> {
+    validate_key(&key)?;
+    if value.len() > MAX_VALUE_BYTES {
+        return Err(AppError::Validation(format!(
+            "Secret value must be at most {} bytes",
+            MAX_VALUE_BYTES
+        )));
+    }
+
+    run_blocking(move || {
+        entry(&key)?
+            .set_password(&value)
+            .map_err(|e| AppError::Internal(format!("Failed to store secret: {}", e)))?;
+        log::info!("Stored secret: {}", key);
+        Ok(())
+    })
+    .await
+}
+
+/// Read a secret from the OS keychain. Returns null if it does not exist.
+#[tauri::command]
+pub async fn get_secret(key: String) -> Result<Option<String>, AppError> {
+    log::debug!("Reading secret: {}", key);
+    run_blocking(move || read_secret(&key)).await
+}
+
+/// Delete a secret from the OS keychain.
+#[tauri::command]
+pub async fn delete_secret(key: String) -> Result<(), AppError> {
+    validate_key(&key)?;
+
+    run_blocking(move || match entry(&key)?.delete_credential() {
+        Ok(()) => {
+            log::info!("Deleted secret: {}", key);
+            Ok(())
+        }
+        Err(keyring::Error::NoEntry) => Err(AppError::NotFound(format!("Secret not found: {}", key))),
+        Err(e) => Err(AppError::Internal(format!("Failed to delete secret: {}", e))),
+    })
+    .await
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_key() {
+        assert!(validate_key("ssh/prod-box").is_ok());
+        assert!(validate_key("stream.token").is_ok());
+        assert!(validate_key("api:openai_key").is_ok());
+
+        assert!(validate_key("").is_err());
+        assert!(validate_key("has space").is_err());
+        assert!(validate_key("emoji🔑").is_err());
+        assert!(validate_key(&"k".repeat(MAX_KEY_LEN + 1)).is_err());
+    }
+}
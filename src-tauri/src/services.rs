@@ -0,0 +1,786 @@
+//! Managed background services.
+//!
+//! Long-running dev processes (a dev server, a database, a file watcher) are
+//! defined once and started/stopped by id, separately from interactive
+//! terminals: there is no PTY, stdout/stderr are captured line by line into a
+//! per-service buffer, and a restart policy can bring crashed services back.
+//! Definitions are persisted to `services.json` in the app data directory;
+//! running processes are not, and are stopped when the app exits.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{watch, Mutex};
+
+use crate::events;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// File name of the service definitions inside the app data directory
+const SERVICES_FILE_NAME: &str = "services.json";
+
+/// Output lines retained per service (oldest are dropped first)
+const MAX_LOG_LINES: usize = 2000;
+
+/// Longer output lines are truncated to this many characters
+const MAX_LINE_CHARS: usize = 4096;
+
+/// Lines returned by `get_service_logs` when `limit` is omitted
+const DEFAULT_LOG_LIMIT: usize = 200;
+
+/// Delay before the first restart; doubles on each consecutive crash
+const RESTART_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+/// A run at least this long counts as healthy and resets the backoff
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// How long a stopped service gets to exit after SIGTERM before SIGKILL
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// What happens when a service's process exits on its own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    /// Leave it exited
+    #[default]
+    Never,
+    /// Restart only after a non-zero exit or a signal
+    OnFailure,
+    /// Restart after any exit
+    Always,
+}
+
+/// A persisted service definition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceDef {
+    pub id: String,
+    pub name: String,
+    /// Command line, run through the user's login shell
+    pub command: String,
+    /// Working directory (defaults to $HOME)
+    pub cwd: Option<String>,
+    /// Extra environment variables on top of Synthia's environment
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    pub created_at: DateTime<Local>,
+}
+
+/// Lifecycle state of a service.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceState {
+    /// Never started, or stopped by the user
+    #[default]
+    Stopped,
+    Running,
+    /// Exited and waiting out the restart backoff
+    Restarting,
+    /// Exited on its own (or failed to spawn) and will not be restarted
+    Exited,
+}
+
+/// A service definition with its current runtime state.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceStatus {
+    pub service: ServiceDef,
+    pub state: ServiceState,
+    pub pid: Option<u32>,
+    /// When the current (or last) process was started
+    pub started_at: Option<DateTime<Local>>,
+    /// Automatic restarts since the service was last started by the user
+    pub restarts: u32,
+    /// Exit code of the last process; None if killed by a signal or still running
+    pub exit_code: Option<i32>,
+    /// Spawn failure message, if the last start failed
+    pub last_error: Option<String>,
+}
+
+/// Which stream a captured line came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+    /// Lifecycle messages written by Synthia (started, exited, restarting)
+    System,
+}
+
+/// One captured output line.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceLogLine {
+    pub ts: DateTime<Local>,
+    pub stream: LogStream,
+    pub line: String,
+}
+
+/// Payload of the `service-log` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceLogEvent {
+    pub service_id: String,
+    pub line: ServiceLogLine,
+}
+
+/// Runtime book-keeping for a service that has been started at least once.
+#[derive(Default)]
+struct ServiceRuntime {
+    state: ServiceState,
+    pid: Option<u32>,
+    started_at: Option<DateTime<Local>>,
+    restarts: u32,
+    exit_code: Option<i32>,
+    last_error: Option<String>,
+    logs: VecDeque<ServiceLogLine>,
+    /// Fired by `stop_service` to make the supervisor task stop the process
+    stop_tx: Option<watch::Sender<bool>>,
+}
+
+impl ServiceRuntime {
+    fn push_log(&mut self, stream: LogStream, line: &str) -> ServiceLogLine {
+        let entry = ServiceLogLine {
+            ts: Local::now(),
+            stream,
+            line: line.chars().take(MAX_LINE_CHARS).collect(),
+        };
+        self.logs.push_back(entry.clone());
+        if self.logs.len() > MAX_LOG_LINES {
+            self.logs.pop_front();
+        }
+        entry
+    }
+}
+
+type Runtimes = Arc<std::sync::Mutex<HashMap<String, ServiceRuntime>>>;
+
+/// Shared state managed by Tauri.
+///
+/// Runtimes use a std Mutex because they are only held for short, non-async
+/// sections (one update per output line).
+#[derive(Default)]
+pub struct ServicesState {
+    defs: Mutex<Vec<ServiceDef>>,
+    runtimes: Runtimes,
+}
+
+fn lock_runtimes(runtimes: &Runtimes) -> std::sync::MutexGuard<'_, HashMap<String, ServiceRuntime>> {
+    // Runtimes hold plain data, so a poisoned lock is still usable
+    runtimes.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn status_of(def: &ServiceDef, runtimes: &HashMap<String, ServiceRuntime>) -> ServiceStatus {
+    let rt = runtimes.get(&def.id);
+    ServiceStatus {
+        service: def.clone(),
+        state: rt.map(|r| r.state).unwrap_or_default(),
+        pid: rt.and_then(|r| r.pid),
+        started_at: rt.and_then(|r| r.started_at),
+        restarts: rt.map(|r| r.restarts).unwrap_or(0),
+        exit_code: rt.and_then(|r| r.exit_code),
+        last_error: rt.and_then(|r| r.last_error.clone()),
+    }
+}
+
+// =============================================================================
+// Service Store Location
+// =============================================================================
+
+/// Get the path to the persisted service definitions.
+fn get_services_path(app: &AppHandle) -> Option<PathBuf> {
+    let data_dir = app.path().app_data_dir().ok()?;
+    Some(data_dir.join(SERVICES_FILE_NAME))
+}
+
+/// Write the definitions to disk, creating the data directory if needed.
+async fn save_defs(app: &AppHandle, defs: &[ServiceDef]) -> Result<(), AppError> {
+    let path = get_services_path(app)
+        .ok_or_else(|| AppError::Internal("Could not determine services file path".into()))?;
+
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| AppError::Io(format!("Failed to create data directory: {}", e)))?;
+    }
+
+    let json = serde_json::to_string_pretty(defs)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize services: {}", e)))?;
+
+    tokio::fs::write(&path, json)
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to write services file: {}", e)))
+}
+
+/// Read the definitions from disk. A missing file yields no services.
+async fn load_defs(app: &AppHandle) -> Result<Vec<ServiceDef>, AppError> {
+    let path = get_services_path(app)
+        .ok_or_else(|| AppError::Internal("Could not determine services file path".into()))?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to read services file: {}", e)))?;
+
+    serde_json::from_str(&json)
+        .map_err(|e| AppError::Internal(format!("Failed to parse services file: {}", e)))
+}
+
+/// Load persisted service definitions. Services are never auto-started.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match load_defs(&app).await {
+            Ok(defs) => {
+                log::info!("Loaded {} service definition(s)", defs.len());
+                *app.state::<ServicesState>().defs.lock().await = defs;
+            }
+            Err(e) => log::error!("Failed to load services: {}", e),
+        }
+    });
+}
+
+// =============================================================================
+// Process Supervision
+// =============================================================================
+
+/// Whether a process that exited with `code` (None = killed by a signal)
+/// should be restarted under `policy`.
+fn should_restart(policy: RestartPolicy, code: Option<i32>) -> bool {
+    match policy {
+        RestartPolicy::Never => false,
+        RestartPolicy::OnFailure => code != Some(0),
+        RestartPolicy::Always => true,
+    }
+}
+
+/// Delay before the next restart, given the previous delay and how long
+/// the process ran.
+fn next_backoff(previous: Duration, ran_for: Duration) -> Duration {
+    if ran_for >= STABLE_RUN {
+        RESTART_BACKOFF_MIN
+    } else {
+        (previous * 2).min(RESTART_BACKOFF_MAX)
+    }
+}
+
+fn spawn_process(def: &ServiceDef) -> std::io::Result<Child> {
+    #[cfg(unix)]
+    let mut cmd = {
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+        let mut cmd = Command::new(shell);
+        cmd.arg("-lc").arg(&def.command);
+        // Own process group so stop signals reach the whole tree
+        cmd.process_group(0);
+        cmd
+    };
+
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(&def.command);
+        cmd
+    };
+
+    let cwd = def
+        .cwd
+        .clone()
+        .or_else(|| std::env::var("HOME").ok())
+        .unwrap_or_else(|| ".".to_string());
+
+    cmd.current_dir(cwd)
+        .envs(&def.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+}
+
+/// Signal the process tree rooted at `pid`. With `force`, kill it outright.
+pub(crate) fn signal_tree(pid: u32, force: bool) {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{killpg, Signal};
+        use nix::unistd::Pid;
+
+        let signal = if force { Signal::SIGKILL } else { Signal::SIGTERM };
+        if let Err(e) = killpg(Pid::from_raw(pid as i32), signal) {
+            log::debug!("killpg({:?}) failed for process {}: {}", signal, pid, e);
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        // No graceful signal for console process trees; always force
+        let _ = force;
+        if let Err(e) = crate::pty::kill_process_tree_windows(pid) {
+            log::debug!("taskkill failed for process {}: {}", pid, e);
+        }
+    }
+}
+
+/// Stop a child gracefully, escalating to a kill after the grace period.
+async fn terminate(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        signal_tree(pid, false);
+        if tokio::time::timeout(STOP_GRACE_PERIOD, child.wait()).await.is_ok() {
+            return;
+        }
+        signal_tree(pid, true);
+    }
+    let _ = child.kill().await;
+}
+
+/// Forward each line of a child's output stream into the service log.
+fn capture_output<R>(
+    app: AppHandle,
+    runtimes: Runtimes,
+    id: String,
+    stream: LogStream,
+    reader: R,
+) where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            log_line(&app, &runtimes, &id, stream, &line);
+        }
+    });
+}
+
+fn log_line(app: &AppHandle, runtimes: &Runtimes, id: &str, stream: LogStream, line: &str) {
+    let entry = match lock_runtimes(runtimes).get_mut(id) {
+        Some(rt) => rt.push_log(stream, line),
+        None => return,
+    };
+    let _ = events::emit(
+        app,
+        "service-log",
+        ServiceLogEvent {
+            service_id: id.to_string(),
+            line: entry,
+        },
+    );
+}
+
+/// Apply `update` to a service's runtime and emit `service-status-changed`.
+fn update_runtime(
+    app: &AppHandle,
+    runtimes: &Runtimes,
+    def: &ServiceDef,
+    update: impl FnOnce(&mut ServiceRuntime),
+) {
+    let status = {
+        let mut guard = lock_runtimes(runtimes);
+        update(guard.entry(def.id.clone()).or_default());
+        status_of(def, &guard)
+    };
+    let _ = events::emit(app, "service-status-changed", status);
+}
+
+/// Run a service until it is stopped or exits without a restart.
+async fn supervise(
+    app: AppHandle,
+    runtimes: Runtimes,
+    def: ServiceDef,
+    mut stop_rx: watch::Receiver<bool>,
+) {
+    let mut backoff = RESTART_BACKOFF_MIN;
+
+    loop {
+        let mut child = match spawn_process(&def) {
+            Ok(c) => c,
+            Err(e) => {
+                let message = format!("Failed to start: {}", e);
+                log::error!("Service '{}': {}", def.name, message);
+                log_line(&app, &runtimes, &def.id, LogStream::System, &message);
+                update_runtime(&app, &runtimes, &def, |rt| {
+                    rt.state = ServiceState::Exited;
+                    rt.pid = None;
+                    rt.last_error = Some(message);
+                    rt.stop_tx = None;
+                });
+                return;
+            }
+        };
+
+        let pid = child.id();
+        let started = Instant::now();
+        if let Some(out) = child.stdout.take() {
+            capture_output(app.clone(), runtimes.clone(), def.id.clone(), LogStream::Stdout, out);
+        }
+        if let Some(err) = child.stderr.take() {
+            capture_output(app.clone(), runtimes.clone(), def.id.clone(), LogStream::Stderr, err);
+        }
+        update_runtime(&app, &runtimes, &def, |rt| {
+            rt.state = ServiceState::Running;
+            rt.pid = pid;
+            rt.started_at = Some(Local::now());
+            rt.exit_code = None;
+            rt.last_error = None;
+        });
+        log::info!("Service '{}' started (pid {:?})", def.name, pid);
+        log_line(&app, &runtimes, &def.id, LogStream::System, &format!("Started: {}", def.command));
+
+        let code = tokio::select! {
+            status = child.wait() => status.ok().and_then(|s| s.code()),
+            _ = stop_rx.changed() => {
+                terminate(&mut child).await;
+                log::info!("Service '{}' stopped", def.name);
+                log_line(&app, &runtimes, &def.id, LogStream::System, "Stopped");
+                update_runtime(&app, &runtimes, &def, |rt| {
+                    rt.state = ServiceState::Stopped;
+                    rt.pid = None;
+                    rt.stop_tx = None;
+                });
+                return;
+            }
+        };
+
+        let exit = code.map_or("a signal".to_string(), |c| format!("code {}", c));
+        if !should_restart(def.restart, code) {
+            log::info!("Service '{}' exited with {}", def.name, exit);
+            log_line(&app, &runtimes, &def.id, LogStream::System, &format!("Exited with {}", exit));
+            update_runtime(&app, &runtimes, &def, |rt| {
+                rt.state = ServiceState::Exited;
+                rt.pid = None;
+                rt.exit_code = code;
+                rt.stop_tx = None;
+            });
+            return;
+        }
+
+        backoff = next_backoff(backoff, started.elapsed());
+        log::warn!("Service '{}' exited with {}, restarting in {:?}", def.name, exit, backoff);
+        log_line(
+            &app,
+            &runtimes,
+            &def.id,
+            LogStream::System,
+            &format!("Exited with {}, restarting in {}s", exit, backoff.as_secs()),
+        );
+        update_runtime(&app, &runtimes, &def, |rt| {
+            rt.state = ServiceState::Restarting;
+            rt.pid = None;
+            rt.exit_code = code;
+            rt.restarts += 1;
+        });
+
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = stop_rx.changed() => {
+                log_line(&app, &runtimes, &def.id, LogStream::System, "Stopped");
+                update_runtime(&app, &runtimes, &def, |rt| {
+                    rt.state = ServiceState::Stopped;
+                    rt.stop_tx = None;
+                });
+                return;
+            }
+        }
+    }
+}
+
+/// Kill every running service. Called on app exit, where async
+/// supervisors no longer get a chance to run.
+pub fn stop_all(state: &ServicesState) {
+    for rt in lock_runtimes(&state.runtimes).values_mut() {
+        if let Some(pid) = rt.pid.take() {
+            signal_tree(pid, true);
+        }
+    }
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Validate the user-editable fields of a service definition.
+fn validate_def(name: &str, command: &str, cwd: Option<&str>) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::Validation("Service name must not be empty".into()));
+    }
+    if command.trim().is_empty() {
+        return Err(AppError::Validation("Service command must not be empty".into()));
+    }
+    if let Some(dir) = cwd {
+        if !Path::new(dir).is_dir() {
+            return Err(AppError::Validation(format!("Working directory does not exist: {}", dir)));
+        }
+    }
+    Ok(())
+}
+
+/// Service definitions, for `export_config`.
+pub(crate) async fn export_defs(state: &ServicesState) -> Vec<ServiceDef> {
+    state.defs.lock().await.clone()
+}
+
+/// Add imported service definitions (`import_config`). Services whose name
+/// is taken or whose definition is invalid on this machine (e.g. a missing
+/// working directory) are skipped with a note in `skipped`. Returns how many
+/// were added.
+pub(crate) async fn import_defs(
+    app: &AppHandle,
+    state: &ServicesState,
+    imported: Vec<ServiceDef>,
+    skipped: &mut Vec<String>,
+) -> Result<usize, AppError> {
+    let mut defs = state.defs.lock().await;
+    let mut added = 0;
+    for mut def in imported {
+        if defs.iter().any(|d| d.name == def.name) {
+            skipped.push(format!("Service '{}': a service with this name exists", def.name));
+            continue;
+        }
+        if let Err(e) = validate_def(&def.name, &def.command, def.cwd.as_deref()) {
+            skipped.push(format!("Service '{}': {}", def.name, e));
+            continue;
+        }
+        if defs.iter().any(|d| d.id == def.id) {
+            def.id = uuid::Uuid::new_v4().to_string();
+        }
+        defs.push(def);
+        added += 1;
+    }
+    if added > 0 {
+        save_defs(app, &defs).await?;
+    }
+    Ok(added)
+}
+
+/// Define a new service. It is not started.
+///
+/// # Arguments
+/// * `name` - Unique label shown in the UI
+/// * `command` - Command line, run through the user's login shell
+/// * `cwd` - Working directory (default: $HOME)
+/// * `env` - Extra environment variables
+/// * `restart` - `never` (default), `on_failure`, or `always`
+#[tauri::command]
+pub async fn add_service(
+    app: AppHandle,
+    state: State<'_, ServicesState>,
+    name: String,
+    command: String,
+    cwd: Option<String>,
+    env: Option<HashMap<String, String>>,
+    restart: Option<RestartPolicy>,
+) -> Result<ServiceDef, AppError> {
+    validate_def(&name, &command, cwd.as_deref())?;
+
+    let mut defs = state.defs.lock().await;
+    if defs.iter().any(|d| d.name == name) {
+        return Err(AppError::Validation(format!("A service named '{}' already exists", name)));
+    }
+
+    let def = ServiceDef {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        command,
+        cwd,
+        env: env.unwrap_or_default(),
+        restart: restart.unwrap_or_default(),
+        created_at: Local::now(),
+    };
+    defs.push(def.clone());
+    save_defs(&app, &defs).await?;
+
+    log::info!("Added service '{}' ({})", def.name, def.id);
+    Ok(def)
+}
+
+/// List all services with their runtime state.
+#[tauri::command]
+pub async fn list_services(state: State<'_, ServicesState>) -> Result<Vec<ServiceStatus>, AppError> {
+    let defs = state.defs.lock().await;
+    let runtimes = lock_runtimes(&state.runtimes);
+    Ok(defs.iter().map(|d| status_of(d, &runtimes)).collect())
+}
+
+/// Remove a service definition, stopping it first if it is running.
+#[tauri::command]
+pub async fn remove_service(
+    app: AppHandle,
+    state: State<'_, ServicesState>,
+    id: String,
+) -> Result<(), AppError> {
+    let mut defs = state.defs.lock().await;
+
+    let before = defs.len();
+    defs.retain(|d| d.id != id);
+    if defs.len() == before {
+        return Err(AppError::NotFound(format!("Service not found: {}", id)));
+    }
+    save_defs(&app, &defs).await?;
+
+    if let Some(rt) = lock_runtimes(&state.runtimes).remove(&id) {
+        if let Some(tx) = rt.stop_tx {
+            let _ = tx.send(true);
+        }
+    }
+
+    log::info!("Removed service {}", id);
+    Ok(())
+}
+
+/// Start a service. Fails if it is already running or restarting.
+#[tauri::command]
+pub async fn start_service(
+    app: AppHandle,
+    state: State<'_, ServicesState>,
+    id: String,
+) -> Result<ServiceStatus, AppError> {
+    let defs = state.defs.lock().await;
+    let def = defs
+        .iter()
+        .find(|d| d.id == id)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("Service not found: {}", id)))?;
+
+    let (stop_tx, stop_rx) = watch::channel(false);
+    {
+        let mut runtimes = lock_runtimes(&state.runtimes);
+        let rt = runtimes.entry(id.clone()).or_default();
+        if rt.stop_tx.is_some() {
+            return Err(AppError::Validation(format!("Service '{}' is already running", def.name)));
+        }
+        rt.stop_tx = Some(stop_tx);
+        rt.restarts = 0;
+    }
+
+    tauri::async_runtime::spawn(supervise(app, state.runtimes.clone(), def.clone(), stop_rx));
+
+    let runtimes = lock_runtimes(&state.runtimes);
+    Ok(status_of(&def, &runtimes))
+}
+
+/// Stop a running (or restarting) service. Its process tree gets SIGTERM,
+/// then SIGKILL after 5 seconds.
+#[tauri::command]
+pub async fn stop_service(state: State<'_, ServicesState>, id: String) -> Result<(), AppError> {
+    if !state.defs.lock().await.iter().any(|d| d.id == id) {
+        return Err(AppError::NotFound(format!("Service not found: {}", id)));
+    }
+
+    let tx = lock_runtimes(&state.runtimes)
+        .get_mut(&id)
+        .and_then(|rt| rt.stop_tx.take())
+        .ok_or_else(|| AppError::Validation(format!("Service is not running: {}", id)))?;
+    let _ = tx.send(true);
+    Ok(())
+}
+
+/// Get one service's definition and runtime state.
+#[tauri::command]
+pub async fn get_service_status(
+    state: State<'_, ServicesState>,
+    id: String,
+) -> Result<ServiceStatus, AppError> {
+    let defs = state.defs.lock().await;
+    let def = defs
+        .iter()
+        .find(|d| d.id == id)
+        .ok_or_else(|| AppError::NotFound(format!("Service not found: {}", id)))?;
+    Ok(status_of(def, &lock_runtimes(&state.runtimes)))
+}
+
+/// Get a service's most recent output lines, oldest first.
+///
+/// # Arguments
+/// * `limit` - Maximum number of lines to return (default: 200, max: 2000)
+#[tauri::command]
+pub async fn get_service_logs(
+    state: State<'_, ServicesState>,
+    id: String,
+    limit: Option<usize>,
+) -> Result<Vec<ServiceLogLine>, AppError> {
+    if !state.defs.lock().await.iter().any(|d| d.id == id) {
+        return Err(AppError::NotFound(format!("Service not found: {}", id)));
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT).min(MAX_LOG_LINES);
+    let runtimes = lock_runtimes(&state.runtimes);
+    Ok(runtimes
+        .get(&id)
+        .map(|rt| {
+            let skip = rt.logs.len().saturating_sub(limit);
+            rt.logs.iter().skip(skip).cloned().collect()
+        })
+        .unwrap_or_default())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_restart() {
+        assert!(!should_restart(RestartPolicy::Never, Some(1)));
+        assert!(should_restart(RestartPolicy::OnFailure, Some(1)));
+        assert!(should_restart(RestartPolicy::OnFailure, None));
+        assert!(!should_restart(RestartPolicy::OnFailure, Some(0)));
+        assert!(should_restart(RestartPolicy::Always, Some(0)));
+    }
+
+    #[test]
+    fn test_next_backoff_doubles_and_resets() {
+        let quick = Duration::from_secs(1);
+        assert_eq!(next_backoff(RESTART_BACKOFF_MIN, quick), Duration::from_secs(2));
+        assert_eq!(next_backoff(Duration::from_secs(20), quick), RESTART_BACKOFF_MAX);
+        assert_eq!(next_backoff(Duration::from_secs(20), STABLE_RUN), RESTART_BACKOFF_MIN);
+    }
+
+    #[test]
+    fn test_log_buffer_is_capped() {
+        let mut rt = ServiceRuntime::default();
+        for i in 0..MAX_LOG_LINES + 5 {
+            rt.push_log(LogStream::Stdout, &i.to_string());
+        }
+        assert_eq!(rt.logs.len(), MAX_LOG_LINES);
+        assert_eq!(rt.logs.front().unwrap().line, "5");
+
+        let long = "x".repeat(MAX_LINE_CHARS + 10);
+        assert_eq!(rt.push_log(LogStream::Stderr, &long).line.len(), MAX_LINE_CHARS);
+    }
+
+    #[test]
+    fn test_validate_def() {
+        assert!(validate_def("web", "npm run dev", None).is_ok());
+        assert!(validate_def(" ", "npm run dev", None).is_err());
+        assert!(validate_def("web", "", None).is_err());
+        assert!(validate_def("web", "ls", Some("/definitely/not/a/dir")).is_err());
+    }
+
+    #[test]
+    fn test_restart_policy_serialization() {
+        assert_eq!(serde_json::to_string(&RestartPolicy::OnFailure).unwrap(), "\"on_failure\"");
+        let def: ServiceDef = serde_json::from_str(
+            r#"{"id":"a","name":"web","command":"ls","cwd":null,"created_at":"2026-01-01T00:00:00+00:00"}"#,
+        )
+        .unwrap();
+        assert_eq!(def.restart, RestartPolicy::Never);
+        assert!(def.env.is_empty());
+    }
+}
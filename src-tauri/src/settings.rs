@@ -0,0 +1,898 @@
+//! Persistent application settings.
+//!
+//! Settings live in `settings.json` in the app config directory. They are
+//! loaded at startup and the file is watched, so edits made outside the app
+//! (e.g. dotfile managers) are applied live and announced with a
+//! `settings-changed` event. Every section uses `#[serde(default)]` so older
+//! files missing newer keys still load, and unknown keys are ignored.
+
+use log::LevelFilter;
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+use crate::events;
+use crate::logging::{self, LogTimezone};
+use crate::network_probe;
+use crate::pty::{self, ControlCharPolicy, PtyState};
+use crate::redact;
+use crate::remote_log;
+use crate::streaming;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// File name of the settings file inside the app config directory
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// Default cap on simultaneous terminal sessions
+const DEFAULT_MAX_SESSIONS: usize = 32;
+
+/// Upper bound accepted for `terminal.max_sessions`
+const MAX_SESSIONS_LIMIT: usize = 256;
+
+/// Default and accepted range for `terminal.max_write_bytes`
+const DEFAULT_MAX_WRITE_BYTES: usize = 1024 * 1024;
+const MIN_WRITE_BYTES_LIMIT: usize = 1024;
+const MAX_WRITE_BYTES_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Upper bound for `terminal.idle_kill_hours` (30 days)
+const MAX_IDLE_KILL_HOURS: u64 = 30 * 24;
+
+/// Default and upper bound for `terminal.idle_warning_mins`
+const DEFAULT_IDLE_WARNING_MINS: u64 = 15;
+const MAX_IDLE_WARNING_MINS: u64 = 24 * 60;
+
+/// Defaults and upper bound for `terminal.rate_limit`
+const DEFAULT_COMMANDS_PER_SEC: u32 = 10;
+const DEFAULT_COMMAND_BURST: u32 = 64;
+const DEFAULT_WRITES_PER_SEC: u32 = 200;
+const DEFAULT_WRITE_BURST: u32 = 400;
+const MAX_RATE_LIMIT: u32 = 10_000;
+
+/// Defaults for `terminal.appearance`, matching the built-in terminal look
+const DEFAULT_TERMINAL_THEME: &str = "synthia";
+const DEFAULT_FONT_FAMILY: &str = "'Space Mono', 'Fira Code', 'Cascadia Code', monospace";
+const DEFAULT_FONT_SIZE: u32 = 13;
+
+/// Accepted range for `terminal.appearance.font_size`
+const MIN_FONT_SIZE: u32 = 6;
+const MAX_FONT_SIZE: u32 = 72;
+
+/// Longest accepted `terminal.appearance` theme name / font family
+const MAX_THEME_NAME_LEN: usize = 64;
+const MAX_FONT_FAMILY_LEN: usize = 256;
+
+/// Upper bound for `stream.idle_stop_secs` (one day)
+const MAX_IDLE_STOP_SECS: u64 = 24 * 60 * 60;
+
+/// Accepted ranges for `logging.remote` batching
+const MAX_REMOTE_BATCH_SIZE: usize = 1000;
+const MAX_REMOTE_FLUSH_SECS: u64 = 300;
+
+/// Quiet period before a burst of file-system events triggers a reload
+/// (editors often write a file several times when saving)
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Terminal-related settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminalSettings {
+    /// Maximum number of PTY sessions that may be open at once
+    pub max_sessions: usize,
+    /// Largest single `write_terminal` / `paste_terminal` payload in bytes
+    pub max_write_bytes: usize,
+    /// Control characters accepted by `write_terminal`
+    pub control_chars: ControlCharPolicy,
+    /// Shell id from `list_available_shells` for new sessions (empty = platform default)
+    pub shell: String,
+    /// Save each session's transcript tail on exit and restore it when a
+    /// session with the same id is spawned again
+    pub persist_scrollback: bool,
+    /// Secrets masked in output handed to AI agents (see `redact`)
+    pub redaction: RedactionSettings,
+    /// Kill sessions that had no input for this many hours while the shell
+    /// sat at its prompt (0 = never). Sessions marked with `set_idle_exempt`
+    /// are kept.
+    pub idle_kill_hours: u64,
+    /// Minutes of notice given by `terminal-idle-warning` before the kill
+    pub idle_warning_mins: u64,
+    /// Named environments `spawn_terminal` can start a session with
+    pub profiles: Vec<EnvProfile>,
+    /// Drop sessions from `list_terminals` as soon as their shell exits
+    /// (otherwise they stay listed as dead until `kill_terminal`)
+    pub auto_remove_dead: bool,
+    /// Throttling of `inject_command(s)` and `write_terminal` (see `rate_limit`)
+    pub rate_limit: RateLimitSettings,
+    /// Look of the terminal UI (`get_terminal_appearance`)
+    pub appearance: TerminalAppearance,
+}
+
+impl Default for TerminalSettings {
+    fn default() -> Self {
+        Self {
+            max_sessions: DEFAULT_MAX_SESSIONS,
+            max_write_bytes: DEFAULT_MAX_WRITE_BYTES,
+            control_chars: ControlCharPolicy::default(),
+            shell: String::new(),
+            persist_scrollback: false,
+            redaction: RedactionSettings::default(),
+            idle_kill_hours: 0,
+            idle_warning_mins: DEFAULT_IDLE_WARNING_MINS,
+            profiles: Vec::new(),
+            auto_remove_dead: false,
+            rate_limit: RateLimitSettings::default(),
+            appearance: TerminalAppearance::default(),
+        }
+    }
+}
+
+/// Terminal colors, font and cursor, shared by every window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminalAppearance {
+    /// Color scheme name known to the frontend (unknown names fall back to
+    /// the default scheme there)
+    pub theme: String,
+    /// CSS font-family list
+    pub font_family: String,
+    /// Font size in pixels
+    pub font_size: u32,
+    pub cursor_style: CursorStyle,
+    pub cursor_blink: bool,
+}
+
+impl Default for TerminalAppearance {
+    fn default() -> Self {
+        Self {
+            theme: DEFAULT_TERMINAL_THEME.into(),
+            font_family: DEFAULT_FONT_FAMILY.into(),
+            font_size: DEFAULT_FONT_SIZE,
+            cursor_style: CursorStyle::default(),
+            cursor_blink: true,
+        }
+    }
+}
+
+impl TerminalAppearance {
+    fn validate(&self) -> Result<(), AppError> {
+        let text_ok = |value: &str, max: usize| {
+            !value.trim().is_empty() && value.len() <= max && !value.chars().any(char::is_control)
+        };
+        if !text_ok(&self.theme, MAX_THEME_NAME_LEN) {
+            return Err(AppError::Validation(format!(
+                "terminal.appearance.theme must be 1-{} printable characters",
+                MAX_THEME_NAME_LEN
+            )));
+        }
+        if !text_ok(&self.font_family, MAX_FONT_FAMILY_LEN) {
+            return Err(AppError::Validation(format!(
+                "terminal.appearance.font_family must be 1-{} printable characters",
+                MAX_FONT_FAMILY_LEN
+            )));
+        }
+        if !(MIN_FONT_SIZE..=MAX_FONT_SIZE).contains(&self.font_size) {
+            return Err(AppError::Validation(format!(
+                "terminal.appearance.font_size must be {}-{}, got: {}",
+                MIN_FONT_SIZE, MAX_FONT_SIZE, self.font_size
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Terminal cursor shape (xterm.js `cursorStyle`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorStyle {
+    #[default]
+    Block,
+    Underline,
+    Bar,
+}
+
+/// Token-bucket limits per caller and session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitSettings {
+    pub enabled: bool,
+    /// Injected commands refilled per second
+    pub commands_per_sec: u32,
+    /// Injected commands accepted back to back
+    pub command_burst: u32,
+    /// `write_terminal` calls refilled per second
+    pub writes_per_sec: u32,
+    /// `write_terminal` calls accepted back to back
+    pub write_burst: u32,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            commands_per_sec: DEFAULT_COMMANDS_PER_SEC,
+            command_burst: DEFAULT_COMMAND_BURST,
+            writes_per_sec: DEFAULT_WRITES_PER_SEC,
+            write_burst: DEFAULT_WRITE_BURST,
+        }
+    }
+}
+
+/// Redaction of terminal output in `terminal-output-captured` events,
+/// `get_transcript` and `wait_for_output`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionSettings {
+    pub enabled: bool,
+    /// Applied in order; the defaults cover common API keys and tokens
+    pub rules: Vec<RedactionRule>,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        let rule = |name: &str, pattern: &str| RedactionRule {
+            name: name.into(),
+            pattern: pattern.into(),
+        };
+        Self {
+            enabled: true,
+            rules: vec![
+                rule("aws_access_key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+                rule(
+                    "aws_secret_key",
+                    concat!(
+                        r#"(?i)aws_?secret_?access_?key["']?\s*[=:]\s*["']?"#,
+                        r"(?P<secret>[A-Za-z0-9/+=]{40})"
+                    ),
+                ),
+                rule("bearer_token", r"(?i)\bbearer\s+(?P<secret>[A-Za-z0-9._~+/-]{8,}=*)"),
+                rule("github_token", r"\bgh[pousr]_[A-Za-z0-9]{36,}"),
+                rule("api_key", r"\bsk-[A-Za-z0-9_-]{20,}"),
+                rule(
+                    "credential",
+                    concat!(
+                        r#"(?i)(?:api[_-]?key|secret|token|password)["']?\s*[=:]\s*["']?"#,
+                        r"(?P<secret>[A-Za-z0-9/+_.-]{8,})"
+                    ),
+                ),
+            ],
+        }
+    }
+}
+
+/// A named redaction pattern. Matches are replaced by
+/// `[REDACTED:<name>]`; with a `(?P<secret>...)` group only that part is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactionRule {
+    pub name: String,
+    /// Rust `regex` syntax
+    pub pattern: String,
+}
+
+/// A named session environment, applied by `spawn_terminal` with `profile`.
+/// Applied in field order: `unset`, then `env`, then `path_prepend`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvProfile {
+    /// Unique name, e.g. `python-venv`
+    pub name: String,
+    /// Variables set for the shell
+    pub env: BTreeMap<String, String>,
+    /// Variables removed from the inherited environment (e.g. cloud
+    /// credentials for a `prod-creds-off` profile)
+    pub unset: Vec<String>,
+    /// Directories put in front of `PATH`, first one searched first
+    pub path_prepend: Vec<String>,
+    /// Typed into the shell once its first prompt appears, before any
+    /// `initial_commands` of the spawn call
+    pub init_commands: Vec<String>,
+}
+
+/// Logging settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingSettings {
+    /// Maximum level written to the log ("off", "error", "warn", "info", "debug", "trace")
+    pub level: String,
+    /// Zone timestamps are written in and shown in the Logs UI
+    pub timezone: LogTimezone,
+    /// Mirror terminal output into the log as `pty:<session>` lines
+    pub capture_terminal: bool,
+    /// Forwarding to a central collector
+    pub remote: RemoteLogSettings,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            level: "info".into(),
+            timezone: LogTimezone::Local,
+            capture_terminal: false,
+            remote: RemoteLogSettings::default(),
+        }
+    }
+}
+
+/// Log shipping to a syslog server or HTTP collector (see `remote_log`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteLogSettings {
+    pub enabled: bool,
+    /// `udp://host[:port]`, `tcp://host[:port]` (syslog) or `http://host[:port]/path`
+    pub endpoint: String,
+    /// Lowest level shipped; records must also pass `logging.level`
+    pub level: String,
+    /// Records sent per request (or burst of syslog messages)
+    pub batch_size: usize,
+    /// Longest a record waits before a partial batch is sent
+    pub flush_secs: u64,
+}
+
+impl Default for RemoteLogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            level: "warn".into(),
+            batch_size: 100,
+            flush_secs: 5,
+        }
+    }
+}
+
+/// Defaults pre-filled in the local stream controls.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamSettings {
+    /// Port of the stream server (0 = first free port of 9100-9199)
+    pub port: u16,
+    pub fps: u32,
+    /// JPEG quality (1-100)
+    pub quality: i32,
+    /// Stop a running stream this many seconds after its last client
+    /// disconnects (0 = never)
+    pub idle_stop_secs: u64,
+    /// Peer IPs or CIDR blocks allowed to connect (empty = any). Loopback
+    /// is always allowed.
+    pub allowed_ips: Vec<String>,
+    /// Maximum concurrent viewers (0 = unlimited)
+    pub max_clients: usize,
+    /// Serve the stream over TLS (`wss://`), see `stream_tls`
+    pub tls: bool,
+    /// PEM certificate chain and private key to serve instead of the
+    /// generated self-signed certificate (both or neither)
+    pub tls_cert_path: String,
+    pub tls_key_path: String,
+    /// Let viewers the user grants control inject keyboard and mouse input
+    /// (see `remote_input`)
+    pub remote_control: bool,
+}
+
+impl Default for StreamSettings {
+    fn default() -> Self {
+        Self {
+            port: streaming::STREAM_PORT_MIN,
+            fps: 30,
+            quality: 80,
+            idle_stop_secs: 0,
+            allowed_ips: Vec::new(),
+            max_clients: 0,
+            tls: false,
+            tls_cert_path: String::new(),
+            tls_key_path: String::new(),
+            remote_control: false,
+        }
+    }
+}
+
+/// Network probe settings (see `network_probe`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// `http://host[:port]/path` downloaded by throughput tests (empty = none)
+    pub throughput_url: String,
+}
+
+/// All persisted settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub terminal: TerminalSettings,
+    pub logging: LoggingSettings,
+    pub stream: StreamSettings,
+    pub network: NetworkSettings,
+}
+
+impl Settings {
+    /// Check that all values are within their accepted ranges.
+    pub(crate) fn validate(&self) -> Result<(), AppError> {
+        if !(1..=MAX_SESSIONS_LIMIT).contains(&self.terminal.max_sessions) {
+            return Err(AppError::Validation(format!(
+                "terminal.max_sessions must be 1-{}, got: {}",
+                MAX_SESSIONS_LIMIT, self.terminal.max_sessions
+            )));
+        }
+        if !(MIN_WRITE_BYTES_LIMIT..=MAX_WRITE_BYTES_LIMIT).contains(&self.terminal.max_write_bytes) {
+            return Err(AppError::Validation(format!(
+                "terminal.max_write_bytes must be {}-{}, got: {}",
+                MIN_WRITE_BYTES_LIMIT, MAX_WRITE_BYTES_LIMIT, self.terminal.max_write_bytes
+            )));
+        }
+        if LevelFilter::from_str(&self.logging.level).is_err() {
+            return Err(AppError::Validation(format!(
+                "logging.level must be one of off/error/warn/info/debug/trace, got: {}",
+                self.logging.level
+            )));
+        }
+        redact::Redactor::compile(&self.terminal.redaction.rules)?;
+        pty::check_env_profiles(&self.terminal.profiles)?;
+        if self.terminal.idle_kill_hours > MAX_IDLE_KILL_HOURS {
+            return Err(AppError::Validation(format!(
+                "terminal.idle_kill_hours must be 0-{}, got: {}",
+                MAX_IDLE_KILL_HOURS, self.terminal.idle_kill_hours
+            )));
+        }
+        if !(1..=MAX_IDLE_WARNING_MINS).contains(&self.terminal.idle_warning_mins) {
+            return Err(AppError::Validation(format!(
+                "terminal.idle_warning_mins must be 1-{}, got: {}",
+                MAX_IDLE_WARNING_MINS, self.terminal.idle_warning_mins
+            )));
+        }
+        let rate_limit = &self.terminal.rate_limit;
+        for (name, value) in [
+            ("commands_per_sec", rate_limit.commands_per_sec),
+            ("command_burst", rate_limit.command_burst),
+            ("writes_per_sec", rate_limit.writes_per_sec),
+            ("write_burst", rate_limit.write_burst),
+        ] {
+            if !(1..=MAX_RATE_LIMIT).contains(&value) {
+                return Err(AppError::Validation(format!(
+                    "terminal.rate_limit.{} must be 1-{}, got: {}",
+                    name, MAX_RATE_LIMIT, value
+                )));
+            }
+        }
+        self.terminal.appearance.validate()?;
+        let remote = &self.logging.remote;
+        if remote.enabled {
+            remote_log::Endpoint::parse(&remote.endpoint)?;
+        }
+        if LevelFilter::from_str(&remote.level).is_err() {
+            return Err(AppError::Validation(format!(
+                "logging.remote.level must be one of off/error/warn/info/debug/trace, got: {}",
+                remote.level
+            )));
+        }
+        if !(1..=MAX_REMOTE_BATCH_SIZE).contains(&remote.batch_size) {
+            return Err(AppError::Validation(format!(
+                "logging.remote.batch_size must be 1-{}, got: {}",
+                MAX_REMOTE_BATCH_SIZE, remote.batch_size
+            )));
+        }
+        if !(1..=MAX_REMOTE_FLUSH_SECS).contains(&remote.flush_secs) {
+            return Err(AppError::Validation(format!(
+                "logging.remote.flush_secs must be 1-{}, got: {}",
+                MAX_REMOTE_FLUSH_SECS, remote.flush_secs
+            )));
+        }
+        if !streaming::is_valid_stream_port(self.stream.port) {
+            return Err(AppError::Validation(format!(
+                "stream.port must be {} (auto) or {}-{}, got: {}",
+                streaming::STREAM_PORT_AUTO,
+                streaming::STREAM_PORT_MIN,
+                streaming::STREAM_PORT_MAX,
+                self.stream.port
+            )));
+        }
+        if self.stream.tls_cert_path.is_empty() != self.stream.tls_key_path.is_empty() {
+            return Err(AppError::Validation(
+                "stream.tls_cert_path and stream.tls_key_path must be set together".into(),
+            ));
+        }
+        if !(1..=streaming::MAX_FPS).contains(&self.stream.fps) {
+            return Err(AppError::Validation(format!(
+                "stream.fps must be 1-{}, got: {}",
+                streaming::MAX_FPS,
+                self.stream.fps
+            )));
+        }
+        if !(1..=100).contains(&self.stream.quality) {
+            return Err(AppError::Validation(format!(
+                "stream.quality must be 1-100, got: {}",
+                self.stream.quality
+            )));
+        }
+        if self.stream.idle_stop_secs > MAX_IDLE_STOP_SECS {
+            return Err(AppError::Validation(format!(
+                "stream.idle_stop_secs must be 0-{}, got: {}",
+                MAX_IDLE_STOP_SECS, self.stream.idle_stop_secs
+            )));
+        }
+        for rule in &self.stream.allowed_ips {
+            streaming::IpRule::parse(rule)?;
+        }
+        if !self.network.throughput_url.is_empty() {
+            network_probe::parse_http_url(&self.network.throughput_url)?;
+        }
+        Ok(())
+    }
+
+    /// The configured log level (validated, so parsing cannot fail).
+    fn log_level(&self) -> LevelFilter {
+        LevelFilter::from_str(&self.logging.level).unwrap_or(LevelFilter::Info)
+    }
+}
+
+/// Current and maximum terminal session counts.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionLimits {
+    pub max_sessions: usize,
+    pub active_sessions: usize,
+}
+
+/// Shared state managed by Tauri.
+#[derive(Default)]
+pub struct SettingsState {
+    settings: Mutex<Settings>,
+    /// Keeps the settings file watcher alive for the app's lifetime
+    watcher: Mutex<Option<Debouncer<RecommendedWatcher>>>,
+}
+
+impl SettingsState {
+    /// Snapshot of the current settings (defaults if the lock is poisoned).
+    pub fn get(&self) -> Settings {
+        self.settings
+            .lock()
+            .map(|s| s.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replace the in-memory settings.
+    fn set(&self, settings: Settings) -> Result<(), AppError> {
+        let mut guard = self
+            .settings
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock settings: {}", e)))?;
+        *guard = settings;
+        Ok(())
+    }
+}
+
+// =============================================================================
+// Settings File
+// =============================================================================
+
+/// Get the path to the settings file.
+fn get_settings_path(app: &AppHandle) -> Option<PathBuf> {
+    let config_dir = app.path().app_config_dir().ok()?;
+    Some(config_dir.join(SETTINGS_FILE_NAME))
+}
+
+/// Read settings from disk. A missing file yields defaults.
+fn load_settings(app: &AppHandle) -> Result<Settings, AppError> {
+    let path = get_settings_path(app)
+        .ok_or_else(|| AppError::Internal("Could not determine settings file path".into()))?;
+
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| AppError::Io(format!("Failed to read settings file: {}", e)))?;
+
+    let settings: Settings = serde_json::from_str(&json)
+        .map_err(|e| AppError::Validation(format!("Failed to parse settings file: {}", e)))?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+/// Write settings to disk, creating the config directory if needed.
+fn save_settings(app: &AppHandle, settings: &Settings) -> Result<(), AppError> {
+    let path = get_settings_path(app)
+        .ok_or_else(|| AppError::Internal("Could not determine settings file path".into()))?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| AppError::Io(format!("Failed to create config directory: {}", e)))?;
+    }
+
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize settings: {}", e)))?;
+
+    std::fs::write(&path, json)
+        .map_err(|e| AppError::Io(format!("Failed to write settings file: {}", e)))
+}
+
+/// Store settings and apply the parts that take effect immediately.
+fn apply(app: &AppHandle, settings: Settings) -> Result<(), AppError> {
+    log::set_max_level(settings.log_level());
+    logging::set_timezone(settings.logging.timezone);
+    logging::set_capture_terminal(settings.logging.capture_terminal);
+    remote_log::configure(&settings.logging.remote);
+    redact::configure(&settings.terminal.redaction);
+    app.state::<SettingsState>().set(settings)
+}
+
+/// Load settings into managed state and start watching the file.
+/// Invalid files fall back to defaults.
+pub fn init(app: &AppHandle) {
+    let settings = match load_settings(app) {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!("Failed to load settings, using defaults: {}", e);
+            Settings::default()
+        }
+    };
+
+    log::info!("Settings loaded: {:?}", settings);
+
+    if let Err(e) = apply(app, settings) {
+        log::error!("{}", e);
+    }
+
+    if let Err(e) = start_watcher(app) {
+        log::warn!("Settings hot reload disabled: {}", e);
+    }
+}
+
+// =============================================================================
+// Hot Reload
+// =============================================================================
+
+/// Watch the config directory and reload when `settings.json` changes.
+///
+/// The directory is watched rather than the file itself because editors
+/// commonly save by writing a temp file and renaming it over the original.
+fn start_watcher(app: &AppHandle) -> Result<(), AppError> {
+    let path = get_settings_path(app)
+        .ok_or_else(|| AppError::Internal("Could not determine settings file path".into()))?;
+    let dir = path
+        .parent()
+        .ok_or_else(|| AppError::Internal("Settings file has no parent directory".into()))?
+        .to_path_buf();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| AppError::Io(format!("Failed to create config directory: {}", e)))?;
+
+    let handle = app.clone();
+    let mut debouncer = new_debouncer(WATCH_DEBOUNCE, move |res: DebounceEventResult| {
+        match res {
+            Ok(events) => {
+                if events
+                    .iter()
+                    .any(|e| e.path.file_name() == path.file_name())
+                {
+                    reload_from_disk(&handle);
+                }
+            }
+            Err(e) => log::warn!("Settings watcher error: {}", e),
+        }
+    })
+    .map_err(|e| AppError::Io(format!("Failed to create settings watcher: {}", e)))?;
+
+    debouncer
+        .watcher()
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| AppError::Io(format!("Failed to watch {:?}: {}", dir, e)))?;
+
+    log::info!("Watching {:?} for settings changes", dir);
+
+    let state = app.state::<SettingsState>();
+    let mut watcher = state
+        .watcher
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock settings watcher: {}", e)))?;
+    *watcher = Some(debouncer);
+    Ok(())
+}
+
+/// Re-read the settings file and apply it if it differs from the current
+/// settings. Invalid edits are logged and ignored, keeping the last good
+/// settings in effect.
+fn reload_from_disk(app: &AppHandle) {
+    let settings = match load_settings(app) {
+        Ok(s) => s,
+        Err(e) => {
+            log::warn!("Ignoring invalid settings file change: {}", e);
+            return;
+        }
+    };
+
+    // Our own writes (update_settings) also trigger the watcher
+    if settings == app.state::<SettingsState>().get() {
+        return;
+    }
+
+    log::info!("Settings file changed, applying: {:?}", settings);
+    let previous = app.state::<SettingsState>().get();
+    if let Err(e) = apply(app, settings.clone()) {
+        log::error!("{}", e);
+        return;
+    }
+    emit_changed(app, &previous, &settings);
+}
+
+/// Emit `settings-changed`, plus `terminal-appearance-changed` when the
+/// terminal appearance differs from `previous`.
+fn emit_changed(app: &AppHandle, previous: &Settings, settings: &Settings) {
+    let _ = events::emit(app, "settings-changed", settings);
+    if previous.terminal.appearance != settings.terminal.appearance {
+        let _ = events::emit(app, "terminal-appearance-changed", &settings.terminal.appearance);
+    }
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Get the current settings.
+#[tauri::command]
+pub fn get_settings(state: State<'_, SettingsState>) -> Settings {
+    state.get()
+}
+
+/// Validate, persist and apply new settings.
+#[tauri::command]
+pub fn update_settings(app: AppHandle, settings: Settings) -> Result<Settings, AppError> {
+    replace(&app, &settings)?;
+    Ok(settings)
+}
+
+/// Validate, persist and apply settings, emitting `settings-changed`.
+pub(crate) fn replace(app: &AppHandle, settings: &Settings) -> Result<(), AppError> {
+    settings.validate()?;
+    save_settings(app, settings)?;
+
+    let previous = app.state::<SettingsState>().get();
+    apply(app, settings.clone())?;
+    log::info!("Settings updated: {:?}", settings);
+    emit_changed(app, &previous, settings);
+    Ok(())
+}
+
+/// Get the terminal appearance (theme, font, cursor).
+#[tauri::command]
+pub fn get_terminal_appearance(state: State<'_, SettingsState>) -> TerminalAppearance {
+    state.get().terminal.appearance
+}
+
+/// Validate, persist and apply a new terminal appearance, emitting
+/// `terminal-appearance-changed` (and `settings-changed`) to every window.
+#[tauri::command]
+pub fn set_terminal_appearance(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    appearance: TerminalAppearance,
+) -> Result<TerminalAppearance, AppError> {
+    let mut settings = state.get();
+    settings.terminal.appearance = appearance.clone();
+    replace(&app, &settings)?;
+    Ok(appearance)
+}
+
+/// Get the terminal session limit and how many sessions are currently open.
+#[tauri::command]
+pub fn get_session_limits(
+    state: State<'_, SettingsState>,
+    pty_state: State<'_, PtyState>,
+) -> Result<SessionLimits, AppError> {
+    let active_sessions = pty_state.sessions().len();
+
+    Ok(SessionLimits {
+        max_sessions: state.get().terminal.max_sessions,
+        active_sessions,
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_keys_use_defaults() {
+        let settings: Settings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings, Settings::default());
+
+        let settings: Settings = serde_json::from_str(r#"{"terminal": {}}"#).unwrap();
+        assert_eq!(settings.terminal.max_sessions, DEFAULT_MAX_SESSIONS);
+    }
+
+    #[test]
+    fn test_validate_appearance() {
+        let mut settings = Settings::default();
+        settings.terminal.appearance.font_size = MAX_FONT_SIZE + 1;
+        assert!(settings.validate().is_err());
+
+        let mut settings = Settings::default();
+        settings.terminal.appearance.theme = " ".into();
+        assert!(settings.validate().is_err());
+
+        let mut settings = Settings::default();
+        settings.terminal.appearance.font_family = "Mono\n".into();
+        assert!(settings.validate().is_err());
+
+        let appearance: TerminalAppearance =
+            serde_json::from_str(r#"{"font_size": 16, "cursor_style": "bar"}"#).unwrap();
+        assert_eq!(appearance.cursor_style, CursorStyle::Bar);
+        assert_eq!(appearance.theme, DEFAULT_TERMINAL_THEME);
+    }
+
+    #[test]
+    fn test_validate_max_sessions() {
+        let mut settings = Settings::default();
+        assert!(settings.validate().is_ok());
+
+        settings.terminal.max_sessions = 0;
+        assert!(settings.validate().is_err());
+
+        settings.terminal.max_sessions = MAX_SESSIONS_LIMIT + 1;
+        assert!(settings.validate().is_err());
+
+        let mut settings = Settings::default();
+        settings.terminal.max_write_bytes = MAX_WRITE_BYTES_LIMIT + 1;
+        assert!(settings.validate().is_err());
+
+        let mut settings = Settings::default();
+        settings.terminal.idle_kill_hours = 8;
+        assert!(settings.validate().is_ok());
+        settings.terminal.idle_warning_mins = 0;
+        assert!(settings.validate().is_err());
+        settings.terminal.idle_warning_mins = DEFAULT_IDLE_WARNING_MINS;
+        settings.terminal.idle_kill_hours = MAX_IDLE_KILL_HOURS + 1;
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_logging_and_stream() {
+        let mut settings = Settings::default();
+        settings.logging.level = "DEBUG".into();
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.log_level(), LevelFilter::Debug);
+
+        settings.logging.level = "verbose".into();
+        assert!(settings.validate().is_err());
+
+        let mut settings = Settings::default();
+        settings.logging.remote.enabled = true;
+        assert!(settings.validate().is_err());
+        settings.logging.remote.endpoint = "udp://logs.lan".into();
+        assert!(settings.validate().is_ok());
+        settings.logging.remote.batch_size = 0;
+        assert!(settings.validate().is_err());
+
+        let mut settings = Settings::default();
+        settings.stream.port = 8080;
+        assert!(settings.validate().is_err());
+        settings.stream.port = streaming::STREAM_PORT_AUTO;
+        assert!(settings.validate().is_ok());
+        settings.stream.tls_cert_path = "/etc/synthia/cert.pem".into();
+        assert!(settings.validate().is_err());
+        settings.stream.tls_key_path = "/etc/synthia/key.pem".into();
+        assert!(settings.validate().is_ok());
+
+        let mut settings = Settings::default();
+        settings.stream.fps = 0;
+        assert!(settings.validate().is_err());
+
+        let mut settings = Settings::default();
+        settings.stream.allowed_ips = vec!["192.168.1.0/24".into(), "10.0.0.5".into()];
+        assert!(settings.validate().is_ok());
+        settings.stream.allowed_ips.push("192.168.1.0/40".into());
+        assert!(settings.validate().is_err());
+    }
+}
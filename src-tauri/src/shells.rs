@@ -0,0 +1,301 @@
+//! Shell discovery.
+//!
+//! Lists the shells a terminal session can be spawned with and picks the
+//! platform default. On Windows `SHELL` is normally unset, so instead of
+//! falling back to `/bin/bash` this detects PowerShell 7, Windows PowerShell,
+//! cmd and any installed WSL distributions.
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::AppError;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// A shell that terminal sessions can be spawned with.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ShellInfo {
+    /// Stable identifier accepted by `spawn_terminal` and `terminal.shell`
+    pub id: String,
+    /// Display name, e.g. "zsh", "PowerShell 7", "WSL: Ubuntu"
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    /// Used when neither the caller nor the settings pick a shell
+    pub is_default: bool,
+}
+
+impl ShellInfo {
+    fn new(id: &str, name: &str, program: &str, args: &[&str]) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            is_default: false,
+        }
+    }
+}
+
+/// Id prefix for WSL distributions (`wsl:<distro>`)
+const WSL_PREFIX: &str = "wsl:";
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// List the shells available on this machine, default first.
+#[tauri::command]
+pub async fn list_available_shells() -> Result<Vec<ShellInfo>, AppError> {
+    log::trace!("list_available_shells() called");
+
+    let shells = tokio::task::spawn_blocking(|| {
+        let mut shells = native_shells();
+        shells.extend(wsl_shells());
+        shells
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Shell scan task failed: {}", e)))?;
+
+    log::debug!("Found {} shell(s)", shells.len());
+    Ok(shells)
+}
+
+/// Resolve a shell id to spawn, or the platform default when `id` is None
+/// or empty (blocking: may run `wsl.exe` for WSL ids).
+pub fn resolve_shell(id: Option<&str>) -> Result<ShellInfo, AppError> {
+    let id = id.filter(|id| !id.is_empty());
+
+    // Only enumerate WSL distros when one was asked for; it spawns a process
+    let candidates = match id {
+        Some(id) if id.starts_with(WSL_PREFIX) => wsl_shells(),
+        _ => native_shells(),
+    };
+
+    match id {
+        Some(id) => candidates
+            .into_iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| AppError::NotFound(format!("Shell not available: {}", id))),
+        None => candidates
+            .into_iter()
+            .find(|s| s.is_default)
+            .ok_or_else(|| AppError::NotFound("No shell found on this system".into())),
+    }
+}
+
+// =============================================================================
+// Platform Detection
+// =============================================================================
+
+/// Shells from `$SHELL` and `/etc/shells`, `$SHELL` first and default.
+#[cfg(unix)]
+fn native_shells() -> Vec<ShellInfo> {
+    let listed = std::fs::read_to_string("/etc/shells")
+        .map(|content| parse_etc_shells(&content))
+        .unwrap_or_default();
+    let env_shell = std::env::var("SHELL").ok();
+    unix_shells(env_shell, listed, |path| Path::new(path).is_file())
+}
+
+/// PowerShell 7, Windows PowerShell and cmd, in that order of preference.
+#[cfg(windows)]
+fn native_shells() -> Vec<ShellInfo> {
+    let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".into());
+    let program_files =
+        std::env::var("ProgramFiles").unwrap_or_else(|_| r"C:\Program Files".into());
+    let mut shells = Vec::new();
+
+    let pwsh = find_on_path("pwsh.exe").or_else(|| {
+        let path = Path::new(&program_files).join(r"PowerShell\7\pwsh.exe");
+        path.is_file().then(|| path.to_string_lossy().to_string())
+    });
+    if let Some(pwsh) = pwsh {
+        shells.push(ShellInfo::new("pwsh", "PowerShell 7", &pwsh, &["-NoLogo"]));
+    }
+
+    let powershell = Path::new(&system_root).join(r"System32\WindowsPowerShell\v1.0\powershell.exe");
+    if powershell.is_file() {
+        shells.push(ShellInfo::new(
+            "powershell",
+            "Windows PowerShell",
+            &powershell.to_string_lossy(),
+            &["-NoLogo"],
+        ));
+    }
+
+    let cmd = std::env::var("ComSpec").unwrap_or_else(|_| {
+        Path::new(&system_root)
+            .join(r"System32\cmd.exe")
+            .to_string_lossy()
+            .to_string()
+    });
+    shells.push(ShellInfo::new("cmd", "Command Prompt", &cmd, &[]));
+
+    shells[0].is_default = true;
+    shells
+}
+
+/// First match for `program` in the `PATH` directories.
+pub(crate) fn find_on_path(program: &str) -> Option<String> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+/// One entry per installed WSL distribution.
+#[cfg(windows)]
+fn wsl_shells() -> Vec<ShellInfo> {
+    use std::os::windows::process::CommandExt;
+
+    /// Process creation flag that suppresses the console window
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let output = match std::process::Command::new("wsl.exe")
+        .args(["--list", "--quiet"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        // WSL not installed, or installed without any distribution
+        _ => return Vec::new(),
+    };
+
+    parse_wsl_list(&output.stdout)
+        .into_iter()
+        .map(|distro| {
+            ShellInfo::new(
+                &format!("{}{}", WSL_PREFIX, distro),
+                &format!("WSL: {}", distro),
+                "wsl.exe",
+                &["-d", &distro],
+            )
+        })
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn wsl_shells() -> Vec<ShellInfo> {
+    Vec::new()
+}
+
+// =============================================================================
+// Parsing
+// =============================================================================
+
+/// Shell paths listed in `/etc/shells` (comments and blank lines skipped).
+#[cfg_attr(not(unix), allow(dead_code))]
+fn parse_etc_shells(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Build the unix shell list: `$SHELL` first (the default), then the listed
+/// shells, then `/bin/bash` and `/bin/sh` as fallbacks. Paths that do not
+/// exist and non-interactive entries are dropped.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn unix_shells(
+    env_shell: Option<String>,
+    listed: Vec<String>,
+    exists: impl Fn(&str) -> bool,
+) -> Vec<ShellInfo> {
+    let candidates = env_shell
+        .into_iter()
+        .chain(listed)
+        .chain(["/bin/bash".to_string(), "/bin/sh".to_string()]);
+
+    let mut shells: Vec<ShellInfo> = Vec::new();
+    for path in candidates {
+        let name = Path::new(&path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        if matches!(name.as_str(), "nologin" | "false")
+            || shells.iter().any(|s| s.id == path)
+            || !exists(&path)
+        {
+            continue;
+        }
+        shells.push(ShellInfo::new(&path, &name, &path, &[]));
+    }
+
+    if let Some(first) = shells.first_mut() {
+        first.is_default = true;
+    }
+    shells
+}
+
+/// Parse `wsl.exe --list --quiet` output, which is UTF-16LE with a BOM.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_wsl_list(stdout: &[u8]) -> Vec<String> {
+    let units: Vec<u16> = stdout
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+
+    String::from_utf16_lossy(&units)
+        .lines()
+        .map(|line| line.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}' || c == '\0'))
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_parse_etc_shells() {
+        let content = "# /etc/shells: valid login shells\n/bin/sh\n\n/bin/zsh\n  /usr/bin/fish  \n";
+        assert_eq!(parse_etc_shells(content), vec!["/bin/sh", "/bin/zsh", "/usr/bin/fish"]);
+    }
+
+    #[test]
+    fn test_unix_shells_prefers_env_and_dedups() {
+        let listed = vec![
+            "/bin/sh".to_string(),
+            "/bin/zsh".to_string(),
+            "/usr/sbin/nologin".to_string(),
+            "/opt/missing/fish".to_string(),
+        ];
+        let shells = unix_shells(Some("/bin/zsh".into()), listed, |p| !p.contains("missing"));
+
+        let ids: Vec<&str> = shells.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["/bin/zsh", "/bin/sh", "/bin/bash"]);
+        assert!(shells[0].is_default);
+        assert_eq!(shells[0].name, "zsh");
+        assert!(shells[1..].iter().all(|s| !s.is_default));
+    }
+
+    #[test]
+    fn test_unix_shells_without_env_shell() {
+        let shells = unix_shells(None, Vec::new(), |p| p == "/bin/sh");
+        assert_eq!(shells.len(), 1);
+        assert_eq!(shells[0].id, "/bin/sh");
+        assert!(shells[0].is_default);
+    }
+
+    #[test]
+    fn test_parse_wsl_list() {
+        let out = utf16le("\u{feff}Ubuntu-22.04\r\ndocker-desktop\r\n\r\n");
+        assert_eq!(parse_wsl_list(&out), vec!["Ubuntu-22.04", "docker-desktop"]);
+        assert!(parse_wsl_list(&[]).is_empty());
+    }
+}
@@ -0,0 +1,255 @@
+//! Ordered teardown on app exit.
+//!
+//! Tauri ends the process with `std::process::exit()` right after
+//! `RunEvent::Exit`, which skips `Drop`, so everything that has to be
+//! stopped or flushed is stopped here, in order:
+//!
+//! 1. **control**: the control socket removed, so scripts stop reaching
+//!    the app while it tears down
+//! 2. **capture**: the screen and camera streams (waiting for their capture,
+//!    WebSocket and audio threads) and any timelapse
+//! 3. **metrics**: the sampler, closing the metrics database
+//! 4. **sessions**: PTY transcripts and history saved, then every session
+//!    killed
+//! 5. **services**: managed services stopped and tunnels closed
+//! 6. **logs**: the log file flushed
+//!
+//! Each stage runs on its own thread with a timeout, so one that hangs
+//! (e.g. a capture thread stuck in the OS) delays exit by at most its
+//! timeout and later stages still run. One summary line with every stage's
+//! outcome is logged before the final flush.
+
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::camera::{self, CameraState};
+use crate::control;
+use crate::metrics::MetricsState;
+use crate::pty::{self, PtyState};
+use crate::services::{self, ServicesState};
+use crate::streaming::{self, StreamingState};
+use crate::timelapse::{self, TimelapseState};
+use crate::tunnels::{self, TunnelsState};
+use crate::{history, AppError};
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Removing the socket file
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Capture threads poll their shutdown flag every 100ms; the WebSocket
+/// server closes its connections first
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Waits for a sample being written
+const METRICS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Saving transcripts writes one file per session
+const SESSIONS_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SERVICES_TIMEOUT: Duration = Duration::from_secs(3);
+
+const LOGS_TIMEOUT: Duration = Duration::from_secs(2);
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// How a stage ended.
+#[derive(Debug, Clone, PartialEq)]
+enum StageResult {
+    /// Finished, with a short note of what it did
+    Done(String),
+    Failed(String),
+    TimedOut,
+}
+
+/// A teardown stage: its name in the summary, timeout and body.
+type Stage = (&'static str, Duration, fn(&AppHandle) -> Result<String, AppError>);
+
+#[derive(Debug, Clone)]
+struct StageOutcome {
+    name: &'static str,
+    result: StageResult,
+    elapsed: Duration,
+}
+
+// =============================================================================
+// Stages
+// =============================================================================
+
+/// Run `stage` on its own thread, waiting at most `timeout`. A stage that
+/// times out is left running; the process exits soon anyway.
+fn run_stage<F>(name: &'static str, timeout: Duration, stage: F) -> StageOutcome
+where
+    F: FnOnce() -> Result<String, AppError> + Send + 'static,
+{
+    let started = Instant::now();
+    let (done_tx, done_rx) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name(format!("shutdown-{}", name))
+        .spawn(move || {
+            let _ = done_tx.send(stage());
+        });
+    let result = match spawned {
+        Err(e) => StageResult::Failed(format!("failed to start: {}", e)),
+        Ok(_) => match done_rx.recv_timeout(timeout) {
+            Ok(Ok(note)) => StageResult::Done(note),
+            Ok(Err(e)) => StageResult::Failed(e.to_string()),
+            Err(mpsc::RecvTimeoutError::Timeout) => StageResult::TimedOut,
+            // The stage panicked (the panic hook has logged it)
+            Err(mpsc::RecvTimeoutError::Disconnected) => StageResult::Failed("panicked".into()),
+        },
+    };
+    let outcome = StageOutcome {
+        name,
+        result,
+        elapsed: started.elapsed(),
+    };
+    if outcome.result == StageResult::TimedOut {
+        log::warn!("Shutdown: {} did not finish within {:?}", name, timeout);
+    }
+    outcome
+}
+
+fn stop_control(_app: &AppHandle) -> Result<String, AppError> {
+    Ok(if control::remove_socket() { "socket removed" } else { "no socket" }.into())
+}
+
+fn stop_capture(app: &AppHandle) -> Result<String, AppError> {
+    let timelapse = timelapse::stop_all(app.state::<TimelapseState>().inner());
+    let state = app.state::<StreamingState>();
+    let stream = match tauri::async_runtime::block_on(streaming::stop_stream(state.inner())) {
+        Ok(()) => true,
+        Err(AppError::NotFound(_)) => false,
+        Err(e) => return Err(e),
+    };
+    let camera_state = app.state::<CameraState>();
+    let camera = match tauri::async_runtime::block_on(camera::stop_camera(camera_state.inner())) {
+        Ok(()) => true,
+        Err(AppError::NotFound(_)) => false,
+        Err(e) => return Err(e),
+    };
+    let stopped: Vec<&str> = [(stream, "stream"), (camera, "camera"), (timelapse, "timelapse")]
+        .into_iter()
+        .filter_map(|(stopped, name)| stopped.then_some(name))
+        .collect();
+    Ok(if stopped.is_empty() {
+        "nothing running".into()
+    } else {
+        format!("{} stopped", stopped.join(", "))
+    })
+}
+
+fn stop_metrics(app: &AppHandle) -> Result<String, AppError> {
+    app.state::<MetricsState>().close();
+    Ok("sampler stopped".into())
+}
+
+fn stop_sessions(app: &AppHandle) -> Result<String, AppError> {
+    let state = app.state::<PtyState>();
+    pty::save_transcripts(app, state.inner());
+    history::end_all(app);
+    let killed = pty::kill_all_sessions(state.inner());
+    Ok(format!("{} session(s) killed", killed))
+}
+
+fn stop_services(app: &AppHandle) -> Result<String, AppError> {
+    services::stop_all(app.state::<ServicesState>().inner());
+    let tunnels = tunnels::close_all(app.state::<TunnelsState>().inner());
+    Ok(format!("services stopped, {} tunnel(s) closed", tunnels))
+}
+
+/// One line with every stage's outcome and the total time.
+fn summary(outcomes: &[StageOutcome], total: Duration) -> String {
+    let stages: Vec<String> = outcomes
+        .iter()
+        .map(|o| {
+            let ms = o.elapsed.as_millis();
+            match &o.result {
+                StageResult::Done(note) => format!("{}: {} ({} ms)", o.name, note, ms),
+                StageResult::Failed(e) => format!("{}: failed: {} ({} ms)", o.name, e, ms),
+                StageResult::TimedOut => format!("{}: timed out after {} ms", o.name, ms),
+            }
+        })
+        .collect();
+    format!("Shutdown finished in {} ms; {}", total.as_millis(), stages.join("; "))
+}
+
+/// Tear everything down in order. Called on `RunEvent::Exit`.
+pub fn run(app: &AppHandle) {
+    let started = Instant::now();
+    log::info!("Shutting down");
+
+    let stages: [Stage; 5] = [
+        ("control", CONTROL_TIMEOUT, stop_control),
+        ("capture", CAPTURE_TIMEOUT, stop_capture),
+        ("metrics", METRICS_TIMEOUT, stop_metrics),
+        ("sessions", SESSIONS_TIMEOUT, stop_sessions),
+        ("services", SERVICES_TIMEOUT, stop_services),
+    ];
+    let outcomes: Vec<StageOutcome> = stages
+        .into_iter()
+        .map(|(name, timeout, stage)| {
+            let app = app.clone();
+            run_stage(name, timeout, move || stage(&app))
+        })
+        .collect();
+    log::info!("{}", summary(&outcomes, started.elapsed()));
+
+    // Also writes the summary of a run of repeated records still pending
+    run_stage("logs", LOGS_TIMEOUT, || {
+        log::logger().flush();
+        Ok(String::new())
+    });
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_stage() {
+        let done = run_stage("ok", Duration::from_secs(5), || Ok("fine".into()));
+        assert_eq!(done.result, StageResult::Done("fine".into()));
+
+        let failed = run_stage("err", Duration::from_secs(5), || {
+            Err(AppError::Io("disk gone".into()))
+        });
+        assert!(matches!(failed.result, StageResult::Failed(_)));
+
+        let slow = run_stage("slow", Duration::from_millis(20), || {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(String::new())
+        });
+        assert_eq!(slow.result, StageResult::TimedOut);
+    }
+
+    #[test]
+    fn test_summary() {
+        let outcome = |name, result, ms| StageOutcome {
+            name,
+            result,
+            elapsed: Duration::from_millis(ms),
+        };
+        let line = summary(
+            &[
+                outcome("capture", StageResult::Done("stream stopped".into()), 120),
+                outcome("metrics", StageResult::TimedOut, 2000),
+            ],
+            Duration::from_millis(2130),
+        );
+        assert_eq!(
+            line,
+            "Shutdown finished in 2130 ms; capture: stream stopped (120 ms); \
+             metrics: timed out after 2000 ms"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! Access log of the streaming server.
+//!
+//! Every WebSocket connection attempt is recorded in `stream_access.log`
+//! next to synthia.log, one JSON object per line: who connected (peer and
+//! Origin), whether they were let in, and for accepted viewers how long they
+//! watched and how much was sent. It is kept apart from the app log so it
+//! is not rotated away by debug noise and can be audited on its own.
+
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// File name of the access log inside the app log directory
+const ACCESS_LOG_FILE_NAME: &str = "stream_access.log";
+
+/// Past this size the log is moved to `stream_access.log.1` (replacing it)
+const MAX_ACCESS_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Entries returned by `get_stream_access_log` when `limit` is omitted
+const DEFAULT_ACCESS_LOG_LIMIT: usize = 200;
+
+/// Upper bound for `get_stream_access_log` `limit`
+const MAX_ACCESS_LOG_LIMIT: usize = 5000;
+
+/// Where entries are written (set by `init`)
+static ACCESS_LOG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Serializes appends and rotation between client tasks
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// What became of a connection attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessOutcome {
+    /// Connected and streamed; the entry is written when the viewer leaves
+    Accepted,
+    /// Peer address not allowed by `stream.allowed_peers`
+    RejectedPeer,
+    /// `stream.max_clients` viewers were already connected
+    RejectedLimit,
+    /// Origin header failed the DNS-rebinding check
+    RejectedOrigin,
+    /// Unknown `format` query parameter
+    RejectedFormat,
+    /// Not a valid WebSocket handshake (or the peer hung up during it)
+    HandshakeFailed,
+}
+
+/// One line of the access log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamAccessEntry {
+    /// RFC 3339 time of the attempt (for accepted viewers, when they left)
+    pub timestamp: String,
+    /// Remote `ip:port`
+    pub peer: String,
+    /// Origin header, when the handshake got that far and sent one
+    pub origin: Option<String>,
+    pub outcome: AccessOutcome,
+    /// Id in `get_stream_clients` (accepted viewers only)
+    pub client_id: Option<u64>,
+    /// How long the viewer was connected (accepted viewers only)
+    pub duration_ms: Option<u64>,
+    /// Video and audio payload bytes sent
+    pub bytes_sent: u64,
+    pub frames_sent: u64,
+}
+
+impl StreamAccessEntry {
+    /// An attempt from `peer` that ended with `outcome` before any data was sent.
+    pub(crate) fn new(peer: SocketAddr, outcome: AccessOutcome) -> Self {
+        Self {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            peer: peer.to_string(),
+            origin: None,
+            outcome,
+            client_id: None,
+            duration_ms: None,
+            bytes_sent: 0,
+            frames_sent: 0,
+        }
+    }
+}
+
+// =============================================================================
+// Writing
+// =============================================================================
+
+/// Start writing the access log next to synthia.log.
+pub fn init(app: &AppHandle) {
+    match app.path().app_log_dir() {
+        Ok(dir) => {
+            let _ = ACCESS_LOG_PATH.set(dir.join(ACCESS_LOG_FILE_NAME));
+        }
+        Err(e) => log::error!("Could not determine stream access log path: {}", e),
+    }
+}
+
+/// Append an entry. Failures are logged, never returned: a full disk must
+/// not take the stream down.
+pub(crate) fn record(entry: &StreamAccessEntry) {
+    let Some(path) = ACCESS_LOG_PATH.get() else {
+        return;
+    };
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = append_entry(path, entry) {
+        log::warn!("Failed to write stream access log: {}", e);
+    }
+}
+
+fn append_entry(path: &Path, entry: &StreamAccessEntry) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    if std::fs::metadata(path).is_ok_and(|m| m.len() >= MAX_ACCESS_LOG_BYTES) {
+        std::fs::rename(path, rotated_path(path))?;
+    }
+    let mut line = serde_json::to_string(entry)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+// =============================================================================
+// Reading
+// =============================================================================
+
+/// The newest `limit` entries, newest first, including the rotated file.
+/// Unparseable lines (e.g. cut short by a crash) are skipped.
+fn read_entries(path: &Path, limit: usize) -> Vec<StreamAccessEntry> {
+    let mut entries: Vec<StreamAccessEntry> = [rotated_path(path), path.to_path_buf()]
+        .iter()
+        .filter_map(|p| std::fs::read_to_string(p).ok())
+        .flat_map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    entries.reverse();
+    entries.truncate(limit);
+    entries
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Recent stream connection attempts, newest first.
+///
+/// # Arguments
+/// * `limit` - Entries to return (default 200, max 5000)
+#[tauri::command]
+pub async fn get_stream_access_log(
+    limit: Option<usize>,
+) -> Result<Vec<StreamAccessEntry>, AppError> {
+    let limit = limit.unwrap_or(DEFAULT_ACCESS_LOG_LIMIT);
+    if !(1..=MAX_ACCESS_LOG_LIMIT).contains(&limit) {
+        return Err(AppError::Validation(format!(
+            "limit must be 1-{}, got: {}",
+            MAX_ACCESS_LOG_LIMIT, limit
+        )));
+    }
+    let path = ACCESS_LOG_PATH
+        .get()
+        .cloned()
+        .ok_or_else(|| AppError::Internal("Could not determine stream access log path".into()))?;
+
+    tokio::task::spawn_blocking(move || read_entries(&path, limit))
+        .await
+        .map_err(|e| AppError::Internal(format!("Access log read task failed: {}", e)))
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(port: u16, outcome: AccessOutcome) -> StreamAccessEntry {
+        StreamAccessEntry::new(SocketAddr::from(([192, 168, 1, 20], port)), outcome)
+    }
+
+    #[test]
+    fn test_append_and_read_newest_first() {
+        let dir = std::env::temp_dir().join(format!("synthia-access-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(ACCESS_LOG_FILE_NAME);
+        assert!(read_entries(&path, 10).is_empty());
+
+        let mut accepted = entry(5001, AccessOutcome::Accepted);
+        accepted.origin = Some("http://localhost:1420".into());
+        accepted.client_id = Some(3);
+        accepted.duration_ms = Some(12_000);
+        accepted.bytes_sent = 4096;
+        accepted.frames_sent = 2;
+        append_entry(&path, &entry(5000, AccessOutcome::RejectedPeer)).unwrap();
+        append_entry(&path, &accepted).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"truncated\n")
+            .unwrap();
+        append_entry(&path, &entry(5002, AccessOutcome::RejectedOrigin)).unwrap();
+
+        let read = read_entries(&path, 10);
+        let ports: Vec<&str> = read.iter().map(|e| e.peer.as_str()).collect();
+        assert_eq!(ports, vec!["192.168.1.20:5002", "192.168.1.20:5001", "192.168.1.20:5000"]);
+        assert_eq!(read[1], accepted);
+        assert_eq!(read_entries(&path, 1).len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rotation_keeps_previous_file_readable() {
+        let dir = std::env::temp_dir().join(format!("synthia-access-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(ACCESS_LOG_FILE_NAME);
+        append_entry(&path, &entry(6000, AccessOutcome::HandshakeFailed)).unwrap();
+        let file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.set_len(MAX_ACCESS_LOG_BYTES).unwrap();
+
+        append_entry(&path, &entry(6001, AccessOutcome::RejectedLimit)).unwrap();
+        assert!(rotated_path(&path).exists());
+        let read = read_entries(&path, 10);
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].outcome, AccessOutcome::RejectedLimit);
+        assert_eq!(read[1].outcome, AccessOutcome::HandshakeFailed);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,319 @@
+//! TLS for the streaming server (`wss://`).
+//!
+//! With `stream.tls` on, every stream connection is TLS-terminated before the
+//! WebSocket handshake, so frames viewed over the LAN are not plaintext. The
+//! certificate is either the user's own (`stream.tls_cert_path` and
+//! `stream.tls_key_path`, PEM) or a self-signed one kept in the app data
+//! directory, created on first use or with `generate_stream_cert`.
+//!
+//! Browsers do not trust the self-signed certificate, so viewers should
+//! compare its SHA-256 fingerprint (`get_stream_cert_fingerprint`) with the
+//! one their browser shows before accepting it.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{self, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+use crate::settings::{SettingsState, StreamSettings};
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Directory of the generated certificate inside the app data directory
+const CERT_DIR_NAME: &str = "stream-tls";
+const CERT_FILE_NAME: &str = "cert.pem";
+const KEY_FILE_NAME: &str = "key.pem";
+
+/// Names always covered by the generated certificate
+const DEFAULT_CERT_NAMES: [&str; 3] = ["localhost", "127.0.0.1", "::1"];
+
+/// Most extra names accepted by `generate_stream_cert`
+const MAX_CERT_NAMES: usize = 32;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Where the stream certificate comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertSource {
+    /// Self-signed, generated by Synthia
+    Generated,
+    /// `stream.tls_cert_path` / `stream.tls_key_path`
+    User,
+}
+
+/// Certificate used by TLS streams, returned by `generate_stream_cert` and
+/// `get_stream_cert_fingerprint`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StreamCertInfo {
+    pub source: CertSource,
+    pub cert_path: String,
+    /// SHA-256 of the DER certificate as colon-separated hex
+    /// (`AB:CD:...`), the form browsers show
+    pub fingerprint_sha256: String,
+}
+
+/// Certificate and key files a TLS stream would use.
+struct CertFiles {
+    source: CertSource,
+    cert: PathBuf,
+    key: PathBuf,
+}
+
+// =============================================================================
+// Certificates
+// =============================================================================
+
+fn generated_cert_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::Internal(format!("No app data directory: {}", e)))?;
+    Ok(data_dir.join(CERT_DIR_NAME))
+}
+
+/// The user's certificate if configured, else the generated one.
+fn cert_files(app: &AppHandle, settings: &StreamSettings) -> Result<CertFiles, AppError> {
+    if !settings.tls_cert_path.is_empty() {
+        return Ok(CertFiles {
+            source: CertSource::User,
+            cert: PathBuf::from(&settings.tls_cert_path),
+            key: PathBuf::from(&settings.tls_key_path),
+        });
+    }
+    let dir = generated_cert_dir(app)?;
+    Ok(CertFiles {
+        source: CertSource::Generated,
+        cert: dir.join(CERT_FILE_NAME),
+        key: dir.join(KEY_FILE_NAME),
+    })
+}
+
+/// Check extra certificate names: hostnames or IP addresses.
+fn check_cert_names(names: &[String]) -> Result<(), AppError> {
+    if names.len() > MAX_CERT_NAMES {
+        return Err(AppError::Validation(format!(
+            "At most {} certificate names are allowed, got: {}",
+            MAX_CERT_NAMES,
+            names.len()
+        )));
+    }
+    for name in names {
+        let valid = !name.is_empty()
+            && name.len() <= 253
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '*'));
+        if !valid {
+            return Err(AppError::Validation(format!(
+                "Invalid certificate name: {:?}",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Create a self-signed certificate for `names` (plus localhost, loopback
+/// and this machine's hostname) in `dir`, replacing any previous one.
+fn generate_cert(dir: &Path, names: &[String]) -> Result<PathBuf, AppError> {
+    let mut subject_alt_names: Vec<String> =
+        DEFAULT_CERT_NAMES.iter().map(|n| n.to_string()).collect();
+    subject_alt_names.extend(sysinfo::System::host_name());
+    for name in names {
+        if !subject_alt_names.contains(name) {
+            subject_alt_names.push(name.clone());
+        }
+    }
+
+    let certified = rcgen::generate_simple_self_signed(subject_alt_names)
+        .map_err(|e| AppError::Internal(format!("Failed to generate certificate: {}", e)))?;
+
+    std::fs::create_dir_all(dir)
+        .map_err(|e| AppError::Io(format!("Failed to create certificate directory: {}", e)))?;
+    let cert_path = dir.join(CERT_FILE_NAME);
+    let key_path = dir.join(KEY_FILE_NAME);
+    write_private(&key_path, certified.key_pair.serialize_pem().as_bytes())?;
+    std::fs::write(&cert_path, certified.cert.pem())
+        .map_err(|e| AppError::Io(format!("Failed to write certificate: {}", e)))?;
+    Ok(cert_path)
+}
+
+/// Write a file readable only by the current user.
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), AppError> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut f| f.write_all(contents))
+        .map_err(|e| AppError::Io(format!("Failed to write private key: {}", e)))
+}
+
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, AppError> {
+    let file = std::fs::File::open(path).map_err(|e| {
+        AppError::Io(format!("Failed to open certificate {}: {}", path.display(), e))
+    })?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::Validation(format!("Invalid certificate {}: {}", path.display(), e)))?;
+    if certs.is_empty() {
+        return Err(AppError::Validation(format!(
+            "No certificate found in {}",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &Path) -> Result<PrivateKeyDer<'static>, AppError> {
+    let file = std::fs::File::open(path).map_err(|e| {
+        AppError::Io(format!("Failed to open private key {}: {}", path.display(), e))
+    })?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| AppError::Validation(format!("Invalid private key {}: {}", path.display(), e)))?
+        .ok_or_else(|| AppError::Validation(format!("No private key found in {}", path.display())))
+}
+
+/// Colon-separated uppercase hex SHA-256 of a DER certificate.
+fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn cert_info(files: &CertFiles) -> Result<StreamCertInfo, AppError> {
+    let certs = read_certs(&files.cert)?;
+    Ok(StreamCertInfo {
+        source: files.source,
+        cert_path: files.cert.to_string_lossy().into_owned(),
+        fingerprint_sha256: fingerprint(&certs[0]),
+    })
+}
+
+/// TLS acceptor for a stream, generating the self-signed certificate first
+/// if no certificate exists yet. Blocking (file I/O, key generation).
+pub(crate) fn load_acceptor(
+    app: &AppHandle,
+    settings: &StreamSettings,
+) -> Result<(TlsAcceptor, StreamCertInfo), AppError> {
+    let files = cert_files(app, settings)?;
+    if files.source == CertSource::Generated && !(files.cert.exists() && files.key.exists()) {
+        log::info!("Generating self-signed stream certificate");
+        generate_cert(&generated_cert_dir(app)?, &[])?;
+    }
+
+    let certs = read_certs(&files.cert)?;
+    let key = read_key(&files.key)?;
+    let info = StreamCertInfo {
+        source: files.source,
+        cert_path: files.cert.to_string_lossy().into_owned(),
+        fingerprint_sha256: fingerprint(&certs[0]),
+    };
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| AppError::Validation(format!("Unusable stream certificate: {}", e)))?;
+    Ok((TlsAcceptor::from(Arc::new(config)), info))
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Generate a new self-signed stream certificate, replacing the previous one.
+///
+/// Covers localhost, the loopback addresses and this machine's hostname, plus
+/// `names` (e.g. a LAN IP like `192.168.1.20` or `devbox.local`). A running
+/// stream keeps its certificate until restarted. When `stream.tls_cert_path`
+/// is set the user's certificate stays in use.
+#[tauri::command]
+pub async fn generate_stream_cert(
+    app: AppHandle,
+    names: Option<Vec<String>>,
+) -> Result<StreamCertInfo, AppError> {
+    let names = names.unwrap_or_default();
+    check_cert_names(&names)?;
+    let dir = generated_cert_dir(&app)?;
+
+    tokio::task::spawn_blocking(move || {
+        let cert = generate_cert(&dir, &names)?;
+        log::info!("Generated stream certificate {:?}", cert);
+        cert_info(&CertFiles {
+            source: CertSource::Generated,
+            key: dir.join(KEY_FILE_NAME),
+            cert,
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Certificate task failed: {}", e)))?
+}
+
+/// Fingerprint of the certificate TLS streams use (the user's if
+/// configured, else the generated one). Fails with `not_found` if no
+/// certificate was generated yet.
+#[tauri::command]
+pub async fn get_stream_cert_fingerprint(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+) -> Result<StreamCertInfo, AppError> {
+    let files = cert_files(&app, &settings.get().stream)?;
+    if !files.cert.exists() {
+        return Err(AppError::NotFound(format!(
+            "No stream certificate at {}",
+            files.cert.display()
+        )));
+    }
+    tokio::task::spawn_blocking(move || cert_info(&files))
+        .await
+        .map_err(|e| AppError::Internal(format!("Certificate task failed: {}", e)))?
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_cert_names() {
+        assert!(check_cert_names(&["192.168.1.20".into(), "devbox.local".into()]).is_ok());
+        assert!(check_cert_names(&["fe80::1".into()]).is_ok());
+        assert!(check_cert_names(&["".into()]).is_err());
+        assert!(check_cert_names(&["bad name".into()]).is_err());
+    }
+
+    #[test]
+    fn test_generated_cert_loads() {
+        let dir = std::env::temp_dir().join(format!("synthia-tls-{}", uuid::Uuid::new_v4()));
+        let cert = generate_cert(&dir, &["devbox.local".into()]).unwrap();
+        let certs = read_certs(&cert).unwrap();
+        assert!(read_key(&dir.join(KEY_FILE_NAME)).is_ok());
+
+        let fp = fingerprint(&certs[0]);
+        assert_eq!(fp.len(), 32 * 3 - 1);
+        assert!(fp.split(':').all(|b| b.len() == 2));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,27 +1,132 @@
-use std::net::SocketAddr;
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
 use futures_util::{SinkExt, StreamExt};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpListener;
-use tokio::sync::{watch, Mutex};
+use tokio::sync::{broadcast, oneshot, watch, Mutex};
+use tokio_rustls::TlsAcceptor;
 use tokio_tungstenite::tungstenite::Message;
 
-use scap::frame::{Frame, FrameType, VideoFrame};
+use base64::Engine;
+use scap::frame::{BGRAFrame, Frame, FrameType, VideoFrame};
 use scap::capturer::{Capturer, Options, Resolution};
 
+use crate::app_capture::{self, AppSelector};
+use crate::audio;
+use crate::delta::{self, DeltaEncoder, Encoded};
+use crate::events;
+use crate::metrics;
+use crate::pixel_format::PixelFormat;
+use crate::power;
+use crate::remote_input::{InputEvent, RemoteInput};
+use crate::settings::{SettingsState, StreamSettings};
+use crate::stream_access::{self, AccessOutcome, StreamAccessEntry};
+use crate::stream_tls;
+use crate::AppError;
+
 // =============================================================================
 // Constants
 // =============================================================================
 
 /// Allowed streaming port range (unprivileged, dedicated to streaming)
-const STREAM_PORT_MIN: u16 = 9100;
-const STREAM_PORT_MAX: u16 = 9199;
+pub(crate) const STREAM_PORT_MIN: u16 = 9100;
+pub(crate) const STREAM_PORT_MAX: u16 = 9199;
+
+/// Port value that asks for the first free port of the range
+pub(crate) const STREAM_PORT_AUTO: u16 = 0;
+
+/// Longest a viewer may take to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Maximum FPS to prevent resource exhaustion
-const MAX_FPS: u32 = 30;
+pub(crate) const MAX_FPS: u32 = 30;
+
+/// Allowed `max_width` range a stream may be downscaled to (8K is the widest
+/// display scap reports)
+const STREAM_MIN_WIDTH: u32 = 320;
+const STREAM_MAX_WIDTH: u32 = 7680;
+
+/// Allowed thumbnail width range for the display picker previews
+const THUMBNAIL_MIN_WIDTH: u32 = 64;
+const THUMBNAIL_MAX_WIDTH: u32 = 1280;
+
+/// JPEG quality for display thumbnails (small previews, favour size)
+const THUMBNAIL_JPEG_QUALITY: u8 = 70;
+
+/// Frames to pull before giving up on a one-shot capture. The first frames
+/// after starting a capturer are frequently empty on external displays.
+const SNAPSHOT_MAX_ATTEMPTS: usize = 10;
+
+/// First byte of every binary stream message identifies its payload:
+/// video is `[type][u32 seq][u64 capture µs][u16 width][u16 height][pixels]`
+/// (little-endian), audio is `[type][u8 channels][Opus packet]` (48 kHz,
+/// 20 ms frames). Video deltas carry only changed tiles (layout in `delta.rs`).
+/// Pixels are BGRA unless the client asked for another `PixelFormat` with
+/// `?format=` when connecting.
+pub(crate) const MSG_TYPE_VIDEO: u8 = 0x01;
+pub(crate) const MSG_TYPE_AUDIO: u8 = 0x02;
+pub(crate) const MSG_TYPE_VIDEO_DELTA: u8 = 0x03;
+
+/// Length of the `[type][u32 seq][u64 capture µs]` prefix of video messages
+pub(crate) const VIDEO_STAMP_LEN: usize = 13;
+
+/// Latency samples kept per client for `get_stream_latency`
+const LATENCY_WINDOW: usize = 300;
+
+/// Seconds between forced video keyframes (bounds how long a glitch persists)
+const KEYFRAME_INTERVAL_SECS: u32 = 2;
+
+/// Frames a local (IPC) viewer may leave unacknowledged before updates are
+/// held back; channel sends never wait for the webview, unlike a socket
+const LOCAL_MAX_IN_FLIGHT: usize = 2;
+
+/// An unacknowledged frame to a local viewer counts as lost after this long
+const LOCAL_ACK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Opus packets buffered per client before the slowest client starts
+/// skipping (~1.3 s at 20 ms per packet)
+const AUDIO_CHANNEL_CAPACITY: usize = 64;
+
+/// How often a pending stream start re-checks screen capture permission
+const PERMISSION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Give up on a pending stream start if permission is not granted by then
+const PERMISSION_WAIT_TIMEOUT: Duration = Duration::from_secs(300);
 
+/// How often a stream with an idle policy checks its client count
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often an app stream re-lists windows so ones opened by other apps
+/// since the last check are excluded too
+const APP_WINDOWS_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Consecutive capture failures (frame errors or failed rebuilds) after
+/// which the capture thread gives up
+const CAPTURE_MAX_RETRIES: u32 = 6;
+
+/// Backoff before the first capturer rebuild, doubled for each further one
+const CAPTURE_RETRY_BASE: Duration = Duration::from_millis(500);
+
+/// Longest backoff between capturer rebuilds
+const CAPTURE_RETRY_MAX: Duration = Duration::from_secs(8);
+
+/// CPU time of the running capture thread, refreshed every loop iteration
+/// (for `get_app_resource_usage`)
+static CAPTURE_CPU_NS: AtomicU64 = AtomicU64::new(0);
+static CAPTURE_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// System Settings pane for the Screen Recording permission
+#[cfg(target_os = "macos")]
+const SCREEN_CAPTURE_SETTINGS_URL: &str =
+    "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture";
 
 /// Allowed WebSocket Origin values for the Tauri webview
 const ALLOWED_ORIGINS: &[&str] = &[
@@ -43,6 +148,270 @@ pub struct StreamStatus {
     pub quality: i32,
     pub clients: usize,
     pub display_id: Option<u32>,
+    /// Whether system audio is multiplexed into the stream
+    pub audio: bool,
+    /// Start requested, waiting for the user to grant screen capture permission
+    pub permission_pending: bool,
+    /// Frames wider than this are downscaled before sending (None = native size)
+    pub max_width: Option<u32>,
+    pub scaling: ScalingQuality,
+    /// Cursor drawn into captured frames
+    pub show_cursor: bool,
+    /// Clicks highlighted in captured frames (macOS only)
+    pub show_highlight: bool,
+    pub resolution: CaptureResolution,
+    /// Application shown by an app stream (None = the whole display)
+    pub target_app: Option<AppSelector>,
+    /// Served over TLS: viewers connect with `wss://`
+    pub tls: bool,
+    /// SHA-256 fingerprint of the certificate served, when `tls`
+    pub cert_fingerprint: Option<String>,
+    /// Viewers may be granted keyboard and mouse control (`stream.remote_control`)
+    pub remote_control: bool,
+    pub client_stats: Vec<StreamClientInfo>,
+}
+
+/// Resampling filter used when a stream is downscaled to `max_width`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalingQuality {
+    /// Cheapest; thin text shimmers as content moves
+    Nearest,
+    /// Averages every covered source pixel; keeps text legible
+    #[default]
+    Area,
+}
+
+/// Output size requested from the capturer. A fixed size keeps the display's
+/// aspect ratio; only macOS honours it, other platforms capture natively.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaptureResolution {
+    /// The display's native size
+    #[default]
+    #[serde(rename = "captured")]
+    Captured,
+    #[serde(rename = "480p")]
+    P480,
+    #[serde(rename = "720p")]
+    P720,
+    #[serde(rename = "1080p")]
+    P1080,
+    #[serde(rename = "1440p")]
+    P1440,
+    #[serde(rename = "2160p")]
+    P2160,
+    #[serde(rename = "4320p")]
+    P4320,
+}
+
+impl CaptureResolution {
+    fn to_scap(self) -> Resolution {
+        match self {
+            Self::Captured => Resolution::Captured,
+            Self::P480 => Resolution::_480p,
+            Self::P720 => Resolution::_720p,
+            Self::P1080 => Resolution::_1080p,
+            Self::P1440 => Resolution::_1440p,
+            Self::P2160 => Resolution::_2160p,
+            Self::P4320 => Resolution::_4320p,
+        }
+    }
+}
+
+/// Screen capture permission state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapturePermissionStatus {
+    Granted,
+    NotGranted,
+    /// Screen capture is not available on this platform at all
+    Unsupported,
+}
+
+/// Payload of the `stream-auto-stopped` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamAutoStopped {
+    pub port: u16,
+    /// Seconds the stream ran without clients before it was stopped
+    pub idle_secs: u64,
+}
+
+/// Payload of the `stream-degraded` event, emitted when frame capture fails
+/// and the capturer is being rebuilt.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamDegraded {
+    pub error: String,
+    /// Rebuilds attempted before giving up and stopping capture
+    pub max_retries: u32,
+}
+
+/// Payload of the `stream-recovered` event, emitted when frames flow again
+/// after `stream-degraded`.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamRecovered {
+    /// Capturer rebuilds it took
+    pub attempts: u32,
+    /// Time without frames
+    pub downtime_ms: u64,
+}
+
+/// Sequence number and capture time carried by every video message, so
+/// clients can report end-to-end latency (see `get_stream_latency`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct FrameStamp {
+    pub seq: u64,
+    /// Capture time in microseconds since the Unix epoch
+    pub capture_us: u64,
+}
+
+impl FrameStamp {
+    fn now(seq: u64) -> Self {
+        let capture_us = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or(0);
+        Self { seq, capture_us }
+    }
+
+    /// Write the `[msg_type][u32 seq][u64 capture µs]` message prefix.
+    pub(crate) fn write_header(&self, msg_type: u8, buf: &mut Vec<u8>) {
+        buf.push(msg_type);
+        // Wraps after ~4.5 years at 30 fps; clients only compare neighbours
+        buf.extend_from_slice(&(self.seq as u32).to_le_bytes());
+        buf.extend_from_slice(&self.capture_us.to_le_bytes());
+    }
+}
+
+/// Text message a viewer sends back over the stream WebSocket.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    /// A video message arrived; `capture_us` is echoed from its header and
+    /// `received_us` is the viewer's clock (µs since the Unix epoch)
+    FrameAck {
+        seq: u64,
+        capture_us: u64,
+        received_us: u64,
+    },
+    /// Change the stream's downscale width (null = native size)
+    SetMaxWidth { max_width: Option<u32> },
+    /// Ask the user for keyboard and mouse control (see `remote_input`)
+    RequestControl,
+    /// Keyboard or mouse input, injected only while the viewer has control
+    Input { event: InputEvent },
+}
+
+/// Text message the server sends a viewer when its control grant changes.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    Control { allowed: bool },
+}
+
+/// Capture-to-receive latency over the most recent samples, in ms.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub last_ms: f64,
+    pub avg_ms: f64,
+    pub min_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    /// Summarize samples in arrival order (the last one is the newest).
+    fn from_samples<'a>(samples: impl IntoIterator<Item = &'a f64>) -> Self {
+        let mut sorted: Vec<f64> = samples.into_iter().copied().collect();
+        let Some(&last_ms) = sorted.last() else {
+            return Self::default();
+        };
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let n = sorted.len();
+        let pct = |p: f64| sorted[((n - 1) as f64 * p).round() as usize];
+        Self {
+            samples: n,
+            last_ms,
+            avg_ms: sorted.iter().sum::<f64>() / n as f64,
+            min_ms: sorted[0],
+            p50_ms: pct(0.5),
+            p95_ms: pct(0.95),
+            max_ms: sorted[n - 1],
+        }
+    }
+}
+
+/// Latency reported by one viewer.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientLatency {
+    pub id: u64,
+    pub addr: String,
+    /// Sequence number of the last acknowledged frame
+    pub last_seq: u64,
+    pub stats: LatencyStats,
+}
+
+/// End-to-end latency returned by `get_stream_latency`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StreamLatency {
+    pub active: bool,
+    /// All clients' samples combined
+    pub overall: LatencyStats,
+    pub clients: Vec<ClientLatency>,
+}
+
+/// Per-client statistics for a connected stream viewer
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamClientInfo {
+    pub id: u64,
+    pub addr: String,
+    pub connected_at: String,
+    pub frames_sent: u64,
+    /// Video and audio payload bytes
+    pub bytes_sent: u64,
+    /// Average time for a frame send to complete (socket backpressure), in ms
+    pub avg_send_latency_ms: f64,
+    /// Pixel format negotiated at connect
+    pub format: PixelFormat,
+    /// Granted keyboard and mouse control with `set_stream_client_control`
+    pub control: bool,
+    pub transport: ClientTransport,
+}
+
+/// How a viewer receives the stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientTransport {
+    WebSocket,
+    /// The app's own webview, over a Tauri IPC channel (`subscribe_local_frames`)
+    Ipc,
+}
+
+/// Capture pipeline counters returned by `get_capture_stats`. Rates and
+/// averages cover the whole time since the stream started.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CaptureStats {
+    pub active: bool,
+    pub uptime_secs: f64,
+    /// Frames delivered by the OS capturer
+    pub frames_captured: u64,
+    /// Dropped: zero-sized frame (transient capture failure)
+    pub skipped_empty: u64,
+    /// Dropped: pixel buffer shorter than the frame dimensions
+    pub skipped_mismatch: u64,
+    /// Dropped: over the low-power frame rate cap
+    pub skipped_low_power: u64,
+    /// Not published because nothing on screen changed
+    pub unchanged: u64,
+    /// Frames sent to viewers
+    pub published: u64,
+    pub captured_fps: f64,
+    pub published_fps: f64,
+    /// Time from a frame arriving to it being published (scaling, diffing
+    /// and pixel format conversion), in ms
+    pub avg_convert_ms: f64,
+    pub max_convert_ms: f64,
 }
 
 /// Display info for the frontend display picker
@@ -53,27 +422,452 @@ pub struct DisplayInfo {
     pub is_primary: bool,
 }
 
+/// Snapshot preview of a display for the frontend display picker
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayThumbnail {
+    pub id: u32,
+    pub title: String,
+    pub is_primary: bool,
+    pub width: u32,
+    pub height: u32,
+    /// JPEG image encoded as a `data:image/jpeg;base64,...` URL
+    pub data_url: String,
+}
+
+/// Validated parameters for starting a stream
+#[derive(Debug, Clone)]
+struct StreamParams {
+    port: u16,
+    quality: i32,
+    fps: u32,
+    display_id: Option<u32>,
+    audio: bool,
+    max_width: Option<u32>,
+    scaling: ScalingQuality,
+    show_cursor: bool,
+    show_highlight: bool,
+    resolution: CaptureResolution,
+    target_app: Option<AppSelector>,
+}
+
+impl StreamParams {
+    /// Validate parameters with explicit errors instead of silent clamping
+    fn validate(&self) -> Result<(), AppError> {
+        if !(1..=100).contains(&self.quality) {
+            return Err(AppError::Validation(format!(
+                "Quality must be 1-100, got: {}",
+                self.quality
+            )));
+        }
+        if !(1..=MAX_FPS).contains(&self.fps) {
+            return Err(AppError::Validation(format!(
+                "FPS must be 1-{}, got: {}",
+                MAX_FPS, self.fps
+            )));
+        }
+        if !is_valid_stream_port(self.port) {
+            return Err(AppError::Validation(format!(
+                "Streaming port must be {} (auto) or {}-{}, got: {}",
+                STREAM_PORT_AUTO, STREAM_PORT_MIN, STREAM_PORT_MAX, self.port
+            )));
+        }
+        validate_max_width(self.max_width)
+    }
+
+    /// Status reported while the start waits for permission
+    fn pending_status(&self) -> StreamStatus {
+        StreamStatus {
+            active: false,
+            port: self.port,
+            fps: self.fps,
+            quality: self.quality,
+            clients: 0,
+            display_id: self.display_id,
+            audio: self.audio,
+            permission_pending: true,
+            max_width: self.max_width,
+            scaling: self.scaling,
+            show_cursor: self.show_cursor,
+            show_highlight: self.show_highlight,
+            resolution: self.resolution,
+            target_app: self.target_app.clone(),
+            tls: false,
+            cert_fingerprint: None,
+            remote_control: false,
+            client_stats: Vec::new(),
+        }
+    }
+}
+
+fn validate_max_width(max_width: Option<u32>) -> Result<(), AppError> {
+    match max_width {
+        Some(w) if !(STREAM_MIN_WIDTH..=STREAM_MAX_WIDTH).contains(&w) => {
+            Err(AppError::Validation(format!(
+                "Max width must be {}-{}, got: {}",
+                STREAM_MIN_WIDTH, STREAM_MAX_WIDTH, w
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Downscale width of a running stream, shared by the capture thread, the
+/// viewers that may change it and `set_stream_max_width` (0 = native size).
+pub(crate) struct SharedMaxWidth(AtomicU32);
+
+impl SharedMaxWidth {
+    pub(crate) fn new(max_width: Option<u32>) -> Self {
+        Self(AtomicU32::new(max_width.unwrap_or(0)))
+    }
+
+    pub(crate) fn get(&self) -> Option<u32> {
+        Some(self.0.load(Ordering::Relaxed)).filter(|&w| w > 0)
+    }
+
+    fn set(&self, max_width: Option<u32>) {
+        self.0.store(max_width.unwrap_or(0), Ordering::Relaxed);
+    }
+}
+
+/// A stream start deferred until screen capture permission is granted
+struct PendingStart {
+    params: StreamParams,
+    /// Fired by `stop_local_stream` to abandon the pending start
+    cancel_tx: oneshot::Sender<()>,
+}
+
+/// One frame as video messages in a single pixel format.
+///
+/// Every update carries the full frame so late or lagging clients can
+/// resync; `delta` is only valid for a client that already has `seq - 1`.
+#[derive(Clone, Default)]
+struct FormatFrame {
+    keyframe: Bytes,
+    delta: Option<Bytes>,
+}
+
+/// Latest video update published by the capture thread, in every pixel
+/// format a connected client uses (indexed by `PixelFormat::index`).
+#[derive(Clone, Default)]
+pub(crate) struct VideoUpdate {
+    seq: u64,
+    frames: [Option<FormatFrame>; PixelFormat::ALL.len()],
+}
+
+impl VideoUpdate {
+    fn frame(&self, format: PixelFormat) -> Option<&FormatFrame> {
+        self.frames[format.index()].as_ref()
+    }
+}
+
 /// Active streaming session with handles to shut it down
 struct StreamSession {
     shutdown_tx: tokio::sync::watch::Sender<bool>,
-    capture_handle: Option<std::thread::JoinHandle<()>>,
+    capture: Option<CapturePipeline>,
     ws_handle: Option<tokio::task::JoinHandle<()>>,
+    audio_handle: Option<std::thread::JoinHandle<()>>,
     port: u16,
     fps: u32,
     quality: i32,
     display_id: Option<u32>,
-    client_count: Arc<std::sync::atomic::AtomicUsize>,
+    audio: bool,
+    max_width: Arc<SharedMaxWidth>,
+    scaling: ScalingQuality,
+    show_cursor: bool,
+    show_highlight: bool,
+    resolution: CaptureResolution,
+    target_app: Option<AppSelector>,
+    /// Fingerprint of the served certificate (None = plain `ws://`)
+    cert_fingerprint: Option<String>,
+    remote_control: bool,
+    clients: Arc<ClientRegistry>,
+    counters: Arc<CaptureCounters>,
+    /// Latest frame, shared by successive capture pipelines
+    frame_tx: Arc<watch::Sender<VideoUpdate>>,
+    /// Opus packets, also forwarded to local viewers
+    audio_tx: broadcast::Sender<Bytes>,
+    remote_input: Option<Arc<RemoteInput>>,
+}
+
+/// Book-keeping for a single connected WebSocket client
+struct ClientEntry {
+    addr: SocketAddr,
+    connected_at: chrono::DateTime<chrono::Local>,
+    frames_sent: u64,
+    bytes_sent: u64,
+    send_time_total: Duration,
+    /// Most recent capture-to-receive latencies reported by the client, in ms
+    latency_ms: VecDeque<f64>,
+    last_acked_seq: u64,
+    /// Carries `last_acked_seq` to a local viewer's flow control
+    acked_tx: watch::Sender<u64>,
+    format: PixelFormat,
+    transport: ClientTransport,
+    /// Fired by `disconnect_stream_client` to make the client task exit
+    kick_tx: Option<oneshot::Sender<()>>,
+    /// Remote control grant, watched by the client task
+    control_tx: watch::Sender<bool>,
+}
+
+/// Counters updated by the capture thread, read by `get_capture_stats`.
+struct CaptureCounters {
+    started: Instant,
+    frames_captured: AtomicU64,
+    skipped_empty: AtomicU64,
+    skipped_mismatch: AtomicU64,
+    skipped_low_power: AtomicU64,
+    unchanged: AtomicU64,
+    published: AtomicU64,
+    convert_ns_total: AtomicU64,
+    convert_ns_max: AtomicU64,
+}
+
+impl CaptureCounters {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            frames_captured: AtomicU64::new(0),
+            skipped_empty: AtomicU64::new(0),
+            skipped_mismatch: AtomicU64::new(0),
+            skipped_low_power: AtomicU64::new(0),
+            unchanged: AtomicU64::new(0),
+            published: AtomicU64::new(0),
+            convert_ns_total: AtomicU64::new(0),
+            convert_ns_max: AtomicU64::new(0),
+        }
+    }
+
+    fn bump(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_published(&self, convert_time: Duration) {
+        let ns = convert_time.as_nanos() as u64;
+        self.published.fetch_add(1, Ordering::Relaxed);
+        self.convert_ns_total.fetch_add(ns, Ordering::Relaxed);
+        self.convert_ns_max.fetch_max(ns, Ordering::Relaxed);
+    }
+
+    fn stats(&self, now: Instant) -> CaptureStats {
+        let uptime = now.saturating_duration_since(self.started).as_secs_f64();
+        let load = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        let (captured, published) = (load(&self.frames_captured), load(&self.published));
+        let per_sec = |n: u64| if uptime > 0.0 { n as f64 / uptime } else { 0.0 };
+        CaptureStats {
+            active: true,
+            uptime_secs: uptime,
+            frames_captured: captured,
+            skipped_empty: load(&self.skipped_empty),
+            skipped_mismatch: load(&self.skipped_mismatch),
+            skipped_low_power: load(&self.skipped_low_power),
+            unchanged: load(&self.unchanged),
+            published,
+            captured_fps: per_sec(captured),
+            published_fps: per_sec(published),
+            avg_convert_ms: if published > 0 {
+                load(&self.convert_ns_total) as f64 / published as f64 / 1e6
+            } else {
+                0.0
+            },
+            max_convert_ms: load(&self.convert_ns_max) as f64 / 1e6,
+        }
+    }
+}
+
+/// Registry of connected WebSocket clients, keyed by client id.
+///
+/// Uses a std Mutex because it is only held for short, non-async sections
+/// (one update per sent frame).
+#[derive(Default)]
+pub(crate) struct ClientRegistry {
+    next_id: AtomicU64,
+    clients: std::sync::Mutex<HashMap<u64, ClientEntry>>,
+}
+
+impl ClientRegistry {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, ClientEntry>> {
+        // Entries hold plain counters, so a poisoned lock is still usable
+        self.clients.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Register a new client, returning its id and the kick and control
+    /// grant receivers. Returns None if `max_clients` (0 = unlimited) are
+    /// already connected.
+    fn register(
+        &self,
+        addr: SocketAddr,
+        max_clients: usize,
+        format: PixelFormat,
+        transport: ClientTransport,
+    ) -> Option<(u64, oneshot::Receiver<()>, watch::Receiver<bool>)> {
+        let mut clients = self.lock();
+        if max_clients > 0 && clients.len() >= max_clients {
+            return None;
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (kick_tx, kick_rx) = oneshot::channel();
+        let (control_tx, control_rx) = watch::channel(false);
+        clients.insert(
+            id,
+            ClientEntry {
+                addr,
+                connected_at: chrono::Local::now(),
+                frames_sent: 0,
+                bytes_sent: 0,
+                send_time_total: Duration::ZERO,
+                latency_ms: VecDeque::new(),
+                last_acked_seq: 0,
+                acked_tx: watch::channel(0).0,
+                format,
+                transport,
+                kick_tx: Some(kick_tx),
+                control_tx,
+            },
+        );
+        Some((id, kick_rx, control_rx))
+    }
+
+    fn unregister(&self, id: u64) -> Option<ClientEntry> {
+        self.lock().remove(&id)
+    }
+
+    fn record_send(&self, id: u64, bytes: usize, elapsed: Duration) {
+        if let Some(entry) = self.lock().get_mut(&id) {
+            entry.frames_sent += 1;
+            entry.bytes_sent += bytes as u64;
+            entry.send_time_total += elapsed;
+        }
+    }
+
+    /// Count an audio packet towards `bytes_sent` (it is not a frame).
+    fn record_audio(&self, id: u64, bytes: usize) {
+        if let Some(entry) = self.lock().get_mut(&id) {
+            entry.bytes_sent += bytes as u64;
+        }
+    }
+
+    /// Record a frame acknowledgement. Clock skew between viewer and host
+    /// can make the difference negative; such samples count as 0 ms.
+    fn record_ack(&self, id: u64, seq: u64, capture_us: u64, received_us: u64) {
+        if let Some(entry) = self.lock().get_mut(&id) {
+            let ms = received_us.saturating_sub(capture_us) as f64 / 1000.0;
+            if entry.latency_ms.len() == LATENCY_WINDOW {
+                entry.latency_ms.pop_front();
+            }
+            entry.latency_ms.push_back(ms);
+            entry.last_acked_seq = seq;
+            entry.acked_tx.send_replace(seq);
+        }
+    }
+
+    fn latency(&self) -> (LatencyStats, Vec<ClientLatency>) {
+        let clients = self.lock();
+        let overall = LatencyStats::from_samples(clients.values().flat_map(|e| &e.latency_ms));
+        let mut per_client: Vec<ClientLatency> = clients
+            .iter()
+            .map(|(id, e)| ClientLatency {
+                id: *id,
+                addr: e.addr.to_string(),
+                last_seq: e.last_acked_seq,
+                stats: LatencyStats::from_samples(&e.latency_ms),
+            })
+            .collect();
+        per_client.sort_by_key(|c| c.id);
+        (overall, per_client)
+    }
+
+    /// Signal a client to disconnect. Returns false if the id is unknown.
+    fn kick(&self, id: u64) -> bool {
+        match self.lock().get_mut(&id) {
+            Some(entry) => {
+                if let Some(tx) = entry.kick_tx.take() {
+                    let _ = tx.send(());
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Grant or revoke remote control. Returns the client's address, or
+    /// None if the id is unknown.
+    fn set_control(&self, id: u64, allowed: bool) -> Option<SocketAddr> {
+        let clients = self.lock();
+        let entry = clients.get(&id)?;
+        entry.control_tx.send_replace(allowed);
+        Some(entry.addr)
+    }
+
+    /// Follow the last frame a client acknowledged.
+    fn watch_acks(&self, id: u64) -> Option<watch::Receiver<u64>> {
+        self.lock().get(&id).map(|e| e.acked_tx.subscribe())
+    }
+
+    fn transport(&self, id: u64) -> Option<ClientTransport> {
+        self.lock().get(&id).map(|e| e.transport)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Pixel formats the capture thread should produce: those of connected
+    /// clients, or BGRA while nobody is connected.
+    fn formats_in_use(&self) -> Vec<PixelFormat> {
+        let clients = self.lock();
+        let formats: Vec<PixelFormat> = PixelFormat::ALL
+            .into_iter()
+            .filter(|f| clients.values().any(|e| e.format == *f))
+            .collect();
+        if formats.is_empty() {
+            vec![PixelFormat::Bgra]
+        } else {
+            formats
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<StreamClientInfo> {
+        let mut infos: Vec<StreamClientInfo> = self
+            .lock()
+            .iter()
+            .map(|(id, e)| StreamClientInfo {
+                id: *id,
+                addr: e.addr.to_string(),
+                connected_at: e.connected_at.to_rfc3339(),
+                frames_sent: e.frames_sent,
+                bytes_sent: e.bytes_sent,
+                avg_send_latency_ms: if e.frames_sent > 0 {
+                    e.send_time_total.as_secs_f64() * 1000.0 / e.frames_sent as f64
+                } else {
+                    0.0
+                },
+                format: e.format,
+                control: *e.control_tx.borrow(),
+                transport: e.transport,
+            })
+            .collect();
+        infos.sort_by_key(|c| c.id);
+        infos
+    }
 }
 
 /// Shared state managed by Tauri
 pub struct StreamingState {
     session: Mutex<Option<StreamSession>>,
+    pending: std::sync::Mutex<Option<PendingStart>>,
+}
+
+impl StreamingState {
+    fn pending(&self) -> std::sync::MutexGuard<'_, Option<PendingStart>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
 }
 
 impl Default for StreamingState {
     fn default() -> Self {
         Self {
             session: Mutex::new(None),
+            pending: std::sync::Mutex::new(None),
         }
     }
 }
@@ -84,9 +878,9 @@ impl Default for StreamingState {
 
 /// List all available displays for capture
 #[tauri::command]
-pub async fn list_displays() -> Result<Vec<DisplayInfo>, String> {
+pub async fn list_displays() -> Result<Vec<DisplayInfo>, AppError> {
     if !scap::is_supported() {
-        return Err("Screen capture not supported on this platform".into());
+        return Err(AppError::Capture("Screen capture not supported on this platform".into()));
     }
 
     let main_display = scap::get_main_display();
@@ -110,126 +904,992 @@ pub async fn list_displays() -> Result<Vec<DisplayInfo>, String> {
     Ok(displays)
 }
 
-/// Start the local MJPEG WebSocket streaming server
+/// Capture a small JPEG preview of every display for the display picker.
+///
+/// # Arguments
+/// * `max_width` - Maximum thumbnail width in pixels (aspect ratio is preserved)
+///
+/// Displays that fail to capture are logged and omitted from the result.
 #[tauri::command]
-pub async fn start_local_stream(
-    state: tauri::State<'_, StreamingState>,
-    port: u16,
-    quality: i32,
-    fps: u32,
-    display_id: Option<u32>,
-) -> Result<StreamStatus, String> {
-    let mut session = state.session.lock().await;
-
-    if session.is_some() {
-        return Err("Stream already running. Stop it first.".into());
-    }
-
-    // Validate parameters with explicit errors instead of silent clamping
-    if !(1..=100).contains(&quality) {
-        return Err(format!("Quality must be 1-100, got: {}", quality));
-    }
-    if !(1..=MAX_FPS).contains(&fps) {
-        return Err(format!("FPS must be 1-{}, got: {}", MAX_FPS, fps));
-    }
-    if !(STREAM_PORT_MIN..=STREAM_PORT_MAX).contains(&port) {
-        return Err(format!(
-            "Streaming port must be {}-{}, got: {}",
-            STREAM_PORT_MIN, STREAM_PORT_MAX, port
-        ));
+pub async fn get_display_thumbnails(max_width: u32) -> Result<Vec<DisplayThumbnail>, AppError> {
+    if !(THUMBNAIL_MIN_WIDTH..=THUMBNAIL_MAX_WIDTH).contains(&max_width) {
+        return Err(AppError::Validation(format!(
+            "Thumbnail width must be {}-{}, got: {}",
+            THUMBNAIL_MIN_WIDTH, THUMBNAIL_MAX_WIDTH, max_width
+        )));
     }
 
     if !scap::is_supported() {
-        return Err("Screen capture not supported on this platform".into());
+        return Err(AppError::Capture("Screen capture not supported on this platform".into()));
     }
 
     if !scap::has_permission() {
-        scap::request_permission();
-        return Err("Screen capture permission not granted. Please allow in System Settings and restart.".into());
+        return Err(AppError::PermissionDenied("Screen capture permission not granted".into()));
     }
 
-    // Find the target display
-    let target = if let Some(id) = display_id {
-        let targets = scap::get_all_targets();
-        let found = targets.into_iter().find(|t| {
-            if let scap::Target::Display(d) = t {
-                d.id == id
-            } else {
-                false
-            }
-        });
-        if found.is_none() {
-            log::warn!("Display id={} not found in available targets", id);
-        }
-        found
-    } else {
-        Some(scap::Target::Display(scap::get_main_display()))
-    };
-
-    // Create shutdown channel
-    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
-    let client_count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    // Capturing is blocking (scap waits on the next frame), keep it off the async runtime
+    tokio::task::spawn_blocking(move || {
+        let main_display = scap::get_main_display();
+        let mut thumbnails = Vec::new();
 
-    // Bind the TCP listener for the WebSocket server
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
-    let listener = TcpListener::bind(addr)
-        .await
-        .map_err(|e| format!("Failed to bind port {}: {}", port, e))?;
+        for target in scap::get_all_targets() {
+            let display = match &target {
+                scap::Target::Display(d) => d.clone(),
+                _ => continue,
+            };
 
-    let actual_port = listener
-        .local_addr()
-        .map(|a| a.port())
-        .unwrap_or(port);
+            let frame = match capture_single_frame(target) {
+                Ok(f) => f,
+                Err(e) => {
+                    log::warn!("Thumbnail capture failed for display {}: {}", display.id, e);
+                    continue;
+                }
+            };
 
-    log::info!("MJPEG WebSocket server starting on ws://127.0.0.1:{}", actual_port);
+            let (rgb, width, height) = downscale_bgra_to_rgb(
+                &frame.data,
+                frame.width as u32,
+                frame.height as u32,
+                max_width,
+            );
 
-    // Watch channel for JPEG frames — latest-frame semantics for real-time streaming.
-    // Only the most recent frame is retained, eliminating stale-frame buffering.
-    let (frame_tx, _) = watch::channel(Bytes::new());
-    let frame_tx = Arc::new(frame_tx);
+            let jpeg = match encode_jpeg(&rgb, width, height, THUMBNAIL_JPEG_QUALITY) {
+                Ok(j) => j,
+                Err(e) => {
+                    log::warn!("Thumbnail encode failed for display {}: {}", display.id, e);
+                    continue;
+                }
+            };
 
-    // Spawn the capture thread (blocking - scap uses blocking get_next_frame)
-    let capture_frame_tx = frame_tx.clone();
-    let capture_shutdown_rx = shutdown_rx.clone();
-    let capture_fps = fps;
+            thumbnails.push(DisplayThumbnail {
+                id: display.id,
+                title: display.title.clone(),
+                is_primary: display.id == main_display.id,
+                width,
+                height,
+                data_url: format!(
+                    "data:image/jpeg;base64,{}",
+                    base64::engine::general_purpose::STANDARD.encode(&jpeg)
+                ),
+            });
+        }
 
-    let capture_handle = std::thread::spawn(move || {
-        let options = Options {
-            fps: capture_fps,
-            show_cursor: true,
-            show_highlight: false,
-            target,
-            output_type: FrameType::BGRAFrame,
-            output_resolution: Resolution::Captured,
-            ..Default::default()
-        };
+        log::debug!("Captured {} display thumbnail(s)", thumbnails.len());
+        Ok(thumbnails)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Thumbnail task failed: {}", e)))?
+}
 
-        let mut capturer = match Capturer::build(options) {
-            Ok(c) => c,
-            Err(e) => {
-                log::error!("Failed to build capturer: {:?}", e);
-                return;
-            }
-        };
+/// Report whether the app may capture the screen.
+#[tauri::command]
+pub fn get_capture_permission_status() -> CapturePermissionStatus {
+    if !scap::is_supported() {
+        CapturePermissionStatus::Unsupported
+    } else if scap::has_permission() {
+        CapturePermissionStatus::Granted
+    } else {
+        CapturePermissionStatus::NotGranted
+    }
+}
 
-        // Reusable buffer for raw BGRA pixels with 4-byte dimension header.
-        let mut frame_buf: Vec<u8> = Vec::new();
+/// Open the OS settings page where screen capture permission is granted.
+///
+/// Only macOS gates screen capture behind a user permission; other platforms
+/// return a validation error.
+#[tauri::command]
+pub fn open_capture_permission_settings(app: AppHandle) -> Result<(), AppError> {
+    #[cfg(target_os = "macos")]
+    {
+        use tauri_plugin_opener::OpenerExt;
 
-        capturer.start_capture();
-        log::info!("Screen capture started ({}fps, raw RGBA)", capture_fps);
+        app.opener()
+            .open_url(SCREEN_CAPTURE_SETTINGS_URL, None::<&str>)
+            .map_err(|e| AppError::Internal(format!("Failed to open System Settings: {}", e)))
+    }
 
-        loop {
-            // Check for shutdown
-            if *capture_shutdown_rx.borrow() {
-                break;
-            }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = app;
+        Err(AppError::Validation(
+            "Screen capture permission is not managed by the OS on this platform".into(),
+        ))
+    }
+}
 
-            match capturer.get_next_frame() {
-                Ok(Frame::Video(VideoFrame::BGRA(frame))) => {
-                    // Guard: skip empty frames (scap returns 0x0 on transient
-                    // capture failures, common with external HDMI/USB displays)
-                    if frame.width == 0 || frame.height == 0 {
+/// Start the local MJPEG WebSocket streaming server
+///
+/// When `audio` is true, system audio is captured as Opus and sent on the
+/// same WebSocket (see `MSG_TYPE_AUDIO`). Audio failing to start aborts the
+/// stream so the caller knows sound is unavailable.
+///
+/// If screen capture permission has not been granted yet, the OS prompt is
+/// shown and a `permission-pending` event is emitted. The returned status has
+/// `permission_pending` set, and the stream starts on its own once permission
+/// is granted (`stream-started`) or gives up (`stream-start-failed`).
+///
+/// `max_width` (320-7680) downscales wider displays before sending (e.g. 1920
+/// for a 4K screen) using `scaling`, which defaults to area averaging. It can
+/// be changed while streaming with `set_stream_max_width`.
+///
+/// `show_cursor` (default true) draws the cursor into the frames and
+/// `show_highlight` (default false, macOS only) marks clicks, e.g. for
+/// tutorials. `resolution` asks the capturer for a fixed output size such as
+/// `"1080p"` instead of the display's native one (`"captured"`, the default).
+///
+/// `port` 0 binds the first free port of 9100-9199; the returned status
+/// carries the port actually used (still 0 while permission is pending).
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_local_stream(
+    app: AppHandle,
+    state: tauri::State<'_, StreamingState>,
+    port: u16,
+    quality: i32,
+    fps: u32,
+    display_id: Option<u32>,
+    audio: Option<bool>,
+    max_width: Option<u32>,
+    scaling: Option<ScalingQuality>,
+    show_cursor: Option<bool>,
+    show_highlight: Option<bool>,
+    resolution: Option<CaptureResolution>,
+) -> Result<StreamStatus, AppError> {
+    let params = StreamParams {
+        port,
+        quality,
+        fps,
+        display_id,
+        audio: audio.unwrap_or(false),
+        max_width,
+        scaling: scaling.unwrap_or_default(),
+        show_cursor: show_cursor.unwrap_or(true),
+        show_highlight: show_highlight.unwrap_or(false),
+        resolution: resolution.unwrap_or_default(),
+        target_app: None,
+    };
+    start_stream(app, &state, params).await
+}
+
+/// Start a stream showing only the windows of one application
+///
+/// Captures the display (`display_id`, default: main display) with every
+/// window of other applications excluded, so a demo shares nothing but the
+/// app being shown. Identify the app by exactly one of `bundle_id` (e.g.
+/// `com.apple.Safari`) or `process_name` (its name, case-insensitive). Fails
+/// with `not_found` if the app has no windows. macOS only.
+///
+/// The other arguments and the permission flow are those of
+/// `start_local_stream`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_app_stream(
+    app: AppHandle,
+    state: tauri::State<'_, StreamingState>,
+    port: u16,
+    quality: i32,
+    fps: u32,
+    bundle_id: Option<String>,
+    process_name: Option<String>,
+    display_id: Option<u32>,
+    audio: Option<bool>,
+    max_width: Option<u32>,
+    scaling: Option<ScalingQuality>,
+    show_cursor: Option<bool>,
+    show_highlight: Option<bool>,
+    resolution: Option<CaptureResolution>,
+) -> Result<StreamStatus, AppError> {
+    let params = StreamParams {
+        port,
+        quality,
+        fps,
+        display_id,
+        audio: audio.unwrap_or(false),
+        max_width,
+        scaling: scaling.unwrap_or_default(),
+        show_cursor: show_cursor.unwrap_or(true),
+        show_highlight: show_highlight.unwrap_or(false),
+        resolution: resolution.unwrap_or_default(),
+        target_app: Some(AppSelector::from_args(bundle_id, process_name)?),
+    };
+    start_stream(app, &state, params).await
+}
+
+/// Validate `params` and launch the stream, or defer it until screen capture
+/// permission is granted.
+async fn start_stream(
+    app: AppHandle,
+    state: &StreamingState,
+    params: StreamParams,
+) -> Result<StreamStatus, AppError> {
+    params.validate()?;
+
+    if !scap::is_supported() {
+        return Err(AppError::Capture("Screen capture not supported on this platform".into()));
+    }
+
+    if state.pending().is_some() {
+        return Err(AppError::Validation(
+            "Stream start already waiting for screen capture permission".into(),
+        ));
+    }
+
+    if !scap::has_permission() {
+        if state.session.lock().await.is_some() {
+            return Err(AppError::Validation("Stream already running. Stop it first.".into()));
+        }
+        scap::request_permission();
+        return Ok(wait_for_permission(app, state, params));
+    }
+
+    launch_stream(&app, state, params).await
+}
+
+/// Record a pending start and poll for permission in the background.
+fn wait_for_permission(app: AppHandle, state: &StreamingState, params: StreamParams) -> StreamStatus {
+    let status = params.pending_status();
+    let (cancel_tx, mut cancel_rx) = oneshot::channel();
+    *state.pending() = Some(PendingStart {
+        params: params.clone(),
+        cancel_tx,
+    });
+
+    log::info!("Screen capture permission not granted, stream will start once it is");
+    let _ = events::emit(&app, "permission-pending", &status);
+
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let mut interval = tokio::time::interval(PERMISSION_POLL_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = &mut cancel_rx => {
+                    log::info!("Pending stream start cancelled");
+                    return;
+                }
+                _ = interval.tick() => {}
+            }
+
+            if scap::has_permission() {
+                break;
+            }
+            if started.elapsed() >= PERMISSION_WAIT_TIMEOUT {
+                app.state::<StreamingState>().pending().take();
+                log::warn!("Gave up waiting for screen capture permission");
+                let err = AppError::Timeout("Timed out waiting for screen capture permission".into());
+                let _ = events::emit(&app, "stream-start-failed", &err);
+                return;
+            }
+        }
+
+        // A stop racing with the grant wins: only start if still pending
+        let state = app.state::<StreamingState>();
+        let still_pending = state.pending().take().is_some();
+        if !still_pending {
+            return;
+        }
+
+        log::info!("Screen capture permission granted, starting pending stream");
+        match launch_stream(&app, &state, params).await {
+            Ok(status) => {
+                let _ = events::emit(&app, "stream-started", &status);
+            }
+            Err(e) => {
+                log::error!("Pending stream start failed: {}", e);
+                let _ = events::emit(&app, "stream-start-failed", &e);
+            }
+        }
+    });
+
+    status
+}
+
+/// Whether `port` is accepted for a stream: auto or the streaming range.
+pub(crate) fn is_valid_stream_port(port: u16) -> bool {
+    port == STREAM_PORT_AUTO || (STREAM_PORT_MIN..=STREAM_PORT_MAX).contains(&port)
+}
+
+/// Bind the stream server on loopback `port`.
+async fn bind_stream_port(port: u16) -> Result<TcpListener, AppError> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    TcpListener::bind(addr).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AddrInUse {
+            AppError::PortInUse(port)
+        } else {
+            AppError::Io(format!("Failed to bind port {}: {}", port, e))
+        }
+    })
+}
+
+/// Bind the stream server on `port`, or for `STREAM_PORT_AUTO` on the first
+/// free port of the streaming range.
+pub(crate) async fn bind_stream_listener(port: u16) -> Result<TcpListener, AppError> {
+    if port != STREAM_PORT_AUTO {
+        return bind_stream_port(port).await;
+    }
+    for candidate in STREAM_PORT_MIN..=STREAM_PORT_MAX {
+        match bind_stream_port(candidate).await {
+            Err(AppError::PortInUse(_)) => continue,
+            result => return result,
+        }
+    }
+    Err(no_free_stream_port())
+}
+
+fn no_free_stream_port() -> AppError {
+    AppError::NotFound(format!(
+        "No free streaming port in {}-{}",
+        STREAM_PORT_MIN, STREAM_PORT_MAX
+    ))
+}
+
+/// First port of the streaming range nothing is listening on.
+///
+/// Only a hint: the port may be taken before the stream binds it. Pass port
+/// 0 to `start_local_stream` to have the server pick one race-free.
+#[tauri::command]
+pub fn suggest_stream_port() -> Result<u16, AppError> {
+    (STREAM_PORT_MIN..=STREAM_PORT_MAX)
+        .find(|&port| std::net::TcpListener::bind(("127.0.0.1", port)).is_ok())
+        .ok_or_else(no_free_stream_port)
+}
+
+/// Windows an app stream of `selector` hides. Fails with `not_found` if the
+/// app has no windows.
+async fn app_stream_exclusions(selector: &AppSelector) -> Result<Vec<u32>, AppError> {
+    let lookup = selector.clone();
+    let windows = tokio::task::spawn_blocking(move || app_capture::app_windows(&lookup))
+        .await
+        .map_err(|e| AppError::Internal(format!("Window listing task failed: {}", e)))??;
+    if windows.own == 0 {
+        return Err(AppError::NotFound(format!(
+            "No windows found for application: {}",
+            selector
+        )));
+    }
+    log::info!(
+        "App stream of {}: {} window(s) shown, {} excluded",
+        selector,
+        windows.own,
+        windows.excluded.len()
+    );
+    Ok(windows.excluded)
+}
+
+/// The TLS acceptor and certificate fingerprint for a stream server, or
+/// None when `stream.tls` is off.
+pub(crate) async fn load_stream_tls(
+    app: &AppHandle,
+    settings: &StreamSettings,
+) -> Result<Option<(TlsAcceptor, String)>, AppError> {
+    if !settings.tls {
+        return Ok(None);
+    }
+    let app = app.clone();
+    let tls_settings = settings.clone();
+    let (acceptor, cert) =
+        tokio::task::spawn_blocking(move || stream_tls::load_acceptor(&app, &tls_settings))
+            .await
+            .map_err(|e| AppError::Internal(format!("Certificate task failed: {}", e)))??;
+    log::info!("Stream served over TLS, certificate SHA-256 {}", cert.fingerprint_sha256);
+    Ok(Some((acceptor, cert.fingerprint_sha256)))
+}
+
+/// Whether `target` is the main display, the only one remote pointer
+/// positions can be mapped onto.
+fn is_main_display(target: &Option<scap::Target>) -> bool {
+    matches!(target, Some(scap::Target::Display(d)) if d.id == scap::get_main_display().id)
+}
+
+/// Bind the server and start capture threads. Permission must already be granted.
+async fn launch_stream(
+    app: &AppHandle,
+    state: &StreamingState,
+    params: StreamParams,
+) -> Result<StreamStatus, AppError> {
+    let StreamParams {
+        port,
+        quality,
+        fps,
+        display_id,
+        audio,
+        max_width,
+        scaling,
+        show_cursor,
+        show_highlight,
+        resolution,
+        target_app,
+    } = params;
+    let mut session = state.session.lock().await;
+
+    if session.is_some() {
+        return Err(AppError::Validation("Stream already running. Stop it first.".into()));
+    }
+
+    // App streams hide every window the app does not own
+    let excluded_windows = match &target_app {
+        Some(selector) => app_stream_exclusions(selector).await?,
+        None => Vec::new(),
+    };
+
+    // Find the target display
+    let target = find_display_target(display_id);
+    if target.is_none() {
+        log::warn!("Display id={:?} not found in available targets", display_id);
+    }
+
+    let stream_settings = app.state::<SettingsState>().get().stream;
+    let peers = Arc::new(PeerPolicy::from_settings(
+        &stream_settings.allowed_ips,
+        stream_settings.max_clients,
+    ));
+
+    // Create shutdown channel
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let clients = Arc::new(ClientRegistry::default());
+    let shared_max_width = Arc::new(SharedMaxWidth::new(max_width));
+
+    // Load (or first generate) the certificate before anything is bound
+    let tls = load_stream_tls(app, &stream_settings).await?;
+    let cert_fingerprint = tls.as_ref().map(|(_, fp)| fp.clone());
+    let tls_acceptor = tls.map(|(acceptor, _)| acceptor);
+
+    // Injection thread for viewers granted control; pointer positions can
+    // only be mapped onto the main display
+    let remote_input = if stream_settings.remote_control {
+        Some(Arc::new(RemoteInput::start(app.clone(), is_main_display(&target))?))
+    } else {
+        None
+    };
+    let remote_control = remote_input.is_some();
+
+    // Bind the TCP listener for the WebSocket server
+    let listener = bind_stream_listener(port).await?;
+
+    let actual_port = listener
+        .local_addr()
+        .map(|a| a.port())
+        .unwrap_or(port);
+
+    log::info!(
+        "MJPEG WebSocket server starting on {}://127.0.0.1:{}",
+        if tls_acceptor.is_some() { "wss" } else { "ws" },
+        actual_port
+    );
+
+    // Watch channel for JPEG frames — latest-frame semantics for real-time streaming.
+    // Only the most recent frame is retained, eliminating stale-frame buffering.
+    let (frame_tx, _) = watch::channel(VideoUpdate::default());
+    let frame_tx = Arc::new(frame_tx);
+
+    // Broadcast channel for audio — unlike video, every packet matters
+    let (audio_tx, _) = broadcast::channel::<Bytes>(AUDIO_CHANNEL_CAPACITY);
+    let audio_handle = if audio {
+        match audio::spawn_audio_capture(audio_tx.clone(), shutdown_rx.clone()) {
+            Ok(h) => Some(h),
+            Err(e) => {
+                log::error!("Failed to start audio capture: {}", e);
+                return Err(e);
+            }
+        }
+    } else {
+        None
+    };
+
+    // Capture thread publishing into `frame_tx`, replaced by `restart_local_stream`
+    let counters = Arc::new(CaptureCounters::new());
+    let (go_tx, go_rx) = std::sync::mpsc::channel();
+    let _ = go_tx.send(());
+    let capture = spawn_capture(
+        CaptureJob {
+            app: app.clone(),
+            fps,
+            target,
+            excluded_windows,
+            target_app: target_app.clone(),
+            look: CaptureLook {
+                show_cursor,
+                show_highlight,
+                resolution,
+            },
+            scaling,
+            max_width: shared_max_width.clone(),
+            frame_tx: frame_tx.clone(),
+            clients: clients.clone(),
+            counters: counters.clone(),
+            shutdown_rx: shutdown_rx.clone(),
+            start_seq: 0,
+        },
+        None,
+        go_rx,
+    );
+
+    // WebSocket server feeding each viewer the latest update
+    let ws_handle = spawn_frame_server(FrameServer {
+        listener,
+        peers,
+        tls_acceptor,
+        frame_tx: frame_tx.clone(),
+        audio_tx: audio_tx.clone(),
+        clients: clients.clone(),
+        max_width: shared_max_width.clone(),
+        remote_input: remote_input.clone(),
+        shutdown_rx: shutdown_rx.clone(),
+    });
+
+    let status = StreamStatus {
+        active: true,
+        port: actual_port,
+        fps,
+        quality,
+        clients: 0,
+        display_id,
+        audio,
+        permission_pending: false,
+        max_width,
+        scaling,
+        show_cursor,
+        show_highlight,
+        resolution,
+        target_app: target_app.clone(),
+        tls: cert_fingerprint.is_some(),
+        cert_fingerprint: cert_fingerprint.clone(),
+        remote_control,
+        client_stats: Vec::new(),
+    };
+
+    *session = Some(StreamSession {
+        shutdown_tx,
+        capture: Some(capture),
+        ws_handle: Some(ws_handle),
+        audio_handle,
+        port: actual_port,
+        fps,
+        quality,
+        display_id,
+        audio,
+        max_width: shared_max_width,
+        scaling,
+        show_cursor,
+        show_highlight,
+        resolution,
+        target_app,
+        cert_fingerprint,
+        remote_control,
+        clients: clients.clone(),
+        counters,
+        frame_tx,
+        audio_tx,
+        remote_input,
+    });
+
+    let idle_stop_secs = stream_settings.idle_stop_secs;
+    if idle_stop_secs > 0 {
+        tokio::spawn(watch_idle(
+            app.clone(),
+            clients,
+            shutdown_rx,
+            Duration::from_secs(idle_stop_secs),
+        ));
+    }
+
+    Ok(status)
+}
+
+/// The WebSocket side of a stream: accepts viewers on `listener` and feeds
+/// each one the latest `frame_tx` update. Shared by the screen and camera
+/// streams.
+pub(crate) struct FrameServer {
+    pub(crate) listener: TcpListener,
+    pub(crate) peers: Arc<PeerPolicy>,
+    pub(crate) tls_acceptor: Option<TlsAcceptor>,
+    pub(crate) frame_tx: Arc<watch::Sender<VideoUpdate>>,
+    pub(crate) audio_tx: broadcast::Sender<Bytes>,
+    pub(crate) clients: Arc<ClientRegistry>,
+    pub(crate) max_width: Arc<SharedMaxWidth>,
+    pub(crate) remote_input: Option<Arc<RemoteInput>>,
+    pub(crate) shutdown_rx: watch::Receiver<bool>,
+}
+
+/// Spawn the accept loop of `server`; it exits when shutdown is signalled.
+pub(crate) fn spawn_frame_server(server: FrameServer) -> tokio::task::JoinHandle<()> {
+    let FrameServer {
+        listener,
+        peers,
+        tls_acceptor,
+        frame_tx,
+        audio_tx,
+        clients,
+        max_width,
+        remote_input,
+        shutdown_rx,
+    } = server;
+
+    tokio::spawn(async move {
+        loop {
+            let mut shutdown = shutdown_rx.clone();
+
+            tokio::select! {
+                result = listener.accept() => {
+                    match result {
+                        Ok((stream, addr)) => {
+                            if !peers.allows(addr.ip()) {
+                                log::warn!("Rejected stream connection from disallowed peer: {}", addr);
+                                stream_access::record(&StreamAccessEntry::new(
+                                    addr,
+                                    AccessOutcome::RejectedPeer,
+                                ));
+                                continue;
+                            }
+                            if peers.is_full(clients.len()) {
+                                log::warn!(
+                                    "Rejected stream connection from {}: {} client limit reached",
+                                    addr,
+                                    peers.max_clients
+                                );
+                                stream_access::record(&StreamAccessEntry::new(
+                                    addr,
+                                    AccessOutcome::RejectedLimit,
+                                ));
+                                continue;
+                            }
+                            // Disable Nagle's algorithm for low-latency frame delivery
+                            stream.set_nodelay(true).ok();
+                            log::debug!("New WebSocket client: {}", addr);
+                            let rx = frame_tx.subscribe();
+                            let client_audio_rx = audio_tx.subscribe();
+                            let registry = clients.clone();
+                            let client_max_width = max_width.clone();
+                            let client_shutdown = shutdown_rx.clone();
+                            let max_clients = peers.max_clients;
+                            let acceptor = tls_acceptor.clone();
+                            let input = remote_input.clone();
+
+                            tokio::spawn(async move {
+                                let Some(acceptor) = acceptor else {
+                                    return handle_ws_client(
+                                        stream,
+                                        addr,
+                                        rx,
+                                        client_audio_rx,
+                                        registry,
+                                        client_max_width,
+                                        max_clients,
+                                        input,
+                                        client_shutdown,
+                                    )
+                                    .await;
+                                };
+                                let handshake =
+                                    tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream));
+                                match handshake.await {
+                                    Ok(Ok(tls_stream)) => {
+                                        handle_ws_client(
+                                            tls_stream,
+                                            addr,
+                                            rx,
+                                            client_audio_rx,
+                                            registry,
+                                            client_max_width,
+                                            max_clients,
+                                            input,
+                                            client_shutdown,
+                                        )
+                                        .await
+                                    }
+                                    Ok(Err(e)) => {
+                                        log::warn!("TLS handshake with {} failed: {}", addr, e);
+                                        stream_access::record(&StreamAccessEntry::new(
+                                            addr,
+                                            AccessOutcome::HandshakeFailed,
+                                        ));
+                                    }
+                                    Err(_) => {
+                                        log::warn!("TLS handshake with {} timed out", addr);
+                                        stream_access::record(&StreamAccessEntry::new(
+                                            addr,
+                                            AccessOutcome::HandshakeFailed,
+                                        ));
+                                    }
+                                }
+                            });
+                        }
+                        Err(e) => {
+                            log::error!("Failed to accept connection: {}", e);
+                        }
+                    }
+                }
+                _ = shutdown.changed() => {
+                    if *shutdown.borrow() {
+                        log::info!("WebSocket server shutting down");
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Everything a capture thread needs; `restart_local_stream` starts a new
+/// one with changed options publishing into the same `frame_tx`.
+struct CaptureJob {
+    app: AppHandle,
+    fps: u32,
+    target: Option<scap::Target>,
+    excluded_windows: Vec<u32>,
+    target_app: Option<AppSelector>,
+    look: CaptureLook,
+    scaling: ScalingQuality,
+    max_width: Arc<SharedMaxWidth>,
+    frame_tx: Arc<watch::Sender<VideoUpdate>>,
+    clients: Arc<ClientRegistry>,
+    counters: Arc<CaptureCounters>,
+    shutdown_rx: watch::Receiver<bool>,
+    /// Sequence number of the last frame published into `frame_tx`
+    start_seq: u64,
+}
+
+/// Turns BGRA frames into `VideoUpdate`s, converting each frame once per
+/// pixel format in use however many clients use it. Shared by the screen
+/// and camera captures.
+pub(crate) struct FramePublisher {
+    encoder: DeltaEncoder,
+    /// Sequence number of the last published frame
+    seq: u64,
+    /// Formats in the last published update
+    published: Vec<PixelFormat>,
+}
+
+impl FramePublisher {
+    pub(crate) fn new(fps: u32, start_seq: u64) -> Self {
+        Self {
+            encoder: DeltaEncoder::new(fps * KEYFRAME_INTERVAL_SECS),
+            seq: start_seq,
+            published: Vec::new(),
+        }
+    }
+
+    /// Publish a frame into `frame_tx`; small changes also get a delta.
+    /// Returns false, publishing nothing, for an unchanged frame unless a
+    /// client needs a format the last update lacks.
+    pub(crate) fn publish(
+        &mut self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        clients: &ClientRegistry,
+        frame_tx: &watch::Sender<VideoUpdate>,
+    ) -> bool {
+        let formats = clients.formats_in_use();
+        let changed = match self.encoder.encode(pixels, width, height) {
+            Encoded::Unchanged if formats.iter().all(|f| self.published.contains(f)) => {
+                return false;
+            }
+            Encoded::Unchanged | Encoded::Keyframe => None,
+            Encoded::Delta(tiles) => Some(tiles),
+        };
+
+        let stamp = FrameStamp::now(self.seq + 1);
+        let mut update = VideoUpdate {
+            seq: stamp.seq,
+            ..Default::default()
+        };
+        for &format in &formats {
+            update.frames[format.index()] = Some(FormatFrame {
+                keyframe: Bytes::from(delta::encode_keyframe(pixels, width, height, stamp, format)),
+                delta: changed.as_ref().map(|tiles| {
+                    Bytes::from(delta::encode_delta(pixels, width, height, tiles, stamp, format))
+                }),
+            });
+        }
+
+        self.seq = stamp.seq;
+        self.published = formats;
+        let _ = frame_tx.send(update);
+        true
+    }
+}
+
+/// A running capture thread and the flag that retires it.
+struct CapturePipeline {
+    handle: std::thread::JoinHandle<()>,
+    stop: Arc<AtomicBool>,
+}
+
+/// Backoff before capturer rebuild number `attempt` (1-based).
+fn capture_retry_delay(attempt: u32) -> Duration {
+    CAPTURE_RETRY_BASE
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(CAPTURE_RETRY_MAX)
+}
+
+/// Build a new capturer after a failure, backing off between attempts.
+///
+/// `failures` counts consecutive failures across calls (the caller resets
+/// it once a frame arrives). Returns None after `CAPTURE_MAX_RETRIES` or
+/// when `stopping` turns true during a backoff.
+fn rebuild_capturer(
+    options: impl Fn() -> Options,
+    failures: &mut u32,
+    stopping: impl Fn() -> bool,
+) -> Option<Capturer> {
+    loop {
+        *failures += 1;
+        if *failures > CAPTURE_MAX_RETRIES {
+            return None;
+        }
+        // Sleep in short slices so a stop is not held up by the backoff
+        let until = Instant::now() + capture_retry_delay(*failures);
+        while Instant::now() < until {
+            if stopping() {
+                return None;
+            }
+            let left = until.saturating_duration_since(Instant::now());
+            std::thread::sleep(Duration::from_millis(50).min(left));
+        }
+        match Capturer::build(options()) {
+            Ok(capturer) => {
+                log::info!("Capturer rebuilt (attempt {})", failures);
+                return Some(capturer);
+            }
+            Err(e) => log::warn!("Failed to rebuild capturer (attempt {}): {:?}", failures, e),
+        }
+    }
+}
+
+/// Spawn the capture thread (blocking - scap uses blocking get_next_frame).
+///
+/// The thread builds and starts its capturer and reports the outcome on
+/// `ready_tx`, then waits for `go_rx` before publishing anything, so a
+/// replacement never interleaves frames with the pipeline it replaces.
+fn spawn_capture(
+    job: CaptureJob,
+    ready_tx: Option<oneshot::Sender<Result<(), AppError>>>,
+    go_rx: std::sync::mpsc::Receiver<()>,
+) -> CapturePipeline {
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = stop.clone();
+    let handle = std::thread::spawn(move || {
+        let stop = thread_stop;
+        let CaptureJob {
+            app,
+            fps,
+            target,
+            mut excluded_windows,
+            target_app,
+            look,
+            scaling,
+            max_width,
+            frame_tx,
+            clients,
+            counters,
+            shutdown_rx,
+            start_seq,
+        } = job;
+        let mut windows_checked = Instant::now();
+        let options = capture_options(fps, look, target.clone(), &excluded_windows);
+
+        let mut capturer = match Capturer::build(options) {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to build capturer: {:?}", e);
+                if let Some(tx) = ready_tx {
+                    let _ = tx.send(Err(AppError::Capture(format!("Failed to build capturer: {:?}", e))));
+                }
+                return;
+            }
+        };
+        capturer.start_capture();
+        if let Some(tx) = ready_tx {
+            let _ = tx.send(Ok(()));
+        }
+        // Publish only once the pipeline being replaced has stopped
+        if go_rx.recv().is_err() {
+            capturer.stop_capture();
+            log::debug!("Replacement capture abandoned before it published");
+            return;
+        }
+
+        let mut publisher = FramePublisher::new(fps, start_seq);
+        // Reusable buffer for downscaled BGRA frames
+        let mut scaled: Vec<u8> = Vec::new();
+        // Arrival of the last frame processed, for the low-power frame cap
+        let mut last_processed: Option<Instant> = None;
+        // Consecutive failures, and when the current failure streak began
+        let mut failures: u32 = 0;
+        let mut degraded_since: Option<Instant> = None;
+
+        log::info!("Screen capture started ({}fps, raw RGBA)", fps);
+        CAPTURE_RUNNING.store(metrics::thread_cpu_time().is_some(), Ordering::Relaxed);
+
+        loop {
+            // Check for shutdown, or replacement by a restart
+            if *shutdown_rx.borrow() || stop.load(Ordering::Relaxed) {
+                break;
+            }
+            if let Some(cpu) = metrics::thread_cpu_time() {
+                CAPTURE_CPU_NS.store(cpu.as_nanos() as u64, Ordering::Relaxed);
+            }
+
+            // The exclusion filter is fixed per capturer, so a changed window
+            // set means rebuilding it
+            if let Some(selector) = &target_app {
+                if windows_checked.elapsed() >= APP_WINDOWS_REFRESH_INTERVAL {
+                    windows_checked = Instant::now();
+                    match app_capture::app_windows(selector) {
+                        Ok(windows) if windows.excluded != excluded_windows => {
+                            let options = capture_options(fps, look, target.clone(), &windows.excluded);
+                            match Capturer::build(options) {
+                                Ok(rebuilt) => {
+                                    capturer.stop_capture();
+                                    capturer = rebuilt;
+                                    capturer.start_capture();
+                                    excluded_windows = windows.excluded;
+                                    log::debug!("App stream window set changed, capturer rebuilt");
+                                }
+                                Err(e) => log::warn!("Failed to rebuild app capturer: {:?}", e),
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => log::warn!("Failed to list windows for app stream: {}", e),
+                    }
+                }
+            }
+
+            match capturer.get_next_frame() {
+                Ok(Frame::Video(VideoFrame::BGRA(frame))) => {
+                    let arrived = Instant::now();
+                    CaptureCounters::bump(&counters.frames_captured);
+                    if let Some(since) = degraded_since.take() {
+                        log::info!("Screen capture recovered after {} rebuild(s)", failures);
+                        let _ = events::emit(
+                            &app,
+                            "stream-recovered",
+                            StreamRecovered {
+                                attempts: failures,
+                                downtime_ms: since.elapsed().as_millis() as u64,
+                            },
+                        );
+                    }
+                    failures = 0;
+
+                    // Low-power mode caps the frame rate
+                    if power::is_low_power()
+                        && last_processed.is_some_and(|t| {
+                            arrived.saturating_duration_since(t)
+                                < Duration::from_secs(1) / power::LOW_POWER_MAX_FPS
+                        })
+                    {
+                        CaptureCounters::bump(&counters.skipped_low_power);
+                        continue;
+                    }
+                    last_processed = Some(arrived);
+
+                    // Guard: skip empty frames (scap returns 0x0 on transient
+                    // capture failures, common with external HDMI/USB displays)
+                    if frame.width == 0 || frame.height == 0 {
                         log::debug!("Skipping empty frame ({}x{})", frame.width, frame.height);
+                        CaptureCounters::bump(&counters.skipped_empty);
                         continue;
                     }
 
@@ -242,169 +1902,831 @@ pub async fn start_local_stream(
                             "Frame data mismatch: {}x{} expects {} bytes, got {}. Skipping.",
                             frame.width, frame.height, expected_len, frame.data.len()
                         );
+                        CaptureCounters::bump(&counters.skipped_mismatch);
                         continue;
                     }
 
                     // Send raw BGRA pixels with dimension header — no byte swap.
                     // The frontend applies a CSS SVG filter to swap R/B channels
                     // on the GPU, which is essentially free.
-                    let src_w = frame.width as usize;
-                    let src_h = frame.height as usize;
-
-                    // 4-byte header (u16 width + u16 height LE) + BGRA pixels
-                    let total = 4 + expected_len;
-                    frame_buf.clear();
-                    frame_buf.reserve(total);
-                    frame_buf.extend_from_slice(&(src_w as u16).to_le_bytes());
-                    frame_buf.extend_from_slice(&(src_h as u16).to_le_bytes());
-                    frame_buf.extend_from_slice(&frame.data[..expected_len]);
-
-                    let _ = capture_frame_tx.send(Bytes::copy_from_slice(&frame_buf));
+                    let mut src_w = frame.width as usize;
+                    let mut src_h = frame.height as usize;
+                    let mut pixels = &frame.data[..expected_len];
+
+                    if let Some(max_w) = max_width.get() {
+                        let (dst_w, dst_h) = fit_width(src_w as u32, src_h as u32, max_w);
+                        if dst_w as usize != src_w {
+                            scale_bgra(pixels, src_w, src_h, dst_w as usize, dst_h as usize, scaling, &mut scaled);
+                            (src_w, src_h) = (dst_w as usize, dst_h as usize);
+                            pixels = &scaled;
+                        }
+                    }
+
+                    // Static screens publish nothing (unless a client needs a format
+                    // the last update lacks)
+                    if !publisher.publish(pixels, src_w, src_h, &clients, &frame_tx) {
+                        CaptureCounters::bump(&counters.unchanged);
+                        continue;
+                    }
+                    counters.record_published(arrived.elapsed());
                 }
                 Ok(_) => {
                     // Skip non-BGRA frames (audio, etc.)
                 }
                 Err(e) => {
-                    log::error!("Frame capture error: {}", e);
-                    break;
+                    // Transient errors are common with external displays
+                    // waking from sleep; a fresh capturer usually works
+                    log::warn!("Frame capture error: {}", e);
+                    if degraded_since.is_none() {
+                        degraded_since = Some(Instant::now());
+                        let _ = events::emit(
+                            &app,
+                            "stream-degraded",
+                            StreamDegraded {
+                                error: e.to_string(),
+                                max_retries: CAPTURE_MAX_RETRIES,
+                            },
+                        );
+                    }
+                    capturer.stop_capture();
+                    let stopping = || *shutdown_rx.borrow() || stop.load(Ordering::Relaxed);
+                    let options = || capture_options(fps, look, target.clone(), &excluded_windows);
+                    match rebuild_capturer(options, &mut failures, stopping) {
+                        Some(rebuilt) => {
+                            capturer = rebuilt;
+                            capturer.start_capture();
+                        }
+                        None => {
+                            if !stopping() {
+                                log::error!(
+                                    "Screen capture failed {} times in a row, giving up",
+                                    failures
+                                );
+                            }
+                            CAPTURE_RUNNING.store(false, Ordering::Relaxed);
+                            log::info!("Screen capture stopped");
+                            return;
+                        }
+                    }
                 }
             }
         }
 
         capturer.stop_capture();
+        CAPTURE_RUNNING.store(false, Ordering::Relaxed);
         log::info!("Screen capture stopped");
     });
+    CapturePipeline { handle, stop }
+}
 
-    // Spawn the WebSocket server task
-    let ws_client_count = client_count.clone();
-    let ws_shutdown_rx = shutdown_rx.clone();
+/// CPU time used so far by the stream's capture thread; None when no
+/// capture is running or thread CPU time is unavailable on this platform.
+pub(crate) fn capture_cpu_time() -> Option<Duration> {
+    CAPTURE_RUNNING
+        .load(Ordering::Relaxed)
+        .then(|| Duration::from_nanos(CAPTURE_CPU_NS.load(Ordering::Relaxed)))
+}
 
-    let ws_handle = tokio::spawn(async move {
-        loop {
-            let mut shutdown = ws_shutdown_rx.clone();
+/// Tracks how long a stream has had no viewers. Only counts once a client
+/// has connected, so a stream nobody opened yet is not stopped.
+#[derive(Default)]
+struct IdleTimer {
+    had_clients: bool,
+    idle_since: Option<Instant>,
+}
 
-            tokio::select! {
-                result = listener.accept() => {
-                    match result {
-                        Ok((stream, addr)) => {
-                            // Disable Nagle's algorithm for low-latency frame delivery
-                            stream.set_nodelay(true).ok();
-                            log::debug!("New WebSocket client: {}", addr);
-                            let rx = frame_tx.subscribe();
-                            let count = ws_client_count.clone();
-                            let client_shutdown = ws_shutdown_rx.clone();
+impl IdleTimer {
+    /// Record the current client count; returns how long the stream has been idle.
+    fn update(&mut self, clients: usize, now: Instant) -> Option<Duration> {
+        if clients > 0 {
+            self.had_clients = true;
+            self.idle_since = None;
+            return None;
+        }
+        if !self.had_clients {
+            return None;
+        }
+        let since = *self.idle_since.get_or_insert(now);
+        Some(now.duration_since(since))
+    }
+}
 
-                            tokio::spawn(handle_ws_client(stream, rx, count, client_shutdown));
-                        }
-                        Err(e) => {
-                            log::error!("Failed to accept connection: {}", e);
+/// Stop the stream once it has had no clients for `grace` after the last
+/// one disconnected (`stream.idle_stop_secs`), emitting `stream-auto-stopped`.
+async fn watch_idle(
+    app: AppHandle,
+    clients: Arc<ClientRegistry>,
+    mut shutdown_rx: watch::Receiver<bool>,
+    grace: Duration,
+) {
+    let mut timer = IdleTimer::default();
+    let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => return,
+            _ = interval.tick() => {}
+        }
+
+        match timer.update(clients.len(), Instant::now()) {
+            Some(idle) if idle >= grace => {}
+            _ => continue,
+        }
+
+        // Only stop the session this watcher belongs to
+        let state = app.state::<StreamingState>();
+        let port = match &*state.session.lock().await {
+            Some(s) if Arc::ptr_eq(&s.clients, &clients) => s.port,
+            _ => return,
+        };
+
+        log::info!("No stream clients for {}s, stopping stream on port {}", grace.as_secs(), port);
+        if stop_stream(state.inner()).await.is_ok() {
+            let _ = events::emit(
+                &app,
+                "stream-auto-stopped",
+                StreamAutoStopped {
+                    port,
+                    idle_secs: grace.as_secs(),
+                },
+            );
+        }
+        return;
+    }
+}
+
+/// Stop the local MJPEG WebSocket streaming server
+#[tauri::command]
+pub async fn stop_local_stream(
+    state: tauri::State<'_, StreamingState>,
+) -> Result<(), AppError> {
+    stop_stream(&state).await
+}
+
+/// Stop the running stream, if any. Shared by the stop command and
+/// backend subsystems (e.g. scheduled jobs) that hold a `StreamingState`.
+pub async fn stop_stream(state: &StreamingState) -> Result<(), AppError> {
+    let pending = state.pending().take();
+    if let Some(p) = pending {
+        let _ = p.cancel_tx.send(());
+        log::info!("Cancelled stream start pending screen capture permission");
+        return Ok(());
+    }
+
+    let mut session = state.session.lock().await;
+
+    if let Some(s) = session.take() {
+        // Signal shutdown to capture thread and WS server
+        let _ = s.shutdown_tx.send(true);
+
+        // Wait for the WebSocket server task to finish
+        if let Some(ws) = s.ws_handle {
+            let _ = ws.await;
+        }
+
+        // Wait for the capture thread to finish
+        if let Some(cap) = s.capture {
+            let _ = cap.handle.join();
+        }
+
+        // Wait for the audio thread (polls shutdown every 100ms)
+        if let Some(audio) = s.audio_handle {
+            let _ = audio.join();
+        }
+
+        log::info!("Local stream stopped");
+        Ok(())
+    } else {
+        Err(AppError::NotFound("No stream is running".into()))
+    }
+}
+
+/// Get the current stream status
+#[tauri::command]
+pub async fn get_stream_status(
+    state: tauri::State<'_, StreamingState>,
+) -> Result<StreamStatus, AppError> {
+    Ok(stream_status(state.inner()).await)
+}
+
+/// Change the downscale width of the running stream without restarting it.
+///
+/// Takes effect from the next captured frame, which is sent as a keyframe at
+/// the new size. Viewers can do the same by sending
+/// `{"type":"set_max_width","max_width":1920}` over the stream WebSocket.
+///
+/// # Arguments
+/// * `max_width` - Width in pixels (320-7680), or None for native size
+#[tauri::command]
+pub async fn set_stream_max_width(
+    state: tauri::State<'_, StreamingState>,
+    max_width: Option<u32>,
+) -> Result<StreamStatus, AppError> {
+    validate_max_width(max_width)?;
+    {
+        let session = state.session.lock().await;
+        let s = session
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("No stream is running".into()))?;
+        s.max_width.set(max_width);
+    }
+    log::info!("Stream max width set to {:?}", max_width);
+    Ok(stream_status(state.inner()).await)
+}
+
+/// Capture options `restart_local_stream` can change; omitted ones keep
+/// their current value.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StreamRestartOptions {
+    pub fps: Option<u32>,
+    pub quality: Option<i32>,
+    pub display_id: Option<u32>,
+    pub scaling: Option<ScalingQuality>,
+    pub show_cursor: Option<bool>,
+    pub show_highlight: Option<bool>,
+    pub resolution: Option<CaptureResolution>,
+}
+
+/// Change the capture options of the running stream without dropping its
+/// viewers.
+///
+/// A new capture pipeline is built and started first; only once it runs is
+/// the old one stopped and the new one allowed to publish, so viewers stay
+/// connected and see a keyframe with the new settings after about one frame
+/// instead of the gap of a stop and start. If the new capture cannot start,
+/// the stream keeps running unchanged and the error is returned.
+///
+/// The port, audio, TLS and the application of an app stream cannot be
+/// changed this way.
+#[tauri::command]
+pub async fn restart_local_stream(
+    app: AppHandle,
+    state: tauri::State<'_, StreamingState>,
+    options: StreamRestartOptions,
+) -> Result<StreamStatus, AppError> {
+    {
+        let mut session = state.session.lock().await;
+        let s = session
+            .as_mut()
+            .ok_or_else(|| AppError::NotFound("No stream is running".into()))?;
+
+        let fps = options.fps.unwrap_or(s.fps);
+        let quality = options.quality.unwrap_or(s.quality);
+        if !(1..=MAX_FPS).contains(&fps) {
+            return Err(AppError::Validation(format!(
+                "FPS must be 1-{}, got: {}",
+                MAX_FPS, fps
+            )));
+        }
+        if !(1..=100).contains(&quality) {
+            return Err(AppError::Validation(format!(
+                "Quality must be 1-100, got: {}",
+                quality
+            )));
+        }
+        let display_id = options.display_id.or(s.display_id);
+        let target = find_display_target(display_id);
+        if target.is_none() && options.display_id.is_some() {
+            return Err(AppError::NotFound(format!("Display not found: {:?}", display_id)));
+        }
+        let excluded_windows = match &s.target_app {
+            Some(selector) => app_stream_exclusions(selector).await?,
+            None => Vec::new(),
+        };
+        let scaling = options.scaling.unwrap_or(s.scaling);
+        let look = CaptureLook {
+            show_cursor: options.show_cursor.unwrap_or(s.show_cursor),
+            show_highlight: options.show_highlight.unwrap_or(s.show_highlight),
+            resolution: options.resolution.unwrap_or(s.resolution),
+        };
+        let main_display = is_main_display(&target);
+
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let (go_tx, go_rx) = std::sync::mpsc::channel();
+        let pipeline = spawn_capture(
+            CaptureJob {
+                app: app.clone(),
+                fps,
+                target,
+                excluded_windows,
+                target_app: s.target_app.clone(),
+                look,
+                scaling,
+                max_width: s.max_width.clone(),
+                frame_tx: s.frame_tx.clone(),
+                clients: s.clients.clone(),
+                counters: s.counters.clone(),
+                shutdown_rx: s.shutdown_tx.subscribe(),
+                start_seq: s.frame_tx.borrow().seq,
+            },
+            Some(ready_tx),
+            go_rx,
+        );
+        ready_rx
+            .await
+            .map_err(|_| AppError::Internal("Capture thread exited during startup".into()))??;
+
+        // Retire the old pipeline, then let the new one publish. Its next
+        // frame continues the sequence numbers, as a keyframe.
+        if let Some(old) = s.capture.replace(pipeline) {
+            old.stop.store(true, Ordering::Relaxed);
+            tokio::task::spawn_blocking(move || old.handle.join())
+                .await
+                .map_err(|e| AppError::Internal(format!("Capture join task failed: {}", e)))?
+                .map_err(|_| AppError::Internal("Previous capture thread panicked".into()))?;
+        }
+        let _ = go_tx.send(());
+
+        if let Some(input) = &s.remote_input {
+            input.set_pointer(main_display);
+        }
+        s.fps = fps;
+        s.quality = quality;
+        s.display_id = display_id;
+        s.scaling = scaling;
+        s.show_cursor = look.show_cursor;
+        s.show_highlight = look.show_highlight;
+        s.resolution = look.resolution;
+        log::info!(
+            "Stream capture restarted ({}fps, display {:?}, {} viewer(s) kept)",
+            fps,
+            display_id,
+            s.clients.len()
+        );
+    }
+    Ok(stream_status(state.inner()).await)
+}
+
+/// Status of the running or pending stream, or an inactive status.
+/// Whether a stream is running, without waiting: None while a start or stop
+/// holds the session.
+pub(crate) fn stream_active(state: &StreamingState) -> Option<bool> {
+    state.session.try_lock().ok().map(|session| session.is_some())
+}
+
+pub(crate) async fn stream_status(state: &StreamingState) -> StreamStatus {
+    let session = state.session.lock().await;
+
+    match &*session {
+        Some(s) => StreamStatus {
+            active: true,
+            port: s.port,
+            fps: s.fps,
+            quality: s.quality,
+            clients: s.clients.len(),
+            display_id: s.display_id,
+            audio: s.audio,
+            permission_pending: false,
+            max_width: s.max_width.get(),
+            scaling: s.scaling,
+            show_cursor: s.show_cursor,
+            show_highlight: s.show_highlight,
+            resolution: s.resolution,
+            target_app: s.target_app.clone(),
+            tls: s.cert_fingerprint.is_some(),
+            cert_fingerprint: s.cert_fingerprint.clone(),
+            remote_control: s.remote_control,
+            client_stats: s.clients.snapshot(),
+        },
+        None => {
+            if let Some(p) = &*state.pending() {
+                return p.params.pending_status();
+            }
+            StreamStatus {
+                active: false,
+                port: 0,
+                fps: 0,
+                quality: 0,
+                clients: 0,
+                display_id: None,
+                audio: false,
+                permission_pending: false,
+                max_width: None,
+                scaling: ScalingQuality::default(),
+                show_cursor: false,
+                show_highlight: false,
+                resolution: CaptureResolution::default(),
+                target_app: None,
+                tls: false,
+                cert_fingerprint: None,
+                remote_control: false,
+                client_stats: Vec::new(),
+            }
+        }
+    }
+}
+
+/// Capture-to-receive latency reported by stream viewers.
+///
+/// Viewers acknowledge each video message with its capture time and their
+/// receive time; this aggregates the last samples per client. Only
+/// meaningful when viewer and host clocks agree (same machine or NTP).
+#[tauri::command]
+pub async fn get_stream_latency(
+    state: tauri::State<'_, StreamingState>,
+) -> Result<StreamLatency, AppError> {
+    let session = state.session.lock().await;
+    Ok(match session.as_ref() {
+        Some(s) => {
+            let (overall, clients) = s.clients.latency();
+            StreamLatency {
+                active: true,
+                overall,
+                clients,
+            }
+        }
+        None => StreamLatency::default(),
+    })
+}
+
+/// Frame-rate and drop counters of the running stream's capture thread.
+///
+/// Use it to tell whether a low frame rate comes from the OS capturer
+/// (`captured_fps`), dropped frames, a static screen (`unchanged`) or slow
+/// conversion (`avg_convert_ms`).
+#[tauri::command]
+pub async fn get_capture_stats(
+    state: tauri::State<'_, StreamingState>,
+) -> Result<CaptureStats, AppError> {
+    let session = state.session.lock().await;
+    Ok(session
+        .as_ref()
+        .map(|s| s.counters.stats(Instant::now()))
+        .unwrap_or_default())
+}
+
+/// Disconnect a single stream viewer by client id (e.g. a stale tab).
+#[tauri::command]
+pub async fn disconnect_stream_client(
+    state: tauri::State<'_, StreamingState>,
+    client_id: u64,
+) -> Result<(), AppError> {
+    let session = state.session.lock().await;
+    let s = session
+        .as_ref()
+        .ok_or_else(|| AppError::NotFound("No stream is running".into()))?;
+
+    if s.clients.kick(client_id) {
+        log::info!("Disconnecting stream client {}", client_id);
+        Ok(())
+    } else {
+        Err(AppError::NotFound(format!("Stream client not found: {}", client_id)))
+    }
+}
+
+/// Grant or revoke keyboard and mouse control for one stream viewer.
+///
+/// Requires `stream.remote_control` to have been on when the stream started.
+/// The viewer is notified, and a grant ends when it disconnects.
+#[tauri::command]
+pub async fn set_stream_client_control(
+    state: tauri::State<'_, StreamingState>,
+    client_id: u64,
+    allowed: bool,
+) -> Result<(), AppError> {
+    let session = state.session.lock().await;
+    let s = session
+        .as_ref()
+        .ok_or_else(|| AppError::NotFound("No stream is running".into()))?;
+    if !s.remote_control {
+        return Err(AppError::Validation(
+            "Remote control is off for this stream (stream.remote_control)".into(),
+        ));
+    }
+
+    let addr = s
+        .clients
+        .set_control(client_id, allowed)
+        .ok_or_else(|| AppError::NotFound(format!("Stream client not found: {}", client_id)))?;
+    log::info!(
+        "Remote control {} for stream client {} ({})",
+        if allowed { "granted" } else { "revoked" },
+        client_id,
+        addr
+    );
+    Ok(())
+}
+
+// =============================================================================
+// Local Viewer Transport
+// =============================================================================
+
+/// Receive the running stream over a Tauri IPC channel instead of the
+/// WebSocket. Meant for the app's own viewer: it skips WebSocket framing
+/// and the loopback socket, and remote viewers keep using the WebSocket.
+///
+/// `on_frame` gets the stream's binary messages unchanged (video, deltas
+/// and audio) in `format` (default BGRA), then an empty message when the
+/// subscription ends: on `unsubscribe_local_frames`, when the stream stops
+/// or when the webview goes away. The subscriber counts towards
+/// `stream.max_clients` and is listed by `get_stream_status`. Acknowledge
+/// video with `local_frame_ack`: updates are held back while frames go
+/// unacknowledged.
+///
+/// # Arguments
+/// * `port` - Port of the stream the viewer would otherwise connect to;
+///   fails with not found unless it is the running stream's
+///
+/// # Returns
+/// The subscriber's client id
+#[tauri::command]
+pub async fn subscribe_local_frames(
+    app: AppHandle,
+    state: tauri::State<'_, StreamingState>,
+    on_frame: Channel,
+    port: u16,
+    format: Option<PixelFormat>,
+) -> Result<u64, AppError> {
+    let session = state.session.lock().await;
+    let s = session
+        .as_ref()
+        .filter(|s| s.port == port)
+        .ok_or_else(|| AppError::NotFound(format!("No stream is running on port {}", port)))?;
+
+    let format = format.unwrap_or_default();
+    let max_clients = app.state::<SettingsState>().get().stream.max_clients;
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+    let (client_id, kick_rx, _control_rx) = s
+        .clients
+        .register(addr, max_clients, format, ClientTransport::Ipc)
+        .ok_or_else(|| {
+            AppError::Validation(format!("Stream client limit reached ({})", max_clients))
+        })?;
+    let acks = s.clients.watch_acks(client_id).unwrap_or_else(|| watch::channel(0).1);
+    log::debug!("Local viewer {} subscribed ({:?})", client_id, format);
+
+    tokio::spawn(run_local_viewer(
+        LocalViewer {
+            on_frame,
+            client_id,
+            format,
+            clients: s.clients.clone(),
+        },
+        s.frame_tx.subscribe(),
+        s.audio_tx.subscribe(),
+        acks,
+        kick_rx,
+        s.shutdown_tx.subscribe(),
+    ));
+    Ok(client_id)
+}
+
+/// End a subscription made with `subscribe_local_frames`.
+#[tauri::command]
+pub async fn unsubscribe_local_frames(
+    state: tauri::State<'_, StreamingState>,
+    client_id: u64,
+) -> Result<(), AppError> {
+    let session = state.session.lock().await;
+    let s = session
+        .as_ref()
+        .ok_or_else(|| AppError::NotFound("No stream is running".into()))?;
+    let is_local = s.clients.transport(client_id) == Some(ClientTransport::Ipc);
+    if !is_local || !s.clients.kick(client_id) {
+        return Err(AppError::NotFound(format!("Local viewer not found: {}", client_id)));
+    }
+    Ok(())
+}
+
+/// Acknowledge a video message received over `subscribe_local_frames`, the
+/// IPC counterpart of the WebSocket `frame_ack` message. `seq` and
+/// `capture_us` come from the message header, `received_us` is the
+/// viewer's clock (µs since the Unix epoch).
+#[tauri::command]
+pub async fn local_frame_ack(
+    state: tauri::State<'_, StreamingState>,
+    client_id: u64,
+    seq: u64,
+    capture_us: u64,
+    received_us: u64,
+) -> Result<(), AppError> {
+    if let Some(s) = state.session.lock().await.as_ref() {
+        s.clients.record_ack(client_id, seq, capture_us, received_us);
+    }
+    Ok(())
+}
+
+/// A local viewer's end of the IPC transport
+struct LocalViewer {
+    on_frame: Channel,
+    client_id: u64,
+    format: PixelFormat,
+    clients: Arc<ClientRegistry>,
+}
+
+impl LocalViewer {
+    /// Send one binary message; false once the webview is gone.
+    fn send(&self, message: &Bytes) -> bool {
+        match self.on_frame.send(InvokeResponseBody::Raw(message.to_vec())) {
+            Ok(()) => true,
+            Err(e) => {
+                log::debug!("Local viewer {} channel closed: {}", self.client_id, e);
+                false
+            }
+        }
+    }
+}
+
+/// Feed a local viewer until it unsubscribes, its webview goes away or the
+/// stream stops. Mirrors `handle_ws_client`, plus flow control: at most
+/// `LOCAL_MAX_IN_FLIGHT` frames go unacknowledged, and a held update is
+/// sent as soon as the viewer catches up.
+async fn run_local_viewer(
+    viewer: LocalViewer,
+    mut frame_rx: watch::Receiver<VideoUpdate>,
+    mut audio_rx: broadcast::Receiver<Bytes>,
+    mut acks: watch::Receiver<u64>,
+    mut kick_rx: oneshot::Receiver<()>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let (client_id, format) = (viewer.client_id, viewer.format);
+    let clients = viewer.clients.clone();
+    // Sequence number of the last video update sent; deltas only apply on top of it
+    let mut last_seq: u64 = 0;
+    // Video sent and not acknowledged yet, oldest first
+    let mut in_flight: VecDeque<(u64, Instant)> = VecDeque::new();
+    // Set when an update arrived; cleared once it is sent
+    let mut pending = false;
+
+    // Send the current frame right away: a static screen publishes no updates
+    frame_rx.mark_changed();
+
+    loop {
+        let oldest = in_flight.front().map(|(_, sent)| *sent + LOCAL_ACK_TIMEOUT);
+        tokio::select! {
+            result = frame_rx.changed(), if !pending => {
+                if result.is_err() {
+                    break;
+                }
+                pending = true;
+            }
+            result = acks.changed() => {
+                if result.is_err() {
+                    break;
+                }
+                let acked = *acks.borrow_and_update();
+                while in_flight.front().is_some_and(|(seq, _)| *seq <= acked) {
+                    in_flight.pop_front();
+                }
+            }
+            // A viewer that never acknowledges still gets a frame a second
+            _ = tokio::time::sleep_until(oldest.unwrap_or_else(Instant::now).into()),
+                if oldest.is_some() => {
+                in_flight.pop_front();
+            }
+            result = audio_rx.recv() => {
+                match result {
+                    Ok(packet) => {
+                        if !viewer.send(&packet) {
+                            break;
                         }
+                        clients.record_audio(client_id, packet.len());
                     }
-                }
-                _ = shutdown.changed() => {
-                    if *shutdown.borrow() {
-                        log::info!("WebSocket server shutting down");
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        log::debug!("Local viewer {} skipped {} audio packet(s)", client_id, n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
                         break;
                     }
                 }
             }
+            // Unsubscribed, or kicked via disconnect_stream_client
+            _ = &mut kick_rx => {
+                break;
+            }
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() {
+                    break;
+                }
+            }
         }
-    });
-
-    let status = StreamStatus {
-        active: true,
-        port: actual_port,
-        fps,
-        quality,
-        clients: 0,
-        display_id,
-    };
 
-    *session = Some(StreamSession {
-        shutdown_tx,
-        capture_handle: Some(capture_handle),
-        ws_handle: Some(ws_handle),
-        port: actual_port,
-        fps,
-        quality,
-        display_id,
-        client_count,
-    });
+        if !pending || in_flight.len() >= LOCAL_MAX_IN_FLIGHT {
+            continue;
+        }
+        pending = false;
+        let update = frame_rx.borrow_and_update().clone();
+        let Some(frame) = update.frame(format).filter(|f| !f.keyframe.is_empty()) else {
+            continue;
+        };
+        // A viewer that missed an update (watch skips, held updates) needs the full frame
+        let frame_data = match &frame.delta {
+            Some(delta) if last_seq + 1 == update.seq => delta,
+            _ => &frame.keyframe,
+        };
+        let send_start = Instant::now();
+        if !viewer.send(frame_data) {
+            break;
+        }
+        clients.record_send(client_id, frame_data.len(), send_start.elapsed());
+        last_seq = update.seq;
+        in_flight.push_back((update.seq, send_start));
+    }
 
-    Ok(status)
+    clients.unregister(client_id);
+    // Tells the viewer to reconnect, possibly over the WebSocket
+    let _ = viewer.on_frame.send(InvokeResponseBody::Raw(Vec::new()));
+    log::debug!("Local viewer {} unsubscribed", client_id);
 }
 
-/// Stop the local MJPEG WebSocket streaming server
-#[tauri::command]
-pub async fn stop_local_stream(
-    state: tauri::State<'_, StreamingState>,
-) -> Result<(), String> {
-    let mut session = state.session.lock().await;
+// =============================================================================
+// WebSocket Client Handler
+// =============================================================================
 
-    if let Some(s) = session.take() {
-        // Signal shutdown to capture thread and WS server
-        let _ = s.shutdown_tx.send(true);
+/// Validate WebSocket Origin header against allowed origins.
+/// Returns true if the origin is in the allowlist.
+fn is_allowed_origin(origin: &str) -> bool {
+    ALLOWED_ORIGINS.contains(&origin)
+}
 
-        // Wait for the WebSocket server task to finish
-        if let Some(ws) = s.ws_handle {
-            let _ = ws.await;
-        }
+/// A `stream.allowed_ips` entry: a single address or a CIDR block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRule {
+    net: IpAddr,
+    prefix: u8,
+}
 
-        // Wait for the capture thread to finish
-        if let Some(cap) = s.capture_handle {
-            let _ = cap.join();
+impl IpRule {
+    /// Parse `"192.168.1.20"`, `"192.168.1.0/24"` or an IPv6 equivalent.
+    pub fn parse(rule: &str) -> Result<Self, AppError> {
+        let invalid =
+            || AppError::Validation(format!("Invalid IP or CIDR in stream.allowed_ips: {}", rule));
+        let (addr, prefix) = match rule.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>().map_err(|_| invalid())?)),
+            None => (rule.trim(), None),
+        };
+        let net: IpAddr = addr.parse().map_err(|_| invalid())?;
+        let max = if net.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        if prefix > max {
+            return Err(invalid());
         }
+        Ok(Self { net, prefix })
+    }
 
-        log::info!("Local stream stopped");
-        Ok(())
-    } else {
-        Err("No stream is running".into())
+    fn matches(&self, ip: IpAddr) -> bool {
+        // IPv4 peers may show up as IPv4-mapped IPv6 addresses
+        match (self.net, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
     }
 }
 
-/// Get the current stream status
-#[tauri::command]
-pub async fn get_stream_status(
-    state: tauri::State<'_, StreamingState>,
-) -> Result<StreamStatus, String> {
-    let session = state.session.lock().await;
+/// Which peers may connect to the stream server, checked at accept time.
+pub(crate) struct PeerPolicy {
+    allowed: Vec<IpRule>,
+    /// 0 = unlimited
+    max_clients: usize,
+}
 
-    match &*session {
-        Some(s) => Ok(StreamStatus {
-            active: true,
-            port: s.port,
-            fps: s.fps,
-            quality: s.quality,
-            clients: s.client_count.load(std::sync::atomic::Ordering::Relaxed),
-            display_id: s.display_id,
-        }),
-        None => Ok(StreamStatus {
-            active: false,
-            port: 0,
-            fps: 0,
-            quality: 0,
-            clients: 0,
-            display_id: None,
-        }),
+impl PeerPolicy {
+    /// Build from (already validated) settings; unparsable rules are skipped.
+    pub(crate) fn from_settings(allowed_ips: &[String], max_clients: usize) -> Self {
+        Self {
+            allowed: allowed_ips.iter().filter_map(|r| IpRule::parse(r).ok()).collect(),
+            max_clients,
+        }
     }
-}
 
-// =============================================================================
-// WebSocket Client Handler
-// =============================================================================
+    /// Loopback is always allowed so the app's own viewer keeps working.
+    fn allows(&self, ip: IpAddr) -> bool {
+        self.allowed.is_empty()
+            || ip.to_canonical().is_loopback()
+            || self.allowed.iter().any(|r| r.matches(ip))
+    }
 
-/// Validate WebSocket Origin header against allowed origins.
-/// Returns true if the origin is in the allowlist.
-fn is_allowed_origin(origin: &str) -> bool {
-    ALLOWED_ORIGINS.iter().any(|a| *a == origin)
+    fn is_full(&self, connected: usize) -> bool {
+        self.max_clients > 0 && connected >= self.max_clients
+    }
 }
 
-async fn handle_ws_client(
-    stream: tokio::net::TcpStream,
-    mut frame_rx: watch::Receiver<Bytes>,
-    client_count: Arc<std::sync::atomic::AtomicUsize>,
+#[allow(clippy::result_large_err)] // Err type is dictated by tungstenite's handshake callback
+#[allow(clippy::too_many_arguments)]
+async fn handle_ws_client<S>(
+    stream: S,
+    addr: SocketAddr,
+    mut frame_rx: watch::Receiver<VideoUpdate>,
+    mut audio_rx: broadcast::Receiver<Bytes>,
+    clients: Arc<ClientRegistry>,
+    max_width: Arc<SharedMaxWidth>,
+    max_clients: usize,
+    remote_input: Option<Arc<RemoteInput>>,
     shutdown_rx: watch::Receiver<bool>,
-) {
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut format = PixelFormat::default();
+    let mut access = StreamAccessEntry::new(addr, AccessOutcome::HandshakeFailed);
+
     // Validate Origin header during WebSocket handshake to prevent
     // DNS rebinding and Cross-Site WebSocket Hijacking (CSWSH) attacks.
     let ws_stream = match tokio_tungstenite::accept_hdr_async(
@@ -416,15 +2738,30 @@ async fn handle_ws_client(
                 .get("Origin")
                 .and_then(|v| v.to_str().ok())
                 .unwrap_or("");
+            access.origin = Some(origin.to_string()).filter(|o| !o.is_empty());
 
-            if is_allowed_origin(origin) {
-                Ok(resp)
-            } else {
+            if !is_allowed_origin(origin) {
                 log::warn!("Rejected WebSocket connection from origin: {}", origin);
-                Err(tokio_tungstenite::tungstenite::handshake::server::Response::builder()
+                access.outcome = AccessOutcome::RejectedOrigin;
+                return Err(tokio_tungstenite::tungstenite::handshake::server::Response::builder()
                     .status(403)
                     .body(Some("Forbidden: invalid origin".into()))
-                    .unwrap())
+                    .unwrap());
+            }
+
+            match requested_format(req.uri().query()) {
+                Ok(requested) => {
+                    format = requested;
+                    Ok(resp)
+                }
+                Err(value) => {
+                    log::warn!("Rejected stream client asking for pixel format: {}", value);
+                    access.outcome = AccessOutcome::RejectedFormat;
+                    Err(tokio_tungstenite::tungstenite::handshake::server::Response::builder()
+                        .status(400)
+                        .body(Some(format!("Unsupported pixel format: {}", value)))
+                        .unwrap())
+                }
             }
         },
     )
@@ -433,43 +2770,153 @@ async fn handle_ws_client(
         Ok(ws) => ws,
         Err(e) => {
             log::error!("WebSocket handshake failed: {}", e);
+            stream_access::record(&access);
             return;
         }
     };
 
-    client_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    // Re-checked here since several handshakes may have been in flight at accept time
+    let Some((client_id, mut kick_rx, mut control_rx)) =
+        clients.register(addr, max_clients, format, ClientTransport::WebSocket)
+    else {
+        log::warn!("Dropping stream client {}: {} client limit reached", addr, max_clients);
+        access.outcome = AccessOutcome::RejectedLimit;
+        stream_access::record(&access);
+        return;
+    };
+    access.outcome = AccessOutcome::Accepted;
+    access.client_id = Some(client_id);
+    log::debug!("WebSocket client {} connected from {} ({:?})", client_id, addr, format);
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
     let mut shutdown = shutdown_rx;
 
+    // Sequence number of the last video update sent; deltas only apply on top of it
+    let mut last_seq: u64 = 0;
+
+    // Send the current frame right away: a static screen publishes no updates.
+    // If it is not in this client's format yet, the next update will be.
+    let initial = frame_rx.borrow_and_update().clone();
+    if let Some(frame) = initial.frame(format).filter(|f| !f.keyframe.is_empty()) {
+        let frame_len = frame.keyframe.len();
+        let send_start = Instant::now();
+        if ws_sender.send(Message::Binary(frame.keyframe.clone())).await.is_err() {
+            record_departure(access, clients.unregister(client_id));
+            return;
+        }
+        clients.record_send(client_id, frame_len, send_start.elapsed());
+        last_seq = initial.seq;
+    }
+
     loop {
         tokio::select! {
             // Send latest frame to the client (watch = always latest, no stale buffering)
             result = frame_rx.changed() => {
                 match result {
                     Ok(()) => {
-                        let frame_data = frame_rx.borrow_and_update().clone();
-                        if frame_data.is_empty() { continue; }
+                        let update = frame_rx.borrow_and_update().clone();
+                        let Some(frame) = update.frame(format) else { continue };
+                        if frame.keyframe.is_empty() { continue; }
+                        // A client that missed an update (watch skips) needs the full frame
+                        let frame_data = match &frame.delta {
+                            Some(delta) if last_seq + 1 == update.seq => delta.clone(),
+                            _ => frame.keyframe.clone(),
+                        };
+                        last_seq = update.seq;
+                        let frame_len = frame_data.len();
+                        let send_start = Instant::now();
                         if let Err(e) = ws_sender.send(Message::Binary(frame_data)).await {
                             log::debug!("WebSocket send error (client disconnected): {}", e);
                             break;
                         }
+                        clients.record_send(client_id, frame_len, send_start.elapsed());
                     }
                     Err(_) => {
                         break;
                     }
                 }
             }
-            // Handle incoming messages (ignore for now, could be control messages)
+            // Forward every audio packet (skipping ahead if this client lags)
+            result = audio_rx.recv() => {
+                match result {
+                    Ok(packet) => {
+                        let packet_len = packet.len();
+                        if let Err(e) = ws_sender.send(Message::Binary(packet)).await {
+                            log::debug!("WebSocket send error (client disconnected): {}", e);
+                            break;
+                        }
+                        clients.record_audio(client_id, packet_len);
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        log::debug!("Stream client {} skipped {} audio packet(s)", client_id, n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        break;
+                    }
+                }
+            }
+            // Viewer messages: frame acks for latency, resolution changes and
+            // remote control; binary and pings are ignored
             msg = ws_receiver.next() => {
                 match msg {
-                    Some(Ok(_)) => {
-                        // Could handle control messages here (quality, fps changes)
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<ClientMessage>(text.as_str()) {
+                            Ok(ClientMessage::FrameAck { seq, capture_us, received_us }) => {
+                                clients.record_ack(client_id, seq, capture_us, received_us);
+                            }
+                            Ok(ClientMessage::SetMaxWidth { max_width: width }) => {
+                                match validate_max_width(width) {
+                                    Ok(()) => {
+                                        log::info!("Stream client {} set max width to {:?}", client_id, width);
+                                        max_width.set(width);
+                                    }
+                                    Err(e) => {
+                                        log::debug!("Ignoring max width from stream client {}: {}", client_id, e);
+                                    }
+                                }
+                            }
+                            Ok(ClientMessage::RequestControl) => match &remote_input {
+                                Some(input) if !*control_rx.borrow() => {
+                                    input.request_control(client_id, addr);
+                                }
+                                Some(_) => {}
+                                None => {
+                                    log::debug!("Stream client {} asked for control, but remote control is off", client_id);
+                                }
+                            },
+                            Ok(ClientMessage::Input { event }) => match &remote_input {
+                                Some(input) if *control_rx.borrow() => input.send(client_id, event),
+                                _ => {
+                                    log::debug!("Ignoring input from stream client {} without control", client_id);
+                                }
+                            },
+                            Err(e) => {
+                                log::debug!("Ignoring message from stream client {}: {}", client_id, e);
+                            }
+                        }
                     }
+                    Some(Ok(_)) => {}
                     _ => {
                         break;
                     }
                 }
             }
+            // Control granted or revoked via set_stream_client_control
+            result = control_rx.changed() => {
+                if result.is_err() {
+                    break;
+                }
+                let allowed = *control_rx.borrow_and_update();
+                let msg = serde_json::to_string(&ServerMessage::Control { allowed }).unwrap_or_default();
+                if let Err(e) = ws_sender.send(Message::Text(msg.into())).await {
+                    log::debug!("WebSocket send error (client disconnected): {}", e);
+                    break;
+                }
+            }
+            // Kicked via disconnect_stream_client
+            _ = &mut kick_rx => {
+                let _ = ws_sender.send(Message::Close(None)).await;
+                break;
+            }
             // Check for shutdown
             _ = shutdown.changed() => {
                 if *shutdown.borrow() {
@@ -479,6 +2926,545 @@ async fn handle_ws_client(
         }
     }
 
-    client_count.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
-    log::debug!("WebSocket client disconnected");
+    record_departure(access, clients.unregister(client_id));
+    log::debug!("WebSocket client {} disconnected", client_id);
+}
+
+/// Write the access log entry of an accepted viewer that left, with the
+/// totals from its registry entry.
+fn record_departure(mut access: StreamAccessEntry, entry: Option<ClientEntry>) {
+    if let Some(entry) = entry {
+        let now = chrono::Local::now();
+        access.timestamp = now.to_rfc3339();
+        access.duration_ms = Some((now - entry.connected_at).num_milliseconds().max(0) as u64);
+        access.bytes_sent = entry.bytes_sent;
+        access.frames_sent = entry.frames_sent;
+    }
+    stream_access::record(&access);
+}
+
+/// Pixel format from the `format` query parameter of a connection URL
+/// (BGRA when absent). Returns the rejected value if it is unknown.
+fn requested_format(query: Option<&str>) -> Result<PixelFormat, String> {
+    let value = query
+        .unwrap_or("")
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("format="));
+    match value {
+        None | Some("") => Ok(PixelFormat::default()),
+        Some(v) => PixelFormat::parse(v).ok_or_else(|| v.to_string()),
+    }
+}
+
+/// Capturer options for a stream of `target`. Windows with an id in
+/// `excluded` (sorted) are hidden from the capture.
+/// Cursor and size options of a stream's capturer
+#[derive(Debug, Clone, Copy)]
+struct CaptureLook {
+    show_cursor: bool,
+    show_highlight: bool,
+    resolution: CaptureResolution,
+}
+
+fn capture_options(
+    fps: u32,
+    look: CaptureLook,
+    target: Option<scap::Target>,
+    excluded: &[u32],
+) -> Options {
+    let excluded_targets = (!excluded.is_empty()).then(|| {
+        scap::get_all_targets()
+            .into_iter()
+            .filter(|t| matches!(t, scap::Target::Window(w) if excluded.binary_search(&w.id).is_ok()))
+            .collect()
+    });
+    Options {
+        fps,
+        show_cursor: look.show_cursor,
+        show_highlight: look.show_highlight,
+        target,
+        output_type: FrameType::BGRAFrame,
+        output_resolution: look.resolution.to_scap(),
+        excluded_targets,
+        ..Default::default()
+    }
+}
+
+// =============================================================================
+// Single-Frame Capture
+// =============================================================================
+
+/// Resolve a display id to a capture target (the main display when `None`).
+/// Returns None if no display has the given id.
+pub(crate) fn find_display_target(display_id: Option<u32>) -> Option<scap::Target> {
+    match display_id {
+        Some(id) => scap::get_all_targets().into_iter().find(|t| {
+            if let scap::Target::Display(d) = t {
+                d.id == id
+            } else {
+                false
+            }
+        }),
+        None => Some(scap::Target::Display(scap::get_main_display())),
+    }
+}
+
+/// Capture one BGRA frame from `target` and stop the capturer.
+///
+/// Blocking — run inside `spawn_blocking`. Skips empty or truncated frames
+/// (same guards as the streaming loop) up to `SNAPSHOT_MAX_ATTEMPTS` times.
+pub(crate) fn capture_single_frame(target: scap::Target) -> Result<BGRAFrame, AppError> {
+    let options = Options {
+        fps: MAX_FPS,
+        show_cursor: false,
+        show_highlight: false,
+        target: Some(target),
+        output_type: FrameType::BGRAFrame,
+        output_resolution: Resolution::Captured,
+        ..Default::default()
+    };
+
+    let mut capturer = Capturer::build(options)
+        .map_err(|e| AppError::Capture(format!("Failed to build capturer: {:?}", e)))?;
+    capturer.start_capture();
+
+    let mut result = Err(AppError::Capture("No usable frame received".into()));
+    for _ in 0..SNAPSHOT_MAX_ATTEMPTS {
+        match capturer.get_next_frame() {
+            Ok(Frame::Video(VideoFrame::BGRA(frame))) => {
+                let expected_len = frame.width.max(0) as usize * frame.height.max(0) as usize * 4;
+                if expected_len > 0 && frame.data.len() >= expected_len {
+                    result = Ok(frame);
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                result = Err(AppError::Capture(format!("Frame capture error: {}", e)));
+                break;
+            }
+        }
+    }
+
+    capturer.stop_capture();
+    result
+}
+
+/// Downscale a BGRA buffer to fit within `max_width` and convert it to packed RGB.
+///
+/// Uses nearest-neighbor sampling, which is plenty for small previews.
+/// Images narrower than `max_width` keep their original size.
+/// Returns the RGB pixels and the output dimensions.
+pub(crate) fn downscale_bgra_to_rgb(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    max_width: u32,
+) -> (Vec<u8>, u32, u32) {
+    let (dst_w, dst_h) = fit_width(width, height, max_width);
+    let (width, dst_w, dst_h) = (width as usize, dst_w as usize, dst_h as usize);
+
+    // Source byte offset of every output column, computed once instead of per pixel
+    let columns: Vec<usize> = (0..dst_w).map(|x| x * width / dst_w * 4).collect();
+
+    let mut rgb = vec![0u8; dst_w * dst_h * 3];
+    for (y, out_row) in rgb.chunks_exact_mut(dst_w * 3).enumerate() {
+        let src_y = y * height as usize / dst_h;
+        let src_row = &data[src_y * width * 4..(src_y + 1) * width * 4];
+        if dst_w == width {
+            bgra_row_to_rgb(src_row, out_row);
+            continue;
+        }
+        for (out, &i) in out_row.chunks_exact_mut(3).zip(&columns) {
+            out.copy_from_slice(&[src_row[i + 2], src_row[i + 1], src_row[i]]);
+        }
+    }
+
+    (rgb, dst_w as u32, dst_h as u32)
+}
+
+/// Output size for an image scaled down to at most `max_width`, keeping the
+/// aspect ratio. Images that already fit keep their size.
+pub(crate) fn fit_width(width: u32, height: u32, max_width: u32) -> (u32, u32) {
+    if width > max_width {
+        let h = ((height as u64 * max_width as u64) / width as u64).max(1) as u32;
+        (max_width, h)
+    } else {
+        (width, height)
+    }
+}
+
+/// Source index range `[start, end)` covered by each of `dst_len` output
+/// pixels along one axis (every range holds at least one source pixel).
+fn source_spans(src_len: usize, dst_len: usize) -> Vec<(usize, usize)> {
+    (0..dst_len)
+        .map(|i| {
+            let start = i * src_len / dst_len;
+            (start, ((i + 1) * src_len / dst_len).max(start + 1))
+        })
+        .collect()
+}
+
+/// Downscale a BGRA frame to `dst_w` x `dst_h`, writing BGRA into `out`.
+pub(crate) fn scale_bgra(
+    src: &[u8],
+    width: usize,
+    height: usize,
+    dst_w: usize,
+    dst_h: usize,
+    quality: ScalingQuality,
+    out: &mut Vec<u8>,
+) {
+    out.clear();
+    out.resize(dst_w * dst_h * 4, 0);
+    let cols = source_spans(width, dst_w);
+    let rows = source_spans(height, dst_h);
+
+    match quality {
+        ScalingQuality::Nearest => {
+            for (out_row, &(y, _)) in out.chunks_exact_mut(dst_w * 4).zip(&rows) {
+                let src_row = &src[y * width * 4..(y + 1) * width * 4];
+                for (px, &(x, _)) in out_row.chunks_exact_mut(4).zip(&cols) {
+                    px.copy_from_slice(&src_row[x * 4..x * 4 + 4]);
+                }
+            }
+        }
+        ScalingQuality::Area => {
+            // Per-channel sums for one output row, reused across rows
+            let mut acc = vec![0u32; dst_w * 4];
+            for (out_row, &(y0, y1)) in out.chunks_exact_mut(dst_w * 4).zip(&rows) {
+                acc.fill(0);
+                for y in y0..y1 {
+                    let src_row = &src[y * width * 4..(y + 1) * width * 4];
+                    for (sum, &(x0, x1)) in acc.chunks_exact_mut(4).zip(&cols) {
+                        for px in src_row[x0 * 4..x1 * 4].chunks_exact(4) {
+                            sum[0] += px[0] as u32;
+                            sum[1] += px[1] as u32;
+                            sum[2] += px[2] as u32;
+                            sum[3] += px[3] as u32;
+                        }
+                    }
+                }
+                for ((px, sum), &(x0, x1)) in out_row.chunks_exact_mut(4).zip(acc.chunks_exact(4)).zip(&cols) {
+                    let n = ((x1 - x0) * (y1 - y0)) as u32;
+                    for c in 0..4 {
+                        px[c] = ((sum[c] + n / 2) / n) as u8;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Convert one row of BGRA pixels to packed RGB.
+///
+/// Fixed-size chunks with no index arithmetic or bounds checks in the
+/// loop body, which lets the compiler vectorize the shuffle.
+pub(crate) fn bgra_row_to_rgb(src: &[u8], dst: &mut [u8]) {
+    for (out, px) in dst.chunks_exact_mut(3).zip(src.chunks_exact(4)) {
+        out[0] = px[2];
+        out[1] = px[1];
+        out[2] = px[0];
+    }
+}
+
+/// Encode packed RGB pixels as a JPEG.
+pub(crate) fn encode_jpeg(rgb: &[u8], width: u32, height: u32, quality: u8) -> Result<Vec<u8>, AppError> {
+    let mut out = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality)
+        .encode(rgb, width, height, image::ExtendedColorType::Rgb8)
+        .map_err(|e| AppError::Capture(format!("JPEG encode failed: {}", e)))?;
+    Ok(out)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_auto_port_skips_ports_in_use() {
+        assert!(is_valid_stream_port(STREAM_PORT_AUTO));
+        assert!(!is_valid_stream_port(STREAM_PORT_MAX + 1));
+
+        let first = bind_stream_listener(STREAM_PORT_AUTO).await.unwrap();
+        let first_port = first.local_addr().unwrap().port();
+        assert!((STREAM_PORT_MIN..=STREAM_PORT_MAX).contains(&first_port));
+        assert!(matches!(
+            bind_stream_listener(first_port).await,
+            Err(AppError::PortInUse(p)) if p == first_port
+        ));
+        let second = bind_stream_listener(STREAM_PORT_AUTO).await.unwrap();
+        assert_ne!(second.local_addr().unwrap().port(), first_port);
+    }
+
+    #[test]
+    fn test_ip_rule_parse_and_match() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let lan = IpRule::parse("192.168.1.0/24").unwrap();
+        assert!(lan.matches(ip("192.168.1.77")));
+        assert!(lan.matches(ip("::ffff:192.168.1.77")));
+        assert!(!lan.matches(ip("192.168.2.1")));
+
+        let host = IpRule::parse("10.0.0.5").unwrap();
+        assert!(host.matches(ip("10.0.0.5")));
+        assert!(!host.matches(ip("10.0.0.6")));
+
+        assert!(IpRule::parse("0.0.0.0/0").unwrap().matches(ip("8.8.8.8")));
+        assert!(IpRule::parse("fd00::/8").unwrap().matches(ip("fd12::1")));
+        assert!(IpRule::parse("10.0.0.0/33").is_err());
+        assert!(IpRule::parse("not-an-ip").is_err());
+    }
+
+    #[test]
+    fn test_peer_policy() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let open = PeerPolicy::from_settings(&[], 0);
+        assert!(open.allows(ip("203.0.113.9")));
+        assert!(!open.is_full(1000));
+
+        let lan = PeerPolicy::from_settings(&["192.168.1.0/24".into()], 2);
+        assert!(lan.allows(ip("192.168.1.10")));
+        assert!(lan.allows(ip("127.0.0.1")));
+        assert!(!lan.allows(ip("203.0.113.9")));
+        assert!(!lan.is_full(1));
+        assert!(lan.is_full(2));
+    }
+
+    #[test]
+    fn test_registry_enforces_client_limit() {
+        let registry = ClientRegistry::default();
+        let addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        let ws = ClientTransport::WebSocket;
+        assert!(registry.register(addr, 1, PixelFormat::Bgra, ws).is_some());
+        assert!(registry.register(addr, 1, PixelFormat::Bgra, ws).is_none());
+        assert!(registry.register(addr, 0, PixelFormat::Bgra, ws).is_some());
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[test]
+    fn test_registry_control_grant() {
+        let registry = ClientRegistry::default();
+        let addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        let (id, _kick_rx, control_rx) = registry
+            .register(addr, 0, PixelFormat::Bgra, ClientTransport::WebSocket)
+            .unwrap();
+        assert!(!*control_rx.borrow());
+
+        assert_eq!(registry.set_control(id, true), Some(addr));
+        assert!(*control_rx.borrow());
+        assert!(registry.snapshot()[0].control);
+        assert_eq!(registry.set_control(id + 1, true), None);
+    }
+
+    #[test]
+    fn test_input_message() {
+        let msg: ClientMessage = serde_json::from_str(
+            r#"{"type":"input","event":{"kind":"key","key":"Enter","action":"click"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(msg, ClientMessage::Input { .. }));
+        let msg: ClientMessage = serde_json::from_str(r#"{"type":"request_control"}"#).unwrap();
+        assert!(matches!(msg, ClientMessage::RequestControl));
+        assert_eq!(
+            serde_json::to_string(&ServerMessage::Control { allowed: true }).unwrap(),
+            r#"{"type":"control","allowed":true}"#
+        );
+    }
+
+    #[test]
+    fn test_capture_retry_delay() {
+        assert_eq!(capture_retry_delay(1), Duration::from_millis(500));
+        assert_eq!(capture_retry_delay(2), Duration::from_secs(1));
+        assert_eq!(capture_retry_delay(4), Duration::from_secs(4));
+        assert_eq!(capture_retry_delay(5), CAPTURE_RETRY_MAX);
+        assert_eq!(capture_retry_delay(40), CAPTURE_RETRY_MAX);
+    }
+
+    #[test]
+    fn test_capture_resolution_names() {
+        let parsed: CaptureResolution = serde_json::from_str(r#""1080p""#).unwrap();
+        assert_eq!(parsed, CaptureResolution::P1080);
+        assert_eq!(serde_json::to_string(&CaptureResolution::default()).unwrap(), r#""captured""#);
+        assert!(matches!(CaptureResolution::P720.to_scap(), Resolution::_720p));
+    }
+
+    #[test]
+    fn test_pixel_format_negotiation() {
+        assert_eq!(requested_format(None), Ok(PixelFormat::Bgra));
+        assert_eq!(requested_format(Some("token=x&format=yuv420")), Ok(PixelFormat::Yuv420));
+        assert_eq!(requested_format(Some("format=RGB")), Ok(PixelFormat::Rgb));
+        assert_eq!(requested_format(Some("format=nv12")), Err("nv12".to_string()));
+
+        let registry = ClientRegistry::default();
+        let addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        assert_eq!(registry.formats_in_use(), vec![PixelFormat::Bgra]);
+        registry.register(addr, 0, PixelFormat::Yuv420, ClientTransport::WebSocket).unwrap();
+        registry.register(addr, 0, PixelFormat::Rgba, ClientTransport::WebSocket).unwrap();
+        registry.register(addr, 0, PixelFormat::Yuv420, ClientTransport::WebSocket).unwrap();
+        assert_eq!(registry.formats_in_use(), vec![PixelFormat::Rgba, PixelFormat::Yuv420]);
+    }
+
+    #[test]
+    fn test_capture_counters() {
+        let counters = CaptureCounters::new();
+        let t0 = counters.started;
+        assert_eq!(counters.stats(t0).captured_fps, 0.0);
+
+        for _ in 0..20 {
+            CaptureCounters::bump(&counters.frames_captured);
+        }
+        CaptureCounters::bump(&counters.skipped_empty);
+        counters.record_published(Duration::from_millis(4));
+        counters.record_published(Duration::from_millis(8));
+
+        let stats = counters.stats(t0 + Duration::from_secs(2));
+        assert!(stats.active);
+        assert_eq!((stats.frames_captured, stats.skipped_empty, stats.published), (20, 1, 2));
+        assert_eq!((stats.captured_fps, stats.published_fps), (10.0, 1.0));
+        assert_eq!((stats.avg_convert_ms, stats.max_convert_ms), (6.0, 8.0));
+    }
+
+    #[test]
+    fn test_frame_stamp_header() {
+        let mut buf = Vec::new();
+        FrameStamp { seq: 0x1_0000_0002, capture_us: 0x0A0B }.write_header(MSG_TYPE_VIDEO, &mut buf);
+        assert_eq!(buf, vec![MSG_TYPE_VIDEO, 2, 0, 0, 0, 0x0B, 0x0A, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(buf.len(), VIDEO_STAMP_LEN);
+    }
+
+    #[test]
+    fn test_latency_stats() {
+        assert_eq!(LatencyStats::from_samples(&[]), LatencyStats::default());
+
+        let samples: Vec<f64> = (1..=20).rev().map(f64::from).collect();
+        let stats = LatencyStats::from_samples(&samples);
+        assert_eq!(stats.samples, 20);
+        assert_eq!(stats.last_ms, 1.0);
+        assert_eq!(stats.min_ms, 1.0);
+        assert_eq!(stats.max_ms, 20.0);
+        assert_eq!(stats.avg_ms, 10.5);
+        assert_eq!(stats.p50_ms, 11.0);
+        assert_eq!(stats.p95_ms, 19.0);
+    }
+
+    #[test]
+    fn test_frame_ack_recorded() {
+        let msg: ClientMessage =
+            serde_json::from_str(r#"{"type":"frame_ack","seq":7,"capture_us":1000,"received_us":13500}"#)
+                .unwrap();
+        let ClientMessage::FrameAck { seq, capture_us, received_us } = msg else {
+            panic!("expected frame_ack, got {:?}", msg);
+        };
+
+        let registry = ClientRegistry::default();
+        let addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        let (id, _kick, _control) = registry
+            .register(addr, 0, PixelFormat::Bgra, ClientTransport::WebSocket)
+            .unwrap();
+        registry.record_ack(id, seq, capture_us, received_us);
+        // Viewer clock behind the host: clamped to 0
+        registry.record_ack(id, 8, 5000, 4000);
+
+        let (overall, clients) = registry.latency();
+        assert_eq!(overall.samples, 2);
+        assert_eq!(overall.max_ms, 12.5);
+        assert_eq!(clients[0].last_seq, 8);
+        assert_eq!(clients[0].stats.last_ms, 0.0);
+    }
+
+    #[test]
+    fn test_set_max_width_message() {
+        let msg: ClientMessage =
+            serde_json::from_str(r#"{"type":"set_max_width","max_width":2560}"#).unwrap();
+        assert!(matches!(msg, ClientMessage::SetMaxWidth { max_width: Some(2560) }));
+
+        let shared = SharedMaxWidth::new(Some(960));
+        assert_eq!(shared.get(), Some(960));
+        shared.set(None);
+        assert_eq!(shared.get(), None);
+
+        assert!(validate_max_width(None).is_ok());
+        assert!(validate_max_width(Some(STREAM_MAX_WIDTH)).is_ok());
+        assert!(validate_max_width(Some(STREAM_MIN_WIDTH - 1)).is_err());
+        assert!(validate_max_width(Some(STREAM_MAX_WIDTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_idle_timer_waits_for_first_client() {
+        let mut timer = IdleTimer::default();
+        let t0 = Instant::now();
+        assert_eq!(timer.update(0, t0), None);
+        assert_eq!(timer.update(2, t0), None);
+        assert_eq!(timer.update(0, t0), Some(Duration::ZERO));
+        assert_eq!(timer.update(0, t0 + Duration::from_secs(5)), Some(Duration::from_secs(5)));
+        // A reconnect resets the idle period
+        assert_eq!(timer.update(1, t0 + Duration::from_secs(6)), None);
+        assert_eq!(timer.update(0, t0 + Duration::from_secs(7)), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_downscale_swaps_channels() {
+        // 1x1 BGRA pixel: B=1, G=2, R=3
+        let (rgb, w, h) = downscale_bgra_to_rgb(&[1, 2, 3, 255], 1, 1, 64);
+        assert_eq!((w, h), (1, 1));
+        assert_eq!(rgb, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_downscale_preserves_aspect_ratio() {
+        let data = vec![0u8; 400 * 200 * 4];
+        let (rgb, w, h) = downscale_bgra_to_rgb(&data, 400, 200, 100);
+        assert_eq!((w, h), (100, 50));
+        assert_eq!(rgb.len(), 100 * 50 * 3);
+    }
+
+    #[test]
+    fn test_scale_bgra_area_averages() {
+        // 2x1 BGRA image: black and white pixels average to mid-grey
+        let src = [0, 0, 0, 255, 255, 255, 255, 255];
+        let mut out = Vec::new();
+        scale_bgra(&src, 2, 1, 1, 1, ScalingQuality::Area, &mut out);
+        assert_eq!(out, vec![128, 128, 128, 255]);
+
+        scale_bgra(&src, 2, 1, 1, 1, ScalingQuality::Nearest, &mut out);
+        assert_eq!(out, vec![0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_source_spans_cover_every_pixel() {
+        let spans = source_spans(10, 3);
+        assert_eq!(spans, vec![(0, 3), (3, 6), (6, 10)]);
+        // Upscaling never yields an empty span
+        assert!(source_spans(2, 5).iter().all(|(s, e)| e > s));
+    }
+
+    #[test]
+    fn test_downscale_samples_nearest_pixel() {
+        // 4x2 BGRA image where each pixel's red channel is its column index
+        let mut data = vec![0u8; 4 * 2 * 4];
+        for (i, px) in data.chunks_exact_mut(4).enumerate() {
+            px[2] = (i % 4) as u8;
+        }
+        let (rgb, w, h) = downscale_bgra_to_rgb(&data, 4, 2, 2);
+        assert_eq!((w, h), (2, 1));
+        assert_eq!(rgb, vec![0, 0, 0, 2, 0, 0]);
+    }
+
+    #[test]
+    fn test_downscale_never_upscales() {
+        let data = vec![0u8; 10 * 10 * 4];
+        let (_, w, h) = downscale_bgra_to_rgb(&data, 10, 10, 320);
+        assert_eq!((w, h), (10, 10));
+    }
+
+    #[test]
+    fn test_encode_jpeg_produces_jpeg_header() {
+        let rgb = vec![128u8; 8 * 8 * 3];
+        let jpeg = encode_jpeg(&rgb, 8, 8, 70).unwrap();
+        assert_eq!(&jpeg[..2], &[0xFF, 0xD8]);
+    }
 }
@@ -0,0 +1,315 @@
+//! Terminal screenshots for multimodal agents.
+//!
+//! `render_terminal_image` replays a session's retained raw output through a
+//! headless `vt100` emulator sized to the requested viewport and paints the
+//! resulting screen into a PNG: colors, bold, underline, inverse and the
+//! cursor as a user would see them. Glyphs come from the built-in 8x8
+//! `font8x8` bitmap font drawn at twice its size, so no system fonts are
+//! needed; characters it lacks (e.g. CJK) are drawn as a hollow box.
+
+use base64::Engine;
+use font8x8::{UnicodeFonts, BASIC_FONTS, BLOCK_FONTS, BOX_FONTS, LATIN_FONTS};
+use image::ImageEncoder;
+use serde::Serialize;
+use tauri::State;
+
+use crate::ansi::{self, Rgb};
+use crate::pty::{self, PtyState};
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Accepted viewport size
+const MAX_COLS: u16 = 500;
+const MAX_ROWS: u16 = 200;
+
+/// Lines of history kept by the emulator, the limit for `scroll_back`
+const EMULATOR_SCROLLBACK_LINES: usize = 10_000;
+
+/// Bitmap glyphs are 8x8 pixels, drawn at this scale
+const GLYPH_SCALE: usize = 2;
+
+/// Cell size in pixels: the scaled glyph plus line spacing
+const CELL_WIDTH: usize = 8 * GLYPH_SCALE;
+const CELL_HEIGHT: usize = 8 * GLYPH_SCALE + 4;
+
+/// Rows of padding above the glyph inside a cell
+const GLYPH_TOP: usize = 2;
+
+/// Colors of text without SGR colors (VS Code dark terminal)
+const DEFAULT_FG: Rgb = (0xcc, 0xcc, 0xcc);
+const DEFAULT_BG: Rgb = (0x1e, 0x1e, 0x1e);
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Result of `render_terminal_image`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TerminalImage {
+    pub session_id: String,
+    pub cols: u16,
+    pub rows: u16,
+    /// Image size in pixels
+    pub width: u32,
+    pub height: u32,
+    /// Lines the view is scrolled back from the live screen
+    pub scroll_back: usize,
+    /// PNG image encoded as a `data:image/png;base64,...` URL
+    pub data_url: String,
+}
+
+/// An RGB image being painted.
+struct Canvas {
+    width: usize,
+    height: usize,
+    rgb: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize, fill: Rgb) -> Self {
+        let rgb = [fill.0, fill.1, fill.2].repeat(width * height);
+        Self { width, height, rgb }
+    }
+
+    fn put(&mut self, x: usize, y: usize, color: Rgb) {
+        if x < self.width && y < self.height {
+            let i = (y * self.width + x) * 3;
+            self.rgb[i..i + 3].copy_from_slice(&[color.0, color.1, color.2]);
+        }
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: Rgb) {
+        for yy in y..y + height {
+            for xx in x..x + width {
+                self.put(xx, yy, color);
+            }
+        }
+    }
+}
+
+// =============================================================================
+// Rendering
+// =============================================================================
+
+/// Resolve a vt100 color; `None` is the default color.
+fn resolve(color: vt100::Color, bold: bool) -> Option<Rgb> {
+    match color {
+        vt100::Color::Default => None,
+        // Bold brightens the 8 base colors, like xterm
+        vt100::Color::Idx(i) if bold && i < 8 => Some(ansi::palette_color(u16::from(i) + 8)),
+        vt100::Color::Idx(i) => Some(ansi::palette_color(u16::from(i))),
+        vt100::Color::Rgb(r, g, b) => Some((r, g, b)),
+    }
+}
+
+/// Bitmap of `c`, or None if the font has no glyph for it.
+fn glyph(c: char) -> Option<[u8; 8]> {
+    BASIC_FONTS
+        .get(c)
+        .or_else(|| LATIN_FONTS.get(c))
+        .or_else(|| BOX_FONTS.get(c))
+        .or_else(|| BLOCK_FONTS.get(c))
+}
+
+/// Draw `c` into the cell whose top-left pixel is (`x`, `y`), `cells` wide.
+fn draw_char(canvas: &mut Canvas, x: usize, y: usize, cells: usize, c: char, color: Rgb, bold: bool) {
+    let Some(bitmap) = glyph(c) else {
+        // Hollow box for characters the font lacks
+        let (w, h) = (cells * CELL_WIDTH - 4, 8 * GLYPH_SCALE);
+        canvas.fill(x + 2, y + GLYPH_TOP, w, 1, color);
+        canvas.fill(x + 2, y + GLYPH_TOP + h - 1, w, 1, color);
+        canvas.fill(x + 2, y + GLYPH_TOP, 1, h, color);
+        canvas.fill(x + 2 + w - 1, y + GLYPH_TOP, 1, h, color);
+        return;
+    };
+    for (row, bits) in bitmap.iter().enumerate() {
+        for col in 0..8 {
+            // Bit 0 is the leftmost pixel
+            if bits & (1 << col) == 0 {
+                continue;
+            }
+            let (px, py) = (x + col * GLYPH_SCALE, y + GLYPH_TOP + row * GLYPH_SCALE);
+            // Bold smears each pixel one to the right
+            let width = GLYPH_SCALE + usize::from(bold);
+            canvas.fill(px, py, width, GLYPH_SCALE, color);
+        }
+    }
+}
+
+/// Paint the visible part of `screen`.
+fn paint(screen: &vt100::Screen, show_cursor: bool) -> Canvas {
+    let (rows, cols) = screen.size();
+    let (rows, cols) = (rows as usize, cols as usize);
+    let mut canvas = Canvas::new(cols * CELL_WIDTH, rows * CELL_HEIGHT, DEFAULT_BG);
+    let cursor = screen.cursor_position();
+
+    for row in 0..rows {
+        for col in 0..cols {
+            let Some(cell) = screen.cell(row as u16, col as u16) else {
+                continue;
+            };
+            if cell.is_wide_continuation() {
+                continue;
+            }
+            let mut fg = resolve(cell.fgcolor(), cell.bold()).unwrap_or(DEFAULT_FG);
+            let mut bg = resolve(cell.bgcolor(), false).unwrap_or(DEFAULT_BG);
+            let is_cursor = show_cursor && (row as u16, col as u16) == cursor;
+            if cell.inverse() != is_cursor {
+                std::mem::swap(&mut fg, &mut bg);
+            }
+            let cells = if cell.is_wide() { 2 } else { 1 };
+            let (x, y) = (col * CELL_WIDTH, row * CELL_HEIGHT);
+            if bg != DEFAULT_BG {
+                canvas.fill(x, y, cells * CELL_WIDTH, CELL_HEIGHT, bg);
+            }
+
+            let contents = cell.contents();
+            // Combining characters are dropped; the font has none
+            if let Some(c) = contents.chars().next().filter(|c| !c.is_whitespace()) {
+                draw_char(&mut canvas, x, y, cells, c, fg, cell.bold());
+            }
+            if cell.underline() {
+                canvas.fill(x, y + CELL_HEIGHT - 2, cells * CELL_WIDTH, 1, fg);
+            }
+        }
+    }
+    canvas
+}
+
+/// Replay `output` into a `rows` x `cols` emulator scrolled back
+/// `scroll_back` lines and paint it. Returns the canvas and the scroll
+/// offset actually applied (limited by the history available).
+fn render(output: &str, cols: u16, rows: u16, scroll_back: usize) -> (Canvas, usize) {
+    let mut parser = vt100::Parser::new(rows, cols, EMULATOR_SCROLLBACK_LINES);
+    parser.process(output.as_bytes());
+    parser.set_scrollback(scroll_back);
+    let screen = parser.screen();
+    let applied = screen.scrollback();
+    // The cursor belongs to the live screen only
+    let show_cursor = applied == 0 && !screen.hide_cursor();
+    (paint(screen, show_cursor), applied)
+}
+
+fn encode_png(canvas: &Canvas) -> Result<Vec<u8>, AppError> {
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(
+            &canvas.rgb,
+            canvas.width as u32,
+            canvas.height as u32,
+            image::ExtendedColorType::Rgb8,
+        )
+        .map_err(|e| AppError::Internal(format!("PNG encode failed: {}", e)))?;
+    Ok(png)
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Render a terminal session's screen as a PNG, as a user would see it.
+///
+/// The session's retained output (its last 1 MiB) is replayed into a
+/// `cols` x `rows` viewport. Pass the size the session was last resized to
+/// for an exact picture; other sizes rewrap the output like a resized
+/// terminal window would. `scroll_back` shows the view that many lines up
+/// in the history instead of the live screen (default 0).
+#[tauri::command]
+pub async fn render_terminal_image(
+    state: State<'_, PtyState>,
+    session_id: String,
+    cols: u16,
+    rows: u16,
+    scroll_back: Option<usize>,
+) -> Result<TerminalImage, AppError> {
+    if !(1..=MAX_COLS).contains(&cols) || !(1..=MAX_ROWS).contains(&rows) {
+        return Err(AppError::Validation(format!(
+            "Viewport must be 1-{} columns and 1-{} rows, got: {}x{}",
+            MAX_COLS, MAX_ROWS, cols, rows
+        )));
+    }
+    let output = pty::retained_output(&state, &session_id)?;
+    let scroll_back = scroll_back.unwrap_or(0);
+
+    tokio::task::spawn_blocking(move || {
+        let (canvas, scroll_back) = render(&output, cols, rows, scroll_back);
+        let png = encode_png(&canvas)?;
+        log::debug!(
+            "Rendered session {} at {}x{} ({} bytes of PNG)",
+            session_id,
+            cols,
+            rows,
+            png.len()
+        );
+        Ok(TerminalImage {
+            session_id,
+            cols,
+            rows,
+            width: canvas.width as u32,
+            height: canvas.height as u32,
+            scroll_back,
+            data_url: format!(
+                "data:image/png;base64,{}",
+                base64::engine::general_purpose::STANDARD.encode(&png)
+            ),
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Render task failed: {}", e)))?
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(canvas: &Canvas, x: usize, y: usize) -> Rgb {
+        let i = (y * canvas.width + x) * 3;
+        (canvas.rgb[i], canvas.rgb[i + 1], canvas.rgb[i + 2])
+    }
+
+    /// Whether any pixel of the cell has color `color`.
+    fn cell_has(canvas: &Canvas, col: usize, row: usize, color: Rgb) -> bool {
+        (0..CELL_HEIGHT).any(|y| {
+            (0..CELL_WIDTH).any(|x| pixel(canvas, col * CELL_WIDTH + x, row * CELL_HEIGHT + y) == color)
+        })
+    }
+
+    #[test]
+    fn test_render_size_and_colors() {
+        let (canvas, scroll_back) = render("\x1b[?25l\x1b[31mA\x1b[0m \x1b[44m \x1b[0m", 10, 2, 0);
+        assert_eq!((canvas.width, canvas.height), (10 * CELL_WIDTH, 2 * CELL_HEIGHT));
+        assert_eq!(scroll_back, 0);
+        // Red glyph, empty cell, blue background
+        assert!(cell_has(&canvas, 0, 0, ansi::palette_color(1)));
+        assert!(!cell_has(&canvas, 1, 0, DEFAULT_FG));
+        assert_eq!(pixel(&canvas, 2 * CELL_WIDTH, 0), ansi::palette_color(4));
+        assert_eq!(pixel(&canvas, 0, CELL_HEIGHT), DEFAULT_BG);
+    }
+
+    #[test]
+    fn test_cursor_shown_on_live_screen_only() {
+        let (canvas, _) = render("ab", 4, 1, 0);
+        // The cursor cell after "ab" is drawn inverted
+        assert_eq!(pixel(&canvas, 2 * CELL_WIDTH, 0), DEFAULT_FG);
+
+        let output: String = (0..10).map(|i| format!("line {}\r\n", i)).collect();
+        let (canvas, scroll_back) = render(&output, 8, 2, 3);
+        assert_eq!(scroll_back, 3);
+        // The live cursor would sit at the start of the second row
+        assert_eq!(pixel(&canvas, 0, CELL_HEIGHT), DEFAULT_BG);
+    }
+
+    #[test]
+    fn test_png_encoding() {
+        let (canvas, _) = render("hi", 2, 1, 0);
+        let png = encode_png(&canvas).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+}
@@ -0,0 +1,577 @@
+//! Timelapse: periodic screenshots of a display saved to disk.
+//!
+//! Meant for reviewing what was on screen during a long unattended agent
+//! run. One timelapse runs at a time; every `interval_s` seconds a frame is
+//! captured, scaled down to at most 1920 pixels wide and written as
+//! `frame-YYYYMMDD-HHMMSS.jpg`. After each frame the oldest ones are pruned
+//! by the retention limits. Only files with that name pattern are listed or
+//! deleted, so pointing the timelapse at a directory holding other files is
+//! safe.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::watch;
+
+use crate::events;
+use crate::streaming;
+use crate::AppError;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Default frame directory, inside the app data directory
+const TIMELAPSE_DIR: &str = "timelapse";
+
+/// File name prefix and extension of frames
+const FRAME_PREFIX: &str = "frame-";
+const FRAME_EXTENSION: &str = ".jpg";
+
+/// chrono format of the timestamp between prefix and extension
+const FRAME_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Allowed capture interval in seconds
+const MIN_INTERVAL_SECS: u64 = 1;
+const MAX_INTERVAL_SECS: u64 = 3600;
+
+/// Frames kept when `retention` is omitted
+const DEFAULT_MAX_FRAMES: u32 = 1000;
+
+/// Upper bound for `retention.max_frames`
+const MAX_MAX_FRAMES: u32 = 100_000;
+
+/// Frames are scaled down to this width to bound disk use
+const FRAME_MAX_WIDTH: u32 = 1920;
+
+const FRAME_JPEG_QUALITY: u8 = 75;
+
+/// Frames returned by `list_timelapse_frames` when `limit` is omitted
+const DEFAULT_LIST_LIMIT: usize = 500;
+
+/// Upper bound for `list_timelapse_frames` `limit`
+const MAX_LIST_LIMIT: usize = 10_000;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// How many frames to keep. At least one limit must be set.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimelapseRetention {
+    /// Keep at most this many frames (1-100000)
+    pub max_frames: Option<u32>,
+    /// Delete frames older than this
+    pub max_age_secs: Option<u64>,
+}
+
+impl Default for TimelapseRetention {
+    fn default() -> Self {
+        Self {
+            max_frames: Some(DEFAULT_MAX_FRAMES),
+            max_age_secs: None,
+        }
+    }
+}
+
+impl TimelapseRetention {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.max_frames.is_none() && self.max_age_secs.is_none() {
+            return Err(AppError::Validation(
+                "retention needs max_frames or max_age_secs".into(),
+            ));
+        }
+        if let Some(n) = self.max_frames {
+            if !(1..=MAX_MAX_FRAMES).contains(&n) {
+                return Err(AppError::Validation(format!(
+                    "retention.max_frames must be 1-{}, got: {}",
+                    MAX_MAX_FRAMES, n
+                )));
+            }
+        }
+        if self.max_age_secs == Some(0) {
+            return Err(AppError::Validation("retention.max_age_secs must be > 0".into()));
+        }
+        Ok(())
+    }
+}
+
+/// A saved frame.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TimelapseFrame {
+    pub path: String,
+    pub file_name: String,
+    /// RFC 3339 time the frame was written
+    pub captured_at: String,
+    pub size_bytes: u64,
+}
+
+/// State of the timelapse, returned by the timelapse commands.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct TimelapseStatus {
+    pub running: bool,
+    /// Display being captured (None for the main display)
+    pub display_id: Option<u32>,
+    pub interval_s: u64,
+    pub dir: Option<String>,
+    pub retention: Option<TimelapseRetention>,
+    /// RFC 3339 start time
+    pub started_at: Option<String>,
+    pub frames_captured: u64,
+    /// Most recent capture or write failure (the timelapse keeps going)
+    pub last_error: Option<String>,
+}
+
+/// Counters updated by the capture task.
+#[derive(Default)]
+struct Progress {
+    frames_captured: u64,
+    last_error: Option<String>,
+}
+
+/// A running timelapse.
+struct TimelapseRun {
+    display_id: Option<u32>,
+    interval_s: u64,
+    dir: PathBuf,
+    retention: TimelapseRetention,
+    started_at: String,
+    progress: Arc<Mutex<Progress>>,
+    /// Fired by `stop_timelapse`; dropping it also ends the task
+    stop_tx: watch::Sender<bool>,
+}
+
+impl TimelapseRun {
+    fn status(&self) -> TimelapseStatus {
+        let progress = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+        TimelapseStatus {
+            running: true,
+            display_id: self.display_id,
+            interval_s: self.interval_s,
+            dir: Some(self.dir.to_string_lossy().to_string()),
+            retention: Some(self.retention),
+            started_at: Some(self.started_at.clone()),
+            frames_captured: progress.frames_captured,
+            last_error: progress.last_error.clone(),
+        }
+    }
+}
+
+/// Shared state managed by Tauri.
+#[derive(Default)]
+pub struct TimelapseState {
+    run: Mutex<Option<TimelapseRun>>,
+}
+
+impl TimelapseState {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<TimelapseRun>> {
+        self.run.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Start saving a screenshot every `interval_s` seconds.
+///
+/// # Arguments
+/// * `display_id` - Display to capture (default: main display)
+/// * `interval_s` - Seconds between frames (1-3600)
+/// * `dir` - Absolute directory for the frames (default: `timelapse` in the
+///   app data directory); created if missing
+/// * `retention` - Pruning limits (default: keep the newest 1000 frames)
+#[tauri::command]
+pub async fn start_timelapse(
+    app: AppHandle,
+    state: State<'_, TimelapseState>,
+    display_id: Option<u32>,
+    interval_s: u64,
+    dir: Option<String>,
+    retention: Option<TimelapseRetention>,
+) -> Result<TimelapseStatus, AppError> {
+    if !(MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(&interval_s) {
+        return Err(AppError::Validation(format!(
+            "interval_s must be {}-{}, got: {}",
+            MIN_INTERVAL_SECS, MAX_INTERVAL_SECS, interval_s
+        )));
+    }
+    let retention = retention.unwrap_or_default();
+    retention.validate()?;
+    let dir = resolve_dir(&app, dir)?;
+
+    if !scap::is_supported() {
+        return Err(AppError::Capture("Screen capture not supported on this platform".into()));
+    }
+    if !scap::has_permission() {
+        return Err(AppError::PermissionDenied("Screen capture permission not granted".into()));
+    }
+    if streaming::find_display_target(display_id).is_none() {
+        return Err(AppError::NotFound(format!("Display not found: {:?}", display_id)));
+    }
+
+    let mut run = state.lock();
+    if run.is_some() {
+        return Err(AppError::Validation("A timelapse is already running".into()));
+    }
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| AppError::Io(format!("Failed to create {}: {}", dir.display(), e)))?;
+
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let started = TimelapseRun {
+        display_id,
+        interval_s,
+        dir,
+        retention,
+        started_at: Local::now().to_rfc3339(),
+        progress: Arc::new(Mutex::new(Progress::default())),
+        stop_tx,
+    };
+    tauri::async_runtime::spawn(capture_loop(
+        app,
+        display_id,
+        Duration::from_secs(interval_s),
+        started.dir.clone(),
+        retention,
+        started.progress.clone(),
+        stop_rx,
+    ));
+    log::info!(
+        "Timelapse started: display {:?} every {}s into {}",
+        display_id,
+        interval_s,
+        started.dir.display()
+    );
+    let status = started.status();
+    *run = Some(started);
+    Ok(status)
+}
+
+/// Stop the running timelapse. Frames already saved are kept.
+#[tauri::command]
+pub fn stop_timelapse(state: State<'_, TimelapseState>) -> Result<TimelapseStatus, AppError> {
+    let run = state
+        .lock()
+        .take()
+        .ok_or_else(|| AppError::Validation("No timelapse is running".into()))?;
+    let _ = run.stop_tx.send(true);
+    let mut status = run.status();
+    status.running = false;
+    log::info!("Timelapse stopped after {} frame(s)", status.frames_captured);
+    Ok(status)
+}
+
+/// Stop the running timelapse, if any, on app exit. Returns whether one was
+/// running.
+pub(crate) fn stop_all(state: &TimelapseState) -> bool {
+    match state.lock().take() {
+        Some(run) => {
+            let _ = run.stop_tx.send(true);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Current timelapse state (`running: false` when none is running).
+#[tauri::command]
+pub fn get_timelapse_status(state: State<'_, TimelapseState>) -> TimelapseStatus {
+    state.lock().as_ref().map(TimelapseRun::status).unwrap_or_default()
+}
+
+/// Saved frames, newest first.
+///
+/// # Arguments
+/// * `dir` - Directory to list (default: that of the running timelapse, or
+///   the default timelapse directory)
+/// * `limit` - Frames to return (default 500, max 10000)
+#[tauri::command]
+pub async fn list_timelapse_frames(
+    app: AppHandle,
+    state: State<'_, TimelapseState>,
+    dir: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<TimelapseFrame>, AppError> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT);
+    if !(1..=MAX_LIST_LIMIT).contains(&limit) {
+        return Err(AppError::Validation(format!(
+            "limit must be 1-{}, got: {}",
+            MAX_LIST_LIMIT, limit
+        )));
+    }
+    let running_dir = state.lock().as_ref().map(|run| run.dir.clone());
+    let dir = match (dir, running_dir) {
+        (None, Some(running)) => running,
+        (dir, _) => resolve_dir(&app, dir)?,
+    };
+
+    tokio::task::spawn_blocking(move || {
+        let mut frames = list_frames(&dir)?;
+        frames.reverse();
+        frames.truncate(limit);
+        Ok(frames)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Frame listing task failed: {}", e)))?
+}
+
+// =============================================================================
+// Capture
+// =============================================================================
+
+/// The requested directory, or the default one. Must be absolute.
+fn resolve_dir(app: &AppHandle, dir: Option<String>) -> Result<PathBuf, AppError> {
+    match dir {
+        Some(dir) => {
+            let path = PathBuf::from(&dir);
+            if !path.is_absolute() {
+                return Err(AppError::Validation(format!("dir must be absolute, got: {}", dir)));
+            }
+            Ok(path)
+        }
+        None => app
+            .path()
+            .app_data_dir()
+            .map(|d| d.join(TIMELAPSE_DIR))
+            .map_err(|e| AppError::Internal(format!("Could not determine app data dir: {}", e))),
+    }
+}
+
+/// Capture, save and prune every `interval` until stopped.
+async fn capture_loop(
+    app: AppHandle,
+    display_id: Option<u32>,
+    interval: Duration,
+    dir: PathBuf,
+    retention: TimelapseRetention,
+    progress: Arc<Mutex<Progress>>,
+    mut stop_rx: watch::Receiver<bool>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    // A slow capture delays the next frame instead of causing a burst
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = stop_rx.changed() => break,
+        }
+
+        let frame_dir = dir.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let frame = capture_frame(display_id, &frame_dir)?;
+            prune_frames(&frame_dir, &retention, SystemTime::now())?;
+            Ok::<_, AppError>(frame)
+        })
+        .await
+        .unwrap_or_else(|e| Err(AppError::Internal(format!("Timelapse task failed: {}", e))));
+
+        let mut p = progress.lock().unwrap_or_else(|e| e.into_inner());
+        match result {
+            Ok(frame) => {
+                p.frames_captured += 1;
+                drop(p);
+                let _ = events::emit(&app, "timelapse-frame-captured", frame);
+            }
+            Err(e) => {
+                log::warn!("Timelapse frame failed: {}", e);
+                p.last_error = Some(e.to_string());
+            }
+        }
+    }
+    log::debug!("Timelapse capture loop ended");
+}
+
+/// Capture one frame and write it to `dir`. Blocking.
+fn capture_frame(display_id: Option<u32>, dir: &Path) -> Result<TimelapseFrame, AppError> {
+    let target = streaming::find_display_target(display_id)
+        .ok_or_else(|| AppError::NotFound(format!("Display not found: {:?}", display_id)))?;
+    let frame = streaming::capture_single_frame(target)?;
+    let (rgb, width, height) = streaming::downscale_bgra_to_rgb(
+        &frame.data,
+        frame.width as u32,
+        frame.height as u32,
+        FRAME_MAX_WIDTH,
+    );
+    let jpeg = streaming::encode_jpeg(&rgb, width, height, FRAME_JPEG_QUALITY)?;
+
+    let path = dir.join(frame_file_name(Local::now()));
+    std::fs::write(&path, &jpeg)
+        .map_err(|e| AppError::Io(format!("Failed to write {}: {}", path.display(), e)))?;
+    Ok(TimelapseFrame {
+        file_name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        captured_at: Local::now().to_rfc3339(),
+        size_bytes: jpeg.len() as u64,
+    })
+}
+
+fn frame_file_name(at: DateTime<Local>) -> String {
+    format!("{}{}{}", FRAME_PREFIX, at.format(FRAME_TIMESTAMP_FORMAT), FRAME_EXTENSION)
+}
+
+fn is_frame_file_name(name: &str) -> bool {
+    name.strip_prefix(FRAME_PREFIX)
+        .and_then(|rest| rest.strip_suffix(FRAME_EXTENSION))
+        .is_some_and(|ts| {
+            chrono::NaiveDateTime::parse_from_str(ts, FRAME_TIMESTAMP_FORMAT).is_ok()
+        })
+}
+
+// =============================================================================
+// Frame Files
+// =============================================================================
+
+/// Frames in `dir`, oldest first (the names sort chronologically). A missing
+/// directory has no frames.
+fn list_frames(dir: &Path) -> Result<Vec<TimelapseFrame>, AppError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::Io(format!("Failed to read {}: {}", dir.display(), e))),
+    };
+
+    let mut frames: Vec<TimelapseFrame> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let file_name = entry.file_name().to_str()?.to_string();
+            if !is_frame_file_name(&file_name) {
+                return None;
+            }
+            let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+            let modified: DateTime<Local> = meta.modified().ok()?.into();
+            Some(TimelapseFrame {
+                path: entry.path().to_string_lossy().to_string(),
+                file_name,
+                captured_at: modified.to_rfc3339(),
+                size_bytes: meta.len(),
+            })
+        })
+        .collect();
+    frames.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    Ok(frames)
+}
+
+/// Delete the frames beyond `retention`: the oldest past `max_frames`, and
+/// any written more than `max_age_secs` before `now`. Returns how many were
+/// deleted.
+fn prune_frames(
+    dir: &Path,
+    retention: &TimelapseRetention,
+    now: SystemTime,
+) -> Result<usize, AppError> {
+    let frames = list_frames(dir)?;
+    let excess = retention
+        .max_frames
+        .map_or(0, |max| frames.len().saturating_sub(max as usize));
+    let cutoff = retention
+        .max_age_secs
+        .and_then(|secs| now.checked_sub(Duration::from_secs(secs)));
+
+    let mut deleted = 0;
+    for (i, frame) in frames.iter().enumerate() {
+        let expired = cutoff.is_some_and(|cutoff| {
+            std::fs::metadata(&frame.path)
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified < cutoff)
+        });
+        if i < excess || expired {
+            match std::fs::remove_file(&frame.path) {
+                Ok(()) => deleted += 1,
+                Err(e) => log::warn!("Failed to delete timelapse frame {}: {}", frame.path, e),
+            }
+        }
+    }
+    Ok(deleted)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("synthia-timelapse-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_frame(dir: &Path, second: u32) -> String {
+        let at = Local.with_ymd_and_hms(2026, 3, 1, 12, 0, second).unwrap();
+        let name = frame_file_name(at);
+        std::fs::write(dir.join(&name), b"jpeg").unwrap();
+        name
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        list_frames(dir).unwrap().into_iter().map(|f| f.file_name).collect()
+    }
+
+    #[test]
+    fn test_frame_file_names() {
+        let at = Local.with_ymd_and_hms(2026, 3, 1, 9, 5, 7).unwrap();
+        assert_eq!(frame_file_name(at), "frame-20260301-090507.jpg");
+        assert!(is_frame_file_name("frame-20260301-090507.jpg"));
+        assert!(!is_frame_file_name("frame-notes.jpg"));
+        assert!(!is_frame_file_name("holiday.jpg"));
+        assert!(!is_frame_file_name("frame-20260301-090507.png"));
+    }
+
+    #[test]
+    fn test_prune_by_count_keeps_newest_and_foreign_files() {
+        let dir = temp_dir();
+        let written: Vec<String> = [3, 1, 4, 2].iter().map(|&s| write_frame(&dir, s)).collect();
+        std::fs::write(dir.join("notes.txt"), b"keep me").unwrap();
+        assert_eq!(list_frames(Path::new("/nonexistent/synthia")).unwrap(), vec![]);
+
+        let retention = TimelapseRetention {
+            max_frames: Some(2),
+            max_age_secs: None,
+        };
+        assert_eq!(prune_frames(&dir, &retention, SystemTime::now()).unwrap(), 2);
+        assert_eq!(names(&dir), vec![written[0].clone(), written[2].clone()]);
+        assert!(dir.join("notes.txt").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_prune_by_age() {
+        let dir = temp_dir();
+        write_frame(&dir, 1);
+        write_frame(&dir, 2);
+        let retention = TimelapseRetention {
+            max_frames: None,
+            max_age_secs: Some(60),
+        };
+        assert_eq!(prune_frames(&dir, &retention, SystemTime::now()).unwrap(), 0);
+        let later = SystemTime::now() + Duration::from_secs(120);
+        assert_eq!(prune_frames(&dir, &retention, later).unwrap(), 2);
+        assert!(names(&dir).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_retention_validation() {
+        assert!(TimelapseRetention::default().validate().is_ok());
+        let none = TimelapseRetention {
+            max_frames: None,
+            max_age_secs: None,
+        };
+        assert!(matches!(none.validate(), Err(AppError::Validation(_))));
+        let zero = TimelapseRetention {
+            max_frames: Some(0),
+            max_age_secs: None,
+        };
+        assert!(matches!(zero.validate(), Err(AppError::Validation(_))));
+    }
+}
@@ -0,0 +1,180 @@
+//! System tray icon with live stats and quick actions.
+//!
+//! The tray tooltip and the first (disabled) menu line show CPU and memory
+//! usage and the number of open terminal sessions, refreshed by the metrics
+//! sampler every second (`update`). The menu offers:
+//!
+//! - **New Terminal** — shows the main window and emits `tray-new-terminal`,
+//!   which the command center answers by opening a terminal panel
+//! - **Stop Stream** — stops the screen stream and emits `stream-stopped`
+//! - **Quit** — exits the app; the exit handler kills sessions and services
+
+use std::sync::Mutex;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager};
+
+use crate::events;
+use crate::pty::PtyState;
+use crate::streaming::{self, StreamingState};
+use crate::SystemStats;
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// Id of the tray icon
+const TRAY_ID: &str = "main";
+
+/// Menu item ids
+const MENU_STATUS: &str = "tray-status";
+const MENU_NEW_TERMINAL: &str = "tray-new-terminal";
+const MENU_STOP_STREAM: &str = "tray-stop-stream";
+const MENU_QUIT: &str = "tray-quit";
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// Shared state managed by Tauri. The menu is built in `init`.
+#[derive(Default)]
+pub struct TrayState {
+    /// Disabled menu line showing the stats
+    status_item: Mutex<Option<MenuItem<tauri::Wry>>>,
+    /// Text last shown, so unchanged stats don't touch the tray
+    last_status: Mutex<String>,
+}
+
+// =============================================================================
+// Tray
+// =============================================================================
+
+/// Create the tray icon and its menu. Failures are logged; the app runs
+/// without a tray.
+pub fn init(app: &AppHandle) {
+    if let Err(e) = build_tray(app) {
+        log::error!("Failed to create tray icon: {}", e);
+    }
+}
+
+fn build_tray(app: &AppHandle) -> tauri::Result<()> {
+    let status = MenuItem::with_id(app, MENU_STATUS, "Starting…", false, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, MENU_NEW_TERMINAL, "New Terminal", true, None::<&str>)?,
+            &MenuItem::with_id(app, MENU_STOP_STREAM, "Stop Stream", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, MENU_QUIT, "Quit Synthia", true, None::<&str>)?,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Synthia")
+        .menu(&menu)
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    if let Ok(mut item) = app.state::<TrayState>().status_item.lock() {
+        *item = Some(status);
+    }
+    Ok(())
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        MENU_NEW_TERMINAL => {
+            show_main_window(app);
+            let _ = events::emit(app, "tray-new-terminal", ());
+        }
+        MENU_STOP_STREAM => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app.state::<StreamingState>();
+                match streaming::stop_stream(&state).await {
+                    Ok(()) => {
+                        log::info!("Stream stopped from the tray");
+                        let _ = events::emit(&app, "stream-stopped", ());
+                    }
+                    Err(e) => log::info!("Tray stop stream: {}", e),
+                }
+            });
+        }
+        MENU_QUIT => {
+            log::info!("Quit requested from the tray");
+            // Runs the RunEvent::Exit cleanup in lib.rs
+            app.exit(0);
+        }
+        _ => {}
+    }
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Refresh the stats shown by the tray. Called by the metrics sampler.
+pub(crate) fn update(app: &AppHandle, stats: &SystemStats) {
+    // The sampler starts before the tray exists
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let sessions = app.state::<PtyState>().sessions().len();
+    let text = status_text(stats, sessions);
+
+    let state = app.state::<TrayState>();
+    {
+        let Ok(mut last) = state.last_status.lock() else {
+            return;
+        };
+        if *last == text {
+            return;
+        }
+        last.clone_from(&text);
+    }
+    if let Some(item) = state.status_item.lock().ok().and_then(|i| i.clone()) {
+        let _ = item.set_text(&text);
+    }
+    let _ = tray.set_tooltip(Some(format!("Synthia — {}", text)));
+}
+
+/// One-line summary, e.g. `CPU 12% · MEM 40% · 3 sessions`.
+fn status_text(stats: &SystemStats, sessions: usize) -> String {
+    format!(
+        "CPU {:.0}% · MEM {:.0}% · {} session{}",
+        stats.cpu,
+        stats.mem,
+        sessions,
+        if sessions == 1 { "" } else { "s" }
+    )
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_text() {
+        let stats = SystemStats {
+            cpu: 12.4,
+            mem: 39.6,
+            mem_used_gb: 6.3,
+            mem_total_gb: 16.0,
+        };
+        assert_eq!(status_text(&stats, 3), "CPU 12% · MEM 40% · 3 sessions");
+        assert_eq!(status_text(&stats, 1), "CPU 12% · MEM 40% · 1 session");
+    }
+}
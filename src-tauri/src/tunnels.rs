@@ -0,0 +1,637 @@
+//! Port forwards (tunnels) managed by the app.
+//!
+//! `create_port_forward` makes `remote_host:remote_port` reachable on
+//! `127.0.0.1:local_port`, so a dev server can be opened in a local browser:
+//!
+//! - **Direct**: the app listens on the local port and relays each
+//!   connection to `remote_host:remote_port` as seen from this machine.
+//! - **Through an SSH session** (`via_ssh_session`): for a terminal session
+//!   running `ssh`, a second `ssh -N -L` with the same options and
+//!   destination is started, so `remote_host` is resolved on the remote
+//!   side (`localhost` is the remote machine). It runs in batch mode, so it
+//!   needs key or agent authentication, or a shared `ControlMaster`
+//!   connection; a password prompt makes it fail instead of hanging.
+//!
+//! Local ports are only ever bound on loopback. Tunnels live until
+//! `close_tunnel`, app exit, or (through SSH) the ssh process exiting, which
+//! emits `tunnel-closed`.
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::{AppHandle, Manager, State};
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+
+use crate::pty::{self, PtyState};
+use crate::{events, services, AppError};
+
+// =============================================================================
+// Constants
+// =============================================================================
+
+/// How long ssh gets to authenticate and open the local port
+const SSH_READY_TIMEOUT: Duration = Duration::from_secs(15);
+
+const SSH_READY_POLL: Duration = Duration::from_millis(100);
+
+/// Pause after a failed accept on a direct forward
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// ssh options that take a value (see ssh(1))
+const SSH_VALUE_OPTIONS: &str = "BbcDEeFIiJLlmOoPpQRSWw";
+
+/// Options of the session's ssh command left out of the tunnel's: its own
+/// forwards would clash with the session's, and the tunnel runs without a
+/// terminal, remote command or backgrounding
+const SSH_DROPPED_OPTIONS: &str = "DLRWtTNfnsG";
+
+/// Tail of ssh's stderr kept for error messages
+const MAX_SSH_STDERR_CHARS: usize = 2000;
+
+// =============================================================================
+// Types
+// =============================================================================
+
+/// A tunnel, as returned by `create_port_forward` and `list_tunnels`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TunnelInfo {
+    pub id: String,
+    /// Port on 127.0.0.1
+    pub local_port: u16,
+    pub remote_host: String,
+    pub remote_port: u16,
+    /// Session whose ssh connection the tunnel goes through; None for a
+    /// direct forward from this machine
+    pub via_ssh_session: Option<String>,
+    /// ssh destination (e.g. `user@host`) of a tunnel through a session
+    pub ssh_destination: Option<String>,
+    /// Process id of the tunnel's ssh
+    pub pid: Option<u32>,
+    pub created_at: DateTime<Local>,
+    /// Connections relayed so far (direct forwards only)
+    pub connections: Option<u64>,
+}
+
+/// Payload of the `tunnel-closed` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct TunnelClosed {
+    pub id: String,
+    /// `closed` for `close_tunnel`, else why the tunnel ended
+    pub reason: String,
+}
+
+struct Tunnel {
+    info: TunnelInfo,
+    /// Dropping it stops the tunnel's tasks
+    _close_tx: watch::Sender<bool>,
+    connections: Option<Arc<AtomicU64>>,
+}
+
+impl Tunnel {
+    fn info(&self) -> TunnelInfo {
+        TunnelInfo {
+            connections: self.connections.as_ref().map(|c| c.load(Ordering::Relaxed)),
+            ..self.info.clone()
+        }
+    }
+}
+
+/// Shared state managed by Tauri.
+#[derive(Default)]
+pub struct TunnelsState {
+    tunnels: Mutex<HashMap<String, Tunnel>>,
+    next_id: AtomicU64,
+}
+
+impl TunnelsState {
+    fn tunnels(&self) -> MutexGuard<'_, HashMap<String, Tunnel>> {
+        // Tunnels hold plain data, so a poisoned lock is still usable
+        self.tunnels.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// How the tunnel's ssh is started: the session's ssh program, options and
+/// destination.
+#[derive(Debug, PartialEq)]
+struct SshCommand {
+    program: String,
+    options: Vec<String>,
+    destination: String,
+}
+
+// =============================================================================
+// Validation
+// =============================================================================
+
+/// Host names and IPv4 addresses; `-` first is refused as it would read as
+/// an option.
+fn is_valid_host(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 253
+        && !host.starts_with('-')
+        && host.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+}
+
+fn bind_error(port: u16, e: std::io::Error) -> AppError {
+    if e.kind() == std::io::ErrorKind::AddrInUse {
+        AppError::PortInUse(port)
+    } else {
+        AppError::Io(format!("Failed to bind port {}: {}", port, e))
+    }
+}
+
+/// Whether something is listening on loopback `port`.
+fn port_taken(port: u16) -> bool {
+    matches!(
+        std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, port)),
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse
+    )
+}
+
+// =============================================================================
+// Direct Forwards
+// =============================================================================
+
+async fn relay(mut inbound: TcpStream, remote: (String, u16)) -> std::io::Result<()> {
+    let mut outbound = TcpStream::connect((remote.0.as_str(), remote.1)).await?;
+    tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await?;
+    Ok(())
+}
+
+/// Accept connections on `listener` and relay each to `remote` until the
+/// tunnel is closed; open connections are cut then too.
+async fn run_direct(
+    listener: TcpListener,
+    remote: (String, u16),
+    connections: Arc<AtomicU64>,
+    mut close_rx: watch::Receiver<bool>,
+) {
+    loop {
+        let accepted = tokio::select! {
+            _ = close_rx.changed() => return,
+            accepted = listener.accept() => accepted,
+        };
+        let inbound = match accepted {
+            Ok((inbound, _)) => inbound,
+            Err(e) => {
+                log::warn!("Tunnel to {}:{} failed to accept: {}", remote.0, remote.1, e);
+                // Out of file descriptors and the like; don't spin
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        connections.fetch_add(1, Ordering::Relaxed);
+        let remote = remote.clone();
+        let mut close_rx = close_rx.clone();
+        tauri::async_runtime::spawn(async move {
+            let target = format!("{}:{}", remote.0, remote.1);
+            tokio::select! {
+                _ = close_rx.changed() => {}
+                result = relay(inbound, remote) => {
+                    if let Err(e) = result {
+                        log::debug!("Tunnel connection to {} ended: {}", target, e);
+                    }
+                }
+            }
+        });
+    }
+}
+
+// =============================================================================
+// SSH Tunnels
+// =============================================================================
+
+/// Options and destination of an ssh command line, without the options in
+/// `SSH_DROPPED_OPTIONS` and the remote command. None without a destination.
+fn ssh_options_and_destination(args: &[String]) -> Option<(Vec<String>, String)> {
+    let mut options = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            return args.next().map(|destination| (options, destination.clone()));
+        }
+        let Some(flags) = arg.strip_prefix('-').filter(|f| !f.is_empty()) else {
+            return Some((options, arg.clone()));
+        };
+        for (i, flag) in flags.char_indices() {
+            let keep = !SSH_DROPPED_OPTIONS.contains(flag);
+            if SSH_VALUE_OPTIONS.contains(flag) {
+                // The value is the rest of this argument (`-p22`) or the next
+                let rest = &flags[i + flag.len_utf8()..];
+                let value = if rest.is_empty() { args.next()?.clone() } else { rest.to_string() };
+                if keep {
+                    options.push(format!("-{}", flag));
+                    options.push(value);
+                }
+                break;
+            }
+            if keep {
+                options.push(format!("-{}", flag));
+            }
+        }
+    }
+    None
+}
+
+/// Command line of a process, one entry per argument.
+fn process_args(pid: u32) -> Option<Vec<String>> {
+    let sys_pid = sysinfo::Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[sys_pid]),
+        true,
+        ProcessRefreshKind::new().with_cmd(UpdateKind::Always),
+    );
+    let args: Vec<String> = system
+        .process(sys_pid)?
+        .cmd()
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    Some(args).filter(|a| !a.is_empty())
+}
+
+/// The ssh command the session is running.
+async fn session_ssh(pty: &PtyState, session_id: &str) -> Result<SshCommand, AppError> {
+    let foreground = pty::session_foreground(pty, session_id)?;
+    let process = match foreground {
+        Some(process) if process.name == "ssh" => process,
+        other => {
+            return Err(AppError::Validation(format!(
+                "Session {} is not running ssh (foreground: {})",
+                session_id,
+                other.map_or_else(|| "unknown".to_string(), |p| p.name)
+            )))
+        }
+    };
+    let pid = process.pid;
+    let args = tokio::task::spawn_blocking(move || process_args(pid))
+        .await
+        .map_err(|e| AppError::Internal(format!("Process lookup failed: {}", e)))?
+        .ok_or_else(|| AppError::NotFound(format!("Command line of ssh ({}) not found", pid)))?;
+    let (options, destination) = ssh_options_and_destination(&args[1..]).ok_or_else(|| {
+        AppError::Validation(format!("No destination in `{}`", process.command))
+    })?;
+    Ok(SshCommand {
+        program: args[0].clone(),
+        options,
+        destination,
+    })
+}
+
+/// Collect the tail of ssh's stderr until it exits.
+fn collect_stderr(child: &mut Child) -> tauri::async_runtime::JoinHandle<String> {
+    let stderr = child.stderr.take();
+    tauri::async_runtime::spawn(async move {
+        let mut text = String::new();
+        let Some(mut stderr) = stderr else {
+            return text;
+        };
+        let mut buf = [0u8; 1024];
+        while let Ok(n @ 1..) = stderr.read(&mut buf).await {
+            text.push_str(&String::from_utf8_lossy(&buf[..n]));
+            if text.len() > MAX_SSH_STDERR_CHARS * 2 {
+                let mut cut = text.len() - MAX_SSH_STDERR_CHARS;
+                while !text.is_char_boundary(cut) {
+                    cut += 1;
+                }
+                text.drain(..cut);
+            }
+        }
+        text
+    })
+}
+
+/// Start `ssh -N -L` and wait until it listens on `local_port`.
+async fn start_ssh(
+    ssh: &SshCommand,
+    local_port: u16,
+    remote_host: &str,
+    remote_port: u16,
+) -> Result<(Child, tauri::async_runtime::JoinHandle<String>), AppError> {
+    let mut cmd = Command::new(&ssh.program);
+    cmd.arg("-N")
+        .args(["-o", "ExitOnForwardFailure=yes", "-o", "BatchMode=yes"])
+        .arg("-L")
+        .arg(format!("127.0.0.1:{}:{}:{}", local_port, remote_host, remote_port))
+        .args(&ssh.options)
+        .arg("--")
+        .arg(&ssh.destination)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // Own process group so `close_all` can kill it like a service
+    #[cfg(unix)]
+    cmd.process_group(0);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| AppError::Io(format!("Failed to start {}: {}", ssh.program, e)))?;
+    let stderr = collect_stderr(&mut child);
+
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|e| AppError::Io(format!("Failed to check ssh: {}", e)))?
+        {
+            let output = stderr.await.unwrap_or_default();
+            return Err(AppError::Io(format!("ssh exited ({}): {}", status, output.trim())));
+        }
+        if port_taken(local_port) {
+            return Ok((child, stderr));
+        }
+        if started.elapsed() > SSH_READY_TIMEOUT {
+            let _ = child.kill().await;
+            return Err(AppError::Timeout(format!(
+                "ssh did not open port {} within {:?}",
+                local_port, SSH_READY_TIMEOUT
+            )));
+        }
+        tokio::time::sleep(SSH_READY_POLL).await;
+    }
+}
+
+/// Wait for the tunnel's ssh to exit, or kill it when the tunnel is closed.
+async fn supervise_ssh(
+    app: AppHandle,
+    id: String,
+    mut child: Child,
+    stderr: tauri::async_runtime::JoinHandle<String>,
+    mut close_rx: watch::Receiver<bool>,
+) {
+    let exited = tokio::select! {
+        _ = close_rx.changed() => None,
+        status = child.wait() => Some(status),
+    };
+    let Some(status) = exited else {
+        let _ = child.kill().await;
+        return;
+    };
+    let output = stderr.await.unwrap_or_default();
+    let reason = match status {
+        Ok(status) => format!("ssh exited ({}) {}", status, output.trim()),
+        Err(e) => format!("ssh failed: {}", e),
+    };
+    let removed = app.state::<TunnelsState>().tunnels().remove(&id);
+    if removed.is_some() {
+        log::warn!("Tunnel {} closed: {}", id, reason.trim());
+        let _ = events::emit(
+            &app,
+            "tunnel-closed",
+            TunnelClosed {
+                id,
+                reason: reason.trim().to_string(),
+            },
+        );
+    }
+}
+
+/// A free loopback port, for tunnels through ssh that ask for any port.
+fn free_local_port() -> Result<u16, AppError> {
+    std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .and_then(|l| l.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| AppError::Io(format!("No free local port: {}", e)))
+}
+
+/// Kill every tunnel's ssh. Called on app exit, where the async supervisors
+/// no longer get a chance to run. Returns the number of tunnels closed.
+pub fn close_all(state: &TunnelsState) -> usize {
+    let mut tunnels = state.tunnels();
+    for tunnel in tunnels.values() {
+        if let Some(pid) = tunnel.info.pid {
+            services::signal_tree(pid, true);
+        }
+    }
+    let closed = tunnels.len();
+    tunnels.clear();
+    closed
+}
+
+// =============================================================================
+// Tauri Commands
+// =============================================================================
+
+/// Make `remote_host:remote_port` reachable on `127.0.0.1:local_port`.
+///
+/// # Arguments
+/// * `local_port` - Loopback port to listen on; 0 picks a free one
+/// * `remote_host` - Host name or IPv4 address; through SSH it is resolved
+///   on the remote machine
+/// * `remote_port` - Port on `remote_host`
+/// * `via_ssh_session` - Terminal session running `ssh` to tunnel through;
+///   without it the connection is made directly from this machine
+#[tauri::command]
+pub async fn create_port_forward(
+    app: AppHandle,
+    state: State<'_, TunnelsState>,
+    pty: State<'_, PtyState>,
+    local_port: u16,
+    remote_host: String,
+    remote_port: u16,
+    via_ssh_session: Option<String>,
+) -> Result<TunnelInfo, AppError> {
+    if !is_valid_host(&remote_host) {
+        return Err(AppError::Validation(format!("Invalid remote host: {}", remote_host)));
+    }
+    if remote_port == 0 {
+        return Err(AppError::Validation("Remote port must not be 0".into()));
+    }
+
+    let id = format!("tunnel-{}", state.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+    let (close_tx, close_rx) = watch::channel(false);
+    let (info, connections, ssh_child) = match via_ssh_session {
+        None => {
+            let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, local_port)))
+                .await
+                .map_err(|e| bind_error(local_port, e))?;
+            let local_port = listener
+                .local_addr()
+                .map_err(|e| AppError::Io(format!("Failed to read local port: {}", e)))?
+                .port();
+            let connections = Arc::new(AtomicU64::new(0));
+            tauri::async_runtime::spawn(run_direct(
+                listener,
+                (remote_host.clone(), remote_port),
+                connections.clone(),
+                close_rx.clone(),
+            ));
+            let info = TunnelInfo {
+                id: id.clone(),
+                local_port,
+                remote_host,
+                remote_port,
+                via_ssh_session: None,
+                ssh_destination: None,
+                pid: None,
+                created_at: Local::now(),
+                connections: None,
+            };
+            (info, Some(connections), None)
+        }
+        Some(session_id) => {
+            let ssh = session_ssh(&pty, &session_id).await?;
+            let local_port = match local_port {
+                0 => free_local_port()?,
+                port if port_taken(port) => return Err(AppError::PortInUse(port)),
+                port => port,
+            };
+            let (child, stderr) = start_ssh(&ssh, local_port, &remote_host, remote_port).await?;
+            let info = TunnelInfo {
+                id: id.clone(),
+                local_port,
+                remote_host,
+                remote_port,
+                via_ssh_session: Some(session_id),
+                ssh_destination: Some(ssh.destination),
+                pid: child.id(),
+                created_at: Local::now(),
+                connections: None,
+            };
+            (info, None, Some((child, stderr)))
+        }
+    };
+
+    log::info!(
+        "Tunnel {}: 127.0.0.1:{} -> {}:{}{}",
+        id,
+        info.local_port,
+        info.remote_host,
+        info.remote_port,
+        info.ssh_destination.as_deref().map(|d| format!(" via {}", d)).unwrap_or_default()
+    );
+    let tunnel = Tunnel {
+        info,
+        _close_tx: close_tx,
+        connections,
+    };
+    let info = tunnel.info();
+    state.tunnels().insert(id.clone(), tunnel);
+    // Supervised once listed, so an early exit still closes the tunnel
+    if let Some((child, stderr)) = ssh_child {
+        tauri::async_runtime::spawn(supervise_ssh(app, id, child, stderr, close_rx));
+    }
+    Ok(info)
+}
+
+/// Open tunnels, by local port.
+#[tauri::command]
+pub fn list_tunnels(state: State<'_, TunnelsState>) -> Result<Vec<TunnelInfo>, AppError> {
+    let mut tunnels: Vec<TunnelInfo> = state.tunnels().values().map(Tunnel::info).collect();
+    tunnels.sort_by_key(|t| t.local_port);
+    Ok(tunnels)
+}
+
+/// Close a tunnel: its local port stops listening and open connections are
+/// cut.
+#[tauri::command]
+pub fn close_tunnel(
+    app: AppHandle,
+    state: State<'_, TunnelsState>,
+    id: String,
+) -> Result<(), AppError> {
+    // Dropping the tunnel stops its tasks, which kill its ssh
+    state
+        .tunnels()
+        .remove(&id)
+        .ok_or_else(|| AppError::NotFound(format!("Tunnel not found: {}", id)))?;
+    log::info!("Tunnel {} closed", id);
+    let _ = events::emit(
+        &app,
+        "tunnel-closed",
+        TunnelClosed {
+            id,
+            reason: "closed".into(),
+        },
+    );
+    Ok(())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_ssh_options_and_destination() {
+        let parsed = ssh_options_and_destination(&args("-p 2222 -tA -i ~/.ssh/dev me@box htop"));
+        assert_eq!(
+            parsed,
+            Some((args("-p 2222 -A -i ~/.ssh/dev"), "me@box".to_string()))
+        );
+
+        // Attached values, dropped forwards and `--`
+        let parsed =
+            ssh_options_and_destination(&args("-oProxyJump=bastion -L8080:localhost:80 -- box"));
+        assert_eq!(parsed, Some((args("-o ProxyJump=bastion"), "box".to_string())));
+
+        assert_eq!(ssh_options_and_destination(&args("-v")), None);
+        assert_eq!(ssh_options_and_destination(&args("-p")), None);
+    }
+
+    #[test]
+    fn test_is_valid_host() {
+        assert!(is_valid_host("localhost"));
+        assert!(is_valid_host("10.0.0.5"));
+        assert!(is_valid_host("db.internal_net"));
+        assert!(!is_valid_host(""));
+        assert!(!is_valid_host("-oProxyCommand=x"));
+        assert!(!is_valid_host("host:22"));
+        assert!(!is_valid_host("a b"));
+    }
+
+    #[tokio::test]
+    async fn test_direct_forward_relays_until_closed() {
+        use tokio::io::AsyncWriteExt;
+
+        let echo = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let echo_port = echo.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut conn, _)) = echo.accept().await {
+                tokio::spawn(async move {
+                    let (mut r, mut w) = conn.split();
+                    let _ = tokio::io::copy(&mut r, &mut w).await;
+                });
+            }
+        });
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connections = Arc::new(AtomicU64::new(0));
+        let (close_tx, close_rx) = watch::channel(false);
+        let task = tokio::spawn(run_direct(
+            listener,
+            ("127.0.0.1".into(), echo_port),
+            connections.clone(),
+            close_rx,
+        ));
+
+        let mut conn = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await.unwrap();
+        conn.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        assert_eq!(connections.load(Ordering::Relaxed), 1);
+
+        drop(close_tx);
+        task.await.unwrap();
+        assert!(TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await.is_err());
+    }
+}
@@ -13,6 +13,8 @@ import "@xterm/xterm/css/xterm.css";
 
 import type { ITheme } from "@xterm/xterm";
 
+import { useTerminalAppearance } from "@/hooks/use-terminal-appearance";
+
 // =============================================================================
 // Types
 // =============================================================================
@@ -39,6 +41,8 @@ export interface EmbeddedTerminalHandle {
   clear: () => void;
   /** Focus the terminal */
   focus: () => void;
+  /** Current size in cells, or null before the terminal is created */
+  size: () => { rows: number; cols: number } | null;
 }
 
 // =============================================================================
@@ -71,6 +75,11 @@ const SYNTHIA_THEME: ITheme = {
   brightWhite: "#ffffff",
 };
 
+/** Color schemes selectable by name in the terminal appearance settings */
+const TERMINAL_THEMES: Record<string, ITheme> = {
+  synthia: SYNTHIA_THEME,
+};
+
 // =============================================================================
 // Terminal Registry
 // =============================================================================
@@ -110,6 +119,7 @@ export const EmbeddedTerminal = forwardRef(function EmbeddedTerminal(
   const containerRef = useRef<HTMLDivElement>(null);
   const terminalRef = useRef<Terminal | null>(null);
   const fitAddonRef = useRef<FitAddon | null>(null);
+  const appearance = useTerminalAppearance();
 
   // Expose write/clear/focus to parent via ref
   useImperativeHandle(ref, () => ({
@@ -122,6 +132,10 @@ export const EmbeddedTerminal = forwardRef(function EmbeddedTerminal(
     focus() {
       terminalRef.current?.focus();
     },
+    size() {
+      const terminal = terminalRef.current;
+      return terminal ? { rows: terminal.rows, cols: terminal.cols } : null;
+    },
   }));
 
   useEffect(() => {
@@ -232,6 +246,19 @@ export const EmbeddedTerminal = forwardRef(function EmbeddedTerminal(
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, [sessionId]);
 
+  // Apply the stored appearance (a `theme` prop still wins for the colors)
+  useEffect(() => {
+    const terminal = terminalRef.current;
+    if (!terminal || !appearance) return;
+    terminal.options.fontFamily = appearance.font_family;
+    terminal.options.fontSize = appearance.font_size;
+    terminal.options.cursorStyle = appearance.cursor_style;
+    terminal.options.cursorBlink = appearance.cursor_blink;
+    terminal.options.theme = theme ?? TERMINAL_THEMES[appearance.theme] ?? SYNTHIA_THEME;
+    // Cell size changed with the font
+    requestAnimationFrame(() => fitAddonRef.current?.fit());
+  }, [appearance, theme, sessionId]);
+
   return (
     <div
       ref={containerRef}
@@ -7,6 +7,7 @@ import {
   useState,
   type CSSProperties,
 } from "react";
+import { Channel, invoke } from "@tauri-apps/api/core";
 import { Wifi, WifiOff, AlertTriangle } from "lucide-react";
 
 export type StreamConnectionState =
@@ -34,9 +35,124 @@ export type ScreenStreamHandle = {
 const MAX_BACKOFF_MS = 30_000;
 const INITIAL_BACKOFF_MS = 1_000;
 
+/** First byte of each binary message (must match streaming.rs) */
+const MSG_TYPE_VIDEO = 0x01;
+const MSG_TYPE_AUDIO = 0x02;
+const MSG_TYPE_VIDEO_DELTA = 0x03;
+
+/**
+ * Video messages start with [type][u32 seq][u64 capture µs since epoch]
+ * (little-endian); width and height follow (must match streaming.rs).
+ */
+const VIDEO_STAMP_LEN = 13;
+
+/** Receive time of a video message, reported back for latency stats */
+type FrameAck = { seq: number; capture_us: number; received_us: number };
+
+/** A subscribe_local_frames subscription (client id known once it resolves) */
+type LocalSubscription = { clientId: number | null };
+
+/**
+ * Port of a stream served by this app, which its own webview can receive over
+ * Tauri IPC instead of the WebSocket (null for other URLs or outside Tauri).
+ */
+function localStreamPort(url: string): number | null {
+  if (!("__TAURI_INTERNALS__" in window)) return null;
+  try {
+    const { hostname, port } = new URL(url);
+    if (hostname !== "127.0.0.1" && hostname !== "localhost") return null;
+    return port ? Number(port) : null;
+  } catch {
+    return null;
+  }
+}
+
+/** Pixel format asked for with ?format= in a stream URL */
+function requestedFormat(url: string): string | undefined {
+  try {
+    return new URL(url).searchParams.get("format")?.toLowerCase() || undefined;
+  } catch {
+    return undefined;
+  }
+}
+
+/** Opus audio from the stream is always 48 kHz */
+const AUDIO_SAMPLE_RATE = 48_000;
+
+/**
+ * Plays Opus packets from the stream using WebCodecs + Web Audio.
+ * Decoded chunks are scheduled back-to-back; if playback falls behind
+ * (e.g. after a stall) it resyncs to "now" rather than accumulating delay.
+ */
+class OpusPlayer {
+  private ctx: AudioContext;
+  private decoder: AudioDecoder | null = null;
+  private channels = 0;
+  private nextTime = 0;
+  private timestamp = 0;
+
+  constructor() {
+    this.ctx = new AudioContext({ sampleRate: AUDIO_SAMPLE_RATE });
+  }
+
+  static isSupported(): boolean {
+    return typeof AudioDecoder !== "undefined";
+  }
+
+  push(channels: number, packet: Uint8Array) {
+    if (!this.decoder || this.channels !== channels) {
+      this.decoder?.close();
+      this.channels = channels;
+      this.decoder = new AudioDecoder({
+        output: (data) => this.play(data),
+        error: (e) => console.warn("Opus decode error", e),
+      });
+      this.decoder.configure({
+        codec: "opus",
+        sampleRate: AUDIO_SAMPLE_RATE,
+        numberOfChannels: channels,
+      });
+    }
+    this.decoder.decode(
+      new EncodedAudioChunk({ type: "key", timestamp: this.timestamp, data: packet }),
+    );
+    this.timestamp += 20_000; // 20 ms frames, in microseconds
+  }
+
+  private play(data: AudioData) {
+    const buffer = this.ctx.createBuffer(
+      data.numberOfChannels,
+      data.numberOfFrames,
+      data.sampleRate,
+    );
+    for (let ch = 0; ch < data.numberOfChannels; ch++) {
+      const plane = new Float32Array(data.numberOfFrames);
+      data.copyTo(plane, { planeIndex: ch, format: "f32-planar" });
+      buffer.copyToChannel(plane, ch);
+    }
+    data.close();
+
+    const source = this.ctx.createBufferSource();
+    source.buffer = buffer;
+    source.connect(this.ctx.destination);
+    const now = this.ctx.currentTime;
+    if (this.nextTime < now) this.nextTime = now + 0.05;
+    source.start(this.nextTime);
+    this.nextTime += buffer.duration;
+  }
+
+  close() {
+    this.decoder?.close();
+    this.decoder = null;
+    void this.ctx.close();
+  }
+}
+
 /**
  * Raw BGRA WebSocket stream viewer with connection lifecycle management.
  * Receives raw BGRA pixel frames over WebSocket and renders via WebGL.
+ * Inside the app, a stream served by the app itself is received over a Tauri
+ * IPC channel instead (subscribe_local_frames), with the WebSocket as fallback.
  * A fragment shader swaps R/B channels on the GPU (zero CPU cost).
  * Shows visual overlays for connecting/disconnected/error states
  * and auto-reconnects with exponential backoff.
@@ -54,15 +170,19 @@ const ScreenStream = forwardRef<ScreenStreamHandle, ScreenStreamProps>(
     const canvasRef = useRef<HTMLCanvasElement | null>(null);
     const glRef = useRef<WebGL2RenderingContext | null>(null);
     const wsRef = useRef<WebSocket | null>(null);
+    const localRef = useRef<LocalSubscription | null>(null);
     const containerRef = useRef<HTMLDivElement | null>(null);
     // Track FPS
     const [fps, setFps] = useState(0);
     const frameCountRef = useRef(0);
     const fpsIntervalRef = useRef<ReturnType<typeof setInterval> | null>(null);
-    // Frame-dropping via rAF render loop: onmessage only stores latest frame,
-    // requestAnimationFrame renders raw RGBA pixels at display refresh rate.
-    const pendingFrameRef = useRef<ArrayBuffer | null>(null);
+    // rAF render loop: onmessage only queues video messages, requestAnimationFrame
+    // renders them at display refresh rate. A keyframe replaces everything queued
+    // before it; deltas (changed tiles) queue up since each one must be applied.
+    const pendingFramesRef = useRef<ArrayBuffer[]>([]);
     const rafIdRef = useRef(0);
+    // Created lazily on the first audio packet (streams without audio never allocate one)
+    const audioRef = useRef<OpusPlayer | null>(null);
 
     // Stable refs for connect/scheduleReconnect to break circular dependency
     const connectRef = useRef<() => void>(() => {});
@@ -119,8 +239,7 @@ const ScreenStream = forwardRef<ScreenStreamHandle, ScreenStreamProps>(
       connectRef.current();
     }, [clearTimers]);
 
-    const connect = useCallback(() => {
-      // Close existing connection
+    const closeTransport = useCallback(() => {
       if (wsRef.current) {
         wsRef.current.onopen = null;
         wsRef.current.onclose = null;
@@ -129,7 +248,18 @@ const ScreenStream = forwardRef<ScreenStreamHandle, ScreenStreamProps>(
         wsRef.current.close();
         wsRef.current = null;
       }
-      pendingFrameRef.current = null;
+      // Messages still in flight are ignored once localRef moves on
+      const local = localRef.current;
+      localRef.current = null;
+      if (local?.clientId != null) {
+        invoke("unsubscribe_local_frames", { clientId: local.clientId }).catch(() => {});
+      }
+    }, []);
+
+    const connect = useCallback(() => {
+      // Close existing connection
+      closeTransport();
+      pendingFramesRef.current = [];
       if (rafIdRef.current) {
         cancelAnimationFrame(rafIdRef.current);
         rafIdRef.current = 0;
@@ -137,32 +267,122 @@ const ScreenStream = forwardRef<ScreenStreamHandle, ScreenStreamProps>(
 
       updateState("connecting");
 
-      try {
-        const ws = new WebSocket(url);
-        ws.binaryType = "arraybuffer";
-        wsRef.current = ws;
-
-        ws.onopen = () => {
-          backoffRef.current = INITIAL_BACKOFF_MS;
-          clearTimers();
-          updateState("connected");
-        };
+      // Video: only stores the latest frame — no processing here.
+      // Audio: every packet is decoded (dropping audio is audible).
+      const handleMessage = (data: ArrayBuffer, ack: (frameAck: FrameAck) => void) => {
+        const type = new Uint8Array(data, 0, 1)[0];
+        if (
+          (type === MSG_TYPE_VIDEO || type === MSG_TYPE_VIDEO_DELTA) &&
+          data.byteLength >= VIDEO_STAMP_LEN
+        ) {
+          // Report receive time so the host can measure end-to-end latency
+          const stamp = new DataView(data);
+          ack({
+            seq: stamp.getUint32(1, true),
+            capture_us: Number(stamp.getBigUint64(5, true)),
+            received_us: Math.round((performance.timeOrigin + performance.now()) * 1000),
+          });
+        }
+        if (type === MSG_TYPE_VIDEO) {
+          pendingFramesRef.current = [data];
+        } else if (type === MSG_TYPE_VIDEO_DELTA) {
+          pendingFramesRef.current.push(data);
+        } else if (type === MSG_TYPE_AUDIO && data.byteLength > 2) {
+          if (!OpusPlayer.isSupported()) return;
+          if (!audioRef.current) audioRef.current = new OpusPlayer();
+          const channels = new Uint8Array(data, 1, 1)[0];
+          audioRef.current.push(channels, new Uint8Array(data, 2));
+        }
+      };
 
-        ws.onclose = () => {
-          updateState("disconnected");
+      const connectWebSocket = () => {
+        try {
+          const ws = new WebSocket(url);
+          ws.binaryType = "arraybuffer";
+          wsRef.current = ws;
+
+          ws.onopen = () => {
+            backoffRef.current = INITIAL_BACKOFF_MS;
+            clearTimers();
+            updateState("connected");
+          };
+
+          ws.onclose = () => {
+            updateState("disconnected");
+            scheduleReconnect();
+          };
+
+          ws.onerror = () => {
+            updateState("error");
+          };
+
+          ws.onmessage = (event) => {
+            if (!(event.data instanceof ArrayBuffer) || event.data.byteLength === 0) return;
+            handleMessage(event.data, (frameAck) =>
+              ws.send(JSON.stringify({ type: "frame_ack", ...frameAck })),
+            );
+          };
+        } catch {
+          updateState("error");
           scheduleReconnect();
-        };
+        }
+      };
 
-        ws.onerror = () => {
-          updateState("error");
+      // Same messages over IPC, skipping WebSocket framing and the loopback socket
+      const connectLocal = (port: number) => {
+        const sub: LocalSubscription = { clientId: null };
+        localRef.current = sub;
+        const channel = new Channel<ArrayBuffer>();
+        channel.onmessage = (data) => {
+          if (localRef.current !== sub) return;
+          // An empty message ends the subscription (e.g. the stream stopped)
+          if (data.byteLength === 0) {
+            localRef.current = null;
+            updateState("disconnected");
+            scheduleReconnect();
+            return;
+          }
+          handleMessage(data, (frameAck) => {
+            if (sub.clientId === null) return;
+            invoke("local_frame_ack", {
+              clientId: sub.clientId,
+              seq: frameAck.seq,
+              captureUs: frameAck.capture_us,
+              receivedUs: frameAck.received_us,
+            }).catch(() => {});
+          });
         };
+        invoke<number>("subscribe_local_frames", {
+          onFrame: channel,
+          port,
+          format: requestedFormat(url),
+        })
+          .then((clientId) => {
+            if (localRef.current !== sub) {
+              invoke("unsubscribe_local_frames", { clientId }).catch(() => {});
+              return;
+            }
+            sub.clientId = clientId;
+            backoffRef.current = INITIAL_BACKOFF_MS;
+            clearTimers();
+            updateState("connected");
+          })
+          .catch(() => {
+            // Not this app's screen stream (e.g. a camera stream): use the WebSocket
+            if (localRef.current !== sub) return;
+            localRef.current = null;
+            connectWebSocket();
+          });
+      };
 
-        // onmessage ONLY stores the latest frame — no processing here.
-        ws.onmessage = (event) => {
-          if (!(event.data instanceof ArrayBuffer)) return;
-          pendingFrameRef.current = event.data;
-        };
+      const localPort = localStreamPort(url);
+      if (localPort !== null) {
+        connectLocal(localPort);
+      } else {
+        connectWebSocket();
+      }
 
+      try {
         // WebGL render loop: uploads raw BGRA pixels as a GPU texture and
         // renders with a fragment shader that swaps R/B channels.
         // texSubImage2D → GPU is far faster than putImageData → CPU → GPU.
@@ -172,16 +392,9 @@ const ScreenStream = forwardRef<ScreenStreamHandle, ScreenStreamProps>(
         function renderLoop() {
           rafIdRef.current = requestAnimationFrame(renderLoop);
 
-          const data = pendingFrameRef.current;
-          if (!data || data.byteLength < 4) return;
-          pendingFrameRef.current = null;
-
-          // Parse 4-byte header: u16 width + u16 height (little-endian)
-          const header = new DataView(data);
-          const w = header.getUint16(0, true);
-          const h = header.getUint16(2, true);
-          const expectedBytes = w * h * 4;
-          if (data.byteLength < 4 + expectedBytes) return;
+          const queue = pendingFramesRef.current;
+          if (queue.length === 0) return;
+          pendingFramesRef.current = [];
 
           const canvas = canvasRef.current;
           if (!canvas) return;
@@ -262,6 +475,36 @@ const ScreenStream = forwardRef<ScreenStreamHandle, ScreenStreamProps>(
             );
           }
 
+          let drew = false;
+          for (const data of queue) {
+            const type = new Uint8Array(data, 0, 1)[0];
+            drew =
+              (type === MSG_TYPE_VIDEO
+                ? uploadKeyframe(gl, canvas, data)
+                : uploadDelta(gl, data)) || drew;
+          }
+
+          if (drew) {
+            gl.drawArrays(gl.TRIANGLE_STRIP, 0, 4);
+            frameCountRef.current++;
+          }
+        }
+
+        // Keyframe: stamp + u16 width + u16 height (little-endian), followed
+        // by the full BGRA frame.
+        function uploadKeyframe(
+          gl: WebGL2RenderingContext,
+          canvas: HTMLCanvasElement,
+          data: ArrayBuffer,
+        ): boolean {
+          const headerLen = VIDEO_STAMP_LEN + 4;
+          if (data.byteLength < headerLen) return false;
+          const header = new DataView(data);
+          const w = header.getUint16(VIDEO_STAMP_LEN, true);
+          const h = header.getUint16(VIDEO_STAMP_LEN + 2, true);
+          const expectedBytes = w * h * 4;
+          if (data.byteLength < headerLen + expectedBytes) return false;
+
           // Resize canvas and reallocate texture if needed
           if (canvas.width !== w || canvas.height !== h) {
             canvas.width = w;
@@ -271,7 +514,7 @@ const ScreenStream = forwardRef<ScreenStreamHandle, ScreenStreamProps>(
           }
 
           // Upload BGRA pixels to GPU texture
-          const pixels = new Uint8Array(data, 4, expectedBytes);
+          const pixels = new Uint8Array(data, headerLen, expectedBytes);
           if (texW !== w || texH !== h) {
             gl.texImage2D(
               gl.TEXTURE_2D, 0, gl.RGBA, w, h, 0,
@@ -285,16 +528,46 @@ const ScreenStream = forwardRef<ScreenStreamHandle, ScreenStreamProps>(
               gl.RGBA, gl.UNSIGNED_BYTE, pixels,
             );
           }
+          return true;
+        }
 
-          gl.drawArrays(gl.TRIANGLE_STRIP, 0, 4);
-          frameCountRef.current++;
+        // Delta: stamp + u16 width + u16 height + u16 tile count, then per
+        // tile u16 x, y, w, h and its BGRA pixels. Uploads only the changed
+        // tiles into the existing texture.
+        function uploadDelta(gl: WebGL2RenderingContext, data: ArrayBuffer): boolean {
+          const headerLen = VIDEO_STAMP_LEN + 6;
+          if (data.byteLength < headerLen) return false;
+          const view = new DataView(data);
+          const w = view.getUint16(VIDEO_STAMP_LEN, true);
+          const h = view.getUint16(VIDEO_STAMP_LEN + 2, true);
+          // Deltas only apply on top of a keyframe of the same size
+          if (texW !== w || texH !== h) return false;
+
+          const count = view.getUint16(VIDEO_STAMP_LEN + 4, true);
+          let offset = headerLen;
+          for (let i = 0; i < count; i++) {
+            if (offset + 8 > data.byteLength) return i > 0;
+            const x = view.getUint16(offset, true);
+            const y = view.getUint16(offset + 2, true);
+            const tw = view.getUint16(offset + 4, true);
+            const th = view.getUint16(offset + 6, true);
+            offset += 8;
+            const bytes = tw * th * 4;
+            if (offset + bytes > data.byteLength) return i > 0;
+            gl.texSubImage2D(
+              gl.TEXTURE_2D, 0, x, y, tw, th,
+              gl.RGBA, gl.UNSIGNED_BYTE, new Uint8Array(data, offset, bytes),
+            );
+            offset += bytes;
+          }
+          return count > 0;
         }
         rafIdRef.current = requestAnimationFrame(renderLoop);
       } catch {
         updateState("error");
         scheduleReconnect();
       }
-    }, [url, updateState, clearTimers, scheduleReconnect]);
+    }, [url, updateState, clearTimers, scheduleReconnect, closeTransport]);
 
     // Keep connectRef in sync with the latest connect function
     connectRef.current = connect;
@@ -311,16 +584,11 @@ const ScreenStream = forwardRef<ScreenStreamHandle, ScreenStreamProps>(
           cancelAnimationFrame(rafIdRef.current);
           rafIdRef.current = 0;
         }
-        if (wsRef.current) {
-          wsRef.current.onopen = null;
-          wsRef.current.onclose = null;
-          wsRef.current.onerror = null;
-          wsRef.current.onmessage = null;
-          wsRef.current.close();
-          wsRef.current = null;
-        }
+        audioRef.current?.close();
+        audioRef.current = null;
+        closeTransport();
       };
-    }, [url, clearTimers, connect]);
+    }, [url, clearTimers, connect, closeTransport]);
 
     // FPS counter
     useEffect(() => {
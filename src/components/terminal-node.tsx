@@ -1,4 +1,6 @@
-import { useState } from "react";
+import { useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWebview } from "@tauri-apps/api/webview";
 import {
   EmbeddedTerminal,
   type EmbeddedTerminalHandle,
@@ -52,15 +54,52 @@ export function TerminalNode({
   killOnCleanup,
   cwd,
 }: TerminalNodeProps) {
-  const { termRef, status, write, resize } = useTerminalSession({ sessionId, killOnCleanup, cwd });
+  const { termRef, status, foreground, unseenBells, unseenActivity, setFocused, write, resize } =
+    useTerminalSession({ sessionId, killOnCleanup, cwd });
   const [mode, setMode] = useState<TerminalMode>(
     initialReadOnly ? "agent" : "interactive",
   );
 
   const isReadOnly = mode === "agent" || status === "exited";
+  const containerRef = useRef<HTMLDivElement>(null);
+
+  // Files dropped from the OS onto this terminal are typed in as quoted paths
+  useEffect(() => {
+    if (isReadOnly) return;
+    let unlisten: (() => void) | null = null;
+    let cancelled = false;
+
+    getCurrentWebview()
+      .onDragDropEvent((event) => {
+        if (event.payload.type !== "drop" || event.payload.paths.length === 0) return;
+        const rect = containerRef.current?.getBoundingClientRect();
+        if (!rect) return;
+        const x = event.payload.position.x / window.devicePixelRatio;
+        const y = event.payload.position.y / window.devicePixelRatio;
+        if (x < rect.left || x > rect.right || y < rect.top || y > rect.bottom) return;
+        invoke("inject_paths", { sessionId, paths: event.payload.paths }).catch((err) =>
+          console.error("inject_paths failed:", err),
+        );
+      })
+      .then((fn) => {
+        if (cancelled) fn();
+        else unlisten = fn;
+      });
+
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [sessionId, isReadOnly]);
 
   return (
-    <div className={`flex flex-col h-full w-full ${className ?? ""}`}>
+    <div
+      className={`flex flex-col h-full w-full ${className ?? ""}`}
+      onFocus={() => setFocused(true)}
+      onBlur={(e) => {
+        if (!e.currentTarget.contains(e.relatedTarget as Node | null)) setFocused(false);
+      }}
+    >
       {/* Status bar */}
       <div className="flex items-center gap-2 px-2 py-1 bg-black/80 border-b border-border/50 shrink-0">
         <StatusDot status={status} />
@@ -72,6 +111,29 @@ export function TerminalNode({
               : "SESSION_ENDED"}
         </span>
 
+        {/* Foreground process */}
+        {status === "running" && foreground && (
+          <span
+            className="font-mono text-[9px] text-primary/70 truncate max-w-[40%]"
+            title={`${foreground.command} (pid ${foreground.pid})`}
+          >
+            {foreground.command}
+          </span>
+        )}
+
+        {/* Attention badges (output or bells while unfocused) */}
+        {unseenBells > 0 && (
+          <span
+            className="font-mono text-[9px] border border-yellow-400/50 text-yellow-400 bg-yellow-400/10 px-1"
+            title={`${unseenBells} bell${unseenBells === 1 ? "" : "s"} while in background`}
+          >
+            BELL {unseenBells}
+          </span>
+        )}
+        {unseenBells === 0 && unseenActivity && (
+          <div className="h-1.5 w-1.5 rounded-full bg-accent" title="New output while in background" />
+        )}
+
         {/* Mode toggle */}
         {status === "running" && (
           <button
@@ -94,7 +156,7 @@ export function TerminalNode({
       </div>
 
       {/* Terminal */}
-      <div className="flex-1 min-h-0">
+      <div ref={containerRef} className="flex-1 min-h-0">
         <EmbeddedTerminal
           ref={termRef as React.Ref<EmbeddedTerminalHandle>}
           sessionId={sessionId}
@@ -0,0 +1,33 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+import { listen } from "@/lib/events";
+import { logger } from "@/lib/logger";
+import type { TerminalAppearance } from "@/types/tauri";
+
+/**
+ * Terminal appearance stored in the backend settings, kept current across
+ * windows via the "terminal-appearance-changed" event. Null until loaded.
+ */
+export function useTerminalAppearance(): TerminalAppearance | null {
+  const [appearance, setAppearance] = useState<TerminalAppearance | null>(null);
+
+  useEffect(() => {
+    let cancelled = false;
+    invoke<TerminalAppearance>("get_terminal_appearance")
+      .then((loaded) => {
+        if (!cancelled) setAppearance(loaded);
+      })
+      .catch((err) => logger.warn(`Failed to load terminal appearance: ${err}`));
+
+    const unlisten = listen<TerminalAppearance>("terminal-appearance-changed", (event) =>
+      setAppearance(event.payload),
+    );
+    return () => {
+      cancelled = true;
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  return appearance;
+}
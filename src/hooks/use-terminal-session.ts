@@ -1,7 +1,21 @@
 import { useEffect, useRef, useCallback, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+import { listen } from "@/lib/events";
 import type { EmbeddedTerminalHandle } from "@/components/embedded-terminal";
+import { toast } from "@/hooks/use-toast";
+import type {
+  CommandFinished,
+  ForegroundChanged,
+  ForegroundProcess,
+  OutputReplay,
+  PtyOutput,
+  RecordingReplay,
+  TerminalActivity,
+  TerminalBell,
+  TerminalSize,
+  TerminalSubscription,
+} from "@/types/tauri";
 
 export type TerminalStatus = "connecting" | "running" | "exited";
 
@@ -11,53 +25,144 @@ interface UseTerminalSessionOptions {
   killOnCleanup?: boolean;
   /** Working directory for the terminal shell. Defaults to $HOME if not specified. */
   cwd?: string;
+  /** Shell id from list_available_shells. Defaults to the configured/platform shell. */
+  shell?: string;
+  /** Extra arguments for the shell, e.g. ["--login"]. Only used when the PTY is spawned. */
+  shellArgs?: string[];
+  /** Commands to run once the first prompt shows, e.g. ["npm run dev"]. Only used when the PTY is spawned. */
+  initialCommands?: string[];
+  /** Play this asciicast recording into a read-only virtual session instead of spawning a PTY. */
+  replay?: { path: string; speed?: number };
 }
 
 /**
  * Hook that manages a PTY session's lifecycle:
  * - Spawns PTY on mount
- * - Streams output to the terminal ref
+ * - Subscribes to the session's output while mounted (the backend emits no
+ *   output events for unviewed sessions) and streams it to the terminal ref,
+ *   replaying chunks missed by the event channel
  * - Provides write() for user keystrokes
  * - Provides resize() for dimension changes
+ * - Tracks the foreground process (e.g. "cargo build" vs "zsh")
+ * - Counts bells and output activity while the terminal is not focused
+ * - Toasts commands that fail while the terminal is not focused
  * - Kills PTY on unmount
+ *
+ * With `replay`, the session plays a recording instead (replay_recording):
+ * input and resizes are ignored and unmounting stops the playback.
  */
-export function useTerminalSession({ sessionId, killOnCleanup = true, cwd }: UseTerminalSessionOptions) {
+export function useTerminalSession({
+  sessionId,
+  killOnCleanup = true,
+  cwd,
+  shell,
+  shellArgs,
+  initialCommands,
+  replay,
+}: UseTerminalSessionOptions) {
   const termRef = useRef<EmbeddedTerminalHandle | null>(null);
   const [status, setStatus] = useState<TerminalStatus>("connecting");
+  const [foreground, setForeground] = useState<ForegroundProcess | null>(null);
+  const [unseenBells, setUnseenBells] = useState(0);
+  const [unseenActivity, setUnseenActivity] = useState(false);
+  const focusedRef = useRef(false);
+  // Read at spawn time only, so a new array each render does not respawn
+  const shellArgsRef = useRef(shellArgs);
+  shellArgsRef.current = shellArgs;
+  const initialCommandsRef = useRef(initialCommands);
+  initialCommandsRef.current = initialCommands;
+  const replayPath = replay?.path;
+  const replaySpeed = replay?.speed;
+
+  /** Mark the terminal focused (clears attention badges) or blurred. */
+  const setFocused = useCallback((focused: boolean) => {
+    focusedRef.current = focused;
+    if (focused) {
+      setUnseenBells(0);
+      setUnseenActivity(false);
+    }
+  }, []);
 
   const write = useCallback(
     async (data: string) => {
+      if (replayPath) return;
       try {
         await invoke("write_terminal", { sessionId, data });
       } catch (err) {
         console.error("write_terminal failed:", err);
       }
     },
-    [sessionId],
+    [sessionId, replayPath],
   );
 
   const resize = useCallback(
     async (cols: number, rows: number) => {
+      if (replayPath) return;
       try {
         await invoke("resize_terminal", { sessionId, rows, cols });
       } catch (err) {
         console.error("resize_terminal failed:", err);
       }
     },
-    [sessionId],
+    [sessionId, replayPath],
   );
 
   useEffect(() => {
     let unlistenOutput: UnlistenFn | null = null;
     let unlistenClose: UnlistenFn | null = null;
+    let unlistenForeground: UnlistenFn | null = null;
+    let unlistenBell: UnlistenFn | null = null;
+    let unlistenActivity: UnlistenFn | null = null;
+    let unlistenCommand: UnlistenFn | null = null;
     let cancelled = false;
+    let subscribed = false;
+
+    // Output chunks are numbered from 1; a jump means events were dropped
+    let nextSeq = 1;
+    let replaying = false;
+    let pending: PtyOutput[] = [];
+
+    const apply = (chunk: PtyOutput) => {
+      if (chunk.seq < nextSeq) return;
+      termRef.current?.write(chunk.data);
+      nextSeq = chunk.seq + 1;
+    };
+
+    const replayFrom = async (fromSeq: number) => {
+      replaying = true;
+      try {
+        const replay = await invoke<OutputReplay>("replay_output", { sessionId, fromSeq });
+        if (cancelled) return;
+        if (!replay.complete) {
+          termRef.current?.write("\r\n\x1b[2m[some output was lost]\x1b[0m\r\n");
+        }
+        replay.chunks.forEach(apply);
+      } catch (err) {
+        console.error("replay_output failed:", err);
+      } finally {
+        replaying = false;
+        // Live chunks that arrived during the replay (duplicates are skipped)
+        const queued = pending.sort((a, b) => a.seq - b.seq);
+        pending = [];
+        if (queued.length > 0 && queued[0].seq > nextSeq) nextSeq = queued[0].seq;
+        queued.forEach(apply);
+      }
+    };
 
     async function setup() {
       // 1. Listen for PTY output BEFORE spawning (so we don't miss early data)
-      unlistenOutput = await listen<string>(
+      unlistenOutput = await listen<PtyOutput>(
         `pty-output-${sessionId}`,
         (event) => {
-          termRef.current?.write(event.payload);
+          const chunk = event.payload;
+          if (replaying) {
+            pending.push(chunk);
+          } else if (chunk.seq > nextSeq) {
+            pending.push(chunk);
+            replayFrom(nextSeq);
+          } else {
+            apply(chunk);
+          }
         },
       );
 
@@ -68,36 +173,124 @@ export function useTerminalSession({ sessionId, killOnCleanup = true, cwd }: Use
         },
       );
 
+      unlistenForeground = await listen<ForegroundChanged>(
+        "terminal-foreground-changed",
+        (event) => {
+          if (!cancelled && event.payload.session_id === sessionId) {
+            setForeground(event.payload.foreground);
+          }
+        },
+      );
+
+      unlistenBell = await listen<TerminalBell>("terminal-bell", (event) => {
+        if (!cancelled && event.payload.session_id === sessionId && !focusedRef.current) {
+          setUnseenBells((n) => n + event.payload.count);
+        }
+      });
+
+      unlistenActivity = await listen<TerminalActivity>("terminal-activity", (event) => {
+        if (!cancelled && event.payload.session_id === sessionId && !focusedRef.current) {
+          setUnseenActivity(true);
+        }
+      });
+
+      unlistenCommand = await listen<CommandFinished>("command-finished", (event) => {
+        const { session_id, command, exit_code } = event.payload;
+        if (cancelled || session_id !== sessionId || focusedRef.current) return;
+        if (exit_code === null || exit_code === 0) return;
+        toast({
+          variant: "destructive",
+          title: `${command ?? "Command"} failed (exit ${exit_code})`,
+          description: `in terminal '${sessionId}'`,
+        });
+      });
+
       if (cancelled) {
         unlistenOutput();
         unlistenClose();
+        unlistenForeground();
+        unlistenBell();
+        unlistenActivity();
+        unlistenCommand();
         return;
       }
 
-      // 2. Spawn the PTY session
+      // 2. Spawn the PTY session (or start the recording, which has no
+      // scrollback to subscribe to: its output only arrives as events)
+      if (replayPath) {
+        try {
+          await invoke<RecordingReplay>("replay_recording", {
+            path: replayPath,
+            speed: replaySpeed,
+            sessionId,
+          });
+          if (!cancelled) setStatus("running");
+        } catch (err) {
+          console.error("replay_recording failed:", err);
+          if (!cancelled) setStatus("exited");
+        }
+        return;
+      }
       try {
-        await invoke<string>("spawn_terminal", { sessionId, cwd });
+        // Spawn at the panel's size so the first prompt doesn't reflow
+        const size: TerminalSize | undefined = termRef.current?.size() ?? undefined;
+        await invoke<string>("spawn_terminal", {
+          sessionId,
+          cwd,
+          shell,
+          size,
+          args: shellArgsRef.current,
+          initialCommands: initialCommandsRef.current,
+        });
         if (!cancelled) setStatus("running");
       } catch (err) {
         console.error("spawn_terminal failed:", err);
         if (!cancelled) setStatus("exited");
+        return;
+      }
+
+      // 3. Subscribe to output events, catching up on output printed before
+      try {
+        const subscription = await invoke<TerminalSubscription>("subscribe_terminal", {
+          sessionId,
+        });
+        subscribed = true;
+        if (cancelled) {
+          invoke("unsubscribe_terminal", { sessionId }).catch(() => {});
+        } else if (subscription.last_seq >= nextSeq && !replaying) {
+          replayFrom(nextSeq);
+        }
+      } catch (err) {
+        console.error("subscribe_terminal failed:", err);
       }
     }
 
     setup();
 
-    // 3. Cleanup on unmount
+    // 4. Cleanup on unmount
     return () => {
       cancelled = true;
+      if (subscribed && !killOnCleanup) {
+        invoke("unsubscribe_terminal", { sessionId }).catch((err) =>
+          console.error("unsubscribe_terminal cleanup failed:", err),
+        );
+      }
       unlistenOutput?.();
       unlistenClose?.();
-      if (killOnCleanup) {
+      unlistenForeground?.();
+      unlistenBell?.();
+      unlistenActivity?.();
+      unlistenCommand?.();
+      if (replayPath) {
+        // Fails harmlessly once the recording has ended
+        invoke("stop_replay", { sessionId }).catch(() => {});
+      } else if (killOnCleanup) {
         invoke("kill_terminal", { sessionId }).catch((err) =>
           console.error("kill_terminal cleanup failed:", err),
         );
       }
     };
-  }, [sessionId, killOnCleanup, cwd]);
+  }, [sessionId, killOnCleanup, cwd, shell, replayPath, replaySpeed]);
 
-  return { termRef, status, write, resize };
+  return { termRef, status, foreground, unseenBells, unseenActivity, setFocused, write, resize };
 }
@@ -0,0 +1,34 @@
+/**
+ * Backend Event Listener
+ *
+ * The backend wraps every event as `{ v, type, payload }` (see
+ * src-tauri/src/events.rs). `listen` here has the signature of Tauri's
+ * `listen` but hands the handler the unwrapped payload, and logs a warning
+ * once per event when the backend speaks a newer schema than this build.
+ */
+
+import { listen as tauriListen, type EventCallback, type UnlistenFn } from "@tauri-apps/api/event";
+
+import { logger } from "@/lib/logger";
+import { EVENT_SCHEMA_VERSION, type EventEnvelope } from "@/types/tauri";
+
+const warned = new Set<string>();
+
+/**
+ * Listen to a backend event, unwrapping its envelope.
+ *
+ * @example
+ * ```typescript
+ * const unlisten = await listen<Settings>("settings-changed", (event) => apply(event.payload));
+ * ```
+ */
+export function listen<T>(name: string, handler: EventCallback<T>): Promise<UnlistenFn> {
+  return tauriListen<EventEnvelope<T>>(name, (event) => {
+    const { v, payload } = event.payload;
+    if (v > EVENT_SCHEMA_VERSION && !warned.has(name)) {
+      warned.add(name);
+      logger.warn(`Event ${name} uses schema v${v}, this build understands v${EVENT_SCHEMA_VERSION}`);
+    }
+    handler({ ...event, payload });
+  });
+}
@@ -1,7 +1,8 @@
 import { useEffect, useMemo, useState, useCallback } from "react";
 import { SYSTEM_STATS_POLL_INTERVAL_MS } from "@/config/constants";
-import { SystemStats } from "@/types/tauri";
+import { HostInfo, SystemStats } from "@/types/tauri";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@/lib/events";
 import { Link } from "wouter";
 import { TerminalNode } from "@/components/terminal-node";
 import { ScreenStream, type StreamConnectionState } from "@/components/screen-stream";
@@ -455,6 +456,15 @@ export default function CommandCenter() {
     return () => clearInterval(interval);
   }, []);
 
+  // Host identity doesn't change while running — fetch once
+  const [hostInfo, setHostInfo] = useState<HostInfo | null>(null);
+
+  useEffect(() => {
+    invoke<HostInfo>("get_host_info")
+      .then(setHostInfo)
+      .catch((err) => console.error("Failed to fetch host info:", err));
+  }, []);
+
   const stats = useMemo(() => {
     const total = plan.length;
     const done = plan.filter((x) => x.status === "done").length;
@@ -553,6 +563,22 @@ export default function CommandCenter() {
     setNewPanelCwd("");
   }
 
+  // "New Terminal" in the system tray menu opens a terminal panel
+  useEffect(() => {
+    const unlisten = listen<void>("tray-new-terminal", () => {
+      const panel: WorkspacePanel = {
+        id: `terminal-${Date.now()}`,
+        kind: "terminal",
+        title: "Terminal",
+        status: "idle",
+      };
+      setPanels((prev) => [...prev, panel]);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   const handleRemovePanel = useCallback((id: string) => {
     const panel = panels.find(p => p.id === id);
     if (panel?.kind === "terminal") {
@@ -679,6 +705,15 @@ export default function CommandCenter() {
                  <div className="corner-brackets brutal-border p-4 flex flex-col justify-between group h-full" data-testid="card-systems">
                    <div className="flex items-center justify-between font-mono text-[10px] uppercase text-muted-foreground group-hover:text-primary transition-colors">
                      <span>Infrastructure</span>
+                     {hostInfo && (
+                       <span
+                         className="truncate mx-2 normal-case text-muted-foreground/70"
+                         title={`${hostInfo.os_long_version} · ${hostInfo.arch} · kernel ${hostInfo.kernel_version}`}
+                         data-testid="text-host-name"
+                       >
+                         {hostInfo.hostname}
+                       </span>
+                     )}
                      <Zap className="h-4 w-4" />
                    </div>
                    <div className="flex flex-col gap-3">
@@ -4,18 +4,27 @@ import { Card } from "@/components/ui/card";
 import { ScrollText, ArrowLeft, Search, Download, Trash2, RefreshCw } from "lucide-react";
 import { useEffect, useMemo, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { isAppError, type LogStats } from "@/types/tauri";
 
 type LogLevel = "TRACE" | "DEBUG" | "INFO" | "WARN" | "ERROR";
 
 type LogEntry = {
   id: string;
+  /** RFC 3339 timestamp in the zone set by logging.timezone */
   ts: string;
   level: LogLevel;
   source: string;
   message: string;
   meta?: Record<string, string>;
+  /** Identical consecutive lines this entry stands for (1 unless collapsed) */
+  count: number;
+  /** Timestamp of the last line collapsed into this entry */
+  last_ts?: string;
 };
 
+/** Range of the stats summary above the table */
+const STATS_RANGE_SECS = 24 * 3600;
+
 // Response type from Tauri backend
 type LogResult = {
   success: boolean;
@@ -54,6 +63,23 @@ function LevelPill({ level }: { level: LogLevel }) {
   );
 }
 
+/** Error rate per bucket as a line, scaled to the busiest bucket's rate */
+function ErrorSparkline({ stats }: { stats: LogStats }) {
+  const width = 120;
+  const height = 16;
+  const rates = stats.buckets.map((b) => b.error_rate);
+  const max = Math.max(...rates, 0.01);
+  const step = rates.length > 1 ? width / (rates.length - 1) : width;
+  const points = rates
+    .map((rate, i) => `${(i * step).toFixed(1)},${(height - (rate / max) * height).toFixed(1)}`)
+    .join(" ");
+  return (
+    <svg width={width} height={height} className="text-destructive" data-testid="sparkline-error-rate">
+      <polyline points={points} fill="none" stroke="currentColor" strokeWidth="1" />
+    </svg>
+  );
+}
+
 export default function Logs() {
   const [query, setQuery] = useState("");
   const [level, setLevel] = useState<"ALL" | LogLevel>("ALL");
@@ -62,6 +88,7 @@ export default function Logs() {
   const [logs, setLogs] = useState<LogEntry[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
+  const [stats, setStats] = useState<LogStats | null>(null);
 
   // Load logs from backend on mount
   async function loadLogs() {
@@ -75,9 +102,17 @@ export default function Logs() {
         level: log.level.toUpperCase() as LogLevel,
       }));
       setLogs(mappedLogs);
+      // The summary is optional; the table still loads without it
+      invoke<LogStats>("get_log_stats", { rangeSecs: STATS_RANGE_SECS })
+        .then(setStats)
+        .catch((err) => console.error("Failed to load log stats:", err));
+      // The user has now seen the errors counted in the status bar
+      invoke("mark_errors_read").catch((err) =>
+        console.error("Failed to mark errors read:", err),
+      );
     } catch (err) {
       console.error("Failed to load logs:", err);
-      setError(String(err));
+      setError(isAppError(err) ? err.message : String(err));
     } finally {
       setLoading(false);
     }
@@ -114,9 +149,10 @@ export default function Logs() {
     try {
       await invoke("clear_logs");
       setLogs([]);
+      setStats(null);
     } catch (err) {
       console.error("Failed to clear logs:", err);
-      setError(String(err));
+      setError(isAppError(err) ? err.message : String(err));
     }
   }
 
@@ -269,6 +305,21 @@ export default function Logs() {
               Showing <span className="text-foreground">{filtered.length}</span> / {logs.length}
             </div>
 
+            {stats && (
+              <div className="flex items-center gap-3 font-mono text-[10px] uppercase tracking-[0.2em] text-muted-foreground" data-testid="text-log-stats">
+                <span>
+                  24h <span className="text-foreground">{stats.total}</span>
+                </span>
+                <span>
+                  Errors <span className="text-destructive">{(stats.levels.ERROR ?? 0) + (stats.levels.FATAL ?? 0)}</span>
+                </span>
+                <span>
+                  Warn <span className="text-accent">{stats.levels.WARN ?? 0}</span>
+                </span>
+                <ErrorSparkline stats={stats} />
+              </div>
+            )}
+
             <div className="flex items-center gap-2">
               <div className="h-2 w-2 bg-primary animate-pulse" />
               <div className="font-mono text-[10px] uppercase tracking-[0.2em] text-primary" data-testid="status-live">
@@ -358,6 +409,15 @@ export default function Logs() {
                       <div className="col-span-5">
                         <div className="font-mono text-xs text-foreground" data-testid={`text-message-${l.id}`}>
                           {l.message}
+                          {l.count > 1 && (
+                            <span
+                              className="ml-2 border border-accent/40 bg-accent/10 px-1.5 py-0.5 text-[10px] text-accent"
+                              title={l.last_ts ? `Last at ${l.last_ts}` : undefined}
+                              data-testid={`badge-count-${l.id}`}
+                            >
+                              ×{l.count}
+                            </span>
+                          )}
                         </div>
                         {l.meta && (
                           <div className="mt-1 flex flex-wrap gap-2" data-testid={`meta-${l.id}`}>
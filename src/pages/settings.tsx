@@ -16,7 +16,17 @@ import { Switch } from "@/components/ui/switch";
 import { Separator } from "@/components/ui/separator";
 import { Slider } from "@/components/ui/slider";
 import { invoke } from "@tauri-apps/api/core";
-import type { StreamStatus, DisplayInfo } from "@/types/tauri";
+import { listen } from "@/lib/events";
+import {
+  isAppError,
+  type AppError,
+  type StreamAutoStopped,
+  type StreamStatus,
+  type StreamRestartOptions,
+  type ScalingQuality,
+  type DisplayInfo,
+  type Settings,
+} from "@/types/tauri";
 import { cn } from "@/lib/utils";
 
 type StreamSource = {
@@ -129,6 +139,18 @@ function LocalCaptureCard({ onStatusChange }: { onStatusChange: (active: boolean
     quality: 80,
     clients: 0,
     display_id: null,
+    audio: false,
+    permission_pending: false,
+    max_width: null,
+    scaling: "area",
+    show_cursor: true,
+    show_highlight: false,
+    resolution: "captured",
+    target_app: null,
+    tls: false,
+    cert_fingerprint: null,
+    remote_control: false,
+    client_stats: [],
   });
 
   useEffect(() => {
@@ -143,8 +165,57 @@ function LocalCaptureCard({ onStatusChange }: { onStatusChange: (active: boolean
     port: 9100,
     fps: 30,
     quality: 80,
+    audio: false,
+    maxWidth: null as number | null,
+    scaling: "area" as ScalingQuality,
   });
 
+  // Stream defaults come from the settings file and follow live edits to it
+  useEffect(() => {
+    const applyDefaults = (s: Settings) =>
+      setConfig((prev) => ({ ...prev, port: s.stream.port, fps: s.stream.fps, quality: s.stream.quality }));
+
+    invoke<Settings>("get_settings")
+      .then(applyDefaults)
+      .catch((err) => console.error("Failed to load settings", err));
+
+    const unlisten = listen<Settings>("settings-changed", (event) => applyDefaults(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // A start deferred until screen capture permission is granted finishes in the background
+  useEffect(() => {
+    const unlistenStarted = listen<StreamStatus>("stream-started", (event) => {
+      setStatus(event.payload);
+      setError(null);
+    });
+    const unlistenFailed = listen<AppError>("stream-start-failed", (event) => {
+      setStatus((prev) => ({ ...prev, active: false, permission_pending: false }));
+      setError(event.payload.message);
+    });
+    const unlistenAutoStopped = listen<StreamAutoStopped>("stream-auto-stopped", () => {
+      setStatus((prev) => ({ ...prev, active: false, clients: 0, client_stats: [] }));
+    });
+    // Stopped from the system tray menu
+    const unlistenStopped = listen<void>("stream-stopped", () => {
+      setStatus((prev) => ({ ...prev, active: false, clients: 0, client_stats: [] }));
+    });
+    return () => {
+      unlistenStarted.then((fn) => fn());
+      unlistenFailed.then((fn) => fn());
+      unlistenAutoStopped.then((fn) => fn());
+      unlistenStopped.then((fn) => fn());
+    };
+  }, []);
+
+  const handleOpenPermissionSettings = () => {
+    invoke("open_capture_permission_settings").catch((err) =>
+      console.error("Failed to open permission settings", err)
+    );
+  };
+
   useEffect(() => {
     // Fetch displays and status together, then pick the right selectedDisplayId
     const init = async () => {
@@ -189,11 +260,24 @@ function LocalCaptureCard({ onStatusChange }: { onStatusChange: (active: boolean
         fps: config.fps,
         quality: config.quality,
         displayId: parseInt(selectedDisplayId),
+        audio: config.audio,
+        maxWidth: config.maxWidth,
+        scaling: config.scaling,
       });
       setStatus(newStatus);
     } catch (err) {
       console.error("Start failed", err);
-      setError("Failed to start capture server. Check port availability.");
+      if (isAppError(err) && err.code === "port_in_use") {
+        const free = await invoke<number>("suggest_stream_port").catch(() => null);
+        if (free !== null) {
+          setConfig((prev) => ({ ...prev, port: free }));
+          setError(`Port ${config.port} is already in use. Switched to free port ${free}.`);
+        } else {
+          setError(`Port ${config.port} is already in use. Choose a different port.`);
+        }
+      } else {
+        setError("Failed to start capture server. Check port availability.");
+      }
     } finally {
       setIsLoading(false);
     }
@@ -203,7 +287,7 @@ function LocalCaptureCard({ onStatusChange }: { onStatusChange: (active: boolean
     setIsLoading(true);
     try {
       await invoke("stop_local_stream");
-      setStatus((prev) => ({ ...prev, active: false }));
+      setStatus((prev) => ({ ...prev, active: false, permission_pending: false }));
     } catch (err) {
       console.error("Stop failed", err);
     } finally {
@@ -211,20 +295,30 @@ function LocalCaptureCard({ onStatusChange }: { onStatusChange: (active: boolean
     }
   };
 
+  const handleMaxWidthChange = async (value: string) => {
+    const maxWidth = value === "native" ? null : parseInt(value);
+    setConfig((prev) => ({ ...prev, maxWidth }));
+    if (!status.active) return;
+    // Applied to the running stream without restarting it
+    setError(null);
+    try {
+      const newStatus = await invoke<StreamStatus>("set_stream_max_width", { maxWidth });
+      setStatus(newStatus);
+    } catch (err) {
+      console.error("Max width change failed", err);
+      setError("Failed to change stream resolution.");
+    }
+  };
+
   const handleDisplaySwitch = async (newDisplayId: string) => {
     setSelectedDisplayId(newDisplayId);
     if (!status.active) return;
-    // Auto-restart capture on the new display
+    // Swap the capture over to the new display; viewers stay connected
     setIsLoading(true);
     setError(null);
     try {
-      await invoke("stop_local_stream");
-      const newStatus = await invoke<StreamStatus>("start_local_stream", {
-        port: config.port,
-        fps: config.fps,
-        quality: config.quality,
-        displayId: parseInt(newDisplayId),
-      });
+      const options: StreamRestartOptions = { display_id: parseInt(newDisplayId) };
+      const newStatus = await invoke<StreamStatus>("restart_local_stream", { options });
       setStatus(newStatus);
     } catch (err) {
       console.error("Display switch failed", err);
@@ -234,7 +328,7 @@ function LocalCaptureCard({ onStatusChange }: { onStatusChange: (active: boolean
     }
   };
 
-  const endpointUrl = `ws://127.0.0.1:${status.active ? status.port : config.port}`;
+  const endpointUrl = `${status.tls ? "wss" : "ws"}://127.0.0.1:${status.active ? status.port : config.port}`;
 
   if (error && displays.length === 0) {
     return null;
@@ -254,19 +348,27 @@ function LocalCaptureCard({ onStatusChange }: { onStatusChange: (active: boolean
             <div className="flex items-center gap-2 mt-1">
               <span className={cn(
                 "inline-block h-1.5 w-1.5 rounded-full",
-                status.active ? "bg-primary animate-pulse" : "bg-muted-foreground"
+                status.active
+                  ? "bg-primary animate-pulse"
+                  : status.permission_pending
+                    ? "bg-yellow-500 animate-pulse"
+                    : "bg-muted-foreground"
               )} />
               <span className={cn(
                 "font-mono text-[10px] uppercase tracking-widest",
-                status.active ? "text-primary" : "text-muted-foreground"
+                status.active
+                  ? "text-primary"
+                  : status.permission_pending
+                    ? "text-yellow-500"
+                    : "text-muted-foreground"
               )}>
-                {status.active ? "Streaming" : "Inactive"}
+                {status.active ? "Streaming" : status.permission_pending ? "Awaiting Permission" : "Inactive"}
               </span>
             </div>
           </div>
         </div>
 
-        {status.active ? (
+        {status.active || status.permission_pending ? (
           <Button
             variant="destructive"
             size="sm"
@@ -275,7 +377,7 @@ function LocalCaptureCard({ onStatusChange }: { onStatusChange: (active: boolean
             disabled={isLoading}
           >
             <Square className="mr-2 h-3 w-3 fill-current" />
-            Stop Capture
+            {status.permission_pending ? "Cancel" : "Stop Capture"}
           </Button>
         ) : (
           <Button
@@ -343,6 +445,54 @@ function LocalCaptureCard({ onStatusChange }: { onStatusChange: (active: boolean
               />
             </div>
           </div>
+
+          <div className="grid grid-cols-2 gap-4">
+            <div>
+              <div className="mb-1 font-mono text-[10px] uppercase text-muted-foreground">Max Width</div>
+              <Select
+                disabled={isLoading}
+                value={String((status.active ? status.max_width : config.maxWidth) ?? "native")}
+                onValueChange={handleMaxWidthChange}
+              >
+                <SelectTrigger className="rounded-none border-border bg-black font-mono text-xs focus:ring-0 focus:border-primary">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent className="rounded-none border-border bg-card">
+                  {["native", "3840", "2560", "1920", "1280"].map((w) => (
+                    <SelectItem key={w} value={w} className="font-mono text-xs">
+                      {w === "native" ? "Native" : `${w}px`}
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+            </div>
+            <div>
+              <div className="mb-1 font-mono text-[10px] uppercase text-muted-foreground">Scaling</div>
+              <Select
+                disabled={status.active || config.maxWidth === null}
+                value={status.active ? status.scaling : config.scaling}
+                onValueChange={(v) => setConfig((prev) => ({ ...prev, scaling: v as ScalingQuality }))}
+              >
+                <SelectTrigger className="rounded-none border-border bg-black font-mono text-xs focus:ring-0 focus:border-primary">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent className="rounded-none border-border bg-card">
+                  <SelectItem value="area" className="font-mono text-xs">Smooth (area)</SelectItem>
+                  <SelectItem value="nearest" className="font-mono text-xs">Fast (nearest)</SelectItem>
+                </SelectContent>
+              </Select>
+            </div>
+          </div>
+
+          <div className="flex items-center justify-between">
+            <span className="font-mono text-[10px] uppercase text-muted-foreground">System Audio</span>
+            <Switch
+              disabled={status.active}
+              checked={status.active ? status.audio : config.audio}
+              onCheckedChange={(v) => setConfig((prev) => ({ ...prev, audio: v }))}
+              data-testid="switch-stream-audio"
+            />
+          </div>
         </div>
 
         <div className="space-y-4">
@@ -389,6 +539,20 @@ function LocalCaptureCard({ onStatusChange }: { onStatusChange: (active: boolean
         </div>
       </div>
 
+      {status.permission_pending && (
+        <div className="mt-4 p-3 border border-yellow-500/50 bg-yellow-500/10 text-yellow-500 font-mono text-xs flex items-center justify-between gap-2">
+          <span>Allow screen recording for Synthia. Capture starts automatically once granted.</span>
+          <Button
+            variant="outline"
+            size="sm"
+            className="rounded-none border-yellow-500/50 font-mono text-[10px] uppercase hover:bg-white hover:text-black"
+            onClick={handleOpenPermissionSettings}
+          >
+            Open Settings
+          </Button>
+        </div>
+      )}
+
       {error && (
         <div className="mt-4 p-3 border border-destructive/50 bg-destructive/10 text-destructive font-mono text-xs flex items-center gap-2">
           <div className="h-2 w-2 bg-destructive rounded-full animate-pulse" />
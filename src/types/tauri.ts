@@ -5,6 +5,74 @@
  * used in Tauri commands. Keep these in sync with src-tauri/src/lib.rs.
  */
 
+/**
+ * Error returned (rejected) by every Tauri command.
+ * Must match AppError serialization in src-tauri/src/error.rs
+ */
+export interface AppError {
+  /** Stable machine-readable code, e.g. "session_not_found", "port_in_use" */
+  code:
+    | "validation"
+    | "session_not_found"
+    | "not_found"
+    | "permission_denied"
+    | "port_in_use"
+    | "session_limit_reached"
+    | "session_locked"
+    | "rate_limited"
+    | "timeout"
+    | "unsupported"
+    | "io"
+    | "capture"
+    | "internal";
+  /** Human-readable description */
+  message: string;
+  /** With rate_limited: milliseconds until the request would be accepted */
+  retry_after_ms?: number;
+}
+
+/** Type guard for errors rejected by invoke() */
+export function isAppError(err: unknown): err is AppError {
+  return typeof err === "object" && err !== null && "code" in err && "message" in err;
+}
+
+/**
+ * Event envelope version this build understands.
+ * Must match EVENT_SCHEMA_VERSION in src-tauri/src/events.rs
+ */
+export const EVENT_SCHEMA_VERSION = 1;
+
+/**
+ * Wire format of every backend event; `listen` in src/lib/events.ts unwraps it.
+ * Must match EventEnvelope struct in src-tauri/src/events.rs
+ */
+export interface EventEnvelope<T> {
+  v: number;
+  /** Event name, with `{session_id}` for per-session events */
+  type: string;
+  payload: T;
+}
+
+/**
+ * Must match EventSpec struct in src-tauri/src/events.rs
+ */
+export interface EventSpec {
+  /** `{session_id}` stands for the session's id */
+  name: string;
+  /** Payload type name in this file, or "null" */
+  payload: string;
+  description: string;
+}
+
+/**
+ * Events the backend emits, returned by get_event_schema command.
+ * Must match EventSchema struct in src-tauri/src/events.rs
+ */
+export interface EventSchema {
+  version: number;
+  events: EventSpec[];
+}
+
 /**
  * System statistics returned by the get_system_stats command.
  * Must match SystemStats struct in src-tauri/src/lib.rs
@@ -20,22 +88,627 @@ export interface SystemStats {
   mem_total_gb: number;
 }
 
+/**
+ * macOS memory breakdown (bytes) returned by the get_memory_details command.
+ * Must match MemoryBreakdown struct in src-tauri/src/lib.rs
+ */
+export interface MemoryBreakdown {
+  total: number;
+  /** Active + wired, as in SystemStats */
+  used: number;
+  free: number;
+  active: number;
+  inactive: number;
+  wired: number;
+  /** Memory occupied by the compressor */
+  compressed: number;
+  /** Cached memory the system may discard under pressure */
+  purgeable: number;
+  speculative: number;
+  page_size: number;
+}
+
+/**
+ * A log line (or run of identical lines) from get_logs, or a dashboard alert.
+ * Must match LogEntry struct in src-tauri/src/logging.rs
+ */
+export interface LogEntry {
+  id: string;
+  /** RFC 3339 timestamp in the zone set by logging.timezone */
+  ts: string;
+  level: string;
+  source: string;
+  message: string;
+  meta?: Record<string, string>;
+  /** Identical consecutive lines this entry stands for (1 unless collapsed) */
+  count: number;
+  /** Timestamp of the last line collapsed into this entry */
+  last_ts?: string;
+}
+
+/**
+ * Everything the dashboard shows, returned by get_dashboard_snapshot command.
+ * Must match DashboardSnapshot struct in src-tauri/src/dashboard.rs
+ */
+export interface DashboardSnapshot {
+  stats: SystemStats;
+  stream: StreamStatus;
+  terminals: TerminalInfo[];
+  /** Latest warnings and errors logged since startup, newest first */
+  alerts: LogEntry[];
+  /** RFC 3339 timestamp of the snapshot */
+  taken_at: string;
+}
+
+/**
+ * The numbers in the status bar, returned by get_statusbar_data command.
+ * Must match StatusBarData struct in src-tauri/src/dashboard.rs
+ */
+export interface StatusBarData {
+  /** CPU and memory usage percent; null before the sampler's first reading */
+  cpu: number | null;
+  mem: number | null;
+  /** A stream is running; null while one is starting or stopping */
+  streaming: boolean | null;
+  sessions: number;
+  /** Errors logged since they were last marked read (mark_errors_read) */
+  unread_errors: number;
+}
+
+/**
+ * Compact system context for LLM prompts, returned by get_agent_context.
+ * Must match AgentContext struct in src-tauri/src/agent_context.rs
+ */
+export interface AgentContext {
+  host: {
+    hostname: string;
+    os: string;
+    arch: string;
+    uptime_secs: number;
+  };
+  system: {
+    cpu_percent: number;
+    mem_percent: number;
+    mem_used_gb: number;
+    mem_total_gb: number;
+  };
+  sessions: AgentSession[];
+  /** Latest warnings and errors, newest first */
+  recent_errors: AgentLogLine[];
+  /** Sessions and log lines left out to fit max_tokens */
+  omitted_sessions: number;
+  omitted_errors: number;
+  /** Estimated size of the document in tokens */
+  approx_tokens: number;
+  /** RFC 3339 time the context was assembled */
+  generated_at: string;
+}
+
+/**
+ * Terminal session entry of AgentContext.
+ * Must match AgentSession struct in src-tauri/src/agent_context.rs
+ */
+export interface AgentSession {
+  id: string;
+  /** Working directory of the foreground process */
+  cwd: string | null;
+  /** Command line of the foreground process */
+  foreground: string | null;
+  /** Whether the shell itself is in the foreground */
+  at_prompt: boolean;
+  /** Seconds since the last input */
+  idle_secs: number;
+}
+
+/**
+ * Log line of AgentContext.
+ * Must match AgentLogLine struct in src-tauri/src/agent_context.rs
+ */
+export interface AgentLogLine {
+  ts: string;
+  level: string;
+  source: string;
+  message: string;
+  count: number;
+}
+
+/**
+ * Host identity returned by the get_host_info command.
+ * Must match HostInfo struct in src-tauri/src/lib.rs
+ */
+export interface HostInfo {
+  hostname: string;
+  /** OS name (e.g. "Darwin", "Ubuntu", "Windows") */
+  os_name: string;
+  os_version: string;
+  /** Human-readable name and version (e.g. "macOS 14.4 Sonoma") */
+  os_long_version: string;
+  kernel_version: string;
+  /** CPU architecture (e.g. "arm64", "x86_64") */
+  arch: string;
+  /** Boot time as Unix timestamp (seconds) */
+  boot_time: number;
+  uptime_secs: number;
+}
+
+/**
+ * Initial PTY size accepted by spawn_terminal command.
+ * Must match TerminalSize struct in src-tauri/src/pty.rs
+ */
+export interface TerminalSize {
+  rows: number;
+  cols: number;
+  pixel_width?: number;
+  pixel_height?: number;
+}
+
 /**
  * Terminal session info returned by list_terminals command.
  * Must match TerminalInfo struct in src-tauri/src/pty.rs
  */
 export interface TerminalInfo {
   session_id: string;
+  /** False once the shell has exited (see exit) */
   is_alive: boolean;
+  /** How the shell exited, if it has */
+  exit: TerminalExit | null;
+  /** Foreground process of the session, if known (always null on Windows) */
+  foreground: ForegroundProcess | null;
+  /** Input lock, if an agent or user holds one */
+  lock: SessionLock | null;
+  /** Bytes read and written so far */
+  stats: SessionStats;
+  /** Viewers subscribed to the session's output events */
+  viewers: number;
+  /** Never killed by the idle cleanup policy (set_idle_exempt) */
+  idle_exempt: boolean;
+  /** Environment profile the session was spawned with */
+  profile: string | null;
+  /** Sandbox of a spawn_sandboxed_terminal session */
+  sandbox: SandboxInfo | null;
+  /** Resource limits the shell was spawned with */
+  limits: ResourceLimits | null;
+}
+
+/**
+ * Limits passed to spawn_terminal / spawn_sandboxed_terminal. Unset fields
+ * are not limited.
+ * Must match ResourceLimits struct in src-tauri/src/limits.rs
+ */
+export interface ResourceLimits {
+  /** Address space per process, in MiB (Linux only) */
+  max_memory_mb?: number | null;
+  /** CPU time per process, in seconds */
+  max_cpu_seconds?: number | null;
+  /** Scheduling priority, 0-19 */
+  nice?: number | null;
+}
+
+/**
+ * Must match SandboxBackend enum in src-tauri/src/sandbox.rs
+ */
+export type SandboxBackend = "firejail" | "sandbox_exec" | "rbash";
+
+/**
+ * Must match SandboxInfo struct in src-tauri/src/sandbox.rs
+ */
+export interface SandboxInfo {
+  backend: SandboxBackend;
+  /** Human-readable restrictions in effect */
+  restrictions: string[];
+}
+
+/**
+ * Result of spawn_sandboxed_terminal command.
+ * Must match SandboxedTerminal struct in src-tauri/src/pty.rs
+ */
+export interface SandboxedTerminal {
+  session_id: string;
+  sandbox: SandboxInfo;
+}
+
+/**
+ * Result of upload_to_session_cwd / download_from_session commands.
+ * Must match FileTransfer struct in src-tauri/src/file_transfer.rs
+ */
+export interface FileTransfer {
+  session_id: string;
+  /** File that was copied */
+  source: string;
+  /** Where the copy was written (may have a numbered name) */
+  destination: string;
+  bytes: number;
+}
+
+/**
+ * Result of subscribe_terminal / unsubscribe_terminal commands.
+ * Must match TerminalSubscription struct in src-tauri/src/pty.rs
+ */
+export interface TerminalSubscription {
+  session_id: string;
+  /** Subscriptions now open */
+  viewers: number;
+  /** Latest output chunk (0 before any output); replay_output fetches what a viewer missed */
+  last_seq: number;
+}
+
+/**
+ * Session I/O counters returned by get_session_stats command.
+ * Must match SessionStats struct in src-tauri/src/pty.rs
+ */
+export interface SessionStats {
+  /** Output read from the PTY since the session started */
+  bytes_read: number;
+  /** Input written to the PTY */
+  bytes_written: number;
+  /** Output rate over the last 5 complete seconds */
+  read_bytes_per_sec: number;
+  /** Most output in any one second so far */
+  peak_read_bytes_per_sec: number;
+  /** RFC 3339 */
+  started_at: string;
+  uptime_secs: number;
+}
+
+/**
+ * Exclusive input lock returned by acquire_session_lock command.
+ * Must match SessionLock struct in src-tauri/src/pty.rs
+ */
+export interface SessionLock {
+  owner: string;
+  /** RFC 3339 timestamp */
+  acquired_at: string;
+}
+
+/**
+ * Process in the foreground of a terminal session.
+ * Must match ForegroundProcess struct in src-tauri/src/pty.rs
+ */
+export interface ForegroundProcess {
+  pid: number;
+  /** Executable name, e.g. "zsh" or "cargo" */
+  name: string;
+  /** Full command line, e.g. "cargo build" */
+  command: string;
+}
+
+/**
+ * Payload of the terminal-foreground-changed event.
+ * Must match ForegroundChanged struct in src-tauri/src/pty.rs
+ */
+export interface ForegroundChanged {
+  session_id: string;
+  foreground: ForegroundProcess | null;
+}
+
+/**
+ * Payload of the pty-output-{session_id} event and a replay_output chunk.
+ * Must match PtyOutput struct in src-tauri/src/pty.rs
+ */
+export interface PtyOutput {
+  /** Per-session chunk number, starting at 1 and increasing by 1 */
+  seq: number;
+  /** Session output bytes emitted before this chunk */
+  offset: number;
+  data: string;
+}
+
+/**
+ * Result of replay_output.
+ * Must match OutputReplay struct in src-tauri/src/pty.rs
+ */
+export interface OutputReplay {
+  chunks: PtyOutput[];
+  /** False if some requested output was already dropped from scrollback */
+  complete: boolean;
+}
+
+/**
+ * Current screen of a session returned by get_visible_screen command.
+ * Must match VisibleScreen struct in src-tauri/src/screen.rs
+ */
+export interface VisibleScreen {
+  session_id: string;
+  rows: number;
+  cols: number;
+  /** One entry per screen row, trailing blanks removed */
+  lines: string[];
+  /** Zero-based cursor position */
+  cursor_row: number;
+  cursor_col: number;
+  cursor_visible: boolean;
+  /** A full-screen program is using the alternate screen */
+  alternate_screen: boolean;
+  /** Window title set by the running program, if any */
+  title: string | null;
+}
+
+/**
+ * Terminal screenshot returned by render_terminal_image command.
+ * Must match TerminalImage struct in src-tauri/src/terminal_image.rs
+ */
+export interface TerminalImage {
+  session_id: string;
+  cols: number;
+  rows: number;
+  /** Image size in pixels */
+  width: number;
+  height: number;
+  /** Lines the view is scrolled back from the live screen */
+  scroll_back: number;
+  /** PNG image as a data:image/png;base64,... URL */
+  data_url: string;
+}
+
+/**
+ * Completion candidates returned by get_completions command.
+ * Must match Completions struct in src-tauri/src/completions.rs
+ */
+export interface Completions {
+  session_id: string;
+  shell: "bash" | "zsh";
+  /** The (unquoted) word being completed; candidates replace it */
+  word: string;
+  /** In the order the shell produced them, without duplicates */
+  candidates: string[];
+  /** More candidates were found than returned (500) */
+  truncated: boolean;
+}
+
+/**
+ * Must match FsChangeKind enum in src-tauri/src/fs_watch.rs
+ */
+export type FsChangeKind = "created" | "modified" | "deleted";
+
+/**
+ * Must match FsChange struct in src-tauri/src/fs_watch.rs
+ */
+export interface FsChange {
+  path: string;
+  kind: FsChangeKind;
+}
+
+/**
+ * Debounced changes under a watched path, emitted on the "fs-changed" event.
+ * Must match FsChangedEvent struct in src-tauri/src/fs_watch.rs
+ */
+export interface FsChangedEvent {
+  watch_id: string;
+  /** In the order their paths were first touched */
+  changes: FsChange[];
+}
+
+/**
+ * Watch started by watch_path command; stop it with unwatch_path.
+ * Must match FsWatch struct in src-tauri/src/fs_watch.rs
+ */
+export interface FsWatch {
+  watch_id: string;
+  /** Canonical path being watched */
+  path: string;
+  recursive: boolean;
+}
+
+/**
+ * Result of start_session_recording / stop_session_recording commands.
+ * Must match RecordingStatus struct in src-tauri/src/recording.rs
+ */
+export interface RecordingStatus {
+  session_id: string;
+  path: string;
+  /** Input is recorded as well as output */
+  record_input: boolean;
+  /** RFC 3339 time the recording started */
+  started_at: string;
+  /** Events written so far */
+  events: number;
+  /** Secure inputs left out */
+  omitted_inputs: number;
+}
+
+/**
+ * Asciicast playback started by replay_recording command. Its output
+ * arrives as pty-output-{session_id} events, then pty-close-{session_id}.
+ * Must match RecordingReplay struct in src-tauri/src/replay.rs
+ */
+export interface RecordingReplay {
+  session_id: string;
+  /** Terminal size the recording was made at */
+  cols: number;
+  rows: number;
+  /** Playback duration at the requested speed, in seconds */
+  duration_secs: number;
+  events: number;
+}
+
+/**
+ * One match returned by search_terminal command.
+ * Must match TerminalSearchMatch struct in src-tauri/src/pty.rs
+ */
+export interface TerminalSearchMatch {
+  /** Line of the transcript, 0 for the oldest retained line */
+  line: number;
+  /** Character column where the match starts */
+  column: number;
+  /** Length of the match in characters */
+  length: number;
+  /** The whole line, ANSI-stripped */
+  text: string;
+}
+
+/**
+ * Result of search_terminal command.
+ * Must match TerminalSearchResult struct in src-tauri/src/pty.rs
+ */
+export interface TerminalSearchResult {
+  session_id: string;
+  /** Matches oldest first */
+  matches: TerminalSearchMatch[];
+  /** Lines searched; total_lines - 1 - line is a match's distance from the bottom */
+  total_lines: number;
+  /** Whether matching stopped at the 1000-match cap */
+  truncated: boolean;
+}
+
+/**
+ * Payload of the terminal-bell event (BEL characters in session output).
+ * Must match TerminalBell struct in src-tauri/src/pty.rs
+ */
+export interface TerminalBell {
+  session_id: string;
+  /** Bells in the output chunk that triggered the event */
+  count: number;
+  /** Bells since the session started */
+  total: number;
+}
+
+/**
+ * Payload of the terminal-idle-warning and terminal-idle-killed events
+ * (terminal.idle_kill_hours cleanup policy).
+ * Must match TerminalIdle struct in src-tauri/src/pty.rs
+ */
+export interface TerminalIdle {
+  session_id: string;
+  /** Seconds since the last input */
+  idle_secs: number;
+  /** Seconds until the session is killed (0 once it was) */
+  kill_in_secs: number;
+}
+
+/**
+ * Payload of the terminal-exited event, also kept in TerminalInfo.exit.
+ * Must match TerminalExit struct in src-tauri/src/pty.rs
+ */
+export interface TerminalExit {
+  session_id: string;
+  /** Exit code of the shell (1 when it was killed by a signal) */
+  exit_code: number;
+  /** Name of the signal that killed the shell, if any */
+  signal: string | null;
+  success: boolean;
+  /** RFC 3339 timestamp of when the exit was noticed */
+  exited_at: string;
+}
+
+/**
+ * Payload of the terminal-activity event, emitted when output starts after a
+ * quiet period and then at most once a second while it continues.
+ * Must match TerminalActivity struct in src-tauri/src/pty.rs
+ */
+export interface TerminalActivity {
+  session_id: string;
+  /** True for the first event of a burst */
+  burst_start: boolean;
+  /** Output bytes since the previous event for this session */
+  bytes: number;
+  /** Milliseconds since the burst started */
+  burst_ms: number;
+}
+
+/**
+ * Why a session is believed to be waiting for a password.
+ * Must match SecureInputReason enum in src-tauri/src/pty.rs
+ */
+export type SecureInputReason = "echo_off" | "prompt_pattern";
+
+/**
+ * Payload of the secure-input-requested event. Answer it with
+ * write_terminal_secure rather than write_terminal.
+ * Must match SecureInputRequested struct in src-tauri/src/pty.rs
+ */
+export interface SecureInputRequested {
+  session_id: string;
+  /** The prompt line, e.g. "[sudo] password for sam:", if known */
+  prompt: string | null;
+  reason: SecureInputReason;
+}
+
+/**
+ * Payload of the command-finished event. Only emitted for shells that send
+ * OSC 133 shell integration marks.
+ * Must match CommandFinished struct in src-tauri/src/pty.rs
+ */
+export interface CommandFinished {
+  session_id: string;
+  /** The command line as the shell reported or echoed it, if known */
+  command: string | null;
+  /** Null if the shell did not report a status */
+  exit_code: number | null;
+  duration_ms: number;
+}
+
+/**
+ * One entry of an inject_commands batch: a bare command line or an object
+ * with options. Options that need the exit status make the batch wait for
+ * the command, which requires OSC 133 shell integration.
+ * Must match BatchCommand enum in src-tauri/src/pty.rs
+ */
+export type BatchCommand =
+  | string
+  | {
+      command: string;
+      /** Skip the rest of the batch unless this command succeeds */
+      stop_on_failure?: boolean;
+      /** Exit code that counts as success (default 0) */
+      expected_exit_code?: number;
+      /** Longest wait for the command to finish (default 1 minute) */
+      timeout_ms?: number;
+      /** Pause before sending the command (default 100 ms, 0 for the first) */
+      delay_ms?: number;
+    };
+
+export type BatchCommandStatus = "sent" | "succeeded" | "failed" | "timed_out" | "skipped";
+
+/**
+ * Per-command entry of the inject_commands result.
+ * Must match BatchCommandResult struct in src-tauri/src/pty.rs
+ */
+export interface BatchCommandResult {
+  command: string;
+  status: BatchCommandStatus;
+  /** Exit code reported by the shell, if the batch waited for it */
+  exit_code: number | null;
+  /** Run time reported by the shell, if the batch waited for it */
+  duration_ms: number | null;
+  /** Why the command could not be sent (e.g. the session ended mid-batch) */
+  error: string | null;
+}
+
+/**
+ * Plain-text transcript slice returned by get_transcript command.
+ * Must match TranscriptSlice struct in src-tauri/src/pty.rs
+ */
+export interface TranscriptSlice {
+  session_id: string;
+  text: string;
+  /** Characters currently retained for the session */
+  total_chars: number;
+  /** Whether text is shorter than the retained transcript */
+  truncated: boolean;
+}
+
+/**
+ * Match returned by the wait_for_output command.
+ * Must match OutputMatch struct in src-tauri/src/pty.rs
+ */
+export interface OutputMatch {
+  /** The text matched by the pattern (ANSI-stripped) */
+  matched: string;
+  /** Plain-text output surrounding the match */
+  context: string;
+  /** Milliseconds waited before the match arrived */
+  elapsed_ms: number;
 }
 
 /**
  * Structured terminal output event for AI agent consumption.
- * Emitted on the "terminal-output-captured" event channel.
+ * Emitted on the "terminal-output-captured" event channel, only while the
+ * session has a subscribe_terminal subscription.
  * Must match TerminalOutput struct in src-tauri/src/pty.rs
  */
 export interface TerminalOutput {
   session_id: string;
+  /** Output with terminal.redaction secrets masked */
   data: string;
   timestamp: string;
 }
@@ -51,14 +724,1213 @@ export interface StreamStatus {
   quality: number;
   clients: number;
   display_id: number | null;
+  /** Whether system audio (Opus) is multiplexed into the stream */
+  audio: boolean;
+  /** Start requested, waiting for screen capture permission to be granted */
+  permission_pending: boolean;
+  /** Frames wider than this are downscaled before sending (null = native size) */
+  max_width: number | null;
+  scaling: ScalingQuality;
+  /** Cursor drawn into captured frames */
+  show_cursor: boolean;
+  /** Clicks highlighted in captured frames (macOS only) */
+  show_highlight: boolean;
+  resolution: CaptureResolution;
+  /** Application shown by start_app_stream (null = the whole display) */
+  target_app: AppSelector | null;
+  /** Served over TLS: viewers connect with wss:// */
+  tls: boolean;
+  /** SHA-256 fingerprint of the served certificate, when tls */
+  cert_fingerprint: string | null;
+  /** Viewers may be granted keyboard and mouse control */
+  remote_control: boolean;
+  client_stats: StreamClientInfo[];
 }
 
 /**
- * Display info returned by list_displays command.
- * Must match DisplayInfo struct in src-tauri/src/streaming.rs
+ * Camera returned by list_cameras command.
+ * Must match CameraDevice struct in src-tauri/src/camera.rs
  */
-export interface DisplayInfo {
-  id: number;
-  title: string;
-  is_primary: boolean;
+export interface CameraDevice {
+  /** Index passed to start_camera_stream */
+  index: number;
+  name: string;
+  description: string;
+}
+
+/**
+ * Camera stream status returned by start_camera_stream and
+ * get_camera_stream_status commands. Viewers connect as to the screen stream.
+ * Must match CameraStreamStatus struct in src-tauri/src/camera.rs
+ */
+export interface CameraStreamStatus {
+  active: boolean;
+  port: number;
+  fps: number;
+  camera_index: number;
+  /** Size the camera delivers, which may differ from the one requested */
+  width: number;
+  height: number;
+  /** Format the camera delivers, e.g. "MJPEG" or "YUYV" */
+  frame_format: string;
+  clients: number;
+  /** Served over TLS: viewers connect with wss:// */
+  tls: boolean;
+  /** SHA-256 fingerprint of the served certificate, when tls */
+  cert_fingerprint: string | null;
+  client_stats: StreamClientInfo[];
+}
+
+/**
+ * Capture options changed by restart_local_stream; omitted ones are kept.
+ * Must match StreamRestartOptions struct in src-tauri/src/streaming.rs
+ */
+export interface StreamRestartOptions {
+  fps?: number;
+  quality?: number;
+  display_id?: number;
+  scaling?: ScalingQuality;
+  show_cursor?: boolean;
+  show_highlight?: boolean;
+  resolution?: CaptureResolution;
+}
+
+/**
+ * Certificate used by TLS streams, returned by generate_stream_cert and
+ * get_stream_cert_fingerprint commands.
+ * Must match StreamCertInfo struct in src-tauri/src/stream_tls.rs
+ */
+export interface StreamCertInfo {
+  /** generated = self-signed by Synthia, user = stream.tls_cert_path */
+  source: "generated" | "user";
+  cert_path: string;
+  /** Colon-separated hex SHA-256, as browsers show it */
+  fingerprint_sha256: string;
+}
+
+/**
+ * Application captured by an app stream.
+ * Must match AppSelector enum in src-tauri/src/app_capture.rs
+ */
+export type AppSelector =
+  | { kind: "bundle_id"; value: string }
+  | { kind: "process_name"; value: string };
+
+/**
+ * Resampling filter used when a stream is downscaled to max_width.
+ * Must match ScalingQuality enum in src-tauri/src/streaming.rs
+ */
+export type ScalingQuality = "nearest" | "area";
+
+/**
+ * Output size requested from the screen capturer (macOS only).
+ * Must match CaptureResolution enum in src-tauri/src/streaming.rs
+ */
+export type CaptureResolution = "captured" | "480p" | "720p" | "1080p" | "1440p" | "2160p" | "4320p";
+
+/**
+ * Screen capture permission returned by get_capture_permission_status command.
+ * Must match CapturePermissionStatus enum in src-tauri/src/streaming.rs
+ */
+export type CapturePermissionStatus = "granted" | "not_granted" | "unsupported";
+
+/**
+ * Payload of the "stream-auto-stopped" event.
+ * Must match StreamAutoStopped struct in src-tauri/src/streaming.rs
+ */
+export interface StreamAutoStopped {
+  port: number;
+  idle_secs: number;
+}
+
+/**
+ * Payload of the "stream-degraded" event: frame capture failed and the
+ * capturer is being rebuilt with backoff.
+ * Must match StreamDegraded struct in src-tauri/src/streaming.rs
+ */
+export interface StreamDegraded {
+  error: string;
+  /** Rebuilds attempted before capture stops */
+  max_retries: number;
+}
+
+/**
+ * Payload of the "stream-recovered" event, sent when frames flow again.
+ * Must match StreamRecovered struct in src-tauri/src/streaming.rs
+ */
+export interface StreamRecovered {
+  /** Capturer rebuilds it took */
+  attempts: number;
+  /** Time without frames */
+  downtime_ms: number;
+}
+
+/**
+ * Payload of the "stream-control-requested" event, sent when a viewer asks
+ * for keyboard and mouse control.
+ * Must match StreamControlRequest struct in src-tauri/src/remote_input.rs
+ */
+export interface StreamControlRequest {
+  client_id: number;
+  addr: string;
+}
+
+/**
+ * Capture-to-receive latency in ms over recent frames.
+ * Must match LatencyStats struct in src-tauri/src/streaming.rs
+ */
+export interface LatencyStats {
+  samples: number;
+  last_ms: number;
+  avg_ms: number;
+  min_ms: number;
+  p50_ms: number;
+  p95_ms: number;
+  max_ms: number;
+}
+
+/**
+ * Latency reported by one stream viewer.
+ * Must match ClientLatency struct in src-tauri/src/streaming.rs
+ */
+export interface ClientLatency {
+  id: number;
+  addr: string;
+  /** Sequence number of the last acknowledged frame */
+  last_seq: number;
+  stats: LatencyStats;
+}
+
+/**
+ * Capture pipeline counters returned by get_capture_stats command.
+ * Rates and averages cover the whole time since the stream started.
+ * Must match CaptureStats struct in src-tauri/src/streaming.rs
+ */
+export interface CaptureStats {
+  active: boolean;
+  uptime_secs: number;
+  /** Frames delivered by the OS capturer */
+  frames_captured: number;
+  /** Dropped: zero-sized frame */
+  skipped_empty: number;
+  /** Dropped: pixel buffer shorter than the frame dimensions */
+  skipped_mismatch: number;
+  /** Dropped: over the low-power frame rate cap */
+  skipped_low_power: number;
+  /** Not published because nothing on screen changed */
+  unchanged: number;
+  /** Frames sent to viewers */
+  published: number;
+  captured_fps: number;
+  published_fps: number;
+  /** Scaling, diffing and pixel format conversion time per published frame */
+  avg_convert_ms: number;
+  max_convert_ms: number;
+}
+
+/**
+ * End-to-end stream latency returned by get_stream_latency command.
+ * Must match StreamLatency struct in src-tauri/src/streaming.rs
+ */
+export interface StreamLatency {
+  active: boolean;
+  overall: LatencyStats;
+  clients: ClientLatency[];
+}
+
+/**
+ * Per-client stream statistics, included in StreamStatus.
+ * Must match StreamClientInfo struct in src-tauri/src/streaming.rs
+ */
+export interface StreamClientInfo {
+  id: number;
+  addr: string;
+  connected_at: string;
+  frames_sent: number;
+  bytes_sent: number;
+  avg_send_latency_ms: number;
+  /** Pixel format negotiated with ?format= at connect */
+  format: PixelFormat;
+  /** Granted keyboard and mouse control with set_stream_client_control */
+  control: boolean;
+  transport: ClientTransport;
+}
+
+/**
+ * How a viewer receives the stream; "ipc" is the app's own webview via
+ * subscribe_local_frames.
+ * Must match ClientTransport enum in src-tauri/src/streaming.rs
+ */
+export type ClientTransport = "web_socket" | "ipc";
+
+/**
+ * Pixel layout of stream video messages, chosen per client with the
+ * `format` query parameter of the stream URL (default "bgra").
+ * Must match PixelFormat enum in src-tauri/src/pixel_format.rs
+ */
+export type PixelFormat = "bgra" | "rgba" | "rgb" | "yuv420";
+
+/**
+ * What became of a stream connection attempt.
+ * Must match AccessOutcome enum in src-tauri/src/stream_access.rs
+ */
+export type AccessOutcome =
+  | "accepted"
+  | "rejected_peer"
+  | "rejected_limit"
+  | "rejected_origin"
+  | "rejected_format"
+  | "handshake_failed";
+
+/**
+ * One stream connection attempt, returned by get_stream_access_log command
+ * (newest first).
+ * Must match StreamAccessEntry struct in src-tauri/src/stream_access.rs
+ */
+export interface StreamAccessEntry {
+  /** RFC 3339; for accepted viewers, when they disconnected */
+  timestamp: string;
+  peer: string;
+  origin: string | null;
+  outcome: AccessOutcome;
+  /** Accepted viewers only */
+  client_id: number | null;
+  /** Accepted viewers only */
+  duration_ms: number | null;
+  bytes_sent: number;
+  frames_sent: number;
+}
+
+/**
+ * Display info returned by list_displays command.
+ * Must match DisplayInfo struct in src-tauri/src/streaming.rs
+ */
+export interface DisplayInfo {
+  id: number;
+  title: string;
+  is_primary: boolean;
+}
+
+/**
+ * Display preview returned by get_display_thumbnails command.
+ * Must match DisplayThumbnail struct in src-tauri/src/streaming.rs
+ */
+export interface DisplayThumbnail {
+  id: number;
+  title: string;
+  is_primary: boolean;
+  width: number;
+  height: number;
+  /** JPEG preview as a data: URL */
+  data_url: string;
+}
+
+/**
+ * Shell returned by list_available_shells command.
+ * Must match ShellInfo struct in src-tauri/src/shells.rs
+ */
+export interface ShellInfo {
+  /** Pass as spawn_terminal's shell or settings terminal.shell */
+  id: string;
+  name: string;
+  program: string;
+  args: string[];
+  is_default: boolean;
+}
+
+/**
+ * Synthia's own resource footprint returned by get_app_resource_usage command.
+ * Must match AppResourceUsage struct in src-tauri/src/metrics.rs
+ */
+export interface AppResourceUsage {
+  pid: number;
+  /** Percent of one core (can exceed 100) */
+  cpu_percent: number;
+  rss_bytes: number;
+  open_ptys: number;
+  /** Screen capture thread CPU, percent of one core (null when not streaming) */
+  capture_cpu_percent: number | null;
+  events_per_sec: number;
+  events_total: number;
+}
+
+/**
+ * Ordering accepted by get_top_processes command.
+ * Must match ProcessSort enum in src-tauri/src/processes.rs
+ */
+export type ProcessSort = "cpu" | "memory";
+
+/**
+ * Row returned by get_top_processes command.
+ * Must match TopProcess struct in src-tauri/src/processes.rs
+ */
+export interface TopProcess {
+  pid: number;
+  name: string;
+  /** Percent of one core since the previous call (can exceed 100) */
+  cpu_percent: number;
+  /** Resident memory */
+  memory_bytes: number;
+}
+
+/**
+ * Scheduling settings returned by get_process_priority, set_process_priority
+ * and set_process_affinity commands.
+ * Must match ProcessPriority struct in src-tauri/src/processes.rs
+ */
+export interface ProcessPriority {
+  pid: number;
+  /** Nice value, -20 (highest priority) to 19 (lowest) */
+  nice: number;
+  /** CPUs the process may run on, or null where affinity is unsupported (macOS, Windows) */
+  affinity: number[] | null;
+}
+
+/**
+ * Panic recorded by the panic hook, returned by get_last_panic command.
+ * Must match PanicReport struct in src-tauri/src/crash.rs
+ */
+export interface PanicReport {
+  message: string;
+  /** "file:line:column" of the panic, if known */
+  location: string | null;
+  thread: string;
+  backtrace: string;
+  /** RFC 3339 timestamp */
+  timestamp: string;
+  pid: number;
+  /** True when the panic happened in an earlier launch of the app */
+  previous_launch: boolean;
+}
+
+/**
+ * Listening socket returned by get_listening_ports command.
+ * Must match ListeningPort struct in src-tauri/src/ports.rs
+ */
+export interface ListeningPort {
+  /** "TCP" or "UDP" */
+  protocol: string;
+  address: string;
+  port: number;
+  pid: number | null;
+  process_name: string | null;
+}
+
+/**
+ * Schedule timing accepted by add_schedule.
+ * Must match ScheduleSpec enum in src-tauri/src/scheduler.rs
+ */
+export type ScheduleSpec =
+  /** Interval of 10 seconds up to one year */
+  | { type: "every"; seconds: number }
+  | { type: "daily"; time: string };
+
+/**
+ * Schedule action accepted by add_schedule.
+ * Must match ScheduleAction enum in src-tauri/src/scheduler.rs
+ */
+export type ScheduleAction =
+  | { type: "run_command"; session_id: string; command: string }
+  | { type: "stop_stream" }
+  | { type: "run_script"; path: string };
+
+/**
+ * Registered schedule returned by add_schedule / list_schedules.
+ * Must match Schedule struct in src-tauri/src/scheduler.rs
+ */
+export interface Schedule {
+  id: string;
+  name: string;
+  spec: ScheduleSpec;
+  action: ScheduleAction;
+  enabled: boolean;
+  created_at: string;
+  last_run: string | null;
+  next_run: string;
+}
+
+/**
+ * Schedule execution record returned by get_schedule_history.
+ * Must match ScheduleRun struct in src-tauri/src/scheduler.rs
+ */
+export interface ScheduleRun {
+  schedule_id: string;
+  schedule_name: string;
+  started_at: string;
+  success: boolean;
+  message: string;
+}
+
+/**
+ * Script run returned by run_script / list_script_runs.
+ * Must match ScriptRun struct in src-tauri/src/scripting.rs
+ */
+export interface ScriptRun {
+  id: string;
+  /** Script path, or "inline" for source */
+  source_label: string;
+  started_at: string;
+  /** null while the script is running */
+  finished_at: string | null;
+  success: boolean;
+  /** print and debug lines, at most 64 KiB */
+  output: string;
+  error: string | null;
+}
+
+/**
+ * Query resolution for query_metrics.
+ * Must match Resolution enum in src-tauri/src/metrics.rs
+ */
+export type MetricsResolution = "1s" | "1m" | "1h";
+
+/**
+ * Historical metric sample returned by query_metrics.
+ * Must match MetricPoint struct in src-tauri/src/metrics.rs
+ */
+export interface MetricPoint {
+  /** Unix timestamp in seconds */
+  ts: number;
+  cpu: number;
+  mem: number;
+  /** Percent of time some task stalled on CPU (Linux PSI; null elsewhere) */
+  cpu_pressure: number | null;
+  /** Percent of time some task stalled on memory (Linux PSI) */
+  mem_pressure: number | null;
+  /** Percent of time some task stalled on I/O (Linux PSI) */
+  io_pressure: number | null;
+}
+
+/**
+ * How run_network_probe measures a target's latency.
+ * Must match ProbeKind enum in src-tauri/src/network_probe.rs
+ */
+export type ProbeKind = "icmp" | "tcp";
+
+/**
+ * Target accepted by run_network_probe command.
+ * Must match ProbeTarget struct in src-tauri/src/network_probe.rs
+ */
+export interface ProbeTarget {
+  host: string;
+  kind: ProbeKind;
+  /** Required for "tcp" */
+  port?: number | null;
+}
+
+/**
+ * Latency of one target in a NetworkProbeReport.
+ * Must match LatencyResult struct in src-tauri/src/network_probe.rs
+ */
+export interface LatencyResult {
+  /** host, or host:port for TCP */
+  target: string;
+  kind: ProbeKind;
+  sent: number;
+  received: number;
+  /** 0-100 */
+  loss_percent: number;
+  min_ms: number | null;
+  avg_ms: number | null;
+  max_ms: number | null;
+  error: string | null;
+}
+
+/**
+ * Download result in a NetworkProbeReport.
+ * Must match ThroughputResult struct in src-tauri/src/network_probe.rs
+ */
+export interface ThroughputResult {
+  url: string;
+  bytes: number;
+  duration_ms: number;
+  /** Megabits per second; null if the download failed */
+  mbps: number | null;
+  error: string | null;
+}
+
+/**
+ * Result of run_network_probe command.
+ * Must match NetworkProbeReport struct in src-tauri/src/network_probe.rs
+ */
+export interface NetworkProbeReport {
+  taken_at: string;
+  /** One entry per target, in request order */
+  latency: LatencyResult[];
+  /** Present when a throughput test was requested */
+  throughput: ThroughputResult | null;
+}
+
+/**
+ * Stored probe result returned by query_network_probes command.
+ * Must match NetworkProbeSample struct in src-tauri/src/metrics.rs
+ */
+export interface NetworkProbeSample {
+  /** Unix timestamp in seconds */
+  ts: number;
+  kind: "icmp" | "tcp" | "throughput";
+  /** Host (host:port for TCP) or throughput URL */
+  target: string;
+  latency_ms: number | null;
+  loss_percent: number | null;
+  throughput_mbps: number | null;
+}
+
+/**
+ * Filter accepted by query_history command; set fields are combined with AND.
+ * Must match HistoryFilter struct in src-tauri/src/history.rs
+ */
+export interface HistoryFilter {
+  session_id?: string;
+  /** Directory the command ran in, including subdirectories */
+  cwd?: string;
+  /** Text the command line contains (case-insensitive) */
+  text?: string;
+  /** Unix timestamps in seconds */
+  since?: number;
+  until?: number;
+  /** Only commands with a non-zero exit code */
+  failed_only?: boolean;
+  /** Default 100, at most 1000 */
+  limit?: number;
+}
+
+/**
+ * Recorded command returned by query_history command (newest first).
+ * Must match HistoryCommand struct in src-tauri/src/history.rs
+ */
+export interface HistoryCommand {
+  session_id: string;
+  /** Command line with secrets redacted, if known */
+  command: string | null;
+  /** Working directory of the shell when the command finished */
+  cwd: string | null;
+  exit_code: number | null;
+  duration_ms: number;
+  /** Unix timestamp in seconds */
+  finished_at: number;
+}
+
+/**
+ * Recorded session returned by query_history_sessions command.
+ * Must match HistorySession struct in src-tauri/src/history.rs
+ */
+export interface HistorySession {
+  session_id: string;
+  shell: string;
+  /** Directory the shell started in */
+  cwd: string | null;
+  profile: string | null;
+  /** Unix timestamps in seconds; ended_at is null while the session is open */
+  started_at: number;
+  ended_at: number | null;
+  /** Exit code of the shell, if it exited on its own */
+  exit_code: number | null;
+  commands: number;
+}
+
+/**
+ * Power mode requested with set_power_mode ("auto" = low power on battery).
+ * Must match PowerMode enum in src-tauri/src/power.rs
+ */
+export type PowerMode = "auto" | "low" | "normal";
+
+/**
+ * Returned by get_power_status and set_power_mode commands, and the payload
+ * of the "power-mode-changed" event.
+ * Must match PowerStatus struct in src-tauri/src/power.rs
+ */
+export interface PowerStatus {
+  mode: PowerMode;
+  /** Running on battery (false if it cannot be detected) */
+  on_battery: boolean;
+  /** Low power is in effect */
+  low_power: boolean;
+}
+
+/**
+ * Screen region in captured-frame pixels, accepted by ocr_screen_region.
+ * Must match CaptureRect struct in src-tauri/src/ocr.rs
+ */
+export interface CaptureRect {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+/**
+ * Recognized word with bounding box in display coordinates.
+ * Must match OcrWord struct in src-tauri/src/ocr.rs
+ */
+export interface OcrWord {
+  text: string;
+  confidence: number;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+/**
+ * Result of capture_burst command.
+ * Must match Burst struct in src-tauri/src/burst.rs
+ */
+export interface Burst {
+  /** Size of the downscaled frames */
+  width: number;
+  height: number;
+  frames: BurstFrame[];
+  /** Largest change between consecutive frames (0 for a static screen) */
+  max_change: number;
+}
+
+/**
+ * Must match BurstFrame struct in src-tauri/src/burst.rs
+ */
+export interface BurstFrame {
+  /** Time since the first frame was captured */
+  offset_ms: number;
+  /** Fraction of pixels (0-1) that differ from the previous frame; null for the first */
+  change: number | null;
+  /** JPEG image as a data:image/jpeg;base64,... URL */
+  data_url: string;
+}
+
+/**
+ * OCR output returned by ocr_screen_region.
+ * Must match OcrResult struct in src-tauri/src/ocr.rs
+ */
+export interface OcrResult {
+  text: string;
+  words: OcrWord[];
+  region: CaptureRect;
+}
+
+/**
+ * Pruning limits of a timelapse (at least one must be set).
+ * Must match TimelapseRetention struct in src-tauri/src/timelapse.rs
+ */
+export interface TimelapseRetention {
+  /** Keep at most this many frames (1-100000) */
+  max_frames: number | null;
+  /** Delete frames older than this */
+  max_age_secs: number | null;
+}
+
+/**
+ * A saved timelapse frame, also the payload of timelapse-frame-captured.
+ * Must match TimelapseFrame struct in src-tauri/src/timelapse.rs
+ */
+export interface TimelapseFrame {
+  path: string;
+  file_name: string;
+  /** RFC 3339 time the frame was written */
+  captured_at: string;
+  size_bytes: number;
+}
+
+/**
+ * Timelapse state returned by start/stop_timelapse and get_timelapse_status.
+ * Must match TimelapseStatus struct in src-tauri/src/timelapse.rs
+ */
+export interface TimelapseStatus {
+  running: boolean;
+  /** Display being captured (null for the main display) */
+  display_id: number | null;
+  interval_s: number;
+  dir: string | null;
+  retention: TimelapseRetention | null;
+  /** RFC 3339 start time */
+  started_at: string | null;
+  frames_captured: number;
+  /** Most recent capture or write failure (the timelapse keeps going) */
+  last_error: string | null;
+}
+
+/**
+ * Terminal settings section.
+ * Must match TerminalSettings struct in src-tauri/src/settings.rs
+ */
+export interface TerminalSettings {
+  /** Maximum number of PTY sessions that may be open at once */
+  max_sessions: number;
+  /** Largest single write_terminal / paste_terminal payload in bytes */
+  max_write_bytes: number;
+  /** Control characters accepted by write_terminal */
+  control_chars: ControlCharPolicy;
+  /** Shell id from list_available_shells for new sessions ("" = platform default) */
+  shell: string;
+  /** Save each session's transcript tail on exit and restore it on respawn */
+  persist_scrollback: boolean;
+  /** Secrets masked in output handed to AI agents */
+  redaction: RedactionSettings;
+  /** Kill sessions idle at their prompt for this many hours (0 = never) */
+  idle_kill_hours: number;
+  /** Minutes of notice given by terminal-idle-warning before the kill */
+  idle_warning_mins: number;
+  /** Named environments spawn_terminal can start a session with (profile) */
+  profiles: EnvProfile[];
+  /** Drop sessions from list_terminals as soon as their shell exits */
+  auto_remove_dead: boolean;
+  /** Throttling of inject_command(s) and write_terminal */
+  rate_limit: RateLimitSettings;
+  /** Look of the terminal UI (get_terminal_appearance) */
+  appearance: TerminalAppearance;
+}
+
+/**
+ * Must match CursorStyle enum in src-tauri/src/settings.rs
+ */
+export type CursorStyle = "block" | "underline" | "bar";
+
+/**
+ * Terminal colors, font and cursor shared by every window, returned by
+ * get_terminal_appearance and emitted on "terminal-appearance-changed".
+ * Must match TerminalAppearance struct in src-tauri/src/settings.rs
+ */
+export interface TerminalAppearance {
+  /** Color scheme name; unknown names fall back to "synthia" */
+  theme: string;
+  /** CSS font-family list */
+  font_family: string;
+  /** Font size in pixels (6-72) */
+  font_size: number;
+  cursor_style: CursorStyle;
+  cursor_blink: boolean;
+}
+
+/**
+ * Token-bucket limits per caller (owner) and session.
+ * Must match RateLimitSettings struct in src-tauri/src/settings.rs
+ */
+export interface RateLimitSettings {
+  enabled: boolean;
+  /** Injected commands refilled per second */
+  commands_per_sec: number;
+  /** Injected commands accepted back to back */
+  command_burst: number;
+  /** write_terminal calls refilled per second */
+  writes_per_sec: number;
+  /** write_terminal calls accepted back to back */
+  write_burst: number;
+}
+
+/**
+ * Session environment applied by spawn_terminal's profile argument, in
+ * field order: unset, env, path_prepend.
+ * Must match EnvProfile struct in src-tauri/src/settings.rs
+ */
+export interface EnvProfile {
+  /** Unique name, e.g. "python-venv" */
+  name: string;
+  env: Record<string, string>;
+  /** Variables removed from the inherited environment */
+  unset: string[];
+  /** Directories put in front of PATH */
+  path_prepend: string[];
+  /** Typed into the shell at its first prompt, before initial_commands */
+  init_commands: string[];
+}
+
+/**
+ * Redaction of terminal-output-captured events, get_transcript and
+ * wait_for_output (the xterm stream is never redacted).
+ * Must match RedactionSettings struct in src-tauri/src/settings.rs
+ */
+export interface RedactionSettings {
+  enabled: boolean;
+  /** Applied in order */
+  rules: RedactionRule[];
+}
+
+/**
+ * Matches are replaced by "[REDACTED:<name>]"; with a (?P<secret>...) group
+ * only that part is.
+ * Must match RedactionRule struct in src-tauri/src/settings.rs
+ */
+export interface RedactionRule {
+  name: string;
+  /** Rust regex syntax */
+  pattern: string;
+}
+
+/**
+ * Control characters accepted by write_terminal: everything, keystrokes only
+ * (no OSC/DCS string sequences or C1 controls), or printable text only.
+ * Must match ControlCharPolicy enum in src-tauri/src/pty.rs
+ */
+export type ControlCharPolicy = "allow" | "standard" | "printable";
+
+/**
+ * Logging settings section.
+ * Must match LoggingSettings struct in src-tauri/src/settings.rs
+ */
+export interface LoggingSettings {
+  /** "off" | "error" | "warn" | "info" | "debug" | "trace" */
+  level: string;
+  /** Zone log timestamps are written in and returned by get_logs */
+  timezone: LogTimezone;
+  /** Mirror terminal output into the log with source "pty:<session>" */
+  capture_terminal: boolean;
+  /** Forwarding to a central syslog server or HTTP collector */
+  remote: RemoteLogSettings;
+}
+
+/**
+ * Log shipping settings, nested in LoggingSettings.
+ * Must match RemoteLogSettings struct in src-tauri/src/settings.rs
+ */
+export interface RemoteLogSettings {
+  enabled: boolean;
+  /** "udp://host[:port]", "tcp://host[:port]" (syslog) or "http://host[:port]/path" */
+  endpoint: string;
+  /** Lowest level shipped; records must also pass logging.level */
+  level: string;
+  /** Records sent per request (or burst of syslog messages) */
+  batch_size: number;
+  /** Longest a record waits before a partial batch is sent */
+  flush_secs: number;
+}
+
+/**
+ * Time zone of log timestamps (RFC 3339, with offset or "Z").
+ * Must match LogTimezone enum in src-tauri/src/logging.rs
+ */
+export type LogTimezone = "local" | "utc";
+
+/**
+ * Default local stream parameters.
+ * Must match StreamSettings struct in src-tauri/src/settings.rs
+ */
+export interface StreamSettings {
+  port: number;
+  fps: number;
+  quality: number;
+  /** Stop a running stream this many seconds after its last client leaves (0 = never) */
+  idle_stop_secs: number;
+  /** Peer IPs or CIDR blocks allowed to connect (empty = any; loopback always allowed) */
+  allowed_ips: string[];
+  /** Maximum concurrent viewers (0 = unlimited) */
+  max_clients: number;
+  /** Serve the stream over TLS (wss://) */
+  tls: boolean;
+  /** PEM certificate and key to use instead of the generated one (both or neither) */
+  tls_cert_path: string;
+  tls_key_path: string;
+  /** Let viewers granted control inject keyboard and mouse input */
+  remote_control: boolean;
+}
+
+/**
+ * Persisted settings returned by get_settings / update_settings and
+ * emitted on the "settings-changed" event.
+ * Must match Settings struct in src-tauri/src/settings.rs
+ */
+export interface Settings {
+  terminal: TerminalSettings;
+  logging: LoggingSettings;
+  stream: StreamSettings;
+  network: NetworkSettings;
+}
+
+/**
+ * Result of export_config command.
+ * Must match ConfigExport struct in src-tauri/src/config_transfer.rs
+ */
+export interface ConfigExport {
+  path: string;
+  /** Environment profiles, services and schedules written */
+  profiles: number;
+  services: number;
+  schedules: number;
+}
+
+/**
+ * Result of import_config command. The bundle's settings replace the
+ * current ones; services and schedules are added.
+ * Must match ConfigImport struct in src-tauri/src/config_transfer.rs
+ */
+export interface ConfigImport {
+  settings: boolean;
+  services_added: number;
+  schedules_added: number;
+  /** Entries left out, with the reason */
+  skipped: string[];
+}
+
+/**
+ * Network probe settings section.
+ * Must match NetworkSettings struct in src-tauri/src/settings.rs
+ */
+export interface NetworkSettings {
+  /** http://host[:port]/path downloaded by throughput tests ("" = none) */
+  throughput_url: string;
+}
+
+/**
+ * Session counts returned by get_session_limits command.
+ * Must match SessionLimits struct in src-tauri/src/settings.rs
+ */
+export interface SessionLimits {
+  max_sessions: number;
+  active_sessions: number;
+}
+
+/**
+ * File format accepted by export_logs command.
+ * Must match ExportFormat enum in src-tauri/src/logging.rs
+ */
+export type ExportFormat = "json" | "csv";
+
+/**
+ * Optional criteria for export_logs; all fields are combined with AND.
+ * Must match LogFilter struct in src-tauri/src/logging.rs
+ */
+export interface LogFilter {
+  /** Levels to include (case-insensitive), e.g. ["WARN", "ERROR"] */
+  levels?: string[];
+  /** Exact source/target */
+  source?: string;
+  /** Case-insensitive substring of the message or source */
+  query?: string;
+  /** Inclusive lower bound on ts ("YYYY-MM-DDTHH:MM:SS" or a prefix) */
+  since?: string;
+  /** Inclusive upper bound on ts ("YYYY-MM-DDTHH:MM:SS" or a prefix) */
+  until?: string;
+}
+
+/**
+ * Summary returned by export_logs command.
+ * Must match LogExportSummary struct in src-tauri/src/logging.rs
+ */
+export interface LogExportSummary {
+  path: string;
+  format: ExportFormat;
+  /** Entries written to the file */
+  exported: number;
+  /** Entries in the log file before filtering */
+  total: number;
+  bytes: number;
+}
+
+/**
+ * Result of get_log_stats command.
+ * Must match LogStats struct in src-tauri/src/logging.rs
+ */
+export interface LogStats {
+  range_secs: number;
+  /** Width of each bucket */
+  bucket_secs: number;
+  /** Records in the range, repeats included */
+  total: number;
+  /** Records per level (uppercase) */
+  levels: Record<string, number>;
+  /** Busiest sources, most records first */
+  sources: LogSourceCount[];
+  /** Records from sources not in sources */
+  other_sources: number;
+  /** Oldest first, covering the whole range */
+  buckets: LogStatsBucket[];
+}
+
+/**
+ * Must match LogSourceCount struct in src-tauri/src/logging.rs
+ */
+export interface LogSourceCount {
+  source: string;
+  count: number;
+}
+
+/**
+ * Must match LogStatsBucket struct in src-tauri/src/logging.rs
+ */
+export interface LogStatsBucket {
+  /** Start of the bucket (RFC 3339) */
+  start: string;
+  total: number;
+  /** ERROR and FATAL records */
+  errors: number;
+  /** errors / total, 0 for an empty bucket */
+  error_rate: number;
+}
+
+/**
+ * What a command palette result refers to.
+ * Must match PaletteKind enum in src-tauri/src/palette.rs
+ */
+export type PaletteKind = "session" | "command" | "log_source" | "action";
+
+/**
+ * Ranked result returned by search_actions command.
+ * Must match PaletteResult struct in src-tauri/src/palette.rs
+ */
+export interface PaletteResult {
+  kind: PaletteKind;
+  /** Session id, command text, log source, or action id (e.g. "nav.logs") */
+  id: string;
+  title: string;
+  subtitle: string | null;
+  /** Higher is better; 0 for an empty query */
+  score: number;
+  /** Character indices in title that matched the query (for highlighting) */
+  positions: number[];
+}
+
+/**
+ * Working tree state of a git repository
+ * Must match RepoStatus struct in src-tauri/src/git.rs
+ */
+export interface RepoStatus {
+  /** Working tree root */
+  root: string;
+  /** Current branch; null when HEAD is detached */
+  branch: string | null;
+  /** Short id of the HEAD commit; null in a repository without commits */
+  head: string | null;
+  detached: boolean;
+  /** Upstream branch, e.g. "origin/main" */
+  upstream: string | null;
+  ahead: number;
+  behind: number;
+  staged: number;
+  modified: number;
+  untracked: number;
+  conflicted: number;
+  clean: boolean;
+}
+
+/**
+ * A commit in the history of HEAD
+ * Must match CommitInfo struct in src-tauri/src/git.rs
+ */
+export interface CommitInfo {
+  id: string;
+  short_id: string;
+  /** First line of the message */
+  summary: string;
+  author: string;
+  /** RFC 3339 timestamp */
+  time: string;
+}
+
+/**
+ * Changes of one file in a diff summary
+ * Must match FileDiff struct in src-tauri/src/git.rs
+ */
+export interface FileDiff {
+  path: string;
+  /** "added", "deleted", "modified", "renamed", "typechange", ... */
+  status: string;
+  insertions: number;
+  deletions: number;
+}
+
+/**
+ * Uncommitted changes (staged and unstaged) relative to HEAD
+ * Must match DiffSummary struct in src-tauri/src/git.rs
+ */
+export interface DiffSummary {
+  files_changed: number;
+  insertions: number;
+  deletions: number;
+  /** Per-file breakdown, capped at 200 entries */
+  files: FileDiff[];
+}
+
+/**
+ * What happens when a managed service exits on its own.
+ * Must match RestartPolicy enum in src-tauri/src/services.rs
+ */
+export type RestartPolicy = "never" | "on_failure" | "always";
+
+/**
+ * A persisted service definition (add_service).
+ * Must match ServiceDef struct in src-tauri/src/services.rs
+ */
+export interface ServiceDef {
+  id: string;
+  name: string;
+  /** Command line, run through the user's login shell */
+  command: string;
+  /** Working directory; null means $HOME */
+  cwd: string | null;
+  env: Record<string, string>;
+  restart: RestartPolicy;
+  created_at: string;
+}
+
+/**
+ * Lifecycle state of a managed service.
+ * Must match ServiceState enum in src-tauri/src/services.rs
+ */
+export type ServiceState = "stopped" | "running" | "restarting" | "exited";
+
+/**
+ * Service definition with runtime state (list_services, get_service_status,
+ * service-status-changed event).
+ * Must match ServiceStatus struct in src-tauri/src/services.rs
+ */
+export interface ServiceStatus {
+  service: ServiceDef;
+  state: ServiceState;
+  pid: number | null;
+  started_at: string | null;
+  /** Automatic restarts since the service was last started by the user */
+  restarts: number;
+  /** Exit code of the last process; null if killed by a signal or still running */
+  exit_code: number | null;
+  /** Spawn failure message, if the last start failed */
+  last_error: string | null;
+}
+
+/**
+ * Captured service output line (get_service_logs).
+ * Must match ServiceLogLine struct in src-tauri/src/services.rs
+ */
+export interface ServiceLogLine {
+  ts: string;
+  /** "system" lines are lifecycle messages written by Synthia */
+  stream: "stdout" | "stderr" | "system";
+  line: string;
+}
+
+/**
+ * Payload of the service-log event.
+ * Must match ServiceLogEvent struct in src-tauri/src/services.rs
+ */
+export interface ServiceLogEvent {
+  service_id: string;
+  line: ServiceLogLine;
+}
+
+/**
+ * Port forward (create_port_forward, list_tunnels).
+ * Must match TunnelInfo struct in src-tauri/src/tunnels.rs
+ */
+export interface TunnelInfo {
+  id: string;
+  /** Port on 127.0.0.1 */
+  local_port: number;
+  remote_host: string;
+  remote_port: number;
+  /** Session whose ssh connection the tunnel goes through; null for a direct forward */
+  via_ssh_session: string | null;
+  /** ssh destination (e.g. "user@host") of a tunnel through a session */
+  ssh_destination: string | null;
+  /** Process id of the tunnel's ssh */
+  pid: number | null;
+  created_at: string;
+  /** Connections relayed so far (direct forwards only) */
+  connections: number | null;
+}
+
+/**
+ * Payload of the tunnel-closed event.
+ * Must match TunnelClosed struct in src-tauri/src/tunnels.rs
+ */
+export interface TunnelClosed {
+  id: string;
+  /** "closed" for close_tunnel, else why the tunnel ended */
+  reason: string;
 }